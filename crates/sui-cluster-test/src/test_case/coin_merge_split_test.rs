@@ -5,6 +5,7 @@ use crate::{helper::ObjectChecker, TestCaseImpl, TestContext};
 use anyhow::bail;
 use async_trait::async_trait;
 use sui_json_rpc_types::SuiTransactionResponse;
+use sui_sdk::amount::Amount;
 use sui_types::base_types::{ObjectID, SuiAddress};
 use sui_types::object::Owner;
 use tracing::{debug, info};
@@ -32,7 +33,10 @@ impl TestCaseImpl for CoinMergeSplitTest {
 
         // Split
         info!("Testing coin split.");
-        let amounts = vec![1, (original_value - 2) / 2];
+        let amounts = vec![
+            Amount::from_mist(1),
+            Amount::from_mist((original_value - 2) / 2),
+        ];
 
         let data = ctx
             .get_gateway()
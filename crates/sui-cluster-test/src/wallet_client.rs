@@ -60,10 +60,11 @@ impl WalletClient {
     }
 
     pub async fn sync_account_state(&self) -> Result<(), anyhow::Error> {
-        self.get_gateway()
+        Ok(self
+            .get_gateway()
             .wallet_sync_api()
             .sync_account_state(self.get_wallet_address())
-            .await
+            .await?)
     }
 
     pub fn sign(&self, txn_data: &TransactionData, desc: &str) -> Signature {
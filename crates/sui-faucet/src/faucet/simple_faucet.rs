@@ -15,6 +15,7 @@ use sui::client_commands::{SuiClientCommands, WalletContext};
 use sui_json_rpc_types::{
     SuiExecutionStatus, SuiTransactionKind, SuiTransactionResponse, SuiTransferSui,
 };
+use sui_sdk::amount::Amount;
 use sui_types::{
     base_types::{ObjectID, SuiAddress, TransactionDigest},
     gas_coin::GasCoin,
@@ -234,7 +235,13 @@ impl SimpleFaucet {
         self.wallet
             .client
             .transaction_builder()
-            .transfer_sui(signer, coin_id, budget, recipient, Some(amount))
+            .transfer_sui(
+                signer,
+                coin_id,
+                budget,
+                recipient,
+                Some(Amount::from_mist(amount)),
+            )
             .await
             .map_err(|e| {
                 anyhow::anyhow!(
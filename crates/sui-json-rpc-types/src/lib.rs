@@ -61,7 +61,44 @@ mod rpc_types_tests;
 pub type GatewayTxSeqNumber = u64;
 pub type SuiMoveTypeParameterIndex = u16;
 
-#[derive(Serialize, Deserialize, Debug, JsonSchema)]
+/// A value of type `T`, or the raw JSON of a value that does not deserialize as `T`, e.g. an
+/// enum variant introduced by a newer node. Clients decoding lists of responses with
+/// `MaybeUnknown` items keep working when the node adds new variants.
+///
+/// Unknown fields of response structs are already ignored, as none of them deny unknown
+/// fields.
+#[derive(Serialize, Deserialize, Debug, JsonSchema, Clone, Eq, PartialEq)]
+#[serde(untagged)]
+pub enum MaybeUnknown<T> {
+    Known(T),
+    Unknown(Value),
+}
+
+impl<T> MaybeUnknown<T> {
+    pub fn known(&self) -> Option<&T> {
+        match self {
+            MaybeUnknown::Known(value) => Some(value),
+            MaybeUnknown::Unknown(_) => None,
+        }
+    }
+
+    pub fn into_known(self) -> Option<T> {
+        match self {
+            MaybeUnknown::Known(value) => Some(value),
+            MaybeUnknown::Unknown(_) => None,
+        }
+    }
+}
+
+impl<T> From<T> for MaybeUnknown<T> {
+    fn from(value: T) -> Self {
+        MaybeUnknown::Known(value)
+    }
+}
+
+#[derive(
+    Serialize, Deserialize, Copy, Clone, Debug, Eq, PartialEq, Ord, PartialOrd, JsonSchema,
+)]
 pub enum SuiMoveAbility {
     Copy,
     Drop,
@@ -1844,6 +1881,17 @@ pub struct SuiGasCostSummary {
     pub storage_rebate: u64,
 }
 
+impl SuiGasCostSummary {
+    pub fn gas_used(&self) -> u64 {
+        self.computation_cost + self.storage_cost
+    }
+
+    /// Get net gas usage, positive number means used gas; negative number means refund.
+    pub fn net_gas_usage(&self) -> i64 {
+        self.gas_used() as i64 - self.storage_rebate as i64
+    }
+}
+
 impl From<GasCostSummary> for SuiGasCostSummary {
     fn from(s: GasCostSummary) -> Self {
         Self {
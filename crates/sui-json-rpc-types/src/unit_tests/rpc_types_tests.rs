@@ -5,8 +5,9 @@ use anyhow::anyhow;
 use move_core_types::ident_str;
 use move_core_types::language_storage::StructTag;
 use move_core_types::value::{MoveStruct, MoveValue};
+use serde_json::json;
 
-use crate::{SuiMoveStruct, SuiMoveValue};
+use crate::{MaybeUnknown, SuiEvent, SuiEventEnvelope, SuiMoveStruct, SuiMoveValue};
 use sui_types::base_types::SequenceNumber;
 use sui_types::base_types::{ObjectID, SuiAddress};
 use sui_types::gas_coin::GasCoin;
@@ -129,3 +130,36 @@ fn test_serde() {
         )
     }
 }
+
+#[test]
+fn test_unknown_event_variants_and_fields() {
+    let json = json!([
+        {
+            "timestamp": 1,
+            "txDigest": null,
+            "event": { "epochChange": 3 },
+            "addedInNewerVersion": true
+        },
+        {
+            "timestamp": 2,
+            "txDigest": null,
+            "event": { "addedInNewerVersion": { "epoch": 3 } }
+        }
+    ]);
+    let events: Vec<MaybeUnknown<SuiEventEnvelope>> = serde_json::from_value(json.clone()).unwrap();
+
+    assert_eq!(
+        Some(&SuiEventEnvelope {
+            timestamp: 1,
+            tx_digest: None,
+            event: SuiEvent::EpochChange(3),
+        }),
+        events[0].known()
+    );
+    assert_eq!(MaybeUnknown::Unknown(json[1].clone()), events[1]);
+    assert_eq!(
+        json[1],
+        serde_json::to_value(&events[1]).unwrap(),
+        "Unknown values serialize back to their raw JSON"
+    );
+}
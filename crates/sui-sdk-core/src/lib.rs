@@ -0,0 +1,172 @@
+// Copyright (c) 2022, Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! The signing core of the Sui SDK, for secure elements and embedded devices.
+//!
+//! This crate is `no_std` and only needs `alloc`. It derives addresses from public keys,
+//! builds the messages signed for a transaction and assembles Sui signatures from the raw
+//! signature and public key, byte-identical to `sui-types`. Key generation and the signing
+//! itself are left to the device, which holds the key.
+//!
+//! This version of the protocol has no intent messages: the signed message of a transaction
+//! is its signable bytes, `TransactionData::` followed by the BCS encoding of the data.
+
+#![no_std]
+
+extern crate alloc;
+#[cfg(feature = "std")]
+extern crate std;
+
+use alloc::vec::Vec;
+use core::fmt::{Display, Formatter};
+
+use sha3::{Digest, Sha3_256};
+
+pub const ADDRESS_LENGTH: usize = 20;
+pub const DIGEST_LENGTH: usize = 32;
+
+const TRANSACTION_DATA_TAG: &[u8] = b"TransactionData::";
+const SENDER_SIGNED_DATA_TAG: &[u8] = b"SenderSignedData::";
+
+/// The signature schemes of user accounts.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SignatureScheme {
+    Ed25519,
+    Secp256k1,
+}
+
+impl SignatureScheme {
+    /// The byte identifying the scheme in addresses and signatures.
+    pub fn flag(&self) -> u8 {
+        match self {
+            SignatureScheme::Ed25519 => 0x00,
+            SignatureScheme::Secp256k1 => 0x01,
+        }
+    }
+
+    pub fn from_flag(flag: u8) -> Result<Self, Error> {
+        match flag {
+            0x00 => Ok(SignatureScheme::Ed25519),
+            0x01 => Ok(SignatureScheme::Secp256k1),
+            flag => Err(Error::UnknownScheme(flag)),
+        }
+    }
+
+    /// Length of public keys, compressed for Secp256k1.
+    pub fn public_key_length(&self) -> usize {
+        match self {
+            SignatureScheme::Ed25519 => 32,
+            SignatureScheme::Secp256k1 => 33,
+        }
+    }
+
+    /// Length of signatures, recoverable for Secp256k1.
+    pub fn signature_length(&self) -> usize {
+        match self {
+            SignatureScheme::Ed25519 => 64,
+            SignatureScheme::Secp256k1 => 65,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Error {
+    UnknownScheme(u8),
+    InvalidPublicKeyLength { expected: usize, actual: usize },
+    InvalidSignatureLength { expected: usize, actual: usize },
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Error::UnknownScheme(flag) => write!(f, "Unknown signature scheme flag [{flag}]."),
+            Error::InvalidPublicKeyLength { expected, actual } => write!(
+                f,
+                "Invalid public key length [{actual}], expected [{expected}]."
+            ),
+            Error::InvalidSignatureLength { expected, actual } => write!(
+                f,
+                "Invalid signature length [{actual}], expected [{expected}]."
+            ),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for Error {}
+
+/// The address of `public_key`: the first 20 bytes of the SHA3-256 hash of the scheme flag
+/// followed by the public key.
+pub fn address_from_public_key(
+    scheme: SignatureScheme,
+    public_key: &[u8],
+) -> Result<[u8; ADDRESS_LENGTH], Error> {
+    check_public_key(scheme, public_key)?;
+    let mut hasher = Sha3_256::default();
+    hasher.update([scheme.flag()]);
+    hasher.update(public_key);
+    let mut address = [0u8; ADDRESS_LENGTH];
+    address.copy_from_slice(&hasher.finalize()[..ADDRESS_LENGTH]);
+    Ok(address)
+}
+
+/// The message to sign for a transaction, given the BCS encoding of its `TransactionData`.
+pub fn transaction_signing_message(tx_data_bcs: &[u8]) -> Vec<u8> {
+    let mut message = Vec::with_capacity(TRANSACTION_DATA_TAG.len() + tx_data_bcs.len());
+    message.extend_from_slice(TRANSACTION_DATA_TAG);
+    message.extend_from_slice(tx_data_bcs);
+    message
+}
+
+/// A Sui signature: the scheme flag, followed by the signature and the public key.
+pub fn assemble_signature(
+    scheme: SignatureScheme,
+    signature: &[u8],
+    public_key: &[u8],
+) -> Result<Vec<u8>, Error> {
+    check_public_key(scheme, public_key)?;
+    if signature.len() != scheme.signature_length() {
+        return Err(Error::InvalidSignatureLength {
+            expected: scheme.signature_length(),
+            actual: signature.len(),
+        });
+    }
+    let mut bytes = Vec::with_capacity(1 + signature.len() + public_key.len());
+    bytes.push(scheme.flag());
+    bytes.extend_from_slice(signature);
+    bytes.extend_from_slice(public_key);
+    Ok(bytes)
+}
+
+/// The digest of a signed transaction, given the BCS encoding of its `TransactionData` and
+/// the assembled signature, as displayed by explorers and returned by the node.
+pub fn transaction_digest(tx_data_bcs: &[u8], signature: &[u8]) -> [u8; DIGEST_LENGTH] {
+    let mut hasher = Sha3_256::default();
+    hasher.update(SENDER_SIGNED_DATA_TAG);
+    hasher.update(tx_data_bcs);
+    // The signature is BCS encoded as a byte vector, prefixed with its ULEB128 length.
+    let mut length = signature.len();
+    loop {
+        let byte = (length & 0x7f) as u8;
+        length >>= 7;
+        if length == 0 {
+            hasher.update([byte]);
+            break;
+        }
+        hasher.update([byte | 0x80]);
+    }
+    hasher.update(signature);
+    let mut digest = [0u8; DIGEST_LENGTH];
+    digest.copy_from_slice(&hasher.finalize());
+    digest
+}
+
+fn check_public_key(scheme: SignatureScheme, public_key: &[u8]) -> Result<(), Error> {
+    if public_key.len() != scheme.public_key_length() {
+        return Err(Error::InvalidPublicKeyLength {
+            expected: scheme.public_key_length(),
+            actual: public_key.len(),
+        });
+    }
+    Ok(())
+}
@@ -0,0 +1,6 @@
+// Copyright (c) 2022, Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+fn main() {
+    uniffi_build::generate_scaffolding("./src/sui_sdk.udl").unwrap();
+}
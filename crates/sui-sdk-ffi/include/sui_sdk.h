@@ -0,0 +1,76 @@
+// Copyright (c) 2022, Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+// C API of the Sui SDK. Link against the `sui_sdk_ffi` static or shared library.
+//
+// Addresses and object IDs are 20 byte arrays. Transaction data is the BCS encoded signable
+// bytes, signatures are `flag || signature || public key`. Buffers returned by the library
+// must be released with sui_buffer_free, handles with the matching sui_*_free function.
+// On failure, sui_last_error_message describes the last error on the calling thread.
+
+#ifndef SUI_SDK_H
+#define SUI_SDK_H
+
+#include <stddef.h>
+#include <stdint.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+#define SUI_ADDRESS_LENGTH 20
+
+typedef enum SuiStatus {
+    SUI_STATUS_OK = 0,
+    SUI_STATUS_NULL_POINTER = 1,
+    SUI_STATUS_INVALID_INPUT = 2,
+    SUI_STATUS_KEYSTORE_ERROR = 3,
+    SUI_STATUS_RPC_ERROR = 4,
+    SUI_STATUS_PANIC = 5,
+} SuiStatus;
+
+typedef struct SuiBuffer {
+    uint8_t *data;
+    size_t len;
+} SuiBuffer;
+
+typedef struct SuiKeystore SuiKeystore;
+typedef struct SuiClient SuiClient;
+
+const char *sui_last_error_message(void);
+
+void sui_buffer_free(SuiBuffer buffer);
+
+SuiStatus sui_keystore_new(SuiKeystore **out);
+SuiStatus sui_keystore_open(const char *path, SuiKeystore **out);
+void sui_keystore_free(SuiKeystore *keystore);
+SuiStatus sui_keystore_generate_key(const SuiKeystore *keystore, uint8_t *out_address);
+SuiStatus sui_keystore_sign(const SuiKeystore *keystore,
+                            const uint8_t *address,
+                            const uint8_t *tx_bytes,
+                            size_t tx_len,
+                            SuiBuffer *out);
+
+SuiStatus sui_client_new(const char *http_url, SuiClient **out);
+void sui_client_free(SuiClient *client);
+// `amount` may be null to transfer the whole coin.
+SuiStatus sui_client_transfer_sui(const SuiClient *client,
+                                  const uint8_t *signer,
+                                  const uint8_t *coin_id,
+                                  uint64_t gas_budget,
+                                  const uint8_t *recipient,
+                                  const uint64_t *amount,
+                                  SuiBuffer *out);
+// Writes the JSON response of the node to `out`.
+SuiStatus sui_client_execute(const SuiClient *client,
+                             const uint8_t *tx_bytes,
+                             size_t tx_len,
+                             const uint8_t *signature,
+                             size_t signature_len,
+                             SuiBuffer *out);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif // SUI_SDK_H
@@ -0,0 +1,344 @@
+// Copyright (c) 2022, Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! A C API over the SDK, declared in `include/sui_sdk.h`.
+//!
+//! Keystores and clients are opaque handles created by `sui_*_new` functions and released
+//! with the matching `sui_*_free`. Addresses and object IDs are passed as 20 byte arrays,
+//! transactions and signatures as byte buffers: transaction data is the BCS encoded
+//! signable bytes, signatures are `flag || signature || public key`. Buffers returned by the
+//! library must be released with [sui_buffer_free].
+//!
+//! Every fallible function returns a [SuiStatus]; on failure, [sui_last_error_message]
+//! describes the error of the last failed call on the calling thread.
+
+use std::cell::RefCell;
+use std::ffi::{CStr, CString};
+use std::os::raw::c_char;
+use std::panic::{catch_unwind, AssertUnwindSafe};
+use std::path::PathBuf;
+use std::ptr;
+use std::sync::Mutex;
+
+use tokio::runtime::Runtime;
+
+use sui_sdk::amount::Amount;
+use sui_sdk::crypto::KeystoreType;
+use sui_sdk::SuiClient;
+use sui_types::base_types::{ObjectID, SuiAddress, SUI_ADDRESS_LENGTH};
+use sui_types::crypto::{SignableBytes, Signature, SignatureScheme, ToFromBytes};
+use sui_types::messages::{Transaction, TransactionData};
+
+use crate::{Client, Keystore};
+
+#[cfg(test)]
+#[path = "unit_tests/c_api_tests.rs"]
+mod c_api_tests;
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SuiStatus {
+    Ok = 0,
+    NullPointer = 1,
+    InvalidInput = 2,
+    KeystoreError = 3,
+    RpcError = 4,
+    Panic = 5,
+}
+
+/// A byte buffer owned by the library.
+#[repr(C)]
+pub struct SuiBuffer {
+    pub data: *mut u8,
+    pub len: usize,
+}
+
+impl SuiBuffer {
+    fn from_vec(bytes: Vec<u8>) -> Self {
+        let mut bytes = bytes.into_boxed_slice();
+        let buffer = SuiBuffer {
+            data: bytes.as_mut_ptr(),
+            len: bytes.len(),
+        };
+        std::mem::forget(bytes);
+        buffer
+    }
+}
+
+thread_local! {
+    static LAST_ERROR: RefCell<Option<CString>> = RefCell::new(None);
+}
+
+struct Error(SuiStatus, String);
+
+fn invalid_input(e: impl ToString) -> Error {
+    Error(SuiStatus::InvalidInput, e.to_string())
+}
+
+fn keystore_error(e: impl ToString) -> Error {
+    Error(SuiStatus::KeystoreError, e.to_string())
+}
+
+fn rpc_error(e: impl ToString) -> Error {
+    Error(SuiStatus::RpcError, e.to_string())
+}
+
+/// Run `f`, recording its error for [sui_last_error_message] and turning panics into
+/// [SuiStatus::Panic], as unwinding into C is undefined behaviour.
+fn ffi_call(f: impl FnOnce() -> Result<(), Error>) -> SuiStatus {
+    let (status, message) = match catch_unwind(AssertUnwindSafe(f)) {
+        Ok(Ok(())) => return SuiStatus::Ok,
+        Ok(Err(Error(status, message))) => (status, message),
+        Err(_) => (SuiStatus::Panic, "The SDK panicked.".to_string()),
+    };
+    LAST_ERROR.with(|last| {
+        *last.borrow_mut() = CString::new(message).ok();
+    });
+    status
+}
+
+unsafe fn non_null<'a, T>(pointer: *const T) -> Result<&'a T, Error> {
+    pointer.as_ref().ok_or_else(|| {
+        Error(
+            SuiStatus::NullPointer,
+            "Unexpected null pointer.".to_string(),
+        )
+    })
+}
+
+unsafe fn bytes<'a>(data: *const u8, len: usize) -> Result<&'a [u8], Error> {
+    if len == 0 {
+        return Ok(&[]);
+    }
+    non_null(data)?;
+    Ok(std::slice::from_raw_parts(data, len))
+}
+
+unsafe fn address(address: *const u8) -> Result<SuiAddress, Error> {
+    SuiAddress::try_from(bytes(address, SUI_ADDRESS_LENGTH)?).map_err(invalid_input)
+}
+
+unsafe fn object_id(object_id: *const u8) -> Result<ObjectID, Error> {
+    ObjectID::try_from(bytes(object_id, ObjectID::LENGTH)?).map_err(invalid_input)
+}
+
+unsafe fn string(string: *const c_char) -> Result<String, Error> {
+    non_null(string)?;
+    CStr::from_ptr(string)
+        .to_str()
+        .map(str::to_string)
+        .map_err(invalid_input)
+}
+
+unsafe fn write<T>(out: *mut T, value: T) -> Result<(), Error> {
+    if out.is_null() {
+        return Err(Error(
+            SuiStatus::NullPointer,
+            "Unexpected null output pointer.".to_string(),
+        ));
+    }
+    out.write(value);
+    Ok(())
+}
+
+/// The message of the last error on the calling thread, or null. The string is valid until
+/// the next failing call on the same thread.
+#[no_mangle]
+pub extern "C" fn sui_last_error_message() -> *const c_char {
+    LAST_ERROR.with(|last| {
+        last.borrow()
+            .as_ref()
+            .map_or(ptr::null(), |message| message.as_ptr())
+    })
+}
+
+/// # Safety
+/// `buffer` must have been returned by this library and not freed yet.
+#[no_mangle]
+pub unsafe extern "C" fn sui_buffer_free(buffer: SuiBuffer) {
+    if !buffer.data.is_null() {
+        drop(Box::from_raw(std::slice::from_raw_parts_mut(
+            buffer.data,
+            buffer.len,
+        )));
+    }
+}
+
+/// Create an empty in-memory keystore.
+///
+/// # Safety
+/// `out` must be valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn sui_keystore_new(out: *mut *mut Keystore) -> SuiStatus {
+    ffi_call(|| write(out, Box::into_raw(Box::new(Keystore::new()))))
+}
+
+/// Open the keystore file at `path`, creating it if it does not exist.
+///
+/// # Safety
+/// `path` must be a NUL terminated string and `out` must be valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn sui_keystore_open(
+    path: *const c_char,
+    out: *mut *mut Keystore,
+) -> SuiStatus {
+    ffi_call(|| {
+        let keystore = KeystoreType::File(PathBuf::from(string(path)?))
+            .init()
+            .map_err(keystore_error)?;
+        write(out, Box::into_raw(Box::new(Keystore(Mutex::new(keystore)))))
+    })
+}
+
+/// # Safety
+/// `keystore` must have been created by this library and not freed yet, or be null.
+#[no_mangle]
+pub unsafe extern "C" fn sui_keystore_free(keystore: *mut Keystore) {
+    if !keystore.is_null() {
+        drop(Box::from_raw(keystore));
+    }
+}
+
+/// Generate an Ed25519 key and write its address to `out_address`, 20 bytes.
+///
+/// # Safety
+/// `keystore` must be a valid handle and `out_address` valid for 20 bytes of writes.
+#[no_mangle]
+pub unsafe extern "C" fn sui_keystore_generate_key(
+    keystore: *const Keystore,
+    out_address: *mut u8,
+) -> SuiStatus {
+    ffi_call(|| {
+        let keystore = non_null(keystore)?;
+        let (address, _, _) = keystore
+            .0
+            .lock()
+            .unwrap()
+            .generate_new_key(SignatureScheme::ED25519, None)
+            .map_err(keystore_error)?;
+        non_null(out_address)?;
+        ptr::copy_nonoverlapping(address.as_ref().as_ptr(), out_address, SUI_ADDRESS_LENGTH);
+        Ok(())
+    })
+}
+
+/// Sign transaction data with the key of `address` and write the signature to `out`.
+///
+/// # Safety
+/// `keystore` must be a valid handle, `address` valid for 20 bytes of reads, `tx_bytes` for
+/// `tx_len` bytes of reads, and `out` valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn sui_keystore_sign(
+    keystore: *const Keystore,
+    address: *const u8,
+    tx_bytes: *const u8,
+    tx_len: usize,
+    out: *mut SuiBuffer,
+) -> SuiStatus {
+    ffi_call(|| {
+        let keystore = non_null(keystore)?;
+        let address = self::address(address)?;
+        let tx_bytes = bytes(tx_bytes, tx_len)?;
+        let signature = keystore
+            .0
+            .lock()
+            .unwrap()
+            .sign(&address, tx_bytes)
+            .map_err(keystore_error)?;
+        write(out, SuiBuffer::from_vec(signature.as_ref().to_vec()))
+    })
+}
+
+/// Connect to the JSON-RPC endpoint at `http_url`.
+///
+/// # Safety
+/// `http_url` must be a NUL terminated string and `out` must be valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn sui_client_new(
+    http_url: *const c_char,
+    out: *mut *mut Client,
+) -> SuiStatus {
+    ffi_call(|| {
+        let http_url = string(http_url)?;
+        let runtime = Runtime::new().map_err(rpc_error)?;
+        let client = runtime
+            .block_on(SuiClient::new_rpc_client(&http_url, None))
+            .map_err(rpc_error)?;
+        write(out, Box::into_raw(Box::new(Client { runtime, client })))
+    })
+}
+
+/// # Safety
+/// `client` must have been created by this library and not freed yet, or be null.
+#[no_mangle]
+pub unsafe extern "C" fn sui_client_free(client: *mut Client) {
+    if !client.is_null() {
+        drop(Box::from_raw(client));
+    }
+}
+
+/// Build a transfer of `amount` MIST, or of the whole coin if `amount` is null, and write
+/// the transaction data to sign to `out`.
+///
+/// # Safety
+/// `client` must be a valid handle, `signer`, `coin_id` and `recipient` valid for 20 bytes
+/// of reads, `amount` null or valid for reads, and `out` valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn sui_client_transfer_sui(
+    client: *const Client,
+    signer: *const u8,
+    coin_id: *const u8,
+    gas_budget: u64,
+    recipient: *const u8,
+    amount: *const u64,
+    out: *mut SuiBuffer,
+) -> SuiStatus {
+    ffi_call(|| {
+        let client = non_null(client)?;
+        let data = client
+            .runtime
+            .block_on(client.client.transaction_builder().transfer_sui(
+                address(signer)?,
+                object_id(coin_id)?,
+                gas_budget,
+                address(recipient)?,
+                amount.as_ref().copied().map(Amount::from_mist),
+            ))
+            .map_err(rpc_error)?;
+        write(out, SuiBuffer::from_vec(data.to_bytes()))
+    })
+}
+
+/// Execute signed transaction data and write the JSON response to `out`.
+///
+/// # Safety
+/// `client` must be a valid handle, `tx_bytes` and `signature` valid for `tx_len` and
+/// `signature_len` bytes of reads, and `out` valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn sui_client_execute(
+    client: *const Client,
+    tx_bytes: *const u8,
+    tx_len: usize,
+    signature: *const u8,
+    signature_len: usize,
+    out: *mut SuiBuffer,
+) -> SuiStatus {
+    ffi_call(|| {
+        let client = non_null(client)?;
+        let data = TransactionData::from_signable_bytes(bytes(tx_bytes, tx_len)?)
+            .map_err(invalid_input)?;
+        let signature =
+            Signature::from_bytes(bytes(signature, signature_len)?).map_err(invalid_input)?;
+        let response = client
+            .runtime
+            .block_on(
+                client
+                    .client
+                    .quorum_driver()
+                    .execute_transaction(Transaction::new(data, signature)),
+            )
+            .map_err(rpc_error)?;
+        let json = serde_json::to_vec(&response).map_err(rpc_error)?;
+        write(out, SuiBuffer::from_vec(json))
+    })
+}
@@ -0,0 +1,288 @@
+// Copyright (c) 2022, Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Bindings of the Sui SDK for mobile platforms, generated with uniffi from
+//! `src/sui_sdk.udl`. Build the library for the target platform, then generate the Kotlin or
+//! Swift sources with
+//!
+//! ```text
+//! uniffi-bindgen generate crates/sui-sdk-ffi/src/sui_sdk.udl --language kotlin
+//! uniffi-bindgen generate crates/sui-sdk-ffi/src/sui_sdk.udl --language swift
+//! ```
+//!
+//! The SDK is asynchronous, while uniffi only exports blocking functions: every [Client]
+//! method blocks on a runtime owned by the client, so call them off the UI thread.
+//!
+//! The same handles are exported to C and C++ through [c_api], and to Python through the
+//! `python` module when the `python` feature is enabled.
+
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::sync::{Arc, Mutex};
+
+use tokio::runtime::Runtime;
+
+use sui_sdk::amount::Amount;
+use sui_sdk::crypto::{KeystoreType, SuiKeystore};
+use sui_sdk::SuiClient;
+use sui_types::base_types::{ObjectID, SuiAddress, TransactionDigest};
+use sui_types::crypto::{SignableBytes, Signature, SignatureScheme, ToFromBytes};
+use sui_types::messages::{Transaction, TransactionData};
+use sui_types::sui_serde::{Base64, Encoding};
+
+uniffi_macros::include_scaffolding!("sui_sdk");
+
+pub mod c_api;
+#[cfg(feature = "python")]
+pub mod python;
+
+#[derive(Debug, thiserror::Error)]
+pub enum SuiSdkError {
+    #[error("Invalid input: {0}")]
+    InvalidInput(String),
+    #[error("Keystore error: {0}")]
+    Keystore(String),
+    #[error("RPC error: {0}")]
+    Rpc(String),
+}
+
+type Result<T> = std::result::Result<T, SuiSdkError>;
+
+fn invalid_input(e: impl ToString) -> SuiSdkError {
+    SuiSdkError::InvalidInput(e.to_string())
+}
+
+fn keystore_error(e: impl ToString) -> SuiSdkError {
+    SuiSdkError::Keystore(e.to_string())
+}
+
+fn rpc_error(e: impl ToString) -> SuiSdkError {
+    SuiSdkError::Rpc(e.to_string())
+}
+
+fn parse_address(address: &str) -> Result<SuiAddress> {
+    SuiAddress::from_str(address).map_err(invalid_input)
+}
+
+fn parse_object_id(object_id: &str) -> Result<ObjectID> {
+    ObjectID::from_hex_literal(object_id).map_err(invalid_input)
+}
+
+fn decode_transaction_data(tx_bytes: &str) -> Result<(Vec<u8>, TransactionData)> {
+    let bytes = Base64::decode(tx_bytes).map_err(invalid_input)?;
+    let data = TransactionData::from_signable_bytes(&bytes).map_err(invalid_input)?;
+    Ok((bytes, data))
+}
+
+fn encode_transaction_data(data: TransactionData) -> String {
+    Base64::encode(data.to_bytes())
+}
+
+pub struct GeneratedKey {
+    pub address: String,
+    pub mnemonic: String,
+}
+
+pub struct ObjectInfo {
+    pub object_id: String,
+    pub version: u64,
+    pub digest: String,
+    pub type_: String,
+}
+
+pub struct Keystore(Mutex<SuiKeystore>);
+
+impl Keystore {
+    pub fn new() -> Self {
+        Self(Mutex::new(
+            KeystoreType::InMem(0)
+                .init()
+                .expect("In-memory keystores cannot fail to initialize"),
+        ))
+    }
+
+    /// Open the keystore file at `path`, creating it if it does not exist.
+    pub fn open(path: String) -> Result<Self> {
+        let keystore = KeystoreType::File(PathBuf::from(path))
+            .init()
+            .map_err(keystore_error)?;
+        Ok(Self(Mutex::new(keystore)))
+    }
+
+    /// Generate an Ed25519 key and return its address and the mnemonic to back it up.
+    pub fn generate_key(&self) -> Result<GeneratedKey> {
+        let (address, mnemonic, _) = self
+            .0
+            .lock()
+            .unwrap()
+            .generate_new_key(SignatureScheme::ED25519, None)
+            .map_err(keystore_error)?;
+        Ok(GeneratedKey {
+            address: address.to_string(),
+            mnemonic,
+        })
+    }
+
+    pub fn import_mnemonic(&self, phrase: String) -> Result<String> {
+        let address = self
+            .0
+            .lock()
+            .unwrap()
+            .import_from_mnemonic(&phrase, SignatureScheme::ED25519, None)
+            .map_err(keystore_error)?;
+        Ok(address.to_string())
+    }
+
+    pub fn addresses(&self) -> Vec<String> {
+        self.0
+            .lock()
+            .unwrap()
+            .addresses()
+            .iter()
+            .map(SuiAddress::to_string)
+            .collect()
+    }
+
+    /// Sign Base64 encoded transaction data with the key of `address`, and return the
+    /// Base64 encoded signature.
+    pub fn sign(&self, address: String, tx_bytes: String) -> Result<String> {
+        let address = parse_address(&address)?;
+        let (bytes, _) = decode_transaction_data(&tx_bytes)?;
+        let signature = self
+            .0
+            .lock()
+            .unwrap()
+            .sign(&address, &bytes)
+            .map_err(keystore_error)?;
+        Ok(Base64::encode(signature))
+    }
+}
+
+impl Default for Keystore {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+pub struct Client {
+    runtime: Runtime,
+    client: SuiClient,
+}
+
+impl Client {
+    pub fn new(http_url: String) -> Result<Self> {
+        let runtime = Runtime::new().map_err(rpc_error)?;
+        let client = runtime
+            .block_on(SuiClient::new_rpc_client(&http_url, None))
+            .map_err(rpc_error)?;
+        Ok(Self { runtime, client })
+    }
+
+    pub fn get_owned_objects(&self, address: String) -> Result<Vec<ObjectInfo>> {
+        let address = parse_address(&address)?;
+        let objects = self
+            .runtime
+            .block_on(self.client.read_api().get_objects_owned_by_address(address))
+            .map_err(rpc_error)?;
+        Ok(objects
+            .into_iter()
+            .map(|info| ObjectInfo {
+                object_id: info.object_id.to_string(),
+                version: info.version.value(),
+                digest: Base64::encode(info.digest),
+                type_: info.type_,
+            })
+            .collect())
+    }
+
+    /// The parsed object, as the JSON returned by `sui_getObject`.
+    pub fn get_object(&self, object_id: String) -> Result<String> {
+        let object_id = parse_object_id(&object_id)?;
+        let object = self
+            .runtime
+            .block_on(self.client.read_api().get_parsed_object(object_id))
+            .map_err(rpc_error)?;
+        serde_json::to_string(&object).map_err(rpc_error)
+    }
+
+    /// The transaction and its effects, as the JSON returned by `sui_getTransaction`.
+    pub fn get_transaction(&self, digest: String) -> Result<String> {
+        let digest = TransactionDigest::from_str(&digest).map_err(invalid_input)?;
+        let transaction = self
+            .runtime
+            .block_on(self.client.read_api().get_transaction(digest))
+            .map_err(rpc_error)?;
+        serde_json::to_string(&transaction).map_err(rpc_error)
+    }
+
+    /// Build a SUI transfer and return the Base64 encoded transaction data to sign.
+    pub fn transfer_sui(
+        &self,
+        signer: String,
+        coin_id: String,
+        gas_budget: u64,
+        recipient: String,
+        amount: Option<u64>,
+    ) -> Result<String> {
+        let data = self
+            .runtime
+            .block_on(self.client.transaction_builder().transfer_sui(
+                parse_address(&signer)?,
+                parse_object_id(&coin_id)?,
+                gas_budget,
+                parse_address(&recipient)?,
+                amount.map(Amount::from_mist),
+            ))
+            .map_err(rpc_error)?;
+        Ok(encode_transaction_data(data))
+    }
+
+    /// Build an object transfer and return the Base64 encoded transaction data to sign.
+    pub fn transfer_object(
+        &self,
+        signer: String,
+        object_id: String,
+        gas: Option<String>,
+        gas_budget: u64,
+        recipient: String,
+    ) -> Result<String> {
+        let gas = gas.as_deref().map(parse_object_id).transpose()?;
+        let data = self
+            .runtime
+            .block_on(self.client.transaction_builder().transfer_object(
+                parse_address(&signer)?,
+                parse_object_id(&object_id)?,
+                gas,
+                gas_budget,
+                parse_address(&recipient)?,
+            ))
+            .map_err(rpc_error)?;
+        Ok(encode_transaction_data(data))
+    }
+
+    /// Execute signed transaction data and return the response as JSON.
+    pub fn execute(&self, tx_bytes: String, signature: String) -> Result<String> {
+        let (_, data) = decode_transaction_data(&tx_bytes)?;
+        let signature = Base64::decode(&signature).map_err(invalid_input)?;
+        let signature = Signature::from_bytes(&signature).map_err(invalid_input)?;
+        let response = self
+            .runtime
+            .block_on(
+                self.client
+                    .quorum_driver()
+                    .execute_transaction(Transaction::new(data, signature)),
+            )
+            .map_err(rpc_error)?;
+        serde_json::to_string(&response).map_err(rpc_error)
+    }
+
+    pub fn sign_and_execute(
+        &self,
+        keystore: Arc<Keystore>,
+        signer: String,
+        tx_bytes: String,
+    ) -> Result<String> {
+        let signature = keystore.sign(signer, tx_bytes.clone())?;
+        self.execute(tx_bytes, signature)
+    }
+}
@@ -0,0 +1,159 @@
+// Copyright (c) 2022, Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! A Python extension module over the SDK, built with the `python` feature, e.g. with
+//!
+//! ```text
+//! maturin build --manifest-path crates/sui-sdk-ffi/Cargo.toml --features python
+//! ```
+//!
+//! The module exports the same `Keystore` and `Client` classes as the mobile bindings.
+//! Transaction data and signatures are Base64 strings and responses are the JSON returned by
+//! the node, e.g. to load with `json.loads`. Blocking calls release the GIL.
+
+use pyo3::exceptions::{PyRuntimeError, PyValueError};
+use pyo3::prelude::*;
+use pyo3::types::PyDict;
+
+use crate::{Client, Keystore, SuiSdkError};
+
+impl From<SuiSdkError> for PyErr {
+    fn from(e: SuiSdkError) -> Self {
+        match e {
+            SuiSdkError::InvalidInput(_) => PyValueError::new_err(e.to_string()),
+            SuiSdkError::Keystore(_) | SuiSdkError::Rpc(_) => {
+                PyRuntimeError::new_err(e.to_string())
+            }
+        }
+    }
+}
+
+#[pyclass(name = "Keystore")]
+pub struct PyKeystore(Keystore);
+
+#[pymethods]
+impl PyKeystore {
+    /// An empty in-memory keystore.
+    #[new]
+    fn new() -> Self {
+        Self(Keystore::new())
+    }
+
+    /// Open the keystore file at `path`, creating it if it does not exist.
+    #[staticmethod]
+    fn open(path: String) -> PyResult<Self> {
+        Ok(Self(Keystore::open(path)?))
+    }
+
+    /// Generate an Ed25519 key and return its address and mnemonic.
+    fn generate_key(&self) -> PyResult<(String, String)> {
+        let key = self.0.generate_key()?;
+        Ok((key.address, key.mnemonic))
+    }
+
+    fn import_mnemonic(&self, phrase: String) -> PyResult<String> {
+        Ok(self.0.import_mnemonic(phrase)?)
+    }
+
+    fn addresses(&self) -> Vec<String> {
+        self.0.addresses()
+    }
+
+    fn sign(&self, address: String, tx_bytes: String) -> PyResult<String> {
+        Ok(self.0.sign(address, tx_bytes)?)
+    }
+}
+
+#[pyclass(name = "Client")]
+pub struct PyClient(Client);
+
+#[pymethods]
+impl PyClient {
+    #[new]
+    fn new(py: Python<'_>, http_url: String) -> PyResult<Self> {
+        Ok(Self(py.allow_threads(|| Client::new(http_url))?))
+    }
+
+    /// The objects owned by `address`, as dicts of `object_id`, `version`, `digest` and
+    /// `type`.
+    fn get_owned_objects<'py>(
+        &self,
+        py: Python<'py>,
+        address: String,
+    ) -> PyResult<Vec<&'py PyDict>> {
+        let objects = py.allow_threads(|| self.0.get_owned_objects(address))?;
+        objects
+            .into_iter()
+            .map(|object| {
+                let dict = PyDict::new(py);
+                dict.set_item("object_id", object.object_id)?;
+                dict.set_item("version", object.version)?;
+                dict.set_item("digest", object.digest)?;
+                dict.set_item("type", object.type_)?;
+                Ok(dict)
+            })
+            .collect()
+    }
+
+    fn get_object(&self, py: Python<'_>, object_id: String) -> PyResult<String> {
+        Ok(py.allow_threads(|| self.0.get_object(object_id))?)
+    }
+
+    fn get_transaction(&self, py: Python<'_>, digest: String) -> PyResult<String> {
+        Ok(py.allow_threads(|| self.0.get_transaction(digest))?)
+    }
+
+    /// Build a SUI transfer, of the whole coin if `amount` is None.
+    fn transfer_sui(
+        &self,
+        py: Python<'_>,
+        signer: String,
+        coin_id: String,
+        gas_budget: u64,
+        recipient: String,
+        amount: Option<u64>,
+    ) -> PyResult<String> {
+        Ok(py.allow_threads(|| {
+            self.0
+                .transfer_sui(signer, coin_id, gas_budget, recipient, amount)
+        })?)
+    }
+
+    /// Build an object transfer, selecting a gas object if `gas` is None.
+    fn transfer_object(
+        &self,
+        py: Python<'_>,
+        signer: String,
+        object_id: String,
+        gas: Option<String>,
+        gas_budget: u64,
+        recipient: String,
+    ) -> PyResult<String> {
+        Ok(py.allow_threads(|| {
+            self.0
+                .transfer_object(signer, object_id, gas, gas_budget, recipient)
+        })?)
+    }
+
+    fn execute(&self, py: Python<'_>, tx_bytes: String, signature: String) -> PyResult<String> {
+        Ok(py.allow_threads(|| self.0.execute(tx_bytes, signature))?)
+    }
+
+    fn sign_and_execute(
+        &self,
+        py: Python<'_>,
+        keystore: &PyKeystore,
+        signer: String,
+        tx_bytes: String,
+    ) -> PyResult<String> {
+        let signature = keystore.0.sign(signer, tx_bytes.clone())?;
+        self.execute(py, tx_bytes, signature)
+    }
+}
+
+#[pymodule]
+fn sui_sdk_ffi(_py: Python<'_>, m: &PyModule) -> PyResult<()> {
+    m.add_class::<PyKeystore>()?;
+    m.add_class::<PyClient>()?;
+    Ok(())
+}
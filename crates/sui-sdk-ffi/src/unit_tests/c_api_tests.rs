@@ -0,0 +1,131 @@
+// Copyright (c) 2022, Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+use std::ffi::CStr;
+use std::ptr;
+
+use sui_types::base_types::{SuiAddress, SUI_ADDRESS_LENGTH};
+use sui_types::crypto::Signature;
+
+use super::*;
+
+fn last_error() -> String {
+    let message = sui_last_error_message();
+    assert!(!message.is_null());
+    unsafe { CStr::from_ptr(message) }
+        .to_str()
+        .unwrap()
+        .to_string()
+}
+
+#[test]
+fn test_null_pointers() {
+    unsafe {
+        assert_eq!(sui_keystore_new(ptr::null_mut()), SuiStatus::NullPointer);
+        assert_eq!(last_error(), "Unexpected null output pointer.");
+
+        let mut address = [0u8; SUI_ADDRESS_LENGTH];
+        assert_eq!(
+            sui_keystore_generate_key(ptr::null(), address.as_mut_ptr()),
+            SuiStatus::NullPointer
+        );
+        assert_eq!(last_error(), "Unexpected null pointer.");
+
+        let mut out = SuiBuffer {
+            data: ptr::null_mut(),
+            len: 0,
+        };
+        assert_eq!(
+            sui_keystore_sign(ptr::null(), address.as_ptr(), ptr::null(), 0, &mut out),
+            SuiStatus::NullPointer
+        );
+        assert!(out.data.is_null());
+
+        // Freeing null handles and empty buffers is a no-op.
+        sui_keystore_free(ptr::null_mut());
+        sui_client_free(ptr::null_mut());
+        sui_buffer_free(out);
+    }
+}
+
+#[test]
+fn test_last_error() {
+    unsafe {
+        let mut keystore = ptr::null_mut();
+        assert_eq!(sui_keystore_new(&mut keystore), SuiStatus::Ok);
+
+        // Signing with an address the keystore does not hold fails, and reports why.
+        let address = [0u8; SUI_ADDRESS_LENGTH];
+        let mut out = SuiBuffer {
+            data: ptr::null_mut(),
+            len: 0,
+        };
+        let tx_bytes = b"message";
+        assert_eq!(
+            sui_keystore_sign(
+                keystore,
+                address.as_ptr(),
+                tx_bytes.as_ptr(),
+                tx_bytes.len(),
+                &mut out
+            ),
+            SuiStatus::KeystoreError
+        );
+        assert!(!last_error().is_empty());
+
+        // Successful calls leave the message of the last failure in place.
+        let mut generated = [0u8; SUI_ADDRESS_LENGTH];
+        assert_eq!(
+            sui_keystore_generate_key(keystore, generated.as_mut_ptr()),
+            SuiStatus::Ok
+        );
+        assert!(!sui_last_error_message().is_null());
+
+        sui_keystore_free(keystore);
+    }
+}
+
+#[test]
+fn test_sign() {
+    unsafe {
+        let mut keystore = ptr::null_mut();
+        assert_eq!(sui_keystore_new(&mut keystore), SuiStatus::Ok);
+        let mut address = [0u8; SUI_ADDRESS_LENGTH];
+        assert_eq!(
+            sui_keystore_generate_key(keystore, address.as_mut_ptr()),
+            SuiStatus::Ok
+        );
+
+        let mut out = SuiBuffer {
+            data: ptr::null_mut(),
+            len: 0,
+        };
+        let tx_bytes = b"message";
+        assert_eq!(
+            sui_keystore_sign(
+                keystore,
+                address.as_ptr(),
+                tx_bytes.as_ptr(),
+                tx_bytes.len(),
+                &mut out
+            ),
+            SuiStatus::Ok
+        );
+        let signature = std::slice::from_raw_parts(out.data, out.len).to_vec();
+        sui_buffer_free(out);
+
+        // The buffer holds the same `flag || signature || public key` bytes the keystore
+        // produces for the generated address.
+        let address = SuiAddress::try_from(address.as_slice()).unwrap();
+        let expected = (*keystore)
+            .0
+            .lock()
+            .unwrap()
+            .sign(&address, tx_bytes)
+            .unwrap();
+        assert_eq!(signature, expected.as_ref());
+        assert!(Signature::from_bytes(&signature).is_ok());
+
+        sui_keystore_free(keystore);
+    }
+}
@@ -3,6 +3,7 @@
 
 use std::str::FromStr;
 use sui_sdk::{
+    amount::Amount,
     crypto::KeystoreType,
     types::{
         base_types::{ObjectID, SuiAddress},
@@ -27,7 +28,13 @@ async fn main() -> Result<(), anyhow::Error> {
     // Create a sui transfer transaction
     let transfer_tx = sui
         .transaction_builder()
-        .transfer_sui(my_address, gas_object_id, 1000, recipient, Some(1000))
+        .transfer_sui(
+            my_address,
+            gas_object_id,
+            1000,
+            recipient,
+            Some(Amount::from_mist(1000)),
+        )
         .await?;
 
     // Get signer from keystore
@@ -0,0 +1,234 @@
+// Copyright (c) 2022, Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Ledgers of the balance changes of an address, classified for accounting software and
+//! tax reports.
+//!
+//! Every transaction from or to the address gives one entry per coin type whose balance it
+//! changed, plus a [LedgerEntryKind::Gas] entry for the gas paid by the address, which is
+//! left out of the other SUI entries. In this version of the protocol, staking rewards are
+//! paid out together with the withdrawn stake, so a [LedgerEntryKind::StakingReward] entry
+//! includes the principal.
+
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use sui_json_rpc_types::{GatewayTxSeqNumber, SuiTransactionKind, SuiTransactionResponse};
+use sui_types::base_types::{SuiAddress, TransactionDigest};
+use sui_types::sui_system_state::SUI_SYSTEM_MODULE_NAME;
+use sui_types::SUI_FRAMEWORK_OBJECT_ID;
+
+use crate::amount::{Amount, NumberFormat};
+use crate::balance_changes::{
+    address_transactions, balance_changes, BalanceChange, BalanceDirection,
+};
+use crate::coin_type::CoinType;
+use crate::error::SuiRpcResult;
+use crate::export::{write_rows, ColumnType, ColumnValue, ExportFormat, ExportRow};
+use crate::SuiClient;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LedgerEntryKind {
+    TransferIn,
+    TransferOut,
+    /// SUI paid to the system by a staking or delegation request.
+    Stake,
+    /// SUI received from the system by withdrawing a stake or delegation.
+    StakingReward,
+    Gas,
+    /// One leg of a transaction that both credits and debits the address in different coin
+    /// types.
+    Swap,
+}
+
+impl LedgerEntryKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::TransferIn => "transfer_in",
+            Self::TransferOut => "transfer_out",
+            Self::Stake => "stake",
+            Self::StakingReward => "staking_reward",
+            Self::Gas => "gas",
+            Self::Swap => "swap",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LedgerEntry {
+    pub seq: GatewayTxSeqNumber,
+    pub tx_digest: TransactionDigest,
+    pub timestamp_ms: Option<u64>,
+    pub kind: LedgerEntryKind,
+    /// The coin type, e.g. `0x2::sui::SUI`.
+    pub coin_type: String,
+    pub direction: BalanceDirection,
+    /// The amount in base units.
+    pub amount: u64,
+    /// The decimals of the coin type, if its currency metadata could be found.
+    pub decimals: Option<u8>,
+}
+
+impl LedgerEntry {
+    /// The amount in whole coins, negative for debits, e.g. `-1.5`, if the decimals are
+    /// known.
+    pub fn quantity(&self) -> Option<String> {
+        let amount =
+            Amount::from_base_units(self.amount, self.decimals?).format(&NumberFormat::PLAIN);
+        Some(match self.direction {
+            BalanceDirection::Credit => amount.to_string(),
+            BalanceDirection::Debit => format!("-{amount}"),
+        })
+    }
+}
+
+impl ExportRow for LedgerEntry {
+    fn schema() -> &'static [(&'static str, ColumnType)] {
+        &[
+            ("seq", ColumnType::Int64),
+            ("tx_digest", ColumnType::Utf8),
+            ("timestamp_ms", ColumnType::Int64),
+            ("kind", ColumnType::Utf8),
+            ("coin_type", ColumnType::Utf8),
+            ("direction", ColumnType::Utf8),
+            ("amount", ColumnType::Int64),
+            ("decimals", ColumnType::Int64),
+            ("quantity", ColumnType::Utf8),
+        ]
+    }
+
+    fn values(&self) -> Vec<ColumnValue> {
+        vec![
+            ColumnValue::int(self.seq),
+            ColumnValue::text(self.tx_digest),
+            ColumnValue::Int64(self.timestamp_ms.map(|t| t as i64)),
+            ColumnValue::text(self.kind.as_str()),
+            ColumnValue::text(&self.coin_type),
+            ColumnValue::text(match self.direction {
+                BalanceDirection::Credit => "credit",
+                BalanceDirection::Debit => "debit",
+            }),
+            ColumnValue::int(self.amount),
+            ColumnValue::Int64(self.decimals.map(i64::from)),
+            ColumnValue::Utf8(self.quantity()),
+        ]
+    }
+}
+
+/// The ledger entries of `address` for a transaction, given its balance changes. Decimals
+/// are left unset.
+pub fn ledger_entries(
+    seq: GatewayTxSeqNumber,
+    address: SuiAddress,
+    response: &SuiTransactionResponse,
+    changes: &[BalanceChange],
+) -> Vec<LedgerEntry> {
+    let entry = |kind: LedgerEntryKind, coin_type: String, total: i128| LedgerEntry {
+        seq,
+        tx_digest: response.certificate.transaction_digest,
+        timestamp_ms: response.timestamp_ms,
+        kind,
+        coin_type,
+        direction: if total > 0 {
+            BalanceDirection::Credit
+        } else {
+            BalanceDirection::Debit
+        },
+        amount: total.unsigned_abs() as u64,
+        decimals: None,
+    };
+
+    let mut totals = BTreeMap::<String, i128>::new();
+    for change in changes {
+        let amount = change.amount as i128;
+        *totals.entry(change.coin_type.clone()).or_default() += match change.direction {
+            BalanceDirection::Credit => amount,
+            BalanceDirection::Debit => -amount,
+        };
+    }
+
+    let mut entries = vec![];
+    if response.certificate.data.sender == address {
+        let gas = &response.effects.gas_used;
+        let fee =
+            gas.computation_cost as i128 + gas.storage_cost as i128 - gas.storage_rebate as i128;
+        if fee != 0 {
+            let sui = CoinType::sui().to_string();
+            *totals.entry(sui.clone()).or_default() += fee;
+            entries.push(entry(LedgerEntryKind::Gas, sui, -fee));
+        }
+    }
+
+    let system_call = response.certificate.data.transactions.iter().any(|kind| {
+        matches!(kind, SuiTransactionKind::Call(call)
+            if call.package.object_id == SUI_FRAMEWORK_OBJECT_ID
+                && call.module == SUI_SYSTEM_MODULE_NAME.as_str())
+    });
+    let swap = totals.values().any(|total| *total > 0) && totals.values().any(|total| *total < 0);
+    for (coin_type, total) in totals {
+        if total == 0 {
+            continue;
+        }
+        let kind = match (swap, system_call, total > 0) {
+            (true, _, _) => LedgerEntryKind::Swap,
+            (false, true, true) => LedgerEntryKind::StakingReward,
+            (false, true, false) => LedgerEntryKind::Stake,
+            (false, false, true) => LedgerEntryKind::TransferIn,
+            (false, false, false) => LedgerEntryKind::TransferOut,
+        };
+        entries.push(entry(kind, coin_type, total));
+    }
+    entries
+}
+
+/// The ledger of an address, ordered by transaction.
+#[derive(Debug, Clone)]
+pub struct AccountingReport {
+    pub address: SuiAddress,
+    pub entries: Vec<LedgerEntry>,
+}
+
+impl AccountingReport {
+    /// Write the ledger to a new file at `path`, one row per entry.
+    pub fn write(&self, format: ExportFormat, path: &Path) -> SuiRpcResult<()> {
+        write_rows(&self.entries, format, path)
+    }
+}
+
+impl SuiClient {
+    /// Walk the full transaction history of `address` and build its ledger, with amounts in
+    /// whole coins for coin types whose decimals are known.
+    pub async fn get_accounting_report(
+        &self,
+        address: SuiAddress,
+    ) -> SuiRpcResult<AccountingReport> {
+        let read_api = self.read_api();
+        let transactions = address_transactions(self.full_node_api(), address).await?;
+        let digests = transactions
+            .iter()
+            .map(|(_, digest)| *digest)
+            .collect::<Vec<_>>();
+        let responses = read_api.multi_get_transactions(&digests).await?;
+
+        let mut decimals = BTreeMap::<String, Option<u8>>::new();
+        let mut entries = vec![];
+        for ((seq, _), response) in transactions.into_iter().zip(responses) {
+            let changes = balance_changes(read_api, address, &response).await?;
+            for mut entry in ledger_entries(seq, address, &response, &changes) {
+                if !decimals.contains_key(&entry.coin_type) {
+                    let metadata = self
+                        .coin_registry()
+                        .get_coin_metadata(&entry.coin_type)
+                        .await;
+                    decimals.insert(
+                        entry.coin_type.clone(),
+                        metadata.ok().map(|metadata| metadata.decimals),
+                    );
+                }
+                entry.decimals = decimals[&entry.coin_type];
+                entries.push(entry);
+            }
+        }
+        Ok(AccountingReport { address, entries })
+    }
+}
@@ -0,0 +1,54 @@
+// Copyright (c) 2022, Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Address derivation without keystore or network, e.g. for backends validating the
+//! addresses users submit together with their public key.
+//!
+//! Addresses are derived with the same code as [sui_sdk_core], the signing core of
+//! embedded signers. This version of the protocol has single key accounts only, so there is
+//! no derivation for multi-signature or zkLogin accounts.
+
+use anyhow::anyhow;
+
+use sui_types::base_types::SuiAddress;
+use sui_types::crypto::{PublicKey, SignatureScheme};
+use sui_types::sui_serde::{Base64, Encoding};
+
+use crate::error::{Error, SuiRpcResult};
+
+/// The address of the raw `public_key` of `scheme`, 32 bytes for Ed25519 and 33 bytes,
+/// compressed, for Secp256k1.
+pub fn address_from_public_key(
+    scheme: SignatureScheme,
+    public_key: &[u8],
+) -> SuiRpcResult<SuiAddress> {
+    let scheme = match scheme {
+        SignatureScheme::ED25519 => sui_sdk_core::SignatureScheme::Ed25519,
+        SignatureScheme::Secp256k1 => sui_sdk_core::SignatureScheme::Secp256k1,
+        SignatureScheme::BLS12381 => {
+            return Err(Error::Other(anyhow!("BLS12381 keys are not account keys.")))
+        }
+    };
+    let address = sui_sdk_core::address_from_public_key(scheme, public_key)?;
+    Ok(SuiAddress::try_from(&address[..])?)
+}
+
+/// The address of a Base64 encoded public key prefixed with its scheme flag, as printed by
+/// `sui keytool` and stored in keystores.
+pub fn address_from_encoded_public_key(flag_and_public_key: &str) -> SuiRpcResult<SuiAddress> {
+    let bytes = Base64::decode(flag_and_public_key)?;
+    let (flag, public_key) = match bytes.split_first() {
+        Some(split) => split,
+        None => return Err(Error::Other(anyhow!("Empty public key."))),
+    };
+    let scheme = match sui_sdk_core::SignatureScheme::from_flag(*flag)? {
+        sui_sdk_core::SignatureScheme::Ed25519 => SignatureScheme::ED25519,
+        sui_sdk_core::SignatureScheme::Secp256k1 => SignatureScheme::Secp256k1,
+    };
+    address_from_public_key(scheme, public_key)
+}
+
+/// Whether `address` is the address of `public_key`.
+pub fn is_address_of(address: &SuiAddress, public_key: &PublicKey) -> bool {
+    *address == SuiAddress::from(public_key)
+}
@@ -0,0 +1,557 @@
+// Copyright (c) 2022, Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Airdrops of coins or NFTs to the recipients listed in a CSV file.
+//!
+//! The CSV file has a `recipient` column and either an `amount` column, for coin airdrops,
+//! or `name`, `description` and `url` columns, for NFT airdrops. Rows with an invalid
+//! address or amount are reported without being sent.
+//!
+//! Coins are sent with one `Pay` transaction per batch of recipients. Transactions run
+//! concurrently in lanes: every lane owns a gas coin, and for coin airdrops an input coin,
+//! so concurrent transactions never lock the same objects. NFTs are minted to the sender
+//! and then transferred, one row at a time per lane, as the order of the objects created by
+//! a batch of mints is not known.
+//!
+//! [Airdrop::run] returns one [AirdropResult] per row, which [write_report] writes to a CSV
+//! file with the status and transaction digest of every row.
+
+use std::collections::VecDeque;
+use std::io::Read;
+use std::path::Path;
+use std::str::FromStr;
+use std::sync::Mutex;
+
+use anyhow::{anyhow, bail};
+use futures::future::join_all;
+
+use sui_json_rpc_types::{
+    SuiExecuteTransactionResponse, SuiExecutionStatus, SuiTransactionEffects,
+};
+use sui_types::base_types::{ObjectID, SuiAddress, TransactionDigest};
+use sui_types::gas_coin::GasCoin;
+use sui_types::messages::{ExecuteTransactionRequestType, Transaction, TransactionData};
+use sui_types::object::Owner;
+use sui_types::SUI_FRAMEWORK_OBJECT_ID;
+
+use crate::amount::Amount;
+use crate::coin_type::normalize_coin_type;
+use crate::crypto::SuiKeystore;
+use crate::error::{Error, SuiRpcResult};
+use crate::SuiClient;
+
+/// What a row of an airdrop sends to its recipient.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AirdropItem {
+    Coins(u64),
+    Nft {
+        name: String,
+        description: String,
+        url: String,
+    },
+}
+
+/// A valid row of an airdrop file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AirdropEntry {
+    /// Line of the row in the file, the header being line 1.
+    pub line: usize,
+    pub recipient: SuiAddress,
+    pub item: AirdropItem,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AirdropStatus {
+    Success,
+    /// The row is invalid and was not sent.
+    Invalid,
+    /// The transaction of the row failed or could not be executed.
+    Failed,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AirdropResult {
+    pub line: usize,
+    pub recipient: String,
+    pub status: AirdropStatus,
+    pub digest: Option<TransactionDigest>,
+    pub error: Option<String>,
+}
+
+impl AirdropResult {
+    fn invalid(line: usize, recipient: &str, error: impl ToString) -> Self {
+        Self {
+            line,
+            recipient: recipient.to_string(),
+            status: AirdropStatus::Invalid,
+            digest: None,
+            error: Some(error.to_string()),
+        }
+    }
+}
+
+/// Parse an airdrop CSV file. Returns the valid rows, and a result for every invalid row.
+pub fn read_entries<R: Read>(reader: R) -> SuiRpcResult<(Vec<AirdropEntry>, Vec<AirdropResult>)> {
+    let mut reader = csv::ReaderBuilder::new()
+        .trim(csv::Trim::All)
+        .from_reader(reader);
+    let headers = reader.headers()?.clone();
+    let column = |name: &str| headers.iter().position(|header| header == name);
+    let recipient_column =
+        column("recipient").ok_or_else(|| anyhow!("Airdrop file has no recipient column."))?;
+    let amount_column = column("amount");
+    let nft_columns = match (column("name"), column("description"), column("url")) {
+        (Some(name), Some(description), Some(url)) => Some((name, description, url)),
+        _ => None,
+    };
+    if amount_column.is_none() && nft_columns.is_none() {
+        return Err(Error::Other(anyhow!(
+            "Airdrop file needs an amount column, or name, description and url columns."
+        )));
+    }
+
+    let mut entries = vec![];
+    let mut invalid = vec![];
+    for (index, record) in reader.records().enumerate() {
+        let line = index + 2;
+        let record = record?;
+        let field = |column: usize| record.get(column).unwrap_or_default();
+        let recipient = field(recipient_column);
+        let address = match SuiAddress::from_str(recipient) {
+            Ok(address) => address,
+            Err(e) => {
+                invalid.push(AirdropResult::invalid(line, recipient, e));
+                continue;
+            }
+        };
+        let item = if let Some(amount_column) = amount_column {
+            match field(amount_column).parse::<u64>() {
+                Ok(amount) if amount > 0 => AirdropItem::Coins(amount),
+                _ => {
+                    let error = format!("Invalid amount [{}].", field(amount_column));
+                    invalid.push(AirdropResult::invalid(line, recipient, error));
+                    continue;
+                }
+            }
+        } else {
+            let (name, description, url) = nft_columns.unwrap();
+            if field(name).is_empty() {
+                invalid.push(AirdropResult::invalid(
+                    line,
+                    recipient,
+                    "NFT name is empty.",
+                ));
+                continue;
+            }
+            AirdropItem::Nft {
+                name: field(name).to_string(),
+                description: field(description).to_string(),
+                url: field(url).to_string(),
+            }
+        };
+        entries.push(AirdropEntry {
+            line,
+            recipient: address,
+            item,
+        });
+    }
+    Ok((entries, invalid))
+}
+
+/// Write `results` to a CSV file at `path`, ordered by line.
+pub fn write_report(results: &[AirdropResult], path: &Path) -> SuiRpcResult<()> {
+    let mut results = results.iter().collect::<Vec<_>>();
+    results.sort_by_key(|result| result.line);
+    let mut writer = csv::Writer::from_path(path)?;
+    writer.write_record(["line", "recipient", "status", "digest", "error"])?;
+    for result in results {
+        let status = match result.status {
+            AirdropStatus::Success => "success",
+            AirdropStatus::Invalid => "invalid",
+            AirdropStatus::Failed => "failed",
+        };
+        writer.write_record([
+            result.line.to_string(),
+            result.recipient.clone(),
+            status.to_string(),
+            result.digest.map(|d| d.to_string()).unwrap_or_default(),
+            result.error.clone().unwrap_or_default(),
+        ])?;
+    }
+    writer.flush()?;
+    Ok(())
+}
+
+/// The objects owned by a lane: its gas coin and, for coin airdrops, the coin it pays from
+/// with its remaining balance.
+struct Lane {
+    gas: ObjectID,
+    input: Option<(ObjectID, u64)>,
+}
+
+pub struct Airdrop<'a> {
+    client: &'a SuiClient,
+    keystore: &'a SuiKeystore,
+    sender: SuiAddress,
+    /// The type of the airdropped coins, e.g. `0x2::coin::Coin<0x2::sui::SUI>`.
+    coin_type: String,
+    /// The package, module and function minting NFTs, following the layout of
+    /// `0x2::devnet_nft::mint`.
+    nft_mint: (ObjectID, String, String),
+    gas_budget: u64,
+    batch_size: usize,
+    max_transaction_size: usize,
+    max_concurrency: usize,
+}
+
+impl<'a> Airdrop<'a> {
+    /// An airdrop of SUI, or of `0x2::devnet_nft::DevNetNFT`s, from `sender`.
+    pub fn new(client: &'a SuiClient, keystore: &'a SuiKeystore, sender: SuiAddress) -> Self {
+        Self {
+            client,
+            keystore,
+            sender,
+            coin_type: GasCoin::type_().to_string(),
+            nft_mint: (
+                SUI_FRAMEWORK_OBJECT_ID,
+                "devnet_nft".to_string(),
+                "mint".to_string(),
+            ),
+            gas_budget: 10_000,
+            batch_size: 100,
+            max_transaction_size: 128 * 1024,
+            max_concurrency: 4,
+        }
+    }
+
+    pub fn with_coin_type(mut self, coin_type: impl Into<String>) -> Self {
+        let coin_type = coin_type.into();
+        self.coin_type = normalize_coin_type(&coin_type).unwrap_or(coin_type);
+        self
+    }
+
+    pub fn with_nft_mint(mut self, package: ObjectID, module: &str, function: &str) -> Self {
+        self.nft_mint = (package, module.to_string(), function.to_string());
+        self
+    }
+
+    /// Gas budget of every transaction.
+    pub fn with_gas_budget(mut self, gas_budget: u64) -> Self {
+        self.gas_budget = gas_budget;
+        self
+    }
+
+    /// Maximum number of recipients of a coin transaction, bounding its gas cost.
+    pub fn with_batch_size(mut self, batch_size: usize) -> Self {
+        self.batch_size = batch_size.max(1);
+        self
+    }
+
+    /// Maximum size of a serialized transaction. Larger batches are split.
+    pub fn with_max_transaction_size(mut self, max_transaction_size: usize) -> Self {
+        self.max_transaction_size = max_transaction_size;
+        self
+    }
+
+    /// Maximum number of transactions executing at once, bounded by the coins of the sender.
+    pub fn with_max_concurrency(mut self, max_concurrency: usize) -> Self {
+        self.max_concurrency = max_concurrency.max(1);
+        self
+    }
+
+    /// Send the items of `entries`. Returns one result per entry.
+    pub async fn run(&self, entries: Vec<AirdropEntry>) -> SuiRpcResult<Vec<AirdropResult>> {
+        let (coins, nfts): (Vec<_>, Vec<_>) = entries
+            .into_iter()
+            .partition(|entry| matches!(entry.item, AirdropItem::Coins(_)));
+        let mut batches = VecDeque::new();
+        for batch in coins.chunks(self.batch_size) {
+            batches.push_back(batch.to_vec());
+        }
+        batches.extend(nfts.into_iter().map(|entry| vec![entry]));
+        if batches.is_empty() {
+            return Ok(vec![]);
+        }
+
+        let lanes = self.lanes(batches.iter().flatten()).await?;
+        let queue = Mutex::new(batches);
+        let results = Mutex::new(vec![]);
+        join_all(
+            lanes
+                .into_iter()
+                .map(|lane| self.run_lane(lane, &queue, &results)),
+        )
+        .await;
+
+        let mut results = results.into_inner().unwrap();
+        // Batches left behind exceeded the balance of every lane.
+        for entry in queue.into_inner().unwrap().into_iter().flatten() {
+            results.push(failed(&entry, None, "Insufficient balance."));
+        }
+        Ok(results)
+    }
+
+    /// Assign the coins of the sender to at most `max_concurrency` lanes.
+    async fn lanes(
+        &self,
+        entries: impl Iterator<Item = &AirdropEntry>,
+    ) -> anyhow::Result<Vec<Lane>> {
+        let needs_input = entries
+            .into_iter()
+            .any(|entry| matches!(entry.item, AirdropItem::Coins(_)));
+        let mut coins = self
+            .client
+            .transaction_builder()
+            .get_coins(self.sender)
+            .await?;
+        let by_balance = |coins: Option<Vec<(_, u64)>>| {
+            let mut coins = coins.unwrap_or_default();
+            coins.sort_by_key(|(_, balance)| std::cmp::Reverse(*balance));
+            coins
+        };
+        let gas_type = GasCoin::type_().to_string();
+        let mut gas_coins = by_balance(coins.remove(&gas_type))
+            .into_iter()
+            .filter(|(_, balance)| *balance >= self.gas_budget)
+            .map(|(coin, balance)| (coin.0, balance))
+            .collect::<Vec<_>>();
+        let mut input_coins = if !needs_input {
+            vec![]
+        } else if self.coin_type == gas_type {
+            // The largest SUI coins pay, the next ones pay for gas.
+            let count = (gas_coins.len() / 2).min(self.max_concurrency);
+            gas_coins.drain(..count).collect()
+        } else {
+            by_balance(coins.remove(&self.coin_type))
+                .into_iter()
+                .map(|(coin, balance)| (coin.0, balance))
+                .collect()
+        };
+
+        let mut count = gas_coins.len().min(self.max_concurrency);
+        if needs_input {
+            count = count.min(input_coins.len());
+        }
+        if count == 0 {
+            bail!(
+                "Sender [{}] needs a SUI coin for gas, and a coin of type [{}] to pay from.",
+                self.sender,
+                self.coin_type
+            );
+        }
+        input_coins.truncate(count);
+        let mut input_coins = input_coins.into_iter();
+        Ok(gas_coins
+            .into_iter()
+            .take(count)
+            .map(|(gas, _)| Lane {
+                gas,
+                input: input_coins.next(),
+            })
+            .collect())
+    }
+
+    async fn run_lane(
+        &self,
+        mut lane: Lane,
+        queue: &Mutex<VecDeque<Vec<AirdropEntry>>>,
+        results: &Mutex<Vec<AirdropResult>>,
+    ) {
+        loop {
+            let batch = {
+                let mut queue = queue.lock().unwrap();
+                let index = queue.iter().position(|batch| match &lane.input {
+                    Some((_, balance)) => total(batch) <= *balance,
+                    None => total(batch) == 0,
+                });
+                match index {
+                    Some(index) => queue.remove(index).unwrap(),
+                    None => return,
+                }
+            };
+            let outcome = match &batch[0].item {
+                AirdropItem::Coins(_) => self.send_coins(&lane, &batch).await,
+                AirdropItem::Nft { .. } => self.send_nft(&lane, &batch[0]).await,
+            };
+            let batch_results = match outcome {
+                Ok(BatchOutcome::Executed(digest, error)) => {
+                    if error.is_none() {
+                        if let Some((_, balance)) = &mut lane.input {
+                            *balance -= total(&batch);
+                        }
+                    }
+                    batch
+                        .iter()
+                        .map(|entry| match &error {
+                            None => AirdropResult {
+                                line: entry.line,
+                                recipient: entry.recipient.to_string(),
+                                status: AirdropStatus::Success,
+                                digest: Some(digest),
+                                error: None,
+                            },
+                            Some(error) => failed(entry, Some(digest), error),
+                        })
+                        .collect()
+                }
+                Ok(BatchOutcome::TooLarge) => {
+                    let mut batch = batch;
+                    let second = batch.split_off(batch.len() / 2);
+                    let mut queue = queue.lock().unwrap();
+                    queue.push_front(second);
+                    queue.push_front(batch);
+                    continue;
+                }
+                Err(e) => batch
+                    .iter()
+                    .map(|entry| failed(entry, None, e.to_string()))
+                    .collect(),
+            };
+            results.lock().unwrap().extend(batch_results);
+        }
+    }
+
+    async fn send_coins(
+        &self,
+        lane: &Lane,
+        batch: &[AirdropEntry],
+    ) -> anyhow::Result<BatchOutcome> {
+        let mut recipients = vec![];
+        let mut amounts = vec![];
+        for entry in batch {
+            if let AirdropItem::Coins(amount) = entry.item {
+                recipients.push(entry.recipient);
+                amounts.push(Amount::from_base_units(amount, 0));
+            }
+        }
+        let (input, _) = lane
+            .input
+            .ok_or_else(|| anyhow!("Lane has no coin to pay from."))?;
+        let data = self
+            .client
+            .transaction_builder()
+            .pay(
+                self.sender,
+                vec![input],
+                recipients,
+                amounts,
+                Some(lane.gas),
+                self.gas_budget,
+            )
+            .await?;
+        if data.to_bytes().len() > self.max_transaction_size && batch.len() > 1 {
+            return Ok(BatchOutcome::TooLarge);
+        }
+        let effects = self.execute(data).await?;
+        Ok(BatchOutcome::executed(&effects))
+    }
+
+    async fn send_nft(&self, lane: &Lane, entry: &AirdropEntry) -> anyhow::Result<BatchOutcome> {
+        let (name, description, url) = match &entry.item {
+            AirdropItem::Nft {
+                name,
+                description,
+                url,
+            } => (name, description, url),
+            AirdropItem::Coins(_) => {
+                bail!("Row [{}] is not an NFT.", entry.line)
+            }
+        };
+        let builder = self.client.transaction_builder();
+        let (package, module, function) = &self.nft_mint;
+        let data = builder
+            .mint_nft(
+                self.sender,
+                *package,
+                module,
+                function,
+                name,
+                description,
+                url,
+                Some(lane.gas),
+                self.gas_budget,
+            )
+            .await?;
+        let effects = self.execute(data).await?;
+        if let SuiExecutionStatus::Failure { .. } = effects.status {
+            return Ok(BatchOutcome::executed(&effects));
+        }
+        let nft = effects
+            .created
+            .iter()
+            .find(|object| object.owner == Owner::AddressOwner(self.sender))
+            .ok_or_else(|| anyhow!("Mint transaction created no object."))?
+            .reference
+            .object_id;
+        let data = builder
+            .transfer_object(
+                self.sender,
+                nft,
+                Some(lane.gas),
+                self.gas_budget,
+                entry.recipient,
+            )
+            .await?;
+        let effects = self.execute(data).await?;
+        Ok(BatchOutcome::executed(&effects))
+    }
+
+    async fn execute(&self, data: TransactionData) -> anyhow::Result<SuiTransactionEffects> {
+        let signature = self.keystore.sign(&self.sender, &data.to_bytes())?;
+        let response = self
+            .client
+            .quorum_driver()
+            .execute_transaction_by_fullnode(
+                Transaction::new(data, signature),
+                ExecuteTransactionRequestType::WaitForLocalExecution,
+            )
+            .await?;
+        match response {
+            SuiExecuteTransactionResponse::EffectsCert { effects, .. } => Ok(effects.effects),
+            _ => {
+                bail!("Node did not return the transaction effects.")
+            }
+        }
+    }
+}
+
+enum BatchOutcome {
+    /// The batch was executed, with an error if its transaction failed.
+    Executed(TransactionDigest, Option<String>),
+    /// The transaction exceeds the size limit and the batch must be split.
+    TooLarge,
+}
+
+impl BatchOutcome {
+    fn executed(effects: &SuiTransactionEffects) -> Self {
+        let error = match &effects.status {
+            SuiExecutionStatus::Success => None,
+            SuiExecutionStatus::Failure { error } => Some(error.clone()),
+        };
+        BatchOutcome::Executed(effects.transaction_digest, error)
+    }
+}
+
+fn total(batch: &[AirdropEntry]) -> u64 {
+    batch
+        .iter()
+        .map(|entry| match entry.item {
+            AirdropItem::Coins(amount) => amount,
+            AirdropItem::Nft { .. } => 0,
+        })
+        .sum()
+}
+
+fn failed(
+    entry: &AirdropEntry,
+    digest: Option<TransactionDigest>,
+    error: impl ToString,
+) -> AirdropResult {
+    AirdropResult {
+        line: entry.line,
+        recipient: entry.recipient.to_string(),
+        status: AirdropStatus::Failed,
+        digest,
+        error: Some(error.to_string()),
+    }
+}
@@ -0,0 +1,331 @@
+// Copyright (c) 2022, Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Amounts of coins, so values in base units and in whole coins cannot be mixed up.
+//!
+//! An [Amount] is a number of base units of a coin, together with the number of decimals
+//! the coin uses, so it converts from and to human readable units without losing
+//! precision. SUI has 9 decimals, its base unit being the MIST, and amounts of SUI are
+//! displayed and parsed with their unit, e.g. `1.5 SUI` or `1500000000 MIST`. Amounts of
+//! other coins are displayed with their decimals, e.g. `1.5 (6 decimals)`, which are read
+//! from the coin metadata by the [CoinRegistry](crate::coin_registry::CoinRegistry).
+//! Arithmetic is checked or saturating, never wrapping, and amounts of coins with different
+//! decimals neither add up nor compare.
+
+use std::cmp::Ordering;
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+
+use anyhow::anyhow;
+use serde::{Deserialize, Serialize};
+
+use crate::coin_type::CoinType;
+use crate::error::{Error, SuiRpcResult};
+
+pub const MIST_PER_SUI: u64 = 1_000_000_000;
+pub const SUI_DECIMALS: u8 = 9;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct Amount {
+    value: u64,
+    decimals: u8,
+}
+
+impl Default for Amount {
+    fn default() -> Self {
+        Self::ZERO
+    }
+}
+
+impl Amount {
+    /// No SUI.
+    pub const ZERO: Amount = Amount::from_mist(0);
+    /// The largest amount of SUI.
+    pub const MAX: Amount = Amount::from_mist(u64::MAX);
+
+    pub const fn from_base_units(value: u64, decimals: u8) -> Self {
+        Self { value, decimals }
+    }
+
+    /// `mist` MIST, an amount of SUI.
+    pub const fn from_mist(mist: u64) -> Self {
+        Self::from_base_units(mist, SUI_DECIMALS)
+    }
+
+    /// `sui` whole SUI, or `None` if the amount does not fit in a u64 of MIST.
+    pub fn from_sui(sui: u64) -> Option<Self> {
+        sui.checked_mul(MIST_PER_SUI).map(Self::from_mist)
+    }
+
+    /// The nearest amount of SUI to `mist`, clamped to the range of a u64.
+    pub fn saturating_from_mist(mist: i128) -> Self {
+        Self::from_mist(mist.clamp(0, u64::MAX as i128) as u64)
+    }
+
+    /// `amount` base units of `coin_type`, e.g. `0x2::sui::SUI`, if the coin type is SUI.
+    pub fn from_coin(coin_type: &str, amount: u64) -> Option<Self> {
+        coin_type
+            .parse::<CoinType>()
+            .map_or(false, |coin_type| coin_type.is_sui())
+            .then_some(Self::from_mist(amount))
+    }
+
+    /// Parse a human readable amount, e.g. `1.5` of a coin with 9 decimals is
+    /// 1_500_000_000 base units.
+    pub fn from_human(amount: &str, decimals: u8) -> SuiRpcResult<Self> {
+        let (whole, fraction) = amount.trim().split_once('.').unwrap_or((amount.trim(), ""));
+        if whole.is_empty() && fraction.is_empty() {
+            return Err(Error::Other(anyhow!("Invalid amount [{amount}].")));
+        }
+        if !whole
+            .chars()
+            .chain(fraction.chars())
+            .all(|c| c.is_ascii_digit())
+        {
+            return Err(Error::Other(anyhow!("Invalid amount [{amount}].")));
+        }
+        if fraction.len() > decimals as usize {
+            return Err(Error::Other(anyhow!(
+                "Amount [{amount}] has more than {decimals} decimal places."
+            )));
+        }
+        let whole = if whole.is_empty() {
+            0
+        } else {
+            whole.parse::<u128>()?
+        };
+        let fraction = format!("{fraction:0<width$}", width = decimals as usize);
+        let fraction = if fraction.is_empty() {
+            0
+        } else {
+            fraction.parse::<u128>()?
+        };
+        let value = 10u128
+            .checked_pow(decimals.into())
+            .and_then(|scale| whole.checked_mul(scale))
+            .and_then(|whole| whole.checked_add(fraction))
+            .and_then(|value| u64::try_from(value).ok())
+            .ok_or_else(|| anyhow!("Amount [{amount}] is too large."))?;
+        Ok(Self { value, decimals })
+    }
+
+    /// The amount in base units, i.e. in MIST for amounts of SUI.
+    pub fn value(&self) -> u64 {
+        self.value
+    }
+
+    pub fn decimals(&self) -> u8 {
+        self.decimals
+    }
+
+    /// The amount in whole coins, for display and estimates only.
+    pub fn as_f64(&self) -> f64 {
+        self.value as f64 / 10f64.powi(self.decimals.into())
+    }
+
+    /// The sum of amounts of the same coin, `None` on overflow or if the decimals differ.
+    pub fn checked_add(self, other: Amount) -> Option<Self> {
+        self.same_coin(other)?;
+        self.value
+            .checked_add(other.value)
+            .map(|value| self.with_value(value))
+    }
+
+    /// The difference of amounts of the same coin, `None` on underflow or if the decimals
+    /// differ.
+    pub fn checked_sub(self, other: Amount) -> Option<Self> {
+        self.same_coin(other)?;
+        self.value
+            .checked_sub(other.value)
+            .map(|value| self.with_value(value))
+    }
+
+    pub fn checked_mul(self, factor: u64) -> Option<Self> {
+        self.value
+            .checked_mul(factor)
+            .map(|value| self.with_value(value))
+    }
+
+    pub fn checked_div(self, divisor: u64) -> Option<Self> {
+        self.value
+            .checked_div(divisor)
+            .map(|value| self.with_value(value))
+    }
+
+    /// The sum of amounts of the same coin.
+    pub fn saturating_add(self, other: Amount) -> Self {
+        debug_assert_eq!(self.decimals, other.decimals);
+        self.with_value(self.value.saturating_add(other.value))
+    }
+
+    /// The difference of amounts of the same coin.
+    pub fn saturating_sub(self, other: Amount) -> Self {
+        debug_assert_eq!(self.decimals, other.decimals);
+        self.with_value(self.value.saturating_sub(other.value))
+    }
+
+    pub fn saturating_mul(self, factor: u64) -> Self {
+        self.with_value(self.value.saturating_mul(factor))
+    }
+
+    /// The amount in whole coins with the separators of `format`, e.g. `1,234.5`, without a
+    /// unit so the caller can add the symbol of the coin.
+    pub fn format(&self, format: &NumberFormat) -> String {
+        let (whole, fraction) = self.split();
+        let whole = group(&whole.to_string(), format.group);
+        if fraction == 0 {
+            return whole;
+        }
+        let fraction = format!("{fraction:0>width$}", width = self.decimals as usize);
+        format!(
+            "{whole}{}{}",
+            format.decimal,
+            fraction.trim_end_matches('0')
+        )
+    }
+
+    /// The whole coins and the remaining base units of the amount.
+    fn split(&self) -> (u128, u128) {
+        match 10u128.checked_pow(self.decimals.into()) {
+            Some(scale) => (self.value as u128 / scale, self.value as u128 % scale),
+            None => (0, self.value as u128),
+        }
+    }
+
+    fn with_value(self, value: u64) -> Self {
+        Self { value, ..self }
+    }
+
+    fn same_coin(self, other: Amount) -> Option<()> {
+        (self.decimals == other.decimals).then_some(())
+    }
+}
+
+impl PartialOrd for Amount {
+    /// Amounts of coins with different decimals are not comparable.
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        self.same_coin(*other)?;
+        Some(self.value.cmp(&other.value))
+    }
+}
+
+impl From<Amount> for u64 {
+    fn from(amount: Amount) -> Self {
+        amount.value
+    }
+}
+
+impl Display for Amount {
+    /// The amount with its unit, `1.5 SUI` for SUI decimals and `1.5 (6 decimals)` otherwise,
+    /// which parses back to the same amount.
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let amount = self.format(&NumberFormat::PLAIN);
+        if self.decimals == SUI_DECIMALS {
+            write!(f, "{amount} SUI")
+        } else {
+            write!(f, "{amount} ({} decimals)", self.decimals)
+        }
+    }
+}
+
+impl FromStr for Amount {
+    type Err = Error;
+
+    /// Parse an amount of SUI with its unit, `SUI` or `MIST`, e.g. `1.5 SUI` or
+    /// `1500000000 MIST`, or an amount of another coin with its decimals, e.g.
+    /// `1.5 (6 decimals)`. Units are case insensitive and `_` may separate digits.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if let Some((number, decimals)) = s
+            .trim()
+            .strip_suffix("decimals)")
+            .and_then(|s| s.rsplit_once('('))
+        {
+            let decimals = decimals
+                .trim()
+                .parse()
+                .map_err(|_| anyhow!("Invalid decimals in amount [{s}]."))?;
+            return Self::from_human(&number.trim().replace('_', ""), decimals)
+                .map_err(|_| Error::Other(anyhow!("Invalid amount [{s}].")));
+        }
+        let (number, unit) = s
+            .trim()
+            .rsplit_once(char::is_whitespace)
+            .ok_or_else(|| anyhow!("Amount [{s}] has no unit, expected SUI or MIST."))?;
+        let number = number.trim().replace('_', "");
+        match unit.to_ascii_uppercase().as_str() {
+            "MIST" => Ok(Self::from_mist(
+                number
+                    .parse()
+                    .map_err(|_| anyhow!("Invalid amount [{s}]."))?,
+            )),
+            "SUI" => Self::from_human(&number, SUI_DECIMALS)
+                .map_err(|_| Error::Other(anyhow!("Invalid amount [{s}]."))),
+            unit => Err(Error::Other(anyhow!(
+                "Unknown unit [{unit}] in amount [{s}], expected SUI or MIST."
+            ))),
+        }
+    }
+}
+
+/// The separators used to format numbers, which differ between locales.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NumberFormat {
+    /// Separator of the groups of thousands, if any.
+    pub group: Option<char>,
+    pub decimal: char,
+}
+
+impl NumberFormat {
+    pub const PLAIN: NumberFormat = NumberFormat {
+        group: None,
+        decimal: '.',
+    };
+    /// English, e.g. `1,234.5`.
+    pub const EN: NumberFormat = NumberFormat {
+        group: Some(','),
+        decimal: '.',
+    };
+    /// German, e.g. `1.234,5`.
+    pub const DE: NumberFormat = NumberFormat {
+        group: Some('.'),
+        decimal: ',',
+    };
+    /// French, e.g. `1 234,5` with a narrow no-break space.
+    pub const FR: NumberFormat = NumberFormat {
+        group: Some('\u{202f}'),
+        decimal: ',',
+    };
+    /// Swiss, e.g. `1'234.5`.
+    pub const CH: NumberFormat = NumberFormat {
+        group: Some('\''),
+        decimal: '.',
+    };
+
+    /// The format of a locale tag such as `en-US` or `de_DE`, falling back to English.
+    pub fn for_locale(locale: &str) -> Self {
+        let locale = locale.to_ascii_lowercase().replace('_', "-");
+        if locale.ends_with("-ch") {
+            return Self::CH;
+        }
+        match locale.split('-').next().unwrap_or_default() {
+            "de" | "nl" | "es" | "it" | "pt" | "id" | "tr" => Self::DE,
+            "fr" | "ru" | "pl" | "cs" | "sv" | "fi" | "nb" | "uk" => Self::FR,
+            _ => Self::EN,
+        }
+    }
+}
+
+fn group(digits: &str, separator: Option<char>) -> String {
+    let separator = match separator {
+        Some(separator) => separator,
+        None => return digits.to_string(),
+    };
+    let mut grouped = String::with_capacity(digits.len() + digits.len() / 3);
+    for (i, digit) in digits.chars().enumerate() {
+        if i > 0 && (digits.len() - i) % 3 == 0 {
+            grouped.push(separator);
+        }
+        grouped.push(digit);
+    }
+    grouped
+}
@@ -0,0 +1,299 @@
+// Copyright (c) 2022, Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Object safe traits of the client APIs, for code accepting any backend, e.g. a mock in
+//! tests or a client wrapping another with its own policies.
+//!
+//! The APIs of a [SuiClient](crate::SuiClient) implement them: [crate::ReadApi] implements
+//! [ReadApi] and [GovernanceApi], [crate::EventApi] implements [EventApi] and
+//! [crate::QuorumDriver] implements [QuorumApi], so code taking `&dyn ReadApi` can be passed
+//! `client.read_api()`. The traits share the names of the APIs, so import them from this
+//! module, e.g. `use sui_sdk::api::ReadApi as _` to call their methods on trait objects.
+
+use async_trait::async_trait;
+use futures::stream::BoxStream;
+use futures::StreamExt;
+
+use sui_json_rpc_types::{
+    GatewayTxSeqNumber, GetObjectDataResponse, GetPastObjectDataResponse, GetRawObjectDataResponse,
+    SuiEventEnvelope, SuiEventFilter, SuiExecuteTransactionResponse, SuiObjectInfo,
+    SuiTransactionFilter, SuiTransactionResponse,
+};
+use sui_types::base_types::{ObjectID, SequenceNumber, SuiAddress, TransactionDigest};
+use sui_types::messages::{ExecuteTransactionRequestType, Transaction};
+use sui_types::sui_system_state::SuiSystemState;
+
+use crate::epochs::EpochInfo;
+use crate::error::SuiRpcResult;
+use crate::staking::{DelegationSummary, StakedSuiSummary, ValidatorSummary};
+use crate::system_state::SystemStateSummary;
+use crate::{EventApi as RpcEventApi, QuorumDriver, ReadApi as RpcReadApi};
+
+#[async_trait]
+pub trait ReadApi: Send + Sync {
+    async fn get_objects_owned_by_address(
+        &self,
+        address: SuiAddress,
+    ) -> SuiRpcResult<Vec<SuiObjectInfo>>;
+
+    async fn get_objects_owned_by_object(
+        &self,
+        object_id: ObjectID,
+    ) -> SuiRpcResult<Vec<SuiObjectInfo>>;
+
+    async fn get_parsed_object(&self, object_id: ObjectID) -> SuiRpcResult<GetObjectDataResponse>;
+
+    async fn try_get_parsed_past_object(
+        &self,
+        object_id: ObjectID,
+        version: SequenceNumber,
+    ) -> SuiRpcResult<GetPastObjectDataResponse>;
+
+    async fn get_object(&self, object_id: ObjectID) -> SuiRpcResult<GetRawObjectDataResponse>;
+
+    /// The objects `object_ids`, in the same order.
+    async fn multi_get_objects(
+        &self,
+        object_ids: &[ObjectID],
+    ) -> SuiRpcResult<Vec<GetRawObjectDataResponse>>;
+
+    async fn get_total_transaction_number(&self) -> SuiRpcResult<u64>;
+
+    async fn get_transactions_in_range(
+        &self,
+        start: GatewayTxSeqNumber,
+        end: GatewayTxSeqNumber,
+    ) -> SuiRpcResult<Vec<(GatewayTxSeqNumber, TransactionDigest)>>;
+
+    async fn get_recent_transactions(
+        &self,
+        count: u64,
+    ) -> SuiRpcResult<Vec<(GatewayTxSeqNumber, TransactionDigest)>>;
+
+    async fn get_transaction(
+        &self,
+        digest: TransactionDigest,
+    ) -> SuiRpcResult<SuiTransactionResponse>;
+
+    /// The transactions `digests`, in the same order.
+    async fn multi_get_transactions(
+        &self,
+        digests: &[TransactionDigest],
+    ) -> SuiRpcResult<Vec<SuiTransactionResponse>>;
+}
+
+#[async_trait]
+pub trait EventApi: Send + Sync {
+    async fn subscribe_event(
+        &self,
+        filter: SuiEventFilter,
+    ) -> SuiRpcResult<BoxStream<'static, SuiRpcResult<SuiEventEnvelope>>>;
+
+    async fn subscribe_transaction(
+        &self,
+        filter: SuiTransactionFilter,
+    ) -> SuiRpcResult<BoxStream<'static, SuiRpcResult<SuiTransactionResponse>>>;
+
+    async fn get_events_by_timerange(
+        &self,
+        count: usize,
+        start_time: u64,
+        end_time: u64,
+    ) -> SuiRpcResult<Vec<SuiEventEnvelope>>;
+
+    async fn get_events_by_move_event_struct_name(
+        &self,
+        move_event_struct_name: String,
+        count: usize,
+        start_time: u64,
+        end_time: u64,
+    ) -> SuiRpcResult<Vec<SuiEventEnvelope>>;
+}
+
+/// Reads of the validators, the stakes and the epochs.
+#[async_trait]
+pub trait GovernanceApi: Send + Sync {
+    async fn get_sui_system_state(&self) -> SuiRpcResult<SuiSystemState>;
+
+    async fn get_validators(&self) -> SuiRpcResult<Vec<ValidatorSummary>>;
+
+    async fn get_delegations(&self, address: SuiAddress) -> SuiRpcResult<Vec<DelegationSummary>>;
+
+    async fn get_staked_sui(&self, address: SuiAddress) -> SuiRpcResult<Vec<StakedSuiSummary>>;
+
+    async fn get_epoch_info(&self) -> SuiRpcResult<EpochInfo>;
+
+    async fn get_system_state_summary(&self) -> SuiRpcResult<SystemStateSummary>;
+}
+
+/// Execution of signed transactions.
+#[async_trait]
+pub trait QuorumApi: Send + Sync {
+    async fn execute_transaction(&self, tx: Transaction) -> SuiRpcResult<SuiTransactionResponse>;
+
+    async fn execute_transaction_by_fullnode(
+        &self,
+        tx: Transaction,
+        request_type: ExecuteTransactionRequestType,
+    ) -> SuiRpcResult<SuiExecuteTransactionResponse>;
+}
+
+#[async_trait]
+impl ReadApi for RpcReadApi {
+    async fn get_objects_owned_by_address(
+        &self,
+        address: SuiAddress,
+    ) -> SuiRpcResult<Vec<SuiObjectInfo>> {
+        RpcReadApi::get_objects_owned_by_address(self, address).await
+    }
+
+    async fn get_objects_owned_by_object(
+        &self,
+        object_id: ObjectID,
+    ) -> SuiRpcResult<Vec<SuiObjectInfo>> {
+        RpcReadApi::get_objects_owned_by_object(self, object_id).await
+    }
+
+    async fn get_parsed_object(&self, object_id: ObjectID) -> SuiRpcResult<GetObjectDataResponse> {
+        RpcReadApi::get_parsed_object(self, object_id).await
+    }
+
+    async fn try_get_parsed_past_object(
+        &self,
+        object_id: ObjectID,
+        version: SequenceNumber,
+    ) -> SuiRpcResult<GetPastObjectDataResponse> {
+        RpcReadApi::try_get_parsed_past_object(self, object_id, version).await
+    }
+
+    async fn get_object(&self, object_id: ObjectID) -> SuiRpcResult<GetRawObjectDataResponse> {
+        RpcReadApi::get_object(self, object_id).await
+    }
+
+    async fn multi_get_objects(
+        &self,
+        object_ids: &[ObjectID],
+    ) -> SuiRpcResult<Vec<GetRawObjectDataResponse>> {
+        RpcReadApi::multi_get_objects(self, object_ids).await
+    }
+
+    async fn get_total_transaction_number(&self) -> SuiRpcResult<u64> {
+        RpcReadApi::get_total_transaction_number(self).await
+    }
+
+    async fn get_transactions_in_range(
+        &self,
+        start: GatewayTxSeqNumber,
+        end: GatewayTxSeqNumber,
+    ) -> SuiRpcResult<Vec<(GatewayTxSeqNumber, TransactionDigest)>> {
+        RpcReadApi::get_transactions_in_range(self, start, end).await
+    }
+
+    async fn get_recent_transactions(
+        &self,
+        count: u64,
+    ) -> SuiRpcResult<Vec<(GatewayTxSeqNumber, TransactionDigest)>> {
+        RpcReadApi::get_recent_transactions(self, count).await
+    }
+
+    async fn get_transaction(
+        &self,
+        digest: TransactionDigest,
+    ) -> SuiRpcResult<SuiTransactionResponse> {
+        RpcReadApi::get_transaction(self, digest).await
+    }
+
+    async fn multi_get_transactions(
+        &self,
+        digests: &[TransactionDigest],
+    ) -> SuiRpcResult<Vec<SuiTransactionResponse>> {
+        RpcReadApi::multi_get_transactions(self, digests).await
+    }
+}
+
+#[async_trait]
+impl GovernanceApi for RpcReadApi {
+    async fn get_sui_system_state(&self) -> SuiRpcResult<SuiSystemState> {
+        Ok(RpcReadApi::get_sui_system_state(self).await?)
+    }
+
+    async fn get_validators(&self) -> SuiRpcResult<Vec<ValidatorSummary>> {
+        Ok(RpcReadApi::get_validators(self).await?)
+    }
+
+    async fn get_delegations(&self, address: SuiAddress) -> SuiRpcResult<Vec<DelegationSummary>> {
+        Ok(RpcReadApi::get_delegations(self, address).await?)
+    }
+
+    async fn get_staked_sui(&self, address: SuiAddress) -> SuiRpcResult<Vec<StakedSuiSummary>> {
+        Ok(RpcReadApi::get_staked_sui(self, address).await?)
+    }
+
+    async fn get_epoch_info(&self) -> SuiRpcResult<EpochInfo> {
+        Ok(RpcReadApi::get_epoch_info(self).await?)
+    }
+
+    async fn get_system_state_summary(&self) -> SuiRpcResult<SystemStateSummary> {
+        Ok(RpcReadApi::get_system_state_summary(self).await?)
+    }
+}
+
+#[async_trait]
+impl EventApi for RpcEventApi {
+    async fn subscribe_event(
+        &self,
+        filter: SuiEventFilter,
+    ) -> SuiRpcResult<BoxStream<'static, SuiRpcResult<SuiEventEnvelope>>> {
+        Ok(RpcEventApi::subscribe_event(self, filter).await?.boxed())
+    }
+
+    async fn subscribe_transaction(
+        &self,
+        filter: SuiTransactionFilter,
+    ) -> SuiRpcResult<BoxStream<'static, SuiRpcResult<SuiTransactionResponse>>> {
+        Ok(RpcEventApi::subscribe_transaction(self, filter)
+            .await?
+            .boxed())
+    }
+
+    async fn get_events_by_timerange(
+        &self,
+        count: usize,
+        start_time: u64,
+        end_time: u64,
+    ) -> SuiRpcResult<Vec<SuiEventEnvelope>> {
+        RpcEventApi::get_events_by_timerange(self, count, start_time, end_time).await
+    }
+
+    async fn get_events_by_move_event_struct_name(
+        &self,
+        move_event_struct_name: String,
+        count: usize,
+        start_time: u64,
+        end_time: u64,
+    ) -> SuiRpcResult<Vec<SuiEventEnvelope>> {
+        RpcEventApi::get_events_by_move_event_struct_name(
+            self,
+            move_event_struct_name,
+            count,
+            start_time,
+            end_time,
+        )
+        .await
+    }
+}
+
+#[async_trait]
+impl QuorumApi for QuorumDriver {
+    async fn execute_transaction(&self, tx: Transaction) -> SuiRpcResult<SuiTransactionResponse> {
+        QuorumDriver::execute_transaction(self, tx).await
+    }
+
+    async fn execute_transaction_by_fullnode(
+        &self,
+        tx: Transaction,
+        request_type: ExecuteTransactionRequestType,
+    ) -> SuiRpcResult<SuiExecuteTransactionResponse> {
+        QuorumDriver::execute_transaction_by_fullnode(self, tx, request_type).await
+    }
+}
@@ -0,0 +1,218 @@
+// Copyright (c) 2022, Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Bounded buffering of subscription streams.
+//!
+//! The node pushes subscription notifications as they happen, whether or not the consumer
+//! keeps up. [buffered] drains a stream into a buffer of bounded capacity on a background
+//! task and applies an [OverflowPolicy] when the buffer is full, so a slow consumer never
+//! grows memory without bound. [StreamMetrics] report how far the consumer lags behind.
+//!
+//! Transaction followers do not need a buffer: they only request the next batch from the
+//! node once the previous one was consumed.
+
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+
+use futures::future::select;
+use futures::{pin_mut, stream, Stream, StreamExt};
+use tokio::sync::Notify;
+
+use crate::cancellation::{cancellable, BackgroundTasks, CancellationToken};
+use crate::error::{Error, SuiRpcResult};
+
+/// What to do with a new item when the buffer is full.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OverflowPolicy {
+    /// Stop reading the source until the consumer frees space. Notifications then queue up
+    /// in the WebSocket client, which drops the subscription if its own buffer overflows.
+    Block,
+    /// Drop the oldest buffered item.
+    DropOldest,
+    /// End the stream with [Error::BufferOverflow].
+    Error,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BufferConfig {
+    pub capacity: usize,
+    pub overflow: OverflowPolicy,
+}
+
+impl Default for BufferConfig {
+    fn default() -> Self {
+        Self {
+            capacity: 1024,
+            overflow: OverflowPolicy::Block,
+        }
+    }
+}
+
+/// Counters of a [buffered] stream.
+#[derive(Debug, Default)]
+pub struct StreamMetrics {
+    received: AtomicU64,
+    delivered: AtomicU64,
+    dropped: AtomicU64,
+    buffered: AtomicUsize,
+}
+
+impl StreamMetrics {
+    /// Items read from the source stream.
+    pub fn received(&self) -> u64 {
+        self.received.load(Ordering::Relaxed)
+    }
+
+    /// Items returned to the consumer.
+    pub fn delivered(&self) -> u64 {
+        self.delivered.load(Ordering::Relaxed)
+    }
+
+    /// Items dropped by [OverflowPolicy::DropOldest].
+    pub fn dropped(&self) -> u64 {
+        self.dropped.load(Ordering::Relaxed)
+    }
+
+    /// Items waiting in the buffer, i.e. how far the consumer lags behind the source.
+    pub fn lag(&self) -> usize {
+        self.buffered.load(Ordering::Relaxed)
+    }
+}
+
+struct State<T> {
+    queue: VecDeque<SuiRpcResult<T>>,
+    closed: bool,
+}
+
+struct Shared<T> {
+    state: Mutex<State<T>>,
+    item_ready: Notify,
+    space_ready: Notify,
+    metrics: Arc<StreamMetrics>,
+    // Cancelled when the consumer drops the stream or the tasks shut down, to stop the
+    // background task.
+    stop: CancellationToken,
+}
+
+impl<T> Shared<T> {
+    fn push(&self, state: &mut State<T>, item: SuiRpcResult<T>) {
+        state.queue.push_back(item);
+        self.metrics
+            .buffered
+            .store(state.queue.len(), Ordering::Relaxed);
+        self.item_ready.notify_one();
+    }
+}
+
+struct Receiver<T>(Arc<Shared<T>>);
+
+impl<T> Drop for Receiver<T> {
+    fn drop(&mut self) {
+        self.0.stop.cancel();
+        self.0.space_ready.notify_one();
+    }
+}
+
+impl<T> Receiver<T> {
+    async fn next(&self) -> Option<SuiRpcResult<T>> {
+        let shared = &self.0;
+        loop {
+            {
+                let mut state = shared.state.lock().unwrap();
+                if let Some(item) = state.queue.pop_front() {
+                    shared
+                        .metrics
+                        .buffered
+                        .store(state.queue.len(), Ordering::Relaxed);
+                    shared.metrics.delivered.fetch_add(1, Ordering::Relaxed);
+                    shared.space_ready.notify_one();
+                    return Some(item);
+                }
+                if state.closed {
+                    return None;
+                }
+            }
+            shared.item_ready.notified().await;
+        }
+    }
+}
+
+async fn pump<S, T>(source: S, shared: Arc<Shared<T>>, config: BufferConfig)
+where
+    S: Stream<Item = SuiRpcResult<T>>,
+{
+    let source = cancellable(source, shared.stop.clone());
+    pin_mut!(source);
+    'items: while let Some(item) = source.next().await {
+        shared.metrics.received.fetch_add(1, Ordering::Relaxed);
+        let mut pending = Some(item);
+        while let Some(item) = pending.take() {
+            if shared.stop.is_cancelled() {
+                break 'items;
+            }
+            // The lock is released before waiting for space.
+            pending = {
+                let mut state = shared.state.lock().unwrap();
+                if state.queue.len() < config.capacity {
+                    shared.push(&mut state, item);
+                    None
+                } else {
+                    match config.overflow {
+                        OverflowPolicy::Block => Some(item),
+                        OverflowPolicy::DropOldest => {
+                            state.queue.pop_front();
+                            shared.metrics.dropped.fetch_add(1, Ordering::Relaxed);
+                            shared.push(&mut state, item);
+                            None
+                        }
+                        OverflowPolicy::Error => {
+                            state.closed = true;
+                            shared.push(&mut state, Err(Error::BufferOverflow(config.capacity)));
+                            return;
+                        }
+                    }
+                }
+            };
+            if pending.is_some() {
+                let space_ready = shared.space_ready.notified();
+                let stopped = shared.stop.cancelled();
+                pin_mut!(space_ready, stopped);
+                select(space_ready, stopped).await;
+            }
+        }
+    }
+    shared.state.lock().unwrap().closed = true;
+    shared.item_ready.notify_one();
+}
+
+/// Drain `source` into a buffer of `config.capacity` items on a task spawned on `tasks`.
+/// Dropping the returned stream stops the task and drops `source`; shutting down `tasks`
+/// also ends the stream once the buffered items are consumed.
+pub fn buffered<S, T>(
+    source: S,
+    config: BufferConfig,
+    tasks: &BackgroundTasks,
+) -> (impl Stream<Item = SuiRpcResult<T>>, Arc<StreamMetrics>)
+where
+    S: Stream<Item = SuiRpcResult<T>> + Send + 'static,
+    T: Send + 'static,
+{
+    let metrics = Arc::new(StreamMetrics::default());
+    let shared = Arc::new(Shared {
+        state: Mutex::new(State {
+            queue: VecDeque::new(),
+            closed: false,
+        }),
+        item_ready: Notify::new(),
+        space_ready: Notify::new(),
+        metrics: metrics.clone(),
+        stop: tasks.token(),
+    });
+    tasks.spawn(pump(source, shared.clone(), config));
+    let items = stream::unfold(Receiver(shared), |receiver| async move {
+        let item = receiver.next().await?;
+        Some((item, receiver))
+    });
+    (items, metrics)
+}
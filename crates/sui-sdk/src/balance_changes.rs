@@ -0,0 +1,267 @@
+// Copyright (c) 2022, Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Credits and debits of address balances, derived from transaction effects, so wallets
+//! can update balances as transactions happen instead of rescanning owned objects.
+//!
+//! The change of a balance is the total of the coins owned by the address after the
+//! transaction, minus the total of its coins the transaction mutated, deleted or wrapped,
+//! read at their previous versions. Gas fees are included, as the gas coin is mutated.
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use anyhow::anyhow;
+use futures::{stream, Stream, StreamExt};
+use move_core_types::language_storage::TypeTag;
+
+use sui_json_rpc_types::{
+    GatewayTxSeqNumber, SuiData, SuiTransactionFilter, SuiTransactionResponse,
+};
+use sui_types::base_types::{ObjectID, SequenceNumber, SuiAddress, TransactionDigest};
+use sui_types::coin::Coin;
+use sui_types::object::Owner;
+use sui_types::parse_sui_struct_tag;
+
+use crate::amount::Amount;
+use crate::coin_type::CoinType;
+use crate::error::{Error, SuiRpcResult};
+use crate::{FullNodeApi, ReadApi, SuiClient};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BalanceDirection {
+    Credit,
+    Debit,
+}
+
+/// The net change of the balance of one coin type of an address in a transaction.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BalanceChange {
+    pub tx_digest: TransactionDigest,
+    pub timestamp_ms: Option<u64>,
+    pub address: SuiAddress,
+    /// The coin type, e.g. `0x2::sui::SUI`.
+    pub coin_type: String,
+    pub direction: BalanceDirection,
+    pub amount: u64,
+}
+
+impl BalanceChange {
+    /// The amount as an [Amount] of SUI, if the coin type is SUI.
+    pub fn sui_amount(&self) -> Option<Amount> {
+        Amount::from_coin(&self.coin_type, self.amount)
+    }
+}
+
+/// A coin at some version: its owner, coin type and balance.
+pub(crate) struct PastCoin {
+    pub owner: Owner,
+    pub coin_type: String,
+    pub balance: u64,
+}
+
+/// The coin `object_id` at `version`, or `None` if the object is not a coin.
+pub(crate) async fn past_coin(
+    read_api: &ReadApi,
+    object_id: ObjectID,
+    version: SequenceNumber,
+) -> anyhow::Result<Option<PastCoin>> {
+    let past = read_api
+        .try_get_parsed_past_object(object_id, version)
+        .await?;
+    let past = past.object()?;
+    let move_object = match past.data.try_as_move() {
+        Some(move_object) => move_object,
+        None => return Ok(None),
+    };
+    let type_ = parse_sui_struct_tag(&move_object.type_)?;
+    if !Coin::is_coin(&type_) {
+        return Ok(None);
+    }
+    let coin_type = match type_.type_params.first() {
+        Some(TypeTag::Struct(tag)) => tag.to_string(),
+        _ => return Ok(None),
+    };
+    let balance = move_object.fields.clone().to_json_value()?["balance"]
+        .as_u64()
+        .ok_or_else(|| anyhow!("Cannot read the balance of coin [{object_id}]."))?;
+    Ok(Some(PastCoin {
+        owner: past.owner,
+        coin_type,
+        balance,
+    }))
+}
+
+/// Whether the transaction may change the balances of `address`.
+pub(crate) fn touches(response: &SuiTransactionResponse, address: SuiAddress) -> bool {
+    let effects = &response.effects;
+    response.certificate.data.sender == address
+        || effects
+            .created
+            .iter()
+            .chain(&effects.mutated)
+            .chain(&effects.unwrapped)
+            .any(|object| object.owner == Owner::AddressOwner(address))
+}
+
+/// The transactions from and to `address`, in execution order.
+pub(crate) async fn address_transactions(
+    full_node_api: &FullNodeApi,
+    address: SuiAddress,
+) -> SuiRpcResult<Vec<(GatewayTxSeqNumber, TransactionDigest)>> {
+    let mut transactions = full_node_api.get_transactions_from_addr(address).await?;
+    transactions.extend(full_node_api.get_transactions_to_addr(address).await?);
+    Ok(transactions
+        .into_iter()
+        .collect::<BTreeSet<_>>()
+        .into_iter()
+        .collect())
+}
+
+/// The balance changes of `address` made by a transaction, one per coin type whose balance
+/// changed.
+pub async fn balance_changes(
+    read_api: &ReadApi,
+    address: SuiAddress,
+    response: &SuiTransactionResponse,
+) -> SuiRpcResult<Vec<BalanceChange>> {
+    if !touches(response, address) {
+        return Ok(vec![]);
+    }
+    let owner = Owner::AddressOwner(address);
+    let effects = &response.effects;
+    let mut totals = BTreeMap::<String, i128>::new();
+    for object in effects
+        .created
+        .iter()
+        .chain(&effects.mutated)
+        .chain(&effects.unwrapped)
+    {
+        if object.owner != owner {
+            continue;
+        }
+        let reference = &object.reference;
+        if let Some(coin) = past_coin(read_api, reference.object_id, reference.version).await? {
+            *totals.entry(coin.coin_type).or_default() += coin.balance as i128;
+        }
+    }
+    // Only the sender can mutate, delete or wrap the coins of an address.
+    if response.certificate.data.sender == address {
+        let previous = effects
+            .mutated
+            .iter()
+            .map(|object| &object.reference)
+            .chain(&effects.deleted)
+            .chain(&effects.wrapped);
+        for reference in previous {
+            let version = reference.version.decrement()?;
+            if let Some(coin) = past_coin(read_api, reference.object_id, version).await? {
+                if coin.owner == owner {
+                    *totals.entry(coin.coin_type).or_default() -= coin.balance as i128;
+                }
+            }
+        }
+    }
+    Ok(totals
+        .into_iter()
+        .filter(|(_, total)| *total != 0)
+        .map(|(coin_type, total)| BalanceChange {
+            tx_digest: response.certificate.transaction_digest,
+            timestamp_ms: response.timestamp_ms,
+            address,
+            coin_type,
+            direction: if total > 0 {
+                BalanceDirection::Credit
+            } else {
+                BalanceDirection::Debit
+            },
+            amount: total.unsigned_abs() as u64,
+        })
+        .collect())
+}
+
+impl SuiClient {
+    /// Stream the changes of the balance of `address` in `coin_type`, e.g. `0x2::sui::SUI`,
+    /// made by the transactions executed from now on.
+    pub async fn subscribe_balance_changes(
+        &self,
+        address: SuiAddress,
+        coin_type: &str,
+    ) -> SuiRpcResult<impl Stream<Item = SuiRpcResult<BalanceChange>> + '_> {
+        let coin_type = coin_type.parse::<CoinType>()?;
+        let transactions = self
+            .event_api()
+            .subscribe_transaction(SuiTransactionFilter::Any)
+            .await?;
+        Ok(transactions
+            .then(move |response| async move {
+                let changes = balance_changes(self.read_api(), address, &response?).await?;
+                Ok::<_, Error>(changes)
+            })
+            .flat_map(move |changes: SuiRpcResult<Vec<BalanceChange>>| {
+                let changes = match changes {
+                    Ok(changes) => changes
+                        .into_iter()
+                        .filter(|change| {
+                            change
+                                .coin_type
+                                .parse::<CoinType>()
+                                .map_or(false, |change_type| change_type == coin_type)
+                        })
+                        .map(Ok)
+                        .collect(),
+                    Err(e) => vec![Err(e)],
+                };
+                stream::iter(changes)
+            }))
+    }
+
+    /// The balance of `address` in `coin_type` after the transaction with sequence number
+    /// `seq`, e.g. for accounting and tax reports.
+    ///
+    /// Nodes in this tree do not serve checkpoints, so transaction sequence numbers stand for
+    /// them, as in the [TransactionFollower](crate::follower::TransactionFollower). The
+    /// balance is the current balance minus the changes made by the later transactions from
+    /// and to `address`, which are read from the archive if the node pruned them.
+    pub async fn get_balance_at_checkpoint(
+        &self,
+        address: SuiAddress,
+        coin_type: &str,
+        seq: GatewayTxSeqNumber,
+    ) -> SuiRpcResult<u64> {
+        let coin_type = coin_type.parse::<CoinType>()?;
+        let current = self
+            .get_portfolio(&[address])
+            .await?
+            .holdings
+            .get(&coin_type.to_string())
+            .and_then(|holding| holding.balances.get(&address).copied())
+            .unwrap_or_default();
+
+        let later = address_transactions(self.full_node_api(), address)
+            .await?
+            .into_iter()
+            .filter(|(tx_seq, _)| *tx_seq > seq)
+            .map(|(_, digest)| digest)
+            .collect::<Vec<_>>();
+
+        let mut balance = current as i128;
+        for response in self.read_api().multi_get_transactions(&later).await? {
+            for change in balance_changes(self.read_api(), address, &response).await? {
+                if change
+                    .coin_type
+                    .parse::<CoinType>()
+                    .map_or(true, |change_type| change_type != coin_type)
+                {
+                    continue;
+                }
+                match change.direction {
+                    BalanceDirection::Credit => balance -= change.amount as i128,
+                    BalanceDirection::Debit => balance += change.amount as i128,
+                }
+            }
+        }
+        u64::try_from(balance).map_err(|_| {
+            anyhow!("Cannot reconstruct the balance of [{address}] at [{seq}], got {balance}.")
+        })
+    }
+}
@@ -0,0 +1,411 @@
+// Copyright (c) 2022, Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Fetch many items from full nodes with bounded concurrency.
+//!
+//! A [BulkFetcher] limits the number of requests in flight overall and per endpoint, so
+//! one busy endpoint cannot starve the others when the fetcher is shared by several clients.
+//! Chunked fetches adapt the chunk size to the observed latency: chunks grow while requests
+//! complete well under the target latency and shrink when they exceed it.
+//!
+//! With adaptive concurrency, the limit of requests in flight to each endpoint follows an
+//! AIMD scheme: it is halved when the endpoint throttles a request (HTTP 429 or 503), which
+//! is then retried, and grows by one after each window of successful requests. Long
+//! backfills then settle at the rate the endpoint accepts without manual tuning.
+
+use std::collections::{BTreeMap, HashMap};
+use std::fmt::{Debug, Formatter};
+use std::future::Future;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use anyhow::anyhow;
+use futures::stream::{self, FuturesUnordered};
+use futures::{StreamExt, TryStreamExt};
+use tokio::sync::{Notify, Semaphore};
+
+use crate::error::{Error, SuiRpcResult};
+use crate::runtime::{Runtime, TokioRuntime};
+
+/// Number of times a throttled request is retried before the fetch fails.
+const MAX_THROTTLED_RETRIES: u32 = 8;
+/// Delay before retrying a throttled request, multiplied by the number of attempts.
+const THROTTLED_BACKOFF: Duration = Duration::from_millis(200);
+
+/// Progress of a bulk fetch, reported after each completed request.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BulkProgress {
+    pub completed: usize,
+    pub total: usize,
+}
+
+pub struct BulkFetcher {
+    permits: Arc<Semaphore>,
+    max_in_flight: usize,
+    max_in_flight_per_endpoint: usize,
+    /// Lower bound of the adaptive limit per endpoint, `None` if concurrency is not adaptive.
+    min_in_flight_per_endpoint: Option<usize>,
+    endpoint_limits: Mutex<HashMap<String, Arc<AdaptiveLimit>>>,
+    chunk_size: AtomicUsize,
+    min_chunk_size: usize,
+    max_chunk_size: usize,
+    target_latency: Duration,
+    progress: Option<Box<dyn Fn(&str, BulkProgress) + Send + Sync>>,
+    runtime: Arc<dyn Runtime>,
+}
+
+impl Debug for BulkFetcher {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("BulkFetcher")
+            .field("max_in_flight", &self.max_in_flight)
+            .field(
+                "max_in_flight_per_endpoint",
+                &self.max_in_flight_per_endpoint,
+            )
+            .field(
+                "min_in_flight_per_endpoint",
+                &self.min_in_flight_per_endpoint,
+            )
+            .field("chunk_size", &self.chunk_size())
+            .field("target_latency", &self.target_latency)
+            .field("runtime", &self.runtime)
+            .finish()
+    }
+}
+
+impl Default for BulkFetcher {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl BulkFetcher {
+    pub fn new() -> Self {
+        Self {
+            permits: Arc::new(Semaphore::new(64)),
+            max_in_flight: 64,
+            max_in_flight_per_endpoint: 16,
+            min_in_flight_per_endpoint: None,
+            endpoint_limits: Mutex::new(HashMap::new()),
+            chunk_size: AtomicUsize::new(50),
+            min_chunk_size: 1,
+            max_chunk_size: 1000,
+            target_latency: Duration::from_secs(1),
+            progress: None,
+            runtime: Arc::new(TokioRuntime),
+        }
+    }
+
+    /// Maximum number of requests in flight across all endpoints. Defaults to 64.
+    pub fn with_max_in_flight(mut self, max_in_flight: usize) -> Self {
+        self.max_in_flight = max_in_flight.max(1);
+        self.permits = Arc::new(Semaphore::new(self.max_in_flight));
+        self
+    }
+
+    /// Maximum number of requests in flight to a single endpoint. Defaults to 16.
+    pub fn with_max_in_flight_per_endpoint(mut self, max_in_flight: usize) -> Self {
+        self.max_in_flight_per_endpoint = max_in_flight.max(1);
+        self
+    }
+
+    /// Adapt the limit of requests in flight to each endpoint to throttling, between
+    /// `min_in_flight` and the maximum per endpoint, and retry throttled requests.
+    pub fn with_adaptive_concurrency(mut self, min_in_flight: usize) -> Self {
+        self.min_in_flight_per_endpoint = Some(min_in_flight.max(1));
+        self
+    }
+
+    /// Bounds and initial value of the chunk size. Defaults to 1, 1000 and 50.
+    pub fn with_chunk_size(mut self, min: usize, max: usize, initial: usize) -> Self {
+        self.min_chunk_size = min.max(1);
+        self.max_chunk_size = max.max(self.min_chunk_size);
+        self.chunk_size = AtomicUsize::new(initial.clamp(self.min_chunk_size, self.max_chunk_size));
+        self
+    }
+
+    /// Latency chunked requests are sized for. Defaults to 1 second.
+    pub fn with_target_latency(mut self, target_latency: Duration) -> Self {
+        self.target_latency = target_latency;
+        self
+    }
+
+    /// Call `progress` with the endpoint and the progress of the fetch after each completed
+    /// request, e.g. to log the progress of long backfills.
+    pub fn with_progress(
+        mut self,
+        progress: impl Fn(&str, BulkProgress) + Send + Sync + 'static,
+    ) -> Self {
+        self.progress = Some(Box::new(progress));
+        self
+    }
+
+    /// Wait before retrying throttled requests on `runtime`. Defaults to [TokioRuntime], and
+    /// to the runtime of the client for the fetcher a client creates.
+    pub fn with_runtime(mut self, runtime: Arc<dyn Runtime>) -> Self {
+        self.runtime = runtime;
+        self
+    }
+
+    /// The current chunk size.
+    pub fn chunk_size(&self) -> usize {
+        self.chunk_size.load(Ordering::Relaxed)
+    }
+
+    /// The current limit of requests in flight to `endpoint`.
+    pub fn in_flight_limit(&self, endpoint: &str) -> usize {
+        self.endpoint_limit(endpoint).limit()
+    }
+
+    /// Fetch every item with `fetch`, one request per item, and return the results in the
+    /// order of `items`. Fails on the first error.
+    pub async fn fetch_all<I, O, F, Fut>(
+        &self,
+        endpoint: &str,
+        items: impl IntoIterator<Item = I>,
+        fetch: F,
+    ) -> SuiRpcResult<Vec<O>>
+    where
+        I: Clone,
+        F: Fn(I) -> Fut,
+        Fut: Future<Output = SuiRpcResult<O>>,
+    {
+        let items = items.into_iter().collect::<Vec<_>>();
+        let total = items.len();
+        let endpoint_limit = self.endpoint_limit(endpoint);
+        let completed = AtomicUsize::new(0);
+        let fetch = &fetch;
+        stream::iter(items)
+            .map(|item| {
+                let endpoint_limit = &endpoint_limit;
+                let completed = &completed;
+                async move {
+                    let (result, _) = self.request(endpoint_limit, || fetch(item.clone())).await?;
+                    let completed = completed.fetch_add(1, Ordering::Relaxed) + 1;
+                    self.report(endpoint, completed, total);
+                    Ok::<_, Error>(result)
+                }
+            })
+            .buffered(self.max_in_flight)
+            .try_collect()
+            .await
+    }
+
+    /// Fetch `items` in chunks with `fetch`, which must return one result per item of its
+    /// chunk, and return the results in the order of `items`. Fails on the first error.
+    pub async fn fetch_chunks<I, O, F, Fut>(
+        &self,
+        endpoint: &str,
+        items: impl IntoIterator<Item = I>,
+        fetch: F,
+    ) -> SuiRpcResult<Vec<O>>
+    where
+        I: Clone,
+        F: Fn(Vec<I>) -> Fut,
+        Fut: Future<Output = SuiRpcResult<Vec<O>>>,
+    {
+        let mut items = items.into_iter().collect::<Vec<_>>().into_iter();
+        let total = items.len();
+        let endpoint_limit = self.endpoint_limit(endpoint);
+        let fetch = &fetch;
+        let mut in_flight = FuturesUnordered::new();
+        let mut results = BTreeMap::new();
+        let mut next_offset = 0;
+        let mut completed = 0;
+        loop {
+            while in_flight.len() < self.max_in_flight && next_offset < total {
+                let size = self.chunk_size().min(total - next_offset);
+                let chunk = items.by_ref().take(size).collect::<Vec<_>>();
+                let offset = next_offset;
+                next_offset += size;
+                let endpoint_limit = &endpoint_limit;
+                in_flight.push(async move {
+                    let (chunk, latency) = self
+                        .request(endpoint_limit, || fetch(chunk.clone()))
+                        .await?;
+                    Ok::<_, Error>((offset, size, latency, chunk))
+                });
+            }
+            let (offset, size, latency, chunk) = match in_flight.next().await {
+                Some(result) => result?,
+                None => break,
+            };
+            if chunk.len() != size {
+                return Err(Error::Other(anyhow!(
+                    "Expected [{size}] results for a chunk, got [{}].",
+                    chunk.len()
+                )));
+            }
+            self.adjust_chunk_size(size, latency);
+            completed += size;
+            self.report(endpoint, completed, total);
+            results.insert(offset, chunk);
+        }
+        Ok(results.into_values().flatten().collect())
+    }
+
+    /// Run `request` within the limits of the fetcher, retrying it while the endpoint
+    /// throttles it if concurrency is adaptive. Returns its result and latency.
+    async fn request<T, Fut>(
+        &self,
+        endpoint_limit: &AdaptiveLimit,
+        request: impl Fn() -> Fut,
+    ) -> SuiRpcResult<(T, Duration)>
+    where
+        Fut: Future<Output = SuiRpcResult<T>>,
+    {
+        let mut attempts = 0;
+        loop {
+            let permit = endpoint_limit.acquire().await;
+            let global = self
+                .permits
+                .acquire()
+                .await
+                .map_err(|e| Error::Other(e.into()))?;
+            let start = Instant::now();
+            let error = match request().await {
+                Ok(result) => {
+                    permit.succeeded();
+                    return Ok((result, start.elapsed()));
+                }
+                Err(e) => e,
+            };
+            attempts += 1;
+            let retry = self.min_in_flight_per_endpoint.is_some()
+                && attempts <= MAX_THROTTLED_RETRIES
+                && error.is_throttled();
+            if !retry {
+                return Err(error);
+            }
+            permit.throttled();
+            drop(global);
+            self.runtime.sleep(THROTTLED_BACKOFF * attempts).await;
+        }
+    }
+
+    fn endpoint_limit(&self, endpoint: &str) -> Arc<AdaptiveLimit> {
+        let max = self.max_in_flight_per_endpoint;
+        let min = self.min_in_flight_per_endpoint.unwrap_or(max).min(max);
+        self.endpoint_limits
+            .lock()
+            .unwrap()
+            .entry(endpoint.to_string())
+            .or_insert_with(|| Arc::new(AdaptiveLimit::new(min, max)))
+            .clone()
+    }
+
+    fn adjust_chunk_size(&self, size: usize, latency: Duration) {
+        let current = self.chunk_size();
+        let next = if latency > self.target_latency {
+            current / 2
+        } else if latency < self.target_latency / 2 && size == current {
+            current * 2
+        } else {
+            current
+        };
+        self.chunk_size.store(
+            next.clamp(self.min_chunk_size, self.max_chunk_size),
+            Ordering::Relaxed,
+        );
+    }
+
+    fn report(&self, endpoint: &str, completed: usize, total: usize) {
+        if let Some(progress) = &self.progress {
+            progress(endpoint, BulkProgress { completed, total });
+        }
+    }
+}
+
+/// A limit of requests in flight, halved when a request is throttled and increased by one
+/// after as many successful requests as the limit.
+#[derive(Debug)]
+pub struct AdaptiveLimit {
+    min: usize,
+    max: usize,
+    state: Mutex<LimitState>,
+    released: Notify,
+}
+
+#[derive(Debug)]
+struct LimitState {
+    limit: usize,
+    in_flight: usize,
+    successes: usize,
+    /// Incremented when the limit is decreased, so that requests sent before the decrease
+    /// do not decrease it again.
+    generation: u64,
+}
+
+impl AdaptiveLimit {
+    /// A limit between `min` and `max`, starting at `max`.
+    pub fn new(min: usize, max: usize) -> Self {
+        let max = max.max(1);
+        Self {
+            min: min.clamp(1, max),
+            max,
+            state: Mutex::new(LimitState {
+                limit: max,
+                in_flight: 0,
+                successes: 0,
+                generation: 0,
+            }),
+            released: Notify::new(),
+        }
+    }
+
+    pub fn limit(&self) -> usize {
+        self.state.lock().unwrap().limit
+    }
+
+    /// Wait until a request can be sent within the limit.
+    pub async fn acquire(&self) -> AdaptivePermit<'_> {
+        loop {
+            {
+                let mut state = self.state.lock().unwrap();
+                if state.in_flight < state.limit {
+                    state.in_flight += 1;
+                    return AdaptivePermit {
+                        limit: self,
+                        generation: state.generation,
+                    };
+                }
+            }
+            self.released.notified().await;
+        }
+    }
+}
+
+/// A request in flight within an [AdaptiveLimit], released when dropped.
+#[derive(Debug)]
+pub struct AdaptivePermit<'a> {
+    limit: &'a AdaptiveLimit,
+    generation: u64,
+}
+
+impl AdaptivePermit<'_> {
+    pub fn succeeded(self) {
+        let mut state = self.limit.state.lock().unwrap();
+        state.successes += 1;
+        if state.successes >= state.limit {
+            state.successes = 0;
+            state.limit = (state.limit + 1).min(self.limit.max);
+        }
+    }
+
+    pub fn throttled(self) {
+        let mut state = self.limit.state.lock().unwrap();
+        if state.generation == self.generation {
+            state.generation += 1;
+            state.successes = 0;
+            state.limit = (state.limit / 2).max(self.limit.min);
+        }
+    }
+}
+
+impl Drop for AdaptivePermit<'_> {
+    fn drop(&mut self) {
+        self.limit.state.lock().unwrap().in_flight -= 1;
+        self.limit.released.notify_one();
+    }
+}
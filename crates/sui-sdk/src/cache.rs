@@ -0,0 +1,188 @@
+// Copyright (c) 2022, Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Client-side caching of data that rarely or never changes on chain.
+//!
+//! A [Cache] stores serialized values by category and key, so it can be backed by an
+//! external store such as Redis or sled; [LruCache] keeps them in memory. A [ClientCache]
+//! adds a TTL per [CacheCategory] on top of a backend. Cache errors never fail a read: the
+//! value is fetched from the node instead.
+
+use std::collections::BTreeMap;
+use std::fmt::{Debug, Display, Formatter};
+use std::future::Future;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use async_trait::async_trait;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+use crate::error::SuiRpcResult;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum CacheCategory {
+    /// Normalized modules of published packages, which are immutable.
+    Package,
+    /// Metadata of coin types, e.g. their number of decimals.
+    CoinMetadata,
+    /// Objects with an immutable owner, which can never change.
+    ImmutableObject,
+    /// Media of NFTs fetched by a [MediaFetcher](crate::nft_media::MediaFetcher).
+    NftMedia,
+}
+
+impl Display for CacheCategory {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CacheCategory::Package => write!(f, "package"),
+            CacheCategory::CoinMetadata => write!(f, "coin_metadata"),
+            CacheCategory::ImmutableObject => write!(f, "immutable_object"),
+            CacheCategory::NftMedia => write!(f, "nft_media"),
+        }
+    }
+}
+
+/// A cache backend.
+#[async_trait]
+pub trait Cache: Send + Sync {
+    async fn get(&self, category: CacheCategory, key: &str) -> SuiRpcResult<Option<Vec<u8>>>;
+
+    /// Store `value`, expiring after `ttl` if set.
+    async fn put(
+        &self,
+        category: CacheCategory,
+        key: &str,
+        value: Vec<u8>,
+        ttl: Option<Duration>,
+    ) -> SuiRpcResult<()>;
+
+    async fn remove(&self, category: CacheCategory, key: &str) -> SuiRpcResult<()>;
+}
+
+/// An in-memory [Cache] evicting the least recently used entries beyond its capacity.
+pub struct LruCache(Mutex<lru::LruCache<(CacheCategory, String), (Vec<u8>, Option<Instant>)>>);
+
+impl LruCache {
+    pub fn new(capacity: usize) -> Self {
+        Self(Mutex::new(lru::LruCache::new(capacity)))
+    }
+}
+
+#[async_trait]
+impl Cache for LruCache {
+    async fn get(&self, category: CacheCategory, key: &str) -> SuiRpcResult<Option<Vec<u8>>> {
+        let mut entries = self.0.lock().unwrap();
+        let entry_key = (category, key.to_string());
+        match entries.get(&entry_key) {
+            Some((_, Some(expiry))) if *expiry <= Instant::now() => {
+                entries.pop(&entry_key);
+                Ok(None)
+            }
+            Some((value, _)) => Ok(Some(value.clone())),
+            None => Ok(None),
+        }
+    }
+
+    async fn put(
+        &self,
+        category: CacheCategory,
+        key: &str,
+        value: Vec<u8>,
+        ttl: Option<Duration>,
+    ) -> SuiRpcResult<()> {
+        let expiry = ttl.map(|ttl| Instant::now() + ttl);
+        self.0
+            .lock()
+            .unwrap()
+            .put((category, key.to_string()), (value, expiry));
+        Ok(())
+    }
+
+    async fn remove(&self, category: CacheCategory, key: &str) -> SuiRpcResult<()> {
+        self.0.lock().unwrap().pop(&(category, key.to_string()));
+        Ok(())
+    }
+}
+
+/// A [Cache] backend with a TTL per category. Values are stored as JSON.
+pub struct ClientCache {
+    backend: Box<dyn Cache>,
+    ttls: BTreeMap<CacheCategory, Option<Duration>>,
+}
+
+impl Debug for ClientCache {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ClientCache")
+            .field("ttls", &self.ttls)
+            .finish()
+    }
+}
+
+impl Default for ClientCache {
+    /// An in-memory LRU cache of 1024 entries.
+    fn default() -> Self {
+        Self::new(LruCache::new(1024))
+    }
+}
+
+impl ClientCache {
+    /// Wrap `backend`. Entries of every category never expire by default, as the cached
+    /// data is immutable on chain.
+    pub fn new(backend: impl Cache + 'static) -> Self {
+        Self {
+            backend: Box::new(backend),
+            ttls: BTreeMap::new(),
+        }
+    }
+
+    /// Expire the entries of `category` after `ttl`, or never if `None`.
+    pub fn with_ttl(mut self, category: CacheCategory, ttl: Option<Duration>) -> Self {
+        self.ttls.insert(category, ttl);
+        self
+    }
+
+    pub fn ttl(&self, category: CacheCategory) -> Option<Duration> {
+        self.ttls.get(&category).copied().flatten()
+    }
+
+    /// Return the cached value of `key`, or fetch it with `fetch` and cache it.
+    pub async fn get_or_fetch<T, F, Fut>(
+        &self,
+        category: CacheCategory,
+        key: &str,
+        fetch: F,
+    ) -> SuiRpcResult<T>
+    where
+        T: Serialize + DeserializeOwned,
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = SuiRpcResult<T>>,
+    {
+        if let Some(value) = self.get(category, key).await {
+            return Ok(value);
+        }
+        let value = fetch().await?;
+        self.put(category, key, &value).await;
+        Ok(value)
+    }
+
+    /// The cached value of `key`, if any.
+    pub async fn get<T: DeserializeOwned>(&self, category: CacheCategory, key: &str) -> Option<T> {
+        let bytes = self.backend.get(category, key).await.ok()??;
+        serde_json::from_slice(&bytes).ok()
+    }
+
+    /// Cache `value` as the value of `key`.
+    pub async fn put<T: Serialize>(&self, category: CacheCategory, key: &str, value: &T) {
+        if let Ok(bytes) = serde_json::to_vec(value) {
+            let _ = self
+                .backend
+                .put(category, key, bytes, self.ttl(category))
+                .await;
+        }
+    }
+
+    pub async fn invalidate(&self, category: CacheCategory, key: &str) -> SuiRpcResult<()> {
+        self.backend.remove(category, key).await
+    }
+}
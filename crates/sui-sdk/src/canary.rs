@@ -0,0 +1,199 @@
+// Copyright (c) 2022, Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Health checks of the transaction submission path, so operators detect outages before
+//! users do.
+//!
+//! A [Canary] periodically transfers 1 MIST from a designated address to itself and measures
+//! the time from building the transaction to its effects being executed by the node. Results
+//! are counted in [CanaryMetrics] and passed to an optional callback, e.g. to export them to
+//! a monitoring system.
+
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use anyhow::{anyhow, bail};
+
+use sui_json_rpc_types::{SuiExecuteTransactionResponse, SuiExecutionStatus};
+use sui_types::base_types::{SuiAddress, TransactionDigest};
+use sui_types::gas_coin::GasCoin;
+use sui_types::messages::{ExecuteTransactionRequestType, Transaction};
+
+use crate::amount::Amount;
+use crate::cancellation::CancellationToken;
+use crate::crypto::SuiKeystore;
+use crate::error::{Error, SuiRpcResult};
+use crate::runtime::timeout;
+use crate::SuiClient;
+
+/// The outcome of a canary transaction.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CanaryResult {
+    /// The digest of the transaction, if it was executed.
+    pub digest: Option<TransactionDigest>,
+    /// Time from building the transaction to its effects being executed.
+    pub latency: Duration,
+    pub error: Option<String>,
+}
+
+impl CanaryResult {
+    pub fn is_healthy(&self) -> bool {
+        self.error.is_none()
+    }
+}
+
+/// Counters of a [Canary].
+#[derive(Debug, Default)]
+pub struct CanaryMetrics {
+    succeeded: AtomicU64,
+    failed: AtomicU64,
+    consecutive_failures: AtomicU64,
+    last_latency_ms: AtomicU64,
+}
+
+impl CanaryMetrics {
+    pub fn succeeded(&self) -> u64 {
+        self.succeeded.load(Ordering::Relaxed)
+    }
+
+    pub fn failed(&self) -> u64 {
+        self.failed.load(Ordering::Relaxed)
+    }
+
+    /// Failures since the last success, the usual alerting signal.
+    pub fn consecutive_failures(&self) -> u64 {
+        self.consecutive_failures.load(Ordering::Relaxed)
+    }
+
+    /// Latency of the last successful canary transaction, in milliseconds.
+    pub fn last_latency_ms(&self) -> u64 {
+        self.last_latency_ms.load(Ordering::Relaxed)
+    }
+
+    pub fn record(&self, result: &CanaryResult) {
+        if result.is_healthy() {
+            self.succeeded.fetch_add(1, Ordering::Relaxed);
+            self.consecutive_failures.store(0, Ordering::Relaxed);
+            self.last_latency_ms
+                .store(result.latency.as_millis() as u64, Ordering::Relaxed);
+        } else {
+            self.failed.fetch_add(1, Ordering::Relaxed);
+            self.consecutive_failures.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+}
+
+type Callback = Box<dyn Fn(&CanaryResult) + Send + Sync>;
+
+pub struct Canary<'a> {
+    client: &'a SuiClient,
+    keystore: &'a SuiKeystore,
+    address: SuiAddress,
+    interval: Duration,
+    gas_budget: u64,
+    metrics: Arc<CanaryMetrics>,
+    callback: Option<Callback>,
+}
+
+impl<'a> Canary<'a> {
+    /// A canary sending its transactions from `address`, whose key must be in `keystore` and
+    /// which must own a SUI coin covering the gas budget.
+    pub fn new(client: &'a SuiClient, keystore: &'a SuiKeystore, address: SuiAddress) -> Self {
+        Self {
+            client,
+            keystore,
+            address,
+            interval: Duration::from_secs(60),
+            gas_budget: 1000,
+            metrics: Arc::default(),
+            callback: None,
+        }
+    }
+
+    /// Time between canary transactions. Defaults to 60 seconds.
+    pub fn with_interval(mut self, interval: Duration) -> Self {
+        self.interval = interval;
+        self
+    }
+
+    /// Gas budget of canary transactions. Defaults to 1000.
+    pub fn with_gas_budget(mut self, gas_budget: u64) -> Self {
+        self.gas_budget = gas_budget;
+        self
+    }
+
+    /// Call `callback` with the result of every canary transaction.
+    pub fn on_result(mut self, callback: impl Fn(&CanaryResult) + Send + Sync + 'static) -> Self {
+        self.callback = Some(Box::new(callback));
+        self
+    }
+
+    pub fn metrics(&self) -> Arc<CanaryMetrics> {
+        self.metrics.clone()
+    }
+
+    /// Send one canary transaction.
+    pub async fn probe(&self) -> CanaryResult {
+        let start = Instant::now();
+        let outcome = self.transfer().await;
+        let result = CanaryResult {
+            digest: outcome.as_ref().ok().copied(),
+            latency: start.elapsed(),
+            error: outcome.err().map(|e| e.to_string()),
+        };
+        self.metrics.record(&result);
+        if let Some(callback) = &self.callback {
+            callback(&result);
+        }
+        result
+    }
+
+    /// Send a canary transaction at every interval until `token` is cancelled.
+    pub async fn run(&self, token: CancellationToken) {
+        while !token.is_cancelled() {
+            self.probe().await;
+            timeout(self.client.runtime(), self.interval, token.cancelled()).await;
+        }
+    }
+
+    async fn transfer(&self) -> anyhow::Result<TransactionDigest> {
+        let builder = self.client.transaction_builder();
+        let (coin, _) = builder
+            .get_coins(self.address)
+            .await?
+            .remove(&GasCoin::type_().to_string())
+            .unwrap_or_default()
+            .into_iter()
+            .find(|(_, balance)| *balance > self.gas_budget)
+            .ok_or_else(|| anyhow!("Canary address [{}] has no coin to pay gas.", self.address))?;
+        let data = builder
+            .transfer_sui(
+                self.address,
+                coin.0,
+                self.gas_budget,
+                self.address,
+                Some(Amount::from_mist(1)),
+            )
+            .await?;
+        let signature = self.keystore.sign(&self.address, &data.to_bytes())?;
+        let response = self
+            .client
+            .quorum_driver()
+            .execute_transaction_by_fullnode(
+                Transaction::new(data, signature),
+                ExecuteTransactionRequestType::WaitForLocalExecution,
+            )
+            .await?;
+        let effects = match response {
+            SuiExecuteTransactionResponse::EffectsCert { effects, .. } => effects.effects,
+            _ => {
+                bail!("Node did not return the transaction effects.")
+            }
+        };
+        match effects.status {
+            SuiExecutionStatus::Success => Ok(effects.transaction_digest),
+            SuiExecutionStatus::Failure { error } => bail!(error),
+        }
+    }
+}
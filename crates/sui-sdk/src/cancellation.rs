@@ -0,0 +1,123 @@
+// Copyright (c) 2022, Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Cancellation of long-running operations, e.g. on shutdown.
+//!
+//! Transaction followers and event indexers accept a [CancellationToken] and stop cleanly once
+//! it is cancelled: their progress is stored as usual, so they resume where they stopped on
+//! the next run. Subscriptions wrapped with [cancellable] end when the token is cancelled,
+//! which unsubscribes from the node.
+//!
+//! The background tasks of a client, e.g. the pumps of buffered subscriptions, are owned by
+//! its [BackgroundTasks], which [SuiClient::shutdown](crate::SuiClient::shutdown) stops.
+//! Services can spawn their followers and indexers there too, with the token of
+//! [BackgroundTasks::token], so that shutting down the client waits for them to store their
+//! progress.
+
+use std::future::Future;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use futures::{Stream, StreamExt};
+use tokio::sync::Notify;
+pub use tokio_util::sync::CancellationToken;
+
+use crate::runtime::{timeout, Runtime};
+
+/// A stream of the items of `stream`, ending when `token` is cancelled.
+pub fn cancellable<S: Stream>(stream: S, token: CancellationToken) -> impl Stream<Item = S::Item> {
+    stream.take_until(async move { token.cancelled().await })
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, thiserror::Error)]
+#[error("{running} background tasks still running after the shutdown deadline.")]
+pub struct ShutdownTimeout {
+    pub running: usize,
+}
+
+/// Tasks running in the background until shut down. Clones share the same tasks.
+#[derive(Debug, Clone)]
+pub struct BackgroundTasks {
+    runtime: Arc<dyn Runtime>,
+    state: Arc<TasksState>,
+}
+
+#[derive(Debug, Default)]
+struct TasksState {
+    token: CancellationToken,
+    running: AtomicUsize,
+    finished: Notify,
+}
+
+/// Counts a task as running until dropped, i.e. until it returns or is dropped by the
+/// runtime.
+struct RunningTask(Arc<TasksState>);
+
+impl Drop for RunningTask {
+    fn drop(&mut self) {
+        self.0.running.fetch_sub(1, Ordering::SeqCst);
+        self.0.finished.notify_waiters();
+    }
+}
+
+impl BackgroundTasks {
+    /// Tasks spawned on `runtime`.
+    pub fn new(runtime: Arc<dyn Runtime>) -> Self {
+        Self {
+            runtime,
+            state: Arc::default(),
+        }
+    }
+
+    pub fn runtime(&self) -> &Arc<dyn Runtime> {
+        &self.runtime
+    }
+
+    /// A token cancelled on shutdown, for the tasks to stop on.
+    pub fn token(&self) -> CancellationToken {
+        self.state.token.child_token()
+    }
+
+    /// Run `task` in the background. It should return soon after the [BackgroundTasks::token]
+    /// is cancelled, once it stored its progress.
+    pub fn spawn(&self, task: impl Future<Output = ()> + Send + 'static) {
+        self.state.running.fetch_add(1, Ordering::SeqCst);
+        let running = RunningTask(self.state.clone());
+        self.runtime.spawn(Box::pin(async move {
+            task.await;
+            drop(running);
+        }));
+    }
+
+    /// Number of tasks still running.
+    pub fn running(&self) -> usize {
+        self.state.running.load(Ordering::SeqCst)
+    }
+
+    pub fn is_shut_down(&self) -> bool {
+        self.state.token.is_cancelled()
+    }
+
+    /// Cancel the tasks and wait up to `deadline` for them to return. Tasks still running
+    /// after the deadline are left running.
+    pub async fn shutdown(&self, deadline: Duration) -> Result<(), ShutdownTimeout> {
+        self.state.token.cancel();
+        let finished = async {
+            loop {
+                // Registered before checking, so a task ending in between is not missed.
+                let finished = self.state.finished.notified();
+                if self.running() == 0 {
+                    return;
+                }
+                finished.await;
+            }
+        };
+        match timeout(&*self.runtime, deadline, finished).await {
+            Some(()) => Ok(()),
+            None => Err(ShutdownTimeout {
+                running: self.running(),
+            }),
+        }
+    }
+}
@@ -0,0 +1,135 @@
+// Copyright (c) 2022, Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Circuit breakers around the endpoints of a client, so a dead node fails requests fast
+//! instead of consuming a timeout on every request.
+//!
+//! A [CircuitBreaker] opens after a number of consecutive failures of its endpoint, i.e.
+//! transport errors and timeouts; errors answered by the node do not count. While open,
+//! requests fail immediately. Once the open duration elapsed, the breaker is half-open: a
+//! single probe request is let through, closing the breaker if it succeeds and opening it
+//! again otherwise. A request dropped before it completes counts as a failure.
+
+use std::future::Future;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use jsonrpsee::core::Error as JsonRpcError;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CircuitState {
+    Closed,
+    /// Requests fail fast until the open duration elapsed.
+    Open,
+    /// A probe request is let through to test the endpoint.
+    HalfOpen,
+}
+
+#[derive(Debug)]
+enum State {
+    Closed { failures: u32 },
+    Open { until: Instant },
+    HalfOpen { probing: bool },
+}
+
+#[derive(Debug)]
+pub struct CircuitBreaker {
+    failure_threshold: u32,
+    open_duration: Duration,
+    state: Mutex<State>,
+}
+
+impl CircuitBreaker {
+    /// A breaker opening after `failure_threshold` consecutive failures, for `open_duration`.
+    pub fn new(failure_threshold: u32, open_duration: Duration) -> Self {
+        Self {
+            failure_threshold: failure_threshold.max(1),
+            open_duration,
+            state: Mutex::new(State::Closed { failures: 0 }),
+        }
+    }
+
+    pub fn state(&self) -> CircuitState {
+        match &*self.state.lock().unwrap() {
+            State::Closed { .. } => CircuitState::Closed,
+            State::Open { until } if Instant::now() < *until => CircuitState::Open,
+            State::Open { .. } | State::HalfOpen { .. } => CircuitState::HalfOpen,
+        }
+    }
+
+    /// Whether a request may be sent now. A half-open breaker allows a single probe request,
+    /// whose outcome must be recorded.
+    pub fn allow_request(&self) -> bool {
+        let mut state = self.state.lock().unwrap();
+        match &*state {
+            State::Closed { .. } => true,
+            State::Open { until } if Instant::now() < *until => false,
+            State::Open { .. } | State::HalfOpen { probing: false } => {
+                *state = State::HalfOpen { probing: true };
+                true
+            }
+            State::HalfOpen { probing: true } => false,
+        }
+    }
+
+    pub fn record_success(&self) {
+        *self.state.lock().unwrap() = State::Closed { failures: 0 };
+    }
+
+    pub fn record_failure(&self) {
+        let mut state = self.state.lock().unwrap();
+        let failures = match &*state {
+            State::Closed { failures } => failures + 1,
+            _ => self.failure_threshold,
+        };
+        *state = if failures >= self.failure_threshold {
+            State::Open {
+                until: Instant::now() + self.open_duration,
+            }
+        } else {
+            State::Closed { failures }
+        };
+    }
+
+    /// Run `request` to `endpoint` through the breaker. A request dropped before it completes,
+    /// e.g. on a timeout or by a hedged request that won, counts as a failure.
+    pub async fn call<T>(
+        &self,
+        endpoint: &str,
+        request: impl Future<Output = Result<T, JsonRpcError>>,
+    ) -> Result<T, JsonRpcError> {
+        if !self.allow_request() {
+            return Err(JsonRpcError::Custom(format!(
+                "Circuit breaker of endpoint [{endpoint}] is open."
+            )));
+        }
+        let mut outcome = Outcome {
+            breaker: self,
+            recorded: false,
+        };
+        let result = request.await;
+        match &result {
+            Err(JsonRpcError::Transport(_)) | Err(JsonRpcError::RequestTimeout) => {
+                self.record_failure()
+            }
+            _ => self.record_success(),
+        }
+        outcome.recorded = true;
+        result
+    }
+}
+
+/// Records a failure of a request dropped before its outcome was recorded, so a dropped
+/// probe does not leave the breaker half-open forever.
+struct Outcome<'a> {
+    breaker: &'a CircuitBreaker,
+    recorded: bool,
+}
+
+impl Drop for Outcome<'_> {
+    fn drop(&mut self) {
+        if !self.recorded {
+            self.breaker.record_failure();
+        }
+    }
+}
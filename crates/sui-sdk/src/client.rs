@@ -0,0 +1,1306 @@
+// Copyright (c) 2022, Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+use std::collections::BTreeMap;
+use std::fmt::{Debug, Write};
+use std::fmt::{Display, Formatter};
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::anyhow;
+use futures::StreamExt;
+use futures_core::Stream;
+use jsonrpsee::core::client::{ClientT, Subscription};
+use jsonrpsee::http_client::HttpClientBuilder;
+use jsonrpsee::ws_client::{WsClient, WsClientBuilder};
+use serde::Deserialize;
+use serde::Serialize;
+use serde_json::Value;
+
+use sui_config::gateway::GatewayConfig;
+use sui_core::gateway_state::{GatewayClient, GatewayState};
+use sui_json_rpc::api::EventReadApiClient;
+use sui_json_rpc::api::EventStreamingApiClient;
+use sui_json_rpc::api::RpcBcsApiClient;
+use sui_json_rpc::api::RpcFullNodeReadApiClient;
+use sui_json_rpc::api::RpcGatewayApiClient;
+use sui_json_rpc::api::RpcReadApiClient;
+use sui_json_rpc::api::TransactionExecutionApiClient;
+use sui_json_rpc::api::TransactionStreamingApiClient;
+use sui_json_rpc::api::WalletSyncApiClient;
+use sui_json_rpc_types::{
+    GatewayTxSeqNumber, GetObjectDataResponse, GetPastObjectDataResponse, GetRawObjectDataResponse,
+    SuiEventEnvelope, SuiEventFilter, SuiExecuteTransactionResponse, SuiGasCostSummary,
+    SuiMoveNormalizedFunction, SuiMoveNormalizedModule, SuiMoveNormalizedStruct, SuiObjectInfo,
+    SuiTransactionEffects, SuiTransactionFilter, SuiTransactionResponse,
+};
+use sui_types::base_types::{ObjectID, SequenceNumber, SuiAddress, TransactionDigest};
+use sui_types::messages::{ExecuteTransactionRequestType, Transaction};
+use sui_types::object::Owner;
+
+use crate::backpressure::{buffered, BufferConfig, StreamMetrics};
+use crate::bulk::BulkFetcher;
+use crate::cache::{CacheCategory, ClientCache};
+use crate::cancellation::{BackgroundTasks, ShutdownTimeout};
+use crate::circuit_breaker::{CircuitBreaker, CircuitState};
+use crate::coin_registry::CoinRegistry;
+use crate::dns::ConnectOptions;
+use crate::error::{Error, RpcError, SuiRpcResult};
+use crate::hedging::{HedgePolicy, HedgeStats, Hedger};
+use crate::request_log::{Redactor, RequestLogHook, RequestLogger};
+use crate::runtime::{Runtime, TokioRuntime};
+use crate::transaction_builder::TransactionBuilder;
+use crate::transport::{
+    header_map, Compression, HttpTransport, HyperTransport, PoolOptions, ReqwestTransport,
+    RpcHttpClient,
+};
+use crate::well_known::ObjectRegistry;
+
+/// Clones of a client share its connections to the node and its caches.
+#[derive(Clone)]
+pub struct SuiClient {
+    pub(crate) api: Arc<SuiClientApi>,
+    transaction_builder: TransactionBuilder,
+    read_api: Arc<ReadApi>,
+    full_node_api: FullNodeApi,
+    event_api: EventApi,
+    quorum_driver: QuorumDriver,
+    wallet_sync_api: WalletSyncApi,
+    coin_registry: Arc<CoinRegistry>,
+    objects: Arc<ObjectRegistry>,
+}
+
+#[allow(clippy::large_enum_variant)]
+pub(crate) enum SuiClientApi {
+    Rpc(RpcClient),
+    Embedded(GatewayClient),
+}
+
+impl Debug for SuiClientApi {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SuiClientApi::Rpc(rpc_client) => write!(
+                f,
+                "RPC client. Http: {:?}, Websocket: {:?}",
+                rpc_client.http, rpc_client.ws
+            ),
+            SuiClientApi::Embedded(_) => write!(f, "Embedded Gateway client."),
+        }
+    }
+}
+
+pub(crate) struct RpcClient {
+    pub(crate) http: RpcHttpClient,
+    /// Node keeping the full history, for the historical queries the node pruned.
+    archive: Option<RpcHttpClient>,
+    pub(crate) ws: Option<Arc<WsClient>>,
+    info: ServerInfo,
+    /// The settings the client was built with, to build the clients of its scopes.
+    builder: SuiClientBuilder,
+}
+
+#[derive(Clone)]
+struct ServerInfo {
+    rpc_methods: Vec<String>,
+    subscriptions: Vec<String>,
+    version: String,
+}
+
+impl RpcClient {
+    pub async fn new(
+        http: &str,
+        ws: Option<&str>,
+        builder: &SuiClientBuilder,
+    ) -> SuiRpcResult<Self> {
+        let mut http = Self::http_client(http, builder)?;
+        if let Some((url, policy)) = &builder.hedging {
+            let hedger = Hedger::new(*policy, builder.runtime.clone());
+            http = http.with_hedging(Self::http_client(url, builder)?, Arc::new(hedger));
+        }
+        let archive = match &builder.archive_url {
+            Some(url) => Some(Self::http_client(url, builder)?),
+            None => None,
+        };
+        let ws = if let Some(url) = ws {
+            Some(
+                WsClientBuilder::default()
+                    .request_timeout(builder.request_timeout)
+                    .connection_timeout(builder.ws_connection_timeout)
+                    .max_request_body_size(builder.max_request_body_size)
+                    .max_concurrent_requests(builder.max_concurrent_requests)
+                    .build(url)
+                    .await?,
+            )
+        } else {
+            None
+        };
+        let info = Self::get_server_info(&http, &ws).await?;
+        Ok(Self {
+            http,
+            archive,
+            ws: ws.map(Arc::new),
+            info,
+            builder: builder.clone(),
+        })
+    }
+
+    /// A client sending requests as set by `options`. Requests to the same endpoint with the
+    /// same headers share the connections of this client, unless `options` lengthen the
+    /// request timeout, which the connections enforce.
+    fn with_options(&self, options: &ClientOptions) -> SuiRpcResult<Self> {
+        let mut builder = self.builder.clone();
+        builder.headers.extend(options.headers.iter().cloned());
+        let longer_timeout = options
+            .request_timeout
+            .filter(|timeout| *timeout > builder.request_timeout);
+        let http = if options.endpoint.is_some()
+            || !options.headers.is_empty()
+            || longer_timeout.is_some()
+        {
+            builder.request_timeout = longer_timeout.unwrap_or(builder.request_timeout);
+            let url = options
+                .endpoint
+                .as_deref()
+                .unwrap_or_else(|| self.http.url());
+            Self::http_client(url, &builder)?
+        } else {
+            self.http.share()
+        };
+        Ok(Self {
+            http: http.with_scope(options.request_timeout, options.retry),
+            archive: self.archive.as_ref().map(RpcHttpClient::share),
+            ws: self.ws.clone(),
+            info: self.info.clone(),
+            builder,
+        })
+    }
+
+    fn http_client(url: &str, builder: &SuiClientBuilder) -> SuiRpcResult<RpcHttpClient> {
+        let headers = header_map(&builder.headers)?;
+        let hyper_options = match (&builder.connect_options, builder.pool_options) {
+            (None, None) => None,
+            (connect, pool) => Some((
+                connect.clone().unwrap_or_default(),
+                pool.unwrap_or_default(),
+            )),
+        };
+        let client = match (&builder.http_transport, builder.compression, hyper_options) {
+            (Some(_), _, _) if !headers.is_empty() => {
+                return Err(Error::IncompatibleSettings("header", "http_transport"))
+            }
+            (Some(transport), _, _) => RpcHttpClient::transport(url, transport.clone()),
+            (None, Some(_), Some(_)) => {
+                let setting = match builder.connect_options {
+                    Some(_) => "connect_options",
+                    None => "pool_options",
+                };
+                return Err(Error::IncompatibleSettings("compression", setting));
+            }
+            (None, None, Some((connect, pool))) => RpcHttpClient::transport(
+                url,
+                Arc::new(
+                    HyperTransport::with_pool(builder.request_timeout, connect, pool)
+                        .with_headers(headers)
+                        .with_runtime(builder.runtime.clone()),
+                ),
+            ),
+            (None, None, None) if headers.is_empty() => RpcHttpClient::jsonrpsee(
+                url,
+                HttpClientBuilder::default()
+                    .request_timeout(builder.request_timeout)
+                    .max_request_body_size(builder.max_request_body_size)
+                    .build(url)?,
+            ),
+            // The jsonrpsee client does not support compression or headers.
+            (None, compression, None) => RpcHttpClient::transport(
+                url,
+                Arc::new(
+                    ReqwestTransport::with_compression(
+                        builder.request_timeout,
+                        compression.unwrap_or_default(),
+                    )?
+                    .with_headers(headers),
+                ),
+            ),
+        };
+        let client = client.with_runtime(builder.runtime.clone());
+        let client = match builder.circuit_breaker {
+            Some((failure_threshold, open_duration)) => client.with_circuit_breaker(Arc::new(
+                CircuitBreaker::new(failure_threshold, open_duration),
+            )),
+            None => client,
+        };
+        Ok(match &builder.log_hook {
+            Some((hook, redactor)) => {
+                client.with_logger(Arc::new(RequestLogger::new(hook.clone(), redactor.clone())))
+            }
+            None => client,
+        })
+    }
+
+    /// Run the historical `query` on the node, then on the archive node if the node pruned
+    /// the data, i.e. failed with a pruned error or returned a response that is `missing`.
+    async fn historical<'a, T, Fut>(
+        &'a self,
+        query: impl Fn(&'a RpcHttpClient) -> Fut,
+        missing: impl Fn(&T) -> bool,
+    ) -> Result<T, jsonrpsee::core::Error>
+    where
+        Fut: std::future::Future<Output = Result<T, jsonrpsee::core::Error>>,
+    {
+        let result = query(&self.http).await;
+        let archive = match &self.archive {
+            Some(archive) => archive,
+            None => return result,
+        };
+        let pruned = match &result {
+            Ok(response) => missing(response),
+            Err(e) => e.to_string().to_lowercase().contains("pruned"),
+        };
+        if pruned {
+            query(archive).await
+        } else {
+            result
+        }
+    }
+
+    async fn get_server_info(
+        http: &RpcHttpClient,
+        ws: &Option<WsClient>,
+    ) -> SuiRpcResult<ServerInfo> {
+        let rpc_spec: Value = http
+            .request("rpc.discover", None)
+            .await
+            .map_err(|e| RpcError {
+                message: format!("Fail to connect to the RPC server: {e}"),
+                ..RpcError::from(e)
+            })?;
+        let version = rpc_spec
+            .pointer("/info/version")
+            .and_then(|v| v.as_str())
+            .ok_or_else(|| anyhow!("Fail parsing server version from rpc.discover endpoint."))?;
+        let rpc_methods = Self::parse_methods(&rpc_spec)?;
+
+        let subscriptions = if let Some(ws) = ws {
+            let rpc_spec: Value = ws
+                .request("rpc.discover", None)
+                .await
+                .map_err(|e| RpcError {
+                    message: format!("Fail to connect to the Websocket server: {e}"),
+                    ..RpcError::from(e)
+                })?;
+            Self::parse_methods(&rpc_spec)?
+        } else {
+            Vec::new()
+        };
+        Ok(ServerInfo {
+            rpc_methods,
+            subscriptions,
+            version: version.to_string(),
+        })
+    }
+
+    fn parse_methods(server_spec: &Value) -> SuiRpcResult<Vec<String>> {
+        let methods = server_spec
+            .pointer("/methods")
+            .and_then(|methods| methods.as_array())
+            .ok_or_else(|| {
+                anyhow!("Fail parsing server information from rpc.discover endpoint.")
+            })?;
+
+        Ok(methods
+            .iter()
+            .flat_map(|method| method["name"].as_str())
+            .map(|s| s.into())
+            .collect())
+    }
+
+    fn is_gateway(&self) -> bool {
+        self.info
+            .rpc_methods
+            .contains(&"sui_syncAccountState".to_string())
+    }
+}
+
+/// Connection settings of an RPC [SuiClient].
+///
+/// The HTTP client keeps connections to the node alive and reuses them for later requests.
+/// Services issuing many requests should build one client and clone it, rather than build a
+/// client per task, so that all clones share the same connections.
+#[derive(Clone)]
+pub struct SuiClientBuilder {
+    request_timeout: Duration,
+    max_request_body_size: u32,
+    ws_connection_timeout: Duration,
+    max_concurrent_requests: usize,
+    bulk_fetcher: Option<Arc<BulkFetcher>>,
+    cache: Option<Arc<ClientCache>>,
+    http_transport: Option<Arc<dyn HttpTransport>>,
+    archive_url: Option<String>,
+    circuit_breaker: Option<(u32, Duration)>,
+    hedging: Option<(String, HedgePolicy)>,
+    compression: Option<Compression>,
+    connect_options: Option<ConnectOptions>,
+    pool_options: Option<PoolOptions>,
+    headers: Vec<(String, String)>,
+    log_hook: Option<(Arc<dyn RequestLogHook>, Redactor)>,
+    runtime: Arc<dyn Runtime>,
+    objects: ObjectRegistry,
+}
+
+impl Debug for SuiClientBuilder {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SuiClientBuilder")
+            .field("request_timeout", &self.request_timeout)
+            .field("max_request_body_size", &self.max_request_body_size)
+            .field("ws_connection_timeout", &self.ws_connection_timeout)
+            .field("max_concurrent_requests", &self.max_concurrent_requests)
+            .field("bulk_fetcher", &self.bulk_fetcher)
+            .field("cache", &self.cache)
+            .field("http_transport", &self.http_transport.is_some())
+            .field("archive_url", &self.archive_url)
+            .field("circuit_breaker", &self.circuit_breaker)
+            .field("hedging", &self.hedging)
+            .field("compression", &self.compression)
+            .field("connect_options", &self.connect_options)
+            .field("pool_options", &self.pool_options)
+            .field("headers", &self.headers)
+            .field("log_hook", &self.log_hook.is_some())
+            .field("runtime", &self.runtime)
+            .field("objects", &self.objects)
+            .finish()
+    }
+}
+
+impl Default for SuiClientBuilder {
+    fn default() -> Self {
+        Self {
+            request_timeout: Duration::from_secs(60),
+            max_request_body_size: 10 * 1024 * 1024,
+            ws_connection_timeout: Duration::from_secs(10),
+            max_concurrent_requests: 256,
+            bulk_fetcher: None,
+            cache: None,
+            http_transport: None,
+            archive_url: None,
+            circuit_breaker: None,
+            hedging: None,
+            compression: None,
+            connect_options: None,
+            pool_options: None,
+            headers: vec![],
+            log_hook: None,
+            runtime: Arc::new(TokioRuntime),
+            objects: ObjectRegistry::default(),
+        }
+    }
+}
+
+impl SuiClientBuilder {
+    /// Timeout of each request. Defaults to 60 seconds.
+    pub fn request_timeout(mut self, request_timeout: Duration) -> Self {
+        self.request_timeout = request_timeout;
+        self
+    }
+
+    /// Maximum size of requests and responses in bytes. Defaults to 10 MiB.
+    pub fn max_request_body_size(mut self, max_request_body_size: u32) -> Self {
+        self.max_request_body_size = max_request_body_size;
+        self
+    }
+
+    /// Timeout of the WebSocket handshake. Defaults to 10 seconds.
+    pub fn ws_connection_timeout(mut self, ws_connection_timeout: Duration) -> Self {
+        self.ws_connection_timeout = ws_connection_timeout;
+        self
+    }
+
+    /// Maximum number of requests in flight on the WebSocket connection, which multiplexes
+    /// all requests and subscriptions. Defaults to 256.
+    pub fn max_concurrent_requests(mut self, max_concurrent_requests: usize) -> Self {
+        self.max_concurrent_requests = max_concurrent_requests;
+        self
+    }
+
+    /// Share a [BulkFetcher] with other clients, so its concurrency limits apply to all of
+    /// them. Each client gets its own fetcher by default, waiting on the [Runtime] of the
+    /// client, while a shared fetcher waits on the runtime set with
+    /// [BulkFetcher::with_runtime].
+    pub fn bulk_fetcher(mut self, bulk_fetcher: Arc<BulkFetcher>) -> Self {
+        self.bulk_fetcher = Some(bulk_fetcher);
+        self
+    }
+
+    /// Share a [ClientCache] with other clients, or use a custom cache backend. Each client
+    /// gets its own in-memory cache by default.
+    pub fn cache(mut self, cache: Arc<ClientCache>) -> Self {
+        self.cache = Some(cache);
+        self
+    }
+
+    /// Send HTTP requests with `transport` instead of the default hyper based client. The
+    /// request timeout, maximum body size and headers are then up to the transport: building
+    /// the client, or a client with [ClientOptions::with_header], fails with
+    /// [Error::IncompatibleSettings] if headers are set too.
+    pub fn http_transport(mut self, transport: Arc<dyn HttpTransport>) -> Self {
+        self.http_transport = Some(transport);
+        self
+    }
+
+    /// Compress the HTTP traffic as set by `compression`, sending requests with a
+    /// [ReqwestTransport] unless another transport is set. Disabled by default. Building the
+    /// client fails with [Error::IncompatibleSettings] if [SuiClientBuilder::connect_options]
+    /// or [SuiClientBuilder::pool_options] are set too, as the [HyperTransport] they need does
+    /// not compress.
+    pub fn compression(mut self, compression: Compression) -> Self {
+        self.compression = Some(compression);
+        self
+    }
+
+    /// Resolve and connect to the nodes as set by `options`, sending requests with a
+    /// [HyperTransport] unless another transport is set. Building the client fails with
+    /// [Error::IncompatibleSettings] if [SuiClientBuilder::compression] is set too.
+    pub fn connect_options(mut self, options: ConnectOptions) -> Self {
+        self.connect_options = Some(options);
+        self
+    }
+
+    /// Pool the connections to the nodes as set by `options`, sending requests with a
+    /// [HyperTransport] unless another transport is set. The connections of the default
+    /// client are kept alive and reused, without limit on idle connections. Building the
+    /// client fails with [Error::IncompatibleSettings] if [SuiClientBuilder::compression] is
+    /// set too.
+    pub fn pool_options(mut self, options: PoolOptions) -> Self {
+        self.pool_options = Some(options);
+        self
+    }
+
+    /// Send the header `name` with every HTTP request, e.g. an API key of the provider. The
+    /// jsonrpsee client cannot send headers, so requests are then sent with a
+    /// [ReqwestTransport]. Building the client fails with [Error::IncompatibleSettings] if a
+    /// custom [SuiClientBuilder::http_transport] is set, which must send the headers itself.
+    pub fn header(mut self, name: &str, value: &str) -> Self {
+        self.headers.push((name.to_string(), value.to_string()));
+        self
+    }
+
+    /// Send the historical queries the node cannot answer because it pruned the data, i.e.
+    /// past object versions and old transactions, to the archive node at `archive_url`.
+    pub fn archive_url(mut self, archive_url: &str) -> Self {
+        self.archive_url = Some(archive_url.to_string());
+        self
+    }
+
+    /// Put each HTTP endpoint behind a [CircuitBreaker] opening after `failure_threshold`
+    /// consecutive transport failures, for `open_duration`. Disabled by default.
+    pub fn circuit_breaker(mut self, failure_threshold: u32, open_duration: Duration) -> Self {
+        self.circuit_breaker = Some((failure_threshold, open_duration));
+        self
+    }
+
+    /// Send reads the node is slow to answer to `hedge_url` too, as decided by `policy`, and
+    /// take the first response. Disabled by default.
+    pub fn hedged_reads(mut self, hedge_url: &str, policy: HedgePolicy) -> Self {
+        self.hedging = Some((hedge_url.to_string(), policy));
+        self
+    }
+
+    /// Send a log of each HTTP request to `hook`, with the parameters redacted by
+    /// `redactor`, e.g. [Redactor::default].
+    pub fn log_hook(mut self, hook: Arc<dyn RequestLogHook>, redactor: Redactor) -> Self {
+        self.log_hook = Some((hook, redactor));
+        self
+    }
+
+    /// Run timers and background tasks on `runtime`. Defaults to [TokioRuntime].
+    pub fn runtime(mut self, runtime: Arc<dyn Runtime>) -> Self {
+        self.runtime = runtime;
+        self
+    }
+
+    /// Name the objects of the application, e.g. its packages, for [SuiClient::objects].
+    pub fn objects(mut self, objects: ObjectRegistry) -> Self {
+        self.objects = objects;
+        self
+    }
+
+    pub async fn build(self, http_url: &str, ws_url: Option<&str>) -> SuiRpcResult<SuiClient> {
+        let rpc = RpcClient::new(http_url, ws_url, &self).await?;
+        let runtime = self.runtime.clone();
+        let mut client = SuiClient::new(
+            SuiClientApi::Rpc(rpc),
+            http_url.to_string(),
+            self.bulk_fetcher
+                .unwrap_or_else(|| Arc::new(BulkFetcher::new().with_runtime(runtime))),
+            self.cache.unwrap_or_default(),
+            BackgroundTasks::new(self.runtime),
+        );
+        client.objects = Arc::new(self.objects);
+        Ok(client)
+    }
+}
+
+/// Overrides of the settings of a client for a scope, see [SuiClient::with_options].
+#[derive(Debug, Clone, Default)]
+pub struct ClientOptions {
+    request_timeout: Option<Duration>,
+    retry: Option<(u32, Duration)>,
+    headers: Vec<(String, String)>,
+    endpoint: Option<String>,
+}
+
+impl ClientOptions {
+    pub fn with_request_timeout(mut self, request_timeout: Duration) -> Self {
+        self.request_timeout = Some(request_timeout);
+        self
+    }
+
+    /// Retry requests failing in transport or timing out, up to `max_attempts` attempts in
+    /// all, waiting `initial_backoff` before the first retry and twice as long before each
+    /// next one.
+    pub fn with_retry(mut self, max_attempts: u32, initial_backoff: Duration) -> Self {
+        self.retry = Some((max_attempts, initial_backoff));
+        self
+    }
+
+    /// Send the header `name` with every HTTP request, besides the headers of the client.
+    /// Not supported by clients with a custom [SuiClientBuilder::http_transport].
+    pub fn with_header(mut self, name: &str, value: &str) -> Self {
+        self.headers.push((name.to_string(), value.to_string()));
+        self
+    }
+
+    /// Send the HTTP requests to `endpoint`, e.g. an archive node, instead of the node of
+    /// the client. Subscriptions still go to the node of the client.
+    pub fn with_endpoint(mut self, endpoint: &str) -> Self {
+        self.endpoint = Some(endpoint.to_string());
+        self
+    }
+}
+
+impl SuiClient {
+    pub async fn new_rpc_client(http_url: &str, ws_url: Option<&str>) -> SuiRpcResult<SuiClient> {
+        SuiClientBuilder::default().build(http_url, ws_url).await
+    }
+
+    pub fn new_embedded_client(config: &GatewayConfig) -> SuiRpcResult<SuiClient> {
+        let state = GatewayState::create_client(config, None)?;
+        Ok(SuiClient::new(
+            SuiClientApi::Embedded(state),
+            "embedded".to_string(),
+            Arc::default(),
+            Arc::default(),
+            BackgroundTasks::new(Arc::new(TokioRuntime)),
+        ))
+    }
+
+    fn new(
+        api: SuiClientApi,
+        endpoint: String,
+        bulk_fetcher: Arc<BulkFetcher>,
+        cache: Arc<ClientCache>,
+        tasks: BackgroundTasks,
+    ) -> Self {
+        let api = Arc::new(api);
+        let read_api = Arc::new(ReadApi {
+            api: api.clone(),
+            endpoint,
+            bulk_fetcher,
+            cache: cache.clone(),
+            runtime: tasks.runtime().clone(),
+        });
+        let quorum_driver = QuorumDriver { api: api.clone() };
+
+        let full_node_api = FullNodeApi(api.clone(), cache.clone());
+        let event_api = EventApi(api.clone(), tasks.clone());
+        let transaction_builder = TransactionBuilder(read_api.clone());
+        let wallet_sync_api = WalletSyncApi(api.clone());
+        let coin_registry = Arc::new(CoinRegistry::new(EventApi(api.clone(), tasks), cache));
+
+        SuiClient {
+            api,
+            transaction_builder,
+            read_api,
+            full_node_api,
+            event_api,
+            quorum_driver,
+            wallet_sync_api,
+            coin_registry,
+            objects: Arc::default(),
+        }
+    }
+
+    pub fn is_gateway(&self) -> bool {
+        match &*self.api {
+            SuiClientApi::Rpc(c) => c.is_gateway(),
+            SuiClientApi::Embedded(_) => true,
+        }
+    }
+
+    pub fn available_rpc_methods(&self) -> Vec<String> {
+        match &*self.api {
+            SuiClientApi::Rpc(c) => c.info.rpc_methods.clone(),
+            SuiClientApi::Embedded(_) => vec![],
+        }
+    }
+
+    pub fn available_subscriptions(&self) -> Vec<String> {
+        match &*self.api {
+            SuiClientApi::Rpc(c) => c.info.subscriptions.clone(),
+            SuiClientApi::Embedded(_) => vec![],
+        }
+    }
+
+    pub fn runtime(&self) -> &dyn Runtime {
+        self.read_api.runtime()
+    }
+
+    /// The tasks the client runs in the background, shared by its clones.
+    pub fn background_tasks(&self) -> &BackgroundTasks {
+        &self.event_api.1
+    }
+
+    /// Stop the background tasks of the client and its clones, waiting up to `deadline` for
+    /// them to return. Buffered subscriptions end once their buffered events are consumed.
+    pub async fn shutdown(&self, deadline: Duration) -> Result<(), ShutdownTimeout> {
+        self.event_api.1.shutdown(deadline).await
+    }
+
+    /// A client overriding the settings of this one with `options`, e.g. for a request
+    /// needing a longer deadline or an archive node. It shares the caches and background
+    /// tasks of this client, and its connections unless `options` change the endpoint or the
+    /// headers, or lengthen the request timeout.
+    pub fn with_options(&self, options: ClientOptions) -> SuiRpcResult<SuiClient> {
+        let rpc = match &*self.api {
+            SuiClientApi::Rpc(c) => c.with_options(&options)?,
+            SuiClientApi::Embedded(_) => return Err(Error::Unsupported("embedded gateway client")),
+        };
+        let read_api = &self.read_api;
+        let mut client = SuiClient::new(
+            SuiClientApi::Rpc(rpc),
+            options
+                .endpoint
+                .unwrap_or_else(|| read_api.endpoint.clone()),
+            read_api.bulk_fetcher.clone(),
+            read_api.cache.clone(),
+            self.event_api.1.clone(),
+        );
+        client.objects = self.objects.clone();
+        Ok(client)
+    }
+
+    /// The state of the circuit breaker of the node, if the client has one.
+    pub fn circuit_state(&self) -> Option<CircuitState> {
+        match &*self.api {
+            SuiClientApi::Rpc(c) => c.http.circuit_breaker().map(CircuitBreaker::state),
+            SuiClientApi::Embedded(_) => None,
+        }
+    }
+
+    /// The counts of hedged reads, if the client hedges reads.
+    pub fn hedge_stats(&self) -> Option<HedgeStats> {
+        match &*self.api {
+            SuiClientApi::Rpc(c) => c.http.hedger().map(Hedger::stats),
+            SuiClientApi::Embedded(_) => None,
+        }
+    }
+
+    pub fn api_version(&self) -> String {
+        match &*self.api {
+            SuiClientApi::Rpc(c) => c.info.version.clone(),
+            SuiClientApi::Embedded(_) => env!("CARGO_PKG_VERSION").to_owned(),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct ReadApi {
+    pub(crate) api: Arc<SuiClientApi>,
+    endpoint: String,
+    bulk_fetcher: Arc<BulkFetcher>,
+    cache: Arc<ClientCache>,
+    runtime: Arc<dyn Runtime>,
+}
+
+impl ReadApi {
+    /// The URL of the node, or `embedded` for an embedded client.
+    pub fn endpoint(&self) -> &str {
+        &self.endpoint
+    }
+
+    pub fn bulk_fetcher(&self) -> &BulkFetcher {
+        &self.bulk_fetcher
+    }
+
+    pub fn runtime(&self) -> &dyn Runtime {
+        &*self.runtime
+    }
+
+    /// Fetch every item with `fetch` through the [BulkFetcher] of the client.
+    pub(crate) async fn fetch_all<I, O, E, F, Fut>(
+        &self,
+        items: impl IntoIterator<Item = I>,
+        fetch: F,
+    ) -> SuiRpcResult<Vec<O>>
+    where
+        I: Clone,
+        E: Into<Error>,
+        F: Fn(I) -> Fut,
+        Fut: std::future::Future<Output = Result<O, E>>,
+    {
+        self.bulk_fetcher
+            .fetch_all(&self.endpoint, items, |item| {
+                let request = fetch(item);
+                async move { request.await.map_err(Into::into) }
+            })
+            .await
+    }
+
+    pub async fn get_objects_owned_by_address(
+        &self,
+        address: SuiAddress,
+    ) -> SuiRpcResult<Vec<SuiObjectInfo>> {
+        Ok(match &*self.api {
+            SuiClientApi::Rpc(c) => c.http.get_objects_owned_by_address(address).await?,
+            SuiClientApi::Embedded(c) => c.get_objects_owned_by_address(address).await?,
+        })
+    }
+
+    pub async fn get_objects_owned_by_object(
+        &self,
+        object_id: ObjectID,
+    ) -> SuiRpcResult<Vec<SuiObjectInfo>> {
+        Ok(match &*self.api {
+            SuiClientApi::Rpc(c) => c.http.get_objects_owned_by_object(object_id).await?,
+            SuiClientApi::Embedded(c) => c.get_objects_owned_by_object(object_id).await?,
+        })
+    }
+
+    pub async fn get_parsed_object(
+        &self,
+        object_id: ObjectID,
+    ) -> SuiRpcResult<GetObjectDataResponse> {
+        Ok(match &*self.api {
+            SuiClientApi::Rpc(c) => c.http.get_object(object_id).await?,
+            SuiClientApi::Embedded(c) => c.get_object(object_id).await?,
+        })
+    }
+
+    pub async fn try_get_parsed_past_object(
+        &self,
+        object_id: ObjectID,
+        version: SequenceNumber,
+    ) -> SuiRpcResult<GetPastObjectDataResponse> {
+        Ok(match &*self.api {
+            SuiClientApi::Rpc(c) => {
+                c.historical(
+                    |http| http.try_get_past_object(object_id, version),
+                    |response| matches!(response, GetPastObjectDataResponse::VersionNotFound(..)),
+                )
+                .await?
+            }
+            // Gateway does not support get past object
+            SuiClientApi::Embedded(_) => {
+                unimplemented!("Gateway/embedded client does not support get past object")
+            }
+        })
+    }
+
+    /// Immutable objects never change, so they are served from the client cache once fetched.
+    pub async fn get_object(&self, object_id: ObjectID) -> SuiRpcResult<GetRawObjectDataResponse> {
+        let key = object_id.to_string();
+        if let Some(response) = self.cache.get(CacheCategory::ImmutableObject, &key).await {
+            return Ok(response);
+        }
+        let response = match &*self.api {
+            SuiClientApi::Rpc(c) => c.http.get_raw_object(object_id).await?,
+            SuiClientApi::Embedded(c) => c.get_raw_object(object_id).await?,
+        };
+        if let GetRawObjectDataResponse::Exists(object) = &response {
+            if object.owner == Owner::Immutable {
+                self.cache
+                    .put(CacheCategory::ImmutableObject, &key, &response)
+                    .await;
+            }
+        }
+        Ok(response)
+    }
+
+    /// Fetch objects with bounded concurrency, returning the responses in the order of
+    /// `object_ids`.
+    pub async fn multi_get_objects(
+        &self,
+        object_ids: &[ObjectID],
+    ) -> SuiRpcResult<Vec<GetRawObjectDataResponse>> {
+        self.fetch_all(object_ids.iter().copied(), |id| self.get_object(id))
+            .await
+    }
+
+    pub async fn get_total_transaction_number(&self) -> SuiRpcResult<u64> {
+        Ok(match &*self.api {
+            SuiClientApi::Rpc(c) => c.http.get_total_transaction_number().await?,
+            SuiClientApi::Embedded(c) => c.get_total_transaction_number()?,
+        })
+    }
+
+    pub async fn get_transactions_in_range(
+        &self,
+        start: GatewayTxSeqNumber,
+        end: GatewayTxSeqNumber,
+    ) -> SuiRpcResult<Vec<(GatewayTxSeqNumber, TransactionDigest)>> {
+        Ok(match &*self.api {
+            SuiClientApi::Rpc(c) => {
+                c.historical(|http| http.get_transactions_in_range(start, end), |_| false)
+                    .await?
+            }
+            SuiClientApi::Embedded(c) => c.get_transactions_in_range(start, end)?,
+        })
+    }
+
+    pub async fn get_recent_transactions(
+        &self,
+        count: u64,
+    ) -> SuiRpcResult<Vec<(GatewayTxSeqNumber, TransactionDigest)>> {
+        Ok(match &*self.api {
+            SuiClientApi::Rpc(c) => c.http.get_recent_transactions(count).await?,
+            SuiClientApi::Embedded(c) => c.get_recent_transactions(count)?,
+        })
+    }
+
+    pub async fn get_transaction(
+        &self,
+        digest: TransactionDigest,
+    ) -> SuiRpcResult<SuiTransactionResponse> {
+        Ok(match &*self.api {
+            SuiClientApi::Rpc(c) => {
+                c.historical(|http| http.get_transaction(digest), |_| false)
+                    .await?
+            }
+            SuiClientApi::Embedded(c) => c.get_transaction(digest).await?,
+        })
+    }
+
+    /// Fetch transactions with bounded concurrency, returning the responses in the order of
+    /// `digests`.
+    pub async fn multi_get_transactions(
+        &self,
+        digests: &[TransactionDigest],
+    ) -> SuiRpcResult<Vec<SuiTransactionResponse>> {
+        self.fetch_all(digests.iter().copied(), |digest| {
+            self.get_transaction(digest)
+        })
+        .await
+    }
+
+    /// Return the storage rebate currently held by the given objects.
+    /// Every object touched by a transaction is either rewritten or deleted, so this is the
+    /// rebate the transaction will receive when it touches exactly these objects.
+    pub async fn estimate_storage_rebate(&self, touched_objects: &[ObjectID]) -> SuiRpcResult<u64> {
+        let mut rebate = 0;
+        for response in self.multi_get_objects(touched_objects).await? {
+            rebate += response.object()?.storage_rebate;
+        }
+        Ok(rebate)
+    }
+
+    /// Estimate the gas cost of a transaction touching `touched_objects`, given the
+    /// computation and storage cost it is expected to incur (e.g. from a dry run).
+    /// Use [SuiGasCostSummary::net_gas_usage] on the result to get the net cost
+    /// (computation + storage - rebate).
+    pub async fn estimate_gas_cost(
+        &self,
+        computation_cost: u64,
+        storage_cost: u64,
+        touched_objects: &[ObjectID],
+    ) -> SuiRpcResult<SuiGasCostSummary> {
+        Ok(SuiGasCostSummary {
+            computation_cost,
+            storage_cost,
+            storage_rebate: self.estimate_storage_rebate(touched_objects).await?,
+        })
+    }
+}
+
+#[derive(Clone)]
+pub struct FullNodeApi(pub(crate) Arc<SuiClientApi>, Arc<ClientCache>);
+
+impl FullNodeApi {
+    /// Execute the transaction against the latest state without committing it,
+    /// returning the effects, including the gas cost, it would have.
+    pub async fn dry_run_transaction(
+        &self,
+        tx: Transaction,
+    ) -> SuiRpcResult<SuiTransactionEffects> {
+        Ok(match &*self.0 {
+            SuiClientApi::Rpc(c) => {
+                let (tx_bytes, flag, signature, pub_key) = tx.to_network_data_for_execution();
+                c.http
+                    .dry_run_transaction(tx_bytes, flag, signature, pub_key)
+                    .await?
+            }
+            SuiClientApi::Embedded(_) => return Err(Error::Unsupported("embedded gateway client")),
+        })
+    }
+
+    /// Packages are immutable, so the modules are served from the client cache once fetched.
+    pub async fn get_normalized_move_modules_by_package(
+        &self,
+        package: ObjectID,
+    ) -> SuiRpcResult<BTreeMap<String, SuiMoveNormalizedModule>> {
+        let fetch = || async {
+            Ok(match &*self.0 {
+                SuiClientApi::Rpc(c) => c.http.get_normalized_move_modules_by_package(package),
+                SuiClientApi::Embedded(_) => {
+                    return Err(Error::Unsupported("embedded gateway client"))
+                }
+            }
+            .await?)
+        };
+        self.1
+            .get_or_fetch(CacheCategory::Package, &package.to_string(), fetch)
+            .await
+    }
+
+    pub async fn get_normalized_move_module(
+        &self,
+        package: ObjectID,
+        module_name: String,
+    ) -> SuiRpcResult<SuiMoveNormalizedModule> {
+        let key = format!("{package}::{module_name}");
+        let fetch = || async {
+            Ok(match &*self.0 {
+                SuiClientApi::Rpc(c) => c.http.get_normalized_move_module(package, module_name),
+                SuiClientApi::Embedded(_) => {
+                    return Err(Error::Unsupported("embedded gateway client"))
+                }
+            }
+            .await?)
+        };
+        self.1
+            .get_or_fetch(CacheCategory::Package, &key, fetch)
+            .await
+    }
+
+    pub async fn get_normalized_move_struct(
+        &self,
+        package: ObjectID,
+        module_name: String,
+        struct_name: String,
+    ) -> SuiRpcResult<SuiMoveNormalizedStruct> {
+        Ok(match &*self.0 {
+            SuiClientApi::Rpc(c) => {
+                c.http
+                    .get_normalized_move_struct(package, module_name, struct_name)
+            }
+            SuiClientApi::Embedded(_) => return Err(Error::Unsupported("embedded gateway client")),
+        }
+        .await?)
+    }
+
+    pub async fn get_normalized_move_function(
+        &self,
+        package: ObjectID,
+        module_name: String,
+        function_name: String,
+    ) -> SuiRpcResult<SuiMoveNormalizedFunction> {
+        Ok(match &*self.0 {
+            SuiClientApi::Rpc(c) => {
+                c.http
+                    .get_normalized_move_function(package, module_name, function_name)
+            }
+            SuiClientApi::Embedded(_) => return Err(Error::Unsupported("embedded gateway client")),
+        }
+        .await?)
+    }
+
+    pub async fn get_transactions_by_input_object(
+        &self,
+        object: ObjectID,
+    ) -> SuiRpcResult<Vec<(GatewayTxSeqNumber, TransactionDigest)>> {
+        Ok(match &*self.0 {
+            SuiClientApi::Rpc(c) => c.http.get_transactions_by_input_object(object).await?,
+            SuiClientApi::Embedded(_) => return Err(Error::Unsupported("embedded gateway client")),
+        })
+    }
+
+    pub async fn get_transactions_by_mutated_object(
+        &self,
+        object: ObjectID,
+    ) -> SuiRpcResult<Vec<(GatewayTxSeqNumber, TransactionDigest)>> {
+        Ok(match &*self.0 {
+            SuiClientApi::Rpc(c) => c.http.get_transactions_by_mutated_object(object),
+            SuiClientApi::Embedded(_) => return Err(Error::Unsupported("embedded gateway client")),
+        }
+        .await?)
+    }
+
+    pub async fn get_transactions_by_move_function(
+        &self,
+        package: ObjectID,
+        module: Option<String>,
+        function: Option<String>,
+    ) -> SuiRpcResult<Vec<(GatewayTxSeqNumber, TransactionDigest)>> {
+        Ok(match &*self.0 {
+            SuiClientApi::Rpc(c) => c
+                .http
+                .get_transactions_by_move_function(package, module, function),
+            SuiClientApi::Embedded(_) => return Err(Error::Unsupported("embedded gateway client")),
+        }
+        .await?)
+    }
+
+    pub async fn get_transactions_from_addr(
+        &self,
+        addr: SuiAddress,
+    ) -> SuiRpcResult<Vec<(GatewayTxSeqNumber, TransactionDigest)>> {
+        Ok(match &*self.0 {
+            SuiClientApi::Rpc(c) => c.http.get_transactions_from_addr(addr),
+            SuiClientApi::Embedded(_) => return Err(Error::Unsupported("embedded gateway client")),
+        }
+        .await?)
+    }
+
+    pub async fn get_transactions_to_addr(
+        &self,
+        addr: SuiAddress,
+    ) -> SuiRpcResult<Vec<(GatewayTxSeqNumber, TransactionDigest)>> {
+        Ok(match &*self.0 {
+            SuiClientApi::Rpc(c) => c.http.get_transactions_to_addr(addr),
+            SuiClientApi::Embedded(_) => return Err(Error::Unsupported("embedded gateway client")),
+        }
+        .await?)
+    }
+}
+#[derive(Clone)]
+pub struct EventApi(pub(crate) Arc<SuiClientApi>, pub(crate) BackgroundTasks);
+
+impl EventApi {
+    pub async fn subscribe_event(
+        &self,
+        filter: SuiEventFilter,
+    ) -> SuiRpcResult<impl Stream<Item = SuiRpcResult<SuiEventEnvelope>> + 'static> {
+        match &*self.0 {
+            SuiClientApi::Rpc(RpcClient { ws: Some(c), .. }) => {
+                let subscription: Subscription<SuiEventEnvelope> =
+                    c.subscribe_event(filter).await?;
+                Ok(subscription.map(|item| Ok(item?)))
+            }
+            _ => Err(Error::Unsupported("clients without a WebSocket connection")),
+        }
+    }
+
+    pub async fn subscribe_transaction(
+        &self,
+        filter: SuiTransactionFilter,
+    ) -> SuiRpcResult<impl Stream<Item = SuiRpcResult<SuiTransactionResponse>> + 'static> {
+        match &*self.0 {
+            SuiClientApi::Rpc(RpcClient { ws: Some(c), .. }) => {
+                let subscription: Subscription<SuiTransactionResponse> =
+                    c.subscribe_transaction(filter).await?;
+                Ok(subscription.map(|item| Ok(item?)))
+            }
+            _ => Err(Error::Unsupported("clients without a WebSocket connection")),
+        }
+    }
+
+    /// Like [EventApi::subscribe_event], buffering events as configured by `config` when the
+    /// caller falls behind. The metrics report how far the caller lags.
+    pub async fn subscribe_event_buffered(
+        &self,
+        filter: SuiEventFilter,
+        config: BufferConfig,
+    ) -> SuiRpcResult<(
+        impl Stream<Item = SuiRpcResult<SuiEventEnvelope>>,
+        Arc<StreamMetrics>,
+    )> {
+        let events = self.subscribe_event(filter).await?;
+        Ok(buffered(events, config, &self.1))
+    }
+
+    pub async fn get_events_by_timerange(
+        &self,
+        count: usize,
+        start_time: u64,
+        end_time: u64,
+    ) -> SuiRpcResult<Vec<SuiEventEnvelope>> {
+        Ok(match &*self.0 {
+            SuiClientApi::Rpc(c) => {
+                c.http
+                    .get_events_by_timerange(count, start_time, end_time)
+                    .await?
+            }
+            SuiClientApi::Embedded(_) => return Err(Error::Unsupported("embedded gateway client")),
+        })
+    }
+
+    pub async fn get_events_by_move_event_struct_name(
+        &self,
+        move_event_struct_name: String,
+        count: usize,
+        start_time: u64,
+        end_time: u64,
+    ) -> SuiRpcResult<Vec<SuiEventEnvelope>> {
+        Ok(match &*self.0 {
+            SuiClientApi::Rpc(c) => {
+                c.http
+                    .get_events_by_move_event_struct_name(
+                        move_event_struct_name,
+                        count,
+                        start_time,
+                        end_time,
+                    )
+                    .await?
+            }
+            SuiClientApi::Embedded(_) => return Err(Error::Unsupported("embedded gateway client")),
+        })
+    }
+}
+#[derive(Clone)]
+pub struct QuorumDriver {
+    api: Arc<SuiClientApi>,
+}
+
+impl QuorumDriver {
+    pub async fn execute_transaction(
+        &self,
+        tx: Transaction,
+    ) -> SuiRpcResult<SuiTransactionResponse> {
+        Ok(match &*self.api {
+            SuiClientApi::Rpc(c) => {
+                let (tx_bytes, flag, signature, pub_key) = tx.to_network_data_for_execution();
+                RpcGatewayApiClient::execute_transaction(
+                    &c.http, tx_bytes, flag, signature, pub_key,
+                )
+                .await?
+            }
+            SuiClientApi::Embedded(c) => c.execute_transaction(tx).await?,
+        })
+    }
+
+    pub async fn execute_transaction_by_fullnode(
+        &self,
+        tx: Transaction,
+        request_type: ExecuteTransactionRequestType,
+    ) -> SuiRpcResult<SuiExecuteTransactionResponse> {
+        Ok(match &*self.api {
+            SuiClientApi::Rpc(c) => {
+                let (tx_bytes, flag, signature, pub_key) = tx.to_network_data_for_execution();
+                TransactionExecutionApiClient::execute_transaction(
+                    &c.http,
+                    tx_bytes,
+                    flag,
+                    signature,
+                    pub_key,
+                    request_type,
+                )
+                .await?
+            }
+            // TODO do we want to support an embedded quorum driver?
+            SuiClientApi::Embedded(_c) => unimplemented!(),
+        })
+    }
+}
+
+#[derive(Clone)]
+pub struct WalletSyncApi(pub(crate) Arc<SuiClientApi>);
+
+impl WalletSyncApi {
+    pub async fn sync_account_state(&self, address: SuiAddress) -> SuiRpcResult<()> {
+        match &*self.0 {
+            SuiClientApi::Rpc(c) => {
+                if c.is_gateway() {
+                    c.http.sync_account_state(address).await?
+                }
+            }
+            SuiClientApi::Embedded(c) => c.sync_account_state(address).await?,
+        }
+        Ok(())
+    }
+}
+
+impl SuiClient {
+    pub fn transaction_builder(&self) -> &TransactionBuilder {
+        &self.transaction_builder
+    }
+    pub fn read_api(&self) -> &ReadApi {
+        &self.read_api
+    }
+    pub fn full_node_api(&self) -> &FullNodeApi {
+        &self.full_node_api
+    }
+    pub fn event_api(&self) -> &EventApi {
+        &self.event_api
+    }
+    pub fn quorum_driver(&self) -> &QuorumDriver {
+        &self.quorum_driver
+    }
+    pub fn wallet_sync_api(&self) -> &WalletSyncApi {
+        &self.wallet_sync_api
+    }
+    pub fn coin_registry(&self) -> &CoinRegistry {
+        &self.coin_registry
+    }
+
+    /// The objects named by the application, see [SuiClientBuilder::objects]. The
+    /// well-known objects are always resolved too.
+    pub fn objects(&self) -> &ObjectRegistry {
+        &self.objects
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ClientType {
+    Embedded(GatewayConfig),
+    RPC(
+        String,
+        #[serde(default, skip_serializing_if = "Option::is_none")] Option<String>,
+    ),
+}
+
+impl Display for ClientType {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let mut writer = String::new();
+
+        match self {
+            ClientType::Embedded(config) => {
+                writeln!(writer, "Client Type : Embedded Gateway")?;
+                writeln!(
+                    writer,
+                    "Gateway state DB folder path : {:?}",
+                    config.db_folder_path
+                )?;
+                let authorities = config
+                    .validator_set
+                    .iter()
+                    .map(|info| info.network_address());
+                write!(
+                    writer,
+                    "Authorities : {:?}",
+                    authorities.collect::<Vec<_>>()
+                )?;
+            }
+            ClientType::RPC(url, ws_url) => {
+                writeln!(writer, "Client Type : JSON-RPC")?;
+                writeln!(writer, "HTTP RPC URL : {}", url)?;
+                write!(
+                    writer,
+                    "WS RPC URL : {}",
+                    ws_url.clone().unwrap_or_else(|| "None".to_string())
+                )?;
+            }
+        }
+        write!(f, "{}", writer)
+    }
+}
+
+impl ClientType {
+    pub async fn init(&self) -> SuiRpcResult<SuiClient> {
+        Ok(match self {
+            ClientType::Embedded(config) => SuiClient::new_embedded_client(config)?,
+            ClientType::RPC(url, ws_url) => {
+                SuiClient::new_rpc_client(url, ws_url.as_deref()).await?
+            }
+        })
+    }
+}
@@ -0,0 +1,103 @@
+// Copyright (c) 2022, Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+use std::sync::Arc;
+
+use anyhow::anyhow;
+use move_core_types::ident_str;
+use move_core_types::identifier::IdentStr;
+use move_core_types::language_storage::{StructTag, TypeTag};
+use serde::{Deserialize, Serialize};
+
+use sui_json_rpc_types::SuiEvent;
+use sui_types::coin::COIN_MODULE_NAME;
+use sui_types::gas_coin::GasCoin;
+use sui_types::{parse_sui_struct_tag, SUI_FRAMEWORK_ADDRESS};
+
+use crate::amount::{Amount, SUI_DECIMALS};
+use crate::cache::{CacheCategory, ClientCache};
+use crate::error::{Error, SuiRpcResult};
+use crate::EventApi;
+
+const CURRENCY_CREATED_STRUCT_NAME: &IdentStr = ident_str!("CurrencyCreated");
+
+/// Rust version of the Move sui::coin::CurrencyCreated event.
+#[derive(Deserialize)]
+struct CurrencyCreated {
+    decimals: u8,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CoinMetadata {
+    pub decimals: u8,
+}
+
+/// Lazily fetches and caches the metadata of coin types, keyed by the coin type
+/// (e.g. `0x2::sui::SUI`). Metadata is read from the `CurrencyCreated` event emitted
+/// when the currency was created.
+pub struct CoinRegistry {
+    event_api: EventApi,
+    cache: Arc<ClientCache>,
+}
+
+impl CoinRegistry {
+    pub(crate) fn new(event_api: EventApi, cache: Arc<ClientCache>) -> Self {
+        Self { event_api, cache }
+    }
+
+    pub async fn get_coin_metadata(&self, coin_type: &str) -> SuiRpcResult<CoinMetadata> {
+        let coin_type = parse_sui_struct_tag(coin_type)?;
+        let key = coin_type.to_string();
+        // SUI is created at genesis, so its creation event is not always queryable.
+        if key == GasCoin::type_().to_string() {
+            return Ok(CoinMetadata {
+                decimals: SUI_DECIMALS,
+            });
+        }
+        self.cache
+            .get_or_fetch(CacheCategory::CoinMetadata, &key, || {
+                self.fetch_coin_metadata(coin_type)
+            })
+            .await
+    }
+
+    async fn fetch_coin_metadata(&self, coin_type: StructTag) -> SuiRpcResult<CoinMetadata> {
+        let key = coin_type.to_string();
+        let event_type = StructTag {
+            address: SUI_FRAMEWORK_ADDRESS,
+            module: COIN_MODULE_NAME.to_owned(),
+            name: CURRENCY_CREATED_STRUCT_NAME.to_owned(),
+            type_params: vec![TypeTag::Struct(coin_type)],
+        };
+        let events = self
+            .event_api
+            .get_events_by_move_event_struct_name(event_type.to_string(), 1, 0, u64::MAX)
+            .await?;
+        let metadata = match events.first().map(|envelope| &envelope.event) {
+            Some(SuiEvent::MoveEvent { bcs, .. }) => {
+                let event: CurrencyCreated = bcs::from_bytes(bcs)?;
+                CoinMetadata {
+                    decimals: event.decimals,
+                }
+            }
+            _ => {
+                return Err(Error::Other(anyhow!(
+                    "Cannot find currency metadata for coin type [{key}]."
+                )))
+            }
+        };
+        Ok(metadata)
+    }
+
+    /// Parse a human readable amount (e.g. `1.5`) of the given coin type.
+    pub async fn parse_amount(&self, coin_type: &str, amount: &str) -> SuiRpcResult<Amount> {
+        let metadata = self.get_coin_metadata(coin_type).await?;
+        Amount::from_human(amount, metadata.decimals)
+    }
+
+    /// Wrap an amount in base units of the given coin type.
+    pub async fn amount(&self, coin_type: &str, value: u64) -> SuiRpcResult<Amount> {
+        let metadata = self.get_coin_metadata(coin_type).await?;
+        Ok(Amount::from_base_units(value, metadata.decimals))
+    }
+}
@@ -0,0 +1,166 @@
+// Copyright (c) 2022, Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Coin types, e.g. `0x2::sui::SUI`, parsed so they compare equal however they are written.
+//!
+//! The same coin type can be written with short or zero padded addresses (`0x2` and
+//! `0x0000000000000000000000000000000000000002`), with named addresses (`sui::sui::SUI`) and
+//! with different spacing between generic parameters. A [CoinType] parses any of those into
+//! a struct tag; its string form is the canonical one used by the node, with short
+//! addresses.
+
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+
+use anyhow::anyhow;
+use move_core_types::account_address::AccountAddress;
+use move_core_types::language_storage::{StructTag, TypeTag};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use sui_types::coin::Coin;
+use sui_types::gas_coin::GAS;
+use sui_types::parse_sui_struct_tag;
+
+use crate::error::{Error, SuiRpcResult};
+
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct CoinType(StructTag);
+
+impl CoinType {
+    pub fn new(tag: StructTag) -> Self {
+        Self(tag)
+    }
+
+    pub fn sui() -> Self {
+        Self(GAS::type_())
+    }
+
+    /// The coin type of a coin object type, e.g. `0x2::sui::SUI` for
+    /// `0x2::coin::Coin<0x2::sui::SUI>`, or `None` if `object_type` is not a coin.
+    pub fn from_object_type(object_type: &str) -> Option<Self> {
+        let tag = parse_sui_struct_tag(object_type.trim()).ok()?;
+        if !Coin::is_coin(&tag) {
+            return None;
+        }
+        match tag.type_params.into_iter().next() {
+            Some(TypeTag::Struct(tag)) => Some(Self(tag)),
+            _ => None,
+        }
+    }
+
+    pub fn is_sui(&self) -> bool {
+        self.0 == GAS::type_()
+    }
+
+    pub fn address(&self) -> AccountAddress {
+        self.0.address
+    }
+
+    pub fn module(&self) -> &str {
+        self.0.module.as_str()
+    }
+
+    pub fn name(&self) -> &str {
+        self.0.name.as_str()
+    }
+
+    pub fn type_params(&self) -> &[TypeTag] {
+        &self.0.type_params
+    }
+
+    pub fn struct_tag(&self) -> &StructTag {
+        &self.0
+    }
+
+    pub fn into_struct_tag(self) -> StructTag {
+        self.0
+    }
+
+    /// The type of the coin objects of this coin type, `0x2::coin::Coin<T>`.
+    pub fn object_type(&self) -> StructTag {
+        Coin::type_(self.0.clone())
+    }
+
+    /// The string form with zero padded addresses, as some indexers and explorers use.
+    pub fn to_long_string(&self) -> String {
+        long_struct_tag(&self.0)
+    }
+}
+
+impl From<StructTag> for CoinType {
+    fn from(tag: StructTag) -> Self {
+        Self(tag)
+    }
+}
+
+impl Display for CoinType {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl FromStr for CoinType {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        if s.is_empty() {
+            return Err(Error::Other(anyhow!("Empty coin type.")));
+        }
+        Ok(Self(parse_sui_struct_tag(s)?))
+    }
+}
+
+impl Serialize for CoinType {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.to_string())
+    }
+}
+
+impl<'de> Deserialize<'de> for CoinType {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        s.parse().map_err(serde::de::Error::custom)
+    }
+}
+
+/// The canonical string form of `coin_type`, e.g. `0x2::sui::SUI` for
+/// `0x0000000000000000000000000000000000000002::sui::SUI`.
+pub fn normalize_coin_type(coin_type: &str) -> SuiRpcResult<String> {
+    Ok(coin_type.parse::<CoinType>()?.to_string())
+}
+
+/// Whether `a` and `b` are the same coin type. Strings that do not parse are compared as
+/// they are.
+pub fn coin_types_equal(a: &str, b: &str) -> bool {
+    match (a.parse::<CoinType>(), b.parse::<CoinType>()) {
+        (Ok(a), Ok(b)) => a == b,
+        _ => a.trim() == b.trim(),
+    }
+}
+
+fn long_struct_tag(tag: &StructTag) -> String {
+    let mut s = format!(
+        "0x{}::{}::{}",
+        hex::encode(tag.address.to_vec()),
+        tag.module,
+        tag.name
+    );
+    if !tag.type_params.is_empty() {
+        let params = tag
+            .type_params
+            .iter()
+            .map(long_type_tag)
+            .collect::<Vec<_>>();
+        s.push_str(&format!("<{}>", params.join(", ")));
+    }
+    s
+}
+
+fn long_type_tag(tag: &TypeTag) -> String {
+    match tag {
+        TypeTag::Struct(tag) => long_struct_tag(tag),
+        TypeTag::Vector(tag) => format!("vector<{}>", long_type_tag(tag)),
+        tag => tag.to_string(),
+    }
+}
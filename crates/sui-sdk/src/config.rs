@@ -0,0 +1,262 @@
+// Copyright (c) 2022, Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Application configuration shared by SDK users: networks with their endpoints and named
+//! objects, keystore and retry policy, applied by [SuiClient::from_config] and
+//! [SuiKeystore::from_config].
+//!
+//! A [Config] is loaded from a TOML or YAML file, chosen by extension, and environment
+//! variables override the loaded values:
+//!
+//! * `SUI_NETWORK`: the active network.
+//! * `SUI_RPC_URL` and `SUI_WS_URL`: the endpoints of the active network.
+//! * `SUI_KEYSTORE_PATH`: the keystore file.
+//! * `SUI_MAX_ATTEMPTS`: the maximum number of attempts of retried requests.
+//!
+//! ```toml
+//! active_network = "devnet"
+//!
+//! [networks.devnet]
+//! http_url = "https://gateway.devnet.sui.io:443"
+//!
+//! [networks.devnet.objects]
+//! game = "0x4a1f0e5c1bd44c7b4e1c9d7c2c2a3f6c1e8f3b2a"
+//!
+//! [keystore]
+//! type = "file"
+//! path = "/home/me/.sui/sui_config/sui.keystore"
+//!
+//! [retry]
+//! max_attempts = 3
+//! initial_backoff_ms = 500
+//! ```
+
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::time::Duration;
+
+use anyhow::anyhow;
+use serde::{Deserialize, Serialize};
+
+use sui_config::{sui_config_dir, SUI_DEV_NET_URL, SUI_KEYSTORE_FILENAME};
+
+use crate::crypto::{KeystoreType, SuiKeystore};
+use crate::error::{Error, SuiRpcResult};
+use crate::well_known::ObjectRegistry;
+use crate::{ClientOptions, SuiClient, SuiClientBuilder};
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Config {
+    /// The network used when none is requested.
+    pub active_network: String,
+    pub networks: BTreeMap<String, NetworkConfig>,
+    pub keystore: KeystoreConfig,
+    pub retry: RetryConfig,
+}
+
+impl Default for Config {
+    /// The devnet gateway and the keystore of the Sui CLI.
+    fn default() -> Self {
+        Self {
+            active_network: "devnet".to_string(),
+            networks: BTreeMap::from([(
+                "devnet".to_string(),
+                NetworkConfig {
+                    http_url: SUI_DEV_NET_URL.to_string(),
+                    ws_url: None,
+                    objects: ObjectRegistry::default(),
+                },
+            )]),
+            keystore: KeystoreConfig::default(),
+            retry: RetryConfig::default(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NetworkConfig {
+    pub http_url: String,
+    #[serde(default)]
+    pub ws_url: Option<String>,
+    /// Aliases of the objects of the network, e.g. the packages of the application.
+    #[serde(default)]
+    pub objects: ObjectRegistry,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum KeystoreConfig {
+    /// A keystore file, by default the keystore of the Sui CLI.
+    File {
+        #[serde(default)]
+        path: Option<PathBuf>,
+    },
+    /// An in-memory keystore with `initial_keys` random keys, for tests.
+    InMemory {
+        #[serde(default)]
+        initial_keys: usize,
+    },
+    /// Keys in the environment variable `var`, so CI pipelines need no key file.
+    Env { var: String },
+    /// Keys read from the standard input.
+    Stdin,
+}
+
+impl Default for KeystoreConfig {
+    fn default() -> Self {
+        KeystoreConfig::File { path: None }
+    }
+}
+
+impl KeystoreConfig {
+    pub fn keystore_type(&self) -> SuiRpcResult<KeystoreType> {
+        Ok(match self {
+            KeystoreConfig::File { path: Some(path) } => KeystoreType::File(path.clone()),
+            KeystoreConfig::File { path: None } => {
+                KeystoreType::File(sui_config_dir()?.join(SUI_KEYSTORE_FILENAME))
+            }
+            KeystoreConfig::InMemory { initial_keys } => KeystoreType::InMem(*initial_keys),
+            KeystoreConfig::Env { var } => KeystoreType::Env(var.clone()),
+            KeystoreConfig::Stdin => KeystoreType::Stdin,
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RetryConfig {
+    /// Maximum number of attempts of a request, including the first one.
+    pub max_attempts: u32,
+    /// Delay before the first retry, doubled after each failed attempt.
+    pub initial_backoff_ms: u64,
+    /// Timeout of each request.
+    pub request_timeout_ms: u64,
+}
+
+impl Default for RetryConfig {
+    fn default() -> Self {
+        Self {
+            max_attempts: 3,
+            initial_backoff_ms: 500,
+            request_timeout_ms: 60_000,
+        }
+    }
+}
+
+impl Config {
+    /// Load the configuration file at `path`, in TOML if its extension is `toml` and YAML if
+    /// it is `yaml` or `yml`, then apply the environment overrides.
+    pub fn load(path: &Path) -> SuiRpcResult<Self> {
+        let content = std::fs::read_to_string(path)
+            .map_err(|e| anyhow!("Cannot read config file [{}]: {e}", path.display()))?;
+        let config = match path.extension().and_then(|extension| extension.to_str()) {
+            Some("toml") => Self::from_toml(&content)?,
+            Some("yaml" | "yml") => Self::from_yaml(&content)?,
+            _ => {
+                return Err(Error::Other(anyhow!(
+                    "Unsupported config file [{}], expected a .toml, .yaml or .yml file.",
+                    path.display()
+                )))
+            }
+        };
+        config.with_overrides(|name| std::env::var(name).ok())
+    }
+
+    /// The default configuration with the environment overrides applied.
+    pub fn from_env() -> SuiRpcResult<Self> {
+        Self::default().with_overrides(|name| std::env::var(name).ok())
+    }
+
+    pub fn from_toml(content: &str) -> SuiRpcResult<Self> {
+        Ok(toml::from_str(content)?)
+    }
+
+    pub fn from_yaml(content: &str) -> SuiRpcResult<Self> {
+        Ok(serde_yaml::from_str(content)?)
+    }
+
+    /// Apply the overrides documented in the module documentation, reading the variables
+    /// with `var`.
+    pub fn with_overrides(mut self, var: impl Fn(&str) -> Option<String>) -> SuiRpcResult<Self> {
+        if let Some(network) = var("SUI_NETWORK") {
+            self.active_network = network;
+        }
+        if let Some(http_url) = var("SUI_RPC_URL") {
+            match self.networks.get_mut(&self.active_network) {
+                Some(network) => network.http_url = http_url,
+                None => {
+                    self.networks.insert(
+                        self.active_network.clone(),
+                        NetworkConfig {
+                            http_url,
+                            ws_url: None,
+                            objects: ObjectRegistry::default(),
+                        },
+                    );
+                }
+            }
+        }
+        if let Some(ws_url) = var("SUI_WS_URL") {
+            self.network_mut()?.ws_url = Some(ws_url);
+        }
+        if let Some(path) = var("SUI_KEYSTORE_PATH") {
+            self.keystore = KeystoreConfig::File {
+                path: Some(path.into()),
+            };
+        }
+        if let Some(max_attempts) = var("SUI_MAX_ATTEMPTS") {
+            self.retry.max_attempts = parse_var("SUI_MAX_ATTEMPTS", &max_attempts)?;
+        }
+        Ok(self)
+    }
+
+    /// The network named `name`, or the active network if `None`.
+    pub fn network(&self, name: Option<&str>) -> SuiRpcResult<&NetworkConfig> {
+        let name = name.unwrap_or(&self.active_network);
+        self.networks
+            .get(name)
+            .ok_or_else(|| Error::Other(anyhow!("Network [{name}] is not configured.")))
+    }
+
+    fn network_mut(&mut self) -> anyhow::Result<&mut NetworkConfig> {
+        let name = &self.active_network;
+        self.networks
+            .get_mut(name)
+            .ok_or_else(|| anyhow!("Network [{name}] is not configured."))
+    }
+}
+
+fn parse_var<T: FromStr>(name: &str, value: &str) -> anyhow::Result<T>
+where
+    T::Err: std::fmt::Display,
+{
+    value
+        .parse()
+        .map_err(|e| anyhow!("Invalid value [{value}] of [{name}]: {e}"))
+}
+
+impl SuiClient {
+    /// Connect to the network named `network` in `config`, or to its active network, with
+    /// the objects of the network and the retry policy of `config`.
+    pub async fn from_config(config: &Config, network: Option<&str>) -> SuiRpcResult<Self> {
+        let network = config.network(network)?;
+        let retry = &config.retry;
+        let client = SuiClientBuilder::default()
+            .request_timeout(Duration::from_millis(retry.request_timeout_ms))
+            .objects(network.objects.clone())
+            .build(&network.http_url, network.ws_url.as_deref())
+            .await?;
+        Ok(client.with_options(ClientOptions::default().with_retry(
+            retry.max_attempts,
+            Duration::from_millis(retry.initial_backoff_ms),
+        ))?)
+    }
+}
+
+impl SuiKeystore {
+    pub fn from_config(config: &Config) -> SuiRpcResult<Self> {
+        Ok(config.keystore.keystore_type()?.init()?)
+    }
+}
@@ -0,0 +1,141 @@
+// Copyright (c) 2022, Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Consistency checks across nodes, for operators validating their own fullnode against
+//! public ones.
+//!
+//! A [ConsistencyChecker] issues the same read to every node and reports a [Divergence]
+//! when they disagree. Objects are compared by reference (version and digest) and
+//! transactions by the status and object references of their effects.
+
+use futures::future::join_all;
+
+use sui_json_rpc_types::{GetRawObjectDataResponse, SuiExecutionStatus, SuiTransactionEffects};
+use sui_types::base_types::{ObjectID, TransactionDigest};
+
+use crate::SuiClient;
+
+/// What a node answered to a read.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Observation {
+    pub endpoint: String,
+    /// A summary of the response, or the error of the request.
+    pub value: Result<String, String>,
+}
+
+/// Nodes disagreeing on a read.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Divergence {
+    /// What was read, e.g. `object 0x...`.
+    pub subject: String,
+    pub observations: Vec<Observation>,
+}
+
+/// A [Divergence] if the observations of `subject` are not all the same.
+pub fn compare(subject: String, observations: Vec<Observation>) -> Option<Divergence> {
+    let first = &observations.first()?.value;
+    if observations
+        .iter()
+        .all(|observation| &observation.value == first)
+    {
+        return None;
+    }
+    Some(Divergence {
+        subject,
+        observations,
+    })
+}
+
+pub struct ConsistencyChecker {
+    clients: Vec<SuiClient>,
+}
+
+impl ConsistencyChecker {
+    /// A checker comparing the reads of `clients`, e.g. one connected to the operator's node
+    /// and others connected to public nodes.
+    pub fn new(clients: Vec<SuiClient>) -> Self {
+        Self { clients }
+    }
+
+    pub async fn check_object(&self, object_id: ObjectID) -> Option<Divergence> {
+        let reads = self.clients.iter().map(|client| async move {
+            let response = client.read_api().get_object(object_id).await;
+            Ok(object_summary(&response?))
+        });
+        compare(format!("object {object_id}"), self.observe(reads).await)
+    }
+
+    pub async fn check_transaction(&self, digest: TransactionDigest) -> Option<Divergence> {
+        let reads = self.clients.iter().map(|client| async move {
+            let response = client.read_api().get_transaction(digest).await;
+            Ok(effects_summary(&response?.effects))
+        });
+        compare(format!("transaction {digest}"), self.observe(reads).await)
+    }
+
+    /// Check the objects and transactions, returning the divergences found.
+    pub async fn check(
+        &self,
+        object_ids: &[ObjectID],
+        digests: &[TransactionDigest],
+    ) -> Vec<Divergence> {
+        let objects = join_all(object_ids.iter().map(|id| self.check_object(*id)));
+        let transactions = join_all(digests.iter().map(|digest| self.check_transaction(*digest)));
+        let (objects, transactions) = futures::join!(objects, transactions);
+        objects.into_iter().chain(transactions).flatten().collect()
+    }
+
+    async fn observe<Fut>(&self, reads: impl Iterator<Item = Fut>) -> Vec<Observation>
+    where
+        Fut: std::future::Future<Output = anyhow::Result<String>>,
+    {
+        self.clients
+            .iter()
+            .zip(join_all(reads).await)
+            .map(|(client, value)| Observation {
+                endpoint: client.read_api().endpoint().to_string(),
+                value: value.map_err(|e| e.to_string()),
+            })
+            .collect()
+    }
+}
+
+fn object_summary(response: &GetRawObjectDataResponse) -> String {
+    match response {
+        GetRawObjectDataResponse::Exists(object) => {
+            let reference = &object.reference;
+            format!(
+                "version {} digest {:?}",
+                reference.version, reference.digest
+            )
+        }
+        GetRawObjectDataResponse::NotExists(_) => "not found".to_string(),
+        GetRawObjectDataResponse::Deleted(reference) => {
+            format!("deleted at version {}", reference.version)
+        }
+    }
+}
+
+fn effects_summary(effects: &SuiTransactionEffects) -> String {
+    let status = match &effects.status {
+        SuiExecutionStatus::Success => "success".to_string(),
+        SuiExecutionStatus::Failure { error } => format!("failure: {error}"),
+    };
+    let mut references = effects
+        .created
+        .iter()
+        .chain(&effects.mutated)
+        .chain(&effects.unwrapped)
+        .map(|object| &object.reference)
+        .chain(&effects.deleted)
+        .chain(&effects.wrapped)
+        .map(|reference| {
+            format!(
+                "{}:{}:{:?}",
+                reference.object_id, reference.version, reference.digest
+            )
+        })
+        .collect::<Vec<_>>();
+    references.sort();
+    format!("{status}, objects [{}]", references.join(", "))
+}
@@ -1,18 +1,19 @@
 // Copyright (c) 2022, Mysten Labs, Inc.
 // SPDX-License-Identifier: Apache-2.0
 
-use anyhow::anyhow;
+use anyhow::{anyhow, bail, ensure};
 use bip32::DerivationPath;
 use bip39::{Language, Mnemonic, MnemonicType, Seed};
 use rand::{rngs::StdRng, SeedableRng};
+use rayon::prelude::*;
 use serde::{Deserialize, Serialize};
 use signature::Signer;
-use std::collections::BTreeMap;
+use std::collections::{BTreeMap, BTreeSet};
 use std::fmt::Write;
 use std::fmt::{Display, Formatter};
 use std::fs;
 use std::fs::File;
-use std::io::BufReader;
+use std::io::{BufReader, Read};
 use std::path::{Path, PathBuf};
 
 use sui_types::base_types::SuiAddress;
@@ -20,6 +21,9 @@ use sui_types::crypto::{
     derive_key_pair_from_path, get_key_pair_from_rng, EncodeDecodeBase64, PublicKey, Signature,
     SignatureScheme, SuiKeyPair,
 };
+use sui_types::sui_serde::{Base64, Encoding};
+
+use crate::error::KeystoreError;
 
 #[derive(Serialize, Deserialize)]
 #[non_exhaustive]
@@ -27,25 +31,84 @@ use sui_types::crypto::{
 pub enum KeystoreType {
     File(PathBuf),
     InMem(usize),
+    /// Keys in the environment variable of this name, e.g. a CI secret, see [parse_keys].
+    /// Keys added later are kept in memory.
+    Env(String),
+    /// Keys read from the standard input, see [parse_keys]. Keys added later are kept in
+    /// memory.
+    Stdin,
 }
 
 pub trait AccountKeystore: Send + Sync {
     fn sign(&self, address: &SuiAddress, msg: &[u8]) -> Result<Signature, signature::Error>;
     fn add_key(&mut self, keypair: SuiKeyPair) -> Result<(), anyhow::Error>;
     fn keys(&self) -> Vec<PublicKey>;
+
+    fn remove_key(&mut self, _address: &SuiAddress) -> Result<(), anyhow::Error> {
+        bail!("This keystore does not support removing keys.")
+    }
+
+    /// The name given to the key of `address`, if any.
+    fn alias(&self, _address: &SuiAddress) -> Option<String> {
+        None
+    }
+
+    fn set_alias(&mut self, _address: &SuiAddress, _alias: &str) -> Result<(), anyhow::Error> {
+        bail!("This keystore does not support aliases.")
+    }
 }
 
 impl KeystoreType {
-    pub fn init(&self) -> Result<SuiKeystore, anyhow::Error> {
+    pub fn init(&self) -> Result<SuiKeystore, KeystoreError> {
         Ok(match self {
-            KeystoreType::File(path) => SuiKeystore::from(FileBasedKeystore::load_or_create(path)?),
+            KeystoreType::File(path) => SuiKeystore::from(
+                FileBasedKeystore::load_or_create(path).map_err(KeystoreError::Storage)?,
+            ),
             KeystoreType::InMem(initial_key_number) => {
                 SuiKeystore::from(InMemKeystore::new(*initial_key_number))
             }
+            KeystoreType::Env(var) => {
+                let blob = std::env::var(var).map_err(|e| {
+                    KeystoreError::Storage(anyhow!("Cannot read keys from [{var}]: {e}"))
+                })?;
+                SuiKeystore::from(InMemKeystore::from_blob(&blob)?)
+            }
+            KeystoreType::Stdin => {
+                let mut blob = String::new();
+                std::io::stdin()
+                    .read_to_string(&mut blob)
+                    .map_err(|e| KeystoreError::Storage(e.into()))?;
+                SuiKeystore::from(InMemKeystore::from_blob(&blob)?)
+            }
         })
     }
 }
 
+/// The keys of a keystore blob: the content of a keystore file, that is a JSON array of
+/// base64 keypairs, the same content encoded in base64, or base64 keypairs separated by
+/// whitespace, e.g. a single keypair.
+pub fn parse_keys(blob: &str) -> Result<BTreeMap<SuiAddress, SuiKeyPair>, anyhow::Error> {
+    let blob = blob.trim();
+    let encoded: Vec<String> = if blob.starts_with('[') {
+        serde_json::from_str(blob)?
+    } else {
+        match Base64::decode(blob) {
+            Ok(bytes) if bytes.first() == Some(&b'[') => serde_json::from_slice(&bytes)?,
+            _ => blob.split_whitespace().map(str::to_string).collect(),
+        }
+    };
+    // Never echo the blob: it holds private keys.
+    encoded
+        .iter()
+        .enumerate()
+        .map(|(index, encoded)| {
+            let key = SuiKeyPair::decode_base64(encoded)
+                .map_err(|_| anyhow!("Invalid keypair at position {index} of the keys"))?;
+            Ok(((&key.public()).into(), key))
+        })
+        .collect()
+}
+
 impl Display for KeystoreType {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         let mut writer = String::new();
@@ -59,13 +122,82 @@ impl Display for KeystoreType {
                 writeln!(writer, "Keystore Type : InMem")?;
                 write!(f, "{}", writer)
             }
+            KeystoreType::Env(var) => {
+                writeln!(writer, "Keystore Type : Env")?;
+                write!(writer, "Keystore Variable : {}", var)?;
+                write!(f, "{}", writer)
+            }
+            KeystoreType::Stdin => {
+                writeln!(writer, "Keystore Type : Stdin")?;
+                write!(f, "{}", writer)
+            }
         }
     }
 }
 
+/// The aliases file kept by the Sui CLI next to its keystore, e.g. `sui.aliases` for
+/// `sui.keystore`.
+pub fn aliases_path(keystore_path: &Path) -> PathBuf {
+    keystore_path.with_extension("aliases")
+}
+
+/// An entry of an aliases file.
+#[derive(Serialize, Deserialize)]
+struct KeyAlias {
+    alias: String,
+    public_key_base64: String,
+}
+
+fn read_aliases(keystore_path: &Path) -> Result<BTreeMap<SuiAddress, String>, anyhow::Error> {
+    let path = aliases_path(keystore_path);
+    if !path.exists() {
+        return Ok(BTreeMap::new());
+    }
+    let reader = BufReader::new(File::open(&path)?);
+    let entries: Vec<KeyAlias> = serde_json::from_reader(reader)?;
+    entries
+        .into_iter()
+        .map(|entry| {
+            let key = PublicKey::decode_base64(&entry.public_key_base64)
+                .map_err(|e| anyhow!("Invalid aliases file {:?}: {e}", path))?;
+            Ok(((&key).into(), entry.alias))
+        })
+        .collect()
+}
+
+/// Merge the aliases set by this process, `ours`, with the aliases of another writer of the
+/// aliases file, `theirs`. Our alias of an address wins; an alias of theirs already taken
+/// by another address gets the first free suffix, e.g. `main-2`.
+pub fn merge_aliases(
+    ours: &BTreeMap<SuiAddress, String>,
+    theirs: &BTreeMap<SuiAddress, String>,
+) -> BTreeMap<SuiAddress, String> {
+    let mut taken = ours.values().cloned().collect::<BTreeSet<_>>();
+    let mut merged = ours.clone();
+    for (address, alias) in theirs {
+        if merged.contains_key(address) {
+            continue;
+        }
+        let mut unique = alias.clone();
+        let mut suffix = 2;
+        while taken.contains(&unique) {
+            unique = format!("{alias}-{suffix}");
+            suffix += 1;
+        }
+        taken.insert(unique.clone());
+        merged.insert(*address, unique);
+    }
+    merged
+}
+
+/// A keystore file, with the aliases of its keys in the aliases file of the Sui CLI, so the
+/// CLI and SDK-based tools sharing a directory see the same names.
 #[derive(Default)]
 pub struct FileBasedKeystore {
     keys: BTreeMap<SuiAddress, SuiKeyPair>,
+    aliases: BTreeMap<SuiAddress, String>,
+    /// Addresses whose alias was set since the aliases file was last written.
+    renamed: BTreeSet<SuiAddress>,
     path: Option<PathBuf>,
 }
 
@@ -82,13 +214,52 @@ impl AccountKeystore for FileBasedKeystore {
     fn add_key(&mut self, keypair: SuiKeyPair) -> Result<(), anyhow::Error> {
         let address: SuiAddress = (&keypair.public()).into();
         self.keys.insert(address, keypair);
+        self.aliases = self.resolved_aliases()?;
         self.save()?;
+        self.renamed.clear();
         Ok(())
     }
 
     fn keys(&self) -> Vec<PublicKey> {
         self.keys.values().map(|key| key.public()).collect()
     }
+
+    fn remove_key(&mut self, address: &SuiAddress) -> Result<(), anyhow::Error> {
+        ensure!(
+            self.keys.remove(address).is_some(),
+            "Cannot find key for address: [{address}]"
+        );
+        self.aliases.remove(address);
+        self.renamed.remove(address);
+        self.save()
+    }
+
+    fn alias(&self, address: &SuiAddress) -> Option<String> {
+        self.aliases.get(address).cloned()
+    }
+
+    /// Name the key of `address`, unless another key has that name, and save the aliases
+    /// file. A key another process gave the same name since the file was read is renamed
+    /// with a suffix, e.g. `main-2`.
+    fn set_alias(&mut self, address: &SuiAddress, alias: &str) -> Result<(), anyhow::Error> {
+        ensure!(
+            self.keys.contains_key(address),
+            "Cannot find key for address: [{address}]"
+        );
+        if let Some((other, _)) = self
+            .aliases
+            .iter()
+            .find(|(other, name)| *other != address && name.as_str() == alias)
+        {
+            bail!("Alias [{alias}] is already used by [{other}]")
+        }
+        self.aliases.insert(*address, alias.to_string());
+        self.renamed.insert(*address);
+        self.aliases = self.resolved_aliases()?;
+        self.save()?;
+        self.renamed.clear();
+        Ok(())
+    }
 }
 
 impl FileBasedKeystore {
@@ -107,9 +278,13 @@ impl FileBasedKeystore {
         } else {
             BTreeMap::new()
         };
+        let mut aliases = read_aliases(path)?;
+        aliases.retain(|address, _| keys.contains_key(address));
 
         Ok(Self {
             keys,
+            aliases,
+            renamed: BTreeSet::new(),
             path: Some(path.to_path_buf()),
         })
     }
@@ -128,11 +303,45 @@ impl FileBasedKeystore {
                     .collect::<Vec<_>>(),
             )
             .unwrap();
-            fs::write(path, store)?
+            fs::write(path, store)?;
+
+            let aliases_path = aliases_path(path);
+            let aliases = self.resolved_aliases()?;
+            if !aliases.is_empty() || aliases_path.exists() {
+                let entries = aliases
+                    .into_iter()
+                    .map(|(address, alias)| KeyAlias {
+                        alias,
+                        public_key_base64: self.keys[&address].public().encode_base64(),
+                    })
+                    .collect::<Vec<_>>();
+                fs::write(aliases_path, serde_json::to_string_pretty(&entries)?)?
+            }
         }
         Ok(())
     }
 
+    /// Our aliases merged with the aliases file, which another process such as the CLI may
+    /// have changed since it was read. The aliases set since it was last written win;
+    /// otherwise the file wins.
+    fn resolved_aliases(&self) -> Result<BTreeMap<SuiAddress, String>, anyhow::Error> {
+        let mut theirs = match &self.path {
+            Some(path) => read_aliases(path)?,
+            None => BTreeMap::new(),
+        };
+        theirs.retain(|address, _| self.keys.contains_key(address));
+        for (address, alias) in &self.aliases {
+            theirs.entry(*address).or_insert_with(|| alias.clone());
+        }
+        let ours = self
+            .aliases
+            .iter()
+            .filter(|(address, _)| self.renamed.contains(address))
+            .map(|(address, alias)| (*address, alias.clone()))
+            .collect();
+        Ok(merge_aliases(&ours, &theirs))
+    }
+
     pub fn key_pairs(&self) -> Vec<&SuiKeyPair> {
         self.keys.values().collect()
     }
@@ -145,15 +354,26 @@ impl SuiKeystore {
         Self(Box::new(keystore))
     }
 
-    pub fn add_key(&mut self, keypair: SuiKeyPair) -> Result<(), anyhow::Error> {
-        self.0.add_key(keypair)
+    pub fn add_key(&mut self, keypair: SuiKeyPair) -> Result<(), KeystoreError> {
+        self.0.add_key(keypair).map_err(KeystoreError::from_storage)
+    }
+
+    pub fn remove_key(&mut self, address: &SuiAddress) -> Result<(), KeystoreError> {
+        self.0
+            .remove_key(address)
+            .map_err(KeystoreError::from_storage)
+    }
+
+    /// This keystore, rejecting any change of its keys, see [ReadOnlyKeystore].
+    pub fn into_read_only(self) -> Self {
+        Self::from(ReadOnlyKeystore::new(self.0))
     }
 
     pub fn generate_new_key(
         &mut self,
         key_scheme: SignatureScheme,
         derivation_path: Option<DerivationPath>,
-    ) -> Result<(SuiAddress, String, SignatureScheme), anyhow::Error> {
+    ) -> Result<(SuiAddress, String, SignatureScheme), KeystoreError> {
         let mnemonic = Mnemonic::new(MnemonicType::Words12, Language::English);
         match derive_key_pair_from_path(
             Seed::new(&mnemonic, "").as_bytes(),
@@ -164,7 +384,7 @@ impl SuiKeystore {
                 self.add_key(keypair)?;
                 Ok((address, mnemonic.phrase().to_string(), key_scheme))
             }
-            Err(e) => Err(anyhow!("error generating key {:?}", e)),
+            Err(e) => Err(KeystoreError::KeyDerivation(format!("{:?}", e))),
         }
     }
 
@@ -176,27 +396,57 @@ impl SuiKeystore {
         self.keys().iter().map(|k| k.into()).collect()
     }
 
+    pub fn alias(&self, address: &SuiAddress) -> Option<String> {
+        self.0.alias(address)
+    }
+
+    pub fn set_alias(&mut self, address: &SuiAddress, alias: &str) -> Result<(), KeystoreError> {
+        self.0
+            .set_alias(address, alias)
+            .map_err(KeystoreError::from_storage)
+    }
+
+    /// The address of the key named `alias`.
+    pub fn address_by_alias(&self, alias: &str) -> Option<SuiAddress> {
+        self.addresses()
+            .into_iter()
+            .find(|address| self.alias(address).as_deref() == Some(alias))
+    }
+
     pub fn import_from_mnemonic(
         &mut self,
         phrase: &str,
         key_scheme: SignatureScheme,
         derivation_path: Option<DerivationPath>,
-    ) -> Result<SuiAddress, anyhow::Error> {
+    ) -> Result<SuiAddress, KeystoreError> {
         let mnemonic = Mnemonic::from_phrase(phrase, Language::English)
-            .map_err(|e| anyhow::anyhow!("Invalid mnemonic phrase: {:?}", e))?;
+            .map_err(|e| KeystoreError::InvalidMnemonic(format!("{:?}", e)))?;
         let seed = Seed::new(&mnemonic, "");
         match derive_key_pair_from_path(seed.as_bytes(), derivation_path, &key_scheme) {
             Ok((address, kp)) => {
-                self.0.add_key(kp)?;
+                self.add_key(kp)?;
                 Ok(address)
             }
-            Err(e) => Err(anyhow!("error getting keypair {:?}", e)),
+            Err(e) => Err(KeystoreError::KeyDerivation(format!("{:?}", e))),
         }
     }
 
     pub fn sign(&self, address: &SuiAddress, msg: &[u8]) -> Result<Signature, signature::Error> {
         self.0.sign(address, msg)
     }
+
+    /// Sign each message with the key of its address, concurrently on the rayon thread pool,
+    /// and return the results in the order of `requests`. Call it inside
+    /// `ThreadPool::install` to use a dedicated pool, and from `spawn_blocking` in async code.
+    pub fn sign_all(
+        &self,
+        requests: Vec<(SuiAddress, Vec<u8>)>,
+    ) -> Vec<Result<Signature, signature::Error>> {
+        requests
+            .par_iter()
+            .map(|(address, msg)| self.sign(address, msg))
+            .collect()
+    }
 }
 
 #[derive(Default)]
@@ -223,6 +473,14 @@ impl AccountKeystore for InMemKeystore {
     fn keys(&self) -> Vec<PublicKey> {
         self.keys.values().map(|key| key.public()).collect()
     }
+
+    fn remove_key(&mut self, address: &SuiAddress) -> Result<(), anyhow::Error> {
+        ensure!(
+            self.keys.remove(address).is_some(),
+            "Cannot find key for address: [{address}]"
+        );
+        Ok(())
+    }
 }
 
 impl InMemKeystore {
@@ -235,6 +493,12 @@ impl InMemKeystore {
 
         Self { keys }
     }
+
+    fn from_blob(blob: &str) -> Result<Self, KeystoreError> {
+        Ok(Self {
+            keys: parse_keys(blob).map_err(KeystoreError::Storage)?,
+        })
+    }
 }
 
 impl AccountKeystore for Box<dyn AccountKeystore> {
@@ -249,4 +513,57 @@ impl AccountKeystore for Box<dyn AccountKeystore> {
     fn keys(&self) -> Vec<PublicKey> {
         (**self).keys()
     }
+
+    fn remove_key(&mut self, address: &SuiAddress) -> Result<(), anyhow::Error> {
+        (**self).remove_key(address)
+    }
+
+    fn alias(&self, address: &SuiAddress) -> Option<String> {
+        (**self).alias(address)
+    }
+
+    fn set_alias(&mut self, address: &SuiAddress, alias: &str) -> Result<(), anyhow::Error> {
+        (**self).set_alias(address, alias)
+    }
+}
+
+/// A keystore that signs with the keys of another keystore but rejects adding, removing or
+/// renaming keys with [KeystoreError::ReadOnly], for services whose key file must never
+/// change, e.g. one mounted as a read-only secret.
+pub struct ReadOnlyKeystore<S>(S);
+
+impl<S: AccountKeystore> ReadOnlyKeystore<S> {
+    pub fn new(keystore: S) -> Self {
+        Self(keystore)
+    }
+
+    pub fn into_inner(self) -> S {
+        self.0
+    }
+}
+
+impl<S: AccountKeystore> AccountKeystore for ReadOnlyKeystore<S> {
+    fn sign(&self, address: &SuiAddress, msg: &[u8]) -> Result<Signature, signature::Error> {
+        self.0.sign(address, msg)
+    }
+
+    fn add_key(&mut self, _keypair: SuiKeyPair) -> Result<(), anyhow::Error> {
+        Err(KeystoreError::ReadOnly.into())
+    }
+
+    fn keys(&self) -> Vec<PublicKey> {
+        self.0.keys()
+    }
+
+    fn remove_key(&mut self, _address: &SuiAddress) -> Result<(), anyhow::Error> {
+        Err(KeystoreError::ReadOnly.into())
+    }
+
+    fn alias(&self, address: &SuiAddress) -> Option<String> {
+        self.0.alias(address)
+    }
+
+    fn set_alias(&mut self, _address: &SuiAddress, _alias: &str) -> Result<(), anyhow::Error> {
+        Err(KeystoreError::ReadOnly.into())
+    }
 }
@@ -0,0 +1,204 @@
+// Copyright (c) 2022, Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Durable cursors of streaming consumers, keyed by stream name.
+//!
+//! A consumer saves its cursor once the items before it are fully handled, so after a
+//! restart it resumes at the first item it did not complete. Both stores replace cursors
+//! atomically, so a crash never leaves a partially written cursor behind. With
+//! [SqliteCursorStore::commit], handlers write their results in the same SQLite transaction
+//! as the cursor: either both are stored or neither is, and no item is applied twice.
+//!
+//! [StreamCursor] adapts a named cursor to the [SequenceStore] of the transaction follower.
+
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+use async_trait::async_trait;
+use futures::future::BoxFuture;
+use serde_json::Value;
+use sqlx::sqlite::{SqliteConnectOptions, SqlitePoolOptions};
+use sqlx::{Executor, Row, SqliteConnection, SqlitePool};
+
+use sui_json_rpc_types::GatewayTxSeqNumber;
+
+use crate::encrypted_store::{read_file, write_file, StoreCipher};
+use crate::error::SuiRpcResult;
+use crate::follower::SequenceStore;
+
+#[async_trait]
+pub trait CursorStore: Send + Sync {
+    async fn load(&self, stream: &str) -> SuiRpcResult<Option<Value>>;
+
+    /// Replace the cursor of `stream`. After a crash, [CursorStore::load] returns either the
+    /// previous or the new cursor.
+    async fn save(&self, stream: &str, cursor: Value) -> SuiRpcResult<()>;
+}
+
+/// Keeps the cursors of all streams in a JSON file, replaced through a temporary file on
+/// every save.
+pub struct FileCursorStore {
+    path: PathBuf,
+    cipher: Option<Arc<StoreCipher>>,
+    // Serializes saves, which read, update and replace the whole file.
+    lock: Mutex<()>,
+}
+
+impl FileCursorStore {
+    pub fn new(path: PathBuf) -> Self {
+        Self {
+            path,
+            cipher: None,
+            lock: Mutex::new(()),
+        }
+    }
+
+    /// Encrypt the file with `cipher`.
+    pub fn with_cipher(mut self, cipher: Arc<StoreCipher>) -> Self {
+        self.cipher = Some(cipher);
+        self
+    }
+
+    fn read(&self) -> anyhow::Result<BTreeMap<String, Value>> {
+        if !self.path.exists() {
+            return Ok(BTreeMap::new());
+        }
+        Ok(serde_json::from_slice(&read_file(
+            &self.path,
+            self.cipher.as_deref(),
+        )?)?)
+    }
+}
+
+#[async_trait]
+impl CursorStore for FileCursorStore {
+    async fn load(&self, stream: &str) -> SuiRpcResult<Option<Value>> {
+        Ok(self.read()?.remove(stream))
+    }
+
+    async fn save(&self, stream: &str, cursor: Value) -> SuiRpcResult<()> {
+        let _guard = self.lock.lock().unwrap();
+        let mut cursors = self.read()?;
+        cursors.insert(stream.to_string(), cursor);
+        let temp = self.path.with_extension("tmp");
+        write_file(
+            &temp,
+            &serde_json::to_vec_pretty(&cursors)?,
+            self.cipher.as_deref(),
+        )?;
+        std::fs::rename(&temp, &self.path)?;
+        Ok(())
+    }
+}
+
+const SQL_UPSERT_CURSOR: &str = "INSERT INTO cursors (stream, cursor) VALUES (?, ?) \
+    ON CONFLICT(stream) DO UPDATE SET cursor = excluded.cursor";
+
+/// Keeps cursors in the `cursors` table of a SQLite database, which handlers can share for
+/// their own tables, see [SqliteCursorStore::commit]. The database is not encrypted, unlike
+/// a [FileCursorStore] with a cipher.
+pub struct SqliteCursorStore {
+    pool: SqlitePool,
+}
+
+impl SqliteCursorStore {
+    /// Open the database at `path`, creating it if it does not exist.
+    pub async fn open(path: &Path) -> SuiRpcResult<Self> {
+        let options = SqliteConnectOptions::new()
+            .filename(path)
+            .create_if_missing(true);
+        Self::initialize(SqlitePool::connect_with(options).await?).await
+    }
+
+    /// An in-memory database, for tests.
+    pub async fn in_memory() -> SuiRpcResult<Self> {
+        // Every connection to `sqlite::memory:` opens a distinct database.
+        let pool = SqlitePoolOptions::new()
+            .max_connections(1)
+            .connect("sqlite::memory:")
+            .await?;
+        Self::initialize(pool).await
+    }
+
+    async fn initialize(pool: SqlitePool) -> SuiRpcResult<Self> {
+        pool.execute(
+            "CREATE TABLE IF NOT EXISTS cursors (stream TEXT PRIMARY KEY, cursor TEXT NOT NULL)",
+        )
+        .await?;
+        Ok(Self { pool })
+    }
+
+    pub fn pool(&self) -> &SqlitePool {
+        &self.pool
+    }
+
+    /// Run `write`, then save `cursor` for `stream`, in one transaction. Nothing is stored if
+    /// `write` fails.
+    pub async fn commit<F>(&self, stream: &str, cursor: Value, write: F) -> SuiRpcResult<()>
+    where
+        F: for<'c> FnOnce(&'c mut SqliteConnection) -> BoxFuture<'c, SuiRpcResult<()>> + Send,
+    {
+        let mut transaction = self.pool.begin().await?;
+        write(&mut *transaction).await?;
+        sqlx::query(SQL_UPSERT_CURSOR)
+            .bind(stream)
+            .bind(cursor.to_string())
+            .execute(&mut *transaction)
+            .await?;
+        transaction.commit().await?;
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl CursorStore for SqliteCursorStore {
+    async fn load(&self, stream: &str) -> SuiRpcResult<Option<Value>> {
+        let row = sqlx::query("SELECT cursor FROM cursors WHERE stream = ?")
+            .bind(stream)
+            .fetch_optional(&self.pool)
+            .await?;
+        Ok(match row {
+            Some(row) => Some(serde_json::from_str(row.try_get::<&str, _>(0)?)?),
+            None => None,
+        })
+    }
+
+    async fn save(&self, stream: &str, cursor: Value) -> SuiRpcResult<()> {
+        sqlx::query(SQL_UPSERT_CURSOR)
+            .bind(stream)
+            .bind(cursor.to_string())
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+}
+
+/// The cursor named `stream` in a [CursorStore].
+pub struct StreamCursor<S> {
+    pub store: S,
+    pub stream: String,
+}
+
+impl<S> StreamCursor<S> {
+    pub fn new(store: S, stream: impl Into<String>) -> Self {
+        Self {
+            store,
+            stream: stream.into(),
+        }
+    }
+}
+
+#[async_trait]
+impl<S: CursorStore> SequenceStore for StreamCursor<S> {
+    async fn load(&self) -> SuiRpcResult<Option<GatewayTxSeqNumber>> {
+        Ok(match self.store.load(&self.stream).await? {
+            Some(cursor) => Some(serde_json::from_value(cursor)?),
+            None => None,
+        })
+    }
+
+    async fn save(&self, next: GatewayTxSeqNumber) -> SuiRpcResult<()> {
+        self.store.save(&self.stream, next.into()).await
+    }
+}
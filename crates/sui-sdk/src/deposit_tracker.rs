@@ -0,0 +1,215 @@
+// Copyright (c) 2022, Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+use std::collections::{BTreeSet, VecDeque};
+
+use futures::{pin_mut, Stream, StreamExt};
+use serde::{Deserialize, Serialize};
+use tokio::sync::mpsc;
+
+use sui_json_rpc_types::GatewayTxSeqNumber;
+use sui_types::base_types::{ObjectID, SequenceNumber, SuiAddress, TransactionDigest};
+use sui_types::object::Owner;
+
+use crate::amount::Amount;
+use crate::balance_changes::past_coin;
+use crate::cursor_store::CursorStore;
+use crate::error::SuiRpcResult;
+use crate::follower::FollowedTransaction;
+use crate::ReadApi;
+
+/// A coin received by one of the addresses watched by a [DepositTracker].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Deposit {
+    pub tx_digest: TransactionDigest,
+    pub seq: GatewayTxSeqNumber,
+    pub timestamp_ms: Option<u64>,
+    pub sender: SuiAddress,
+    pub recipient: SuiAddress,
+    pub coin_object_id: ObjectID,
+    pub version: SequenceNumber,
+    /// The coin type, e.g. `0x2::sui::SUI`.
+    pub coin_type: String,
+    pub amount: u64,
+}
+
+impl Deposit {
+    /// The amount as an [Amount] of SUI, if the coin type is SUI.
+    pub fn sui_amount(&self) -> Option<Amount> {
+        Amount::from_coin(&self.coin_type, self.amount)
+    }
+}
+
+/// When a detected deposit is reported.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConfirmationPolicy {
+    /// Report deposits as soon as their transaction is seen. Transactions served by a full
+    /// node are final, so this is safe for most uses.
+    Immediate,
+    /// Hold deposits back until the node has executed this many later transactions.
+    AfterTransactions(u64),
+}
+
+/// Detects incoming coin transfers to a set of addresses from the stream of a
+/// [crate::follower::TransactionFollower]. Only successful transactions sent by an
+/// address other than the recipient are reported, and every coin object is reported at
+/// most once per transaction.
+///
+/// The coins already reported are only remembered down to the confirmation horizon, the
+/// sequence number of the last transaction minus the confirmations of the policy; older
+/// transactions are ignored altogether. With a [store](DepositTracker::with_store), the
+/// pending deposits and this state survive restarts.
+pub struct DepositTracker<'a> {
+    read_api: &'a ReadApi,
+    addresses: BTreeSet<SuiAddress>,
+    policy: ConfirmationPolicy,
+    store: Option<(Box<dyn CursorStore + 'a>, String)>,
+    state: TrackerState,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct TrackerState {
+    /// Transactions before this sequence number are ignored.
+    horizon: GatewayTxSeqNumber,
+    seen: BTreeSet<(GatewayTxSeqNumber, TransactionDigest, ObjectID)>,
+    pending: VecDeque<Deposit>,
+}
+
+impl<'a> DepositTracker<'a> {
+    pub fn new(
+        read_api: &'a ReadApi,
+        addresses: impl IntoIterator<Item = SuiAddress>,
+        policy: ConfirmationPolicy,
+    ) -> Self {
+        Self {
+            read_api,
+            addresses: addresses.into_iter().collect(),
+            policy,
+            store: None,
+            state: TrackerState::default(),
+        }
+    }
+
+    /// Keep the pending deposits and the coins already reported under the cursor name
+    /// `stream` of `store`, saved after each transaction [run](DepositTracker::run) handles.
+    pub fn with_store(mut self, store: impl CursorStore + 'a, stream: impl Into<String>) -> Self {
+        self.store = Some((Box::new(store), stream.into()));
+        self
+    }
+
+    /// Consume `transactions` and send detected deposits to `sender`, until the stream ends,
+    /// fails, or the receiver is dropped. The state saved in the store is loaded first.
+    ///
+    /// Deposits are sent before the state is saved, so after a crash the deposits of the
+    /// last transaction may be sent again.
+    pub async fn run(
+        &mut self,
+        transactions: impl Stream<Item = SuiRpcResult<FollowedTransaction>>,
+        sender: mpsc::Sender<Deposit>,
+    ) -> SuiRpcResult<()> {
+        self.load().await?;
+        let confirmations = match self.policy {
+            ConfirmationPolicy::Immediate => 0,
+            ConfirmationPolicy::AfterTransactions(n) => n,
+        };
+        pin_mut!(transactions);
+        while let Some(transaction) = transactions.next().await {
+            let transaction = transaction?;
+            let seq = transaction.seq;
+            let deposits = self.detect(transaction).await?;
+            self.state.pending.extend(deposits);
+
+            while let Some(deposit) = self.state.pending.front() {
+                if deposit.seq.saturating_add(confirmations) > seq {
+                    break;
+                }
+                let deposit = self.state.pending.pop_front().unwrap();
+                if let Err(mpsc::error::SendError(deposit)) = sender.send(deposit).await {
+                    self.state.pending.push_front(deposit);
+                    return self.save().await;
+                }
+            }
+            self.prune(seq.saturating_sub(confirmations));
+            self.save().await?;
+        }
+        Ok(())
+    }
+
+    /// Forget the coins reported by transactions before `horizon`, and ignore these
+    /// transactions from now on.
+    fn prune(&mut self, horizon: GatewayTxSeqNumber) {
+        let state = &mut self.state;
+        state.horizon = state.horizon.max(horizon);
+        let horizon = state.horizon;
+        state.seen.retain(|(seq, _, _)| *seq >= horizon);
+    }
+
+    async fn load(&mut self) -> SuiRpcResult<()> {
+        if let Some((store, stream)) = &self.store {
+            if let Some(state) = store.load(stream).await? {
+                self.state = serde_json::from_value(state)?;
+            }
+        }
+        Ok(())
+    }
+
+    async fn save(&self) -> SuiRpcResult<()> {
+        match &self.store {
+            Some((store, stream)) => store.save(stream, serde_json::to_value(&self.state)?).await,
+            None => Ok(()),
+        }
+    }
+
+    /// Return the deposits made by a transaction to the watched addresses, none if it is
+    /// before the confirmation horizon.
+    pub async fn detect(&mut self, transaction: FollowedTransaction) -> SuiRpcResult<Vec<Deposit>> {
+        let FollowedTransaction {
+            seq,
+            digest,
+            response,
+        } = transaction;
+        if seq < self.state.horizon || !response.effects.status.is_ok() {
+            return Ok(vec![]);
+        }
+        let sender = response.certificate.data.sender;
+
+        let mut deposits = vec![];
+        let effects = &response.effects;
+        for object in effects
+            .created
+            .iter()
+            .chain(&effects.mutated)
+            .chain(&effects.unwrapped)
+        {
+            let recipient = match object.owner {
+                Owner::AddressOwner(owner)
+                    if owner != sender && self.addresses.contains(&owner) =>
+                {
+                    owner
+                }
+                _ => continue,
+            };
+            let reference = &object.reference;
+            if !self.state.seen.insert((seq, digest, reference.object_id)) {
+                continue;
+            }
+            let coin =
+                match past_coin(self.read_api, reference.object_id, reference.version).await? {
+                    Some(coin) => coin,
+                    None => continue,
+                };
+            deposits.push(Deposit {
+                tx_digest: digest,
+                seq,
+                timestamp_ms: response.timestamp_ms,
+                sender,
+                recipient,
+                coin_object_id: reference.object_id,
+                version: reference.version,
+                coin_type: coin.coin_type,
+                amount: coin.balance,
+            });
+        }
+        Ok(deposits)
+    }
+}
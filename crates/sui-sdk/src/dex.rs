@@ -0,0 +1,342 @@
+// Copyright (c) 2022, Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! A uniform swap API over DEXes, for trading bots.
+//!
+//! A [DexAdapter] loads the pools of a DEX trading one coin type for another as
+//! [PoolQuoter]s, which quote amounts from the loaded pool state and build swap calls. The
+//! [SwapRouter] splits an order across the pools of all its adapters for the best total
+//! output. This version of the protocol has no programmable transactions: a routed swap is
+//! a batch transaction of one swap call per pool, each swapping a coin of the amount routed
+//! to that pool, see [Route::amounts_in].
+//!
+//! [ConstantProductAdapter] supports pools with the layout of the `defi::pool` example: a
+//! shared `Pool<P, T>` holding SUI and `T`, with a fee in basis points.
+
+use std::str::FromStr;
+
+use anyhow::anyhow;
+use async_trait::async_trait;
+use move_core_types::identifier::Identifier;
+use move_core_types::language_storage::TypeTag;
+
+use sui_types::base_types::{ObjectID, ObjectRef, SuiAddress};
+use sui_types::messages::{
+    CallArg, MoveCall, ObjectArg, SingleTransactionKind, TransactionData, TransactionKind,
+};
+use sui_types::parse_sui_struct_tag;
+
+use crate::coin_type::CoinType;
+use crate::error::{Error, SuiRpcResult};
+use crate::ReadApi;
+
+/// A pool of a DEX, with the state it was loaded with.
+pub trait PoolQuoter: Send + Sync {
+    fn pool_id(&self) -> ObjectID;
+
+    /// The amount of the output coin received for `amount_in` of the input coin.
+    fn quote(&self, amount_in: u64) -> u64;
+
+    /// The call swapping the whole of `coin`, of the input coin type, for the output coin,
+    /// sent to the transaction sender.
+    fn swap_call(&self, coin: ObjectRef) -> MoveCall;
+}
+
+#[async_trait]
+pub trait DexAdapter: Send + Sync {
+    fn name(&self) -> &str;
+
+    /// The pools of this DEX swapping `input` coins for `output` coins.
+    async fn pools(
+        &self,
+        read_api: &ReadApi,
+        input: &CoinType,
+        output: &CoinType,
+    ) -> SuiRpcResult<Vec<Box<dyn PoolQuoter>>>;
+}
+
+/// The output of a constant product pool holding `input_reserve` and `output_reserve`, with
+/// a fee of `fee_bps` basis points of the input.
+pub fn constant_product_output(
+    amount_in: u64,
+    input_reserve: u64,
+    output_reserve: u64,
+    fee_bps: u64,
+) -> u64 {
+    let amount_in = amount_in as u128 * 10_000u128.saturating_sub(fee_bps as u128);
+    let denominator = input_reserve as u128 * 10_000 + amount_in;
+    if denominator == 0 {
+        return 0;
+    }
+    (amount_in * output_reserve as u128 / denominator) as u64
+}
+
+/// Pools with the layout of the `defi::pool` example, published in `package`.
+pub struct ConstantProductAdapter {
+    name: String,
+    package: ObjectID,
+    module: String,
+    pools: Vec<ObjectID>,
+}
+
+impl ConstantProductAdapter {
+    /// The pools `pools` of the `pool` module of `package`.
+    pub fn new(name: impl Into<String>, package: ObjectID, pools: Vec<ObjectID>) -> Self {
+        Self {
+            name: name.into(),
+            package,
+            module: "pool".to_string(),
+            pools,
+        }
+    }
+
+    /// The name of the pool module, if not `pool`.
+    pub fn with_module(mut self, module: impl Into<String>) -> Self {
+        self.module = module.into();
+        self
+    }
+}
+
+#[async_trait]
+impl DexAdapter for ConstantProductAdapter {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    async fn pools(
+        &self,
+        read_api: &ReadApi,
+        input: &CoinType,
+        output: &CoinType,
+    ) -> SuiRpcResult<Vec<Box<dyn PoolQuoter>>> {
+        let package = read_api
+            .get_object(self.package)
+            .await?
+            .object()?
+            .reference
+            .to_object_ref();
+        let mut pools: Vec<Box<dyn PoolQuoter>> = vec![];
+        for pool_id in &self.pools {
+            let object = read_api.get_parsed_object(*pool_id).await?.into_object()?;
+            let move_object = object
+                .data
+                .try_as_move()
+                .ok_or_else(|| anyhow!("Pool [{pool_id}] is not a Move object."))?;
+            let tag = parse_sui_struct_tag(&move_object.type_)?;
+            if ObjectID::from(tag.address) != self.package
+                || tag.module.as_str() != self.module
+                || tag.type_params.len() != 2
+            {
+                return Err(Error::Other(anyhow!(
+                    "Object [{pool_id}] is not a pool of [{}].",
+                    self.name
+                )));
+            }
+            let token = match &tag.type_params[1] {
+                TypeTag::Struct(token) => CoinType::new(token.clone()),
+                _ => continue,
+            };
+            let fields = move_object.fields.clone().to_json_value()?;
+            let field = |name: &str| {
+                fields[name]
+                    .as_u64()
+                    .ok_or_else(|| anyhow!("Cannot read [{name}] of pool [{pool_id}]."))
+            };
+            let (sui, token_reserve) = (field("sui")?, field("token")?);
+            let (function, input_reserve, output_reserve) = if input.is_sui() && *output == token {
+                ("swap_sui_", sui, token_reserve)
+            } else if *input == token && output.is_sui() {
+                ("swap_token_", token_reserve, sui)
+            } else {
+                continue;
+            };
+            pools.push(Box::new(ConstantProductPool {
+                pool_id: *pool_id,
+                package,
+                module: Identifier::from_str(&self.module)?,
+                function: Identifier::from_str(function)?,
+                type_args: tag.type_params.clone(),
+                input_reserve,
+                output_reserve,
+                fee_bps: field("fee_percent")?,
+            }));
+        }
+        Ok(pools)
+    }
+}
+
+struct ConstantProductPool {
+    pool_id: ObjectID,
+    package: ObjectRef,
+    module: Identifier,
+    function: Identifier,
+    type_args: Vec<TypeTag>,
+    input_reserve: u64,
+    output_reserve: u64,
+    fee_bps: u64,
+}
+
+impl PoolQuoter for ConstantProductPool {
+    fn pool_id(&self) -> ObjectID {
+        self.pool_id
+    }
+
+    fn quote(&self, amount_in: u64) -> u64 {
+        constant_product_output(
+            amount_in,
+            self.input_reserve,
+            self.output_reserve,
+            self.fee_bps,
+        )
+    }
+
+    fn swap_call(&self, coin: ObjectRef) -> MoveCall {
+        MoveCall {
+            package: self.package,
+            module: self.module.clone(),
+            function: self.function.clone(),
+            type_arguments: self.type_args.clone(),
+            arguments: vec![
+                CallArg::Object(ObjectArg::SharedObject(self.pool_id)),
+                CallArg::Object(ObjectArg::ImmOrOwnedObject(coin)),
+            ],
+        }
+    }
+}
+
+/// Split `amount_in` across `pools` for the best total output, in `steps` parts each given to
+/// the pool paying the most for it. Returns the amount routed to each pool.
+pub fn split_order(pools: &[Box<dyn PoolQuoter>], amount_in: u64, steps: u64) -> Vec<u64> {
+    let mut amounts = vec![0u64; pools.len()];
+    if pools.is_empty() {
+        return amounts;
+    }
+    let steps = steps.clamp(1, amount_in.max(1));
+    for step in 0..steps {
+        // The last part takes the remainder.
+        let part = if step + 1 == steps {
+            amount_in - amount_in / steps * (steps - 1)
+        } else {
+            amount_in / steps
+        };
+        let best = (0..pools.len())
+            .max_by_key(|&i| {
+                pools[i]
+                    .quote(amounts[i] + part)
+                    .saturating_sub(pools[i].quote(amounts[i]))
+            })
+            .unwrap_or_default();
+        amounts[best] += part;
+    }
+    amounts
+}
+
+/// A swap split across pools.
+pub struct Route {
+    legs: Vec<(Box<dyn PoolQuoter>, u64)>,
+}
+
+impl Route {
+    /// The amount of the input coin each pool of the route takes, in the order of the coins
+    /// expected by [Route::swap_transaction].
+    pub fn amounts_in(&self) -> Vec<u64> {
+        self.legs.iter().map(|(_, amount)| *amount).collect()
+    }
+
+    pub fn pools(&self) -> Vec<ObjectID> {
+        self.legs.iter().map(|(pool, _)| pool.pool_id()).collect()
+    }
+
+    /// The quoted output of the route, before other swaps change the pools.
+    pub fn amount_out(&self) -> u64 {
+        self.legs
+            .iter()
+            .map(|(pool, amount)| pool.quote(*amount))
+            .sum()
+    }
+
+    /// The transaction swapping `coins`, holding the amounts of [Route::amounts_in] in order,
+    /// e.g. split off a larger coin beforehand.
+    pub fn swap_transaction(
+        &self,
+        signer: SuiAddress,
+        coins: &[ObjectRef],
+        gas: ObjectRef,
+        gas_budget: u64,
+    ) -> SuiRpcResult<TransactionData> {
+        if coins.len() != self.legs.len() {
+            return Err(Error::Other(anyhow!(
+                "The route swaps {} coins, {} were given.",
+                self.legs.len(),
+                coins.len()
+            )));
+        }
+        let mut calls = self
+            .legs
+            .iter()
+            .zip(coins)
+            .map(|((pool, _), coin)| SingleTransactionKind::Call(pool.swap_call(*coin)))
+            .collect::<Vec<_>>();
+        let kind = if calls.len() == 1 {
+            TransactionKind::Single(calls.remove(0))
+        } else {
+            TransactionKind::Batch(calls)
+        };
+        Ok(TransactionData::new(kind, signer, gas, gas_budget))
+    }
+}
+
+pub struct SwapRouter {
+    adapters: Vec<Box<dyn DexAdapter>>,
+    steps: u64,
+}
+
+impl Default for SwapRouter {
+    fn default() -> Self {
+        Self {
+            adapters: vec![],
+            steps: 20,
+        }
+    }
+}
+
+impl SwapRouter {
+    pub fn with_adapter(mut self, adapter: impl DexAdapter + 'static) -> Self {
+        self.adapters.push(Box::new(adapter));
+        self
+    }
+
+    /// Number of parts an order is split in across pools. Defaults to 20.
+    pub fn with_steps(mut self, steps: u64) -> Self {
+        self.steps = steps.max(1);
+        self
+    }
+
+    /// The best route swapping `amount_in` of `input` for `output` across the pools of all
+    /// adapters.
+    pub async fn route(
+        &self,
+        read_api: &ReadApi,
+        input: &CoinType,
+        output: &CoinType,
+        amount_in: u64,
+    ) -> SuiRpcResult<Route> {
+        let mut pools = vec![];
+        for adapter in &self.adapters {
+            pools.extend(adapter.pools(read_api, input, output).await?);
+        }
+        if pools.is_empty() {
+            return Err(Error::Other(anyhow!(
+                "No pool swaps [{input}] for [{output}]."
+            )));
+        }
+        let amounts = split_order(&pools, amount_in, self.steps);
+        Ok(Route {
+            legs: pools
+                .into_iter()
+                .zip(amounts)
+                .filter(|(_, amount)| *amount > 0)
+                .collect(),
+        })
+    }
+}
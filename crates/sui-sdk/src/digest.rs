@@ -0,0 +1,41 @@
+// Copyright (c) 2022, Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Transaction digests computed offline, e.g. to register a transaction in a database before
+//! submitting it.
+//!
+//! The digest of a transaction is the SHA3-256 hash of its `SenderSignedData`, the data and
+//! the sender's signature: in this version of the protocol, the digest is only known once
+//! the transaction is signed. Signing is deterministic for Ed25519 and Secp256k1 keys, so
+//! signing the same data with the same key gives the same digest.
+
+use sui_types::base_types::TransactionDigest;
+use sui_types::crypto::{sha3_hash, SignableBytes, Signature, ToFromBytes};
+use sui_types::messages::{SenderSignedData, Transaction, TransactionData};
+use sui_types::sui_serde::{Base64, Encoding};
+
+use crate::error::SuiRpcResult;
+
+/// The digest of `data` signed with `signature`, as returned by the node once executed.
+pub fn transaction_digest(data: &TransactionData, signature: &Signature) -> TransactionDigest {
+    TransactionDigest::new(sha3_hash(&SenderSignedData {
+        data: data.clone(),
+        tx_signature: signature.clone(),
+    }))
+}
+
+pub fn signed_transaction_digest(transaction: &Transaction) -> TransactionDigest {
+    *transaction.digest()
+}
+
+/// The digest of a transaction as exchanged with wallets and the `sui_executeTransaction`
+/// method: the Base64 encoded signable bytes of the data and the Base64 encoded signature,
+/// with its flag and public key.
+pub fn encoded_transaction_digest(
+    tx_bytes: &str,
+    signature: &str,
+) -> SuiRpcResult<TransactionDigest> {
+    let data = TransactionData::from_signable_bytes(&Base64::decode(tx_bytes)?)?;
+    let signature = Signature::from_bytes(&Base64::decode(signature)?)?;
+    Ok(transaction_digest(&data, &signature))
+}
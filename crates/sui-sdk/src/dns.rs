@@ -0,0 +1,201 @@
+// Copyright (c) 2022, Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Name resolution and address family selection of the connections to the nodes, for
+//! deployments with split-horizon DNS or broken IPv6.
+//!
+//! [ConnectOptions] set with [SuiClientBuilder::connect_options] send requests over a
+//! [HyperTransport] resolving host names with a [DnsResolver], by default the system
+//! resolver, and connecting to the addresses in the order of the [IpPreference]. With both
+//! address families allowed, connections race the addresses of both families ("happy
+//! eyeballs"), starting with the preferred family.
+//!
+//! [SuiClientBuilder::connect_options]: crate::SuiClientBuilder::connect_options
+//! [HyperTransport]: crate::transport::HyperTransport
+
+use std::collections::BTreeMap;
+use std::fmt::{Debug, Formatter};
+use std::net::{IpAddr, ToSocketAddrs};
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::anyhow;
+use async_trait::async_trait;
+
+use crate::runtime::{spawn_blocking, Runtime, TokioRuntime};
+
+#[async_trait]
+pub trait DnsResolver: Send + Sync {
+    /// The addresses of `host`.
+    async fn resolve(&self, host: &str) -> anyhow::Result<Vec<IpAddr>>;
+}
+
+/// The resolver of the operating system, whose blocking lookups run with
+/// [Runtime::spawn_blocking].
+#[derive(Debug, Clone)]
+pub struct SystemResolver {
+    runtime: Arc<dyn Runtime>,
+}
+
+impl Default for SystemResolver {
+    fn default() -> Self {
+        Self::new(Arc::new(TokioRuntime))
+    }
+}
+
+impl SystemResolver {
+    pub fn new(runtime: Arc<dyn Runtime>) -> Self {
+        Self { runtime }
+    }
+}
+
+#[async_trait]
+impl DnsResolver for SystemResolver {
+    async fn resolve(&self, host: &str) -> anyhow::Result<Vec<IpAddr>> {
+        let host = host.to_string();
+        let addresses =
+            spawn_blocking(&*self.runtime, move || (host.as_str(), 0).to_socket_addrs()).await??;
+        Ok(addresses.map(|address| address.ip()).collect())
+    }
+}
+
+/// Fixed addresses for some hosts, e.g. the internal address of a node behind a public
+/// name, resolving other hosts with a fallback resolver.
+#[derive(Clone)]
+pub struct StaticResolver {
+    hosts: BTreeMap<String, Vec<IpAddr>>,
+    fallback: Option<Arc<dyn DnsResolver>>,
+}
+
+impl Default for StaticResolver {
+    fn default() -> Self {
+        Self {
+            hosts: BTreeMap::new(),
+            fallback: Some(Arc::new(SystemResolver::default())),
+        }
+    }
+}
+
+impl StaticResolver {
+    pub fn with_host(mut self, host: &str, addresses: impl IntoIterator<Item = IpAddr>) -> Self {
+        self.hosts
+            .entry(host.to_ascii_lowercase())
+            .or_default()
+            .extend(addresses);
+        self
+    }
+
+    /// Resolve the other hosts with `fallback` instead of the system resolver.
+    pub fn with_fallback(mut self, fallback: Arc<dyn DnsResolver>) -> Self {
+        self.fallback = Some(fallback);
+        self
+    }
+
+    /// Fail to resolve the other hosts.
+    pub fn without_fallback(mut self) -> Self {
+        self.fallback = None;
+        self
+    }
+}
+
+impl Debug for StaticResolver {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("StaticResolver")
+            .field("hosts", &self.hosts)
+            .field("fallback", &self.fallback.is_some())
+            .finish()
+    }
+}
+
+#[async_trait]
+impl DnsResolver for StaticResolver {
+    async fn resolve(&self, host: &str) -> anyhow::Result<Vec<IpAddr>> {
+        match (self.hosts.get(&host.to_ascii_lowercase()), &self.fallback) {
+            (Some(addresses), _) => Ok(addresses.clone()),
+            (None, Some(fallback)) => fallback.resolve(host).await,
+            (None, None) => Err(anyhow!("No address for host [{host}].")),
+        }
+    }
+}
+
+/// The address families connections may use, and the one tried first.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum IpPreference {
+    /// In the order resolved.
+    #[default]
+    System,
+    PreferIpv4,
+    PreferIpv6,
+    Ipv4Only,
+    Ipv6Only,
+}
+
+impl IpPreference {
+    /// `addresses` allowed, in the order to try them.
+    pub fn sort(&self, mut addresses: Vec<IpAddr>) -> Vec<IpAddr> {
+        match self {
+            IpPreference::System => {}
+            IpPreference::PreferIpv4 => addresses.sort_by_key(|address| address.is_ipv6()),
+            IpPreference::PreferIpv6 => addresses.sort_by_key(|address| address.is_ipv4()),
+            IpPreference::Ipv4Only => addresses.retain(|address| address.is_ipv4()),
+            IpPreference::Ipv6Only => addresses.retain(|address| address.is_ipv6()),
+        }
+        addresses
+    }
+}
+
+/// How a [HyperTransport](crate::transport::HyperTransport) connects to the nodes.
+#[derive(Clone)]
+pub struct ConnectOptions {
+    pub(crate) resolver: Arc<dyn DnsResolver>,
+    pub(crate) ip_preference: IpPreference,
+    pub(crate) happy_eyeballs_timeout: Option<Duration>,
+    pub(crate) connect_timeout: Option<Duration>,
+}
+
+impl Default for ConnectOptions {
+    fn default() -> Self {
+        Self {
+            resolver: Arc::new(SystemResolver::default()),
+            ip_preference: IpPreference::default(),
+            happy_eyeballs_timeout: Some(Duration::from_millis(300)),
+            connect_timeout: None,
+        }
+    }
+}
+
+impl ConnectOptions {
+    pub fn with_resolver(mut self, resolver: Arc<dyn DnsResolver>) -> Self {
+        self.resolver = resolver;
+        self
+    }
+
+    pub fn with_ip_preference(mut self, ip_preference: IpPreference) -> Self {
+        self.ip_preference = ip_preference;
+        self
+    }
+
+    /// Delay before racing a connection to the other address family, or `None` to try the
+    /// addresses one after the other. Defaults to 300 milliseconds.
+    pub fn with_happy_eyeballs_timeout(mut self, timeout: Option<Duration>) -> Self {
+        self.happy_eyeballs_timeout = timeout;
+        self
+    }
+
+    /// Timeout of each connection attempt. Unbounded by default, i.e. up to the request
+    /// timeout.
+    pub fn with_connect_timeout(mut self, timeout: Duration) -> Self {
+        self.connect_timeout = Some(timeout);
+        self
+    }
+}
+
+impl Debug for ConnectOptions {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ConnectOptions")
+            .field("ip_preference", &self.ip_preference)
+            .field("happy_eyeballs_timeout", &self.happy_eyeballs_timeout)
+            .field("connect_timeout", &self.connect_timeout)
+            .finish()
+    }
+}
@@ -0,0 +1,156 @@
+// Copyright (c) 2022, Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Encryption at rest of the files of the local stores, e.g. the objects and balances of a
+//! [LocalStore] and the cursors of a [FileCursorStore], which reveal the holdings and
+//! activity of the wallet on a shared machine.
+//!
+//! A [StoreCipher] derives its keys from a password, usually the one protecting the
+//! keystore, with PBKDF2-HMAC-SHA256 and a random salt kept in every file, and encrypts
+//! with ChaCha20-Poly1305. Stores given a cipher refuse plaintext files, which anyone able to
+//! write them could otherwise substitute; encrypt existing plaintext files once with
+//! [encrypt_file]. The SQLite stores, [SqliteStore] and [SqliteCursorStore], are not
+//! encrypted.
+//!
+//! [LocalStore]: crate::local_store::LocalStore
+//! [FileCursorStore]: crate::cursor_store::FileCursorStore
+//! [SqliteStore]: crate::sqlite_store::SqliteStore
+//! [SqliteCursorStore]: crate::cursor_store::SqliteCursorStore
+
+use std::collections::BTreeMap;
+use std::fmt::{Debug, Formatter};
+use std::path::Path;
+use std::sync::Mutex;
+
+use anyhow::{anyhow, bail};
+use chacha20poly1305::aead::{Aead, KeyInit};
+use chacha20poly1305::{ChaCha20Poly1305, Key, Nonce};
+use hmac::Hmac;
+use rand::rngs::OsRng;
+use rand::RngCore;
+use sha2::Sha256;
+
+use crate::error::{Error, SuiRpcResult};
+
+const MAGIC: &[u8] = b"SUIENC01";
+const SALT_LENGTH: usize = 16;
+const NONCE_LENGTH: usize = 12;
+const HEADER_LENGTH: usize = MAGIC.len() + SALT_LENGTH + NONCE_LENGTH;
+const PBKDF2_ROUNDS: u32 = 100_000;
+
+type Salt = [u8; SALT_LENGTH];
+
+/// Encrypts and decrypts store files with keys derived from a password.
+pub struct StoreCipher {
+    password: Vec<u8>,
+    // The salt of the files written by this cipher.
+    salt: Salt,
+    // Keys derived per salt, as the derivation is deliberately slow.
+    keys: Mutex<BTreeMap<Salt, [u8; 32]>>,
+}
+
+impl Debug for StoreCipher {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("StoreCipher").finish_non_exhaustive()
+    }
+}
+
+impl StoreCipher {
+    pub fn new(password: &str) -> Self {
+        let mut salt = [0u8; SALT_LENGTH];
+        OsRng.fill_bytes(&mut salt);
+        Self {
+            password: password.as_bytes().to_vec(),
+            salt,
+            keys: Mutex::new(BTreeMap::new()),
+        }
+    }
+
+    fn key(&self, salt: &Salt) -> [u8; 32] {
+        *self.keys.lock().unwrap().entry(*salt).or_insert_with(|| {
+            let mut key = [0u8; 32];
+            pbkdf2::pbkdf2::<Hmac<Sha256>>(&self.password, salt, PBKDF2_ROUNDS, &mut key);
+            key
+        })
+    }
+
+    /// Encrypt `plaintext` as `magic || salt || nonce || ciphertext`.
+    pub fn seal(&self, plaintext: &[u8]) -> SuiRpcResult<Vec<u8>> {
+        let mut nonce = [0u8; NONCE_LENGTH];
+        OsRng.fill_bytes(&mut nonce);
+        let ciphertext = ChaCha20Poly1305::new(Key::from_slice(&self.key(&self.salt)))
+            .encrypt(Nonce::from_slice(&nonce), plaintext)
+            .map_err(|_| anyhow!("Cannot encrypt store data."))?;
+        Ok([MAGIC, &self.salt, &nonce, &ciphertext].concat())
+    }
+
+    /// Decrypt data encrypted by [StoreCipher::seal] with the same password.
+    pub fn open(&self, sealed: &[u8]) -> SuiRpcResult<Vec<u8>> {
+        if !is_sealed(sealed) {
+            return Err(Error::Other(anyhow!("Store data is not encrypted.")));
+        }
+        let salt: Salt = sealed[MAGIC.len()..MAGIC.len() + SALT_LENGTH].try_into()?;
+        let nonce = &sealed[MAGIC.len() + SALT_LENGTH..HEADER_LENGTH];
+        ChaCha20Poly1305::new(Key::from_slice(&self.key(&salt)))
+            .decrypt(Nonce::from_slice(nonce), &sealed[HEADER_LENGTH..])
+            .map_err(|_| {
+                Error::Other(anyhow!(
+                    "Cannot decrypt store data, the password may be wrong."
+                ))
+            })
+    }
+}
+
+/// Whether `data` was encrypted by a [StoreCipher].
+pub fn is_sealed(data: &[u8]) -> bool {
+    data.len() >= HEADER_LENGTH && data.starts_with(MAGIC)
+}
+
+/// Encrypt the plaintext store file at `path` with `cipher`, replacing it through a temporary
+/// file. A file already encrypted is left as is, once checked to open with `cipher`.
+pub fn encrypt_file(path: &Path, cipher: &StoreCipher) -> SuiRpcResult<()> {
+    let data = std::fs::read(path)?;
+    if is_sealed(&data) {
+        cipher.open(&data)?;
+        return Ok(());
+    }
+    let temp = path.with_extension("tmp");
+    write_file(&temp, &data, Some(cipher))?;
+    std::fs::rename(&temp, path)?;
+    Ok(())
+}
+
+/// Read the store file at `path`, decrypting it with `cipher` if set. Fails if the file is
+/// encrypted without `cipher` or in plaintext with it.
+pub(crate) fn read_file(path: &Path, cipher: Option<&StoreCipher>) -> anyhow::Result<Vec<u8>> {
+    let data = std::fs::read(path)?;
+    match cipher {
+        Some(cipher) if is_sealed(&data) => cipher.open(&data),
+        Some(_) => {
+            bail!(
+                "Store file [{}] is not encrypted, encrypt it with encrypt_file first.",
+                path.display()
+            )
+        }
+        None if is_sealed(&data) => {
+            bail!(
+                "Store file [{}] is encrypted, open it with a password.",
+                path.display()
+            )
+        }
+        None => Ok(data),
+    }
+}
+
+/// Write `contents` to `path`, encrypted with `cipher` if set.
+pub(crate) fn write_file(
+    path: &Path,
+    contents: &[u8],
+    cipher: Option<&StoreCipher>,
+) -> anyhow::Result<()> {
+    match cipher {
+        Some(cipher) => std::fs::write(path, cipher.seal(contents)?)?,
+        None => std::fs::write(path, contents)?,
+    }
+    Ok(())
+}
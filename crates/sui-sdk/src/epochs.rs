@@ -0,0 +1,98 @@
+// Copyright (c) 2022, Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Notifications of epoch changes, so staking services and verifiers refresh the committee
+//! and gas price they rely on. Protocol versions do not exist in this tree yet, so an epoch
+//! is described by its committee and reference gas price.
+
+use futures::{Stream, StreamExt};
+
+use sui_json_rpc_types::{SuiEvent, SuiEventFilter};
+use sui_types::base_types::SuiAddress;
+use sui_types::event::EventType;
+use sui_types::sui_system_state::SuiSystemState;
+
+use crate::error::{Error, SuiRpcResult};
+use crate::{ReadApi, SuiClient};
+
+/// A validator of the committee of an epoch.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CommitteeMember {
+    pub sui_address: SuiAddress,
+    pub name: String,
+    /// Voting power: the validator's own stake plus its delegated stake.
+    pub stake: u64,
+    pub gas_price: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EpochInfo {
+    pub epoch: u64,
+    pub committee: Vec<CommitteeMember>,
+    pub reference_gas_price: u64,
+}
+
+impl EpochInfo {
+    pub fn new(state: &SuiSystemState) -> Self {
+        Self {
+            epoch: state.epoch,
+            committee: state
+                .validators
+                .active_validators
+                .iter()
+                .map(|validator| CommitteeMember {
+                    sui_address: validator.metadata.sui_address.into(),
+                    name: String::from_utf8_lossy(&validator.metadata.name).into_owned(),
+                    stake: validator.stake_amount + validator.delegation_staking_pool.sui_balance,
+                    gas_price: validator.gas_price,
+                })
+                .collect(),
+            reference_gas_price: state.reference_gas_price,
+        }
+    }
+
+    pub fn total_stake(&self) -> u64 {
+        self.committee.iter().map(|member| member.stake).sum()
+    }
+}
+
+impl ReadApi {
+    /// The current epoch.
+    pub async fn get_epoch_info(&self) -> SuiRpcResult<EpochInfo> {
+        Ok(EpochInfo::new(&self.get_sui_system_state().await?))
+    }
+}
+
+impl SuiClient {
+    /// Stream the new epoch at every epoch change from now on.
+    pub async fn subscribe_epochs(
+        &self,
+    ) -> SuiRpcResult<impl Stream<Item = SuiRpcResult<EpochInfo>> + '_> {
+        let events = self
+            .event_api()
+            .subscribe_event(SuiEventFilter::EventType(EventType::EpochChange))
+            .await?;
+        Ok(events.filter_map(move |event| async move {
+            let epoch = match event {
+                Ok(event) => match event.event {
+                    SuiEvent::EpochChange(epoch) => epoch,
+                    _ => return None,
+                },
+                Err(e) => return Some(Err(e)),
+            };
+            Some(self.epoch_info_after(epoch).await)
+        }))
+    }
+
+    async fn epoch_info_after(&self, epoch: u64) -> SuiRpcResult<EpochInfo> {
+        let info = self.read_api().get_epoch_info().await?;
+        // The event carries the number of the new epoch, the node may not have applied it.
+        if info.epoch < epoch {
+            return Err(Error::Other(anyhow::anyhow!(
+                "Node has not applied epoch [{epoch}] yet, it is at epoch [{}].",
+                info.epoch
+            )));
+        }
+        Ok(info)
+    }
+}
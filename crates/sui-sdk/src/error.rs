@@ -0,0 +1,345 @@
+// Copyright (c) 2022, Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Errors of the client and keystore APIs.
+//!
+//! [Error] distinguishes failures reported by the node ([RpcError], with the JSON-RPC error
+//! code when there is one), keystore failures ([KeystoreError]) and failed transactions
+//! ([ExecutionError]), so callers can match on the kind of failure instead of parsing
+//! messages. The cause of a node error is decoded from its message with [RpcError::kind].
+//! Errors converted to [anyhow::Error] keep their type and can be recovered with
+//! `downcast_ref::<Error>()`.
+
+#[cfg(feature = "client")]
+use jsonrpsee::types::error::CallError;
+use sui_json_rpc_types::SuiExecutionStatus;
+use sui_types::base_types::{ObjectID, SequenceNumber};
+use sui_types::error::SuiError;
+
+pub type SuiRpcResult<T> = Result<T, Error>;
+
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    #[error(transparent)]
+    Rpc(#[from] RpcError),
+    #[error(transparent)]
+    Keystore(#[from] KeystoreError),
+    #[error(transparent)]
+    Execution(#[from] ExecutionError),
+    #[error("Method not supported by {0}.")]
+    Unsupported(&'static str),
+    /// A buffered stream overflowed its capacity, see [crate::backpressure].
+    #[error("Stream buffer overflowed its capacity of {0} items.")]
+    BufferOverflow(usize),
+    /// Two settings of a [SuiClientBuilder](crate::SuiClientBuilder) cannot be combined.
+    #[error("Client setting [{0}] cannot be combined with [{1}].")]
+    IncompatibleSettings(&'static str, &'static str),
+    #[error(transparent)]
+    Other(anyhow::Error),
+}
+
+impl From<anyhow::Error> for Error {
+    fn from(e: anyhow::Error) -> Self {
+        match e.downcast::<Error>() {
+            Ok(e) => e,
+            Err(e) => match e.downcast::<RpcError>() {
+                Ok(e) => Error::Rpc(e),
+                Err(e) => Error::Other(e),
+            },
+        }
+    }
+}
+
+impl Error {
+    /// Whether the node throttled the request, see [RpcError::is_throttled].
+    pub fn is_throttled(&self) -> bool {
+        match self {
+            Error::Rpc(e) => e.is_throttled(),
+            _ => false,
+        }
+    }
+
+    /// The cause of a node error, see [RpcError::kind].
+    pub fn rpc_kind(&self) -> Option<RpcErrorKind> {
+        match self {
+            Error::Rpc(e) => Some(e.kind()),
+            _ => None,
+        }
+    }
+}
+
+/// Errors of the dependencies of the SDK, kept as [Error::Other].
+macro_rules! impl_from_other {
+    ($($error:ty),* $(,)?) => {$(
+        impl From<$error> for Error {
+            fn from(e: $error) -> Self {
+                Error::Other(e.into())
+            }
+        }
+    )*};
+}
+
+impl_from_other!(
+    SuiError,
+    bcs::Error,
+    serde_json::Error,
+    std::io::Error,
+    std::num::ParseIntError,
+    std::num::TryFromIntError,
+    std::array::TryFromSliceError,
+    std::str::Utf8Error,
+    csv::Error,
+    hex::FromHexError,
+    url::ParseError,
+    qrcode::types::QrError,
+    toml::de::Error,
+    serde_yaml::Error,
+    signature::Error,
+    sui_sdk_core::Error,
+    move_binary_format::errors::PartialVMError,
+);
+
+#[cfg(feature = "client")]
+impl_from_other!(reqwest::Error, sqlx::Error);
+
+#[cfg(feature = "client")]
+impl From<jsonrpsee::core::Error> for Error {
+    fn from(e: jsonrpsee::core::Error) -> Self {
+        Error::Rpc(e.into())
+    }
+}
+
+/// An error returned by the node or raised by the transport.
+#[derive(Debug, thiserror::Error)]
+#[error("RPC error{}: {message}", .code.map(|code| format!(" [{code}]")).unwrap_or_default())]
+pub struct RpcError {
+    /// The JSON-RPC error code, if the node answered with an error object.
+    pub code: Option<i32>,
+    /// The HTTP status of the response, if the request was rejected with a non-success
+    /// status.
+    pub http_status: Option<u16>,
+    pub message: String,
+}
+
+/// A response with a non-success HTTP status. [HttpTransport](crate::transport::HttpTransport)s
+/// return it so the status reaches [RpcError::http_status].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, thiserror::Error)]
+#[error("Request failed with HTTP status [{0}].")]
+pub struct HttpStatusError(pub u16);
+
+#[cfg(feature = "client")]
+impl From<jsonrpsee::core::Error> for RpcError {
+    fn from(e: jsonrpsee::core::Error) -> Self {
+        let code = match &e {
+            jsonrpsee::core::Error::Call(CallError::Custom(error)) => Some(error.code()),
+            jsonrpsee::core::Error::Call(CallError::InvalidParams(_)) => Some(-32602),
+            jsonrpsee::core::Error::Call(CallError::Failed(_)) => Some(-32000),
+            _ => None,
+        };
+        let http_status = match &e {
+            jsonrpsee::core::Error::Transport(e) => http_status(e),
+            _ => None,
+        };
+        Self {
+            code,
+            http_status,
+            message: e.to_string(),
+        }
+    }
+}
+
+/// The HTTP status of a transport error of the jsonrpsee client or of a custom transport.
+#[cfg(feature = "client")]
+fn http_status(e: &anyhow::Error) -> Option<u16> {
+    use jsonrpsee::http_client::transport::Error as HttpError;
+    if let Some(HttpStatusError(status)) = e.downcast_ref() {
+        return Some(*status);
+    }
+    match e.downcast_ref() {
+        Some(HttpError::Rejected { status_code }) => Some(*status_code),
+        _ => None,
+    }
+}
+
+impl RpcError {
+    /// Whether the node or a proxy in front of it rejected the request because it is rate
+    /// limiting or overloaded, i.e. answered with HTTP status 429 or 503.
+    pub fn is_throttled(&self) -> bool {
+        matches!(self.http_status, Some(429 | 503))
+    }
+
+    /// The cause of the error, decoded from the message of the node.
+    pub fn kind(&self) -> RpcErrorKind {
+        if self.is_throttled() {
+            return RpcErrorKind::Throttled;
+        }
+        RpcErrorKind::from_message(&self.message)
+    }
+}
+
+/// The cause of an [RpcError]. Nodes report errors of the authorities as messages only, so
+/// the variants are decoded from the message; the original code and message stay available
+/// on the [RpcError].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RpcErrorKind {
+    /// The object does not exist, or not at `version` if set.
+    ObjectNotFound {
+        object_id: ObjectID,
+        version: Option<SequenceNumber>,
+    },
+    TransactionNotFound,
+    /// The gas coin cannot pay the gas budget, or the budget cannot pay the transaction.
+    InsufficientGas {
+        reason: String,
+    },
+    GasBudgetTooHigh {
+        reason: String,
+    },
+    /// A published package failed to deserialize or verify.
+    PackageVerificationFailure {
+        reason: String,
+    },
+    /// An owned object is locked by another transaction.
+    ObjectLocked,
+    /// The validators did not reach a quorum; `errors` holds the error of each validator
+    /// that rejected the transaction, see [RpcErrorKind::from_message] to decode them.
+    QuorumFailure {
+        errors: Vec<String>,
+    },
+    Throttled,
+    Other,
+}
+
+impl RpcErrorKind {
+    pub fn from_message(message: &str) -> Self {
+        const QUORUM_FAILURES: [&str; 2] = [
+            "Failed to achieve quorum between authorities",
+            "Failed to execute certificate on a quorum of validators",
+        ];
+        if QUORUM_FAILURES
+            .iter()
+            .any(|failure| message.contains(failure))
+        {
+            return RpcErrorKind::QuorumFailure {
+                errors: quorum_errors(message),
+            };
+        }
+        if let Some(rest) = after(message, "Could not find the referenced object ") {
+            if let Some(object_id) = rest.split_whitespace().next().and_then(parse_object_id) {
+                let version = after(rest, "at version SequenceNumber(")
+                    .and_then(|version| version.split(')').next())
+                    .and_then(|version| version.parse().ok())
+                    .map(SequenceNumber::from);
+                return RpcErrorKind::ObjectNotFound { object_id, version };
+            }
+        }
+        if message.contains("Could not find the referenced transaction") {
+            return RpcErrorKind::TransactionNotFound;
+        }
+        if let Some(reason) = after(message, "Insufficient gas: ") {
+            return RpcErrorKind::InsufficientGas {
+                reason: reason_str(reason),
+            };
+        }
+        if let Some(reason) = after(message, "Gas budget set higher than max: ") {
+            return RpcErrorKind::GasBudgetTooHigh {
+                reason: reason_str(reason),
+            };
+        }
+        if let Some(reason) = after(message, "Failed to verify the Move module, reason: ") {
+            return RpcErrorKind::PackageVerificationFailure {
+                reason: reason_str(reason),
+            };
+        }
+        if message.contains("already locked by a different transaction") {
+            return RpcErrorKind::ObjectLocked;
+        }
+        RpcErrorKind::Other
+    }
+}
+
+fn after<'a>(message: &'a str, pattern: &str) -> Option<&'a str> {
+    message
+        .find(pattern)
+        .map(|start| &message[start + pattern.len()..])
+}
+
+fn parse_object_id(s: &str) -> Option<ObjectID> {
+    ObjectID::from_hex_literal(s.trim_end_matches(|c: char| !c.is_ascii_hexdigit())).ok()
+}
+
+/// The reason of a `reason: {error:?}.` message, without the quotes and escapes of its debug
+/// representation.
+fn reason_str(reason: &str) -> String {
+    let reason = reason.trim().trim_end_matches('.');
+    serde_json::from_str(reason).unwrap_or_else(|_| reason.to_string())
+}
+
+/// The errors of a quorum failure, listed in the message in their pretty printed debug
+/// representation, one quoted string per line.
+fn quorum_errors(message: &str) -> Vec<String> {
+    message
+        .lines()
+        .map(|line| line.trim().trim_end_matches(','))
+        .filter(|line| line.len() >= 2 && line.starts_with('"') && line.ends_with('"'))
+        .map(|line| {
+            serde_json::from_str(line).unwrap_or_else(|_| line[1..line.len() - 1].to_string())
+        })
+        .collect()
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum KeystoreError {
+    #[error("Invalid mnemonic phrase: {0}")]
+    InvalidMnemonic(String),
+    #[error("Cannot derive key: {0}")]
+    KeyDerivation(String),
+    #[error("Keystore storage error: {0}")]
+    Storage(anyhow::Error),
+    /// The keystore is a [crate::crypto::ReadOnlyKeystore].
+    #[error("The keystore is read-only.")]
+    ReadOnly,
+}
+
+impl KeystoreError {
+    /// The keystore error carried by `error`, or a storage error.
+    pub(crate) fn from_storage(error: anyhow::Error) -> Self {
+        error
+            .downcast::<KeystoreError>()
+            .unwrap_or_else(KeystoreError::Storage)
+    }
+}
+
+/// A transaction that was executed but failed.
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum ExecutionError {
+    /// A Move abort, in the module at `location`, e.g. `0x2::coin`.
+    #[error("Move abort in [{location}] with code [{code}].")]
+    MoveAbort { location: String, code: u64 },
+    #[error("Transaction execution failed: {0}")]
+    Failure(String),
+}
+
+impl ExecutionError {
+    /// The failure of a transaction with status `status`, if it failed.
+    pub fn from_status(status: &SuiExecutionStatus) -> Option<Self> {
+        match status {
+            SuiExecutionStatus::Success => None,
+            SuiExecutionStatus::Failure { error } => Some(match parse_move_abort(error) {
+                Some((location, code)) => ExecutionError::MoveAbort { location, code },
+                None => ExecutionError::Failure(error.clone()),
+            }),
+        }
+    }
+}
+
+/// Parse the debug representation of `ExecutionFailureStatus::MoveAbort`, as reported in
+/// transaction effects.
+fn parse_move_abort(error: &str) -> Option<(String, u64)> {
+    let rest = error.strip_prefix("MoveAbort(ModuleId { address: ")?;
+    let (address, rest) = rest.split_once(", name: Identifier(\"")?;
+    let (name, rest) = rest.split_once("\") }, ")?;
+    let code = rest.strip_suffix(')')?.parse().ok()?;
+    let address = address.trim_start_matches("0x");
+    Some((format!("0x{address}::{name}"), code))
+}
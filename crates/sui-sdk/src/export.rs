@@ -0,0 +1,578 @@
+// Copyright (c) 2022, Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Export transactions, events and balance changes to CSV, JSON or Parquet files, e.g. to
+//! load them with pandas or DuckDB.
+//!
+//! Every row type has a fixed schema, [ExportRow::schema]: columns are only ever appended,
+//! so files written by older versions keep loading with the same queries. Addresses, object
+//! IDs and digests are exported as strings. Parquet export requires the `parquet` feature.
+//!
+//! Balance changes are derived from coin transfer events: they do not include gas fees, and
+//! events do not carry the coin type, which can be looked up by coin object ID.
+
+use std::fs::File;
+use std::ops::Range;
+use std::path::Path;
+
+use anyhow::bail;
+
+use sui_json_rpc::api::EVENT_QUERY_MAX_LIMIT;
+use sui_json_rpc_types::{
+    GatewayTxSeqNumber, SuiEvent, SuiEventEnvelope, SuiExecutionStatus, SuiTransactionKind,
+    SuiTransactionResponse,
+};
+use sui_types::event::TransferType;
+use sui_types::object::Owner;
+
+use crate::error::{Error, SuiRpcResult};
+use crate::indexer::event_keys;
+use crate::SuiClient;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportFormat {
+    Csv,
+    /// An array of objects keyed by column name.
+    Json,
+    #[cfg(feature = "parquet")]
+    Parquet,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ColumnType {
+    Int64,
+    Utf8,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ColumnValue {
+    Int64(Option<i64>),
+    Utf8(Option<String>),
+}
+
+impl ColumnValue {
+    pub(crate) fn int(value: u64) -> Self {
+        ColumnValue::Int64(Some(value as i64))
+    }
+
+    pub(crate) fn text(value: impl ToString) -> Self {
+        ColumnValue::Utf8(Some(value.to_string()))
+    }
+
+    fn to_json(&self) -> serde_json::Value {
+        match self {
+            ColumnValue::Int64(value) => value.map_or(serde_json::Value::Null, Into::into),
+            ColumnValue::Utf8(value) => value.clone().map_or(serde_json::Value::Null, Into::into),
+        }
+    }
+
+    fn to_csv_field(&self) -> String {
+        match self {
+            ColumnValue::Int64(value) => value.map(|v| v.to_string()).unwrap_or_default(),
+            ColumnValue::Utf8(value) => value.clone().unwrap_or_default(),
+        }
+    }
+}
+
+/// A row of an exported table. `values` returns one value per column of `schema`, in the
+/// same order.
+pub trait ExportRow {
+    fn schema() -> &'static [(&'static str, ColumnType)];
+
+    fn values(&self) -> Vec<ColumnValue>;
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TransactionRow {
+    pub seq: GatewayTxSeqNumber,
+    pub digest: String,
+    pub timestamp_ms: Option<u64>,
+    pub sender: String,
+    /// The kinds of the single transactions, e.g. `TransferSui`, separated by `,`.
+    pub kinds: String,
+    pub success: bool,
+    pub computation_cost: u64,
+    pub storage_cost: u64,
+    pub storage_rebate: u64,
+}
+
+impl TransactionRow {
+    pub fn new(seq: GatewayTxSeqNumber, response: &SuiTransactionResponse) -> Self {
+        let certificate = &response.certificate;
+        let gas = &response.effects.gas_used;
+        Self {
+            seq,
+            digest: certificate.transaction_digest.to_string(),
+            timestamp_ms: response.timestamp_ms,
+            sender: certificate.data.sender.to_string(),
+            kinds: certificate
+                .data
+                .transactions
+                .iter()
+                .map(transaction_kind)
+                .collect::<Vec<_>>()
+                .join(","),
+            success: matches!(response.effects.status, SuiExecutionStatus::Success),
+            computation_cost: gas.computation_cost,
+            storage_cost: gas.storage_cost,
+            storage_rebate: gas.storage_rebate,
+        }
+    }
+}
+
+fn transaction_kind(kind: &SuiTransactionKind) -> &'static str {
+    match kind {
+        SuiTransactionKind::TransferObject(_) => "TransferObject",
+        SuiTransactionKind::Pay(_) => "Pay",
+        SuiTransactionKind::Publish(_) => "Publish",
+        SuiTransactionKind::Call(_) => "Call",
+        SuiTransactionKind::TransferSui(_) => "TransferSui",
+        SuiTransactionKind::ChangeEpoch(_) => "ChangeEpoch",
+    }
+}
+
+impl ExportRow for TransactionRow {
+    fn schema() -> &'static [(&'static str, ColumnType)] {
+        &[
+            ("seq", ColumnType::Int64),
+            ("digest", ColumnType::Utf8),
+            ("timestamp_ms", ColumnType::Int64),
+            ("sender", ColumnType::Utf8),
+            ("kinds", ColumnType::Utf8),
+            ("success", ColumnType::Int64),
+            ("computation_cost", ColumnType::Int64),
+            ("storage_cost", ColumnType::Int64),
+            ("storage_rebate", ColumnType::Int64),
+        ]
+    }
+
+    fn values(&self) -> Vec<ColumnValue> {
+        vec![
+            ColumnValue::int(self.seq),
+            ColumnValue::text(&self.digest),
+            ColumnValue::Int64(self.timestamp_ms.map(|t| t as i64)),
+            ColumnValue::text(&self.sender),
+            ColumnValue::text(&self.kinds),
+            ColumnValue::int(self.success as u64),
+            ColumnValue::int(self.computation_cost),
+            ColumnValue::int(self.storage_cost),
+            ColumnValue::int(self.storage_rebate),
+        ]
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct EventRow {
+    pub tx_digest: Option<String>,
+    pub timestamp_ms: u64,
+    /// Position of the event among the events of its transaction.
+    pub event_seq: usize,
+    /// The event variant, e.g. `transferObject`.
+    pub event_type: String,
+    pub package_id: Option<String>,
+    pub module: Option<String>,
+    pub sender: Option<String>,
+    pub recipient: Option<String>,
+    pub object_id: Option<String>,
+    /// The Move type of Move events.
+    pub move_type: Option<String>,
+    /// The fields of Move events, as JSON.
+    pub fields: Option<String>,
+    pub amount: Option<u64>,
+}
+
+impl EventRow {
+    pub fn new(event_seq: usize, envelope: &SuiEventEnvelope) -> Self {
+        let mut row = Self {
+            tx_digest: envelope.tx_digest.map(|digest| digest.to_string()),
+            timestamp_ms: envelope.timestamp,
+            event_seq,
+            ..Default::default()
+        };
+        match &envelope.event {
+            SuiEvent::MoveEvent {
+                package_id,
+                transaction_module,
+                sender,
+                type_,
+                fields,
+                ..
+            } => {
+                row.event_type = "moveEvent".to_string();
+                row.package_id = Some(package_id.to_string());
+                row.module = Some(transaction_module.clone());
+                row.sender = Some(sender.to_string());
+                row.move_type = Some(type_.clone());
+                row.fields = fields
+                    .as_ref()
+                    .and_then(|fields| fields.clone().to_json_value().ok())
+                    .map(|fields| fields.to_string());
+            }
+            SuiEvent::Publish { sender, package_id } => {
+                row.event_type = "publish".to_string();
+                row.package_id = Some(package_id.to_string());
+                row.sender = Some(sender.to_string());
+            }
+            SuiEvent::TransferObject {
+                package_id,
+                transaction_module,
+                sender,
+                recipient,
+                object_id,
+                amount,
+                ..
+            } => {
+                row.event_type = "transferObject".to_string();
+                row.package_id = Some(package_id.to_string());
+                row.module = Some(transaction_module.clone());
+                row.sender = Some(sender.to_string());
+                row.recipient = Some(recipient.to_string());
+                row.object_id = Some(object_id.to_string());
+                row.amount = *amount;
+            }
+            SuiEvent::DeleteObject {
+                package_id,
+                transaction_module,
+                sender,
+                object_id,
+            } => {
+                row.event_type = "deleteObject".to_string();
+                row.package_id = Some(package_id.to_string());
+                row.module = Some(transaction_module.clone());
+                row.sender = Some(sender.to_string());
+                row.object_id = Some(object_id.to_string());
+            }
+            SuiEvent::NewObject {
+                package_id,
+                transaction_module,
+                sender,
+                recipient,
+                object_id,
+            } => {
+                row.event_type = "newObject".to_string();
+                row.package_id = Some(package_id.to_string());
+                row.module = Some(transaction_module.clone());
+                row.sender = Some(sender.to_string());
+                row.recipient = Some(recipient.to_string());
+                row.object_id = Some(object_id.to_string());
+            }
+            SuiEvent::EpochChange(epoch) => {
+                row.event_type = "epochChange".to_string();
+                row.amount = Some(*epoch);
+            }
+            SuiEvent::Checkpoint(seq) => {
+                row.event_type = "checkpoint".to_string();
+                row.amount = Some(*seq);
+            }
+        }
+        row
+    }
+}
+
+impl ExportRow for EventRow {
+    fn schema() -> &'static [(&'static str, ColumnType)] {
+        &[
+            ("tx_digest", ColumnType::Utf8),
+            ("timestamp_ms", ColumnType::Int64),
+            ("event_seq", ColumnType::Int64),
+            ("event_type", ColumnType::Utf8),
+            ("package_id", ColumnType::Utf8),
+            ("module", ColumnType::Utf8),
+            ("sender", ColumnType::Utf8),
+            ("recipient", ColumnType::Utf8),
+            ("object_id", ColumnType::Utf8),
+            ("move_type", ColumnType::Utf8),
+            ("fields", ColumnType::Utf8),
+            ("amount", ColumnType::Int64),
+        ]
+    }
+
+    fn values(&self) -> Vec<ColumnValue> {
+        vec![
+            ColumnValue::Utf8(self.tx_digest.clone()),
+            ColumnValue::int(self.timestamp_ms),
+            ColumnValue::int(self.event_seq as u64),
+            ColumnValue::text(&self.event_type),
+            ColumnValue::Utf8(self.package_id.clone()),
+            ColumnValue::Utf8(self.module.clone()),
+            ColumnValue::Utf8(self.sender.clone()),
+            ColumnValue::Utf8(self.recipient.clone()),
+            ColumnValue::Utf8(self.object_id.clone()),
+            ColumnValue::Utf8(self.move_type.clone()),
+            ColumnValue::Utf8(self.fields.clone()),
+            ColumnValue::Int64(self.amount.map(|a| a as i64)),
+        ]
+    }
+}
+
+/// The change of an address balance by a coin transfer.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BalanceChangeRow {
+    pub tx_digest: Option<String>,
+    pub timestamp_ms: u64,
+    pub address: String,
+    pub coin_object_id: String,
+    /// Negative for the sender, positive for the recipient.
+    pub amount: i64,
+}
+
+impl BalanceChangeRow {
+    /// The balance changes of a coin transfer event, none for other events and transfers to
+    /// the sender itself.
+    pub fn from_event(envelope: &SuiEventEnvelope) -> Vec<Self> {
+        let (sender, recipient, object_id, amount) = match &envelope.event {
+            SuiEvent::TransferObject {
+                sender,
+                recipient: Owner::AddressOwner(recipient),
+                object_id,
+                type_: TransferType::Coin,
+                amount: Some(amount),
+                ..
+            } if sender != recipient => (sender, recipient, object_id, *amount as i64),
+            _ => return vec![],
+        };
+        let change = |address: String, amount| Self {
+            tx_digest: envelope.tx_digest.map(|digest| digest.to_string()),
+            timestamp_ms: envelope.timestamp,
+            address,
+            coin_object_id: object_id.to_string(),
+            amount,
+        };
+        vec![
+            change(sender.to_string(), -amount),
+            change(recipient.to_string(), amount),
+        ]
+    }
+}
+
+impl ExportRow for BalanceChangeRow {
+    fn schema() -> &'static [(&'static str, ColumnType)] {
+        &[
+            ("tx_digest", ColumnType::Utf8),
+            ("timestamp_ms", ColumnType::Int64),
+            ("address", ColumnType::Utf8),
+            ("coin_object_id", ColumnType::Utf8),
+            ("amount", ColumnType::Int64),
+        ]
+    }
+
+    fn values(&self) -> Vec<ColumnValue> {
+        vec![
+            ColumnValue::Utf8(self.tx_digest.clone()),
+            ColumnValue::int(self.timestamp_ms),
+            ColumnValue::text(&self.address),
+            ColumnValue::text(&self.coin_object_id),
+            ColumnValue::Int64(Some(self.amount)),
+        ]
+    }
+}
+
+/// Write `rows` to a new file at `path`.
+pub fn write_rows<R: ExportRow>(rows: &[R], format: ExportFormat, path: &Path) -> SuiRpcResult<()> {
+    let file = File::create(path)?;
+    match format {
+        ExportFormat::Csv => write_csv(rows, file)?,
+        ExportFormat::Json => write_json(rows, file)?,
+        #[cfg(feature = "parquet")]
+        ExportFormat::Parquet => write_parquet(rows, file)?,
+    }
+    Ok(())
+}
+
+fn write_csv<R: ExportRow>(rows: &[R], file: File) -> anyhow::Result<()> {
+    let mut writer = csv::Writer::from_writer(file);
+    writer.write_record(R::schema().iter().map(|(name, _)| name))?;
+    for row in rows {
+        writer.write_record(row.values().iter().map(ColumnValue::to_csv_field))?;
+    }
+    writer.flush()?;
+    Ok(())
+}
+
+fn write_json<R: ExportRow>(rows: &[R], file: File) -> anyhow::Result<()> {
+    let rows = rows
+        .iter()
+        .map(|row| {
+            R::schema()
+                .iter()
+                .zip(row.values())
+                .map(|((name, _), value)| (name.to_string(), value.to_json()))
+                .collect::<serde_json::Map<_, _>>()
+        })
+        .collect::<Vec<_>>();
+    serde_json::to_writer_pretty(std::io::BufWriter::new(file), &rows)?;
+    Ok(())
+}
+
+#[cfg(feature = "parquet")]
+fn write_parquet<R: ExportRow>(rows: &[R], file: File) -> anyhow::Result<()> {
+    use std::sync::Arc;
+
+    use parquet::data_type::{ByteArray, ByteArrayType, Int64Type};
+    use parquet::file::properties::WriterProperties;
+    use parquet::file::writer::SerializedFileWriter;
+    use parquet::schema::parser::parse_message_type;
+
+    let columns = R::schema()
+        .iter()
+        .map(|(name, column_type)| match column_type {
+            ColumnType::Int64 => format!("OPTIONAL INT64 {name};"),
+            ColumnType::Utf8 => format!("OPTIONAL BYTE_ARRAY {name} (UTF8);"),
+        })
+        .collect::<Vec<_>>();
+    let schema = parse_message_type(&format!("message sui {{ {} }}", columns.join(" ")))?;
+    let properties = WriterProperties::builder().build();
+    let mut writer = SerializedFileWriter::new(file, Arc::new(schema), Arc::new(properties))?;
+
+    let values = rows.iter().map(ExportRow::values).collect::<Vec<_>>();
+    let mut row_group = writer.next_row_group()?;
+    let mut index = 0;
+    while let Some(mut column) = row_group.next_column()? {
+        // Definition level 1 marks a present value, 0 a null.
+        let mut levels = Vec::with_capacity(values.len());
+        match R::schema()[index].1 {
+            ColumnType::Int64 => {
+                let mut present = vec![];
+                for row in &values {
+                    match &row[index] {
+                        ColumnValue::Int64(Some(value)) => {
+                            present.push(*value);
+                            levels.push(1);
+                        }
+                        _ => levels.push(0),
+                    }
+                }
+                column
+                    .typed::<Int64Type>()
+                    .write_batch(&present, Some(&levels), None)?;
+            }
+            ColumnType::Utf8 => {
+                let mut present = vec![];
+                for row in &values {
+                    match &row[index] {
+                        ColumnValue::Utf8(Some(value)) => {
+                            present.push(ByteArray::from(value.as_str()));
+                            levels.push(1);
+                        }
+                        _ => levels.push(0),
+                    }
+                }
+                column
+                    .typed::<ByteArrayType>()
+                    .write_batch(&present, Some(&levels), None)?;
+            }
+        }
+        column.close()?;
+        index += 1;
+    }
+    row_group.close()?;
+    writer.close()?;
+    Ok(())
+}
+
+/// Fetches on-chain data and writes it with [write_rows].
+pub struct Exporter<'a> {
+    client: &'a SuiClient,
+    format: ExportFormat,
+}
+
+impl<'a> Exporter<'a> {
+    pub fn new(client: &'a SuiClient, format: ExportFormat) -> Self {
+        Self { client, format }
+    }
+
+    /// Export the transactions with sequence numbers in `range`. Returns the number of rows.
+    pub async fn export_transactions(
+        &self,
+        range: Range<GatewayTxSeqNumber>,
+        path: &Path,
+    ) -> SuiRpcResult<usize> {
+        let read_api = self.client.read_api();
+        let transactions = read_api
+            .get_transactions_in_range(range.start, range.end)
+            .await?;
+        let digests = transactions
+            .iter()
+            .map(|(_, digest)| *digest)
+            .collect::<Vec<_>>();
+        let responses = read_api.multi_get_transactions(&digests).await?;
+        let rows = transactions
+            .iter()
+            .zip(&responses)
+            .map(|((seq, _), response)| TransactionRow::new(*seq, response))
+            .collect::<Vec<_>>();
+        write_rows(&rows, self.format, path)?;
+        Ok(rows.len())
+    }
+
+    /// Export the events with timestamps from `start_time` to `end_time`, in milliseconds.
+    pub async fn export_events(
+        &self,
+        start_time: u64,
+        end_time: u64,
+        path: &Path,
+    ) -> SuiRpcResult<usize> {
+        let events = self.fetch_events(start_time, end_time).await?;
+        let rows = event_keys(&events)
+            .zip(&events)
+            .map(|(key, event)| EventRow::new(key.event_seq, event))
+            .collect::<Vec<_>>();
+        write_rows(&rows, self.format, path)?;
+        Ok(rows.len())
+    }
+
+    /// Export the balance changes of the coin transfers from `start_time` to `end_time`.
+    pub async fn export_balance_changes(
+        &self,
+        start_time: u64,
+        end_time: u64,
+        path: &Path,
+    ) -> SuiRpcResult<usize> {
+        let events = self.fetch_events(start_time, end_time).await?;
+        let rows = events
+            .iter()
+            .flat_map(BalanceChangeRow::from_event)
+            .collect::<Vec<_>>();
+        write_rows(&rows, self.format, path)?;
+        Ok(rows.len())
+    }
+
+    async fn fetch_events(
+        &self,
+        start_time: u64,
+        end_time: u64,
+    ) -> anyhow::Result<Vec<SuiEventEnvelope>> {
+        let mut events: Vec<SuiEventEnvelope> = vec![];
+        let mut start_time = start_time;
+        // Pages start at the timestamp of the last event, inclusive, as more events may share
+        // it; the events of the previous page with that timestamp are skipped.
+        let mut skip = 0;
+        loop {
+            let page = self
+                .client
+                .event_api()
+                .get_events_by_timerange(EVENT_QUERY_MAX_LIMIT, start_time, end_time)
+                .await?;
+            let full = page.len() == EVENT_QUERY_MAX_LIMIT;
+            let last_timestamp = match page.last() {
+                Some(last) => last.timestamp,
+                None => break,
+            };
+            let new_events = page.into_iter().skip(skip).collect::<Vec<_>>();
+            if new_events.is_empty() && full {
+                bail!("More than {EVENT_QUERY_MAX_LIMIT} events at timestamp [{last_timestamp}].");
+            }
+            events.extend(new_events);
+            if !full {
+                break;
+            }
+            skip = events
+                .iter()
+                .rev()
+                .take_while(|event| event.timestamp == last_timestamp)
+                .count();
+            start_time = last_timestamp;
+        }
+        Ok(events)
+    }
+}
@@ -0,0 +1,198 @@
+// Copyright (c) 2022, Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! One subscription for the events or transactions of many addresses, e.g. the deposit
+//! addresses of an exchange, instead of one subscription per address.
+//!
+//! [EventApi::subscribe_addresses] subscribes once to the events involving any of a set of
+//! addresses and tags each event with the watched addresses it involves, as sender or as
+//! recipient. Nodes cannot filter events by recipient, so the subscription also receives
+//! every transfer and new object, and those of other addresses are dropped by the client.
+//! Transactions cannot be filtered at all, so
+//! [EventApi::subscribe_address_transactions] receives every transaction of the node.
+//!
+//! Items arrive in the order of the node, through a buffer bounded as set by the
+//! [BufferConfig], so memory stays bounded whatever the number of addresses.
+
+use std::collections::BTreeSet;
+use std::sync::Arc;
+
+use futures::future::ready;
+use futures::{Stream, StreamExt};
+
+use sui_json_rpc_types::{
+    SuiEvent, SuiEventEnvelope, SuiEventFilter, SuiTransactionFilter, SuiTransactionResponse,
+};
+use sui_types::base_types::SuiAddress;
+use sui_types::event::EventType;
+use sui_types::object::Owner;
+
+use crate::backpressure::{buffered, BufferConfig, StreamMetrics};
+use crate::error::SuiRpcResult;
+use crate::EventApi;
+
+/// An event involving some of the watched addresses.
+#[derive(Debug)]
+pub struct TaggedEvent {
+    /// The watched addresses involved, in order.
+    pub addresses: Vec<SuiAddress>,
+    pub envelope: SuiEventEnvelope,
+}
+
+/// A transaction involving some of the watched addresses.
+#[derive(Debug)]
+pub struct TaggedTransaction {
+    /// The watched addresses involved, in order.
+    pub addresses: Vec<SuiAddress>,
+    pub response: SuiTransactionResponse,
+}
+
+/// The addresses involved in `event`: its sender, and the recipient of a transferred or new
+/// object owned by an address.
+pub fn event_addresses(event: &SuiEvent) -> BTreeSet<SuiAddress> {
+    let (sender, recipient) = match event {
+        SuiEvent::TransferObject {
+            sender, recipient, ..
+        }
+        | SuiEvent::NewObject {
+            sender, recipient, ..
+        } => (sender, Some(recipient)),
+        SuiEvent::MoveEvent { sender, .. }
+        | SuiEvent::Publish { sender, .. }
+        | SuiEvent::DeleteObject { sender, .. } => (sender, None),
+        SuiEvent::EpochChange(_) | SuiEvent::Checkpoint(_) => return BTreeSet::new(),
+    };
+    let mut addresses = BTreeSet::from([*sender]);
+    if let Some(Owner::AddressOwner(recipient)) = recipient {
+        addresses.insert(*recipient);
+    }
+    addresses
+}
+
+/// The addresses involved in a transaction: its sender, and the owners of the objects it
+/// created, mutated or unwrapped.
+pub fn transaction_addresses(response: &SuiTransactionResponse) -> BTreeSet<SuiAddress> {
+    let effects = &response.effects;
+    effects
+        .created
+        .iter()
+        .chain(&effects.mutated)
+        .chain(&effects.unwrapped)
+        .filter_map(|object| match object.owner {
+            Owner::AddressOwner(owner) => Some(owner),
+            _ => None,
+        })
+        .chain([response.certificate.data.sender])
+        .collect()
+}
+
+/// The subscription filter of the events involving `addresses`, before the transfers to
+/// other addresses are dropped.
+pub fn address_filter(addresses: &BTreeSet<SuiAddress>) -> SuiEventFilter {
+    SuiEventFilter::Any(
+        addresses
+            .iter()
+            .map(|address| SuiEventFilter::SenderAddress(*address))
+            .chain([
+                SuiEventFilter::EventType(EventType::TransferObject),
+                SuiEventFilter::EventType(EventType::NewObject),
+            ])
+            .collect(),
+    )
+}
+
+/// Tag the events of `events` with the addresses of `addresses` they involve, dropping the
+/// events involving none.
+pub fn tag_events<S>(
+    events: S,
+    addresses: BTreeSet<SuiAddress>,
+) -> impl Stream<Item = SuiRpcResult<TaggedEvent>>
+where
+    S: Stream<Item = SuiRpcResult<SuiEventEnvelope>>,
+{
+    let addresses = Arc::new(addresses);
+    events.filter_map(move |event| {
+        ready(match event {
+            Ok(envelope) => {
+                let involved = watched(&addresses, event_addresses(&envelope.event));
+                (!involved.is_empty()).then(|| {
+                    Ok(TaggedEvent {
+                        addresses: involved,
+                        envelope,
+                    })
+                })
+            }
+            Err(e) => Some(Err(e)),
+        })
+    })
+}
+
+/// Tag the transactions of `transactions` with the addresses of `addresses` they involve,
+/// dropping the transactions involving none.
+pub fn tag_transactions<S>(
+    transactions: S,
+    addresses: BTreeSet<SuiAddress>,
+) -> impl Stream<Item = SuiRpcResult<TaggedTransaction>>
+where
+    S: Stream<Item = SuiRpcResult<SuiTransactionResponse>>,
+{
+    let addresses = Arc::new(addresses);
+    transactions.filter_map(move |transaction| {
+        ready(match transaction {
+            Ok(response) => {
+                let involved = watched(&addresses, transaction_addresses(&response));
+                (!involved.is_empty()).then(|| {
+                    Ok(TaggedTransaction {
+                        addresses: involved,
+                        response,
+                    })
+                })
+            }
+            Err(e) => Some(Err(e)),
+        })
+    })
+}
+
+fn watched(watched: &BTreeSet<SuiAddress>, involved: BTreeSet<SuiAddress>) -> Vec<SuiAddress> {
+    involved
+        .into_iter()
+        .filter(|address| watched.contains(address))
+        .collect()
+}
+
+impl EventApi {
+    /// Stream the events involving any of `addresses`, tagged with the addresses involved.
+    pub async fn subscribe_addresses(
+        &self,
+        addresses: impl IntoIterator<Item = SuiAddress>,
+        config: BufferConfig,
+    ) -> SuiRpcResult<(
+        impl Stream<Item = SuiRpcResult<TaggedEvent>>,
+        Arc<StreamMetrics>,
+    )> {
+        let addresses = addresses.into_iter().collect::<BTreeSet<_>>();
+        let events = self.subscribe_event(address_filter(&addresses)).await?;
+        Ok(buffered(tag_events(events, addresses), config, &self.1))
+    }
+
+    /// Stream the transactions involving any of `addresses`, tagged with the addresses
+    /// involved.
+    pub async fn subscribe_address_transactions(
+        &self,
+        addresses: impl IntoIterator<Item = SuiAddress>,
+        config: BufferConfig,
+    ) -> SuiRpcResult<(
+        impl Stream<Item = SuiRpcResult<TaggedTransaction>>,
+        Arc<StreamMetrics>,
+    )> {
+        let addresses = addresses.into_iter().collect();
+        let transactions = self
+            .subscribe_transaction(SuiTransactionFilter::Any)
+            .await?;
+        Ok(buffered(
+            tag_transactions(transactions, addresses),
+            config,
+            &self.1,
+        ))
+    }
+}
@@ -0,0 +1,193 @@
+// Copyright (c) 2022, Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Aggregation of the gas spent by transactions, e.g. for the cost dashboards of dApp
+//! operators.
+//!
+//! Nodes in this tree do not serve checkpoints, so transactions are selected by sequence
+//! number, by timestamp, or by sender. A [FeeReport] sums the gas costs of the selected
+//! transactions in total, per sender and per Move function.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::ops::Range;
+
+use sui_json_rpc_types::{
+    GatewayTxSeqNumber, SuiGasCostSummary, SuiTransactionKind, SuiTransactionResponse,
+};
+use sui_types::base_types::{SuiAddress, TransactionDigest};
+
+use crate::error::SuiRpcResult;
+use crate::SuiClient;
+
+/// Number of transactions fetched per request, below the maximum range size of the node.
+const PAGE_SIZE: u64 = 1000;
+
+/// The gas costs of a set of transactions.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct GasSpend {
+    pub transactions: u64,
+    pub computation_cost: u64,
+    pub storage_cost: u64,
+    pub storage_rebate: u64,
+}
+
+impl GasSpend {
+    fn add(&mut self, gas: &SuiGasCostSummary) {
+        self.transactions += 1;
+        self.computation_cost += gas.computation_cost;
+        self.storage_cost += gas.storage_cost;
+        self.storage_rebate += gas.storage_rebate;
+    }
+
+    /// Gas charged, before storage rebates.
+    pub fn gas_used(&self) -> u64 {
+        self.computation_cost + self.storage_cost
+    }
+
+    /// Gas charged after storage rebates. Negative if rebates exceed the charges.
+    pub fn net_gas_usage(&self) -> i128 {
+        self.gas_used() as i128 - self.storage_rebate as i128
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FeeReport {
+    pub total: GasSpend,
+    pub by_sender: BTreeMap<SuiAddress, GasSpend>,
+    /// Spend of the transactions calling each Move function, keyed by
+    /// `package::module::function`. A batch transaction calling several functions counts
+    /// towards each of them, so these do not add up to the total.
+    pub by_function: BTreeMap<String, GasSpend>,
+}
+
+impl FeeReport {
+    /// Add the gas cost of a transaction from `sender` calling `functions`.
+    pub fn record(
+        &mut self,
+        sender: SuiAddress,
+        functions: &BTreeSet<String>,
+        gas: &SuiGasCostSummary,
+    ) {
+        self.total.add(gas);
+        self.by_sender.entry(sender).or_default().add(gas);
+        for function in functions {
+            self.by_function
+                .entry(function.clone())
+                .or_default()
+                .add(gas);
+        }
+    }
+
+    pub fn add(&mut self, response: &SuiTransactionResponse) {
+        let data = &response.certificate.data;
+        let functions = data
+            .transactions
+            .iter()
+            .filter_map(|kind| match kind {
+                SuiTransactionKind::Call(call) => Some(format!(
+                    "{}::{}::{}",
+                    call.package.object_id, call.module, call.function
+                )),
+                _ => None,
+            })
+            .collect();
+        self.record(data.sender, &functions, &response.effects.gas_used);
+    }
+}
+
+pub struct FeeAnalytics<'a> {
+    client: &'a SuiClient,
+}
+
+impl<'a> FeeAnalytics<'a> {
+    pub fn new(client: &'a SuiClient) -> Self {
+        Self { client }
+    }
+
+    /// Gas spent by the transactions with sequence numbers in `range`.
+    pub async fn for_range(&self, range: Range<GatewayTxSeqNumber>) -> SuiRpcResult<FeeReport> {
+        let mut report = FeeReport::default();
+        let mut start = range.start;
+        while start < range.end {
+            let end = (start + PAGE_SIZE).min(range.end);
+            for response in self.fetch_range(start, end).await? {
+                report.add(&response);
+            }
+            start = end;
+        }
+        Ok(report)
+    }
+
+    /// Gas spent by the transactions executed from `start_time` to `end_time`, in
+    /// milliseconds since epoch. Transactions are scanned backwards from the latest one, so
+    /// recent windows are cheapest to aggregate.
+    pub async fn for_time_range(&self, start_time: u64, end_time: u64) -> SuiRpcResult<FeeReport> {
+        let mut report = FeeReport::default();
+        let mut end = self
+            .client
+            .read_api()
+            .get_total_transaction_number()
+            .await?;
+        while end > 0 {
+            let start = end.saturating_sub(PAGE_SIZE);
+            let mut reached_start = false;
+            for response in self.fetch_range(start, end).await? {
+                match response.timestamp_ms {
+                    Some(timestamp) if timestamp < start_time => reached_start = true,
+                    Some(timestamp) if timestamp < end_time => report.add(&response),
+                    _ => {}
+                }
+            }
+            if reached_start {
+                break;
+            }
+            end = start;
+        }
+        Ok(report)
+    }
+
+    /// Gas spent by the transactions sent by `addresses`.
+    pub async fn for_senders(&self, addresses: &[SuiAddress]) -> SuiRpcResult<FeeReport> {
+        let mut report = FeeReport::default();
+        let full_node_api = self.client.full_node_api();
+        for address in addresses {
+            let digests = full_node_api
+                .get_transactions_from_addr(*address)
+                .await?
+                .into_iter()
+                .map(|(_, digest)| digest)
+                .collect::<Vec<_>>();
+            for response in self.fetch(&digests).await? {
+                report.add(&response);
+            }
+        }
+        Ok(report)
+    }
+
+    async fn fetch_range(
+        &self,
+        start: GatewayTxSeqNumber,
+        end: GatewayTxSeqNumber,
+    ) -> anyhow::Result<Vec<SuiTransactionResponse>> {
+        let digests = self
+            .client
+            .read_api()
+            .get_transactions_in_range(start, end)
+            .await?
+            .into_iter()
+            .map(|(_, digest)| digest)
+            .collect::<Vec<_>>();
+        self.fetch(&digests).await
+    }
+
+    async fn fetch(
+        &self,
+        digests: &[TransactionDigest],
+    ) -> anyhow::Result<Vec<SuiTransactionResponse>> {
+        Ok(self
+            .client
+            .read_api()
+            .multi_get_transactions(digests)
+            .await?)
+    }
+}
@@ -0,0 +1,197 @@
+// Copyright (c) 2022, Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Follow the totally ordered log of transactions executed by a full node.
+//!
+//! This tree does not expose checkpoints over RPC yet; the node's transaction sequence
+//! numbers give the same guarantees a checkpoint stream would for a single node: every
+//! transaction is delivered exactly once, in execution order, and a missing sequence number
+//! is detected as a gap.
+//!
+//! The node is trusted: the only check of a delivered transaction is that its certificate and
+//! effects carry the digest listed at its sequence number. Signatures and certificates are
+//! not verified.
+
+use std::collections::VecDeque;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+
+use anyhow::bail;
+use async_trait::async_trait;
+use futures::{stream, Stream};
+
+use sui_json_rpc_types::{GatewayTxSeqNumber, SuiTransactionResponse};
+use sui_types::base_types::TransactionDigest;
+
+use crate::cancellation::{cancellable, CancellationToken};
+use crate::error::{Error, SuiRpcResult};
+use crate::ReadApi;
+
+/// A transaction delivered by the [TransactionFollower], together with its effects and
+/// events.
+#[derive(Debug, Clone)]
+pub struct FollowedTransaction {
+    pub seq: GatewayTxSeqNumber,
+    pub digest: TransactionDigest,
+    pub response: SuiTransactionResponse,
+}
+
+/// Persists the sequence number of the next transaction to process.
+#[async_trait]
+pub trait SequenceStore: Send + Sync {
+    async fn load(&self) -> SuiRpcResult<Option<GatewayTxSeqNumber>>;
+
+    async fn save(&self, next: GatewayTxSeqNumber) -> SuiRpcResult<()>;
+}
+
+#[derive(Default)]
+pub struct InMemorySequenceStore(AtomicU64);
+
+#[async_trait]
+impl SequenceStore for InMemorySequenceStore {
+    async fn load(&self) -> SuiRpcResult<Option<GatewayTxSeqNumber>> {
+        Ok(Some(self.0.load(Ordering::SeqCst)))
+    }
+
+    async fn save(&self, next: GatewayTxSeqNumber) -> SuiRpcResult<()> {
+        self.0.store(next, Ordering::SeqCst);
+        Ok(())
+    }
+}
+
+/// Keeps the sequence number in a plain text file, replaced through a temporary file on
+/// every save.
+pub struct FileSequenceStore(pub PathBuf);
+
+#[async_trait]
+impl SequenceStore for FileSequenceStore {
+    async fn load(&self) -> SuiRpcResult<Option<GatewayTxSeqNumber>> {
+        if !self.0.exists() {
+            return Ok(None);
+        }
+        Ok(Some(std::fs::read_to_string(&self.0)?.trim().parse()?))
+    }
+
+    async fn save(&self, next: GatewayTxSeqNumber) -> SuiRpcResult<()> {
+        let temp = self.0.with_extension("tmp");
+        std::fs::write(&temp, next.to_string())?;
+        std::fs::rename(&temp, &self.0)?;
+        Ok(())
+    }
+}
+
+pub struct TransactionFollower<'a, S> {
+    read_api: &'a ReadApi,
+    store: S,
+    batch_size: u64,
+    poll_interval: Duration,
+    cancellation: CancellationToken,
+}
+
+struct FollowerState<'a, S> {
+    follower: TransactionFollower<'a, S>,
+    next: Option<GatewayTxSeqNumber>,
+    buffer: VecDeque<FollowedTransaction>,
+}
+
+impl<'a, S: SequenceStore + 'a> TransactionFollower<'a, S> {
+    pub fn new(read_api: &'a ReadApi, store: S) -> Self {
+        Self {
+            read_api,
+            store,
+            batch_size: 100,
+            poll_interval: Duration::from_secs(1),
+            cancellation: CancellationToken::new(),
+        }
+    }
+
+    pub fn with_batch_size(mut self, batch_size: u64) -> Self {
+        self.batch_size = batch_size;
+        self
+    }
+
+    pub fn with_poll_interval(mut self, poll_interval: Duration) -> Self {
+        self.poll_interval = poll_interval;
+        self
+    }
+
+    /// End the stream when `cancellation` is cancelled.
+    pub fn with_cancellation(mut self, cancellation: CancellationToken) -> Self {
+        self.cancellation = cancellation;
+        self
+    }
+
+    /// Stream transactions from the stored sequence number onwards. The stream only ends when
+    /// cancelled; it polls the node for new transactions once it has caught up.
+    ///
+    /// Progress is saved each time a new batch is requested, i.e. once every transaction of
+    /// the previous batch has been taken from the stream, so after a restart the follower
+    /// resumes at the start of the batch that was in flight.
+    pub fn stream(self) -> impl Stream<Item = SuiRpcResult<FollowedTransaction>> + 'a {
+        let cancellation = self.cancellation.clone();
+        let state = FollowerState {
+            follower: self,
+            next: None,
+            buffer: VecDeque::new(),
+        };
+        let transactions = stream::try_unfold(state, |mut state| async move {
+            let item = state.next_transaction().await?;
+            Ok::<_, Error>(Some((item, state)))
+        });
+        cancellable(transactions, cancellation)
+    }
+}
+
+impl<'a, S: SequenceStore> FollowerState<'a, S> {
+    async fn next_transaction(&mut self) -> anyhow::Result<FollowedTransaction> {
+        let follower = &self.follower;
+        loop {
+            if let Some(transaction) = self.buffer.pop_front() {
+                let digest = transaction.digest;
+                let response = &transaction.response;
+                if response.certificate.transaction_digest != digest
+                    || response.effects.transaction_digest != digest
+                {
+                    bail!("Node returned a different transaction for digest [{digest}].");
+                }
+                return Ok(transaction);
+            }
+
+            let next = match self.next {
+                Some(next) => next,
+                None => follower.store.load().await?.unwrap_or_default(),
+            };
+            follower.store.save(next).await?;
+            self.next = Some(next);
+
+            let batch = follower
+                .read_api
+                .get_transactions_in_range(next, next + follower.batch_size)
+                .await?;
+            if batch.is_empty() {
+                follower
+                    .read_api
+                    .runtime()
+                    .sleep(follower.poll_interval)
+                    .await;
+                continue;
+            }
+            for (expected, (seq, _)) in (next..).zip(&batch) {
+                if *seq != expected {
+                    bail!("Gap in the transaction sequence: expected [{expected}], got [{seq}].");
+                }
+            }
+            let digests = batch.iter().map(|(_, digest)| *digest).collect::<Vec<_>>();
+            let responses = follower.read_api.multi_get_transactions(&digests).await?;
+            self.next = Some(next + batch.len() as u64);
+            self.buffer.extend(batch.into_iter().zip(responses).map(
+                |((seq, digest), response)| FollowedTransaction {
+                    seq,
+                    digest,
+                    response,
+                },
+            ));
+        }
+    }
+}
@@ -0,0 +1,237 @@
+// Copyright (c) 2022, Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Proptest strategies for the types the SDK encodes, available with the `fuzzing` feature.
+
+use move_core_types::account_address::AccountAddress;
+use move_core_types::identifier::Identifier;
+use move_core_types::language_storage::{StructTag, TypeTag};
+use move_core_types::value::{MoveStruct, MoveStructLayout, MoveTypeLayout, MoveValue};
+use proptest::collection::vec;
+use proptest::prelude::*;
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+
+use sui_types::base_types::{
+    ObjectDigest, ObjectID, ObjectRef, SequenceNumber, SuiAddress, SUI_ADDRESS_LENGTH,
+};
+use sui_types::crypto::{get_key_pair_from_rng, AccountKeyPair, Signature};
+use sui_types::messages::{
+    CallArg, MoveCall, ObjectArg, Pay, SingleTransactionKind, Transaction, TransactionData,
+    TransactionKind, TransferObject, TransferSui,
+};
+
+pub fn arb_sui_address() -> impl Strategy<Value = SuiAddress> {
+    any::<[u8; SUI_ADDRESS_LENGTH]>().prop_map(|bytes| SuiAddress::try_from(&bytes[..]).unwrap())
+}
+
+pub fn arb_object_id() -> impl Strategy<Value = ObjectID> {
+    any::<[u8; ObjectID::LENGTH]>().prop_map(ObjectID::from)
+}
+
+pub fn arb_object_ref() -> impl Strategy<Value = ObjectRef> {
+    (arb_object_id(), any::<u64>(), any::<[u8; 32]>()).prop_map(|(id, version, digest)| {
+        (id, SequenceNumber::from(version), ObjectDigest::new(digest))
+    })
+}
+
+pub fn arb_identifier() -> impl Strategy<Value = Identifier> {
+    "[a-zA-Z][a-zA-Z0-9_]{0,31}".prop_map(|s| Identifier::new(s).unwrap())
+}
+
+pub fn arb_type_tag() -> impl Strategy<Value = TypeTag> {
+    let leaf = prop_oneof![
+        Just(TypeTag::Bool),
+        Just(TypeTag::U8),
+        Just(TypeTag::U64),
+        Just(TypeTag::U128),
+        Just(TypeTag::Address),
+        Just(TypeTag::Signer),
+    ];
+    leaf.prop_recursive(4, 16, 4, |inner| {
+        prop_oneof![
+            inner.clone().prop_map(|tag| TypeTag::Vector(Box::new(tag))),
+            (
+                any::<[u8; AccountAddress::LENGTH]>(),
+                arb_identifier(),
+                arb_identifier(),
+                vec(inner, 0..3),
+            )
+                .prop_map(|(address, module, name, type_params)| {
+                    TypeTag::Struct(StructTag {
+                        address: AccountAddress::new(address),
+                        module,
+                        name,
+                        type_params,
+                    })
+                }),
+        ]
+    })
+}
+
+/// A value layout, as needed to decode BCS encoded Move values.
+pub fn arb_move_type_layout() -> impl Strategy<Value = MoveTypeLayout> {
+    let leaf = prop_oneof![
+        Just(MoveTypeLayout::Bool),
+        Just(MoveTypeLayout::U8),
+        Just(MoveTypeLayout::U64),
+        Just(MoveTypeLayout::U128),
+        Just(MoveTypeLayout::Address),
+    ];
+    leaf.prop_recursive(4, 16, 4, |inner| {
+        prop_oneof![
+            inner
+                .clone()
+                .prop_map(|layout| MoveTypeLayout::Vector(Box::new(layout))),
+            vec(inner, 1..4)
+                .prop_map(|fields| MoveTypeLayout::Struct(MoveStructLayout::Runtime(fields))),
+        ]
+    })
+}
+
+/// A Move value of the given layout.
+pub fn arb_move_value(layout: MoveTypeLayout) -> BoxedStrategy<MoveValue> {
+    match layout {
+        MoveTypeLayout::Bool => any::<bool>().prop_map(MoveValue::Bool).boxed(),
+        MoveTypeLayout::U8 => any::<u8>().prop_map(MoveValue::U8).boxed(),
+        MoveTypeLayout::U64 => any::<u64>().prop_map(MoveValue::U64).boxed(),
+        MoveTypeLayout::U128 => any::<u128>().prop_map(MoveValue::U128).boxed(),
+        MoveTypeLayout::Address => any::<[u8; AccountAddress::LENGTH]>()
+            .prop_map(|address| MoveValue::Address(AccountAddress::new(address)))
+            .boxed(),
+        MoveTypeLayout::Signer => any::<[u8; AccountAddress::LENGTH]>()
+            .prop_map(|address| MoveValue::Signer(AccountAddress::new(address)))
+            .boxed(),
+        MoveTypeLayout::Vector(element) => vec(arb_move_value(*element), 0..8)
+            .prop_map(MoveValue::Vector)
+            .boxed(),
+        MoveTypeLayout::Struct(MoveStructLayout::Runtime(fields)) => fields
+            .into_iter()
+            .map(arb_move_value)
+            .collect::<Vec<_>>()
+            .prop_map(|fields| MoveValue::Struct(MoveStruct::Runtime(fields)))
+            .boxed(),
+        MoveTypeLayout::Struct(MoveStructLayout::WithTypes { fields, .. }) => fields
+            .into_iter()
+            .map(|field| arb_move_value(field.layout))
+            .collect::<Vec<_>>()
+            .prop_map(|fields| MoveValue::Struct(MoveStruct::Runtime(fields)))
+            .boxed(),
+        MoveTypeLayout::Struct(MoveStructLayout::WithFields(fields)) => fields
+            .into_iter()
+            .map(|field| arb_move_value(field.layout))
+            .collect::<Vec<_>>()
+            .prop_map(|fields| MoveValue::Struct(MoveStruct::Runtime(fields)))
+            .boxed(),
+    }
+}
+
+/// A layout together with a value of that layout.
+pub fn arb_move_value_with_layout() -> impl Strategy<Value = (MoveTypeLayout, MoveValue)> {
+    arb_move_type_layout().prop_flat_map(|layout| {
+        arb_move_value(layout.clone()).prop_map(move |value| (layout.clone(), value))
+    })
+}
+
+pub fn arb_call_arg() -> impl Strategy<Value = CallArg> {
+    let object_arg = prop_oneof![
+        arb_object_ref().prop_map(ObjectArg::ImmOrOwnedObject),
+        arb_object_id().prop_map(ObjectArg::SharedObject),
+    ];
+    prop_oneof![
+        vec(any::<u8>(), 0..64).prop_map(CallArg::Pure),
+        object_arg.clone().prop_map(CallArg::Object),
+        vec(object_arg, 0..4).prop_map(CallArg::ObjVec),
+    ]
+}
+
+pub fn arb_single_transaction_kind() -> impl Strategy<Value = SingleTransactionKind> {
+    prop_oneof![
+        (arb_sui_address(), arb_object_ref()).prop_map(|(recipient, object_ref)| {
+            SingleTransactionKind::TransferObject(TransferObject {
+                recipient,
+                object_ref,
+            })
+        }),
+        (arb_sui_address(), any::<Option<u64>>()).prop_map(|(recipient, amount)| {
+            SingleTransactionKind::TransferSui(TransferSui { recipient, amount })
+        }),
+        (
+            vec(arb_object_ref(), 1..4),
+            vec((arb_sui_address(), any::<u64>()), 1..4)
+        )
+            .prop_map(|(coins, payments)| {
+                let (recipients, amounts) = payments.into_iter().unzip();
+                SingleTransactionKind::Pay(Pay {
+                    coins,
+                    recipients,
+                    amounts,
+                })
+            }),
+        (
+            arb_object_ref(),
+            arb_identifier(),
+            arb_identifier(),
+            vec(arb_type_tag(), 0..3),
+            vec(arb_call_arg(), 0..4),
+        )
+            .prop_map(|(package, module, function, type_arguments, arguments)| {
+                SingleTransactionKind::Call(MoveCall {
+                    package,
+                    module,
+                    function,
+                    type_arguments,
+                    arguments,
+                })
+            }),
+    ]
+}
+
+pub fn arb_transaction_kind() -> impl Strategy<Value = TransactionKind> {
+    let batchable = arb_single_transaction_kind()
+        .prop_filter("TransferSui cannot be batched", |kind| {
+            !matches!(kind, SingleTransactionKind::TransferSui(_))
+        });
+    prop_oneof![
+        arb_single_transaction_kind().prop_map(TransactionKind::Single),
+        vec(batchable, 1..4).prop_map(TransactionKind::Batch),
+    ]
+}
+
+pub fn arb_transaction_data() -> impl Strategy<Value = TransactionData> {
+    arb_sui_address().prop_flat_map(arb_transaction_data_from)
+}
+
+fn arb_transaction_data_from(sender: SuiAddress) -> impl Strategy<Value = TransactionData> {
+    (
+        arb_transaction_kind(),
+        arb_object_ref(),
+        any::<u64>(),
+        any::<u64>(),
+    )
+        .prop_map(move |(kind, gas, gas_budget, gas_price)| {
+            let mut data = TransactionData::new(kind, sender, gas, gas_budget);
+            data.gas_price = gas_price;
+            data
+        })
+}
+
+/// An Ed25519 key pair derived from a generated seed.
+pub fn arb_key_pair() -> impl Strategy<Value = (SuiAddress, AccountKeyPair)> {
+    any::<[u8; 32]>().prop_map(|seed| get_key_pair_from_rng(&mut StdRng::from_seed(seed)))
+}
+
+/// A signature by a generated key over generated transaction data.
+pub fn arb_signature() -> impl Strategy<Value = Signature> {
+    arb_transaction().prop_map(|transaction| transaction.signed_data.tx_signature)
+}
+
+/// Transaction data whose sender is a generated key, signed by that key.
+pub fn arb_transaction() -> impl Strategy<Value = Transaction> {
+    arb_key_pair().prop_flat_map(|(address, key_pair)| {
+        arb_transaction_data_from(address).prop_map(move |data| {
+            let signature = Signature::new(&data, &key_pair);
+            Transaction::new(data, signature)
+        })
+    })
+}
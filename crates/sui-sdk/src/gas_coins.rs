@@ -0,0 +1,279 @@
+// Copyright (c) 2022, Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! A pool of gas coins of a target size for one sender, so concurrent workers never use the
+//! same gas coin.
+//!
+//! Owned objects can only be used by one transaction at a time; workers sending
+//! transactions for the same address concurrently fail with locked objects when they pick
+//! the same gas coin. A [GasCoinPool] hands out exclusive leases on the sender's coins, and
+//! [GasCoinPool::rebalance] keeps enough coins of the target size by splitting the largest
+//! coin and merging the small coins left behind into it, in background transactions.
+
+use std::time::Duration;
+
+use anyhow::bail;
+
+use sui_json_rpc_types::SuiTransactionEffects;
+use sui_types::base_types::{ObjectRef, SuiAddress};
+use sui_types::gas_coin::GasCoin;
+use sui_types::messages::{Transaction, TransactionData};
+
+use crate::amount::Amount;
+use crate::crypto::SuiKeystore;
+use crate::error::{Error, SuiRpcResult};
+use crate::gas_station::{GasPool, Reservation};
+use crate::SuiClient;
+
+/// A background transaction rebalancing the coins of a [GasCoinPool].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum GasCoinAction {
+    /// Split coins of `amounts` off `coin`, paying gas with `gas`.
+    Split {
+        coin: ObjectRef,
+        gas: ObjectRef,
+        amounts: Vec<u64>,
+    },
+    /// Split one coin of `amount` off `coin`, which also pays the gas, when the sender has no
+    /// other coin to pay gas with.
+    SplitFromGas { coin: ObjectRef, amount: u64 },
+    /// Merge `coin` into `primary`, paying gas with `gas`.
+    Merge {
+        primary: ObjectRef,
+        coin: ObjectRef,
+        gas: ObjectRef,
+    },
+}
+
+/// The next transaction bringing the `available` coins, with `leased` coins out, towards
+/// `count` coins holding at least `target_balance`, or `None` if the coins are balanced or
+/// there is not enough SUI to split another coin.
+///
+/// Coins are split off the largest coin. Once there are enough coins, coins below the
+/// target balance are merged into the largest coin, one per transaction.
+pub fn plan_gas_coins(
+    available: &[(ObjectRef, u64)],
+    leased: usize,
+    count: usize,
+    target_balance: u64,
+    gas_budget: u64,
+) -> Option<GasCoinAction> {
+    let mut coins = available.to_vec();
+    coins.sort_by(|(_, a), (_, b)| b.cmp(a));
+    let (largest, largest_balance) = *coins.first()?;
+    let gas = coins
+        .iter()
+        .skip(1)
+        .find(|(_, balance)| *balance >= gas_budget)
+        .map(|(coin, _)| *coin);
+    let sized = coins
+        .iter()
+        .filter(|(_, balance)| *balance >= target_balance)
+        .count();
+
+    let missing = count.saturating_sub(sized + leased);
+    if missing > 0 {
+        return match gas {
+            Some(gas) => {
+                let splits = (largest_balance / target_balance.max(1)) as usize;
+                // The largest coin stays in the pool, so keep enough in it to be sized.
+                let splits = missing.min(splits.saturating_sub(1));
+                (splits > 0).then(|| GasCoinAction::Split {
+                    coin: largest,
+                    gas,
+                    amounts: vec![target_balance; splits],
+                })
+            }
+            None => (largest_balance >= 2 * target_balance + gas_budget).then(|| {
+                GasCoinAction::SplitFromGas {
+                    coin: largest,
+                    amount: target_balance,
+                }
+            }),
+        };
+    }
+
+    let (small, _) = coins
+        .iter()
+        .rev()
+        .find(|(_, balance)| *balance < target_balance)?;
+    let gas = coins
+        .iter()
+        .skip(1)
+        .find(|(coin, balance)| coin != small && *balance >= gas_budget)?;
+    Some(GasCoinAction::Merge {
+        primary: largest,
+        coin: *small,
+        gas: gas.0,
+    })
+}
+
+pub struct GasCoinPool {
+    owner: SuiAddress,
+    keystore: SuiKeystore,
+    pool: GasPool,
+    count: usize,
+    target_balance: u64,
+    gas_budget: u64,
+    lease_ttl: Duration,
+}
+
+impl GasCoinPool {
+    /// Create a pool keeping `count` SUI coins of at least `target_balance` for `owner`, whose
+    /// key must be in `keystore`. The pool is empty until [GasCoinPool::rebalance] is called.
+    pub fn new(
+        owner: SuiAddress,
+        keystore: SuiKeystore,
+        count: usize,
+        target_balance: u64,
+    ) -> Self {
+        Self {
+            owner,
+            keystore,
+            pool: GasPool::default(),
+            count,
+            target_balance,
+            gas_budget: 1_000,
+            lease_ttl: Duration::from_secs(60),
+        }
+    }
+
+    /// Gas budget of the rebalancing transactions. Defaults to 1000.
+    pub fn with_gas_budget(mut self, gas_budget: u64) -> Self {
+        self.gas_budget = gas_budget;
+        self
+    }
+
+    /// How long a coin stays leased if it is never released. Defaults to 60 seconds.
+    pub fn with_lease_ttl(mut self, lease_ttl: Duration) -> Self {
+        self.lease_ttl = lease_ttl;
+        self
+    }
+
+    pub fn pool(&self) -> &GasPool {
+        &self.pool
+    }
+
+    /// Lease a coin holding at least `min_balance` to pay the gas of one transaction.
+    pub fn lease(&self, min_balance: u64) -> Option<Reservation> {
+        self.pool.reserve(min_balance, self.lease_ttl)
+    }
+
+    /// Return a leased coin once the transaction using it as gas was executed.
+    pub fn release(&self, lease: &Reservation, effects: &SuiTransactionEffects) {
+        let gas = &effects.gas_used;
+        let gas_cost = (gas.computation_cost + gas.storage_cost).saturating_sub(gas.storage_rebate);
+        self.pool.release(
+            lease.id,
+            effects.gas_object.reference.to_object_ref(),
+            lease.balance.saturating_sub(gas_cost),
+        );
+    }
+
+    /// Return a leased coin that was not used.
+    pub fn cancel(&self, lease: &Reservation) {
+        self.pool.release(lease.id, lease.coin, lease.balance);
+    }
+
+    /// Reload the owner's coins and run rebalancing transactions until the pool holds `count`
+    /// coins of the target balance, counting leased coins, and no smaller coins. Returns the
+    /// number of transactions executed.
+    pub async fn rebalance(&self, client: &SuiClient) -> SuiRpcResult<usize> {
+        let mut executed = 0;
+        loop {
+            self.pool.expire_reservations();
+            let coins = client
+                .transaction_builder()
+                .get_coins(self.owner)
+                .await?
+                .remove(&GasCoin::type_().to_string())
+                .unwrap_or_default();
+            self.pool.reset(coins);
+            let action = match plan_gas_coins(
+                &self.pool.available_coins(),
+                self.pool.reserved_count(),
+                self.count,
+                self.target_balance,
+                self.gas_budget,
+            ) {
+                Some(action) => action,
+                None => return Ok(executed),
+            };
+            if self.execute(client, action).await? {
+                executed += 1;
+            }
+        }
+    }
+
+    /// Execute `action`, unless one of its coins was leased since it was planned.
+    async fn execute(&self, client: &SuiClient, action: GasCoinAction) -> anyhow::Result<bool> {
+        let coins = match &action {
+            GasCoinAction::Split { coin, gas, .. } => vec![*coin, *gas],
+            GasCoinAction::SplitFromGas { coin, .. } => vec![*coin],
+            GasCoinAction::Merge { primary, coin, gas } => vec![*primary, *coin, *gas],
+        };
+        // Keep the coins of the transaction from being leased while it runs.
+        let mut reservations = vec![];
+        for coin in &coins {
+            match self.pool.reserve_coin(coin.0, self.lease_ttl) {
+                Some(reservation) => reservations.push(reservation),
+                None => break,
+            }
+        }
+        let result = if reservations.len() == coins.len() {
+            self.sign_and_execute(client, action).await.map(|_| true)
+        } else {
+            Ok(false)
+        };
+        // The coins are reloaded from the node before the next transaction.
+        for reservation in reservations {
+            self.pool.release(reservation.id, reservation.coin, 0);
+        }
+        result
+    }
+
+    async fn sign_and_execute(
+        &self,
+        client: &SuiClient,
+        action: GasCoinAction,
+    ) -> anyhow::Result<()> {
+        let builder = client.transaction_builder();
+        let data: TransactionData = match action {
+            GasCoinAction::Split { coin, gas, amounts } => {
+                let amounts = amounts.into_iter().map(Amount::from_mist).collect();
+                builder
+                    .split_coin(self.owner, coin.0, amounts, Some(gas.0), self.gas_budget)
+                    .await?
+            }
+            GasCoinAction::SplitFromGas { coin, amount } => {
+                builder
+                    .transfer_sui(
+                        self.owner,
+                        coin.0,
+                        self.gas_budget,
+                        self.owner,
+                        Some(Amount::from_mist(amount)),
+                    )
+                    .await?
+            }
+            GasCoinAction::Merge { primary, coin, gas } => {
+                builder
+                    .merge_coins(self.owner, primary.0, coin.0, Some(gas.0), self.gas_budget)
+                    .await?
+            }
+        };
+        let signature = self.keystore.sign(&self.owner, &data.to_bytes())?;
+        let response = client
+            .quorum_driver()
+            .execute_transaction(Transaction::new(data, signature))
+            .await?;
+        if !response.effects.status.is_ok() {
+            bail!(
+                "Gas coin rebalancing transaction [{}] failed: {:?}",
+                response.certificate.transaction_digest,
+                response.effects.status
+            );
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,413 @@
+// Copyright (c) 2022, Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Building blocks for a gas station paying the gas of other addresses' transactions.
+//!
+//! Gas must be owned by the sender of a transaction in this version of Sui, so the station
+//! sponsors a transaction by sending its sender the MIST the requested gas budget costs at
+//! the station's gas price, from one of the station's coins. Each coin of the [GasPool] is
+//! reserved while a funding transaction using it is in flight, so concurrent requests never
+//! use the same coin version.
+//!
+//! The funded SUI belongs to the sender, who may spend it on any transaction: policies can
+//! restrict who is sponsored and how much, but not what the gas is used for.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use anyhow::anyhow;
+
+use sui_json_rpc_types::SuiTransactionEffects;
+use sui_types::base_types::{ObjectID, ObjectRef, SuiAddress};
+use sui_types::gas_coin::GasCoin;
+use sui_types::messages::{Transaction, TransactionData, TransactionKind};
+
+use crate::crypto::SuiKeystore;
+use crate::error::{Error, SuiRpcResult};
+use crate::transaction_builder::TransactionBuilder;
+
+/// A handle on a coin reserved from a [GasPool].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Reservation {
+    pub id: u64,
+    pub coin: ObjectRef,
+    pub balance: u64,
+}
+
+struct ReservedCoin {
+    coin: ObjectRef,
+    balance: u64,
+    expires_at: Instant,
+}
+
+#[derive(Default)]
+struct GasPoolInner {
+    available: BTreeMap<ObjectID, (ObjectRef, u64)>,
+    reserved: BTreeMap<u64, ReservedCoin>,
+    next_id: u64,
+}
+
+/// The SUI coins a gas station pays from.
+#[derive(Default)]
+pub struct GasPool(Mutex<GasPoolInner>);
+
+impl GasPool {
+    /// Add a coin with its balance, replacing any previous version of it.
+    pub fn add_coin(&self, coin: ObjectRef, balance: u64) {
+        let mut inner = self.0.lock().unwrap();
+        if !inner
+            .reserved
+            .values()
+            .any(|reserved| reserved.coin.0 == coin.0)
+        {
+            inner.available.insert(coin.0, (coin, balance));
+        }
+    }
+
+    /// Reserve the smallest available coin holding at least `min_balance`, for at most `ttl`.
+    pub fn reserve(&self, min_balance: u64, ttl: Duration) -> Option<Reservation> {
+        let mut inner = self.0.lock().unwrap();
+        let (coin, balance) = inner
+            .available
+            .values()
+            .filter(|(_, balance)| *balance >= min_balance)
+            .min_by_key(|(_, balance)| *balance)
+            .copied()?;
+        inner.available.remove(&coin.0);
+        let id = inner.next_id;
+        inner.next_id += 1;
+        inner.reserved.insert(
+            id,
+            ReservedCoin {
+                coin,
+                balance,
+                expires_at: Instant::now() + ttl,
+            },
+        );
+        Some(Reservation { id, coin, balance })
+    }
+
+    /// Reserve the coin `object_id`, if it is available, for at most `ttl`.
+    pub fn reserve_coin(&self, object_id: ObjectID, ttl: Duration) -> Option<Reservation> {
+        let mut inner = self.0.lock().unwrap();
+        let (coin, balance) = inner.available.remove(&object_id)?;
+        let id = inner.next_id;
+        inner.next_id += 1;
+        inner.reserved.insert(
+            id,
+            ReservedCoin {
+                coin,
+                balance,
+                expires_at: Instant::now() + ttl,
+            },
+        );
+        Some(Reservation { id, coin, balance })
+    }
+
+    /// Return a reserved coin to the pool with its version after use. A coin whose balance
+    /// dropped to zero is not returned.
+    pub fn release(&self, id: u64, coin: ObjectRef, balance: u64) {
+        let mut inner = self.0.lock().unwrap();
+        if inner.reserved.remove(&id).is_some() && balance > 0 {
+            inner.available.insert(coin.0, (coin, balance));
+        }
+    }
+
+    /// Return the coins of expired reservations to the pool, unchanged, and return their IDs.
+    /// Their version may be stale if a funding transaction was executed after all;
+    /// [GasStation::refill] fetches the current versions.
+    pub fn expire_reservations(&self) -> Vec<ObjectID> {
+        let mut inner = self.0.lock().unwrap();
+        let now = Instant::now();
+        let expired = inner
+            .reserved
+            .iter()
+            .filter(|(_, reserved)| reserved.expires_at <= now)
+            .map(|(id, _)| *id)
+            .collect::<Vec<_>>();
+        expired
+            .into_iter()
+            .filter_map(|id| inner.reserved.remove(&id))
+            .map(|reserved| {
+                inner
+                    .available
+                    .insert(reserved.coin.0, (reserved.coin, reserved.balance));
+                reserved.coin.0
+            })
+            .collect()
+    }
+
+    /// Replace the available coins with `coins`, e.g. as fetched from the node, leaving out
+    /// the reserved ones. Coins no longer listed are dropped.
+    pub fn reset(&self, coins: impl IntoIterator<Item = (ObjectRef, u64)>) {
+        let mut inner = self.0.lock().unwrap();
+        let reserved = inner
+            .reserved
+            .values()
+            .map(|reserved| reserved.coin.0)
+            .collect::<BTreeSet<_>>();
+        inner.available = coins
+            .into_iter()
+            .filter(|(coin, _)| !reserved.contains(&coin.0))
+            .map(|(coin, balance)| (coin.0, (coin, balance)))
+            .collect();
+    }
+
+    /// The available coins with their balances.
+    pub fn available_coins(&self) -> Vec<(ObjectRef, u64)> {
+        self.0.lock().unwrap().available.values().copied().collect()
+    }
+
+    /// The total balance of the available coins, saturating at `u64::MAX`.
+    pub fn available_balance(&self) -> u64 {
+        let inner = self.0.lock().unwrap();
+        inner
+            .available
+            .values()
+            .fold(0, |total: u64, (_, balance)| total.saturating_add(*balance))
+    }
+
+    pub fn reserved_count(&self) -> usize {
+        self.0.lock().unwrap().reserved.len()
+    }
+}
+
+/// A request to pay the gas of a transaction.
+#[derive(Debug, Clone)]
+pub struct SponsorRequest {
+    pub sender: SuiAddress,
+    /// The transaction the sender declares it will run with the sponsored gas. It is not
+    /// binding, the sender may spend the gas on any other transaction.
+    pub kind: TransactionKind,
+    /// The gas budget of the transaction, in gas units.
+    pub gas_budget: u64,
+}
+
+/// Decides which requests a gas station sponsors.
+pub trait SponsorshipPolicy: Send + Sync {
+    /// Return an error explaining the refusal if `request` must not be sponsored.
+    fn check(&self, request: &SponsorRequest) -> SuiRpcResult<()>;
+}
+
+/// Sponsors requests from the allowed senders up to a maximum gas budget, in gas units. An
+/// unset list allows every sender.
+#[derive(Debug, Clone, Default)]
+pub struct AllowListPolicy {
+    pub senders: Option<BTreeSet<SuiAddress>>,
+    pub max_gas_budget: Option<u64>,
+}
+
+impl SponsorshipPolicy for AllowListPolicy {
+    fn check(&self, request: &SponsorRequest) -> SuiRpcResult<()> {
+        if let Some(senders) = &self.senders {
+            if !senders.contains(&request.sender) {
+                return Err(Error::Other(anyhow!(
+                    "Sender [{}] is not sponsored.",
+                    request.sender
+                )));
+            }
+        }
+        if let Some(max_gas_budget) = self.max_gas_budget {
+            if request.gas_budget > max_gas_budget {
+                return Err(Error::Other(anyhow!(
+                    "Gas budget [{}] exceeds [{max_gas_budget}].",
+                    request.gas_budget
+                )));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Counters of a gas station's activity.
+#[derive(Default)]
+pub struct GasStationMetrics {
+    pub sponsored_transactions: AtomicU64,
+    pub rejected_requests: AtomicU64,
+    /// MIST sent to senders.
+    pub sponsored_amount: AtomicU64,
+    /// MIST paid by the station for the gas of funding transactions.
+    pub funding_gas: AtomicU64,
+    spend_by_sender: Mutex<BTreeMap<SuiAddress, u64>>,
+}
+
+impl GasStationMetrics {
+    /// MIST sent to each sender.
+    pub fn spend_by_sender(&self) -> BTreeMap<SuiAddress, u64> {
+        self.spend_by_sender.lock().unwrap().clone()
+    }
+}
+
+/// A funding transaction signed by the station, waiting for execution.
+#[derive(Debug)]
+pub struct Sponsorship {
+    pub reservation: Reservation,
+    pub sender: SuiAddress,
+    /// The MIST sent to the sender.
+    pub amount: u64,
+    pub transaction: Transaction,
+}
+
+pub struct GasStation<P> {
+    sponsor: SuiAddress,
+    keystore: SuiKeystore,
+    policy: P,
+    pool: GasPool,
+    metrics: GasStationMetrics,
+    funding_gas_budget: u64,
+    gas_price: u64,
+    reservation_ttl: Duration,
+}
+
+impl<P: SponsorshipPolicy> GasStation<P> {
+    /// Create a station paying from the coins of `sponsor`, whose key must be in `keystore`.
+    /// The pool is empty until [GasStation::refill] is called.
+    pub fn new(sponsor: SuiAddress, keystore: SuiKeystore, policy: P) -> Self {
+        Self {
+            sponsor,
+            keystore,
+            policy,
+            pool: GasPool::default(),
+            metrics: GasStationMetrics::default(),
+            funding_gas_budget: 1_000,
+            gas_price: 1,
+            reservation_ttl: Duration::from_secs(60),
+        }
+    }
+
+    /// Gas budget of the funding transactions, in gas units. Defaults to 1000.
+    pub fn with_funding_gas_budget(mut self, funding_gas_budget: u64) -> Self {
+        self.funding_gas_budget = funding_gas_budget;
+        self
+    }
+
+    /// Gas price, in MIST per gas unit, of the funding transactions and of the sponsored
+    /// gas budgets. Defaults to 1.
+    pub fn with_gas_price(mut self, gas_price: u64) -> Self {
+        self.gas_price = gas_price;
+        self
+    }
+
+    /// How long a coin stays reserved if the funding transaction is never confirmed.
+    /// Defaults to 60 seconds.
+    pub fn with_reservation_ttl(mut self, reservation_ttl: Duration) -> Self {
+        self.reservation_ttl = reservation_ttl;
+        self
+    }
+
+    pub fn pool(&self) -> &GasPool {
+        &self.pool
+    }
+
+    pub fn metrics(&self) -> &GasStationMetrics {
+        &self.metrics
+    }
+
+    /// Load the current version and balance of every SUI coin of the sponsor into the pool.
+    pub async fn refill(&self, builder: &TransactionBuilder) -> SuiRpcResult<()> {
+        self.pool.expire_reservations();
+        let coins = builder
+            .get_coins(self.sponsor)
+            .await?
+            .remove(&GasCoin::type_().to_string())
+            .unwrap_or_default();
+        for (coin, balance) in coins {
+            self.pool.add_coin(coin, balance);
+        }
+        Ok(())
+    }
+
+    /// Check `request` against the policy and return a signed transaction sending the cost
+    /// of the requested gas budget to its sender. Execute it, then pass its effects to
+    /// [GasStation::confirm].
+    pub fn sponsor(&self, request: &SponsorRequest) -> SuiRpcResult<Sponsorship> {
+        let (amount, required) = match self.price(request) {
+            Ok(price) => price,
+            Err(e) => {
+                self.metrics
+                    .rejected_requests
+                    .fetch_add(1, Ordering::Relaxed);
+                return Err(e.into());
+            }
+        };
+        let reservation = self
+            .pool
+            .reserve(required, self.reservation_ttl)
+            .ok_or_else(|| anyhow!("No gas coin in the pool holds [{required}] MIST."))?;
+        let mut data = TransactionData::new_transfer_sui(
+            request.sender,
+            self.sponsor,
+            Some(amount),
+            reservation.coin,
+            self.funding_gas_budget,
+        );
+        data.gas_price = self.gas_price;
+        let signature = match self.keystore.sign(&self.sponsor, &data.to_bytes()) {
+            Ok(signature) => signature,
+            Err(e) => {
+                self.pool
+                    .release(reservation.id, reservation.coin, reservation.balance);
+                return Err(e.into());
+            }
+        };
+        Ok(Sponsorship {
+            reservation,
+            sender: request.sender,
+            amount,
+            transaction: Transaction::new(data, signature),
+        })
+    }
+
+    /// Check `request` against the policy and return the MIST to send to its sender, and the
+    /// MIST the funding coin must hold to also pay the funding gas.
+    fn price(&self, request: &SponsorRequest) -> anyhow::Result<(u64, u64)> {
+        self.policy.check(request)?;
+        let amount = request.gas_budget.checked_mul(self.gas_price);
+        let required = self
+            .funding_gas_budget
+            .checked_mul(self.gas_price)
+            .zip(amount)
+            .and_then(|(funding_gas, amount)| funding_gas.checked_add(amount));
+        amount
+            .zip(required)
+            .ok_or_else(|| anyhow!("Gas budget [{}] is too large.", request.gas_budget))
+    }
+
+    /// Return the coin of an executed funding transaction to the pool, and record the spend.
+    pub fn confirm(&self, sponsorship: &Sponsorship, effects: &SuiTransactionEffects) {
+        let gas = &effects.gas_used;
+        let gas_cost = gas
+            .computation_cost
+            .saturating_add(gas.storage_cost)
+            .saturating_sub(gas.storage_rebate);
+        let amount = if effects.status.is_ok() {
+            sponsorship.amount
+        } else {
+            0
+        };
+        let reservation = &sponsorship.reservation;
+        self.pool.release(
+            reservation.id,
+            effects.gas_object.reference.to_object_ref(),
+            reservation
+                .balance
+                .saturating_sub(amount.saturating_add(gas_cost)),
+        );
+
+        let metrics = &self.metrics;
+        metrics.funding_gas.fetch_add(gas_cost, Ordering::Relaxed);
+        if amount > 0 {
+            metrics
+                .sponsored_transactions
+                .fetch_add(1, Ordering::Relaxed);
+            metrics
+                .sponsored_amount
+                .fetch_add(amount, Ordering::Relaxed);
+            let mut spend_by_sender = metrics.spend_by_sender.lock().unwrap();
+            let spend = spend_by_sender.entry(sponsorship.sender).or_default();
+            *spend = spend.saturating_add(amount);
+        }
+    }
+}
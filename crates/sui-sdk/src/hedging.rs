@@ -0,0 +1,206 @@
+// Copyright (c) 2022, Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Hedged reads, cutting the tail latency of latency critical services.
+//!
+//! A read the node has not answered after the delay of the [HedgePolicy], by default the
+//! 99th percentile of the recent read latencies, is sent again to a second endpoint, and
+//! the first successful response wins. Only reads (`sui_get*`, `sui_tryGet*`) are hedged, as
+//! they can safely be sent twice. A budget shared by all requests of the client bounds the
+//! share of hedged requests, so a slow node does not double the load on the endpoints.
+
+use std::collections::VecDeque;
+use std::future::Future;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use futures::future::{select, Either};
+use futures::pin_mut;
+
+use crate::runtime::Runtime;
+
+/// Latencies needed before hedging after their percentile rather than the initial delay.
+const MIN_SAMPLES: usize = 20;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct HedgePolicy {
+    percentile: f64,
+    initial_delay: Duration,
+    min_delay: Duration,
+    window: usize,
+    budget: f64,
+    burst: f64,
+}
+
+impl Default for HedgePolicy {
+    fn default() -> Self {
+        Self {
+            percentile: 0.99,
+            initial_delay: Duration::from_millis(500),
+            min_delay: Duration::from_millis(10),
+            window: 1000,
+            budget: 0.05,
+            burst: 10.0,
+        }
+    }
+}
+
+impl HedgePolicy {
+    /// Hedge reads slower than the `percentile`, between 0 and 1, of the last `window` read
+    /// latencies. Defaults to the 99th percentile of the last 1000 reads.
+    pub fn with_percentile(mut self, percentile: f64, window: usize) -> Self {
+        self.percentile = percentile.clamp(0.0, 1.0);
+        self.window = window.max(MIN_SAMPLES);
+        self
+    }
+
+    /// Delay before hedging until enough latencies are known. Defaults to 500 milliseconds.
+    pub fn with_initial_delay(mut self, initial_delay: Duration) -> Self {
+        self.initial_delay = initial_delay;
+        self
+    }
+
+    /// Never hedge reads sooner than `min_delay`. Defaults to 10 milliseconds.
+    pub fn with_min_delay(mut self, min_delay: Duration) -> Self {
+        self.min_delay = min_delay;
+        self
+    }
+
+    /// Hedge at most `budget`, between 0 and 1, of the reads, with bursts of up to `burst`
+    /// hedged reads. Defaults to 5% of the reads, in bursts of up to 10.
+    pub fn with_budget(mut self, budget: f64, burst: u32) -> Self {
+        self.budget = budget.clamp(0.0, 1.0);
+        self.burst = burst.max(1) as f64;
+        self
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct HedgeStats {
+    pub requests: u64,
+    /// Requests sent to the second endpoint.
+    pub hedged: u64,
+    /// Hedged requests answered by the second endpoint first.
+    pub hedge_wins: u64,
+}
+
+#[derive(Debug)]
+pub struct Hedger {
+    policy: HedgePolicy,
+    runtime: Arc<dyn Runtime>,
+    latencies: Mutex<VecDeque<Duration>>,
+    /// Hedges left in the budget, gaining `budget` per request up to `burst`.
+    tokens: Mutex<f64>,
+    requests: AtomicU64,
+    hedged: AtomicU64,
+    hedge_wins: AtomicU64,
+}
+
+impl Hedger {
+    pub fn new(policy: HedgePolicy, runtime: Arc<dyn Runtime>) -> Self {
+        Self {
+            policy,
+            runtime,
+            latencies: Mutex::new(VecDeque::new()),
+            tokens: Mutex::new(policy.burst),
+            requests: AtomicU64::new(0),
+            hedged: AtomicU64::new(0),
+            hedge_wins: AtomicU64::new(0),
+        }
+    }
+
+    /// The delay after which a request is hedged.
+    pub fn delay(&self) -> Duration {
+        let latencies = self.latencies.lock().unwrap();
+        if latencies.len() < MIN_SAMPLES {
+            return self.policy.initial_delay.max(self.policy.min_delay);
+        }
+        let mut sorted = latencies.iter().copied().collect::<Vec<_>>();
+        sorted.sort();
+        let index = ((sorted.len() - 1) as f64 * self.policy.percentile).round() as usize;
+        sorted[index].max(self.policy.min_delay)
+    }
+
+    /// Record the latency of a successful request.
+    pub fn record(&self, latency: Duration) {
+        let mut latencies = self.latencies.lock().unwrap();
+        if latencies.len() == self.policy.window {
+            latencies.pop_front();
+        }
+        latencies.push_back(latency);
+    }
+
+    /// Take a hedged request from the budget, if it is not spent.
+    pub fn try_hedge(&self) -> bool {
+        let mut tokens = self.tokens.lock().unwrap();
+        if *tokens >= 1.0 {
+            *tokens -= 1.0;
+            true
+        } else {
+            false
+        }
+    }
+
+    pub fn stats(&self) -> HedgeStats {
+        HedgeStats {
+            requests: self.requests.load(Ordering::Relaxed),
+            hedged: self.hedged.load(Ordering::Relaxed),
+            hedge_wins: self.hedge_wins.load(Ordering::Relaxed),
+        }
+    }
+
+    /// Run `primary`, and `hedge` too if `primary` is slower than [Hedger::delay] and the
+    /// budget allows, returning the first successful response. Fails with the error of
+    /// `primary` if both fail.
+    pub async fn run<T, E, H, F>(
+        &self,
+        primary: impl Future<Output = Result<T, E>>,
+        hedge: H,
+    ) -> Result<T, E>
+    where
+        H: FnOnce() -> F,
+        F: Future<Output = Result<T, E>>,
+    {
+        self.requests.fetch_add(1, Ordering::Relaxed);
+        {
+            let mut tokens = self.tokens.lock().unwrap();
+            *tokens = (*tokens + self.policy.budget).min(self.policy.burst);
+        }
+        let start = Instant::now();
+        pin_mut!(primary);
+        let primary = match select(primary, self.runtime.sleep(self.delay())).await {
+            Either::Left((result, _)) => return self.recorded(start, result),
+            Either::Right((_, primary)) => primary,
+        };
+        if !self.try_hedge() {
+            return self.recorded(start, primary.await);
+        }
+
+        self.hedged.fetch_add(1, Ordering::Relaxed);
+        let hedge = hedge();
+        pin_mut!(hedge);
+        let result = match select(primary, hedge).await {
+            Either::Left((Ok(response), _)) => Ok(response),
+            Either::Left((Err(e), hedge)) => hedge.await.map_err(|_| e),
+            Either::Right((Ok(response), _)) => {
+                self.hedge_wins.fetch_add(1, Ordering::Relaxed);
+                Ok(response)
+            }
+            Either::Right((Err(_), primary)) => primary.await,
+        };
+        self.recorded(start, result)
+    }
+
+    fn recorded<T, E>(&self, start: Instant, result: Result<T, E>) -> Result<T, E> {
+        if result.is_ok() {
+            self.record(start.elapsed());
+        }
+        result
+    }
+}
+
+/// Whether `method` only reads, and may be hedged.
+pub(crate) fn is_hedgeable(method: &str) -> bool {
+    method.starts_with("sui_get") || method.starts_with("sui_tryGet")
+}
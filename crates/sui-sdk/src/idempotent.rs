@@ -0,0 +1,152 @@
+// Copyright (c) 2022, Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Idempotent transaction submission, so that retry loops, e.g. of payment services, never
+//! send the same payment twice.
+//!
+//! The [IdempotentSubmitter] records the digest of every transaction it broadcasts in a
+//! [CursorStore], before broadcasting it, under an idempotency key: the digest itself, or a
+//! key chosen by the caller such as a payment ID. Submitting again for a recorded key does
+//! not broadcast anything; the status of the recorded transaction is returned instead. This
+//! also holds after a restart, and for a transaction signed anew by a retry.
+
+use std::collections::BTreeSet;
+use std::sync::Mutex;
+
+use anyhow::{anyhow, bail};
+use serde_json::Value;
+
+use sui_json_rpc_types::SuiTransactionResponse;
+use sui_types::base_types::TransactionDigest;
+use sui_types::messages::Transaction;
+
+use crate::cursor_store::CursorStore;
+use crate::error::{Error, SuiRpcResult};
+use crate::SuiClient;
+
+#[derive(Debug, Clone)]
+pub struct Submission {
+    pub digest: TransactionDigest,
+    /// Whether a transaction was already submitted for the key, in which case nothing was
+    /// broadcast and `digest` is the digest of that transaction.
+    pub duplicate: bool,
+    pub response: SuiTransactionResponse,
+}
+
+pub struct IdempotentSubmitter<'a, S> {
+    client: &'a SuiClient,
+    store: S,
+    prefix: String,
+    in_flight: Mutex<BTreeSet<String>>,
+}
+
+impl<'a, S: CursorStore> IdempotentSubmitter<'a, S> {
+    pub fn new(client: &'a SuiClient, store: S) -> Self {
+        Self {
+            client,
+            store,
+            prefix: "submissions".to_string(),
+            in_flight: Mutex::new(BTreeSet::new()),
+        }
+    }
+
+    /// Record submissions under the cursor names `{prefix}/{key}`, so several submitters can
+    /// share a store.
+    pub fn with_prefix(mut self, prefix: impl Into<String>) -> Self {
+        self.prefix = prefix.into();
+        self
+    }
+
+    /// Submit `tx`, unless it was already submitted.
+    pub async fn submit(&self, tx: Transaction) -> SuiRpcResult<Submission> {
+        let key = tx.digest().to_string();
+        self.submit_with_key(&key, tx).await
+    }
+
+    /// Submit `tx` for the idempotency key `key`, unless a transaction, `tx` or another one,
+    /// was already submitted for `key`.
+    ///
+    /// Fails if the transaction recorded for `key` is not `tx` and cannot be found on the
+    /// node; see [IdempotentSubmitter::forget].
+    pub async fn submit_with_key(&self, key: &str, tx: Transaction) -> SuiRpcResult<Submission> {
+        let _in_flight = InFlight::enter(&self.in_flight, key)?;
+        let name = self.record_name(key);
+        let digest = *tx.digest();
+        if let Some(recorded) = self.load(&name).await? {
+            return match self.client.read_api().get_transaction(recorded).await {
+                Ok(response) => Ok(Submission {
+                    digest: recorded,
+                    duplicate: true,
+                    response,
+                }),
+                // The node may have never received the transaction. Broadcasting the same
+                // transaction again cannot execute it twice.
+                Err(_) if recorded == digest => self.execute(tx, true).await,
+                Err(e) => Err(Error::Other(anyhow!(
+                    "Transaction [{recorded}] submitted for [{key}] cannot be found: {e}"
+                ))),
+            };
+        }
+        self.store
+            .save(&name, serde_json::to_value(digest)?)
+            .await?;
+        self.execute(tx, false).await
+    }
+
+    /// The digest of the transaction submitted for `key`, if any.
+    pub async fn recorded_digest(&self, key: &str) -> SuiRpcResult<Option<TransactionDigest>> {
+        self.load(&self.record_name(key)).await
+    }
+
+    /// Forget the transaction submitted for `key`, so the next submission for `key` is
+    /// broadcast. Only forget a transaction known never to execute, e.g. one whose input
+    /// objects were used by a later transaction.
+    pub async fn forget(&self, key: &str) -> SuiRpcResult<()> {
+        self.store.save(&self.record_name(key), Value::Null).await
+    }
+
+    async fn execute(&self, tx: Transaction, duplicate: bool) -> SuiRpcResult<Submission> {
+        let digest = *tx.digest();
+        let response = self.client.quorum_driver().execute_transaction(tx).await?;
+        Ok(Submission {
+            digest,
+            duplicate,
+            response,
+        })
+    }
+
+    async fn load(&self, name: &str) -> SuiRpcResult<Option<TransactionDigest>> {
+        Ok(match self.store.load(name).await? {
+            None | Some(Value::Null) => None,
+            Some(digest) => Some(serde_json::from_value(digest)?),
+        })
+    }
+
+    fn record_name(&self, key: &str) -> String {
+        format!("{}/{key}", self.prefix)
+    }
+}
+
+/// Marks a key as being submitted, until dropped.
+struct InFlight<'a> {
+    keys: &'a Mutex<BTreeSet<String>>,
+    key: String,
+}
+
+impl<'a> InFlight<'a> {
+    fn enter(keys: &'a Mutex<BTreeSet<String>>, key: &str) -> anyhow::Result<Self> {
+        if !keys.lock().unwrap().insert(key.to_string()) {
+            bail!("A transaction for [{key}] is already being submitted.");
+        }
+        Ok(Self {
+            keys,
+            key: key.to_string(),
+        })
+    }
+}
+
+impl Drop for InFlight<'_> {
+    fn drop(&mut self) {
+        self.keys.lock().unwrap().remove(&self.key);
+    }
+}
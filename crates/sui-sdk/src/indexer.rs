@@ -0,0 +1,291 @@
+// Copyright (c) 2022, Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! A small event indexing framework. An [EventIndexer] pulls events from a full node, either
+//! by polling time ranges or through a WebSocket subscription, feeds every event to the
+//! registered [EventHandler]s and records its progress in an [IndexerStore].
+//!
+//! Delivery is at-least-once: the cursor is only advanced after all handlers of a batch
+//! succeeded, so events may be replayed after a restart or error. Every event carries an
+//! [EventKey] which handlers should use as an idempotency key, e.g. as the primary key of the
+//! rows they write.
+
+use std::collections::BTreeMap;
+use std::fmt::{Display, Formatter};
+use std::sync::RwLock;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use futures::{pin_mut, StreamExt};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use sui_json_rpc::api::EVENT_QUERY_MAX_LIMIT;
+use sui_json_rpc_types::{SuiEventEnvelope, SuiEventFilter};
+use sui_types::base_types::TransactionDigest;
+
+use crate::cancellation::{cancellable, CancellationToken};
+use crate::error::SuiRpcResult;
+use crate::runtime::timeout;
+use crate::SuiClient;
+
+/// Position of an indexer in the event stream.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EventCursor {
+    /// Timestamp of the last processed event, in milliseconds since epoch.
+    pub timestamp: u64,
+    /// Number of events with this timestamp already processed.
+    pub processed: usize,
+}
+
+/// Unique identifier of an event, stable across replays.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub struct EventKey {
+    pub tx_digest: Option<TransactionDigest>,
+    pub timestamp: u64,
+    /// Position of the event among the events emitted by the same transaction.
+    pub event_seq: usize,
+}
+
+impl Display for EventKey {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match &self.tx_digest {
+            Some(digest) => write!(f, "{digest}:{}", self.event_seq),
+            None => write!(f, "{}:{}", self.timestamp, self.event_seq),
+        }
+    }
+}
+
+/// Storage backend of an indexer. Implementations backed by a database (e.g. SQLite or
+/// Postgres) should make `upsert_row` idempotent on `key`, so replayed events overwrite the
+/// rows they produced the first time.
+#[async_trait]
+pub trait IndexerStore: Send + Sync {
+    async fn load_cursor(&self, indexer: &str) -> SuiRpcResult<Option<EventCursor>>;
+
+    async fn save_cursor(&self, indexer: &str, cursor: EventCursor) -> SuiRpcResult<()>;
+
+    async fn upsert_row(&self, table: &str, key: &EventKey, row: Value) -> SuiRpcResult<()>;
+}
+
+/// A handler processing indexed events.
+#[async_trait]
+pub trait EventHandler: Send + Sync {
+    /// Return true if the handler is interested in `event`.
+    fn matches(&self, _event: &SuiEventEnvelope) -> bool {
+        true
+    }
+
+    async fn handle(
+        &self,
+        key: &EventKey,
+        event: &SuiEventEnvelope,
+        store: &dyn IndexerStore,
+    ) -> SuiRpcResult<()>;
+}
+
+/// An [IndexerStore] keeping cursors and rows in memory, for tests and prototyping.
+#[derive(Default)]
+pub struct InMemoryIndexerStore {
+    cursors: RwLock<BTreeMap<String, EventCursor>>,
+    rows: RwLock<BTreeMap<String, BTreeMap<EventKey, Value>>>,
+}
+
+impl InMemoryIndexerStore {
+    pub fn rows(&self, table: &str) -> BTreeMap<EventKey, Value> {
+        self.rows
+            .read()
+            .unwrap()
+            .get(table)
+            .cloned()
+            .unwrap_or_default()
+    }
+}
+
+#[async_trait]
+impl IndexerStore for InMemoryIndexerStore {
+    async fn load_cursor(&self, indexer: &str) -> SuiRpcResult<Option<EventCursor>> {
+        Ok(self.cursors.read().unwrap().get(indexer).copied())
+    }
+
+    async fn save_cursor(&self, indexer: &str, cursor: EventCursor) -> SuiRpcResult<()> {
+        self.cursors
+            .write()
+            .unwrap()
+            .insert(indexer.to_string(), cursor);
+        Ok(())
+    }
+
+    async fn upsert_row(&self, table: &str, key: &EventKey, row: Value) -> SuiRpcResult<()> {
+        self.rows
+            .write()
+            .unwrap()
+            .entry(table.to_string())
+            .or_default()
+            .insert(key.clone(), row);
+        Ok(())
+    }
+}
+
+pub struct EventIndexer<S> {
+    name: String,
+    store: S,
+    handlers: Vec<Box<dyn EventHandler>>,
+    poll_interval: Duration,
+    cancellation: CancellationToken,
+}
+
+impl<S: IndexerStore> EventIndexer<S> {
+    pub fn new(name: impl Into<String>, store: S) -> Self {
+        Self {
+            name: name.into(),
+            store,
+            handlers: vec![],
+            poll_interval: Duration::from_secs(1),
+            cancellation: CancellationToken::new(),
+        }
+    }
+
+    pub fn with_handler(mut self, handler: impl EventHandler + 'static) -> Self {
+        self.handlers.push(Box::new(handler));
+        self
+    }
+
+    pub fn with_poll_interval(mut self, poll_interval: Duration) -> Self {
+        self.poll_interval = poll_interval;
+        self
+    }
+
+    /// Stop [EventIndexer::run] and [EventIndexer::run_subscription] when `cancellation` is
+    /// cancelled, once the events being processed are recorded.
+    pub fn with_cancellation(mut self, cancellation: CancellationToken) -> Self {
+        self.cancellation = cancellation;
+        self
+    }
+
+    pub fn store(&self) -> &S {
+        &self.store
+    }
+
+    /// Fetch and process the next batch of events after the stored cursor. Returns the
+    /// number of new events processed.
+    pub async fn poll_once(&self, client: &SuiClient) -> SuiRpcResult<usize> {
+        let cursor = self.store.load_cursor(&self.name).await?;
+        let start_time = cursor.map_or(0, |cursor| cursor.timestamp);
+        // Query from the cursor timestamp inclusive, as more events may share it, and skip
+        // the ones already processed. This assumes the node returns events with the same
+        // timestamp in a stable order, and no more of them than fit in one query.
+        let events = client
+            .event_api()
+            .get_events_by_timerange(EVENT_QUERY_MAX_LIMIT, start_time, u64::MAX)
+            .await?;
+        let skip = cursor.map_or(0, |cursor| cursor.processed);
+
+        let mut new_events = 0;
+        for (key, event) in event_keys(&events).zip(&events).skip(skip) {
+            self.dispatch(&key, event).await?;
+            new_events += 1;
+        }
+        if let Some(last) = events.last() {
+            let processed = events
+                .iter()
+                .filter(|event| event.timestamp == last.timestamp)
+                .count();
+            self.store
+                .save_cursor(
+                    &self.name,
+                    EventCursor {
+                        timestamp: last.timestamp,
+                        processed,
+                    },
+                )
+                .await?;
+        }
+        Ok(new_events)
+    }
+
+    /// Poll for new events until cancelled, sleeping for the poll interval whenever the node
+    /// has no new events.
+    pub async fn run(&self, client: &SuiClient) -> SuiRpcResult<()> {
+        while !self.cancellation.is_cancelled() {
+            if self.poll_once(client).await? == 0 {
+                timeout(
+                    client.runtime(),
+                    self.poll_interval,
+                    self.cancellation.cancelled(),
+                )
+                .await;
+            }
+        }
+        Ok(())
+    }
+
+    /// Process events from a WebSocket subscription until the subscription ends or the
+    /// indexer is cancelled.
+    pub async fn run_subscription(
+        &self,
+        client: &SuiClient,
+        filter: SuiEventFilter,
+    ) -> SuiRpcResult<()> {
+        let events = cancellable(
+            client.event_api().subscribe_event(filter).await?,
+            self.cancellation.clone(),
+        );
+        pin_mut!(events);
+        let mut previous: Option<SuiEventEnvelope> = None;
+        let mut event_seq = 0;
+        let mut processed = 0;
+        while let Some(event) = events.next().await {
+            let event = event?;
+            match &previous {
+                Some(previous) if previous.tx_digest == event.tx_digest => event_seq += 1,
+                _ => event_seq = 0,
+            }
+            match &previous {
+                Some(previous) if previous.timestamp == event.timestamp => processed += 1,
+                _ => processed = 1,
+            }
+            let key = EventKey {
+                tx_digest: event.tx_digest,
+                timestamp: event.timestamp,
+                event_seq,
+            };
+            self.dispatch(&key, &event).await?;
+            self.store
+                .save_cursor(
+                    &self.name,
+                    EventCursor {
+                        timestamp: event.timestamp,
+                        processed,
+                    },
+                )
+                .await?;
+            previous = Some(event);
+        }
+        Ok(())
+    }
+
+    async fn dispatch(&self, key: &EventKey, event: &SuiEventEnvelope) -> anyhow::Result<()> {
+        for handler in self.handlers.iter().filter(|h| h.matches(event)) {
+            handler.handle(key, event, &self.store).await?;
+        }
+        Ok(())
+    }
+}
+
+/// Compute the keys of a sequence of events, numbering the events of each transaction.
+pub(crate) fn event_keys(events: &[SuiEventEnvelope]) -> impl Iterator<Item = EventKey> + '_ {
+    let mut event_seq = 0;
+    events.iter().enumerate().map(move |(i, event)| {
+        if i > 0 && events[i - 1].tx_digest == event.tx_digest {
+            event_seq += 1;
+        } else {
+            event_seq = 0;
+        }
+        EventKey {
+            tx_digest: event.tx_digest,
+            timestamp: event.timestamp,
+            event_seq,
+        }
+    })
+}
@@ -0,0 +1,161 @@
+// Copyright (c) 2022, Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Typed views of the Move modules of a package, for explorers and code generators.
+//!
+//! Modules are read from the package bytecode rather than from the normalized modules
+//! returned by the node, which omit private functions. Structs, fields and function
+//! signatures use the normalized types of the Move bytecode format, so types print as in
+//! Move source, e.g. `&mut 0x2::tx_context::TxContext`.
+
+use std::collections::BTreeMap;
+
+use anyhow::bail;
+use move_binary_format::access::ModuleAccess;
+use move_binary_format::normalized;
+use move_binary_format::CompiledModule;
+use move_core_types::language_storage::ModuleId;
+
+use sui_json_rpc_types::SuiRawData;
+use sui_types::base_types::ObjectID;
+
+pub use move_binary_format::file_format::{Ability, AbilitySet, Visibility};
+pub use move_binary_format::normalized::{Field, Function, Struct, Type};
+
+use crate::error::{Error, SuiRpcResult};
+use crate::ReadApi;
+
+#[derive(Debug, Clone)]
+pub struct ModuleInfo {
+    pub id: ModuleId,
+    pub friends: Vec<ModuleId>,
+    pub structs: BTreeMap<String, Struct>,
+    /// Every function of the module, including private ones.
+    pub functions: BTreeMap<String, Function>,
+}
+
+impl ModuleInfo {
+    pub fn from_bytecode(bytes: &[u8]) -> SuiRpcResult<Self> {
+        Ok(Self::from_compiled(&CompiledModule::deserialize(bytes)?))
+    }
+
+    pub fn from_compiled(module: &CompiledModule) -> Self {
+        let normalized = normalized::Module::new(module);
+        let functions = module
+            .function_defs()
+            .iter()
+            .map(|def| {
+                let (name, function) = Function::new(module, def);
+                (name.to_string(), function)
+            })
+            .collect();
+        Self {
+            id: module.self_id(),
+            friends: normalized.friends,
+            structs: normalized
+                .structs
+                .into_iter()
+                .map(|(name, struct_)| (name.to_string(), struct_))
+                .collect(),
+            functions,
+        }
+    }
+
+    /// Functions callable from other packages, including entry functions.
+    pub fn public_functions(&self) -> impl Iterator<Item = (&String, &Function)> {
+        self.functions
+            .iter()
+            .filter(|(_, function)| function.visibility == Visibility::Public)
+    }
+
+    /// Functions callable in a transaction.
+    pub fn entry_functions(&self) -> impl Iterator<Item = (&String, &Function)> {
+        self.functions
+            .iter()
+            .filter(|(_, function)| function.is_entry)
+    }
+
+    /// The signature of `function` as declared in Move, e.g.
+    /// `public entry fun mint(u64, &mut 0x2::tx_context::TxContext)`.
+    pub fn signature(&self, function: &str) -> Option<String> {
+        let (name, function) = self.functions.get_key_value(function)?;
+        let mut signature = match function.visibility {
+            Visibility::Private => String::new(),
+            Visibility::Public => "public ".to_string(),
+            Visibility::Friend => "public(friend) ".to_string(),
+        };
+        if function.is_entry {
+            signature.push_str("entry ");
+        }
+        signature.push_str("fun ");
+        signature.push_str(name);
+        if !function.type_parameters.is_empty() {
+            let type_parameters = function
+                .type_parameters
+                .iter()
+                .enumerate()
+                .map(|(i, abilities)| match abilities_str(abilities) {
+                    abilities if abilities.is_empty() => format!("T{i}"),
+                    abilities => format!("T{i}: {abilities}"),
+                })
+                .collect::<Vec<_>>();
+            signature.push_str(&format!("<{}>", type_parameters.join(", ")));
+        }
+        signature.push_str(&format!("({})", types_str(&function.parameters)));
+        match function.return_.as_slice() {
+            [] => {}
+            [type_] => signature.push_str(&format!(": {type_}")),
+            types => signature.push_str(&format!(": ({})", types_str(types))),
+        }
+        Some(signature)
+    }
+}
+
+/// `abilities` as declared in Move, e.g. `copy + drop`.
+pub fn abilities_str(abilities: &AbilitySet) -> String {
+    abilities
+        .into_iter()
+        .map(|ability| match ability {
+            Ability::Copy => "copy",
+            Ability::Drop => "drop",
+            Ability::Store => "store",
+            Ability::Key => "key",
+        })
+        .collect::<Vec<_>>()
+        .join(" + ")
+}
+
+fn types_str(types: &[Type]) -> String {
+    types
+        .iter()
+        .map(|type_| type_.to_string())
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// The bytecode of the modules of `package_id`, keyed by module name.
+pub(crate) async fn package_bytecode(
+    read_api: &ReadApi,
+    package_id: ObjectID,
+) -> anyhow::Result<BTreeMap<String, Vec<u8>>> {
+    match read_api.get_object(package_id).await?.into_object()?.data {
+        SuiRawData::Package(package) => Ok(package.module_map),
+        SuiRawData::MoveObject(_) => {
+            bail!("Object [{package_id}] is not a package.")
+        }
+    }
+}
+
+impl ReadApi {
+    /// The modules of the package `package_id`, keyed by module name.
+    pub async fn get_package_modules(
+        &self,
+        package_id: ObjectID,
+    ) -> SuiRpcResult<BTreeMap<String, ModuleInfo>> {
+        package_bytecode(self, package_id)
+            .await?
+            .into_iter()
+            .map(|(name, bytes)| Ok((name, ModuleInfo::from_bytecode(&bytes)?)))
+            .collect()
+    }
+}
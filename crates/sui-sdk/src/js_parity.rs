@@ -0,0 +1,91 @@
+// Copyright (c) 2022, Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Encodings byte-identical to the TypeScript SDK, `@mysten/sui.js`, so systems built on
+//! both SDKs can verify each other's payloads.
+//!
+//! Transaction data is exchanged as the Base64 encoded signable bytes, `TransactionData::`
+//! followed by the BCS encoding, as produced by the `LocalTxnDataSerializer`. Those bytes
+//! are also the signed message: this version of the protocol has no intent messages.
+//! Signatures are exchanged as the `SignaturePubkeyPair` of the TypeScript signers, and
+//! addresses as unprefixed lower case hex, as returned by `PublicKey.toSuiAddress`.
+//!
+//! The vectors shared with the TypeScript SDK tests are in `tests/data/sui_js_vectors.json`.
+
+use std::str::FromStr;
+
+use anyhow::anyhow;
+use serde::{Deserialize, Serialize};
+
+use sui_types::base_types::SuiAddress;
+use sui_types::crypto::{SignableBytes, Signature, SignatureScheme, SuiSignature, ToFromBytes};
+use sui_types::messages::TransactionData;
+use sui_types::sui_serde::{Base64, Encoding};
+
+use crate::error::{Error, SuiRpcResult};
+
+/// A signature as returned by `Signer.signData`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SignaturePubkeyPair {
+    /// `ED25519` or `Secp256k1`.
+    pub signature_scheme: String,
+    /// The Base64 encoded signature, without flag or public key.
+    pub signature: String,
+    /// The Base64 encoded public key.
+    pub pub_key: String,
+}
+
+impl SignaturePubkeyPair {
+    pub fn from_signature(signature: &Signature) -> Self {
+        let scheme = match signature.scheme() {
+            SignatureScheme::ED25519 => "ED25519",
+            SignatureScheme::Secp256k1 => "Secp256k1",
+            SignatureScheme::BLS12381 => "BLS12381",
+        };
+        Self {
+            signature_scheme: scheme.to_string(),
+            signature: Base64::encode(signature.signature_bytes()),
+            pub_key: Base64::encode(signature.public_key_bytes()),
+        }
+    }
+
+    pub fn to_signature(&self) -> SuiRpcResult<Signature> {
+        let scheme = match self.signature_scheme.as_str() {
+            "ED25519" => sui_sdk_core::SignatureScheme::Ed25519,
+            "Secp256k1" => sui_sdk_core::SignatureScheme::Secp256k1,
+            scheme => {
+                return Err(Error::Other(anyhow!(
+                    "Unsupported signature scheme [{scheme}]."
+                )))
+            }
+        };
+        let bytes = sui_sdk_core::assemble_signature(
+            scheme,
+            &Base64::decode(&self.signature)?,
+            &Base64::decode(&self.pub_key)?,
+        )?;
+        Ok(Signature::from_bytes(&bytes)?)
+    }
+}
+
+/// Encode transaction data as the TypeScript serializers do.
+pub fn encode_transaction_data(data: &TransactionData) -> String {
+    Base64::encode(data.to_bytes())
+}
+
+pub fn decode_transaction_data(tx_bytes: &str) -> SuiRpcResult<TransactionData> {
+    Ok(TransactionData::from_signable_bytes(&Base64::decode(
+        tx_bytes,
+    )?)?)
+}
+
+/// Encode an address as `PublicKey.toSuiAddress` does, without the `0x` prefix.
+pub fn encode_address(address: &SuiAddress) -> String {
+    format!("{:x}", address)
+}
+
+/// Decode an address, with or without the `0x` prefix.
+pub fn decode_address(address: &str) -> SuiRpcResult<SuiAddress> {
+    Ok(SuiAddress::from_str(address)?)
+}
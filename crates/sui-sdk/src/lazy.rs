@@ -0,0 +1,215 @@
+// Copyright (c) 2022, Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! RPC responses kept as the JSON text received from the node, and only deserialized when
+//! accessed.
+//!
+//! Large object contents are expensive to convert into [SuiParsedData] trees when a caller
+//! only needs a couple of fields. A [LazyResponse] holds the raw response, lets callers
+//! extract single fields, borrowing from the raw text wherever the target type allows
+//! (e.g. `&str` or `&RawValue`), and only builds the full response struct on
+//! [LazyResponse::parse].
+//!
+//! [SuiClient::request_lazy] calls any method, including methods this version of the SDK
+//! does not know. Responses parsed as [MaybeUnknown] items keep the raw JSON of values this
+//! version cannot deserialize, e.g. enum variants added by a newer node.
+//!
+//! A [WithRaw] response holds both the deserialized value and the raw JSON it came from,
+//! e.g. to debug schema mismatches or to forward responses unchanged from a proxy.
+//!
+//! [SuiParsedData]: sui_json_rpc_types::SuiParsedData
+//! [MaybeUnknown]: sui_json_rpc_types::MaybeUnknown
+
+use std::collections::HashMap;
+use std::marker::PhantomData;
+use std::ops::Deref;
+
+use jsonrpsee::core::client::ClientT;
+use jsonrpsee::rpc_params;
+use jsonrpsee::types::ParamsSer;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::value::{to_raw_value, RawValue};
+
+use sui_json_rpc_types::{GetObjectDataResponse, SuiTransactionResponse};
+use sui_types::base_types::{ObjectID, TransactionDigest};
+
+use crate::error::{Error, SuiRpcResult};
+use crate::transport::RpcHttpClient;
+use crate::{ReadApi, SuiClient, SuiClientApi};
+
+/// A response of type `T` whose deserialization is deferred.
+pub struct LazyResponse<T> {
+    raw: Box<RawValue>,
+    _response: PhantomData<fn() -> T>,
+}
+
+impl<T> LazyResponse<T> {
+    pub fn from_raw(raw: Box<RawValue>) -> Self {
+        Self {
+            raw,
+            _response: PhantomData,
+        }
+    }
+
+    /// The JSON text of the response.
+    pub fn raw(&self) -> &str {
+        self.raw.get()
+    }
+
+    /// The raw JSON of the value at `path`, a list of object keys, without parsing any value
+    /// beside the objects along the path. Returns `None` if a key is missing.
+    pub fn raw_field(&self, path: &[&str]) -> SuiRpcResult<Option<&RawValue>> {
+        let mut current: &RawValue = &self.raw;
+        for key in path {
+            let fields: HashMap<String, &RawValue> = serde_json::from_str(current.get())?;
+            match fields.get(*key) {
+                Some(value) => current = value,
+                None => return Ok(None),
+            }
+        }
+        Ok(Some(current))
+    }
+
+    /// Deserialize the value at `path`, borrowing from the raw response where `F` allows.
+    pub fn field<'a, F: Deserialize<'a>>(&'a self, path: &[&str]) -> SuiRpcResult<Option<F>> {
+        Ok(match self.raw_field(path)? {
+            Some(value) => Some(serde_json::from_str(value.get())?),
+            None => None,
+        })
+    }
+}
+
+impl<T: Serialize> LazyResponse<T> {
+    /// Wrap an already deserialized response, e.g. from the embedded gateway.
+    pub fn from_value(value: &T) -> SuiRpcResult<Self> {
+        Ok(Self::from_raw(to_raw_value(value)?))
+    }
+}
+
+impl<T: DeserializeOwned> LazyResponse<T> {
+    /// Deserialize the whole response.
+    pub fn parse(&self) -> SuiRpcResult<T> {
+        Ok(serde_json::from_str(self.raw.get())?)
+    }
+
+    /// Deserialize the whole response, keeping the raw JSON alongside.
+    pub fn with_raw(self) -> SuiRpcResult<WithRaw<T>> {
+        Ok(WithRaw {
+            value: self.parse()?,
+            raw: self.raw,
+        })
+    }
+}
+
+/// A deserialized response along with the JSON text it was deserialized from.
+#[derive(Debug)]
+pub struct WithRaw<T> {
+    pub value: T,
+    pub raw: Box<RawValue>,
+}
+
+impl<T> WithRaw<T> {
+    /// The JSON text of the response.
+    pub fn raw(&self) -> &str {
+        self.raw.get()
+    }
+
+    pub fn into_inner(self) -> T {
+        self.value
+    }
+}
+
+impl<T> Deref for WithRaw<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.value
+    }
+}
+
+async fn request_raw<T>(
+    client: &RpcHttpClient,
+    method: &str,
+    params: Option<ParamsSer<'_>>,
+) -> SuiRpcResult<LazyResponse<T>> {
+    let raw: Box<RawValue> = client.request(method, params).await?;
+    Ok(LazyResponse::from_raw(raw))
+}
+
+impl SuiClient {
+    /// Call the JSON-RPC method `method` with positional `params`, deferring deserialization
+    /// of the response, e.g. as a `Vec<MaybeUnknown<SuiEventEnvelope>>`.
+    pub async fn request_lazy<T>(
+        &self,
+        method: &str,
+        params: Vec<serde_json::Value>,
+    ) -> SuiRpcResult<LazyResponse<T>> {
+        match &*self.api {
+            SuiClientApi::Rpc(c) => {
+                let raw: Box<RawValue> = c
+                    .http
+                    .request(method, Some(ParamsSer::Array(params)))
+                    .await?;
+                Ok(LazyResponse::from_raw(raw))
+            }
+            SuiClientApi::Embedded(_) => Err(Error::Unsupported("embedded gateway client")),
+        }
+    }
+
+    /// Call the JSON-RPC method `method` with positional `params`, returning the deserialized
+    /// response along with its raw JSON.
+    pub async fn request_with_raw<T: DeserializeOwned>(
+        &self,
+        method: &str,
+        params: Vec<serde_json::Value>,
+    ) -> SuiRpcResult<WithRaw<T>> {
+        Ok(self.request_lazy(method, params).await?.with_raw()?)
+    }
+}
+
+impl ReadApi {
+    /// Like [ReadApi::get_parsed_object], deferring deserialization of the response.
+    pub async fn get_parsed_object_lazy(
+        &self,
+        object_id: ObjectID,
+    ) -> SuiRpcResult<LazyResponse<GetObjectDataResponse>> {
+        match &*self.api {
+            SuiClientApi::Rpc(c) => {
+                request_raw(&c.http, "sui_getObject", rpc_params![object_id]).await
+            }
+            SuiClientApi::Embedded(c) => LazyResponse::from_value(&c.get_object(object_id).await?),
+        }
+    }
+
+    /// Like [ReadApi::get_transaction], deferring deserialization of the response.
+    pub async fn get_transaction_lazy(
+        &self,
+        digest: TransactionDigest,
+    ) -> SuiRpcResult<LazyResponse<SuiTransactionResponse>> {
+        match &*self.api {
+            SuiClientApi::Rpc(c) => {
+                request_raw(&c.http, "sui_getTransaction", rpc_params![digest]).await
+            }
+            SuiClientApi::Embedded(c) => {
+                LazyResponse::from_value(&c.get_transaction(digest).await?)
+            }
+        }
+    }
+
+    /// Like [ReadApi::get_parsed_object], keeping the raw JSON of the response.
+    pub async fn get_parsed_object_with_raw(
+        &self,
+        object_id: ObjectID,
+    ) -> SuiRpcResult<WithRaw<GetObjectDataResponse>> {
+        self.get_parsed_object_lazy(object_id).await?.with_raw()
+    }
+
+    /// Like [ReadApi::get_transaction], keeping the raw JSON of the response.
+    pub async fn get_transaction_with_raw(
+        &self,
+        digest: TransactionDigest,
+    ) -> SuiRpcResult<WithRaw<SuiTransactionResponse>> {
+        self.get_transaction_lazy(digest).await?.with_raw()
+    }
+}
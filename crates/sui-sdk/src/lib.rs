@@ -1,540 +1,162 @@
 // Copyright (c) 2022, Mysten Labs, Inc.
 // SPDX-License-Identifier: Apache-2.0
 
-use std::fmt::{Debug, Write};
-use std::fmt::{Display, Formatter};
-use std::sync::Arc;
+//! The RPC client is behind the default `client` feature. Without default features, the
+//! crate keeps key management, signing and encoding only. The `signing-only` feature adds the
+//! construction of transactions from object references the caller holds, in
+//! [offline_builder]. Building transactions from object IDs, reading the references from a
+//! node, needs `client`.
 
-use anyhow::anyhow;
-use futures::StreamExt;
-use futures_core::Stream;
-use jsonrpsee::core::client::{ClientT, Subscription};
-use jsonrpsee::http_client::{HttpClient, HttpClientBuilder};
-use jsonrpsee::ws_client::{WsClient, WsClientBuilder};
-use serde::Deserialize;
-use serde::Serialize;
-use serde_json::Value;
-
-use rpc_types::{GetPastObjectDataResponse, SuiExecuteTransactionResponse};
-pub use sui_config::gateway;
-use sui_config::gateway::GatewayConfig;
-use sui_core::gateway_state::{GatewayClient, GatewayState};
+// re-export essential sui crates
 pub use sui_json as json;
-use sui_json_rpc::api::EventStreamingApiClient;
-use sui_json_rpc::api::RpcBcsApiClient;
-use sui_json_rpc::api::RpcFullNodeReadApiClient;
-use sui_json_rpc::api::RpcGatewayApiClient;
-use sui_json_rpc::api::RpcReadApiClient;
-use sui_json_rpc::api::TransactionExecutionApiClient;
-use sui_json_rpc::api::WalletSyncApiClient;
 pub use sui_json_rpc_types as rpc_types;
-use sui_json_rpc_types::{
-    GatewayTxSeqNumber, GetObjectDataResponse, GetRawObjectDataResponse, SuiEventEnvelope,
-    SuiEventFilter, SuiObjectInfo, SuiTransactionResponse,
-};
 pub use sui_types as types;
-use sui_types::base_types::{ObjectID, SuiAddress, TransactionDigest};
-use sui_types::messages::Transaction;
-use types::base_types::SequenceNumber;
-use types::messages::ExecuteTransactionRequestType;
 
-use crate::transaction_builder::TransactionBuilder;
+pub use crate::error::{Error, SuiRpcResult};
+#[cfg(feature = "client")]
+pub use sui_config::gateway;
 
-// re-export essential sui crates
+#[cfg(feature = "client")]
+pub use crate::client::*;
+#[cfg(feature = "client")]
+pub(crate) use crate::client::{RpcClient, SuiClientApi};
+#[cfg(feature = "signing-only")]
+pub use crate::offline_builder::ValidatorRegistration;
+
+#[cfg(feature = "client")]
+pub mod accounting;
+pub mod address;
+#[cfg(feature = "client")]
+pub mod airdrop;
+pub mod amount;
+#[cfg(feature = "client")]
+pub mod api;
+#[cfg(feature = "client")]
+pub mod backpressure;
+#[cfg(feature = "client")]
+pub mod balance_changes;
+#[cfg(feature = "client")]
+pub mod bulk;
+#[cfg(feature = "client")]
+pub mod cache;
+#[cfg(feature = "client")]
+pub mod canary;
+#[cfg(feature = "client")]
+pub mod cancellation;
+#[cfg(feature = "client")]
+pub mod circuit_breaker;
+#[cfg(feature = "client")]
+mod client;
+#[cfg(feature = "client")]
+pub mod coin_registry;
+pub mod coin_type;
+#[cfg(feature = "client")]
+pub mod config;
+#[cfg(feature = "client")]
+pub mod consistency;
 pub mod crypto;
+#[cfg(feature = "client")]
+pub mod cursor_store;
+#[cfg(feature = "client")]
+pub mod deposit_tracker;
+#[cfg(feature = "client")]
+pub mod dex;
+pub mod digest;
+#[cfg(feature = "client")]
+pub mod dns;
+pub mod encrypted_store;
+#[cfg(feature = "client")]
+pub mod epochs;
+pub mod error;
+#[cfg(feature = "client")]
+pub mod export;
+#[cfg(feature = "client")]
+pub mod fan_in;
+#[cfg(feature = "client")]
+pub mod fee_analytics;
+#[cfg(feature = "client")]
+pub mod follower;
+#[cfg(feature = "fuzzing")]
+pub mod fuzzing;
+#[cfg(feature = "client")]
+pub mod gas_coins;
+#[cfg(feature = "client")]
+pub mod gas_station;
+#[cfg(feature = "client")]
+pub mod hedging;
+#[cfg(feature = "client")]
+pub mod idempotent;
+#[cfg(feature = "client")]
+pub mod indexer;
+#[cfg(feature = "client")]
+pub mod introspection;
+pub mod js_parity;
+#[cfg(feature = "client")]
+pub mod lazy;
+#[cfg(feature = "client")]
+pub mod local_store;
+#[cfg(feature = "client")]
+pub mod move_call_validation;
+pub mod move_json;
+#[cfg(feature = "client")]
+pub mod nft;
+#[cfg(feature = "client")]
+pub mod nft_media;
+#[cfg(feature = "client")]
+pub mod object_query;
+#[cfg(feature = "client")]
+pub mod object_stream;
+#[cfg(feature = "signing-only")]
+pub mod offline_builder;
+#[cfg(feature = "client")]
+pub mod oracle;
+#[cfg(feature = "client")]
+pub mod owned_objects;
+#[cfg(feature = "client")]
+pub mod ownership_graph;
+#[cfg(feature = "client")]
+pub mod payment_request;
+#[cfg(feature = "client")]
+pub mod portfolio;
+pub mod preflight;
+#[cfg(feature = "client")]
+pub mod remote_signer;
+#[cfg(feature = "client")]
+pub mod request_log;
+#[cfg(feature = "client")]
+pub mod runtime;
+#[cfg(feature = "test-support")]
+pub mod scenario;
+#[cfg(feature = "client")]
+pub mod scheduler;
+pub mod signing_policy;
+#[cfg(feature = "client")]
+pub mod source_verification;
+#[cfg(feature = "client")]
+pub mod spending_guard;
+#[cfg(feature = "client")]
+pub mod sqlite_store;
+#[cfg(feature = "client")]
+pub mod staking;
+#[cfg(feature = "client")]
+pub mod sweep;
+#[cfg(feature = "client")]
+pub mod system_state;
+#[cfg(feature = "test-support")]
+pub mod test_network;
+pub mod test_vectors;
+#[cfg(feature = "client")]
+pub mod tracer;
+#[cfg(feature = "client")]
+pub mod tracking;
+#[cfg(feature = "client")]
 mod transaction_builder;
-
-pub struct SuiClient {
-    api: Arc<SuiClientApi>,
-    transaction_builder: TransactionBuilder,
-    read_api: Arc<ReadApi>,
-    full_node_api: FullNodeApi,
-    event_api: EventApi,
-    quorum_driver: QuorumDriver,
-    wallet_sync_api: WalletSyncApi,
-}
-
-#[allow(clippy::large_enum_variant)]
-enum SuiClientApi {
-    Rpc(RpcClient),
-    Embedded(GatewayClient),
-}
-
-impl Debug for SuiClientApi {
-    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
-        match self {
-            SuiClientApi::Rpc(rpc_client) => write!(
-                f,
-                "RPC client. Http: {:?}, Websocket: {:?}",
-                rpc_client.http, rpc_client.ws
-            ),
-            SuiClientApi::Embedded(_) => write!(f, "Embedded Gateway client."),
-        }
-    }
-}
-
-struct RpcClient {
-    http: HttpClient,
-    ws: Option<WsClient>,
-    info: ServerInfo,
-}
-
-struct ServerInfo {
-    rpc_methods: Vec<String>,
-    subscriptions: Vec<String>,
-    version: String,
-}
-
-impl RpcClient {
-    pub async fn new(http: &str, ws: Option<&str>) -> Result<Self, anyhow::Error> {
-        let http = HttpClientBuilder::default().build(http)?;
-        let ws = if let Some(url) = ws {
-            Some(WsClientBuilder::default().build(url).await?)
-        } else {
-            None
-        };
-        let info = Self::get_server_info(&http, &ws).await?;
-        Ok(Self { http, ws, info })
-    }
-
-    async fn get_server_info(
-        http: &HttpClient,
-        ws: &Option<WsClient>,
-    ) -> Result<ServerInfo, anyhow::Error> {
-        let rpc_spec: Value = http
-            .request("rpc.discover", None)
-            .await
-            .map_err(|e| anyhow!("Fail to connect to the RPC server: {e}"))?;
-        let version = rpc_spec
-            .pointer("/info/version")
-            .and_then(|v| v.as_str())
-            .ok_or_else(|| anyhow!("Fail parsing server version from rpc.discover endpoint."))?;
-        let rpc_methods = Self::parse_methods(&rpc_spec)?;
-
-        let subscriptions = if let Some(ws) = ws {
-            let rpc_spec: Value = ws
-                .request("rpc.discover", None)
-                .await
-                .map_err(|e| anyhow!("Fail to connect to the Websocket server: {e}"))?;
-            Self::parse_methods(&rpc_spec)?
-        } else {
-            Vec::new()
-        };
-        Ok(ServerInfo {
-            rpc_methods,
-            subscriptions,
-            version: version.to_string(),
-        })
-    }
-
-    fn parse_methods(server_spec: &Value) -> Result<Vec<String>, anyhow::Error> {
-        let methods = server_spec
-            .pointer("/methods")
-            .and_then(|methods| methods.as_array())
-            .ok_or_else(|| {
-                anyhow!("Fail parsing server information from rpc.discover endpoint.")
-            })?;
-
-        Ok(methods
-            .iter()
-            .flat_map(|method| method["name"].as_str())
-            .map(|s| s.into())
-            .collect())
-    }
-
-    fn is_gateway(&self) -> bool {
-        self.info
-            .rpc_methods
-            .contains(&"sui_syncAccountState".to_string())
-    }
-}
-
-impl SuiClient {
-    pub async fn new_rpc_client(
-        http_url: &str,
-        ws_url: Option<&str>,
-    ) -> Result<SuiClient, anyhow::Error> {
-        let rpc = RpcClient::new(http_url, ws_url).await?;
-        Ok(SuiClient::new(SuiClientApi::Rpc(rpc)))
-    }
-
-    pub fn new_embedded_client(config: &GatewayConfig) -> Result<SuiClient, anyhow::Error> {
-        let state = GatewayState::create_client(config, None)?;
-        Ok(SuiClient::new(SuiClientApi::Embedded(state)))
-    }
-
-    fn new(api: SuiClientApi) -> Self {
-        let api = Arc::new(api);
-        let read_api = Arc::new(ReadApi { api: api.clone() });
-        let quorum_driver = QuorumDriver { api: api.clone() };
-
-        let full_node_api = FullNodeApi(api.clone());
-        let event_api = EventApi(api.clone());
-        let transaction_builder = TransactionBuilder(read_api.clone());
-        let wallet_sync_api = WalletSyncApi(api.clone());
-
-        SuiClient {
-            api,
-            transaction_builder,
-            read_api,
-            full_node_api,
-            event_api,
-            quorum_driver,
-            wallet_sync_api,
-        }
-    }
-
-    pub fn is_gateway(&self) -> bool {
-        match &*self.api {
-            SuiClientApi::Rpc(c) => c.is_gateway(),
-            SuiClientApi::Embedded(_) => true,
-        }
-    }
-
-    pub fn available_rpc_methods(&self) -> Vec<String> {
-        match &*self.api {
-            SuiClientApi::Rpc(c) => c.info.rpc_methods.clone(),
-            SuiClientApi::Embedded(_) => vec![],
-        }
-    }
-
-    pub fn available_subscriptions(&self) -> Vec<String> {
-        match &*self.api {
-            SuiClientApi::Rpc(c) => c.info.subscriptions.clone(),
-            SuiClientApi::Embedded(_) => vec![],
-        }
-    }
-
-    pub fn api_version(&self) -> String {
-        match &*self.api {
-            SuiClientApi::Rpc(c) => c.info.version.clone(),
-            SuiClientApi::Embedded(_) => env!("CARGO_PKG_VERSION").to_owned(),
-        }
-    }
-}
-
-#[derive(Debug)]
-pub struct ReadApi {
-    api: Arc<SuiClientApi>,
-}
-
-impl ReadApi {
-    pub async fn get_objects_owned_by_address(
-        &self,
-        address: SuiAddress,
-    ) -> anyhow::Result<Vec<SuiObjectInfo>> {
-        Ok(match &*self.api {
-            SuiClientApi::Rpc(c) => c.http.get_objects_owned_by_address(address).await?,
-            SuiClientApi::Embedded(c) => c.get_objects_owned_by_address(address).await?,
-        })
-    }
-
-    pub async fn get_objects_owned_by_object(
-        &self,
-        object_id: ObjectID,
-    ) -> anyhow::Result<Vec<SuiObjectInfo>> {
-        Ok(match &*self.api {
-            SuiClientApi::Rpc(c) => c.http.get_objects_owned_by_object(object_id).await?,
-            SuiClientApi::Embedded(c) => c.get_objects_owned_by_object(object_id).await?,
-        })
-    }
-
-    pub async fn get_parsed_object(
-        &self,
-        object_id: ObjectID,
-    ) -> anyhow::Result<GetObjectDataResponse> {
-        Ok(match &*self.api {
-            SuiClientApi::Rpc(c) => c.http.get_object(object_id).await?,
-            SuiClientApi::Embedded(c) => c.get_object(object_id).await?,
-        })
-    }
-
-    pub async fn try_get_parsed_past_object(
-        &self,
-        object_id: ObjectID,
-        version: SequenceNumber,
-    ) -> anyhow::Result<GetPastObjectDataResponse> {
-        Ok(match &*self.api {
-            SuiClientApi::Rpc(c) => c.http.try_get_past_object(object_id, version).await?,
-            // Gateway does not support get past object
-            SuiClientApi::Embedded(_) => {
-                unimplemented!("Gateway/embedded client does not support get past object")
-            }
-        })
-    }
-
-    pub async fn get_object(
-        &self,
-        object_id: ObjectID,
-    ) -> anyhow::Result<GetRawObjectDataResponse> {
-        Ok(match &*self.api {
-            SuiClientApi::Rpc(c) => c.http.get_raw_object(object_id).await?,
-            SuiClientApi::Embedded(c) => c.get_raw_object(object_id).await?,
-        })
-    }
-
-    pub async fn get_total_transaction_number(&self) -> anyhow::Result<u64> {
-        Ok(match &*self.api {
-            SuiClientApi::Rpc(c) => c.http.get_total_transaction_number().await?,
-            SuiClientApi::Embedded(c) => c.get_total_transaction_number()?,
-        })
-    }
-
-    pub async fn get_transactions_in_range(
-        &self,
-        start: GatewayTxSeqNumber,
-        end: GatewayTxSeqNumber,
-    ) -> anyhow::Result<Vec<(GatewayTxSeqNumber, TransactionDigest)>> {
-        Ok(match &*self.api {
-            SuiClientApi::Rpc(c) => c.http.get_transactions_in_range(start, end).await?,
-            SuiClientApi::Embedded(c) => c.get_transactions_in_range(start, end)?,
-        })
-    }
-
-    pub async fn get_recent_transactions(
-        &self,
-        count: u64,
-    ) -> anyhow::Result<Vec<(GatewayTxSeqNumber, TransactionDigest)>> {
-        Ok(match &*self.api {
-            SuiClientApi::Rpc(c) => c.http.get_recent_transactions(count).await?,
-            SuiClientApi::Embedded(c) => c.get_recent_transactions(count)?,
-        })
-    }
-
-    pub async fn get_transaction(
-        &self,
-        digest: TransactionDigest,
-    ) -> anyhow::Result<SuiTransactionResponse> {
-        Ok(match &*self.api {
-            SuiClientApi::Rpc(c) => c.http.get_transaction(digest).await?,
-            SuiClientApi::Embedded(c) => c.get_transaction(digest).await?,
-        })
-    }
-}
-
-pub struct FullNodeApi(Arc<SuiClientApi>);
-
-impl FullNodeApi {
-    pub async fn get_transactions_by_input_object(
-        &self,
-        object: ObjectID,
-    ) -> anyhow::Result<Vec<(GatewayTxSeqNumber, TransactionDigest)>> {
-        Ok(match &*self.0 {
-            SuiClientApi::Rpc(c) => c.http.get_transactions_by_input_object(object).await?,
-            SuiClientApi::Embedded(_) => {
-                return Err(anyhow!("Method not supported by embedded gateway client."))
-            }
-        })
-    }
-
-    pub async fn get_transactions_by_mutated_object(
-        &self,
-        object: ObjectID,
-    ) -> anyhow::Result<Vec<(GatewayTxSeqNumber, TransactionDigest)>> {
-        Ok(match &*self.0 {
-            SuiClientApi::Rpc(c) => c.http.get_transactions_by_mutated_object(object),
-            SuiClientApi::Embedded(_) => {
-                return Err(anyhow!("Method not supported by embedded gateway client."))
-            }
-        }
-        .await?)
-    }
-
-    pub async fn get_transactions_by_move_function(
-        &self,
-        package: ObjectID,
-        module: Option<String>,
-        function: Option<String>,
-    ) -> anyhow::Result<Vec<(GatewayTxSeqNumber, TransactionDigest)>> {
-        Ok(match &*self.0 {
-            SuiClientApi::Rpc(c) => c
-                .http
-                .get_transactions_by_move_function(package, module, function),
-            SuiClientApi::Embedded(_) => {
-                return Err(anyhow!("Method not supported by embedded gateway client."))
-            }
-        }
-        .await?)
-    }
-
-    pub async fn get_transactions_from_addr(
-        &self,
-        addr: SuiAddress,
-    ) -> anyhow::Result<Vec<(GatewayTxSeqNumber, TransactionDigest)>> {
-        Ok(match &*self.0 {
-            SuiClientApi::Rpc(c) => c.http.get_transactions_from_addr(addr),
-            SuiClientApi::Embedded(_) => {
-                return Err(anyhow!("Method not supported by embedded gateway client."))
-            }
-        }
-        .await?)
-    }
-
-    pub async fn get_transactions_to_addr(
-        &self,
-        addr: SuiAddress,
-    ) -> anyhow::Result<Vec<(GatewayTxSeqNumber, TransactionDigest)>> {
-        Ok(match &*self.0 {
-            SuiClientApi::Rpc(c) => c.http.get_transactions_to_addr(addr),
-            SuiClientApi::Embedded(_) => {
-                return Err(anyhow!("Method not supported by embedded gateway client."))
-            }
-        }
-        .await?)
-    }
-}
-pub struct EventApi(Arc<SuiClientApi>);
-
-impl EventApi {
-    pub async fn subscribe_event(
-        &self,
-        filter: SuiEventFilter,
-    ) -> anyhow::Result<impl Stream<Item = Result<SuiEventEnvelope, anyhow::Error>>> {
-        match &*self.0 {
-            SuiClientApi::Rpc(RpcClient { ws: Some(c), .. }) => {
-                let subscription: Subscription<SuiEventEnvelope> =
-                    c.subscribe_event(filter).await?;
-                Ok(subscription.map(|item| Ok(item?)))
-            }
-            _ => Err(anyhow!("Subscription only supported by WebSocket client.")),
-        }
-    }
-}
-pub struct QuorumDriver {
-    api: Arc<SuiClientApi>,
-}
-
-impl QuorumDriver {
-    pub async fn execute_transaction(
-        &self,
-        tx: Transaction,
-    ) -> anyhow::Result<SuiTransactionResponse> {
-        Ok(match &*self.api {
-            SuiClientApi::Rpc(c) => {
-                let (tx_bytes, flag, signature, pub_key) = tx.to_network_data_for_execution();
-                RpcGatewayApiClient::execute_transaction(
-                    &c.http, tx_bytes, flag, signature, pub_key,
-                )
-                .await?
-            }
-            SuiClientApi::Embedded(c) => c.execute_transaction(tx).await?,
-        })
-    }
-
-    pub async fn execute_transaction_by_fullnode(
-        &self,
-        tx: Transaction,
-        request_type: ExecuteTransactionRequestType,
-    ) -> anyhow::Result<SuiExecuteTransactionResponse> {
-        Ok(match &*self.api {
-            SuiClientApi::Rpc(c) => {
-                let (tx_bytes, flag, signature, pub_key) = tx.to_network_data_for_execution();
-                TransactionExecutionApiClient::execute_transaction(
-                    &c.http,
-                    tx_bytes,
-                    flag,
-                    signature,
-                    pub_key,
-                    request_type,
-                )
-                .await?
-            }
-            // TODO do we want to support an embedded quorum driver?
-            SuiClientApi::Embedded(_c) => unimplemented!(),
-        })
-    }
-}
-
-pub struct WalletSyncApi(Arc<SuiClientApi>);
-
-impl WalletSyncApi {
-    pub async fn sync_account_state(&self, address: SuiAddress) -> anyhow::Result<()> {
-        match &*self.0 {
-            SuiClientApi::Rpc(c) => {
-                if c.is_gateway() {
-                    c.http.sync_account_state(address).await?
-                }
-            }
-            SuiClientApi::Embedded(c) => c.sync_account_state(address).await?,
-        }
-        Ok(())
-    }
-}
-
-impl SuiClient {
-    pub fn transaction_builder(&self) -> &TransactionBuilder {
-        &self.transaction_builder
-    }
-    pub fn read_api(&self) -> &ReadApi {
-        &self.read_api
-    }
-    pub fn full_node_api(&self) -> &FullNodeApi {
-        &self.full_node_api
-    }
-    pub fn event_api(&self) -> &EventApi {
-        &self.event_api
-    }
-    pub fn quorum_driver(&self) -> &QuorumDriver {
-        &self.quorum_driver
-    }
-    pub fn wallet_sync_api(&self) -> &WalletSyncApi {
-        &self.wallet_sync_api
-    }
-}
-
-#[derive(Serialize, Deserialize)]
-#[serde(rename_all = "lowercase")]
-pub enum ClientType {
-    Embedded(GatewayConfig),
-    RPC(
-        String,
-        #[serde(default, skip_serializing_if = "Option::is_none")] Option<String>,
-    ),
-}
-
-impl Display for ClientType {
-    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
-        let mut writer = String::new();
-
-        match self {
-            ClientType::Embedded(config) => {
-                writeln!(writer, "Client Type : Embedded Gateway")?;
-                writeln!(
-                    writer,
-                    "Gateway state DB folder path : {:?}",
-                    config.db_folder_path
-                )?;
-                let authorities = config
-                    .validator_set
-                    .iter()
-                    .map(|info| info.network_address());
-                write!(
-                    writer,
-                    "Authorities : {:?}",
-                    authorities.collect::<Vec<_>>()
-                )?;
-            }
-            ClientType::RPC(url, ws_url) => {
-                writeln!(writer, "Client Type : JSON-RPC")?;
-                writeln!(writer, "HTTP RPC URL : {}", url)?;
-                write!(
-                    writer,
-                    "WS RPC URL : {}",
-                    ws_url.clone().unwrap_or_else(|| "None".to_string())
-                )?;
-            }
-        }
-        write!(f, "{}", writer)
-    }
-}
-
-impl ClientType {
-    pub async fn init(&self) -> Result<SuiClient, anyhow::Error> {
-        Ok(match self {
-            ClientType::Embedded(config) => SuiClient::new_embedded_client(config)?,
-            ClientType::RPC(url, ws_url) => {
-                SuiClient::new_rpc_client(url, ws_url.as_deref()).await?
-            }
-        })
-    }
-}
+#[cfg(feature = "client")]
+pub mod transport;
+#[cfg(feature = "client")]
+pub mod validator_monitor;
+#[cfg(feature = "client")]
+pub mod vesting;
+#[cfg(feature = "client")]
+pub mod webhook;
+pub mod well_known;
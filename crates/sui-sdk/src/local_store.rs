@@ -0,0 +1,242 @@
+// Copyright (c) 2022, Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use serde::{Deserialize, Serialize};
+
+use sui_json_rpc_types::{SuiData, SuiObjectInfo, SuiRawData, SuiTransactionEffects};
+use sui_types::base_types::{ObjectID, SuiAddress};
+use sui_types::coin::Coin;
+use sui_types::object::Owner;
+use sui_types::parse_sui_struct_tag;
+
+use crate::coin_type::coin_types_equal;
+use crate::encrypted_store::{read_file, write_file, StoreCipher};
+use crate::error::SuiRpcResult;
+use crate::ReadApi;
+
+/// An object owned by one of the addresses tracked by a [LocalStore].
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StoredObject {
+    pub info: SuiObjectInfo,
+    /// Balance of the coin, if the object is a `0x2::coin::Coin`.
+    pub balance: Option<u64>,
+}
+
+impl StoredObject {
+    pub fn owner(&self) -> Option<SuiAddress> {
+        match self.info.owner {
+            Owner::AddressOwner(address) => Some(address),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Default, Serialize, Deserialize)]
+struct LocalStoreData {
+    addresses: BTreeSet<SuiAddress>,
+    objects: BTreeMap<ObjectID, StoredObject>,
+}
+
+/// A local copy of all objects owned by a set of addresses, persisted to a JSON file.
+/// Call [LocalStore::sync] for a full sync from the node, and [LocalStore::apply_effects]
+/// with the effects of every transaction touching the tracked addresses to keep it up to
+/// date incrementally. Reads are served locally, so they keep working while the node is
+/// unreachable.
+pub struct LocalStore {
+    path: Option<PathBuf>,
+    cipher: Option<Arc<StoreCipher>>,
+    data: LocalStoreData,
+}
+
+impl LocalStore {
+    /// Open the store persisted at `path`, creating an empty one if the file does not exist.
+    pub fn open(path: PathBuf) -> SuiRpcResult<Self> {
+        Self::open_with(path, None)
+    }
+
+    /// Open the store persisted at `path`, encrypted with `cipher`. Fails on a plaintext
+    /// store, see [encrypt_file](crate::encrypted_store::encrypt_file).
+    pub fn open_encrypted(path: PathBuf, cipher: Arc<StoreCipher>) -> SuiRpcResult<Self> {
+        Self::open_with(path, Some(cipher))
+    }
+
+    fn open_with(path: PathBuf, cipher: Option<Arc<StoreCipher>>) -> SuiRpcResult<Self> {
+        let data = if path.exists() {
+            serde_json::from_slice(&read_file(&path, cipher.as_deref())?)?
+        } else {
+            LocalStoreData::default()
+        };
+        Ok(Self {
+            path: Some(path),
+            cipher,
+            data,
+        })
+    }
+
+    /// Create a store which is not persisted.
+    pub fn in_memory() -> Self {
+        Self {
+            path: None,
+            cipher: None,
+            data: LocalStoreData::default(),
+        }
+    }
+
+    pub fn save(&self) -> SuiRpcResult<()> {
+        if let Some(path) = &self.path {
+            write_file(
+                path,
+                &serde_json::to_vec(&self.data)?,
+                self.cipher.as_deref(),
+            )?;
+        }
+        Ok(())
+    }
+
+    pub fn addresses(&self) -> &BTreeSet<SuiAddress> {
+        &self.data.addresses
+    }
+
+    /// Start tracking `address`. Its objects are fetched on the next [LocalStore::sync].
+    pub fn add_address(&mut self, address: SuiAddress) {
+        self.data.addresses.insert(address);
+    }
+
+    pub fn remove_address(&mut self, address: &SuiAddress) {
+        self.data.addresses.remove(address);
+        self.data
+            .objects
+            .retain(|_, object| object.owner().as_ref() != Some(address));
+    }
+
+    pub fn get_object(&self, object_id: &ObjectID) -> Option<&StoredObject> {
+        self.data.objects.get(object_id)
+    }
+
+    pub fn objects_owned_by(&self, address: SuiAddress) -> impl Iterator<Item = &StoredObject> {
+        self.data
+            .objects
+            .values()
+            .filter(move |object| object.owner() == Some(address))
+    }
+
+    /// Total balance of the coins of type `coin_type` (e.g. `0x2::coin::Coin<0x2::sui::SUI>`)
+    /// owned by `address`.
+    pub fn balance(&self, address: SuiAddress, coin_type: &str) -> u64 {
+        self.objects_owned_by(address)
+            .filter(|object| coin_types_equal(&object.info.type_, coin_type))
+            .filter_map(|object| object.balance)
+            .sum()
+    }
+
+    /// Replace the local objects of every tracked address with the node's current view, and
+    /// persist the result.
+    pub async fn sync(&mut self, read_api: &ReadApi) -> SuiRpcResult<()> {
+        self.data.objects = fetch_owned(read_api, &self.data.addresses)
+            .await?
+            .into_iter()
+            .map(|object| (object.info.object_id, object))
+            .collect();
+        self.save()
+    }
+
+    /// Update the store with the effects of an executed transaction, and persist the result.
+    pub async fn apply_effects(
+        &mut self,
+        read_api: &ReadApi,
+        effects: &SuiTransactionEffects,
+    ) -> SuiRpcResult<()> {
+        for (object_id, object) in effect_updates(read_api, effects, &self.data.addresses).await? {
+            match object {
+                Some(object) => self.data.objects.insert(object_id, object),
+                None => self.data.objects.remove(&object_id),
+            };
+        }
+        self.save()
+    }
+}
+
+/// The objects currently owned by `addresses`.
+pub(crate) async fn fetch_owned(
+    read_api: &ReadApi,
+    addresses: &BTreeSet<SuiAddress>,
+) -> anyhow::Result<Vec<StoredObject>> {
+    let mut objects = vec![];
+    for address in addresses {
+        let infos = read_api.get_objects_owned_by_address(*address).await?;
+        let fetched = read_api
+            .fetch_all(infos.iter().map(|info| info.object_id), |id| {
+                fetch(read_api, id)
+            })
+            .await?;
+        objects.extend(fetched.into_iter().flatten());
+    }
+    Ok(objects)
+}
+
+/// The changes of the objects owned by `addresses` made by a transaction, in order: the new
+/// state of an object, or `None` if it was removed from the addresses.
+pub(crate) async fn effect_updates(
+    read_api: &ReadApi,
+    effects: &SuiTransactionEffects,
+    addresses: &BTreeSet<SuiAddress>,
+) -> anyhow::Result<Vec<(ObjectID, Option<StoredObject>)>> {
+    let mut updates = vec![];
+    for object in effects.deleted.iter().chain(&effects.wrapped) {
+        updates.push((object.object_id, None));
+    }
+    for object in effects
+        .created
+        .iter()
+        .chain(&effects.mutated)
+        .chain(&effects.unwrapped)
+    {
+        let tracked = matches!(
+            object.owner,
+            Owner::AddressOwner(owner) if addresses.contains(&owner)
+        );
+        let object_id = object.reference.object_id;
+        if tracked {
+            updates.push((object_id, fetch(read_api, object_id).await?));
+        } else {
+            updates.push((object_id, None));
+        }
+    }
+    Ok(updates)
+}
+
+pub(crate) async fn fetch(
+    read_api: &ReadApi,
+    object_id: ObjectID,
+) -> anyhow::Result<Option<StoredObject>> {
+    let response = read_api.get_object(object_id).await?;
+    let object = match response.into_object() {
+        Ok(object) => object,
+        // The object was deleted or wrapped since it was listed.
+        Err(_) => return Ok(None),
+    };
+    let type_ = object.data.type_().unwrap_or("package").to_string();
+    let balance = match &object.data {
+        SuiRawData::MoveObject(o)
+            if parse_sui_struct_tag(&type_).map_or(false, |t| Coin::is_coin(&t)) =>
+        {
+            Some(bcs::from_bytes::<Coin>(&o.bcs_bytes)?.balance.value())
+        }
+        _ => None,
+    };
+    Ok(Some(StoredObject {
+        info: SuiObjectInfo {
+            object_id,
+            version: object.reference.version,
+            digest: object.reference.digest,
+            type_,
+            owner: object.owner,
+            previous_transaction: object.previous_transaction,
+        },
+        balance,
+    }))
+}
@@ -0,0 +1,331 @@
+// Copyright (c) 2022, Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+use std::collections::BTreeSet;
+use std::str::FromStr;
+
+use futures::future::BoxFuture;
+use futures::FutureExt;
+use move_core_types::account_address::AccountAddress;
+use move_core_types::language_storage::TypeTag;
+use serde_json::Value;
+use thiserror::Error;
+
+use sui_json::SuiJsonValue;
+use sui_json_rpc_types::{SuiMoveAbility, SuiMoveNormalizedType};
+use sui_types::base_types::{
+    ObjectID, SuiAddress, STD_ASCII_MODULE_NAME, STD_ASCII_STRUCT_NAME, STD_OPTION_MODULE_NAME,
+    STD_OPTION_STRUCT_NAME, STD_UTF8_MODULE_NAME, STD_UTF8_STRUCT_NAME, TX_CONTEXT_MODULE_NAME,
+    TX_CONTEXT_STRUCT_NAME,
+};
+use sui_types::{MOVE_STDLIB_ADDRESS, SUI_FRAMEWORK_ADDRESS};
+
+use crate::FullNodeApi;
+
+#[derive(Debug, Error)]
+pub enum MoveCallValidationError {
+    #[error("Function [{module}::{function}] is not an entry function and cannot be called by a transaction.")]
+    NotEntryFunction { module: String, function: String },
+    #[error("Expected {expected} type arguments, found {actual}.")]
+    TypeArgumentCountMismatch { expected: usize, actual: usize },
+    #[error("Type argument {index} [{type_arg}] is missing the required abilities {missing:?}.")]
+    TypeArgumentConstraintNotSatisfied {
+        index: usize,
+        type_arg: TypeTag,
+        missing: Vec<SuiMoveAbility>,
+    },
+    #[error("Expected {expected} arguments, found {actual}.")]
+    ArgumentCountMismatch { expected: usize, actual: usize },
+    #[error("Argument {index} [{value}] does not match the parameter type [{expected}].")]
+    ArgumentTypeMismatch {
+        index: usize,
+        expected: String,
+        value: Value,
+    },
+    #[error(transparent)]
+    RpcError(#[from] crate::Error),
+}
+
+impl FullNodeApi {
+    /// Check a Move call against the normalized signature of the on-chain function before
+    /// it is submitted, so that a wrong argument count, argument type, or type argument
+    /// is reported precisely instead of surfacing as an opaque VM error.
+    pub async fn validate_move_call(
+        &self,
+        package: ObjectID,
+        module: &str,
+        function: &str,
+        type_args: &[TypeTag],
+        call_args: &[SuiJsonValue],
+    ) -> Result<(), MoveCallValidationError> {
+        let normalized = self
+            .get_normalized_move_function(package, module.to_string(), function.to_string())
+            .await?;
+
+        if !normalized.is_entry {
+            return Err(MoveCallValidationError::NotEntryFunction {
+                module: module.to_string(),
+                function: function.to_string(),
+            });
+        }
+
+        if normalized.type_parameters.len() != type_args.len() {
+            return Err(MoveCallValidationError::TypeArgumentCountMismatch {
+                expected: normalized.type_parameters.len(),
+                actual: type_args.len(),
+            });
+        }
+        for (index, (constraints, type_arg)) in
+            normalized.type_parameters.iter().zip(type_args).enumerate()
+        {
+            let abilities = self.type_abilities(type_arg).await?;
+            let missing = constraints
+                .abilities
+                .iter()
+                .filter(|ability| !abilities.contains(ability))
+                .copied()
+                .collect::<Vec<_>>();
+            if !missing.is_empty() {
+                return Err(
+                    MoveCallValidationError::TypeArgumentConstraintNotSatisfied {
+                        index,
+                        type_arg: type_arg.clone(),
+                        missing,
+                    },
+                );
+            }
+        }
+
+        // The trailing TxContext is supplied by the runtime, not by the caller.
+        let parameters = match normalized.parameters.split_last() {
+            Some((last, parameters)) if is_tx_context(last) => parameters,
+            _ => &normalized.parameters[..],
+        };
+        if parameters.len() != call_args.len() {
+            return Err(MoveCallValidationError::ArgumentCountMismatch {
+                expected: parameters.len(),
+                actual: call_args.len(),
+            });
+        }
+        for (index, (parameter, arg)) in parameters.iter().zip(call_args).enumerate() {
+            let value = arg.to_json_value();
+            if !matches_type(parameter, type_args, &value) {
+                return Err(MoveCallValidationError::ArgumentTypeMismatch {
+                    index,
+                    expected: type_to_string(parameter, type_args),
+                    value,
+                });
+            }
+        }
+        Ok(())
+    }
+
+    /// Resolve the abilities of a fully instantiated type, fetching struct declarations
+    /// from the node where needed.
+    fn type_abilities<'a>(
+        &'a self,
+        type_: &'a TypeTag,
+    ) -> BoxFuture<'a, crate::SuiRpcResult<BTreeSet<SuiMoveAbility>>> {
+        async move {
+            Ok(match type_ {
+                TypeTag::Bool | TypeTag::U8 | TypeTag::U64 | TypeTag::U128 | TypeTag::Address => {
+                    BTreeSet::from([
+                        SuiMoveAbility::Copy,
+                        SuiMoveAbility::Drop,
+                        SuiMoveAbility::Store,
+                    ])
+                }
+                TypeTag::Signer => BTreeSet::from([SuiMoveAbility::Drop]),
+                TypeTag::Vector(inner) => {
+                    let mut abilities = self.type_abilities(inner).await?;
+                    abilities.remove(&SuiMoveAbility::Key);
+                    abilities
+                }
+                TypeTag::Struct(tag) => {
+                    let struct_ = self
+                        .get_normalized_move_struct(
+                            ObjectID::from(tag.address),
+                            tag.module.to_string(),
+                            tag.name.to_string(),
+                        )
+                        .await?;
+                    let mut abilities = struct_
+                        .abilities
+                        .abilities
+                        .into_iter()
+                        .collect::<BTreeSet<_>>();
+                    for (parameter, arg) in struct_.type_parameters.iter().zip(&tag.type_params) {
+                        if parameter.is_phantom {
+                            continue;
+                        }
+                        let arg_abilities = self.type_abilities(arg).await?;
+                        abilities.retain(|ability| {
+                            // A struct has `key` only if all its non-phantom type
+                            // arguments have `store`.
+                            let required = match ability {
+                                SuiMoveAbility::Key => SuiMoveAbility::Store,
+                                ability => *ability,
+                            };
+                            arg_abilities.contains(&required)
+                        });
+                    }
+                    abilities
+                }
+            })
+        }
+        .boxed()
+    }
+}
+
+fn is_struct(address: &str, expected_address: AccountAddress) -> bool {
+    AccountAddress::from_hex_literal(address).map_or(false, |a| a == expected_address)
+}
+
+fn is_tx_context(type_: &SuiMoveNormalizedType) -> bool {
+    match type_ {
+        SuiMoveNormalizedType::Reference(inner)
+        | SuiMoveNormalizedType::MutableReference(inner) => {
+            matches!(
+                &**inner,
+                SuiMoveNormalizedType::Struct { address, module, name, .. }
+                    if is_struct(address, SUI_FRAMEWORK_ADDRESS)
+                        && module == TX_CONTEXT_MODULE_NAME.as_str()
+                        && name == TX_CONTEXT_STRUCT_NAME.as_str()
+            )
+        }
+        _ => false,
+    }
+}
+
+fn is_object_id(value: &Value) -> bool {
+    value
+        .as_str()
+        .map_or(false, |s| ObjectID::from_str(s).is_ok())
+}
+
+fn as_u128(value: &Value) -> Option<u128> {
+    match value {
+        Value::Number(n) => n.as_u64().map(u128::from),
+        Value::String(s) => match s.strip_prefix("0x") {
+            Some(hex) => u128::from_str_radix(hex, 16).ok(),
+            None => s.parse().ok(),
+        },
+        _ => None,
+    }
+}
+
+fn to_normalized_type(type_: &TypeTag) -> SuiMoveNormalizedType {
+    match type_ {
+        TypeTag::Bool => SuiMoveNormalizedType::Bool,
+        TypeTag::U8 => SuiMoveNormalizedType::U8,
+        TypeTag::U64 => SuiMoveNormalizedType::U64,
+        TypeTag::U128 => SuiMoveNormalizedType::U128,
+        TypeTag::Address => SuiMoveNormalizedType::Address,
+        TypeTag::Signer => SuiMoveNormalizedType::Signer,
+        TypeTag::Vector(inner) => {
+            SuiMoveNormalizedType::Vector(Box::new(to_normalized_type(inner)))
+        }
+        TypeTag::Struct(tag) => SuiMoveNormalizedType::Struct {
+            address: tag.address.to_hex_literal(),
+            module: tag.module.to_string(),
+            name: tag.name.to_string(),
+            type_arguments: tag.type_params.iter().map(to_normalized_type).collect(),
+        },
+    }
+}
+
+/// Check whether a JSON argument can be resolved into a value of the given parameter type,
+/// following the conversion rules of [sui_json::resolve_move_function_args].
+fn matches_type(type_: &SuiMoveNormalizedType, type_args: &[TypeTag], value: &Value) -> bool {
+    match type_ {
+        SuiMoveNormalizedType::Bool => value.is_boolean(),
+        SuiMoveNormalizedType::U8 => as_u128(value).map_or(false, |v| v <= u8::MAX.into()),
+        SuiMoveNormalizedType::U64 => as_u128(value).map_or(false, |v| v <= u64::MAX.into()),
+        SuiMoveNormalizedType::U128 => as_u128(value).is_some(),
+        SuiMoveNormalizedType::Address => value
+            .as_str()
+            .map_or(false, |s| SuiAddress::from_str(s).is_ok()),
+        SuiMoveNormalizedType::Signer => false,
+        SuiMoveNormalizedType::Vector(inner) => match value {
+            Value::Array(values) => values.iter().all(|v| matches_type(inner, type_args, v)),
+            // Byte vectors can be supplied as strings.
+            Value::String(_) => matches!(**inner, SuiMoveNormalizedType::U8),
+            _ => false,
+        },
+        SuiMoveNormalizedType::Struct {
+            address,
+            module,
+            name,
+            type_arguments,
+        } => {
+            if is_struct(address, MOVE_STDLIB_ADDRESS)
+                && ((module == STD_UTF8_MODULE_NAME.as_str()
+                    && name == STD_UTF8_STRUCT_NAME.as_str())
+                    || (module == STD_ASCII_MODULE_NAME.as_str()
+                        && name == STD_ASCII_STRUCT_NAME.as_str()))
+            {
+                value.is_string()
+            } else if is_struct(address, MOVE_STDLIB_ADDRESS)
+                && module == STD_OPTION_MODULE_NAME.as_str()
+                && name == STD_OPTION_STRUCT_NAME.as_str()
+            {
+                match (value, type_arguments.first()) {
+                    (Value::Array(values), Some(inner)) => {
+                        values.len() <= 1
+                            && values.iter().all(|v| matches_type(inner, type_args, v))
+                    }
+                    _ => false,
+                }
+            } else {
+                // Any other struct parameter has to be an object, passed by ID.
+                is_object_id(value)
+            }
+        }
+        SuiMoveNormalizedType::TypeParameter(index) => {
+            type_args.get(*index as usize).map_or(false, |tag| {
+                matches_type(&to_normalized_type(tag), type_args, value)
+            })
+        }
+        SuiMoveNormalizedType::Reference(_) | SuiMoveNormalizedType::MutableReference(_) => {
+            is_object_id(value)
+        }
+    }
+}
+
+fn type_to_string(type_: &SuiMoveNormalizedType, type_args: &[TypeTag]) -> String {
+    match type_ {
+        SuiMoveNormalizedType::Bool => "bool".to_string(),
+        SuiMoveNormalizedType::U8 => "u8".to_string(),
+        SuiMoveNormalizedType::U64 => "u64".to_string(),
+        SuiMoveNormalizedType::U128 => "u128".to_string(),
+        SuiMoveNormalizedType::Address => "address".to_string(),
+        SuiMoveNormalizedType::Signer => "signer".to_string(),
+        SuiMoveNormalizedType::Vector(inner) => {
+            format!("vector<{}>", type_to_string(inner, type_args))
+        }
+        SuiMoveNormalizedType::Struct {
+            address,
+            module,
+            name,
+            type_arguments,
+        } => {
+            if type_arguments.is_empty() {
+                format!("{address}::{module}::{name}")
+            } else {
+                let type_arguments = type_arguments
+                    .iter()
+                    .map(|t| type_to_string(t, type_args))
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                format!("{address}::{module}::{name}<{type_arguments}>")
+            }
+        }
+        SuiMoveNormalizedType::TypeParameter(index) => type_args
+            .get(*index as usize)
+            .map_or_else(|| format!("T{index}"), |tag| tag.to_string()),
+        SuiMoveNormalizedType::Reference(inner) => format!("&{}", type_to_string(inner, type_args)),
+        SuiMoveNormalizedType::MutableReference(inner) => {
+            format!("&mut {}", type_to_string(inner, type_args))
+        }
+    }
+}
@@ -0,0 +1,192 @@
+// Copyright (c) 2022, Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Conversions between BCS encoded Move values and JSON, given the layout of their type, e.g.
+//! to render the contents of arbitrary objects or to build pure arguments from configuration
+//! files.
+//!
+//! The JSON form of a value is:
+//! * `bool`, `u8` and `u64` as JSON booleans and numbers;
+//! * `u128` as a decimal string, as JSON numbers lose precision beyond 2^53;
+//! * addresses and signers as `0x` prefixed hex strings;
+//! * vectors as arrays;
+//! * structs as objects keyed by field name, or as arrays of their fields if the layout has
+//!   no field names.
+//!
+//! Conversions from JSON also accept integers as strings and `vector<u8>` as a `0x` prefixed
+//! hex string, so every value rendered by [bcs_to_json] converts back to the same bytes.
+
+use anyhow::{anyhow, bail};
+use move_core_types::account_address::AccountAddress;
+use move_core_types::value::{
+    MoveFieldLayout, MoveStruct, MoveStructLayout, MoveTypeLayout, MoveValue,
+};
+use serde_json::{Map, Value};
+
+use crate::error::{Error, SuiRpcResult};
+
+/// Decode the BCS encoded value `bytes` of `layout` to JSON.
+pub fn bcs_to_json(bytes: &[u8], layout: &MoveTypeLayout) -> SuiRpcResult<Value> {
+    let value = MoveValue::simple_deserialize(bytes, layout)?;
+    move_value_to_json(&value, layout)
+}
+
+/// Encode `value` as a BCS encoded value of `layout`.
+pub fn json_to_bcs(value: &Value, layout: &MoveTypeLayout) -> SuiRpcResult<Vec<u8>> {
+    let value = json_to_move_value(value, layout)?;
+    value
+        .simple_serialize()
+        .ok_or_else(|| Error::Other(anyhow!("Cannot serialize Move value of layout [{layout}].")))
+}
+
+pub fn move_value_to_json(value: &MoveValue, layout: &MoveTypeLayout) -> SuiRpcResult<Value> {
+    Ok(match (value, layout) {
+        (MoveValue::Bool(b), MoveTypeLayout::Bool) => Value::Bool(*b),
+        (MoveValue::U8(n), MoveTypeLayout::U8) => Value::from(*n),
+        (MoveValue::U64(n), MoveTypeLayout::U64) => Value::from(*n),
+        (MoveValue::U128(n), MoveTypeLayout::U128) => Value::String(n.to_string()),
+        (MoveValue::Address(address), MoveTypeLayout::Address)
+        | (MoveValue::Signer(address), MoveTypeLayout::Signer) => {
+            Value::String(address.to_hex_literal())
+        }
+        (MoveValue::Vector(values), MoveTypeLayout::Vector(layout)) => Value::Array(
+            values
+                .iter()
+                .map(|value| move_value_to_json(value, layout))
+                .collect::<SuiRpcResult<_>>()?,
+        ),
+        (MoveValue::Struct(value), MoveTypeLayout::Struct(layout)) => {
+            let values = match value {
+                MoveStruct::Runtime(values) => values.iter().collect::<Vec<_>>(),
+                MoveStruct::WithFields(fields) | MoveStruct::WithTypes { fields, .. } => {
+                    fields.iter().map(|(_, value)| value).collect()
+                }
+            };
+            match layout {
+                MoveStructLayout::Runtime(layouts) => Value::Array(
+                    zip_fields(&values, layouts)?
+                        .map(|(value, layout)| move_value_to_json(value, layout))
+                        .collect::<SuiRpcResult<_>>()?,
+                ),
+                MoveStructLayout::WithFields(fields)
+                | MoveStructLayout::WithTypes { fields, .. } => {
+                    let layouts = fields
+                        .iter()
+                        .map(|field| field.layout.clone())
+                        .collect::<Vec<_>>();
+                    let mut object = Map::new();
+                    for ((value, layout), field) in zip_fields(&values, &layouts)?.zip(fields) {
+                        object.insert(field.name.to_string(), move_value_to_json(value, layout)?);
+                    }
+                    Value::Object(object)
+                }
+            }
+        }
+        (value, layout) => {
+            return Err(Error::Other(anyhow!(
+                "Move value [{value:?}] does not match layout [{layout}]."
+            )))
+        }
+    })
+}
+
+pub fn json_to_move_value(value: &Value, layout: &MoveTypeLayout) -> SuiRpcResult<MoveValue> {
+    Ok(match (value, layout) {
+        (Value::Bool(b), MoveTypeLayout::Bool) => MoveValue::Bool(*b),
+        (value, MoveTypeLayout::U8) => MoveValue::U8(u8::try_from(integer(value)?)?),
+        (value, MoveTypeLayout::U64) => MoveValue::U64(u64::try_from(integer(value)?)?),
+        (value, MoveTypeLayout::U128) => MoveValue::U128(integer(value)?),
+        (Value::String(s), MoveTypeLayout::Address) => MoveValue::Address(address(s)?),
+        (Value::String(s), MoveTypeLayout::Signer) => MoveValue::Signer(address(s)?),
+        (Value::String(s), MoveTypeLayout::Vector(element))
+            if matches!(**element, MoveTypeLayout::U8) =>
+        {
+            let hex = s
+                .strip_prefix("0x")
+                .ok_or_else(|| anyhow!("Byte string [{s}] must be 0x prefixed hex."))?;
+            MoveValue::vector_u8(hex::decode(hex)?)
+        }
+        (Value::Array(values), MoveTypeLayout::Vector(element)) => MoveValue::Vector(
+            values
+                .iter()
+                .map(|value| json_to_move_value(value, element))
+                .collect::<SuiRpcResult<_>>()?,
+        ),
+        (Value::Array(values), MoveTypeLayout::Struct(MoveStructLayout::Runtime(layouts))) => {
+            if values.len() != layouts.len() {
+                return Err(Error::Other(anyhow!(
+                    "Expected {} struct fields, got {}.",
+                    layouts.len(),
+                    values.len()
+                )));
+            }
+            MoveValue::Struct(MoveStruct::Runtime(
+                values
+                    .iter()
+                    .zip(layouts)
+                    .map(|(value, layout)| json_to_move_value(value, layout))
+                    .collect::<SuiRpcResult<_>>()?,
+            ))
+        }
+        (
+            Value::Object(object),
+            MoveTypeLayout::Struct(
+                MoveStructLayout::WithFields(fields) | MoveStructLayout::WithTypes { fields, .. },
+            ),
+        ) => {
+            if let Some(name) = object
+                .keys()
+                .find(|name| !fields.iter().any(|field| field.name.as_str() == *name))
+            {
+                return Err(Error::Other(anyhow!("Unknown struct field [{name}].")));
+            }
+            MoveValue::Struct(MoveStruct::Runtime(
+                fields
+                    .iter()
+                    .map(|MoveFieldLayout { name, layout }| {
+                        let value = object
+                            .get(name.as_str())
+                            .ok_or_else(|| anyhow!("Missing struct field [{name}]."))?;
+                        json_to_move_value(value, layout)
+                    })
+                    .collect::<SuiRpcResult<_>>()?,
+            ))
+        }
+        (value, layout) => {
+            return Err(Error::Other(anyhow!(
+                "JSON value [{value}] does not match layout [{layout}]."
+            )))
+        }
+    })
+}
+
+fn zip_fields<'a>(
+    values: &'a [&'a MoveValue],
+    layouts: &'a [MoveTypeLayout],
+) -> anyhow::Result<impl Iterator<Item = (&'a MoveValue, &'a MoveTypeLayout)>> {
+    if values.len() != layouts.len() {
+        bail!(
+            "Expected {} struct fields, got {}.",
+            layouts.len(),
+            values.len()
+        );
+    }
+    Ok(values.iter().copied().zip(layouts))
+}
+
+fn integer(value: &Value) -> anyhow::Result<u128> {
+    match value {
+        Value::Number(n) => n
+            .as_u64()
+            .map(u128::from)
+            .ok_or_else(|| anyhow!("Expected an unsigned integer, got [{n}].")),
+        Value::String(s) => Ok(s.trim().parse()?),
+        value => {
+            bail!("Expected an unsigned integer, got [{value}].")
+        }
+    }
+}
+
+fn address(s: &str) -> anyhow::Result<AccountAddress> {
+    AccountAddress::from_hex_literal(s.trim()).map_err(|e| anyhow!("Invalid address [{s}]: {e}"))
+}
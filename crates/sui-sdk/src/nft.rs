@@ -0,0 +1,258 @@
+// Copyright (c) 2022, Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+use std::collections::BTreeMap;
+
+use anyhow::anyhow;
+use serde_json::Value;
+use thiserror::Error;
+
+use sui_json::SuiJsonValue;
+use sui_json_rpc_types::{SuiData, SuiObjectInfo};
+use sui_types::base_types::{ObjectID, SuiAddress};
+use sui_types::coin::Coin;
+use sui_types::messages::TransactionData;
+use sui_types::{parse_sui_struct_tag, SUI_FRAMEWORK_OBJECT_ID};
+
+use crate::error::SuiRpcResult;
+use crate::transaction_builder::TransactionBuilder;
+use crate::ReadApi;
+
+/// A set of display templates, keyed by display field (e.g. `name`, `image_url`).
+/// Templates reference the object's fields with `{field}` or `{field.nested_field}`.
+#[derive(Debug, Clone, Default)]
+pub struct DisplayTemplate(pub BTreeMap<String, String>);
+
+impl DisplayTemplate {
+    pub fn new<K: Into<String>, V: Into<String>>(fields: impl IntoIterator<Item = (K, V)>) -> Self {
+        Self(
+            fields
+                .into_iter()
+                .map(|(k, v)| (k.into(), v.into()))
+                .collect(),
+        )
+    }
+
+    /// The templates used for objects with no explicit template, matching the
+    /// name/description/url layout of `0x2::devnet_nft::DevNetNFT`.
+    pub fn standard() -> Self {
+        Self::new([
+            ("name", "{name}"),
+            ("description", "{description}"),
+            ("image_url", "{url}"),
+        ])
+    }
+
+    /// Render every template against the object's fields, with the error of each template
+    /// that cannot be rendered.
+    pub fn try_render(&self, fields: &Value) -> BTreeMap<String, Result<String, DisplayError>> {
+        self.0
+            .iter()
+            .map(|(key, template)| (key.clone(), try_render_template(template, fields)))
+            .collect()
+    }
+
+    /// Render every template against the object's fields. Templates referencing a field the
+    /// object does not have are skipped.
+    pub fn render(&self, fields: &Value) -> BTreeMap<String, String> {
+        self.try_render(fields)
+            .into_iter()
+            .filter_map(|(key, rendered)| Some((key, rendered.ok()?)))
+            .collect()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum DisplayError {
+    #[error("Template [{template}] references [{field}], which the object does not have.")]
+    UnresolvedField { template: String, field: String },
+    #[error("Template [{template}] has an unclosed '{{'.")]
+    UnclosedPlaceholder { template: String },
+    #[error("Template [{template}] has an unmatched '}}'.")]
+    UnmatchedBrace { template: String },
+}
+
+/// Substitute the `{field.path}` placeholders of `template` with values from `fields`.
+/// Returns `None` if the template cannot be rendered, see [try_render_template].
+pub fn render_template(template: &str, fields: &Value) -> Option<String> {
+    try_render_template(template, fields).ok()
+}
+
+/// Substitute the `{field.path}` placeholders of `template` with values from `fields`.
+/// Paths go through nested structs by field name and through vectors by index, e.g.
+/// `{attributes.0.value}`. `\{`, `\}` and `\\` render a literal brace or backslash. A
+/// placeholder resolving to no value, or to an empty option, is an error.
+pub fn try_render_template(template: &str, fields: &Value) -> Result<String, DisplayError> {
+    let mut rendered = String::new();
+    let mut chars = template.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => rendered.push(chars.next().unwrap_or('\\')),
+            '{' => {
+                let mut path = String::new();
+                loop {
+                    match chars.next() {
+                        Some('}') => break,
+                        Some(c) => path.push(c),
+                        None => {
+                            return Err(DisplayError::UnclosedPlaceholder {
+                                template: template.to_string(),
+                            })
+                        }
+                    }
+                }
+                let value =
+                    resolve(path.trim(), fields).ok_or_else(|| DisplayError::UnresolvedField {
+                        template: template.to_string(),
+                        field: path.trim().to_string(),
+                    })?;
+                rendered.push_str(&value);
+            }
+            '}' => {
+                return Err(DisplayError::UnmatchedBrace {
+                    template: template.to_string(),
+                })
+            }
+            c => rendered.push(c),
+        }
+    }
+    Ok(rendered)
+}
+
+fn resolve(path: &str, fields: &Value) -> Option<String> {
+    let value = path
+        .split('.')
+        .try_fold(fields, |value, field| match value {
+            Value::Array(values) => values.get(field.parse::<usize>().ok()?),
+            value => value.get(field),
+        })?;
+    match value {
+        Value::Null => None,
+        Value::String(s) => Some(s.clone()),
+        value => Some(value.to_string()),
+    }
+}
+
+/// Display metadata of an NFT object.
+#[derive(Debug, Clone)]
+pub struct NftDisplay {
+    pub object_id: ObjectID,
+    pub type_: String,
+    pub display: BTreeMap<String, String>,
+    /// The errors of the templates that could not be rendered, by display field.
+    pub errors: BTreeMap<String, DisplayError>,
+}
+
+impl ReadApi {
+    /// Fetch an object and render its display metadata with `template`, or with
+    /// [DisplayTemplate::standard] if none is given.
+    pub async fn get_nft_display(
+        &self,
+        object_id: ObjectID,
+        template: Option<&DisplayTemplate>,
+    ) -> SuiRpcResult<NftDisplay> {
+        let object = self.get_parsed_object(object_id).await?.into_object()?;
+        let move_object = object
+            .data
+            .try_as_move()
+            .ok_or_else(|| anyhow!("Object [{object_id}] is not a Move object."))?;
+        let fields = move_object.fields.clone().to_json_value()?;
+        let rendered = match template {
+            Some(template) => template.try_render(&fields),
+            None => DisplayTemplate::standard().try_render(&fields),
+        };
+        let mut display = BTreeMap::new();
+        let mut errors = BTreeMap::new();
+        for (key, rendered) in rendered {
+            match rendered {
+                Ok(value) => {
+                    display.insert(key, value);
+                }
+                Err(e) => {
+                    errors.insert(key, e);
+                }
+            }
+        }
+        Ok(NftDisplay {
+            object_id,
+            type_: move_object.type_.clone(),
+            display,
+            errors,
+        })
+    }
+
+    /// List the objects owned by `address` grouped by their Move type, leaving out coins.
+    pub async fn get_nfts_owned_by_address(
+        &self,
+        address: SuiAddress,
+    ) -> SuiRpcResult<BTreeMap<String, Vec<SuiObjectInfo>>> {
+        let mut collections = BTreeMap::<_, Vec<_>>::new();
+        for info in self.get_objects_owned_by_address(address).await? {
+            if parse_sui_struct_tag(&info.type_).map_or(false, |tag| Coin::is_coin(&tag)) {
+                continue;
+            }
+            collections
+                .entry(info.type_.clone())
+                .or_default()
+                .push(info);
+        }
+        Ok(collections)
+    }
+}
+
+impl TransactionBuilder {
+    /// Mint a `0x2::devnet_nft::DevNetNFT` to the signer.
+    pub async fn mint_example_nft(
+        &self,
+        signer: SuiAddress,
+        name: &str,
+        description: &str,
+        url: &str,
+        gas: Option<ObjectID>,
+        gas_budget: u64,
+    ) -> SuiRpcResult<TransactionData> {
+        self.mint_nft(
+            signer,
+            SUI_FRAMEWORK_OBJECT_ID,
+            "devnet_nft",
+            "mint",
+            name,
+            description,
+            url,
+            gas,
+            gas_budget,
+        )
+        .await
+    }
+
+    /// Mint an NFT from a package following the common `mint(name, description, url, ctx)`
+    /// entry function layout.
+    pub async fn mint_nft(
+        &self,
+        signer: SuiAddress,
+        package: ObjectID,
+        module: &str,
+        function: &str,
+        name: &str,
+        description: &str,
+        url: &str,
+        gas: Option<ObjectID>,
+        gas_budget: u64,
+    ) -> SuiRpcResult<TransactionData> {
+        let args = [name, description, url]
+            .iter()
+            .map(|arg| SuiJsonValue::new(Value::String(arg.to_string())))
+            .collect::<Result<Vec<_>, _>>()?;
+        self.move_call(
+            signer,
+            package,
+            module,
+            function,
+            vec![],
+            args,
+            gas,
+            gas_budget,
+        )
+        .await
+    }
+}
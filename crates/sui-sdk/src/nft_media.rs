@@ -0,0 +1,312 @@
+// Copyright (c) 2022, Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Fetching of the media and metadata of NFTs, from the URLs rendered by their
+//! [display templates](crate::nft::DisplayTemplate).
+//!
+//! A [MediaFetcher] resolves `ipfs://` and `ar://` URLs through HTTP gateways and fetches
+//! `https://` URLs directly. Other schemes, e.g. `file://`, `data:` or plain `http://`
+//! unless allowed, are rejected. Responses larger than the size limit or with a content type
+//! other than those expected are rejected too, without reading more of the body than the
+//! limit. Redirects are followed only to the schemes fetched directly. Fetched media are
+//! cached by resolved URL, and checked against the content types and size limit again when
+//! read from the cache.
+
+use std::fmt::{Debug, Formatter};
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::{anyhow, bail};
+use reqwest::header::{CONTENT_LENGTH, CONTENT_TYPE};
+use reqwest::redirect::Policy;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use url::Url;
+
+use crate::cache::{Cache, CacheCategory, LruCache};
+use crate::error::{Error, SuiRpcResult};
+use crate::nft::NftDisplay;
+
+pub const DEFAULT_IPFS_GATEWAY: &str = "https://ipfs.io/ipfs/";
+pub const DEFAULT_ARWEAVE_GATEWAY: &str = "https://arweave.net/";
+pub const MAX_REDIRECTS: usize = 10;
+
+/// A fetched media file.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Media {
+    /// The resolved URL it was fetched from.
+    pub url: String,
+    pub content_type: String,
+    pub bytes: Vec<u8>,
+}
+
+#[derive(Clone)]
+pub struct MediaFetcher {
+    client: reqwest::Client,
+    custom_client: bool,
+    ipfs_gateway: Url,
+    arweave_gateway: Url,
+    allow_http: bool,
+    max_size: usize,
+    content_types: Vec<String>,
+    cache: Option<Arc<dyn Cache>>,
+    cache_ttl: Option<Duration>,
+}
+
+impl Debug for MediaFetcher {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("MediaFetcher")
+            .field("ipfs_gateway", &self.ipfs_gateway.as_str())
+            .field("arweave_gateway", &self.arweave_gateway.as_str())
+            .field("custom_client", &self.custom_client)
+            .field("allow_http", &self.allow_http)
+            .field("max_size", &self.max_size)
+            .field("content_types", &self.content_types)
+            .field("cache", &self.cache.is_some())
+            .field("cache_ttl", &self.cache_ttl)
+            .finish()
+    }
+}
+
+impl Default for MediaFetcher {
+    fn default() -> Self {
+        Self {
+            client: Self::client(false),
+            custom_client: false,
+            ipfs_gateway: Url::parse(DEFAULT_IPFS_GATEWAY).unwrap(),
+            arweave_gateway: Url::parse(DEFAULT_ARWEAVE_GATEWAY).unwrap(),
+            allow_http: false,
+            max_size: 10 * 1024 * 1024,
+            content_types: vec!["image/".to_string(), "video/".to_string()],
+            cache: Some(Arc::new(LruCache::new(64))),
+            cache_ttl: Some(Duration::from_secs(3600)),
+        }
+    }
+}
+
+impl MediaFetcher {
+    /// Use a custom HTTP client, e.g. to set timeouts or proxies. The client must not follow
+    /// redirects to other schemes than the fetcher, i.e. must be built with the
+    /// [MediaFetcher::redirect_policy] of the fetcher.
+    pub fn with_client(mut self, client: reqwest::Client) -> Self {
+        self.client = client;
+        self.custom_client = true;
+        self
+    }
+
+    /// Follow up to [MAX_REDIRECTS] redirects to `https://` URLs, and to `http://` URLs if
+    /// `allow_http`.
+    pub fn redirect_policy(allow_http: bool) -> Policy {
+        Policy::custom(move |attempt| {
+            let scheme = attempt.url().scheme().to_string();
+            if attempt.previous().len() >= MAX_REDIRECTS {
+                attempt.error(format!("Media URL redirected over {MAX_REDIRECTS} times."))
+            } else if is_fetched_directly(&scheme, allow_http) {
+                attempt.follow()
+            } else {
+                attempt.error(format!(
+                    "Media URL redirected to unsupported scheme [{scheme}]."
+                ))
+            }
+        })
+    }
+
+    /// Resolve `ipfs://` URLs with `gateway`, ending with a slash. Defaults to
+    /// [DEFAULT_IPFS_GATEWAY].
+    pub fn with_ipfs_gateway(mut self, gateway: Url) -> Self {
+        self.ipfs_gateway = gateway;
+        self
+    }
+
+    /// Resolve `ar://` URLs with `gateway`, ending with a slash. Defaults to
+    /// [DEFAULT_ARWEAVE_GATEWAY].
+    pub fn with_arweave_gateway(mut self, gateway: Url) -> Self {
+        self.arweave_gateway = gateway;
+        self
+    }
+
+    /// Also fetch `http://` URLs, e.g. from a local test server. Disabled by default.
+    pub fn with_allow_http(mut self, allow_http: bool) -> Self {
+        self.allow_http = allow_http;
+        if !self.custom_client {
+            self.client = Self::client(allow_http);
+        }
+        self
+    }
+
+    /// Largest media fetched, in bytes. Defaults to 10 MiB.
+    pub fn with_max_size(mut self, max_size: usize) -> Self {
+        self.max_size = max_size;
+        self
+    }
+
+    /// Content types of the media fetched, matched by prefix, e.g. `image/`. Defaults to
+    /// images and videos.
+    pub fn with_content_types(
+        mut self,
+        content_types: impl IntoIterator<Item = impl Into<String>>,
+    ) -> Self {
+        self.content_types = content_types.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Cache the fetched media in `cache`, expiring after `ttl` if set. Defaults to an
+    /// in-memory cache of 64 media expiring after an hour.
+    pub fn with_cache(mut self, cache: Arc<dyn Cache>, ttl: Option<Duration>) -> Self {
+        self.cache = Some(cache);
+        self.cache_ttl = ttl;
+        self
+    }
+
+    pub fn without_cache(mut self) -> Self {
+        self.cache = None;
+        self
+    }
+
+    /// The HTTP URL to fetch `url` from.
+    pub fn resolve(&self, url: &str) -> SuiRpcResult<Url> {
+        let parsed = Url::parse(url.trim())?;
+        let (_, rest) = url.trim().split_once(':').unwrap_or_default();
+        let rest = rest.trim_start_matches('/');
+        let (gateway, path) = match parsed.scheme() {
+            scheme if is_fetched_directly(scheme, self.allow_http) => return Ok(parsed),
+            // The path of `ipfs://<cid>/<path>` is split into the host and path of the URL,
+            // and some wallets write `ipfs://ipfs/<cid>`.
+            "ipfs" => (
+                &self.ipfs_gateway,
+                rest.strip_prefix("ipfs/").unwrap_or(rest),
+            ),
+            "ar" => (&self.arweave_gateway, rest),
+            scheme => {
+                return Err(Error::Other(anyhow!(
+                    "Unsupported scheme [{scheme}] of media URL [{url}]."
+                )))
+            }
+        };
+        if path.is_empty() {
+            return Err(Error::Other(anyhow!(
+                "Media URL [{url}] has no content id."
+            )));
+        }
+        Ok(gateway.join(path)?)
+    }
+
+    /// Fetch the media at `url`.
+    pub async fn fetch(&self, url: &str) -> SuiRpcResult<Media> {
+        let url = self.resolve(url)?;
+        let key = url.to_string();
+        if let Some(cache) = &self.cache {
+            let cached = cache
+                .get(CacheCategory::NftMedia, &key)
+                .await
+                .ok()
+                .flatten();
+            if let Some(media) = cached.and_then(|bytes| bcs::from_bytes::<Media>(&bytes).ok()) {
+                // The entry may have been cached by a fetcher with another policy, e.g. by
+                // [MediaFetcher::fetch_json].
+                self.check_content_type(&key, &media.content_type)?;
+                self.check_size(&key, media.bytes.len())?;
+                return Ok(media);
+            }
+        }
+
+        let mut response = self.client.get(url).send().await?.error_for_status()?;
+        let content_type = response
+            .headers()
+            .get(CONTENT_TYPE)
+            .and_then(|value| value.to_str().ok())
+            .unwrap_or_default()
+            .to_ascii_lowercase();
+        self.check_content_type(&key, &content_type)?;
+        let length = response
+            .headers()
+            .get(CONTENT_LENGTH)
+            .and_then(|value| value.to_str().ok()?.parse::<usize>().ok());
+        if let Some(length) = length {
+            self.check_size(&key, length)?;
+        }
+        let mut bytes = Vec::with_capacity(length.unwrap_or_default().min(self.max_size));
+        while let Some(chunk) = response.chunk().await? {
+            if bytes.len() + chunk.len() > self.max_size {
+                return Err(Error::Other(anyhow!(
+                    "Media [{key}] is over the limit of {} bytes.",
+                    self.max_size
+                )));
+            }
+            bytes.extend_from_slice(&chunk);
+        }
+
+        let media = Media {
+            url: key,
+            content_type,
+            bytes,
+        };
+        if let Some(cache) = &self.cache {
+            let _ = cache
+                .put(
+                    CacheCategory::NftMedia,
+                    &media.url,
+                    bcs::to_bytes(&media)?,
+                    self.cache_ttl,
+                )
+                .await;
+        }
+        Ok(media)
+    }
+
+    /// Fetch the JSON document at `url`, e.g. off-chain NFT metadata.
+    pub async fn fetch_json(&self, url: &str) -> SuiRpcResult<Value> {
+        let media = self
+            .clone()
+            .with_content_types(["application/json"])
+            .fetch(url)
+            .await?;
+        Ok(serde_json::from_slice(&media.bytes)?)
+    }
+
+    /// Fetch the image of `display`, rendered in its `image_url` field.
+    pub async fn fetch_image(&self, display: &NftDisplay) -> SuiRpcResult<Media> {
+        let url = display
+            .display
+            .get("image_url")
+            .ok_or_else(|| anyhow!("Object [{}] has no image URL.", display.object_id))?;
+        self.fetch(url).await
+    }
+
+    fn client(allow_http: bool) -> reqwest::Client {
+        reqwest::Client::builder()
+            .redirect(Self::redirect_policy(allow_http))
+            .build()
+            .unwrap_or_default()
+    }
+
+    fn check_content_type(&self, key: &str, content_type: &str) -> anyhow::Result<()> {
+        let essence = content_type.split(';').next().unwrap_or_default().trim();
+        if !self
+            .content_types
+            .iter()
+            .any(|allowed| essence.starts_with(&allowed.to_ascii_lowercase()))
+        {
+            bail!(
+                "Media [{key}] has content type [{content_type}], expected one of {:?}.",
+                self.content_types
+            );
+        }
+        Ok(())
+    }
+
+    fn check_size(&self, key: &str, length: usize) -> anyhow::Result<()> {
+        if length > self.max_size {
+            bail!(
+                "Media [{key}] is {length} bytes, over the limit of {} bytes.",
+                self.max_size
+            );
+        }
+        Ok(())
+    }
+}
+
+/// Whether URLs of `scheme` are fetched as they are, rather than through a gateway.
+fn is_fetched_directly(scheme: &str, allow_http: bool) -> bool {
+    scheme == "https" || (allow_http && scheme == "http")
+}
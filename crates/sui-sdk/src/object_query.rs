@@ -0,0 +1,308 @@
+// Copyright (c) 2022, Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Queries of the objects owned by an address, filtered by type and package.
+//!
+//! The node of this tree returns every object of an owner in one response, without filters;
+//! queries are evaluated on the client, against the types of the returned objects. A filter
+//! serializes to the JSON filter format of the `suix_getOwnedObjects` method of newer nodes
+//! (`StructType`, `Package`, `MoveModule`, `MatchAll`, `MatchAny` and `MatchNone`), so the
+//! same query can be forwarded once the node supports it.
+
+use std::collections::VecDeque;
+
+use anyhow::anyhow;
+use futures::{stream, Stream};
+use move_core_types::language_storage::StructTag;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+use sui_json_rpc_types::{SuiData, SuiObjectInfo};
+use sui_types::base_types::{ObjectID, SuiAddress};
+use sui_types::parse_sui_struct_tag;
+
+use crate::error::{Error, SuiRpcResult};
+use crate::ReadApi;
+
+/// Objects fetched per request when the content of the objects is selected.
+const DEFAULT_PAGE_SIZE: usize = 50;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ObjectFilter {
+    /// Objects of a struct type. A type without type parameters, e.g. `0x2::coin::Coin`,
+    /// matches every instantiation of a generic struct; a type with type parameters, e.g.
+    /// `0x2::coin::Coin<0x2::sui::SUI>`, only matches that instantiation.
+    StructType(#[serde(with = "struct_tag_string")] StructTag),
+    /// Objects of a struct type defined in a package.
+    Package(ObjectID),
+    /// Objects of a struct type defined in a module of a package.
+    MoveModule {
+        package: ObjectID,
+        module: String,
+    },
+    MatchAll(Vec<ObjectFilter>),
+    MatchAny(Vec<ObjectFilter>),
+    MatchNone(Vec<ObjectFilter>),
+}
+
+impl ObjectFilter {
+    /// A [ObjectFilter::StructType] filter from a type written as in Move, e.g.
+    /// `0x2::coin::Coin<0x2::sui::SUI>`.
+    pub fn struct_type(type_: &str) -> SuiRpcResult<Self> {
+        Ok(Self::StructType(parse_sui_struct_tag(type_.trim())?))
+    }
+
+    pub fn matches(&self, type_: &StructTag) -> bool {
+        match self {
+            Self::StructType(tag) => {
+                tag.address == type_.address
+                    && tag.module == type_.module
+                    && tag.name == type_.name
+                    && (tag.type_params.is_empty() || tag.type_params == type_.type_params)
+            }
+            Self::Package(package) => ObjectID::from(type_.address) == *package,
+            Self::MoveModule { package, module } => {
+                ObjectID::from(type_.address) == *package && type_.module.as_str() == module
+            }
+            Self::MatchAll(filters) => filters.iter().all(|filter| filter.matches(type_)),
+            Self::MatchAny(filters) => filters.iter().any(|filter| filter.matches(type_)),
+            Self::MatchNone(filters) => !filters.iter().any(|filter| filter.matches(type_)),
+        }
+    }
+
+    /// Whether an object of the type `type_`, as returned by the node, matches. Types that do
+    /// not parse, e.g. the `package` type of packages, match no filter.
+    pub fn matches_type_str(&self, type_: &str) -> bool {
+        parse_sui_struct_tag(type_).map_or(false, |tag| self.matches(&tag))
+    }
+
+    /// The filter in the JSON filter format of the node.
+    pub fn to_rpc_filter(&self) -> Value {
+        serde_json::to_value(self).expect("Object filters always serialize to JSON")
+    }
+
+    fn and(self, filter: Self) -> Self {
+        match self {
+            Self::MatchAll(mut filters) => {
+                filters.push(filter);
+                Self::MatchAll(filters)
+            }
+            this => Self::MatchAll(vec![this, filter]),
+        }
+    }
+}
+
+/// A query of the objects owned by an address. Filters added with the `with_*` methods must
+/// all match.
+#[derive(Debug, Clone)]
+pub struct ObjectQuery {
+    owner: SuiAddress,
+    filter: Option<ObjectFilter>,
+    fields: Option<Vec<String>>,
+    page_size: usize,
+}
+
+impl ObjectQuery {
+    pub fn owned_by(owner: SuiAddress) -> Self {
+        Self {
+            owner,
+            filter: None,
+            fields: None,
+            page_size: DEFAULT_PAGE_SIZE,
+        }
+    }
+
+    pub fn with_filter(mut self, filter: ObjectFilter) -> Self {
+        self.filter = Some(match self.filter {
+            Some(existing) => existing.and(filter),
+            None => filter,
+        });
+        self
+    }
+
+    pub fn with_struct_type(self, type_: StructTag) -> Self {
+        self.with_filter(ObjectFilter::StructType(type_))
+    }
+
+    pub fn with_package(self, package: ObjectID) -> Self {
+        self.with_filter(ObjectFilter::Package(package))
+    }
+
+    pub fn with_module(self, package: ObjectID, module: impl Into<String>) -> Self {
+        self.with_filter(ObjectFilter::MoveModule {
+            package,
+            module: module.into(),
+        })
+    }
+
+    /// Leave out objects matching `filter`.
+    pub fn without(self, filter: ObjectFilter) -> Self {
+        self.with_filter(ObjectFilter::MatchNone(vec![filter]))
+    }
+
+    /// Fetch the content of the matching objects, keeping only `fields`. Nested fields are
+    /// written `field.nested_field`; an empty list keeps every field.
+    pub fn select_fields<S: Into<String>>(mut self, fields: impl IntoIterator<Item = S>) -> Self {
+        self.fields = Some(fields.into_iter().map(Into::into).collect());
+        self
+    }
+
+    /// Number of objects whose content is fetched per round of requests.
+    pub fn with_page_size(mut self, page_size: usize) -> Self {
+        self.page_size = page_size.max(1);
+        self
+    }
+
+    pub fn owner(&self) -> SuiAddress {
+        self.owner
+    }
+
+    pub fn filter(&self) -> Option<&ObjectFilter> {
+        self.filter.as_ref()
+    }
+
+    /// The query as the parameters of the `suix_getOwnedObjects` method of newer nodes.
+    pub fn to_rpc_params(&self) -> Value {
+        let mut query = Map::new();
+        if let Some(filter) = &self.filter {
+            query.insert("filter".to_string(), filter.to_rpc_filter());
+        }
+        if self.fields.is_some() {
+            query.insert(
+                "options".to_string(),
+                serde_json::json!({ "showType": true, "showContent": true }),
+            );
+        }
+        Value::Array(vec![
+            Value::String(self.owner.to_string()),
+            Value::Object(query),
+        ])
+    }
+
+    pub fn matches(&self, info: &SuiObjectInfo) -> bool {
+        self.filter
+            .as_ref()
+            .map_or(true, |filter| filter.matches_type_str(&info.type_))
+    }
+}
+
+/// An object matching an [ObjectQuery], with the selected fields of its content if the query
+/// selects fields.
+#[derive(Debug, Clone)]
+pub struct QueriedObject {
+    pub info: SuiObjectInfo,
+    pub fields: Option<Value>,
+}
+
+/// Keep the `fields` of the JSON object `content`, or every field if `fields` is empty.
+/// Fields the object does not have are left out.
+pub fn select_fields(content: &Value, fields: &[String]) -> Value {
+    if fields.is_empty() {
+        return content.clone();
+    }
+    let mut selected = Map::new();
+    for field in fields {
+        let value = field
+            .split('.')
+            .try_fold(content, |value, field| value.get(field));
+        if let Some(value) = value {
+            selected.insert(field.clone(), value.clone());
+        }
+    }
+    Value::Object(selected)
+}
+
+impl ReadApi {
+    /// The objects matching `query`.
+    pub async fn query_objects(&self, query: &ObjectQuery) -> SuiRpcResult<Vec<QueriedObject>> {
+        let infos = self.matching_objects(query).await?;
+        Ok(self.with_content(query, infos).await?)
+    }
+
+    /// The objects matching `query`, fetching their content a page at a time as the stream is
+    /// consumed.
+    pub fn query_objects_stream<'a>(
+        &'a self,
+        query: ObjectQuery,
+    ) -> impl Stream<Item = SuiRpcResult<QueriedObject>> + 'a {
+        let state = (query, None::<VecDeque<SuiObjectInfo>>, VecDeque::new());
+        stream::try_unfold(state, move |(query, mut pending, mut page)| async move {
+            loop {
+                if let Some(object) = page.pop_front() {
+                    return Ok::<_, Error>(Some((object, (query, pending, page))));
+                }
+                let infos = match &mut pending {
+                    Some(infos) => infos,
+                    None => pending.insert(self.matching_objects(&query).await?.into()),
+                };
+                if infos.is_empty() {
+                    return Ok(None);
+                }
+                let size = query.page_size.min(infos.len());
+                let infos = infos.drain(..size).collect();
+                page = self.with_content(&query, infos).await?.into();
+            }
+        })
+    }
+
+    async fn matching_objects(&self, query: &ObjectQuery) -> anyhow::Result<Vec<SuiObjectInfo>> {
+        Ok(self
+            .get_objects_owned_by_address(query.owner)
+            .await?
+            .into_iter()
+            .filter(|info| query.matches(info))
+            .collect())
+    }
+
+    async fn with_content(
+        &self,
+        query: &ObjectQuery,
+        infos: Vec<SuiObjectInfo>,
+    ) -> anyhow::Result<Vec<QueriedObject>> {
+        let fields = match &query.fields {
+            Some(fields) => fields,
+            None => {
+                return Ok(infos
+                    .into_iter()
+                    .map(|info| QueriedObject { info, fields: None })
+                    .collect())
+            }
+        };
+        let responses = self
+            .fetch_all(infos.iter().map(|info| info.object_id), |id| {
+                self.get_parsed_object(id)
+            })
+            .await?;
+        infos
+            .into_iter()
+            .zip(responses)
+            .map(|(info, response)| {
+                let object = response.into_object()?;
+                let move_object = object
+                    .data
+                    .try_as_move()
+                    .ok_or_else(|| anyhow!("Object [{}] is not a Move object.", info.object_id))?;
+                let content = move_object.fields.clone().to_json_value()?;
+                Ok(QueriedObject {
+                    fields: Some(select_fields(&content, fields)),
+                    info,
+                })
+            })
+            .collect()
+    }
+}
+
+mod struct_tag_string {
+    use move_core_types::language_storage::StructTag;
+    use serde::{Deserialize, Deserializer, Serializer};
+    use sui_types::parse_sui_struct_tag;
+
+    pub fn serialize<S: Serializer>(tag: &StructTag, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&tag.to_string())
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<StructTag, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        parse_sui_struct_tag(&s).map_err(serde::de::Error::custom)
+    }
+}
@@ -0,0 +1,177 @@
+// Copyright (c) 2022, Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Streaming reads of large objects, for memory constrained services.
+//!
+//! A raw object read returns the BCS bytes of the object base64 encoded in a single JSON
+//! response, which the RPC client buffers and decodes whole: an object of a few megabytes
+//! costs several times its size in memory. [ReadApi::stream_object_bcs] instead decodes the
+//! response as it arrives, yielding the BCS bytes in chunks of the size received. The node
+//! has no ranged or field selected reads, so the whole object is still downloaded; to read
+//! only a few fields of an object, see [crate::lazy].
+
+use anyhow::anyhow;
+use futures::{stream, Stream};
+use serde_json::json;
+
+use sui_types::base_types::ObjectID;
+use sui_types::sui_serde::{Base64, Encoding};
+
+use crate::error::{Error, SuiRpcResult};
+use crate::{ReadApi, SuiClientApi};
+
+const CONTENT_KEY: &[u8] = b"\"bcs_bytes\"";
+/// Bytes of the start of a response kept to report responses without content.
+const HEAD_LEN: usize = 512;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum DecoderState {
+    /// Looking for the content in the response.
+    Key,
+    /// Decoding the content.
+    Value,
+    Done,
+}
+
+/// Decodes the BCS bytes of a raw object read from the JSON-RPC response text, chunk by
+/// chunk, keeping at most a few bytes between chunks.
+#[derive(Debug)]
+pub struct ContentDecoder {
+    state: DecoderState,
+    pending: Vec<u8>,
+    head: Vec<u8>,
+}
+
+impl Default for ContentDecoder {
+    fn default() -> Self {
+        Self {
+            state: DecoderState::Key,
+            pending: vec![],
+            head: vec![],
+        }
+    }
+}
+
+impl ContentDecoder {
+    /// Decode the next `chunk` of the response, returning the bytes of the content it
+    /// completes.
+    pub fn feed(&mut self, chunk: &[u8]) -> SuiRpcResult<Vec<u8>> {
+        if self.head.len() < HEAD_LEN {
+            let len = chunk.len().min(HEAD_LEN - self.head.len());
+            self.head.extend_from_slice(&chunk[..len]);
+        }
+        if self.state == DecoderState::Done {
+            return Ok(vec![]);
+        }
+        self.pending.extend_from_slice(chunk);
+
+        if self.state == DecoderState::Key {
+            let at = match find(&self.pending, CONTENT_KEY) {
+                Some(at) => at,
+                None => {
+                    // Keep the bytes which may start the key.
+                    let keep = self.pending.len().min(CONTENT_KEY.len() - 1);
+                    self.pending.drain(..self.pending.len() - keep);
+                    return Ok(vec![]);
+                }
+            };
+            self.pending.drain(..at + CONTENT_KEY.len());
+            match self.pending.iter().position(|b| !b" \t\r\n:".contains(b)) {
+                Some(start) if self.pending[start] == b'"' => {
+                    self.pending.drain(..=start);
+                    self.state = DecoderState::Value;
+                }
+                Some(_) => {
+                    return Err(Error::Other(anyhow!("The object content is not a string.")))
+                }
+                None => {
+                    // Look for the key again once the value arrives.
+                    self.pending.splice(..0, CONTENT_KEY.iter().copied());
+                    return Ok(vec![]);
+                }
+            }
+        }
+
+        let end = self.pending.iter().position(|b| *b == b'"');
+        // Decode whole groups of 4 characters, until the end of the content.
+        let len = end.unwrap_or(self.pending.len() / 4 * 4);
+        let decoded = Base64::decode(std::str::from_utf8(&self.pending[..len])?)?;
+        self.pending.drain(..len);
+        if end.is_some() {
+            self.pending.clear();
+            self.state = DecoderState::Done;
+        }
+        Ok(decoded)
+    }
+
+    pub fn is_done(&self) -> bool {
+        self.state == DecoderState::Done
+    }
+
+    /// Check the response was complete.
+    pub fn finish(&self) -> SuiRpcResult<()> {
+        match self.state {
+            DecoderState::Done => Ok(()),
+            DecoderState::Value => Err(Error::Other(anyhow!(
+                "The response ended within the object content."
+            ))),
+            DecoderState::Key => Err(Error::Other(anyhow!(
+                "The response has no object content: {}",
+                String::from_utf8_lossy(&self.head)
+            ))),
+        }
+    }
+}
+
+fn find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack
+        .windows(needle.len())
+        .position(|window| window == needle)
+}
+
+impl ReadApi {
+    /// The BCS bytes of the Move object `object_id`, decoded in chunks as the response
+    /// arrives.
+    pub async fn stream_object_bcs(
+        &self,
+        object_id: ObjectID,
+    ) -> SuiRpcResult<impl Stream<Item = SuiRpcResult<Vec<u8>>>> {
+        let url = match &*self.api {
+            SuiClientApi::Rpc(c) => c.http.url().to_string(),
+            SuiClientApi::Embedded(_) => {
+                return Err(Error::Other(anyhow!(
+                    "Streaming reads are not supported by the embedded gateway client."
+                )))
+            }
+        };
+        let request = json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "method": "sui_getRawObject",
+            "params": [object_id],
+        });
+        let response = reqwest::Client::new()
+            .post(url)
+            .json(&request)
+            .send()
+            .await?
+            .error_for_status()?;
+        Ok(stream::try_unfold(
+            (response, ContentDecoder::default()),
+            |(mut response, mut decoder)| async move {
+                while !decoder.is_done() {
+                    let chunk = match response.chunk().await? {
+                        Some(chunk) => chunk,
+                        None => break,
+                    };
+                    let decoded = decoder.feed(&chunk)?;
+                    if !decoded.is_empty() {
+                        return Ok(Some((decoded, (response, decoder))));
+                    }
+                }
+                decoder.finish()?;
+                Ok::<_, Error>(None)
+            },
+        ))
+    }
+}
@@ -0,0 +1,390 @@
+// Copyright (c) 2022, Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Construction of transactions from object references the caller already holds, without
+//! reading objects from a node, e.g. on an air-gapped signer given the references by an
+//! online machine. Available with the `signing-only` feature.
+//!
+//! The `*_call` functions build single commands, with `framework` the reference of the Sui
+//! framework package, and [transaction] turns them into the data to sign.
+//! [TransactionBuilder](crate::TransactionBuilder) reads the references from the node and
+//! builds its transactions with these functions.
+
+use move_core_types::ident_str;
+use move_core_types::identifier::IdentStr;
+use move_core_types::language_storage::TypeTag;
+
+use sui_types::base_types::{ObjectRef, SuiAddress};
+use sui_types::error::SuiError;
+use sui_types::messages::{
+    CallArg, MoveCall, ObjectArg, SingleTransactionKind, TransactionData, TransactionKind,
+    TransferObject,
+};
+use sui_types::sui_system_state::{
+    ADD_DELEGATION_FUNCTION_NAME, ADD_STAKE_FUNCTION_NAME, ADD_VALIDATOR_FUNCTION_NAME,
+    REMOVE_VALIDATOR_FUNCTION_NAME, SET_GAS_PRICE_FUNCTION_NAME, SUI_SYSTEM_MODULE_NAME,
+    WITHDRAW_DELEGATION_FUNCTION_NAME, WITHDRAW_STAKE_FUNCTION_NAME,
+};
+use sui_types::{coin, fp_ensure, SUI_SYSTEM_STATE_OBJECT_ID};
+
+use crate::amount::Amount;
+use crate::error::SuiRpcResult;
+
+pub const LOCKED_COIN_MODULE_NAME: &IdentStr = ident_str!("locked_coin");
+pub const LOCK_COIN_FUNCTION_NAME: &IdentStr = ident_str!("lock_coin");
+pub const UNLOCK_COIN_FUNCTION_NAME: &IdentStr = ident_str!("unlock_coin");
+
+/// Metadata a new validator registers with the system state.
+#[derive(Debug, Clone)]
+pub struct ValidatorRegistration {
+    pub pubkey_bytes: Vec<u8>,
+    pub network_pubkey_bytes: Vec<u8>,
+    /// Signature of the validator key over `pubkey_bytes || sui_address`.
+    pub proof_of_possession: Vec<u8>,
+    pub name: String,
+    pub net_address: Vec<u8>,
+    pub gas_price: u64,
+}
+
+/// A transaction of `calls`, batched if there are several.
+pub fn transaction(
+    signer: SuiAddress,
+    mut calls: Vec<SingleTransactionKind>,
+    gas: ObjectRef,
+    gas_budget: u64,
+) -> SuiRpcResult<TransactionData> {
+    fp_ensure!(
+        !calls.is_empty(),
+        SuiError::InvalidBatchTransaction {
+            error: "Batch Transaction cannot be empty".to_owned(),
+        }
+        .into()
+    );
+    let kind = if calls.len() == 1 {
+        TransactionKind::Single(calls.remove(0))
+    } else {
+        TransactionKind::Batch(calls)
+    };
+    Ok(TransactionData::new(kind, signer, gas, gas_budget))
+}
+
+pub fn transfer_object(
+    signer: SuiAddress,
+    object: ObjectRef,
+    recipient: SuiAddress,
+    gas: ObjectRef,
+    gas_budget: u64,
+) -> TransactionData {
+    TransactionData::new(
+        TransactionKind::Single(transfer_object_call(object, recipient)),
+        signer,
+        gas,
+        gas_budget,
+    )
+}
+
+pub fn transfer_object_call(object: ObjectRef, recipient: SuiAddress) -> SingleTransactionKind {
+    SingleTransactionKind::TransferObject(TransferObject {
+        recipient,
+        object_ref: object,
+    })
+}
+
+/// Send `amount` of the SUI coin `sui_coin`, or the whole of it if `None`, paying the gas
+/// from the same coin.
+pub fn transfer_sui(
+    signer: SuiAddress,
+    sui_coin: ObjectRef,
+    recipient: SuiAddress,
+    amount: Option<Amount>,
+    gas_budget: u64,
+) -> TransactionData {
+    TransactionData::new_transfer_sui(
+        recipient,
+        signer,
+        amount.map(|amount| amount.value()),
+        sui_coin,
+        gas_budget,
+    )
+}
+
+/// Pay `amounts[i]` to `recipients[i]` from `coins`, which must not include `gas`. The
+/// amounts are of the coin type of `coins`.
+pub fn pay(
+    signer: SuiAddress,
+    coins: Vec<ObjectRef>,
+    recipients: Vec<SuiAddress>,
+    amounts: Vec<Amount>,
+    gas: ObjectRef,
+    gas_budget: u64,
+) -> TransactionData {
+    TransactionData::new_pay(
+        signer,
+        coins,
+        recipients,
+        amounts.iter().map(Amount::value).collect(),
+        gas,
+        gas_budget,
+    )
+}
+
+/// Split `split_amounts` off `coin`, a `Coin<coin_type>`.
+pub fn split_coin_call(
+    framework: ObjectRef,
+    coin: ObjectRef,
+    coin_type: TypeTag,
+    split_amounts: &[Amount],
+) -> SuiRpcResult<SingleTransactionKind> {
+    let split_amounts = split_amounts.iter().map(Amount::value).collect::<Vec<_>>();
+    Ok(coin_call(
+        framework,
+        coin::COIN_SPLIT_VEC_FUNC_NAME,
+        coin_type,
+        vec![
+            CallArg::Object(ObjectArg::ImmOrOwnedObject(coin)),
+            CallArg::Pure(bcs::to_bytes(&split_amounts)?),
+        ],
+    ))
+}
+
+/// Split `coin`, a `Coin<coin_type>`, into `split_count` coins of equal value.
+pub fn split_coin_equal_call(
+    framework: ObjectRef,
+    coin: ObjectRef,
+    coin_type: TypeTag,
+    split_count: u64,
+) -> SuiRpcResult<SingleTransactionKind> {
+    Ok(coin_call(
+        framework,
+        coin::COIN_SPLIT_N_FUNC_NAME,
+        coin_type,
+        vec![
+            CallArg::Object(ObjectArg::ImmOrOwnedObject(coin)),
+            CallArg::Pure(bcs::to_bytes(&split_count)?),
+        ],
+    ))
+}
+
+/// Merge `coin_to_merge` into `primary_coin`, both `Coin<coin_type>`s.
+pub fn merge_coins_call(
+    framework: ObjectRef,
+    primary_coin: ObjectRef,
+    coin_to_merge: ObjectRef,
+    coin_type: TypeTag,
+) -> SingleTransactionKind {
+    coin_call(
+        framework,
+        coin::COIN_JOIN_FUNC_NAME,
+        coin_type,
+        vec![
+            CallArg::Object(ObjectArg::ImmOrOwnedObject(primary_coin)),
+            CallArg::Object(ObjectArg::ImmOrOwnedObject(coin_to_merge)),
+        ],
+    )
+}
+
+fn coin_call(
+    framework: ObjectRef,
+    function: &IdentStr,
+    coin_type: TypeTag,
+    arguments: Vec<CallArg>,
+) -> SingleTransactionKind {
+    SingleTransactionKind::Call(MoveCall {
+        package: framework,
+        module: coin::COIN_MODULE_NAME.to_owned(),
+        function: function.to_owned(),
+        type_arguments: vec![coin_type],
+        arguments,
+    })
+}
+
+/// Call `function` of the `sui_system` module on the system state, followed by `call_args`.
+pub fn sui_system_call(
+    framework: ObjectRef,
+    function: &IdentStr,
+    call_args: Vec<CallArg>,
+) -> SingleTransactionKind {
+    let mut arguments = vec![CallArg::Object(ObjectArg::SharedObject(
+        SUI_SYSTEM_STATE_OBJECT_ID,
+    ))];
+    arguments.extend(call_args);
+    SingleTransactionKind::Call(MoveCall {
+        package: framework,
+        module: SUI_SYSTEM_MODULE_NAME.to_owned(),
+        function: function.to_owned(),
+        type_arguments: vec![],
+        arguments,
+    })
+}
+
+/// Delegate the whole of `coin` to `validator`.
+pub fn add_delegation_call(
+    framework: ObjectRef,
+    coin: ObjectRef,
+    validator: SuiAddress,
+) -> SuiRpcResult<SingleTransactionKind> {
+    Ok(sui_system_call(
+        framework,
+        ADD_DELEGATION_FUNCTION_NAME,
+        vec![
+            CallArg::Object(ObjectArg::ImmOrOwnedObject(coin)),
+            CallArg::Pure(bcs::to_bytes(&validator)?),
+        ],
+    ))
+}
+
+/// Withdraw `pool_token_amount` pool tokens worth of stake from `delegation`, paying the
+/// principal back from `staked_sui`.
+pub fn withdraw_delegation_call(
+    framework: ObjectRef,
+    delegation: ObjectRef,
+    staked_sui: ObjectRef,
+    pool_token_amount: u64,
+) -> SuiRpcResult<SingleTransactionKind> {
+    Ok(sui_system_call(
+        framework,
+        WITHDRAW_DELEGATION_FUNCTION_NAME,
+        vec![
+            CallArg::Object(ObjectArg::ImmOrOwnedObject(delegation)),
+            CallArg::Object(ObjectArg::ImmOrOwnedObject(staked_sui)),
+            CallArg::Pure(bcs::to_bytes(&pool_token_amount)?),
+        ],
+    ))
+}
+
+/// Join the validator set at the next epoch, staking the whole of `stake`.
+pub fn add_validator_call(
+    framework: ObjectRef,
+    metadata: &ValidatorRegistration,
+    stake: ObjectRef,
+) -> SuiRpcResult<SingleTransactionKind> {
+    Ok(sui_system_call(
+        framework,
+        ADD_VALIDATOR_FUNCTION_NAME,
+        vec![
+            CallArg::Pure(bcs::to_bytes(&metadata.pubkey_bytes)?),
+            CallArg::Pure(bcs::to_bytes(&metadata.network_pubkey_bytes)?),
+            CallArg::Pure(bcs::to_bytes(&metadata.proof_of_possession)?),
+            CallArg::Pure(bcs::to_bytes(metadata.name.as_bytes())?),
+            CallArg::Pure(bcs::to_bytes(&metadata.net_address)?),
+            CallArg::Object(ObjectArg::ImmOrOwnedObject(stake)),
+            CallArg::Pure(bcs::to_bytes(&metadata.gas_price)?),
+        ],
+    ))
+}
+
+/// Leave the validator set at the end of the current epoch.
+pub fn remove_validator_call(framework: ObjectRef) -> SingleTransactionKind {
+    sui_system_call(framework, REMOVE_VALIDATOR_FUNCTION_NAME, vec![])
+}
+
+/// Quote `gas_price` for the next epoch.
+pub fn set_gas_price_call(
+    framework: ObjectRef,
+    gas_price: u64,
+) -> SuiRpcResult<SingleTransactionKind> {
+    Ok(sui_system_call(
+        framework,
+        SET_GAS_PRICE_FUNCTION_NAME,
+        vec![CallArg::Pure(bcs::to_bytes(&gas_price)?)],
+    ))
+}
+
+/// Add the whole of `coin` to the signer's validator stake.
+pub fn add_stake_call(framework: ObjectRef, coin: ObjectRef) -> SingleTransactionKind {
+    sui_system_call(
+        framework,
+        ADD_STAKE_FUNCTION_NAME,
+        vec![CallArg::Object(ObjectArg::ImmOrOwnedObject(coin))],
+    )
+}
+
+/// Withdraw `amount` from the validator `stake` object.
+pub fn withdraw_stake_call(
+    framework: ObjectRef,
+    stake: ObjectRef,
+    amount: u64,
+) -> SuiRpcResult<SingleTransactionKind> {
+    Ok(sui_system_call(
+        framework,
+        WITHDRAW_STAKE_FUNCTION_NAME,
+        vec![
+            CallArg::Object(ObjectArg::ImmOrOwnedObject(stake)),
+            CallArg::Pure(bcs::to_bytes(&amount)?),
+        ],
+    ))
+}
+
+/// Send the whole of `coin`, a `Coin<coin_type>`, to `recipient`, locked until
+/// `unlock_epoch`.
+pub fn lock_coin_call(
+    framework: ObjectRef,
+    coin: ObjectRef,
+    coin_type: TypeTag,
+    recipient: SuiAddress,
+    unlock_epoch: u64,
+) -> SuiRpcResult<SingleTransactionKind> {
+    Ok(locked_coin_call(
+        framework,
+        LOCK_COIN_FUNCTION_NAME,
+        coin,
+        coin_type,
+        vec![
+            CallArg::Pure(bcs::to_bytes(&recipient)?),
+            CallArg::Pure(bcs::to_bytes(&unlock_epoch)?),
+        ],
+    ))
+}
+
+/// Turn `locked_coin`, a `LockedCoin<coin_type>` whose unlock epoch has started, back into a
+/// coin of the signer.
+pub fn unlock_coin_call(
+    framework: ObjectRef,
+    locked_coin: ObjectRef,
+    coin_type: TypeTag,
+) -> SingleTransactionKind {
+    locked_coin_call(
+        framework,
+        UNLOCK_COIN_FUNCTION_NAME,
+        locked_coin,
+        coin_type,
+        vec![],
+    )
+}
+
+/// Grant `coin`, a `Coin<coin_type>`, to `grantee` from `unlock_epoch`: locked if that is
+/// after the current `epoch`, transferred otherwise.
+pub fn vesting_tranche_call(
+    framework: ObjectRef,
+    coin: ObjectRef,
+    coin_type: TypeTag,
+    grantee: SuiAddress,
+    unlock_epoch: u64,
+    epoch: u64,
+) -> SuiRpcResult<SingleTransactionKind> {
+    if unlock_epoch > epoch {
+        lock_coin_call(framework, coin, coin_type, grantee, unlock_epoch)
+    } else {
+        Ok(transfer_object_call(coin, grantee))
+    }
+}
+
+/// Call `function` of the `locked_coin` module on `object`, a `Coin<T>` or a
+/// `LockedCoin<T>`, followed by `call_args`.
+fn locked_coin_call(
+    framework: ObjectRef,
+    function: &IdentStr,
+    object: ObjectRef,
+    coin_type: TypeTag,
+    call_args: Vec<CallArg>,
+) -> SingleTransactionKind {
+    let mut arguments = vec![CallArg::Object(ObjectArg::ImmOrOwnedObject(object))];
+    arguments.extend(call_args);
+    SingleTransactionKind::Call(MoveCall {
+        package: framework,
+        module: LOCKED_COIN_MODULE_NAME.to_owned(),
+        function: function.to_owned(),
+        type_arguments: vec![coin_type],
+        arguments,
+    })
+}
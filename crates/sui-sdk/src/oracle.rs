@@ -0,0 +1,234 @@
+// Copyright (c) 2022, Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Typed reads of on-chain price oracle objects, for DeFi backends.
+//!
+//! Oracles keep a price as an integer and a scale: an exponent field, e.g. `-8` for a price
+//! in hundred-millionths, or decimals fixed by the feed. An [OracleLayout] names the fields
+//! of an oracle object holding the price, its scale, its confidence interval and its
+//! publication time; field names may be dotted paths into nested structs, e.g.
+//! `price_info.price`. Signed integers are read from numbers, strings, or structs with
+//! `negative` and `magnitude` fields.
+
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use anyhow::anyhow;
+use serde_json::Value;
+
+use sui_types::base_types::ObjectID;
+
+use crate::error::{Error, SuiRpcResult};
+use crate::ReadApi;
+
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum OracleError {
+    #[error("Price of [{object_id}] is {age:?} old, older than {max_age:?}.")]
+    Stale {
+        object_id: ObjectID,
+        age: Duration,
+        max_age: Duration,
+    },
+    #[error("Price of [{object_id}] has no timestamp to check its age.")]
+    MissingTimestamp { object_id: ObjectID },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum TimestampUnit {
+    Seconds,
+    Milliseconds,
+}
+
+/// The fields of an oracle object.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OracleLayout {
+    price: String,
+    exponent: Option<String>,
+    decimals: u8,
+    confidence: Option<String>,
+    timestamp: Option<(String, TimestampUnit)>,
+}
+
+impl OracleLayout {
+    /// Prices in the field `price`, as integers.
+    pub fn new(price: impl Into<String>) -> Self {
+        Self {
+            price: price.into(),
+            exponent: None,
+            decimals: 0,
+            confidence: None,
+            timestamp: None,
+        }
+    }
+
+    /// The price is scaled by 10 to the power of the field `exponent`.
+    pub fn with_exponent(mut self, exponent: impl Into<String>) -> Self {
+        self.exponent = Some(exponent.into());
+        self
+    }
+
+    /// The price has `decimals` decimals, for feeds without an exponent field.
+    pub fn with_decimals(mut self, decimals: u8) -> Self {
+        self.decimals = decimals;
+        self
+    }
+
+    /// The confidence interval of the price, at the scale of the price.
+    pub fn with_confidence(mut self, confidence: impl Into<String>) -> Self {
+        self.confidence = Some(confidence.into());
+        self
+    }
+
+    /// The publication time of the price, in seconds since the Unix epoch.
+    pub fn with_timestamp_secs(mut self, timestamp: impl Into<String>) -> Self {
+        self.timestamp = Some((timestamp.into(), TimestampUnit::Seconds));
+        self
+    }
+
+    /// The publication time of the price, in milliseconds since the Unix epoch.
+    pub fn with_timestamp_ms(mut self, timestamp: impl Into<String>) -> Self {
+        self.timestamp = Some((timestamp.into(), TimestampUnit::Milliseconds));
+        self
+    }
+
+    /// Read the price of the oracle object `object_id` from its `fields`.
+    pub fn parse(&self, object_id: ObjectID, fields: &Value) -> SuiRpcResult<OraclePrice> {
+        let read = |name: &str| {
+            let value = name
+                .split('.')
+                .try_fold(fields, |value, field| value.get(field))
+                .ok_or_else(|| anyhow!("Oracle [{object_id}] has no field [{name}]."))?;
+            integer(value)
+                .ok_or_else(|| anyhow!("Field [{name}] of oracle [{object_id}] is not a number."))
+        };
+        let exponent = match &self.exponent {
+            Some(name) => i32::try_from(read(name)?)?,
+            None => -(self.decimals as i32),
+        };
+        let confidence = match &self.confidence {
+            Some(name) => Some(u128::try_from(read(name)?)?),
+            None => None,
+        };
+        let timestamp_ms = match &self.timestamp {
+            Some((name, unit)) => {
+                let timestamp = u64::try_from(read(name)?)?;
+                Some(match unit {
+                    TimestampUnit::Seconds => timestamp.saturating_mul(1000),
+                    TimestampUnit::Milliseconds => timestamp,
+                })
+            }
+            None => None,
+        };
+        Ok(OraclePrice {
+            object_id,
+            price: read(&self.price)?,
+            exponent,
+            confidence,
+            timestamp_ms,
+        })
+    }
+}
+
+/// A signed integer in JSON: a number, a string, or a struct with `negative` and
+/// `magnitude` fields.
+fn integer(value: &Value) -> Option<i128> {
+    match value {
+        Value::Number(n) => n
+            .as_i64()
+            .map(i128::from)
+            .or_else(|| n.as_u64().map(i128::from)),
+        Value::String(s) => s.parse().ok(),
+        Value::Object(fields) => {
+            let magnitude = integer(fields.get("magnitude")?)?;
+            Some(match fields.get("negative")?.as_bool()? {
+                true => -magnitude,
+                false => magnitude,
+            })
+        }
+        _ => None,
+    }
+}
+
+/// A price read from an oracle: `price * 10^exponent`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OraclePrice {
+    pub object_id: ObjectID,
+    pub price: i128,
+    pub exponent: i32,
+    /// The confidence interval, at the scale of the price.
+    pub confidence: Option<u128>,
+    pub timestamp_ms: Option<u64>,
+}
+
+impl OraclePrice {
+    /// The price with `decimals` decimals, e.g. `123_45` for a price of 123.45 with 2
+    /// decimals, rounded towards zero. `None` if it does not fit in an `i128`.
+    pub fn normalized(&self, decimals: u8) -> Option<i128> {
+        rescale(self.price, self.exponent + decimals as i32)
+    }
+
+    /// The confidence interval with `decimals` decimals.
+    pub fn normalized_confidence(&self, decimals: u8) -> Option<u128> {
+        let confidence = i128::try_from(self.confidence?).ok()?;
+        rescale(confidence, self.exponent + decimals as i32).map(|c| c as u128)
+    }
+
+    pub fn to_f64(&self) -> f64 {
+        self.price as f64 * 10f64.powi(self.exponent)
+    }
+
+    /// Fail if the price was published more than `max_age` ago.
+    pub fn check_age(&self, max_age: Duration) -> Result<(), OracleError> {
+        let timestamp_ms = self.timestamp_ms.ok_or(OracleError::MissingTimestamp {
+            object_id: self.object_id,
+        })?;
+        let now_ms = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |d| d.as_millis() as u64);
+        let age = Duration::from_millis(now_ms.saturating_sub(timestamp_ms));
+        if age > max_age {
+            return Err(OracleError::Stale {
+                object_id: self.object_id,
+                age,
+                max_age,
+            });
+        }
+        Ok(())
+    }
+}
+
+/// `value * 10^shift`, rounded towards zero.
+fn rescale(value: i128, shift: i32) -> Option<i128> {
+    if shift >= 0 {
+        value.checked_mul(10i128.checked_pow(shift as u32)?)
+    } else {
+        Some(
+            10i128
+                .checked_pow(shift.unsigned_abs())
+                .map_or(0, |factor| value / factor),
+        )
+    }
+}
+
+impl ReadApi {
+    /// Read the price of the oracle object `object_id` laid out as `layout`, failing if it
+    /// is older than `max_age`.
+    pub async fn get_oracle_price(
+        &self,
+        object_id: ObjectID,
+        layout: &OracleLayout,
+        max_age: Option<Duration>,
+    ) -> SuiRpcResult<OraclePrice> {
+        let object = self.get_parsed_object(object_id).await?.into_object()?;
+        let move_object = object
+            .data
+            .try_as_move()
+            .ok_or_else(|| anyhow!("Oracle [{object_id}] is not a Move object."))?;
+        let price = layout.parse(object_id, &move_object.fields.clone().to_json_value()?)?;
+        if let Some(max_age) = max_age {
+            price
+                .check_age(max_age)
+                .map_err(|e| Error::Other(e.into()))?;
+        }
+        Ok(price)
+    }
+}
@@ -0,0 +1,177 @@
+// Copyright (c) 2022, Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Changes of the objects owned by an address, derived from transaction effects, so wallets
+//! can keep their object list up to date instead of rescanning owned objects.
+//!
+//! Objects owned by an address can only be mutated, deleted, wrapped or given away by
+//! transactions the address sends; the previous owner of those objects is read at their
+//! previous version. Objects sent to the address by others are seen in the effects alone.
+
+use futures::{stream, Stream, StreamExt};
+
+use sui_json_rpc_types::{SuiTransactionFilter, SuiTransactionResponse};
+use sui_types::base_types::{ObjectID, ObjectRef, SequenceNumber, SuiAddress, TransactionDigest};
+use sui_types::object::Owner;
+
+use crate::balance_changes::touches;
+use crate::error::{Error, SuiRpcResult};
+use crate::{ReadApi, SuiClient};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum OwnedObjectChangeKind {
+    Created,
+    /// Mutated by the owner, who still owns it.
+    Mutated,
+    Deleted,
+    /// Wrapped in another object; it leaves the object list until unwrapped.
+    Wrapped,
+    /// Sent to the address by another address, or unwrapped.
+    TransferredIn,
+    /// Given away by the address, e.g. transferred, shared or frozen.
+    TransferredOut {
+        recipient: Owner,
+    },
+}
+
+/// A change of an object owned by an address in a transaction.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OwnedObjectChange {
+    pub tx_digest: TransactionDigest,
+    pub timestamp_ms: Option<u64>,
+    pub address: SuiAddress,
+    pub object_id: ObjectID,
+    /// The version of the object after the transaction.
+    pub version: SequenceNumber,
+    pub kind: OwnedObjectChangeKind,
+}
+
+impl OwnedObjectChange {
+    /// Whether the object is owned by the address after the transaction.
+    pub fn is_owned(&self) -> bool {
+        matches!(
+            self.kind,
+            OwnedObjectChangeKind::Created
+                | OwnedObjectChangeKind::Mutated
+                | OwnedObjectChangeKind::TransferredIn
+        )
+    }
+}
+
+/// The owner of `object_id` at `version`.
+async fn past_owner(
+    read_api: &ReadApi,
+    object_id: ObjectID,
+    version: SequenceNumber,
+) -> anyhow::Result<Owner> {
+    let past = read_api
+        .try_get_parsed_past_object(object_id, version)
+        .await?;
+    Ok(past.object()?.owner)
+}
+
+/// The changes of the objects owned by `address` made by a transaction.
+pub async fn owned_object_changes(
+    read_api: &ReadApi,
+    address: SuiAddress,
+    response: &SuiTransactionResponse,
+) -> SuiRpcResult<Vec<OwnedObjectChange>> {
+    if !touches(response, address) {
+        return Ok(vec![]);
+    }
+    let owner = Owner::AddressOwner(address);
+    let is_sender = response.certificate.data.sender == address;
+    let effects = &response.effects;
+    let change = |reference: &ObjectRef, kind: OwnedObjectChangeKind| OwnedObjectChange {
+        tx_digest: response.certificate.transaction_digest,
+        timestamp_ms: response.timestamp_ms,
+        address,
+        object_id: reference.0,
+        version: reference.1,
+        kind,
+    };
+
+    let mut changes = vec![];
+    for object in &effects.created {
+        if object.owner == owner {
+            changes.push(change(
+                &object.reference.to_object_ref(),
+                OwnedObjectChangeKind::Created,
+            ));
+        }
+    }
+    for object in &effects.unwrapped {
+        if object.owner == owner {
+            changes.push(change(
+                &object.reference.to_object_ref(),
+                OwnedObjectChangeKind::TransferredIn,
+            ));
+        }
+    }
+    for object in &effects.mutated {
+        let reference = object.reference.to_object_ref();
+        if !is_sender {
+            if object.owner == owner {
+                changes.push(change(&reference, OwnedObjectChangeKind::TransferredIn));
+            }
+            continue;
+        }
+        let previous = past_owner(read_api, reference.0, reference.1.decrement()?).await?;
+        match (previous == owner, object.owner == owner) {
+            (true, true) => changes.push(change(&reference, OwnedObjectChangeKind::Mutated)),
+            (false, true) => changes.push(change(&reference, OwnedObjectChangeKind::TransferredIn)),
+            (true, false) => changes.push(change(
+                &reference,
+                OwnedObjectChangeKind::TransferredOut {
+                    recipient: object.owner,
+                },
+            )),
+            (false, false) => {}
+        }
+    }
+    if is_sender {
+        let removed = effects
+            .deleted
+            .iter()
+            .map(|reference| (reference, OwnedObjectChangeKind::Deleted))
+            .chain(
+                effects
+                    .wrapped
+                    .iter()
+                    .map(|reference| (reference, OwnedObjectChangeKind::Wrapped)),
+            );
+        for (reference, kind) in removed {
+            let reference = reference.to_object_ref();
+            if past_owner(read_api, reference.0, reference.1.decrement()?).await? == owner {
+                changes.push(change(&reference, kind));
+            }
+        }
+    }
+    Ok(changes)
+}
+
+impl SuiClient {
+    /// Stream the changes of the objects owned by `address` made by the transactions executed
+    /// from now on.
+    pub async fn subscribe_owned_objects(
+        &self,
+        address: SuiAddress,
+    ) -> SuiRpcResult<impl Stream<Item = SuiRpcResult<OwnedObjectChange>> + '_> {
+        let transactions = self
+            .event_api()
+            .subscribe_transaction(SuiTransactionFilter::Any)
+            .await?;
+        Ok(transactions
+            .then(move |response| async move {
+                let changes = owned_object_changes(self.read_api(), address, &response?).await?;
+                Ok::<_, Error>(changes)
+            })
+            .flat_map(|changes: SuiRpcResult<Vec<OwnedObjectChange>>| {
+                let changes = match changes {
+                    Ok(changes) => changes.into_iter().map(Ok).collect(),
+                    Err(e) => vec![Err(e)],
+                };
+                stream::iter(changes)
+            }))
+    }
+}
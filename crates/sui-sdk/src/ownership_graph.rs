@@ -0,0 +1,279 @@
+// Copyright (c) 2022, Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+use std::collections::{BTreeMap, HashSet};
+use std::sync::Mutex;
+
+use futures::future::{try_join_all, BoxFuture};
+use futures::FutureExt;
+use tokio::sync::Semaphore;
+
+use sui_json_rpc_types::{SuiData, SuiMoveStruct, SuiMoveValue, SuiParsedData};
+use sui_types::base_types::{ObjectID, SequenceNumber};
+
+use crate::error::SuiRpcResult;
+use crate::ReadApi;
+
+/// How an [OwnershipNode] is held by its parent.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Relation {
+    Root,
+    /// The object is owned by its parent and stored separately.
+    Owned,
+    /// The object is stored inside its parent, at the given field path, e.g. `items[2].inner`.
+    Wrapped {
+        field: String,
+    },
+}
+
+#[derive(Debug, Clone)]
+pub struct OwnershipNode {
+    pub object_id: ObjectID,
+    /// The Move type, if known.
+    pub type_: Option<String>,
+    /// The object version; wrapped objects have no version of their own.
+    pub version: Option<SequenceNumber>,
+    pub relation: Relation,
+    pub children: Vec<OwnershipNode>,
+    /// The children were not explored because the depth limit was reached.
+    pub truncated: bool,
+}
+
+impl OwnershipNode {
+    /// Visit the node and its descendants depth first, with their depth.
+    pub fn walk(&self, mut visit: impl FnMut(&OwnershipNode, usize)) {
+        fn walk_inner(
+            node: &OwnershipNode,
+            depth: usize,
+            visit: &mut dyn FnMut(&OwnershipNode, usize),
+        ) {
+            visit(node, depth);
+            for child in &node.children {
+                walk_inner(child, depth + 1, visit);
+            }
+        }
+        walk_inner(self, 0, &mut visit)
+    }
+
+    /// Number of nodes in the tree rooted at this node.
+    pub fn node_count(&self) -> usize {
+        1 + self
+            .children
+            .iter()
+            .map(OwnershipNode::node_count)
+            .sum::<usize>()
+    }
+}
+
+/// Resolves the objects owned by, or wrapped in, a root object into an [OwnershipNode]
+/// tree. Wrapped objects are found by looking for structs with an `id: UID` field in the
+/// parsed contents of their parent.
+pub struct OwnershipExplorer<'a> {
+    read_api: &'a ReadApi,
+    max_depth: usize,
+    permits: Semaphore,
+    visited: Mutex<HashSet<ObjectID>>,
+}
+
+impl<'a> OwnershipExplorer<'a> {
+    pub fn new(read_api: &'a ReadApi) -> Self {
+        Self {
+            read_api,
+            max_depth: 8,
+            permits: Semaphore::new(16),
+            visited: Mutex::new(HashSet::new()),
+        }
+    }
+
+    /// Depth below the root at which exploration stops. Defaults to 8.
+    pub fn with_max_depth(mut self, max_depth: usize) -> Self {
+        self.max_depth = max_depth;
+        self
+    }
+
+    /// Maximum number of concurrent requests to the node. Defaults to 16.
+    pub fn with_concurrency(mut self, concurrency: usize) -> Self {
+        self.permits = Semaphore::new(concurrency.max(1));
+        self
+    }
+
+    pub async fn explore(&self, root: ObjectID) -> SuiRpcResult<OwnershipNode> {
+        self.visited.lock().unwrap().clear();
+        Ok(self.explore_owned(root, Relation::Root, 0).await?)
+    }
+
+    fn explore_owned(
+        &self,
+        object_id: ObjectID,
+        relation: Relation,
+        depth: usize,
+    ) -> BoxFuture<'_, anyhow::Result<OwnershipNode>> {
+        async move {
+            let object = {
+                let _permit = self.permits.acquire().await?;
+                self.read_api
+                    .get_parsed_object(object_id)
+                    .await?
+                    .into_object()?
+            };
+            let mut node = OwnershipNode {
+                object_id,
+                type_: object.data.type_().map(|type_| type_.to_string()),
+                version: Some(object.reference.version),
+                relation,
+                children: vec![],
+                truncated: false,
+            };
+            let wrapped = match &object.data {
+                SuiParsedData::MoveObject(object) => wrapped_objects(&object.fields),
+                SuiParsedData::Package(_) => vec![],
+            };
+            self.explore_children(&mut node, wrapped, depth).await?;
+            Ok(node)
+        }
+        .boxed()
+    }
+
+    fn explore_wrapped(
+        &self,
+        wrapped: WrappedObject,
+        depth: usize,
+    ) -> BoxFuture<'_, anyhow::Result<OwnershipNode>> {
+        async move {
+            let mut node = OwnershipNode {
+                object_id: wrapped.object_id,
+                type_: wrapped.type_,
+                version: None,
+                relation: Relation::Wrapped {
+                    field: wrapped.field,
+                },
+                children: vec![],
+                truncated: false,
+            };
+            let nested = wrapped
+                .fields
+                .as_ref()
+                .map(wrapped_objects)
+                .unwrap_or_default();
+            self.explore_children(&mut node, nested, depth).await?;
+            Ok(node)
+        }
+        .boxed()
+    }
+
+    async fn explore_children(
+        &self,
+        node: &mut OwnershipNode,
+        wrapped: Vec<WrappedObject>,
+        depth: usize,
+    ) -> anyhow::Result<()> {
+        if !self.visited.lock().unwrap().insert(node.object_id) {
+            return Ok(());
+        }
+        if depth >= self.max_depth {
+            node.truncated = true;
+            return Ok(());
+        }
+        let owned = {
+            let _permit = self.permits.acquire().await?;
+            self.read_api
+                .get_objects_owned_by_object(node.object_id)
+                .await?
+        };
+        let owned = owned
+            .into_iter()
+            .map(|info| self.explore_owned(info.object_id, Relation::Owned, depth + 1));
+        let wrapped = wrapped
+            .into_iter()
+            .map(|wrapped| self.explore_wrapped(wrapped, depth + 1));
+        node.children = try_join_all(owned.chain(wrapped)).await?;
+        Ok(())
+    }
+}
+
+impl ReadApi {
+    /// Resolve the ownership tree of `root` with the default depth and concurrency limits.
+    pub async fn get_ownership_tree(&self, root: ObjectID) -> SuiRpcResult<OwnershipNode> {
+        OwnershipExplorer::new(self).explore(root).await
+    }
+}
+
+struct WrappedObject {
+    object_id: ObjectID,
+    type_: Option<String>,
+    field: String,
+    fields: Option<SuiMoveStruct>,
+}
+
+/// Find the objects stored directly in `fields`, without descending into them.
+fn wrapped_objects(fields: &SuiMoveStruct) -> Vec<WrappedObject> {
+    let mut found = vec![];
+    for (path, value) in struct_fields(fields, "") {
+        collect_wrapped(value, path, &mut found);
+    }
+    found
+}
+
+fn struct_fields<'a>(fields: &'a SuiMoveStruct, prefix: &str) -> Vec<(String, &'a SuiMoveValue)> {
+    let join = |name: &str| {
+        if prefix.is_empty() {
+            name.to_string()
+        } else {
+            format!("{prefix}.{name}")
+        }
+    };
+    match fields {
+        SuiMoveStruct::Runtime(values) => values
+            .iter()
+            .enumerate()
+            .map(|(i, value)| (join(&i.to_string()), value))
+            .collect(),
+        SuiMoveStruct::WithTypes { fields, .. } | SuiMoveStruct::WithFields(fields) => fields
+            .iter()
+            .map(|(name, value)| (join(name), value))
+            .collect(),
+    }
+}
+
+fn struct_id(fields: &SuiMoveStruct) -> Option<ObjectID> {
+    let fields: &BTreeMap<String, SuiMoveValue> = match fields {
+        SuiMoveStruct::WithTypes { fields, .. } | SuiMoveStruct::WithFields(fields) => fields,
+        SuiMoveStruct::Runtime(_) => return None,
+    };
+    match fields.get("id") {
+        Some(SuiMoveValue::UID { id }) => Some(*id),
+        _ => None,
+    }
+}
+
+fn collect_wrapped(value: &SuiMoveValue, path: String, found: &mut Vec<WrappedObject>) {
+    match value {
+        SuiMoveValue::Struct(inner) => match struct_id(inner) {
+            Some(object_id) => found.push(WrappedObject {
+                object_id,
+                type_: match inner {
+                    SuiMoveStruct::WithTypes { type_, .. } => Some(type_.clone()),
+                    _ => None,
+                },
+                field: path,
+                fields: Some(inner.clone()),
+            }),
+            None => {
+                for (path, value) in struct_fields(inner, &path) {
+                    collect_wrapped(value, path, found);
+                }
+            }
+        },
+        SuiMoveValue::Vector(values) => {
+            for (i, value) in values.iter().enumerate() {
+                collect_wrapped(value, format!("{path}[{i}]"), found);
+            }
+        }
+        SuiMoveValue::Option(value) => {
+            if let Some(value) = value.as_ref() {
+                collect_wrapped(value, path, found);
+            }
+        }
+        _ => {}
+    }
+}
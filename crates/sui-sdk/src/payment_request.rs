@@ -0,0 +1,281 @@
+// Copyright (c) 2022, Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use anyhow::anyhow;
+use qrcode::render::unicode::Dense1x2;
+use qrcode::QrCode;
+use serde::{Deserialize, Serialize};
+use url::Url;
+
+use sui_json_rpc_types::SuiExecutionStatus;
+use sui_types::base_types::{ObjectID, SuiAddress, TransactionDigest};
+use sui_types::coin::Coin;
+use sui_types::gas_coin::{GasCoin, GAS};
+use sui_types::messages::TransactionData;
+use sui_types::parse_sui_struct_tag;
+
+use crate::amount::Amount;
+use crate::coin_type::coin_types_equal;
+use crate::deposit_tracker::{ConfirmationPolicy, DepositTracker};
+use crate::error::{Error, SuiRpcResult};
+use crate::follower::FollowedTransaction;
+use crate::transaction_builder::TransactionBuilder;
+use crate::ReadApi;
+
+pub const PAYMENT_URI_SCHEME: &str = "sui";
+
+/// A request for a payment of `amount` coins of `coin_type` to `recipient`, encoded as
+/// `sui:<recipient>?coin=<coin type>&amount=<amount>[&memo=<memo>][&expiry=<expiry ms>]`.
+///
+/// The memo is not recorded on chain; it is for the payer's reference only.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PaymentRequest {
+    pub recipient: SuiAddress,
+    /// The coin type, e.g. `0x2::sui::SUI`.
+    pub coin_type: String,
+    /// The amount in base units of the coin.
+    pub amount: u64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub memo: Option<String>,
+    /// Milliseconds since the Unix epoch after which the request can no longer be paid.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub expiry_ms: Option<u64>,
+}
+
+/// The outcome of checking a transaction against a [PaymentRequest].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PaymentVerification {
+    /// The transaction paid at least the requested amount to the recipient.
+    Satisfied { received: u64 },
+    /// The transaction paid the recipient less than the requested amount.
+    Underpaid { received: u64 },
+    /// The transaction was executed after the request expired.
+    Expired { timestamp_ms: u64 },
+    /// The transaction failed.
+    Failed(String),
+}
+
+impl PaymentVerification {
+    pub fn is_satisfied(&self) -> bool {
+        matches!(self, Self::Satisfied { .. })
+    }
+}
+
+impl PaymentRequest {
+    pub fn new(recipient: SuiAddress, coin_type: &str, amount: u64) -> SuiRpcResult<Self> {
+        Ok(Self {
+            recipient,
+            coin_type: parse_sui_struct_tag(coin_type)?.to_string(),
+            amount,
+            memo: None,
+            expiry_ms: None,
+        })
+    }
+
+    /// A request for `amount` MIST.
+    pub fn sui(recipient: SuiAddress, amount: u64) -> Self {
+        Self {
+            recipient,
+            coin_type: GAS::type_().to_string(),
+            amount,
+            memo: None,
+            expiry_ms: None,
+        }
+    }
+
+    pub fn with_memo(mut self, memo: impl Into<String>) -> Self {
+        self.memo = Some(memo.into());
+        self
+    }
+
+    pub fn with_expiry_ms(mut self, expiry_ms: u64) -> Self {
+        self.expiry_ms = Some(expiry_ms);
+        self
+    }
+
+    pub fn is_expired_at(&self, timestamp_ms: u64) -> bool {
+        self.expiry_ms.map_or(false, |expiry| timestamp_ms > expiry)
+    }
+
+    pub fn is_expired(&self) -> bool {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |d| d.as_millis() as u64);
+        self.is_expired_at(now)
+    }
+
+    pub fn to_uri(&self) -> String {
+        let mut uri = Url::parse(&format!("{PAYMENT_URI_SCHEME}:{}", self.recipient))
+            .expect("A Sui address is a valid URI path");
+        {
+            let mut query = uri.query_pairs_mut();
+            query
+                .append_pair("coin", &self.coin_type)
+                .append_pair("amount", &self.amount.to_string());
+            if let Some(memo) = &self.memo {
+                query.append_pair("memo", memo);
+            }
+            if let Some(expiry_ms) = self.expiry_ms {
+                query.append_pair("expiry", &expiry_ms.to_string());
+            }
+        }
+        uri.to_string()
+    }
+
+    pub fn from_uri(uri: &str) -> SuiRpcResult<Self> {
+        let uri = Url::parse(uri)?;
+        if uri.scheme() != PAYMENT_URI_SCHEME {
+            return Err(Error::Other(anyhow!(
+                "Invalid payment request scheme [{}].",
+                uri.scheme()
+            )));
+        }
+        let mut request = Self::sui(SuiAddress::from_str(uri.path())?, 0);
+        let mut amount = None;
+        for (key, value) in uri.query_pairs() {
+            match key.as_ref() {
+                "coin" => request.coin_type = parse_sui_struct_tag(&value)?.to_string(),
+                "amount" => amount = Some(value.parse()?),
+                "memo" => request.memo = Some(value.into_owned()),
+                "expiry" => request.expiry_ms = Some(value.parse()?),
+                _ => {
+                    return Err(Error::Other(anyhow!(
+                        "Unknown payment request parameter [{key}]."
+                    )))
+                }
+            }
+        }
+        request.amount = amount.ok_or_else(|| anyhow!("Payment request has no amount."))?;
+        Ok(request)
+    }
+
+    /// Render the request URI as a QR code made of unicode block characters, for display in
+    /// a terminal.
+    pub fn to_qr_code(&self) -> SuiRpcResult<String> {
+        let code = QrCode::new(self.to_uri())?;
+        Ok(code.render::<Dense1x2>().quiet_zone(true).build())
+    }
+
+    fn coin_object_type(&self) -> anyhow::Result<String> {
+        Ok(Coin::type_(parse_sui_struct_tag(&self.coin_type)?).to_string())
+    }
+}
+
+impl Display for PaymentRequest {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.to_uri())
+    }
+}
+
+impl FromStr for PaymentRequest {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::from_uri(s)
+    }
+}
+
+impl TransactionBuilder {
+    /// Build a transaction paying `request` from the coins of `signer`, using the largest
+    /// coins first. If no gas coin is given and the request is for SUI, the largest SUI coin
+    /// covering `gas_budget` is kept aside for gas.
+    pub async fn pay_request(
+        &self,
+        signer: SuiAddress,
+        request: &PaymentRequest,
+        gas: Option<ObjectID>,
+        gas_budget: u64,
+    ) -> SuiRpcResult<TransactionData> {
+        if request.is_expired() {
+            return Err(Error::Other(anyhow!("Payment request has expired.")));
+        }
+        let coin_type = request.coin_object_type()?;
+        let mut coins = self
+            .get_coins(signer)
+            .await?
+            .remove(&coin_type)
+            .unwrap_or_default();
+        coins.sort_by_key(|(_, balance)| std::cmp::Reverse(*balance));
+
+        let mut gas = gas;
+        if coin_type == GasCoin::type_().to_string() && gas.is_none() {
+            if let Some(index) = coins.iter().position(|(_, balance)| *balance >= gas_budget) {
+                gas = Some(coins.remove(index).0 .0);
+            }
+        }
+        if let Some(gas) = gas {
+            coins.retain(|(coin, _)| coin.0 != gas);
+        }
+
+        let mut input_coins = vec![];
+        let mut total = 0;
+        for (coin, balance) in coins {
+            if total >= request.amount {
+                break;
+            }
+            input_coins.push(coin.0);
+            total += balance;
+        }
+        if total < request.amount {
+            return Err(Error::Other(anyhow!(
+                "Address [{signer}] holds [{total}] of [{}], [{}] is required.",
+                request.coin_type,
+                request.amount
+            )));
+        }
+        self.pay(
+            signer,
+            input_coins,
+            vec![request.recipient],
+            vec![Amount::from_base_units(request.amount, 0)],
+            gas,
+            gas_budget,
+        )
+        .await
+    }
+}
+
+impl ReadApi {
+    /// Check whether the transaction `digest` pays `request`. Only coins received by the
+    /// recipient from another address are counted.
+    pub async fn verify_payment(
+        &self,
+        request: &PaymentRequest,
+        digest: TransactionDigest,
+    ) -> SuiRpcResult<PaymentVerification> {
+        let response = self.get_transaction(digest).await?;
+        if let SuiExecutionStatus::Failure { error } = &response.effects.status {
+            return Ok(PaymentVerification::Failed(error.clone()));
+        }
+        if let Some(timestamp_ms) = response.timestamp_ms {
+            if request.is_expired_at(timestamp_ms) {
+                return Ok(PaymentVerification::Expired { timestamp_ms });
+            }
+        }
+
+        let mut tracker =
+            DepositTracker::new(self, [request.recipient], ConfirmationPolicy::Immediate);
+        let deposits = tracker
+            .detect(FollowedTransaction {
+                seq: 0,
+                digest,
+                response,
+            })
+            .await?;
+        let received = deposits
+            .iter()
+            .filter(|deposit| coin_types_equal(&deposit.coin_type, &request.coin_type))
+            .map(|deposit| deposit.amount)
+            .sum();
+        Ok(if received >= request.amount {
+            PaymentVerification::Satisfied { received }
+        } else {
+            PaymentVerification::Underpaid { received }
+        })
+    }
+}
@@ -0,0 +1,109 @@
+// Copyright (c) 2022, Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+use std::collections::BTreeMap;
+
+use futures::future::{join_all, try_join_all};
+use move_core_types::language_storage::TypeTag;
+
+use sui_types::base_types::SuiAddress;
+use sui_types::coin::Coin;
+use sui_types::parse_sui_struct_tag;
+
+use crate::amount::Amount;
+use crate::error::SuiRpcResult;
+use crate::local_store::fetch;
+use crate::SuiClient;
+
+/// Holdings of a single coin type across all addresses of a [Portfolio].
+#[derive(Debug, Clone)]
+pub struct CoinHolding {
+    /// The coin type, e.g. `0x2::sui::SUI`.
+    pub coin_type: String,
+    /// The name of the coin type's struct, e.g. `SUI`.
+    pub symbol: String,
+    /// Number of decimals, if the currency metadata could be found.
+    pub decimals: Option<u8>,
+    /// The sum of all balances, saturating at `u64::MAX` like the balances by address.
+    pub total: u64,
+    pub coin_count: usize,
+    pub balances: BTreeMap<SuiAddress, u64>,
+}
+
+impl CoinHolding {
+    /// The total as an [Amount], if the decimals are known.
+    pub fn total_amount(&self) -> Option<Amount> {
+        self.decimals
+            .map(|decimals| Amount::from_base_units(self.total, decimals))
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Portfolio {
+    /// Holdings keyed by coin type.
+    pub holdings: BTreeMap<String, CoinHolding>,
+}
+
+impl SuiClient {
+    /// Aggregate the coin balances of `addresses`, fetching all addresses and coins
+    /// concurrently.
+    pub async fn get_portfolio(&self, addresses: &[SuiAddress]) -> SuiRpcResult<Portfolio> {
+        let read_api = self.read_api();
+        let owned = try_join_all(
+            addresses
+                .iter()
+                .map(|address| read_api.get_objects_owned_by_address(*address)),
+        )
+        .await?;
+
+        let coin_ids = owned
+            .into_iter()
+            .flatten()
+            .filter(|info| parse_sui_struct_tag(&info.type_).map_or(false, |t| Coin::is_coin(&t)))
+            .map(|info| info.object_id)
+            .collect::<Vec<_>>();
+        let coins = read_api
+            .fetch_all(coin_ids, |id| fetch(read_api, id))
+            .await?;
+
+        let mut portfolio = Portfolio::default();
+        for coin in coins.into_iter().flatten() {
+            let (owner, balance) = match (coin.owner(), coin.balance) {
+                (Some(owner), Some(balance)) => (owner, balance),
+                _ => continue,
+            };
+            let coin_type = match parse_sui_struct_tag(&coin.info.type_)?.type_params.first() {
+                Some(TypeTag::Struct(tag)) => tag.clone(),
+                _ => continue,
+            };
+            let holding = portfolio
+                .holdings
+                .entry(coin_type.to_string())
+                .or_insert_with(|| CoinHolding {
+                    coin_type: coin_type.to_string(),
+                    symbol: coin_type.name.to_string(),
+                    decimals: None,
+                    total: 0,
+                    coin_count: 0,
+                    balances: BTreeMap::new(),
+                });
+            holding.total = holding.total.saturating_add(balance);
+            holding.coin_count += 1;
+            let owner_balance = holding.balances.entry(owner).or_default();
+            *owner_balance = owner_balance.saturating_add(balance);
+        }
+
+        let registry = self.coin_registry();
+        let decimals = join_all(
+            portfolio
+                .holdings
+                .keys()
+                .map(|coin_type| registry.get_coin_metadata(coin_type)),
+        )
+        .await;
+        for (holding, metadata) in portfolio.holdings.values_mut().zip(decimals) {
+            holding.decimals = metadata.ok().map(|metadata| metadata.decimals);
+        }
+        Ok(portfolio)
+    }
+}
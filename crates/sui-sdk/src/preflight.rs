@@ -0,0 +1,146 @@
+// Copyright (c) 2022, Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Preflight checks of the size of transactions against the limits of the network, so that a
+//! transaction over a limit fails before it is signed and submitted, with the limit it is
+//! over, instead of being rejected by the node.
+//!
+//! The nodes of this protocol version do not publish their limits, so [TransactionLimits]
+//! default to the limits they enforce: the gas budget bounds of the cost table, the batch
+//! rules of [TransactionKind::validity_check], and the size of a request accepted by the
+//! JSON-RPC server, the transaction bytes being sent Base64 encoded. Set the limits of other
+//! networks with the `with_*` methods.
+//!
+//! [TransactionKind::validity_check]: sui_types::messages::TransactionKind::validity_check
+
+use thiserror::Error;
+
+use sui_types::crypto::SignableBytes;
+use sui_types::gas::{MAX_GAS_BUDGET, MIN_GAS_BUDGET};
+use sui_types::messages::TransactionData;
+
+/// Size of the largest request body accepted by the JSON-RPC server.
+const MAX_REQUEST_BODY_SIZE: usize = 10 * 1024 * 1024;
+
+#[derive(Debug, Error)]
+pub enum PreflightError {
+    #[error("Transaction is {actual} bytes, over the limit of {limit} bytes.")]
+    TooLarge { actual: usize, limit: usize },
+    #[error("Transaction has {actual} commands, over the limit of {limit}.")]
+    TooManyCommands { actual: usize, limit: usize },
+    #[error("Transaction has {actual} input objects, over the limit of {limit}.")]
+    TooManyInputObjects { actual: usize, limit: usize },
+    #[error("Gas budget {actual} is outside of the range [{min}, {max}].")]
+    GasBudgetOutOfRange { actual: u64, min: u64, max: u64 },
+    #[error("Invalid transaction: {0}")]
+    InvalidTransaction(String),
+}
+
+/// The size of a transaction, as counted by the limits.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TransactionStats {
+    /// Size of the signable bytes of the transaction data.
+    pub tx_bytes: usize,
+    /// Number of single transactions in the batch, 1 if not a batch.
+    pub commands: usize,
+    /// Number of input objects, including the gas object.
+    pub input_objects: usize,
+}
+
+impl TransactionStats {
+    pub fn of(data: &TransactionData) -> Result<Self, PreflightError> {
+        let input_objects = data
+            .input_objects()
+            .map_err(|e| PreflightError::InvalidTransaction(e.to_string()))?;
+        Ok(Self {
+            tx_bytes: data.to_bytes().len(),
+            commands: data.kind.batch_size(),
+            input_objects: input_objects.len(),
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TransactionLimits {
+    max_tx_bytes: Option<usize>,
+    max_commands: Option<usize>,
+    max_input_objects: Option<usize>,
+    min_gas_budget: u64,
+    max_gas_budget: u64,
+}
+
+impl Default for TransactionLimits {
+    fn default() -> Self {
+        Self {
+            // Base64 encodes 3 bytes as 4 characters, leaving room for the other parameters.
+            max_tx_bytes: Some(MAX_REQUEST_BODY_SIZE / 4 * 3 - 64 * 1024),
+            max_commands: None,
+            max_input_objects: None,
+            min_gas_budget: *MIN_GAS_BUDGET,
+            max_gas_budget: *MAX_GAS_BUDGET,
+        }
+    }
+}
+
+impl TransactionLimits {
+    /// Largest signable bytes of a transaction, or `None` for no limit.
+    pub fn with_max_tx_bytes(mut self, max: Option<usize>) -> Self {
+        self.max_tx_bytes = max;
+        self
+    }
+
+    /// Largest batch of a transaction, or `None` for no limit. Unlimited by default.
+    pub fn with_max_commands(mut self, max: Option<usize>) -> Self {
+        self.max_commands = max;
+        self
+    }
+
+    /// Most input objects of a transaction, or `None` for no limit. Unlimited by default.
+    pub fn with_max_input_objects(mut self, max: Option<usize>) -> Self {
+        self.max_input_objects = max;
+        self
+    }
+
+    pub fn with_gas_budget_range(mut self, min: u64, max: u64) -> Self {
+        self.min_gas_budget = min;
+        self.max_gas_budget = max;
+        self
+    }
+
+    /// Check `data` against the limits, returning its stats if within them.
+    pub fn check(&self, data: &TransactionData) -> Result<TransactionStats, PreflightError> {
+        data.kind
+            .validity_check()
+            .map_err(|e| PreflightError::InvalidTransaction(e.to_string()))?;
+        let stats = TransactionStats::of(data)?;
+        if let Some(limit) = self.max_tx_bytes.filter(|limit| stats.tx_bytes > *limit) {
+            return Err(PreflightError::TooLarge {
+                actual: stats.tx_bytes,
+                limit,
+            });
+        }
+        if let Some(limit) = self.max_commands.filter(|limit| stats.commands > *limit) {
+            return Err(PreflightError::TooManyCommands {
+                actual: stats.commands,
+                limit,
+            });
+        }
+        if let Some(limit) = self
+            .max_input_objects
+            .filter(|limit| stats.input_objects > *limit)
+        {
+            return Err(PreflightError::TooManyInputObjects {
+                actual: stats.input_objects,
+                limit,
+            });
+        }
+        if !(self.min_gas_budget..=self.max_gas_budget).contains(&data.gas_budget) {
+            return Err(PreflightError::GasBudgetOutOfRange {
+                actual: data.gas_budget,
+                min: self.min_gas_budget,
+                max: self.max_gas_budget,
+            });
+        }
+        Ok(stats)
+    }
+}
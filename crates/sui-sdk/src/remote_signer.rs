@@ -0,0 +1,511 @@
+// Copyright (c) 2022, Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Pair a dApp backend with a user's wallet over an untrusted relay, and ask the wallet to
+//! sign transactions.
+//!
+//! 1. The dApp creates a [PairingProposal] and shows its URI to the user, e.g. as a QR code.
+//! 2. The wallet reads the URI with [WalletPairing::from_uri] and answers on the pairing
+//!    topic with its own X25519 public key and the accounts it exposes.
+//! 3. Both sides derive a ChaCha20-Poly1305 key from the X25519 shared secret with
+//!    HKDF-SHA256. Every later message is encrypted with it and sent on a session topic the
+//!    relay cannot link to the pairing.
+//! 4. The session expires at the time chosen by the wallet; an expired [Session] neither
+//!    sends nor accepts messages.
+
+use std::collections::{BTreeMap, VecDeque};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use anyhow::{anyhow, bail};
+use async_trait::async_trait;
+use chacha20poly1305::aead::{Aead, KeyInit};
+use chacha20poly1305::{ChaCha20Poly1305, Key, Nonce};
+use hkdf::Hkdf;
+use rand::rngs::OsRng;
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use tokio::sync::Notify;
+use url::Url;
+use x25519_dalek::{PublicKey, StaticSecret};
+
+use sui_types::base_types::SuiAddress;
+use sui_types::crypto::{Signature, SuiSignature};
+use sui_types::messages::{Transaction, TransactionData};
+
+use crate::crypto::SuiKeystore;
+use crate::error::{Error, SuiRpcResult};
+use crate::runtime::{timeout, Runtime, TokioRuntime};
+
+pub const PAIRING_URI_SCHEME: &str = "sui-pair";
+const KEY_DERIVATION_INFO: &[u8] = b"sui-remote-signer";
+const NONCE_LENGTH: usize = 12;
+
+/// A message relay shared by the dApp and the wallet. The relay only sees topics and
+/// encrypted payloads.
+#[async_trait]
+pub trait Relay: Send + Sync {
+    async fn publish(&self, topic: &str, message: Vec<u8>) -> SuiRpcResult<()>;
+
+    /// Wait for the next message published on `topic`.
+    async fn next_message(&self, topic: &str) -> SuiRpcResult<Vec<u8>>;
+
+    /// The runtime timing out the waits for messages. Defaults to [TokioRuntime].
+    fn runtime(&self) -> &dyn Runtime {
+        &TokioRuntime
+    }
+}
+
+/// A relay for a dApp and a wallet running in the same process, mainly for tests.
+#[derive(Default)]
+pub struct InMemoryRelay {
+    topics: Mutex<BTreeMap<String, VecDeque<Vec<u8>>>>,
+    notify: Notify,
+}
+
+#[async_trait]
+impl Relay for InMemoryRelay {
+    async fn publish(&self, topic: &str, message: Vec<u8>) -> SuiRpcResult<()> {
+        self.topics
+            .lock()
+            .unwrap()
+            .entry(topic.to_string())
+            .or_default()
+            .push_back(message);
+        self.notify.notify_waiters();
+        Ok(())
+    }
+
+    async fn next_message(&self, topic: &str) -> SuiRpcResult<Vec<u8>> {
+        loop {
+            let notified = self.notify.notified();
+            if let Some(message) = self
+                .topics
+                .lock()
+                .unwrap()
+                .get_mut(topic)
+                .and_then(|messages| messages.pop_front())
+            {
+                return Ok(message);
+            }
+            notified.await;
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct PairingResponse {
+    public_key: [u8; 32],
+    sealed: Vec<u8>,
+}
+
+#[derive(Serialize, Deserialize)]
+enum SessionMessage {
+    Approve {
+        accounts: Vec<SuiAddress>,
+        expiry_ms: u64,
+    },
+    Reject {
+        reason: String,
+    },
+    SignRequest(SignRequest),
+    SignResponse {
+        id: u64,
+        signature: Signature,
+    },
+    SignRejected {
+        id: u64,
+        reason: String,
+    },
+    Disconnect,
+}
+
+/// A transaction the dApp asks the wallet to sign.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SignRequest {
+    pub id: u64,
+    pub data: TransactionData,
+}
+
+/// Created by the dApp to start pairing with a wallet.
+pub struct PairingProposal {
+    topic: String,
+    secret: StaticSecret,
+    expiry_ms: u64,
+}
+
+impl PairingProposal {
+    /// Create a proposal the wallet must answer within `ttl`.
+    pub fn new(ttl: Duration) -> Self {
+        let mut topic = [0u8; 32];
+        OsRng.fill_bytes(&mut topic);
+        Self {
+            topic: hex::encode(topic),
+            secret: StaticSecret::random_from_rng(OsRng),
+            expiry_ms: now_ms() + ttl.as_millis() as u64,
+        }
+    }
+
+    /// The URI to hand to the wallet:
+    /// `sui-pair:<topic>?key=<hex X25519 public key>&expiry=<expiry ms>`.
+    pub fn uri(&self) -> String {
+        let mut uri = Url::parse(&format!("{PAIRING_URI_SCHEME}:{}", self.topic))
+            .expect("A hex topic is a valid URI path");
+        uri.query_pairs_mut()
+            .append_pair(
+                "key",
+                &hex::encode(PublicKey::from(&self.secret).as_bytes()),
+            )
+            .append_pair("expiry", &self.expiry_ms.to_string());
+        uri.to_string()
+    }
+
+    /// Wait for the wallet to answer the proposal, and open the session it approved.
+    pub async fn await_session(self, relay: &dyn Relay) -> SuiRpcResult<Session> {
+        let ttl = Duration::from_millis(self.expiry_ms.saturating_sub(now_ms()));
+        let response = timeout(relay.runtime(), ttl, relay.next_message(&self.topic))
+            .await
+            .ok_or_else(|| anyhow!("Pairing proposal expired before the wallet answered."))??;
+        let response: PairingResponse = bcs::from_bytes(&response)?;
+
+        let shared = self
+            .secret
+            .diffie_hellman(&PublicKey::from(response.public_key));
+        let key = derive_key(&self.topic, shared.as_bytes())?;
+        match open(&key, &response.sealed)? {
+            SessionMessage::Approve {
+                accounts,
+                expiry_ms,
+            } => Ok(Session::new(Role::Dapp, key, accounts, expiry_ms)),
+            SessionMessage::Reject { reason } => Err(Error::Other(anyhow!(
+                "Wallet rejected the pairing: {reason}"
+            ))),
+            _ => Err(Error::Other(anyhow!(
+                "Unexpected message in pairing response."
+            ))),
+        }
+    }
+}
+
+/// A pairing proposal received by the wallet.
+pub struct WalletPairing {
+    topic: String,
+    dapp_key: PublicKey,
+    expiry_ms: u64,
+}
+
+impl WalletPairing {
+    pub fn from_uri(uri: &str) -> SuiRpcResult<Self> {
+        let uri = Url::parse(uri)?;
+        if uri.scheme() != PAIRING_URI_SCHEME {
+            return Err(Error::Other(anyhow!(
+                "Invalid pairing URI scheme [{}].",
+                uri.scheme()
+            )));
+        }
+        let mut dapp_key = None;
+        let mut expiry_ms = None;
+        for (key, value) in uri.query_pairs() {
+            match key.as_ref() {
+                "key" => {
+                    let bytes: [u8; 32] = hex::decode(value.as_ref())?
+                        .try_into()
+                        .map_err(|_| anyhow!("Pairing key must be 32 bytes."))?;
+                    dapp_key = Some(PublicKey::from(bytes))
+                }
+                "expiry" => expiry_ms = Some(value.parse()?),
+                _ => {
+                    return Err(Error::Other(anyhow!(
+                        "Unknown pairing URI parameter [{key}]."
+                    )))
+                }
+            }
+        }
+        Ok(Self {
+            topic: uri.path().to_string(),
+            dapp_key: dapp_key.ok_or_else(|| anyhow!("Pairing URI has no key."))?,
+            expiry_ms: expiry_ms.ok_or_else(|| anyhow!("Pairing URI has no expiry."))?,
+        })
+    }
+
+    /// Expose `accounts` to the dApp for `ttl`.
+    pub async fn approve(
+        self,
+        relay: &dyn Relay,
+        accounts: Vec<SuiAddress>,
+        ttl: Duration,
+    ) -> SuiRpcResult<Session> {
+        let expiry_ms = now_ms() + ttl.as_millis() as u64;
+        let key = self
+            .respond(
+                relay,
+                SessionMessage::Approve {
+                    accounts: accounts.clone(),
+                    expiry_ms,
+                },
+            )
+            .await?;
+        Ok(Session::new(Role::Wallet, key, accounts, expiry_ms))
+    }
+
+    pub async fn reject(self, relay: &dyn Relay, reason: impl Into<String>) -> SuiRpcResult<()> {
+        let reason = reason.into();
+        self.respond(relay, SessionMessage::Reject { reason })
+            .await?;
+        Ok(())
+    }
+
+    async fn respond(
+        &self,
+        relay: &dyn Relay,
+        message: SessionMessage,
+    ) -> anyhow::Result<[u8; 32]> {
+        if now_ms() > self.expiry_ms {
+            bail!("Pairing proposal has expired.");
+        }
+        let secret = StaticSecret::random_from_rng(OsRng);
+        let key = derive_key(
+            &self.topic,
+            secret.diffie_hellman(&self.dapp_key).as_bytes(),
+        )?;
+        let response = PairingResponse {
+            public_key: PublicKey::from(&secret).to_bytes(),
+            sealed: seal(&key, &message)?,
+        };
+        relay
+            .publish(&self.topic, bcs::to_bytes(&response)?)
+            .await?;
+        Ok(key)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Role {
+    Dapp,
+    Wallet,
+}
+
+/// An encrypted channel between a dApp and a wallet, created by
+/// [PairingProposal::await_session] on the dApp side and [WalletPairing::approve] on the
+/// wallet side.
+pub struct Session {
+    role: Role,
+    key: [u8; 32],
+    topic: String,
+    accounts: Vec<SuiAddress>,
+    expiry_ms: u64,
+    next_id: AtomicU64,
+}
+
+impl Session {
+    fn new(role: Role, key: [u8; 32], accounts: Vec<SuiAddress>, expiry_ms: u64) -> Self {
+        Self {
+            role,
+            key,
+            topic: hex::encode(Sha256::digest(key)),
+            accounts,
+            expiry_ms,
+            next_id: AtomicU64::new(0),
+        }
+    }
+
+    /// The accounts the wallet exposed to the dApp.
+    pub fn accounts(&self) -> &[SuiAddress] {
+        &self.accounts
+    }
+
+    pub fn expiry_ms(&self) -> u64 {
+        self.expiry_ms
+    }
+
+    pub fn is_expired(&self) -> bool {
+        now_ms() > self.expiry_ms
+    }
+
+    /// Ask the wallet to sign `data`, and return the signed transaction once the wallet's
+    /// signature has been verified.
+    pub async fn request_signature(
+        &self,
+        relay: &dyn Relay,
+        data: TransactionData,
+    ) -> SuiRpcResult<Transaction> {
+        self.ensure_role(Role::Dapp)?;
+        let signer = data.signer();
+        if !self.accounts.contains(&signer) {
+            return Err(Error::Other(anyhow!(
+                "Account [{signer}] is not part of the session."
+            )));
+        }
+        let id = self.next_id.fetch_add(1, Ordering::SeqCst);
+        self.send(
+            relay,
+            &SessionMessage::SignRequest(SignRequest {
+                id,
+                data: data.clone(),
+            }),
+        )
+        .await?;
+        loop {
+            match self.receive(relay).await? {
+                SessionMessage::SignResponse {
+                    id: response_id,
+                    signature,
+                } if response_id == id => {
+                    signature.verify(&data, signer)?;
+                    return Ok(Transaction::new(data, signature));
+                }
+                SessionMessage::SignRejected {
+                    id: response_id,
+                    reason,
+                } if response_id == id => {
+                    return Err(Error::Other(anyhow!(
+                        "Wallet rejected the transaction: {reason}"
+                    )))
+                }
+                SessionMessage::Disconnect => {
+                    return Err(Error::Other(anyhow!("Wallet closed the session.")))
+                }
+                // A late answer to an earlier request which timed out.
+                _ => continue,
+            }
+        }
+    }
+
+    /// Wait for the next signing request from the dApp. Returns `None` once the dApp closes
+    /// the session.
+    pub async fn next_request(&self, relay: &dyn Relay) -> SuiRpcResult<Option<SignRequest>> {
+        self.ensure_role(Role::Wallet)?;
+        loop {
+            match self.receive(relay).await? {
+                SessionMessage::SignRequest(request) => {
+                    if !self.accounts.contains(&request.data.signer()) {
+                        self.reject(relay, request.id, "Account is not part of the session.")
+                            .await?;
+                        continue;
+                    }
+                    return Ok(Some(request));
+                }
+                SessionMessage::Disconnect => return Ok(None),
+                _ => continue,
+            }
+        }
+    }
+
+    pub async fn respond(
+        &self,
+        relay: &dyn Relay,
+        id: u64,
+        signature: Signature,
+    ) -> SuiRpcResult<()> {
+        self.ensure_role(Role::Wallet)?;
+        self.send(relay, &SessionMessage::SignResponse { id, signature })
+            .await
+    }
+
+    /// Sign `request` with the key of its sender held in `keystore`, and send the signature
+    /// to the dApp.
+    pub async fn sign_and_respond(
+        &self,
+        relay: &dyn Relay,
+        request: &SignRequest,
+        keystore: &SuiKeystore,
+    ) -> SuiRpcResult<()> {
+        let signature = keystore.sign(&request.data.signer(), &request.data.to_bytes())?;
+        self.respond(relay, request.id, signature).await
+    }
+
+    pub async fn reject(
+        &self,
+        relay: &dyn Relay,
+        id: u64,
+        reason: impl Into<String>,
+    ) -> SuiRpcResult<()> {
+        self.ensure_role(Role::Wallet)?;
+        let reason = reason.into();
+        self.send(relay, &SessionMessage::SignRejected { id, reason })
+            .await
+    }
+
+    pub async fn disconnect(&self, relay: &dyn Relay) -> SuiRpcResult<()> {
+        self.send(relay, &SessionMessage::Disconnect).await
+    }
+
+    fn ensure_role(&self, role: Role) -> anyhow::Result<()> {
+        if self.role != role {
+            bail!(
+                "Operation is not available on the {:?} side of a session.",
+                self.role
+            );
+        }
+        Ok(())
+    }
+
+    fn outgoing_topic(&self, role: Role) -> String {
+        match role {
+            Role::Dapp => format!("{}/dapp", self.topic),
+            Role::Wallet => format!("{}/wallet", self.topic),
+        }
+    }
+
+    async fn send(&self, relay: &dyn Relay, message: &SessionMessage) -> SuiRpcResult<()> {
+        if self.is_expired() {
+            return Err(Error::Other(anyhow!("Session has expired.")));
+        }
+        relay
+            .publish(&self.outgoing_topic(self.role), seal(&self.key, message)?)
+            .await
+    }
+
+    async fn receive(&self, relay: &dyn Relay) -> anyhow::Result<SessionMessage> {
+        let peer = match self.role {
+            Role::Dapp => Role::Wallet,
+            Role::Wallet => Role::Dapp,
+        };
+        let ttl = Duration::from_millis(self.expiry_ms.saturating_sub(now_ms()));
+        let topic = self.outgoing_topic(peer);
+        let message = timeout(relay.runtime(), ttl, relay.next_message(&topic))
+            .await
+            .ok_or_else(|| anyhow!("Session has expired."))??;
+        open(&self.key, &message)
+    }
+}
+
+fn derive_key(topic: &str, shared_secret: &[u8]) -> anyhow::Result<[u8; 32]> {
+    let mut key = [0u8; 32];
+    Hkdf::<Sha256>::new(Some(topic.as_bytes()), shared_secret)
+        .expand(KEY_DERIVATION_INFO, &mut key)
+        .map_err(|e| anyhow!("Cannot derive session key: {e}"))?;
+    Ok(key)
+}
+
+/// Encrypt `message` as `nonce || ciphertext`.
+fn seal(key: &[u8; 32], message: &SessionMessage) -> anyhow::Result<Vec<u8>> {
+    let mut nonce = [0u8; NONCE_LENGTH];
+    OsRng.fill_bytes(&mut nonce);
+    let ciphertext = ChaCha20Poly1305::new(Key::from_slice(key))
+        .encrypt(
+            Nonce::from_slice(&nonce),
+            bcs::to_bytes(message)?.as_slice(),
+        )
+        .map_err(|_| anyhow!("Cannot encrypt session message."))?;
+    Ok([nonce.as_slice(), &ciphertext].concat())
+}
+
+fn open(key: &[u8; 32], sealed: &[u8]) -> anyhow::Result<SessionMessage> {
+    if sealed.len() < NONCE_LENGTH {
+        bail!("Session message is too short.");
+    }
+    let (nonce, ciphertext) = sealed.split_at(NONCE_LENGTH);
+    let plaintext = ChaCha20Poly1305::new(Key::from_slice(key))
+        .decrypt(Nonce::from_slice(nonce), ciphertext)
+        .map_err(|_| anyhow!("Cannot decrypt session message."))?;
+    Ok(bcs::from_bytes(&plaintext)?)
+}
+
+fn now_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_millis() as u64)
+}
@@ -0,0 +1,164 @@
+// Copyright (c) 2022, Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Structured logs of the requests of a client, for services with their own log pipeline.
+//!
+//! A [RequestLogHook] set with [SuiClientBuilder::log_hook] receives a [RequestLog] for each
+//! JSON-RPC request of the client over HTTP: the method, its parameters with signatures and
+//! keys redacted by a [Redactor], the latency, the size of the result and the error. A
+//! retried request is logged once, with the latency of all its attempts. Hedged reads are
+//! logged by both endpoints, the first endpoint logging the latency of the hedged read.
+//!
+//! [SuiClientBuilder::log_hook]: crate::SuiClientBuilder::log_hook
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use jsonrpsee::core::Error as JsonRpcError;
+use jsonrpsee::types::ParamsSer;
+use serde_json::Value;
+
+/// The value replacing redacted parameters.
+pub const REDACTED: &str = "[redacted]";
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct RequestLog<'a> {
+    pub endpoint: &'a str,
+    pub method: &'a str,
+    /// The parameters, redacted.
+    pub params: Value,
+    pub latency: Duration,
+    /// Size of the JSON result, for successful requests other than batches.
+    pub response_size: Option<usize>,
+    pub error: Option<String>,
+}
+
+pub trait RequestLogHook: Send + Sync {
+    fn on_request(&self, log: &RequestLog<'_>);
+}
+
+impl<F> RequestLogHook for F
+where
+    F: Fn(&RequestLog<'_>) + Send + Sync,
+{
+    fn on_request(&self, log: &RequestLog<'_>) {
+        self(log)
+    }
+}
+
+/// Redacts the parameters named after secrets, at any depth, and the positional parameters
+/// of methods holding them. By default, the signatures and public keys of the transactions
+/// executed or dry run.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Redactor {
+    keys: BTreeSet<String>,
+    positions: BTreeMap<String, BTreeSet<usize>>,
+}
+
+impl Default for Redactor {
+    fn default() -> Self {
+        let redactor = Self::none()
+            .with_position("sui_executeTransaction", 2)
+            .with_position("sui_executeTransaction", 3)
+            .with_position("sui_dryRunTransaction", 2)
+            .with_position("sui_dryRunTransaction", 3);
+        [
+            "signature",
+            "pub_key",
+            "public_key",
+            "private_key",
+            "secret_key",
+            "mnemonic",
+            "password",
+        ]
+        .into_iter()
+        .fold(redactor, Redactor::with_key)
+    }
+}
+
+impl Redactor {
+    /// A redactor keeping all parameters.
+    pub fn none() -> Self {
+        Self {
+            keys: BTreeSet::new(),
+            positions: BTreeMap::new(),
+        }
+    }
+
+    /// Redact the fields named `key`, ignoring case.
+    pub fn with_key(mut self, key: &str) -> Self {
+        self.keys.insert(key.to_ascii_lowercase());
+        self
+    }
+
+    /// Redact the parameter at `index` of `method`.
+    pub fn with_position(mut self, method: &str, index: usize) -> Self {
+        self.positions
+            .entry(method.to_string())
+            .or_default()
+            .insert(index);
+        self
+    }
+
+    /// The parameters `params` of `method`, redacted.
+    pub fn redact(&self, method: &str, mut params: Value) -> Value {
+        if let (Value::Array(values), Some(positions)) = (&mut params, self.positions.get(method)) {
+            for index in positions {
+                if let Some(value) = values.get_mut(*index) {
+                    *value = REDACTED.into();
+                }
+            }
+        }
+        self.redact_keys(&mut params);
+        params
+    }
+
+    fn redact_keys(&self, value: &mut Value) {
+        match value {
+            Value::Array(values) => values.iter_mut().for_each(|v| self.redact_keys(v)),
+            Value::Object(fields) => {
+                for (key, value) in fields.iter_mut() {
+                    if self.keys.contains(&key.to_ascii_lowercase()) {
+                        *value = REDACTED.into();
+                    } else {
+                        self.redact_keys(value);
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Sends the logs of the requests to an endpoint to a hook.
+pub(crate) struct RequestLogger {
+    hook: Arc<dyn RequestLogHook>,
+    redactor: Redactor,
+}
+
+impl RequestLogger {
+    pub fn new(hook: Arc<dyn RequestLogHook>, redactor: Redactor) -> Self {
+        Self { hook, redactor }
+    }
+
+    pub fn log(
+        &self,
+        endpoint: &str,
+        method: &str,
+        params: &Option<ParamsSer<'_>>,
+        start: Instant,
+        response_size: Option<usize>,
+        error: Option<&JsonRpcError>,
+    ) {
+        let params = serde_json::to_value(params).unwrap_or(Value::Null);
+        self.hook.on_request(&RequestLog {
+            endpoint,
+            method,
+            params: self.redactor.redact(method, params),
+            latency: start.elapsed(),
+            response_size,
+            error: error.map(ToString::to_string),
+        });
+    }
+}
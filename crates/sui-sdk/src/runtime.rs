@@ -0,0 +1,75 @@
+// Copyright (c) 2022, Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! The async runtime used by the client for timers and background tasks.
+//!
+//! Clients use [TokioRuntime] by default. Applications running on another executor, e.g.
+//! async-std or smol, set their own [Runtime] with [SuiClientBuilder::runtime], together with
+//! a [HttpTransport] built on their HTTP stack, as the default HTTP client and the WebSocket
+//! client need a Tokio reactor.
+//!
+//! [SuiClientBuilder::runtime]: crate::SuiClientBuilder::runtime
+//! [HttpTransport]: crate::transport::HttpTransport
+
+use std::fmt::Debug;
+use std::future::Future;
+use std::time::Duration;
+
+use anyhow::anyhow;
+use futures::channel::oneshot;
+use futures::future::{select, BoxFuture, Either};
+use futures::FutureExt;
+
+pub trait Runtime: Debug + Send + Sync {
+    fn sleep(&self, duration: Duration) -> BoxFuture<'static, ()>;
+
+    /// Run `task` in the background.
+    fn spawn(&self, task: BoxFuture<'static, ()>);
+
+    /// Run the blocking function `f` outside of the threads polling futures.
+    fn spawn_blocking(&self, f: Box<dyn FnOnce() + Send>);
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct TokioRuntime;
+
+impl Runtime for TokioRuntime {
+    fn sleep(&self, duration: Duration) -> BoxFuture<'static, ()> {
+        tokio::time::sleep(duration).boxed()
+    }
+
+    fn spawn(&self, task: BoxFuture<'static, ()>) {
+        tokio::spawn(task);
+    }
+
+    fn spawn_blocking(&self, f: Box<dyn FnOnce() + Send>) {
+        tokio::task::spawn_blocking(f);
+    }
+}
+
+/// Run the blocking function `f` with [Runtime::spawn_blocking] and wait for its result.
+pub async fn spawn_blocking<T, F>(runtime: &dyn Runtime, f: F) -> anyhow::Result<T>
+where
+    T: Send + 'static,
+    F: FnOnce() -> T + Send + 'static,
+{
+    let (sender, receiver) = oneshot::channel();
+    runtime.spawn_blocking(Box::new(move || {
+        let _ = sender.send(f());
+    }));
+    receiver
+        .await
+        .map_err(|_| anyhow!("Blocking task was cancelled or panicked."))
+}
+
+/// The output of `future`, or `None` if it did not complete within `duration`.
+pub async fn timeout<F: Future>(
+    runtime: &dyn Runtime,
+    duration: Duration,
+    future: F,
+) -> Option<F::Output> {
+    match select(Box::pin(future), runtime.sleep(duration)).await {
+        Either::Left((output, _)) => Some(output),
+        Either::Right(_) => None,
+    }
+}
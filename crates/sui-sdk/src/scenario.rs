@@ -0,0 +1,466 @@
+// Copyright (c) 2022, Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Declarative end-to-end test scenarios run against a [LocalNetwork], available with the
+//! `test-support` feature.
+//!
+//! ```ignore
+//! let outcome = Scenario::new()
+//!     .account("alice", 2, 1_000_000)
+//!     .account("bob", 1, 1_000_000)
+//!     .publish("alice", "counter", "path/to/counter")
+//!     .call(
+//!         Call::new("alice", "counter", "counter", "create")
+//!             .label("counter")
+//!             .expect(Expect::success().created(1)),
+//!     )
+//!     .call(
+//!         Call::new("bob", "counter", "counter", "increment")
+//!             .arg(Arg::Created("counter".into(), 0))
+//!             .expect(Expect::success().event("counter::Incremented")),
+//!     )
+//!     .transfer_sui("alice", "bob", 100, Expect::success())
+//!     .run()
+//!     .await?;
+//! ```
+
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+
+use anyhow::{anyhow, bail};
+use move_package::BuildConfig;
+use serde_json::Value;
+
+use sui_config::genesis_config::{AccountConfig, GenesisConfig, ObjectConfig};
+use sui_framework::build_move_package_to_bytes;
+use sui_json::SuiJsonValue;
+use sui_json_rpc_types::{
+    SuiEvent, SuiExecuteTransactionResponse, SuiExecutionStatus, SuiTransactionEffects, SuiTypeTag,
+};
+use sui_types::base_types::{ObjectID, SuiAddress};
+use sui_types::gas_coin::GasCoin;
+use sui_types::messages::{ExecuteTransactionRequestType, Transaction, TransactionData};
+use sui_types::object::Owner;
+
+use crate::amount::Amount;
+use crate::error::{Error, SuiRpcResult};
+use crate::test_network::LocalNetwork;
+use crate::SuiClient;
+
+const DEFAULT_GAS_BUDGET: u64 = 10_000;
+
+/// The expected outcome of a scenario step.
+#[derive(Debug, Clone)]
+pub struct Expect {
+    success: bool,
+    created: Option<usize>,
+    mutated: Option<usize>,
+    deleted: Option<usize>,
+    events: Vec<String>,
+}
+
+impl Expect {
+    pub fn success() -> Self {
+        Self {
+            success: true,
+            created: None,
+            mutated: None,
+            deleted: None,
+            events: vec![],
+        }
+    }
+
+    pub fn failure() -> Self {
+        Self {
+            success: false,
+            ..Self::success()
+        }
+    }
+
+    /// Expect exactly `count` objects to be created.
+    pub fn created(mut self, count: usize) -> Self {
+        self.created = Some(count);
+        self
+    }
+
+    /// Expect exactly `count` objects to be mutated, including the gas coin.
+    pub fn mutated(mut self, count: usize) -> Self {
+        self.mutated = Some(count);
+        self
+    }
+
+    pub fn deleted(mut self, count: usize) -> Self {
+        self.deleted = Some(count);
+        self
+    }
+
+    /// Expect a Move event whose type ends with `type_suffix`, e.g. `counter::Incremented`.
+    pub fn event(mut self, type_suffix: impl Into<String>) -> Self {
+        self.events.push(type_suffix.into());
+        self
+    }
+
+    fn check(&self, step: &str, effects: &SuiTransactionEffects) -> anyhow::Result<()> {
+        match (&effects.status, self.success) {
+            (SuiExecutionStatus::Success, true) | (SuiExecutionStatus::Failure { .. }, false) => {}
+            (SuiExecutionStatus::Failure { error }, true) => {
+                bail!("Step [{step}] failed: {error}")
+            }
+            (SuiExecutionStatus::Success, false) => {
+                bail!("Step [{step}] succeeded, a failure was expected.")
+            }
+        }
+        for (kind, expected, actual) in [
+            ("created", self.created, effects.created.len()),
+            ("mutated", self.mutated, effects.mutated.len()),
+            ("deleted", self.deleted, effects.deleted.len()),
+        ] {
+            if let Some(expected) = expected {
+                if expected != actual {
+                    bail!("Step [{step}] {kind} [{actual}] objects, expected [{expected}].");
+                }
+            }
+        }
+        for expected in &self.events {
+            let found = effects.events.iter().any(|event| {
+                matches!(event, SuiEvent::MoveEvent { type_, .. } if type_.ends_with(expected.as_str()))
+            });
+            if !found {
+                bail!("Step [{step}] did not emit event [{expected}].");
+            }
+        }
+        Ok(())
+    }
+}
+
+/// A Move call argument, resolved when its step runs.
+#[derive(Debug, Clone)]
+pub enum Arg {
+    Value(SuiJsonValue),
+    /// The address of a scenario account.
+    Account(String),
+    /// The object created at `index` by the step with the given label.
+    Created(String, usize),
+    Object(ObjectID),
+}
+
+impl From<SuiJsonValue> for Arg {
+    fn from(value: SuiJsonValue) -> Self {
+        Self::Value(value)
+    }
+}
+
+/// A Move call step.
+#[derive(Clone)]
+pub struct Call {
+    sender: String,
+    package: String,
+    module: String,
+    function: String,
+    type_args: Vec<SuiTypeTag>,
+    args: Vec<Arg>,
+    expect: Expect,
+    label: Option<String>,
+}
+
+impl Call {
+    /// Call `module::function` of the package published under the name `package`.
+    pub fn new(sender: &str, package: &str, module: &str, function: &str) -> Self {
+        Self {
+            sender: sender.to_string(),
+            package: package.to_string(),
+            module: module.to_string(),
+            function: function.to_string(),
+            type_args: vec![],
+            args: vec![],
+            expect: Expect::success(),
+            label: None,
+        }
+    }
+
+    pub fn type_arg(mut self, type_arg: SuiTypeTag) -> Self {
+        self.type_args.push(type_arg);
+        self
+    }
+
+    pub fn arg(mut self, arg: impl Into<Arg>) -> Self {
+        self.args.push(arg.into());
+        self
+    }
+
+    pub fn expect(mut self, expect: Expect) -> Self {
+        self.expect = expect;
+        self
+    }
+
+    /// Name the step so later steps can refer to the objects it created.
+    pub fn label(mut self, label: impl Into<String>) -> Self {
+        self.label = Some(label.into());
+        self
+    }
+}
+
+#[derive(Clone)]
+enum Step {
+    Publish {
+        sender: String,
+        name: String,
+        path: PathBuf,
+    },
+    Call(Call),
+    TransferSui {
+        sender: String,
+        recipient: String,
+        amount: u64,
+        expect: Expect,
+    },
+}
+
+#[derive(Clone, Default)]
+pub struct Scenario {
+    accounts: Vec<(String, usize, u64)>,
+    steps: Vec<Step>,
+    gas_budget: Option<u64>,
+}
+
+/// The state of a scenario after its last step. The network keeps running until the outcome
+/// is dropped, so tests can make further assertions against it.
+pub struct ScenarioOutcome {
+    pub network: LocalNetwork,
+    pub client: SuiClient,
+    pub accounts: BTreeMap<String, SuiAddress>,
+    pub packages: BTreeMap<String, ObjectID>,
+    /// Objects created by labelled steps.
+    pub created: BTreeMap<String, Vec<ObjectID>>,
+    /// Effects of every transaction, in order.
+    pub effects: Vec<SuiTransactionEffects>,
+}
+
+impl Scenario {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Declare an account funded at genesis with `gas_objects` coins of `gas_value` each.
+    pub fn account(mut self, name: &str, gas_objects: usize, gas_value: u64) -> Self {
+        self.accounts
+            .push((name.to_string(), gas_objects, gas_value));
+        self
+    }
+
+    /// Gas budget of every transaction. Defaults to 10000.
+    pub fn gas_budget(mut self, gas_budget: u64) -> Self {
+        self.gas_budget = Some(gas_budget);
+        self
+    }
+
+    /// Build the Move package at `path` and publish it from `sender`, under the name `name`.
+    pub fn publish(mut self, sender: &str, name: &str, path: impl Into<PathBuf>) -> Self {
+        self.steps.push(Step::Publish {
+            sender: sender.to_string(),
+            name: name.to_string(),
+            path: path.into(),
+        });
+        self
+    }
+
+    pub fn call(mut self, call: Call) -> Self {
+        self.steps.push(Step::Call(call));
+        self
+    }
+
+    pub fn transfer_sui(
+        mut self,
+        sender: &str,
+        recipient: &str,
+        amount: u64,
+        expect: Expect,
+    ) -> Self {
+        self.steps.push(Step::TransferSui {
+            sender: sender.to_string(),
+            recipient: recipient.to_string(),
+            amount,
+            expect,
+        });
+        self
+    }
+
+    /// Start a local network with the declared accounts, run every step in order, and check
+    /// its expectations. Fails at the first step that does not meet them.
+    pub async fn run(self) -> SuiRpcResult<ScenarioOutcome> {
+        let mut genesis_config = GenesisConfig::for_local_testing();
+        genesis_config.accounts = self
+            .accounts
+            .iter()
+            .map(|(_, gas_objects, gas_value)| AccountConfig {
+                address: None,
+                gas_objects: (0..*gas_objects)
+                    .map(|_| ObjectConfig {
+                        object_id: ObjectID::random(),
+                        gas_value: *gas_value,
+                    })
+                    .collect(),
+                gas_object_ranges: None,
+            })
+            .collect();
+        let network = LocalNetwork::builder()
+            .genesis_config(genesis_config)
+            .build()
+            .await?;
+        let client = network.client().await?;
+        let accounts = self
+            .accounts
+            .iter()
+            .map(|(name, _, _)| name.clone())
+            .zip(network.accounts().iter().copied())
+            .collect();
+
+        let mut outcome = ScenarioOutcome {
+            network,
+            client,
+            accounts,
+            packages: BTreeMap::new(),
+            created: BTreeMap::new(),
+            effects: vec![],
+        };
+        let gas_budget = self.gas_budget.unwrap_or(DEFAULT_GAS_BUDGET);
+        for (index, step) in self.steps.into_iter().enumerate() {
+            outcome.run_step(index, step, gas_budget).await?;
+        }
+        Ok(outcome)
+    }
+}
+
+impl ScenarioOutcome {
+    pub fn account(&self, name: &str) -> SuiRpcResult<SuiAddress> {
+        self.accounts
+            .get(name)
+            .copied()
+            .ok_or_else(|| Error::Other(anyhow!("Unknown scenario account [{name}].")))
+    }
+
+    async fn run_step(&mut self, index: usize, step: Step, gas_budget: u64) -> anyhow::Result<()> {
+        let builder = self.client.transaction_builder();
+        match step {
+            Step::Publish { sender, name, path } => {
+                let modules = build_move_package_to_bytes(&path, BuildConfig::default())?;
+                let data = builder
+                    .publish(self.account(&sender)?, modules, None, gas_budget)
+                    .await?;
+                let effects = self.execute(data).await?;
+                Expect::success().check(&format!("{index}: publish {name}"), &effects)?;
+                let package = effects
+                    .created
+                    .iter()
+                    .find(|object| object.owner == Owner::Immutable)
+                    .ok_or_else(|| anyhow!("Publishing [{name}] created no package."))?;
+                self.packages.insert(name, package.reference.object_id);
+                self.effects.push(effects);
+            }
+            Step::Call(call) => {
+                let package = *self
+                    .packages
+                    .get(&call.package)
+                    .ok_or_else(|| anyhow!("Unknown scenario package [{}].", call.package))?;
+                let args = call
+                    .args
+                    .iter()
+                    .map(|arg| self.resolve(arg))
+                    .collect::<SuiRpcResult<Vec<_>>>()?;
+                let data = builder
+                    .move_call(
+                        self.account(&call.sender)?,
+                        package,
+                        &call.module,
+                        &call.function,
+                        call.type_args,
+                        args,
+                        None,
+                        gas_budget,
+                    )
+                    .await?;
+                let effects = self.execute(data).await?;
+                let step = call
+                    .label
+                    .clone()
+                    .unwrap_or_else(|| format!("{index}: {}::{}", call.module, call.function));
+                call.expect.check(&step, &effects)?;
+                if let Some(label) = call.label {
+                    let created = effects
+                        .created
+                        .iter()
+                        .map(|object| object.reference.object_id)
+                        .collect();
+                    self.created.insert(label, created);
+                }
+                self.effects.push(effects);
+            }
+            Step::TransferSui {
+                sender,
+                recipient,
+                amount,
+                expect,
+            } => {
+                let sender = self.account(&sender)?;
+                let coin = builder
+                    .get_coins(sender)
+                    .await?
+                    .remove(&GasCoin::type_().to_string())
+                    .and_then(|coins| coins.into_iter().max_by_key(|(_, balance)| *balance))
+                    .ok_or_else(|| anyhow!("Account [{sender}] has no SUI coin."))?;
+                let data = builder
+                    .transfer_sui(
+                        sender,
+                        coin.0 .0,
+                        gas_budget,
+                        self.account(&recipient)?,
+                        Some(Amount::from_mist(amount)),
+                    )
+                    .await?;
+                let effects = self.execute(data).await?;
+                expect.check(&format!("{index}: transfer_sui"), &effects)?;
+                self.effects.push(effects);
+            }
+        }
+        Ok(())
+    }
+
+    fn resolve(&self, arg: &Arg) -> anyhow::Result<SuiJsonValue> {
+        Ok(match arg {
+            Arg::Value(value) => value.clone(),
+            Arg::Account(name) => {
+                SuiJsonValue::new(Value::String(self.account(name)?.to_string()))?
+            }
+            Arg::Created(label, index) => {
+                let id = self
+                    .created
+                    .get(label)
+                    .and_then(|created| created.get(*index))
+                    .ok_or_else(|| anyhow!("Step [{label}] created no object [{index}]."))?;
+                SuiJsonValue::from_object_id(*id)
+            }
+            Arg::Object(id) => SuiJsonValue::from_object_id(*id),
+        })
+    }
+
+    async fn execute(&self, data: TransactionData) -> anyhow::Result<SuiTransactionEffects> {
+        let signature = self
+            .network
+            .keystore()
+            .sign(&data.signer(), &data.to_bytes())?;
+        let response = self
+            .client
+            .quorum_driver()
+            .execute_transaction_by_fullnode(
+                Transaction::new(data, signature),
+                ExecuteTransactionRequestType::WaitForLocalExecution,
+            )
+            .await?;
+        match response {
+            SuiExecuteTransactionResponse::EffectsCert { effects, .. } => Ok(effects.effects),
+            _ => {
+                bail!("Node did not return the transaction effects.")
+            }
+        }
+    }
+}
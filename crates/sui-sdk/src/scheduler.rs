@@ -0,0 +1,224 @@
+// Copyright (c) 2022, Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Execution of a batch of transactions ordered by the objects they use, e.g. for migration
+//! and bulk administration scripts.
+//!
+//! Each transaction declares the objects it uses. A transaction depends on the previous
+//! transactions of the batch using one of its objects: it is built and executed once they
+//! succeeded, so it can read the new versions of their objects. Transactions with no
+//! objects in common run concurrently. A transaction whose dependency failed is skipped.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::future::Future;
+use std::sync::Arc;
+
+use futures::future::{join_all, FutureExt, LocalBoxFuture, Shared};
+use tokio::sync::Semaphore;
+
+use sui_types::base_types::{ObjectID, TransactionDigest};
+use sui_types::messages::Transaction;
+
+use crate::error::SuiRpcResult;
+use crate::SuiClient;
+
+type BuildTransaction<'a> = Box<dyn FnOnce() -> LocalBoxFuture<'a, SuiRpcResult<Transaction>> + 'a>;
+
+/// A transaction of a batch, built once the transactions it depends on are executed.
+pub struct ScheduledTransaction<'a> {
+    pub name: String,
+    pub objects: BTreeSet<ObjectID>,
+    build: BuildTransaction<'a>,
+}
+
+impl<'a> ScheduledTransaction<'a> {
+    /// A transaction using `objects`, signed by `build`.
+    pub fn new<F, Fut>(
+        name: impl Into<String>,
+        objects: impl IntoIterator<Item = ObjectID>,
+        build: F,
+    ) -> Self
+    where
+        F: FnOnce() -> Fut + 'a,
+        Fut: Future<Output = SuiRpcResult<Transaction>> + 'a,
+    {
+        Self {
+            name: name.into(),
+            objects: objects.into_iter().collect(),
+            build: Box::new(move || build().boxed_local()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TaskStatus {
+    Executed {
+        digest: TransactionDigest,
+    },
+    /// Executed, but the execution failed, e.g. aborted in Move.
+    ExecutionFailed {
+        digest: TransactionDigest,
+        error: String,
+    },
+    /// Not executed, as building or submitting it failed.
+    Failed {
+        error: String,
+    },
+    /// Not executed, as the transaction `dependency` did not succeed.
+    Skipped {
+        dependency: String,
+    },
+}
+
+impl TaskStatus {
+    pub fn is_success(&self) -> bool {
+        matches!(self, TaskStatus::Executed { .. })
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct TaskReport {
+    pub name: String,
+    pub status: TaskStatus,
+}
+
+/// The outcome of every transaction of a batch, in the order they were added.
+#[derive(Debug, Clone, Default)]
+pub struct ScheduleReport {
+    pub tasks: Vec<TaskReport>,
+}
+
+impl ScheduleReport {
+    pub fn succeeded(&self) -> usize {
+        self.tasks
+            .iter()
+            .filter(|task| task.status.is_success())
+            .count()
+    }
+
+    pub fn is_success(&self) -> bool {
+        self.succeeded() == self.tasks.len()
+    }
+
+    /// The transactions that did not succeed.
+    pub fn failures(&self) -> impl Iterator<Item = &TaskReport> {
+        self.tasks.iter().filter(|task| !task.status.is_success())
+    }
+}
+
+/// For each transaction using `objects`, the indices of the transactions it depends on: for
+/// each of its objects, the last previous transaction using it.
+pub fn dependencies(objects: &[BTreeSet<ObjectID>]) -> Vec<BTreeSet<usize>> {
+    let mut last_use = BTreeMap::<ObjectID, usize>::new();
+    objects
+        .iter()
+        .enumerate()
+        .map(|(index, objects)| {
+            objects
+                .iter()
+                .filter_map(|object| last_use.insert(*object, index))
+                .collect()
+        })
+        .collect()
+}
+
+pub struct TransactionScheduler<'a> {
+    client: &'a SuiClient,
+    transactions: Vec<ScheduledTransaction<'a>>,
+    max_concurrency: usize,
+}
+
+impl<'a> TransactionScheduler<'a> {
+    pub fn new(client: &'a SuiClient) -> Self {
+        Self {
+            client,
+            transactions: vec![],
+            max_concurrency: 16,
+        }
+    }
+
+    /// Maximum number of transactions built or executed at the same time. Defaults to 16.
+    pub fn with_max_concurrency(mut self, max_concurrency: usize) -> Self {
+        self.max_concurrency = max_concurrency.max(1);
+        self
+    }
+
+    pub fn add(&mut self, transaction: ScheduledTransaction<'a>) {
+        self.transactions.push(transaction);
+    }
+
+    /// Execute every transaction once its dependencies succeeded.
+    pub async fn run(self) -> ScheduleReport {
+        let objects = self
+            .transactions
+            .iter()
+            .map(|transaction| transaction.objects.clone())
+            .collect::<Vec<_>>();
+        let names = self
+            .transactions
+            .iter()
+            .map(|transaction| transaction.name.clone())
+            .collect::<Vec<_>>();
+        let semaphore = Arc::new(Semaphore::new(self.max_concurrency));
+
+        let mut tasks: Vec<Shared<LocalBoxFuture<'a, TaskStatus>>> = vec![];
+        for (transaction, dependencies) in self.transactions.into_iter().zip(dependencies(&objects))
+        {
+            let dependencies = dependencies
+                .into_iter()
+                .map(|index| (names[index].clone(), tasks[index].clone()))
+                .collect::<Vec<_>>();
+            let client = self.client;
+            let semaphore = semaphore.clone();
+            let task = async move {
+                for (dependency, task) in dependencies {
+                    if !task.await.is_success() {
+                        return TaskStatus::Skipped { dependency };
+                    }
+                }
+                let _permit = semaphore
+                    .acquire()
+                    .await
+                    .expect("The semaphore is never closed");
+                execute(client, transaction.build).await
+            };
+            tasks.push(task.boxed_local().shared());
+        }
+
+        let statuses = join_all(tasks).await;
+        ScheduleReport {
+            tasks: names
+                .into_iter()
+                .zip(statuses)
+                .map(|(name, status)| TaskReport { name, status })
+                .collect(),
+        }
+    }
+}
+
+async fn execute(client: &SuiClient, build: BuildTransaction<'_>) -> TaskStatus {
+    let transaction = match build().await {
+        Ok(transaction) => transaction,
+        Err(e) => {
+            return TaskStatus::Failed {
+                error: e.to_string(),
+            }
+        }
+    };
+    match client
+        .quorum_driver()
+        .execute_transaction(transaction)
+        .await
+    {
+        Ok(response) if response.effects.status.is_ok() => TaskStatus::Executed {
+            digest: response.certificate.transaction_digest,
+        },
+        Ok(response) => TaskStatus::ExecutionFailed {
+            digest: response.certificate.transaction_digest,
+            error: format!("{:?}", response.effects.status),
+        },
+        Err(e) => TaskStatus::Failed {
+            error: e.to_string(),
+        },
+    }
+}
@@ -0,0 +1,158 @@
+// Copyright (c) 2022, Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! A policy checked on the decoded transaction data before it is signed, as a last line of
+//! defense for hot wallets: a compromised or buggy caller cannot get the wallet to call
+//! denied Move functions or to send objects to addresses outside its address book.
+//!
+//! Recipients are checked for transfers and payments. Addresses passed as arguments of Move
+//! calls cannot be told apart from other values, so restrict the callable packages with
+//! [SigningPolicy::allow_only_packages] to the packages whose transfers are trusted.
+
+use std::collections::BTreeSet;
+
+use serde::{Deserialize, Serialize};
+
+use sui_types::base_types::{ObjectID, SuiAddress};
+use sui_types::crypto::Signature;
+use sui_types::messages::{SingleTransactionKind, TransactionData};
+
+use crate::crypto::SuiKeystore;
+use crate::error::{Error, SuiRpcResult};
+
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum PolicyViolation {
+    #[error("Calls to package [{package}] are denied.")]
+    DeniedPackage { package: ObjectID },
+    #[error("Calls to [{package}::{module}::{function}] are denied.")]
+    DeniedFunction {
+        package: ObjectID,
+        module: String,
+        function: String,
+    },
+    #[error("Calls to package [{package}] are not allowed.")]
+    PackageNotAllowed { package: ObjectID },
+    #[error("Recipient [{recipient}] is not in the address book.")]
+    UnknownRecipient { recipient: SuiAddress },
+    #[error("Publishing packages is denied.")]
+    PublishDenied,
+}
+
+/// A Move function, e.g. `0x2::devnet_nft::mint`.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct MoveFunction {
+    pub package: ObjectID,
+    pub module: String,
+    pub function: String,
+}
+
+/// The transactions a wallet may sign. The default policy allows everything; it can be
+/// loaded from a configuration file.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SigningPolicy {
+    pub denied_packages: BTreeSet<ObjectID>,
+    pub denied_functions: BTreeSet<MoveFunction>,
+    /// If set, the only packages whose functions can be called.
+    pub allowed_packages: Option<BTreeSet<ObjectID>>,
+    /// If set, the address book: the only addresses, besides the sender, objects and coins
+    /// can be sent to.
+    pub known_recipients: Option<BTreeSet<SuiAddress>>,
+    pub deny_publish: bool,
+}
+
+impl SigningPolicy {
+    pub fn deny_package(mut self, package: ObjectID) -> Self {
+        self.denied_packages.insert(package);
+        self
+    }
+
+    pub fn deny_function(mut self, package: ObjectID, module: &str, function: &str) -> Self {
+        self.denied_functions.insert(MoveFunction {
+            package,
+            module: module.to_string(),
+            function: function.to_string(),
+        });
+        self
+    }
+
+    pub fn allow_only_packages(mut self, packages: impl IntoIterator<Item = ObjectID>) -> Self {
+        self.allowed_packages = Some(packages.into_iter().collect());
+        self
+    }
+
+    /// Only allow sending objects and coins to the sender and the addresses of
+    /// `address_book`.
+    pub fn with_address_book(mut self, address_book: impl IntoIterator<Item = SuiAddress>) -> Self {
+        self.known_recipients = Some(address_book.into_iter().collect());
+        self
+    }
+
+    pub fn deny_publish(mut self) -> Self {
+        self.deny_publish = true;
+        self
+    }
+
+    /// Check every transaction of `data` against the policy.
+    pub fn check(&self, data: &TransactionData) -> Result<(), PolicyViolation> {
+        let sender = data.signer();
+        let check_recipient = |recipient: &SuiAddress| match &self.known_recipients {
+            Some(known) if *recipient != sender && !known.contains(recipient) => {
+                Err(PolicyViolation::UnknownRecipient {
+                    recipient: *recipient,
+                })
+            }
+            _ => Ok(()),
+        };
+        for transaction in data.kind.single_transactions() {
+            match transaction {
+                SingleTransactionKind::TransferObject(transfer) => {
+                    check_recipient(&transfer.recipient)?
+                }
+                SingleTransactionKind::TransferSui(transfer) => {
+                    check_recipient(&transfer.recipient)?
+                }
+                SingleTransactionKind::Pay(pay) => {
+                    pay.recipients.iter().try_for_each(check_recipient)?
+                }
+                SingleTransactionKind::Call(call) => {
+                    let package = call.package.0;
+                    if self.denied_packages.contains(&package) {
+                        return Err(PolicyViolation::DeniedPackage { package });
+                    }
+                    if let Some(allowed) = &self.allowed_packages {
+                        if !allowed.contains(&package) {
+                            return Err(PolicyViolation::PackageNotAllowed { package });
+                        }
+                    }
+                    let function = MoveFunction {
+                        package,
+                        module: call.module.to_string(),
+                        function: call.function.to_string(),
+                    };
+                    if self.denied_functions.contains(&function) {
+                        return Err(PolicyViolation::DeniedFunction {
+                            package,
+                            module: function.module,
+                            function: function.function,
+                        });
+                    }
+                }
+                SingleTransactionKind::Publish(_) => {
+                    if self.deny_publish {
+                        return Err(PolicyViolation::PublishDenied);
+                    }
+                }
+                SingleTransactionKind::ChangeEpoch(_) => {}
+            }
+        }
+        Ok(())
+    }
+
+    /// Sign `data` with the key of its sender, if it complies with the policy. A violation is
+    /// returned as [PolicyViolation] wrapped in [Error::Other].
+    pub fn sign(&self, keystore: &SuiKeystore, data: &TransactionData) -> SuiRpcResult<Signature> {
+        self.check(data).map_err(|e| Error::Other(e.into()))?;
+        Ok(keystore.sign(&data.signer(), &data.to_bytes())?)
+    }
+}
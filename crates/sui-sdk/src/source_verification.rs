@@ -0,0 +1,113 @@
+// Copyright (c) 2022, Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+
+use move_binary_format::CompiledModule;
+use move_core_types::account_address::AccountAddress;
+use move_core_types::language_storage::ModuleId;
+use move_package::BuildConfig;
+
+use sui_adapter::bytecode_rewriter::ModuleHandleRewriter;
+use sui_framework::build_move_package;
+use sui_types::base_types::ObjectID;
+
+use crate::error::SuiRpcResult;
+use crate::introspection::package_bytecode;
+use crate::runtime::spawn_blocking;
+use crate::ReadApi;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ModuleVerification {
+    /// The local bytecode is identical to the on-chain bytecode.
+    Match,
+    Mismatch,
+    /// The module is in the local sources but not in the on-chain package.
+    MissingOnChain,
+    /// The module is in the on-chain package but not in the local sources.
+    MissingLocally,
+}
+
+#[derive(Debug, Clone)]
+pub struct SourceVerificationReport {
+    pub package_id: ObjectID,
+    /// Verification result keyed by module name.
+    pub modules: BTreeMap<String, ModuleVerification>,
+}
+
+impl SourceVerificationReport {
+    /// True if every module matches.
+    pub fn is_verified(&self) -> bool {
+        self.modules
+            .values()
+            .all(|result| *result == ModuleVerification::Match)
+    }
+}
+
+impl ReadApi {
+    /// Compile the Move package at `path` and compare its bytecode against the package
+    /// `package_id`, module by module.
+    ///
+    /// Publishing replaces the zero address of a package's modules with the package ID, so the
+    /// same substitution is applied to the local modules before comparing them.
+    pub async fn verify_package_source(
+        &self,
+        package_id: ObjectID,
+        path: PathBuf,
+        build_config: BuildConfig,
+    ) -> SuiRpcResult<SourceVerificationReport> {
+        let mut local = spawn_blocking(self.runtime(), move || {
+            build_move_package(&path, build_config)
+        })
+        .await??;
+        substitute_package_id(&mut local, package_id)?;
+        let mut local = local
+            .iter()
+            .map(|module| {
+                let mut bytes = vec![];
+                module.serialize(&mut bytes)?;
+                Ok((module.self_id().name().to_string(), bytes))
+            })
+            .collect::<anyhow::Result<BTreeMap<_, _>>>()?;
+
+        let on_chain = package_bytecode(self, package_id).await?;
+
+        let mut modules = BTreeMap::new();
+        for (name, bytes) in on_chain {
+            let result = match local.remove(&name) {
+                Some(local_bytes) if local_bytes == bytes => ModuleVerification::Match,
+                Some(_) => ModuleVerification::Mismatch,
+                None => ModuleVerification::MissingLocally,
+            };
+            modules.insert(name, result);
+        }
+        for name in local.into_keys() {
+            modules.insert(name, ModuleVerification::MissingOnChain);
+        }
+        Ok(SourceVerificationReport {
+            package_id,
+            modules,
+        })
+    }
+}
+
+fn substitute_package_id(
+    modules: &mut [CompiledModule],
+    package_id: ObjectID,
+) -> anyhow::Result<()> {
+    let sub_map = modules
+        .iter()
+        .map(|module| module.self_id())
+        .filter(|id| *id.address() == AccountAddress::ZERO)
+        .map(|id| {
+            let new_id = ModuleId::new(AccountAddress::from(package_id), id.name().to_owned());
+            (id, new_id)
+        })
+        .collect();
+    let rewriter = ModuleHandleRewriter::new(sub_map)?;
+    for module in modules {
+        rewriter.sub_module_ids(module);
+    }
+    Ok(())
+}
@@ -0,0 +1,262 @@
+// Copyright (c) 2022, Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Spending limits per address over rolling time windows, e.g. at most 100 SUI per hour and
+//! 1000 SUI per day, for hot wallets.
+//!
+//! The [SpendingGuard] records every authorized spend in a [CursorStore], so the limits hold
+//! across restarts. A spend over a limit is rejected, unless a [CoSigner] approves it. A spend
+//! is recorded when authorized, before the transaction is broadcast, so a transaction that
+//! is never executed still counts against the limits.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use anyhow::anyhow;
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use tokio::sync::Mutex;
+
+use sui_types::base_types::{ObjectID, SuiAddress};
+use sui_types::crypto::Signature;
+use sui_types::messages::{SingleTransactionKind, TransactionData};
+
+use crate::crypto::SuiKeystore;
+use crate::cursor_store::CursorStore;
+use crate::error::{Error, SuiRpcResult};
+
+/// At most `max` base units spent in any `window`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SpendingLimit {
+    pub window: Duration,
+    pub max: u64,
+}
+
+/// A spend over a limit, rejected or submitted to the [CoSigner].
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+#[error(
+    "Spending [{amount}] from [{address}] exceeds the limit of [{}] per {:?}, with [{spent}] already spent.",
+    limit.max,
+    limit.window
+)]
+pub struct LimitExceeded {
+    pub address: SuiAddress,
+    pub amount: u64,
+    /// The amount already spent in the window of `limit`.
+    pub spent: u64,
+    pub limit: SpendingLimit,
+}
+
+/// Approves spends over the limits, e.g. by asking a second operator or signing service.
+#[async_trait]
+pub trait CoSigner: Send + Sync {
+    async fn approve(&self, request: &LimitExceeded) -> SuiRpcResult<bool>;
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+struct Spend {
+    timestamp_ms: u64,
+    amount: u64,
+}
+
+/// The MIST `data` declares spending: the amounts of its SUI payments and transfers to other
+/// addresses, and the most its gas can cost. `sui_coins` are the ids of the SUI coins of the
+/// sender, e.g. the `Coin<0x2::sui::SUI>` objects listed by
+/// [ReadApi::get_objects_owned_by_address](crate::ReadApi::get_objects_owned_by_address), as
+/// the coin types of payments are not part of the transaction data.
+///
+/// Returns `None` when the value spent is not part of the transaction data: for Move calls
+/// and publishes, which may move any coin of the sender, for transfers of whole objects or
+/// whole SUI coins, and for payments from coins not in `sui_coins`.
+pub fn declared_outgoing_amount(
+    data: &TransactionData,
+    sui_coins: &BTreeSet<ObjectID>,
+) -> Option<u64> {
+    let sender = data.signer();
+    let mut amount = data.gas_budget.checked_mul(data.gas_price)?;
+    for transaction in data.kind.single_transactions() {
+        match transaction {
+            SingleTransactionKind::TransferSui(transfer) if transfer.recipient != sender => {
+                amount = amount.checked_add(transfer.amount?)?
+            }
+            SingleTransactionKind::TransferObject(transfer) if transfer.recipient != sender => {
+                return None
+            }
+            SingleTransactionKind::Pay(pay) => {
+                if !pay.coins.iter().all(|coin| sui_coins.contains(&coin.0)) {
+                    return None;
+                }
+                for (recipient, paid) in pay.recipients.iter().zip(&pay.amounts) {
+                    if *recipient != sender {
+                        amount = amount.checked_add(*paid)?;
+                    }
+                }
+            }
+            SingleTransactionKind::Call(_) | SingleTransactionKind::Publish(_) => return None,
+            _ => {}
+        }
+    }
+    Some(amount)
+}
+
+pub struct SpendingGuard<S> {
+    store: S,
+    prefix: String,
+    limits: Vec<SpendingLimit>,
+    address_limits: BTreeMap<SuiAddress, Vec<SpendingLimit>>,
+    cosigner: Option<Box<dyn CoSigner>>,
+    // Serializes authorizations, which read, check and update the spends of an address.
+    lock: Mutex<()>,
+}
+
+impl<S: CursorStore> SpendingGuard<S> {
+    pub fn new(store: S) -> Self {
+        Self {
+            store,
+            prefix: "spending".to_string(),
+            limits: vec![],
+            address_limits: BTreeMap::new(),
+            cosigner: None,
+            lock: Mutex::new(()),
+        }
+    }
+
+    /// Record spends under the cursor names `{prefix}/{address}`. Defaults to `spending`.
+    pub fn with_prefix(mut self, prefix: impl Into<String>) -> Self {
+        self.prefix = prefix.into();
+        self
+    }
+
+    /// Limit the spends of every address without limits of its own.
+    pub fn with_limit(mut self, window: Duration, max: u64) -> Self {
+        self.limits.push(SpendingLimit { window, max });
+        self
+    }
+
+    /// Limit the spends of `address`, instead of the limits of every address.
+    pub fn with_address_limit(mut self, address: SuiAddress, window: Duration, max: u64) -> Self {
+        self.address_limits
+            .entry(address)
+            .or_default()
+            .push(SpendingLimit { window, max });
+        self
+    }
+
+    /// Submit spends over the limits to `cosigner` instead of rejecting them.
+    pub fn with_cosigner(mut self, cosigner: impl CoSigner + 'static) -> Self {
+        self.cosigner = Some(Box::new(cosigner));
+        self
+    }
+
+    /// Authorize and record a spend of `amount` from `address` now. Fails with
+    /// [LimitExceeded], as [Error::Other], if it exceeds a limit and is not approved.
+    pub async fn authorize(&self, address: SuiAddress, amount: u64) -> SuiRpcResult<()> {
+        self.authorize_at(address, amount, now_ms()).await
+    }
+
+    /// Authorize and record a spend of `amount` from `address` at `timestamp_ms`.
+    pub async fn authorize_at(
+        &self,
+        address: SuiAddress,
+        amount: u64,
+        timestamp_ms: u64,
+    ) -> SuiRpcResult<()> {
+        let _lock = self.lock.lock().await;
+        let limits = self.limits(&address);
+        let longest = limits.iter().map(|limit| limit.window).max();
+        let mut spends = self.load(&address).await?;
+        // Spends older than the longest window never count again.
+        spends.retain(|spend| {
+            longest.map_or(false, |window| {
+                spend.timestamp_ms + window.as_millis() as u64 > timestamp_ms
+            })
+        });
+
+        for limit in limits {
+            let spent = spent_since(&spends, timestamp_ms, limit.window);
+            if spent.saturating_add(amount) > limit.max {
+                let exceeded = LimitExceeded {
+                    address,
+                    amount,
+                    spent,
+                    limit: *limit,
+                };
+                let approved = match &self.cosigner {
+                    Some(cosigner) => cosigner.approve(&exceeded).await?,
+                    None => false,
+                };
+                if !approved {
+                    return Err(Error::Other(exceeded.into()));
+                }
+                break;
+            }
+        }
+
+        spends.push(Spend {
+            timestamp_ms,
+            amount,
+        });
+        self.store
+            .save(&self.record_name(&address), serde_json::to_value(spends)?)
+            .await
+    }
+
+    /// The amount spent by `address` in the last `window`.
+    pub async fn spent(&self, address: SuiAddress, window: Duration) -> SuiRpcResult<u64> {
+        let spends = self.load(&address).await?;
+        Ok(spent_since(&spends, now_ms(), window))
+    }
+
+    /// Authorize the amount `data` spends, see [declared_outgoing_amount], and sign it with
+    /// the key of its sender. Transactions spending an unknown amount, e.g. Move calls, are
+    /// rejected: [authorize](SpendingGuard::authorize) the amount they may spend and sign them
+    /// directly instead.
+    pub async fn sign(
+        &self,
+        keystore: &SuiKeystore,
+        data: &TransactionData,
+        sui_coins: &BTreeSet<ObjectID>,
+    ) -> SuiRpcResult<Signature> {
+        let amount = match declared_outgoing_amount(data, sui_coins) {
+            Some(amount) => amount,
+            None => {
+                return Err(Error::Other(anyhow!(
+                    "The amount sent by the transaction is unknown; authorize it explicitly."
+                )))
+            }
+        };
+        self.authorize(data.signer(), amount).await?;
+        Ok(keystore.sign(&data.signer(), &data.to_bytes())?)
+    }
+
+    fn limits(&self, address: &SuiAddress) -> &[SpendingLimit] {
+        self.address_limits
+            .get(address)
+            .map_or(&self.limits, |limits| limits)
+    }
+
+    async fn load(&self, address: &SuiAddress) -> anyhow::Result<Vec<Spend>> {
+        Ok(match self.store.load(&self.record_name(address)).await? {
+            Some(spends) => serde_json::from_value(spends)?,
+            None => vec![],
+        })
+    }
+
+    fn record_name(&self, address: &SuiAddress) -> String {
+        format!("{}/{address}", self.prefix)
+    }
+}
+
+fn spent_since(spends: &[Spend], now_ms: u64, window: Duration) -> u64 {
+    let start = now_ms.saturating_sub(window.as_millis() as u64);
+    spends
+        .iter()
+        .filter(|spend| spend.timestamp_ms > start)
+        .fold(0u64, |spent, spend| spent.saturating_add(spend.amount))
+}
+
+fn now_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_millis() as u64)
+}
@@ -0,0 +1,308 @@
+// Copyright (c) 2022, Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! An embedded SQLite backend for the local object store and for indexers, so wallets and
+//! small indexers get durable storage without running a database server.
+//!
+//! The schema is created and upgraded by [MIGRATIONS] when the database is opened; the
+//! applied version is kept in the `user_version` pragma. [SqliteStore::compact] deletes
+//! indexer rows older than a retention window and reclaims the freed space.
+
+use std::collections::BTreeSet;
+use std::path::Path;
+use std::str::FromStr;
+
+use anyhow::anyhow;
+use async_trait::async_trait;
+use serde_json::Value;
+use sqlx::sqlite::{SqliteConnectOptions, SqlitePoolOptions};
+use sqlx::{Executor, Row, SqlitePool};
+
+use sui_json_rpc_types::SuiTransactionEffects;
+use sui_types::base_types::{ObjectID, SuiAddress};
+
+use crate::error::{Error, SuiRpcResult};
+use crate::indexer::{EventCursor, EventKey, IndexerStore};
+use crate::local_store::{effect_updates, fetch_owned, StoredObject};
+use crate::ReadApi;
+
+/// Schema migrations, applied in order. Released migrations must never change: add a new
+/// one instead.
+pub const MIGRATIONS: &[&str] = &["CREATE TABLE addresses (address TEXT PRIMARY KEY);
+    CREATE TABLE objects (
+        object_id TEXT PRIMARY KEY,
+        owner TEXT,
+        type TEXT NOT NULL,
+        balance INTEGER,
+        object TEXT NOT NULL
+    );
+    CREATE INDEX objects_owner ON objects (owner, type);
+    CREATE TABLE indexer_cursors (indexer TEXT PRIMARY KEY, cursor TEXT NOT NULL);
+    CREATE TABLE indexer_rows (
+        table_name TEXT NOT NULL,
+        event_key TEXT NOT NULL,
+        timestamp INTEGER NOT NULL,
+        row TEXT NOT NULL,
+        PRIMARY KEY (table_name, event_key)
+    );
+    CREATE INDEX indexer_rows_timestamp ON indexer_rows (timestamp);"];
+
+/// A local object store, like [LocalStore](crate::local_store::LocalStore), and an
+/// [IndexerStore] in one SQLite database. The database is not encrypted, keep it on a
+/// disk only its owner can read, e.g. an encrypted volume.
+pub struct SqliteStore {
+    pool: SqlitePool,
+}
+
+impl SqliteStore {
+    /// Open the database at `path`, creating it if it does not exist, and migrate it to the
+    /// current schema.
+    pub async fn open(path: &Path) -> SuiRpcResult<Self> {
+        let options = SqliteConnectOptions::new()
+            .filename(path)
+            .create_if_missing(true);
+        Self::migrate(SqlitePool::connect_with(options).await?).await
+    }
+
+    /// An in-memory database, for tests.
+    pub async fn in_memory() -> SuiRpcResult<Self> {
+        // Every connection to `sqlite::memory:` opens a distinct database.
+        let pool = SqlitePoolOptions::new()
+            .max_connections(1)
+            .connect("sqlite::memory:")
+            .await?;
+        Self::migrate(pool).await
+    }
+
+    async fn migrate(pool: SqlitePool) -> SuiRpcResult<Self> {
+        let version: i64 = sqlx::query_scalar("PRAGMA user_version")
+            .fetch_one(&pool)
+            .await?;
+        let version = version as usize;
+        if version > MIGRATIONS.len() {
+            return Err(Error::Other(anyhow!(
+                "Database schema version {version} is newer than the supported version {}.",
+                MIGRATIONS.len()
+            )));
+        }
+        for (index, migration) in MIGRATIONS.iter().enumerate().skip(version) {
+            let mut transaction = pool.begin().await?;
+            transaction.execute(*migration).await?;
+            transaction
+                .execute(format!("PRAGMA user_version = {}", index + 1).as_str())
+                .await?;
+            transaction.commit().await?;
+        }
+        Ok(Self { pool })
+    }
+
+    pub fn pool(&self) -> &SqlitePool {
+        &self.pool
+    }
+
+    /// The version of the database schema, the number of applied [MIGRATIONS].
+    pub async fn schema_version(&self) -> SuiRpcResult<usize> {
+        let version: i64 = sqlx::query_scalar("PRAGMA user_version")
+            .fetch_one(&self.pool)
+            .await?;
+        Ok(version as usize)
+    }
+
+    pub async fn addresses(&self) -> SuiRpcResult<BTreeSet<SuiAddress>> {
+        let rows = sqlx::query("SELECT address FROM addresses")
+            .fetch_all(&self.pool)
+            .await?;
+        rows.iter()
+            .map(|row| Ok(SuiAddress::from_str(row.try_get(0)?)?))
+            .collect()
+    }
+
+    /// Start tracking `address`. Its objects are fetched on the next [SqliteStore::sync].
+    pub async fn add_address(&self, address: SuiAddress) -> SuiRpcResult<()> {
+        sqlx::query("INSERT OR IGNORE INTO addresses VALUES (?)")
+            .bind(address.to_string())
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+
+    pub async fn remove_address(&self, address: &SuiAddress) -> SuiRpcResult<()> {
+        let mut transaction = self.pool.begin().await?;
+        sqlx::query("DELETE FROM addresses WHERE address = ?")
+            .bind(address.to_string())
+            .execute(&mut *transaction)
+            .await?;
+        sqlx::query("DELETE FROM objects WHERE owner = ?")
+            .bind(address.to_string())
+            .execute(&mut *transaction)
+            .await?;
+        transaction.commit().await?;
+        Ok(())
+    }
+
+    pub async fn get_object(&self, object_id: &ObjectID) -> SuiRpcResult<Option<StoredObject>> {
+        let row = sqlx::query("SELECT object FROM objects WHERE object_id = ?")
+            .bind(object_id.to_string())
+            .fetch_optional(&self.pool)
+            .await?;
+        Ok(match row {
+            Some(row) => Some(serde_json::from_str(row.try_get(0)?)?),
+            None => None,
+        })
+    }
+
+    pub async fn objects_owned_by(&self, address: SuiAddress) -> SuiRpcResult<Vec<StoredObject>> {
+        let rows = sqlx::query("SELECT object FROM objects WHERE owner = ? ORDER BY object_id")
+            .bind(address.to_string())
+            .fetch_all(&self.pool)
+            .await?;
+        rows.iter()
+            .map(|row| Ok(serde_json::from_str(row.try_get(0)?)?))
+            .collect()
+    }
+
+    /// Total balance of the coins of type `coin_type` (e.g. `0x2::coin::Coin<0x2::sui::SUI>`)
+    /// owned by `address`.
+    pub async fn balance(&self, address: SuiAddress, coin_type: &str) -> SuiRpcResult<u64> {
+        // Summed here, as the total of u64 balances may overflow the i64 sum of SQLite.
+        let balances: Vec<i64> = sqlx::query_scalar(
+            "SELECT balance FROM objects WHERE owner = ? AND type = ? AND balance IS NOT NULL",
+        )
+        .bind(address.to_string())
+        .bind(coin_type)
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(balances.into_iter().map(|balance| balance as u64).sum())
+    }
+
+    /// Replace the local objects of every tracked address with the node's current view.
+    pub async fn sync(&self, read_api: &ReadApi) -> SuiRpcResult<()> {
+        let objects = fetch_owned(read_api, &self.addresses().await?).await?;
+        let mut transaction = self.pool.begin().await?;
+        transaction.execute("DELETE FROM objects").await?;
+        for object in &objects {
+            upsert_object(&mut *transaction, object).await?;
+        }
+        transaction.commit().await?;
+        Ok(())
+    }
+
+    /// Update the store with the effects of an executed transaction.
+    pub async fn apply_effects(
+        &self,
+        read_api: &ReadApi,
+        effects: &SuiTransactionEffects,
+    ) -> SuiRpcResult<()> {
+        let updates = effect_updates(read_api, effects, &self.addresses().await?).await?;
+        let mut transaction = self.pool.begin().await?;
+        for (object_id, object) in &updates {
+            match object {
+                Some(object) => upsert_object(&mut *transaction, object).await?,
+                None => {
+                    sqlx::query("DELETE FROM objects WHERE object_id = ?")
+                        .bind(object_id.to_string())
+                        .execute(&mut *transaction)
+                        .await?;
+                }
+            }
+        }
+        transaction.commit().await?;
+        Ok(())
+    }
+
+    /// The rows written by indexer handlers to `table`, ordered by event timestamp.
+    pub async fn rows(&self, table: &str) -> SuiRpcResult<Vec<(EventKey, Value)>> {
+        let rows = sqlx::query(
+            "SELECT event_key, row FROM indexer_rows WHERE table_name = ? ORDER BY timestamp, event_key",
+        )
+        .bind(table)
+        .fetch_all(&self.pool)
+        .await?;
+        rows.iter()
+            .map(|row| {
+                Ok((
+                    serde_json::from_str(row.try_get(0)?)?,
+                    serde_json::from_str(row.try_get(1)?)?,
+                ))
+            })
+            .collect()
+    }
+
+    /// Delete the indexer rows of events older than `before_timestamp`, in milliseconds since
+    /// epoch, and the objects of addresses which are no longer tracked, then reclaim the free
+    /// space of the database file. Returns the number of deleted rows.
+    pub async fn compact(&self, before_timestamp: u64) -> SuiRpcResult<u64> {
+        let mut transaction = self.pool.begin().await?;
+        let rows = sqlx::query("DELETE FROM indexer_rows WHERE timestamp < ?")
+            .bind(before_timestamp as i64)
+            .execute(&mut *transaction)
+            .await?
+            .rows_affected();
+        transaction
+            .execute("DELETE FROM objects WHERE owner NOT IN (SELECT address FROM addresses)")
+            .await?;
+        transaction.commit().await?;
+        // VACUUM cannot run inside a transaction.
+        self.pool.execute("VACUUM").await?;
+        Ok(rows)
+    }
+}
+
+async fn upsert_object(
+    connection: &mut sqlx::SqliteConnection,
+    object: &StoredObject,
+) -> anyhow::Result<()> {
+    sqlx::query(
+        "INSERT INTO objects (object_id, owner, type, balance, object) VALUES (?, ?, ?, ?, ?) \
+        ON CONFLICT(object_id) DO UPDATE SET owner = excluded.owner, type = excluded.type, \
+        balance = excluded.balance, object = excluded.object",
+    )
+    .bind(object.info.object_id.to_string())
+    .bind(object.owner().map(|owner| owner.to_string()))
+    .bind(&object.info.type_)
+    .bind(object.balance.map(|balance| balance as i64))
+    .bind(serde_json::to_string(object)?)
+    .execute(connection)
+    .await?;
+    Ok(())
+}
+
+#[async_trait]
+impl IndexerStore for SqliteStore {
+    async fn load_cursor(&self, indexer: &str) -> SuiRpcResult<Option<EventCursor>> {
+        let row = sqlx::query("SELECT cursor FROM indexer_cursors WHERE indexer = ?")
+            .bind(indexer)
+            .fetch_optional(&self.pool)
+            .await?;
+        Ok(match row {
+            Some(row) => Some(serde_json::from_str(row.try_get(0)?)?),
+            None => None,
+        })
+    }
+
+    async fn save_cursor(&self, indexer: &str, cursor: EventCursor) -> SuiRpcResult<()> {
+        sqlx::query(
+            "INSERT INTO indexer_cursors (indexer, cursor) VALUES (?, ?) \
+            ON CONFLICT(indexer) DO UPDATE SET cursor = excluded.cursor",
+        )
+        .bind(indexer)
+        .bind(serde_json::to_string(&cursor)?)
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
+    async fn upsert_row(&self, table: &str, key: &EventKey, row: Value) -> SuiRpcResult<()> {
+        sqlx::query(
+            "INSERT INTO indexer_rows (table_name, event_key, timestamp, row) VALUES (?, ?, ?, ?) \
+            ON CONFLICT(table_name, event_key) DO UPDATE SET row = excluded.row",
+        )
+        .bind(table)
+        .bind(serde_json::to_string(key)?)
+        .bind(key.timestamp as i64)
+        .bind(row.to_string())
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,225 @@
+// Copyright (c) 2022, Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+use anyhow::anyhow;
+use futures::future::join_all;
+use serde::de::DeserializeOwned;
+
+use sui_json_rpc_types::SuiData;
+use sui_types::base_types::{ObjectID, SuiAddress};
+use sui_types::sui_system_state::{Delegation, StakedSui, SuiSystemState, Validator};
+use sui_types::SUI_SYSTEM_STATE_OBJECT_ID;
+
+use crate::error::SuiRpcResult;
+use crate::ReadApi;
+
+/// Summary of an active validator, as shown to delegators.
+#[derive(Debug, Clone)]
+pub struct ValidatorSummary {
+    pub sui_address: SuiAddress,
+    pub name: String,
+    pub net_address: Vec<u8>,
+    /// The validator's own stake.
+    pub stake_amount: u64,
+    /// Total SUI held by the validator's delegation staking pool, including rewards.
+    pub delegation_amount: u64,
+    pub gas_price: u64,
+    /// Epoch at which the validator's staking pool started.
+    pub pool_starting_epoch: u64,
+    /// Epoch of the system state this summary was taken from.
+    pub epoch: u64,
+    /// Number of SUI a single pool token is currently worth.
+    pub pool_exchange_rate: f64,
+}
+
+impl ValidatorSummary {
+    fn new(validator: &Validator, epoch: u64) -> Self {
+        let pool = &validator.delegation_staking_pool;
+        let pool_exchange_rate = if pool.delegation_token_supply.value == 0 {
+            1.0
+        } else {
+            pool.sui_balance as f64 / pool.delegation_token_supply.value as f64
+        };
+        Self {
+            sui_address: validator.metadata.sui_address.into(),
+            name: String::from_utf8_lossy(&validator.metadata.name).into_owned(),
+            net_address: validator.metadata.net_address.clone(),
+            stake_amount: validator.stake_amount,
+            delegation_amount: pool.sui_balance,
+            gas_price: validator.gas_price,
+            pool_starting_epoch: pool.starting_epoch,
+            epoch,
+            pool_exchange_rate,
+        }
+    }
+
+    /// Estimate the annual percentage yield of delegating to this validator, extrapolating
+    /// the average per-epoch growth of the pool exchange rate since the pool started.
+    /// The framework does not charge a validator commission yet, so the pool growth is what
+    /// delegators earn.
+    pub fn estimated_apy(&self, epochs_per_year: u64) -> f64 {
+        let epochs = self.epoch.saturating_sub(self.pool_starting_epoch);
+        if epochs == 0 {
+            return 0.0;
+        }
+        let growth_per_epoch = self.pool_exchange_rate.powf(1.0 / epochs as f64);
+        growth_per_epoch.powf(epochs_per_year as f64) - 1.0
+    }
+}
+
+/// An activated delegation owned by an address.
+#[derive(Debug, Clone)]
+pub struct DelegationSummary {
+    pub object_id: ObjectID,
+    pub validator_address: SuiAddress,
+    pub pool_starting_epoch: u64,
+    pub pool_tokens: u64,
+    pub principal_sui_amount: u64,
+    /// Rewards earned so far, at the current pool exchange rate.
+    pub estimated_rewards: u64,
+}
+
+/// Staked SUI principal owned by an address. A `StakedSui` object is created as soon as a
+/// delegation is requested; the matching [DelegationSummary] only appears once the
+/// delegation is activated at the start of the next epoch.
+#[derive(Debug, Clone)]
+pub struct StakedSuiSummary {
+    pub object_id: ObjectID,
+    pub principal: u64,
+    /// While the delegation is pending, the epoch it is activated at: the next one. `None`
+    /// once it is active, as the framework does not record when that happened. Pending
+    /// delegations are matched to `StakedSui` objects by delegator and amount.
+    pub activation_epoch: Option<u64>,
+    /// Set if the stake came from a locked coin, the epoch the original lock expires.
+    pub locked_until_epoch: Option<u64>,
+}
+
+impl ReadApi {
+    pub(crate) async fn get_move_object<T: DeserializeOwned>(
+        &self,
+        object_id: ObjectID,
+    ) -> anyhow::Result<T> {
+        let object = self.get_object(object_id).await?.into_object()?;
+        let move_object = object
+            .data
+            .try_as_move()
+            .ok_or_else(|| anyhow!("Object [{object_id}] is not a Move object."))?;
+        Ok(bcs::from_bytes(&move_object.bcs_bytes)?)
+    }
+
+    async fn get_owned_move_objects<T: DeserializeOwned>(
+        &self,
+        address: SuiAddress,
+        type_: String,
+    ) -> anyhow::Result<Vec<(ObjectID, T)>> {
+        let object_ids = self
+            .get_objects_owned_by_address(address)
+            .await?
+            .into_iter()
+            .filter(|info| info.type_ == type_)
+            .map(|info| info.object_id)
+            .collect::<Vec<_>>();
+        let handles = object_ids.iter().map(|id| self.get_move_object(*id));
+        object_ids
+            .iter()
+            .zip(join_all(handles).await)
+            .map(|(id, object)| Ok((*id, object?)))
+            .collect()
+    }
+
+    pub async fn get_sui_system_state(&self) -> SuiRpcResult<SuiSystemState> {
+        Ok(self.get_move_object(SUI_SYSTEM_STATE_OBJECT_ID).await?)
+    }
+
+    /// Return a summary of all active validators.
+    pub async fn get_validators(&self) -> SuiRpcResult<Vec<ValidatorSummary>> {
+        let state = self.get_sui_system_state().await?;
+        Ok(state
+            .validators
+            .active_validators
+            .iter()
+            .map(|validator| ValidatorSummary::new(validator, state.epoch))
+            .collect())
+    }
+
+    /// Return all activated delegations owned by `address`, with rewards estimated from the
+    /// current exchange rate of the validator's staking pool.
+    pub async fn get_delegations(
+        &self,
+        address: SuiAddress,
+    ) -> SuiRpcResult<Vec<DelegationSummary>> {
+        let state = self.get_sui_system_state().await?;
+        let delegations: Vec<(ObjectID, Delegation)> = self
+            .get_owned_move_objects(address, Delegation::type_().to_string())
+            .await?;
+        Ok(delegations
+            .into_iter()
+            .map(|(object_id, delegation)| {
+                let pool_tokens = delegation.pool_tokens.value();
+                let estimated_rewards = state
+                    .validators
+                    .active_validators
+                    .iter()
+                    .map(|v| &v.delegation_staking_pool)
+                    .find(|pool| {
+                        pool.validator_address == delegation.validator_address
+                            && pool.starting_epoch == delegation.pool_starting_epoch
+                    })
+                    .map_or(0, |pool| {
+                        pool.sui_amount(pool_tokens)
+                            .saturating_sub(delegation.principal_sui_amount)
+                    });
+                DelegationSummary {
+                    object_id,
+                    validator_address: delegation.validator_address.into(),
+                    pool_starting_epoch: delegation.pool_starting_epoch,
+                    pool_tokens,
+                    principal_sui_amount: delegation.principal_sui_amount,
+                    estimated_rewards,
+                }
+            })
+            .collect())
+    }
+
+    /// Return all `StakedSui` objects owned by `address`.
+    pub async fn get_staked_sui(&self, address: SuiAddress) -> SuiRpcResult<Vec<StakedSuiSummary>> {
+        let state = self.get_sui_system_state().await?;
+        let staked_sui: Vec<(ObjectID, StakedSui)> = self
+            .get_owned_move_objects(address, StakedSui::type_().to_string())
+            .await?;
+        // The amounts of the delegations of `address` requested in the current epoch.
+        let validators = &state.validators;
+        let mut pending = validators
+            .active_validators
+            .iter()
+            .chain(&validators.pending_validators)
+            .flat_map(|v| &v.delegation_staking_pool.pending_delegations)
+            .filter(|entry| SuiAddress::from(entry.delegator) == address)
+            .map(|entry| entry.sui_amount)
+            .collect::<Vec<_>>();
+        Ok(staked_sui
+            .into_iter()
+            .map(|(object_id, staked_sui)| {
+                let principal = staked_sui.principal.value();
+                let activation_epoch =
+                    pending
+                        .iter()
+                        .position(|amount| *amount == principal)
+                        .map(|index| {
+                            pending.swap_remove(index);
+                            state.epoch + 1
+                        });
+                StakedSuiSummary {
+                    object_id,
+                    principal,
+                    activation_epoch,
+                    locked_until_epoch: staked_sui
+                        .sui_token_lock
+                        .vec
+                        .first()
+                        .map(|lock| lock.epoch),
+                }
+            })
+            .collect())
+    }
+}
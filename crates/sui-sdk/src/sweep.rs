@@ -0,0 +1,190 @@
+// Copyright (c) 2022, Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+use std::collections::BTreeMap;
+
+use anyhow::bail;
+
+use sui_types::base_types::{ObjectRef, SuiAddress};
+use sui_types::coin::Coin;
+use sui_types::gas_coin::GasCoin;
+use sui_types::messages::{Pay, SingleTransactionKind, TransactionData, TransactionKind};
+use sui_types::parse_sui_struct_tag;
+
+use crate::error::{Error, SuiRpcResult};
+use crate::local_store::{fetch, StoredObject};
+use crate::transaction_builder::TransactionBuilder;
+
+/// An unsigned transaction moving the coins of one deposit address to the cold address.
+/// Sign `data.to_bytes()` with the deposit address' key.
+#[derive(Debug, Clone)]
+pub struct SweepTransaction {
+    pub address: SuiAddress,
+    pub data: TransactionData,
+    /// Total swept per coin type (e.g. `0x2::coin::Coin<0x2::sui::SUI>`).
+    pub amounts: BTreeMap<String, u64>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct SweepPlan {
+    /// One transaction per deposit address holding coins and enough SUI for gas.
+    pub sweeps: Vec<SweepTransaction>,
+    /// A transaction from the fee payer sending a gas coin to every deposit address that
+    /// holds coins but no SUI coin large enough to pay for its sweep. Those addresses must
+    /// be planned again once the funding transaction is executed.
+    pub funding: Option<TransactionData>,
+    /// Deposit addresses that need funding before they can be swept.
+    pub unfunded: Vec<SuiAddress>,
+}
+
+impl TransactionBuilder {
+    /// Plan the consolidation of all coins held by `deposit_addresses` into `cold_address`.
+    ///
+    /// Transactions in this tree are paid for by their sender, so every deposit address pays
+    /// the gas of its own sweep with its largest SUI coin. Each sweep is a single transaction:
+    /// an address holding nothing but its gas coin transfers that coin as a whole, otherwise
+    /// each coin type is merged into one coin for the cold address with a `Pay`, and the gas
+    /// coin stays behind. If `fee_payer` is given, addresses without enough SUI for gas are
+    /// funded from its SUI coins.
+    pub async fn sweep(
+        &self,
+        deposit_addresses: &[SuiAddress],
+        cold_address: SuiAddress,
+        fee_payer: Option<SuiAddress>,
+        gas_budget: u64,
+    ) -> SuiRpcResult<SweepPlan> {
+        let mut plan = SweepPlan::default();
+        for address in deposit_addresses {
+            let mut coins = self.get_coins(*address).await?;
+            if coins.is_empty() {
+                continue;
+            }
+            let gas_type = GasCoin::type_().to_string();
+            let gas = coins
+                .get(&gas_type)
+                .and_then(|coins| coins.iter().max_by_key(|(_, balance)| *balance))
+                .filter(|(_, balance)| *balance >= gas_budget)
+                .copied();
+            let (gas, gas_balance) = match gas {
+                Some(gas) => gas,
+                None => {
+                    plan.unfunded.push(*address);
+                    continue;
+                }
+            };
+            if let Some(sui_coins) = coins.get_mut(&gas_type) {
+                sui_coins.retain(|(coin, _)| coin.0 != gas.0);
+                if sui_coins.is_empty() {
+                    coins.remove(&gas_type);
+                }
+            }
+
+            let sweep = if coins.is_empty() {
+                SweepTransaction {
+                    address: *address,
+                    data: TransactionData::new_transfer_sui(
+                        cold_address,
+                        *address,
+                        None,
+                        gas,
+                        gas_budget,
+                    ),
+                    amounts: BTreeMap::from([(gas_type, gas_balance - gas_budget)]),
+                }
+            } else {
+                let mut amounts = BTreeMap::new();
+                let mut payments = vec![];
+                for (coin_type, coins) in coins {
+                    let total = coins.iter().map(|(_, balance)| balance).sum();
+                    payments.push(SingleTransactionKind::Pay(Pay {
+                        coins: coins.into_iter().map(|(coin, _)| coin).collect(),
+                        recipients: vec![cold_address],
+                        amounts: vec![total],
+                    }));
+                    amounts.insert(coin_type, total);
+                }
+                let kind = if payments.len() == 1 {
+                    TransactionKind::Single(payments.remove(0))
+                } else {
+                    TransactionKind::Batch(payments)
+                };
+                SweepTransaction {
+                    address: *address,
+                    data: TransactionData::new(kind, *address, gas, gas_budget),
+                    amounts,
+                }
+            };
+            plan.sweeps.push(sweep);
+        }
+
+        if let (Some(fee_payer), false) = (fee_payer, plan.unfunded.is_empty()) {
+            let funding = self
+                .pay_sui_from(fee_payer, &plan.unfunded, gas_budget, gas_budget)
+                .await?;
+            plan.funding = Some(funding);
+        }
+        Ok(plan)
+    }
+
+    /// Return the coins owned by `address` with their balances, grouped by coin type.
+    pub(crate) async fn get_coins(
+        &self,
+        address: SuiAddress,
+    ) -> anyhow::Result<BTreeMap<String, Vec<(ObjectRef, u64)>>> {
+        let read_api = &*self.0;
+        let coin_ids = read_api
+            .get_objects_owned_by_address(address)
+            .await?
+            .into_iter()
+            .filter(|info| parse_sui_struct_tag(&info.type_).map_or(false, |t| Coin::is_coin(&t)))
+            .map(|info| info.object_id);
+        let coins = read_api
+            .fetch_all(coin_ids, |id| fetch(read_api, id))
+            .await?;
+
+        let mut grouped = BTreeMap::<_, Vec<_>>::new();
+        for StoredObject { info, balance } in coins.into_iter().flatten() {
+            if let Some(balance) = balance {
+                grouped
+                    .entry(info.type_.clone())
+                    .or_default()
+                    .push((info.to_object_ref(), balance));
+            }
+        }
+        Ok(grouped)
+    }
+
+    /// Send `amount` SUI from the coins of `payer` to each of `recipients`, paying gas with
+    /// the payer's largest SUI coin.
+    async fn pay_sui_from(
+        &self,
+        payer: SuiAddress,
+        recipients: &[SuiAddress],
+        amount: u64,
+        gas_budget: u64,
+    ) -> anyhow::Result<TransactionData> {
+        let mut sui_coins = self
+            .get_coins(payer)
+            .await?
+            .remove(&GasCoin::type_().to_string())
+            .unwrap_or_default();
+        sui_coins.sort_by_key(|(_, balance)| std::cmp::Reverse(*balance));
+        if sui_coins.len() < 2 {
+            bail!("Fee payer [{payer}] needs a gas coin and at least one other SUI coin.");
+        }
+        let (gas, _) = sui_coins.remove(0);
+        let required = amount * recipients.len() as u64;
+        let available: u64 = sui_coins.iter().map(|(_, balance)| balance).sum();
+        if available < required {
+            bail!("Fee payer [{payer}] holds [{available}] SUI, [{required}] is required.");
+        }
+        Ok(TransactionData::new_pay(
+            payer,
+            sui_coins.into_iter().map(|(coin, _)| coin).collect(),
+            recipients.to_vec(),
+            vec![amount; recipients.len()],
+            gas,
+            gas_budget,
+        ))
+    }
+}
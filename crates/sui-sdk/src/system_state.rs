@@ -0,0 +1,245 @@
+// Copyright (c) 2022, Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! A stable view of the system state object, for dashboards.
+//!
+//! The layout of [SuiSystemState] is that of the Move struct of the framework, which
+//! protocol upgrades change. [ReadApi::get_system_state_summary] decodes the system state
+//! with the layout of this SDK, and reads the fields by name from the parsed object if it no
+//! longer decodes, so that a dashboard keeps working across an upgrade. Fields renamed or
+//! moved by an upgrade are looked up at each of their known paths. Numbers may be JSON
+//! numbers or strings.
+
+use std::collections::BTreeMap;
+use std::str::FromStr;
+
+use anyhow::anyhow;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use sui_json_rpc_types::SuiData;
+use sui_types::base_types::SuiAddress;
+use sui_types::sui_serde::{Base64, Encoding};
+use sui_types::sui_system_state::{SuiSystemState, Validator};
+use sui_types::SUI_SYSTEM_STATE_OBJECT_ID;
+
+use crate::error::SuiRpcResult;
+use crate::ReadApi;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SystemStateSummary {
+    pub epoch: u64,
+    pub reference_gas_price: u64,
+    /// Total supply of SUI.
+    pub total_supply: u64,
+    pub storage_fund: u64,
+    /// Stake of the validators, their own and delegated.
+    pub total_stake: u64,
+    /// Amount of the next stake subsidy, `None` in protocol versions without subsidies.
+    pub stake_subsidy: Option<u64>,
+    /// The numeric system parameters, by name, e.g. `min_validator_stake`.
+    pub parameters: BTreeMap<String, u64>,
+    pub active_validators: Vec<SystemValidator>,
+    /// Number of validators joining at the next epoch.
+    pub pending_validators: usize,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SystemValidator {
+    pub sui_address: SuiAddress,
+    pub name: String,
+    /// The validator's own stake.
+    pub stake: u64,
+    /// SUI held by the validator's delegation staking pool, including rewards.
+    pub delegated_stake: u64,
+    pub gas_price: u64,
+}
+
+impl SystemStateSummary {
+    pub fn new(state: &SuiSystemState) -> Self {
+        let parameters = &state.parameters;
+        Self {
+            epoch: state.epoch,
+            reference_gas_price: state.reference_gas_price,
+            total_supply: state.treasury_cap.value,
+            storage_fund: state.storage_fund.value(),
+            total_stake: state.validators.validator_stake + state.validators.delegation_stake,
+            stake_subsidy: None,
+            parameters: BTreeMap::from([
+                (
+                    "min_validator_stake".to_string(),
+                    parameters.min_validator_stake,
+                ),
+                (
+                    "max_validator_candidate_count".to_string(),
+                    parameters.max_validator_candidate_count,
+                ),
+                (
+                    "storage_gas_price".to_string(),
+                    parameters.storage_gas_price,
+                ),
+            ]),
+            active_validators: state
+                .validators
+                .active_validators
+                .iter()
+                .map(SystemValidator::new)
+                .collect(),
+            pending_validators: state.validators.pending_validators.len(),
+        }
+    }
+
+    /// Read the summary from the JSON fields of the system state object, whatever its
+    /// layout.
+    pub fn from_fields(fields: &Value) -> SuiRpcResult<Self> {
+        let active_validators = field(fields, &["validators.active_validators"])
+            .and_then(Value::as_array)
+            .ok_or_else(|| anyhow!("System state has no active validators."))?
+            .iter()
+            .map(SystemValidator::from_fields)
+            .collect::<SuiRpcResult<Vec<_>>>()?;
+        let total_stake = match (
+            u64_field(fields, &["validators.validator_stake"]),
+            u64_field(fields, &["validators.delegation_stake"]),
+        ) {
+            (Some(validator_stake), delegation_stake) => {
+                validator_stake + delegation_stake.unwrap_or_default()
+            }
+            (None, _) => u64_field(fields, &["validators.total_stake"]).unwrap_or_else(|| {
+                active_validators
+                    .iter()
+                    .map(|validator| validator.stake + validator.delegated_stake)
+                    .sum()
+            }),
+        };
+        let parameters = field(fields, &["parameters"])
+            .and_then(Value::as_object)
+            .map(|parameters| {
+                parameters
+                    .iter()
+                    .filter_map(|(name, value)| Some((name.clone(), as_u64(value)?)))
+                    .collect()
+            })
+            .unwrap_or_default();
+        Ok(Self {
+            epoch: required_u64(fields, &["epoch"])?,
+            reference_gas_price: required_u64(fields, &["reference_gas_price"])?,
+            total_supply: required_u64(
+                fields,
+                &["treasury_cap.value", "treasury_cap.total_supply.value"],
+            )?,
+            storage_fund: storage_fund(fields)?,
+            total_stake,
+            stake_subsidy: u64_field(
+                fields,
+                &[
+                    "stake_subsidy.current_epoch_amount",
+                    "stake_subsidy.current_distribution_amount",
+                ],
+            ),
+            parameters,
+            pending_validators: field(fields, &["validators.pending_validators"])
+                .and_then(Value::as_array)
+                .map_or(0, Vec::len),
+            active_validators,
+        })
+    }
+
+    pub fn validator(&self, address: &SuiAddress) -> Option<&SystemValidator> {
+        self.active_validators
+            .iter()
+            .find(|validator| validator.sui_address == *address)
+    }
+}
+
+impl SystemValidator {
+    fn new(validator: &Validator) -> Self {
+        Self {
+            sui_address: validator.metadata.sui_address.into(),
+            name: String::from_utf8_lossy(&validator.metadata.name).into_owned(),
+            stake: validator.stake_amount,
+            delegated_stake: validator.delegation_staking_pool.sui_balance,
+            gas_price: validator.gas_price,
+        }
+    }
+
+    fn from_fields(fields: &Value) -> anyhow::Result<Self> {
+        let sui_address = field(fields, &["metadata.sui_address"])
+            .and_then(Value::as_str)
+            .ok_or_else(|| anyhow!("Validator has no address."))?;
+        // Names are byte vectors, encoded in Base64, or strings in later versions.
+        let name = match field(fields, &["metadata.name"]).and_then(Value::as_str) {
+            Some(name) => Base64::decode(name)
+                .ok()
+                .and_then(|bytes| String::from_utf8(bytes).ok())
+                .unwrap_or_else(|| name.to_string()),
+            None => String::new(),
+        };
+        Ok(Self {
+            sui_address: SuiAddress::from_str(sui_address)?,
+            name,
+            stake: u64_field(fields, &["stake_amount"]).unwrap_or_default(),
+            delegated_stake: u64_field(
+                fields,
+                &[
+                    "delegation_staking_pool.sui_balance",
+                    "staking_pool.sui_balance",
+                ],
+            )
+            .unwrap_or_default(),
+            gas_price: required_u64(fields, &["gas_price", "next_epoch_gas_price"])?,
+        })
+    }
+}
+
+/// The storage fund, a balance, or the sum of the balances of a storage fund struct.
+fn storage_fund(fields: &Value) -> anyhow::Result<u64> {
+    match field(fields, &["storage_fund"]) {
+        Some(Value::Object(balances)) => Ok(balances.values().filter_map(as_u64).sum()),
+        Some(value) => as_u64(value).ok_or_else(|| anyhow!("Invalid storage fund [{value}].")),
+        None => Err(anyhow!("System state has no storage fund.")),
+    }
+}
+
+/// The value at the first of `paths` found, each a dot separated path of field names.
+fn field<'a>(fields: &'a Value, paths: &[&str]) -> Option<&'a Value> {
+    paths.iter().find_map(|path| {
+        path.split('.')
+            .try_fold(fields, |value, name| value.get(name))
+    })
+}
+
+fn u64_field(fields: &Value, paths: &[&str]) -> Option<u64> {
+    field(fields, paths).and_then(as_u64)
+}
+
+fn required_u64(fields: &Value, paths: &[&str]) -> anyhow::Result<u64> {
+    u64_field(fields, paths).ok_or_else(|| anyhow!("System state has no number at {paths:?}."))
+}
+
+fn as_u64(value: &Value) -> Option<u64> {
+    match value {
+        Value::Number(number) => number.as_u64(),
+        Value::String(number) => number.parse().ok(),
+        _ => None,
+    }
+}
+
+impl ReadApi {
+    /// The summary of the system state, whatever the protocol version of the node.
+    pub async fn get_system_state_summary(&self) -> SuiRpcResult<SystemStateSummary> {
+        if let Ok(state) = self.get_sui_system_state().await {
+            return Ok(SystemStateSummary::new(&state));
+        }
+        // The layout changed in a protocol upgrade, read the fields by name.
+        let object = self
+            .get_parsed_object(SUI_SYSTEM_STATE_OBJECT_ID)
+            .await?
+            .into_object()?;
+        let move_object = object
+            .data
+            .try_as_move()
+            .ok_or_else(|| anyhow!("The system state is not a Move object."))?;
+        SystemStateSummary::from_fields(&move_object.fields.clone().to_json_value()?)
+    }
+}
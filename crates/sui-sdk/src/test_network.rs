@@ -0,0 +1,133 @@
+// Copyright (c) 2022, Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! An in-process local Sui network for integration tests, available with the `test-support`
+//! feature.
+
+use std::net::SocketAddr;
+use std::num::NonZeroUsize;
+
+use sui_config::genesis_config::GenesisConfig;
+use sui_config::utils::get_available_port;
+use sui_swarm::memory::Swarm;
+use sui_types::base_types::SuiAddress;
+use sui_types::crypto::KeypairTraits;
+use sui_types::crypto::SuiKeyPair::Ed25519SuiKeyPair;
+
+use crate::crypto::{KeystoreType, SuiKeystore};
+use crate::error::SuiRpcResult;
+use crate::SuiClient;
+
+pub struct LocalNetworkBuilder {
+    committee_size: NonZeroUsize,
+    genesis_config: Option<GenesisConfig>,
+}
+
+impl Default for LocalNetworkBuilder {
+    fn default() -> Self {
+        Self {
+            committee_size: NonZeroUsize::new(4).unwrap(),
+            genesis_config: None,
+        }
+    }
+}
+
+impl LocalNetworkBuilder {
+    /// Set the number of validators. Defaults to 4.
+    pub fn committee_size(mut self, committee_size: NonZeroUsize) -> Self {
+        self.committee_size = committee_size;
+        self
+    }
+
+    /// Set the accounts and objects created at genesis. Defaults to
+    /// [GenesisConfig::for_local_testing].
+    pub fn genesis_config(mut self, genesis_config: GenesisConfig) -> Self {
+        self.genesis_config = Some(genesis_config);
+        self
+    }
+
+    /// Start the validators and a full node serving JSON-RPC on free local ports, and wait
+    /// until they are up.
+    pub async fn build(self) -> SuiRpcResult<LocalNetwork> {
+        let rpc_address = local_address();
+        let ws_address = local_address();
+        let mut builder = Swarm::builder()
+            .committee_size(self.committee_size)
+            .with_fullnode_count(1)
+            .with_fullnode_rpc_addr(rpc_address)
+            .with_websocket_rpc_addr(ws_address);
+        if let Some(genesis_config) = self.genesis_config {
+            builder = builder.initial_accounts_config(genesis_config);
+        }
+        let mut swarm = builder.build();
+        swarm.launch().await?;
+
+        let mut keystore = KeystoreType::InMem(0).init()?;
+        let mut accounts = vec![];
+        for key in &swarm.config().account_keys {
+            accounts.push(SuiAddress::from(key.public()));
+            keystore.add_key(Ed25519SuiKeyPair(key.copy()))?;
+        }
+        Ok(LocalNetwork {
+            swarm,
+            rpc_url: format!("http://{rpc_address}"),
+            ws_url: format!("ws://{ws_address}"),
+            keystore,
+            accounts,
+        })
+    }
+}
+
+/// A running local network. The validators and the full node are stopped, and their data
+/// directory deleted, when it is dropped.
+pub struct LocalNetwork {
+    swarm: Swarm,
+    rpc_url: String,
+    ws_url: String,
+    keystore: SuiKeystore,
+    accounts: Vec<SuiAddress>,
+}
+
+impl LocalNetwork {
+    pub fn builder() -> LocalNetworkBuilder {
+        LocalNetworkBuilder::default()
+    }
+
+    /// Start a network with the default configuration.
+    pub async fn start() -> SuiRpcResult<Self> {
+        Self::builder().build().await
+    }
+
+    /// The full node's JSON-RPC URL.
+    pub fn rpc_url(&self) -> &str {
+        &self.rpc_url
+    }
+
+    /// The full node's websocket URL, for subscriptions.
+    pub fn ws_url(&self) -> &str {
+        &self.ws_url
+    }
+
+    /// The accounts funded at genesis, in the order of the genesis config.
+    pub fn accounts(&self) -> &[SuiAddress] {
+        &self.accounts
+    }
+
+    /// An in-memory keystore holding the keys of [LocalNetwork::accounts].
+    pub fn keystore(&self) -> &SuiKeystore {
+        &self.keystore
+    }
+
+    pub fn swarm(&self) -> &Swarm {
+        &self.swarm
+    }
+
+    /// Connect a client to the full node.
+    pub async fn client(&self) -> SuiRpcResult<SuiClient> {
+        Ok(SuiClient::new_rpc_client(&self.rpc_url, Some(&self.ws_url)).await?)
+    }
+}
+
+fn local_address() -> SocketAddr {
+    SocketAddr::from(([127, 0, 0, 1], get_available_port()))
+}
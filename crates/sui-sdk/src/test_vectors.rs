@@ -0,0 +1,190 @@
+// Copyright (c) 2022, Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Deterministic test vectors, for wallets in other languages checking their compatibility
+//! with this SDK.
+//!
+//! [vectors] are the keys derived from known mnemonics for each signature scheme and
+//! derivation path, and known transaction data with its signature and digest. They are
+//! published as `data/test_vectors.json` for other test suites to read. A wallet exports
+//! the same cases as JSON, with its own results, and checks them with [check], which fails
+//! with every case this SDK disagrees with. Mnemonics have no passphrase.
+
+use std::str::FromStr;
+
+use anyhow::{anyhow, bail};
+use bip32::DerivationPath;
+use bip39::{Language, Mnemonic, Seed};
+use serde::{Deserialize, Serialize};
+use signature::Signer;
+
+use sui_types::base_types::SuiAddress;
+use sui_types::crypto::{
+    derive_key_pair_from_path, SignableBytes, Signature, SignatureScheme, SuiKeyPair,
+};
+use sui_types::messages::TransactionData;
+use sui_types::sui_serde::{Base64, Encoding};
+
+use crate::digest::transaction_digest;
+use crate::error::{Error, SuiRpcResult};
+use crate::js_parity::{
+    decode_transaction_data, encode_address, encode_transaction_data, SignaturePubkeyPair,
+};
+
+const VECTORS: &str = include_str!("../data/test_vectors.json");
+
+/// The key derived from `mnemonic` at `path`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct KeyVector {
+    pub mnemonic: String,
+    /// `ED25519` or `Secp256k1`.
+    pub scheme: String,
+    pub path: String,
+    /// The Base64 encoded public key, without flag.
+    pub pub_key: String,
+    /// The address, as unprefixed lower case hex.
+    pub address: String,
+}
+
+/// The transaction data `tx_bytes` signed with the key derived from `mnemonic` at `path`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TransactionVector {
+    pub mnemonic: String,
+    pub scheme: String,
+    pub path: String,
+    /// The Base64 encoded signable bytes of the transaction data.
+    pub tx_bytes: String,
+    pub signature: SignaturePubkeyPair,
+    /// The Base64 encoded transaction digest.
+    pub digest: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub struct TestVectors {
+    pub keys: Vec<KeyVector>,
+    pub transactions: Vec<TransactionVector>,
+}
+
+/// The test vectors of this SDK.
+pub fn vectors() -> TestVectors {
+    serde_json::from_str(VECTORS).expect("The test vectors should be valid JSON.")
+}
+
+fn parse_scheme(scheme: &str) -> anyhow::Result<SignatureScheme> {
+    match scheme {
+        "ED25519" => Ok(SignatureScheme::ED25519),
+        "Secp256k1" => Ok(SignatureScheme::Secp256k1),
+        scheme => {
+            bail!("Unsupported signature scheme [{scheme}].")
+        }
+    }
+}
+
+fn derive_key(mnemonic: &str, scheme: &str, path: &str) -> anyhow::Result<SuiKeyPair> {
+    let mnemonic = Mnemonic::from_phrase(mnemonic, Language::English)
+        .map_err(|e| anyhow!("Invalid mnemonic: {e:?}"))?;
+    let path = DerivationPath::from_str(path)?;
+    let (_, keypair) = derive_key_pair_from_path(
+        Seed::new(&mnemonic, "").as_bytes(),
+        Some(path),
+        &parse_scheme(scheme)?,
+    )?;
+    Ok(keypair)
+}
+
+/// The key vector of `mnemonic` for `scheme` at `path`.
+pub fn key_vector(mnemonic: &str, scheme: &str, path: &str) -> SuiRpcResult<KeyVector> {
+    let public_key = derive_key(mnemonic, scheme, path)?.public();
+    Ok(KeyVector {
+        mnemonic: mnemonic.to_string(),
+        scheme: scheme.to_string(),
+        path: path.to_string(),
+        pub_key: Base64::encode(&public_key),
+        address: encode_address(&SuiAddress::from(&public_key)),
+    })
+}
+
+/// The transaction vector of `data` signed with the key of `mnemonic` for `scheme` at
+/// `path`.
+pub fn transaction_vector(
+    mnemonic: &str,
+    scheme: &str,
+    path: &str,
+    data: &TransactionData,
+) -> SuiRpcResult<TransactionVector> {
+    let tx_bytes = encode_transaction_data(data);
+    let signature: Signature = derive_key(mnemonic, scheme, path)?.sign(&data.to_bytes());
+    Ok(TransactionVector {
+        mnemonic: mnemonic.to_string(),
+        scheme: scheme.to_string(),
+        path: path.to_string(),
+        tx_bytes,
+        signature: SignaturePubkeyPair::from_signature(&signature),
+        digest: Base64::encode(transaction_digest(data, &signature)),
+    })
+}
+
+pub fn check_key(vector: &KeyVector) -> SuiRpcResult<()> {
+    let expected = key_vector(&vector.mnemonic, &vector.scheme, &vector.path)?;
+    if *vector != expected {
+        return Err(Error::Other(anyhow!(
+            "Expected {} key at [{}] with public key [{}] and address [{}], got [{}] and [{}].",
+            vector.scheme,
+            vector.path,
+            expected.pub_key,
+            expected.address,
+            vector.pub_key,
+            vector.address
+        )));
+    }
+    Ok(())
+}
+
+pub fn check_transaction(vector: &TransactionVector) -> SuiRpcResult<()> {
+    let data = decode_transaction_data(&vector.tx_bytes)?;
+    let expected = transaction_vector(&vector.mnemonic, &vector.scheme, &vector.path, &data)?;
+    if vector.tx_bytes != expected.tx_bytes {
+        return Err(Error::Other(anyhow!(
+            "Transaction data [{}] re-encodes as [{}].",
+            vector.tx_bytes,
+            expected.tx_bytes
+        )));
+    }
+    if vector.signature != expected.signature {
+        return Err(Error::Other(anyhow!(
+            "Expected signature {:?} of [{}], got {:?}.",
+            expected.signature,
+            vector.tx_bytes,
+            vector.signature
+        )));
+    }
+    if vector.digest != expected.digest {
+        return Err(Error::Other(anyhow!(
+            "Expected digest [{}] of [{}], got [{}].",
+            expected.digest,
+            vector.tx_bytes,
+            vector.digest
+        )));
+    }
+    Ok(())
+}
+
+/// Check all the cases of `vectors`, failing with the errors of those not matching this SDK.
+pub fn check(vectors: &TestVectors) -> SuiRpcResult<()> {
+    let errors = vectors
+        .keys
+        .iter()
+        .map(check_key)
+        .chain(vectors.transactions.iter().map(check_transaction))
+        .filter_map(Result::err)
+        .map(|e| e.to_string())
+        .collect::<Vec<_>>();
+    if !errors.is_empty() {
+        return Err(Error::Other(anyhow!(
+            "{} test vectors do not match:\n{}",
+            errors.len(),
+            errors.join("\n")
+        )));
+    }
+    Ok(())
+}
@@ -0,0 +1,256 @@
+// Copyright (c) 2022, Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Step-by-step traces of transactions, to find where a failing transaction aborts.
+//!
+//! Nodes in this tree have no dev-inspect endpoint and transactions are single commands or
+//! batches of them, so a [Tracer] dry-runs every prefix of a batch and reports the effects
+//! each command adds to the previous ones, stopping at the first failing command. Entry
+//! functions cannot return values, so traces hold effects rather than return values. Move
+//! aborts report the module only: the abort is attributed to the traced call of that
+//! module, or to a dependency of the failing command otherwise.
+//!
+//! Nodes report the gas of a transaction as a whole, so the gas of each command is likewise
+//! the difference between dry runs, which makes a trace the gas profile of a transaction:
+//! [Trace::gas_by_command] sums it per Move function. The gas coin is mutated by every
+//! transaction, so its storage cost and rebate are attributed to the first command.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt::{Display, Formatter};
+
+use sui_json_rpc_types::SuiTransactionEffects;
+use sui_types::base_types::ObjectID;
+use sui_types::gas::GasCostSummary;
+use sui_types::messages::{SingleTransactionKind, Transaction, TransactionData, TransactionKind};
+
+use crate::crypto::SuiKeystore;
+use crate::error::{ExecutionError, SuiRpcResult};
+use crate::SuiClient;
+
+/// The effects of one command of a transaction.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TraceStep {
+    pub index: usize,
+    /// The command, e.g. `TransferObject` or `0x2::coin::split`.
+    pub command: String,
+    pub created: Vec<ObjectID>,
+    pub mutated: Vec<ObjectID>,
+    pub deleted: Vec<ObjectID>,
+    /// Computation cost of the command.
+    pub computation_cost: u64,
+    /// Storage cost of the objects the command writes.
+    pub storage_cost: u64,
+    /// Storage rebate of the objects the command writes or deletes.
+    pub storage_rebate: u64,
+    pub error: Option<ExecutionError>,
+}
+
+impl TraceStep {
+    pub fn gas_cost(&self) -> GasCostSummary {
+        GasCostSummary {
+            computation_cost: self.computation_cost,
+            storage_cost: self.storage_cost,
+            storage_rebate: self.storage_rebate,
+        }
+    }
+}
+
+/// Where a traced transaction aborted.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AbortLocation {
+    /// Index of the failing command.
+    pub command_index: usize,
+    /// The aborting module, e.g. `0x2::coin`.
+    pub module: String,
+    /// The function called by the failing command, if the abort is in its module.
+    pub function: Option<String>,
+    pub code: u64,
+}
+
+impl Display for AbortLocation {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match &self.function {
+            Some(function) => write!(f, "{}::{function}", self.module)?,
+            None => write!(f, "{} (dependency)", self.module)?,
+        }
+        write!(
+            f,
+            " aborted with code {} in command {}",
+            self.code, self.command_index
+        )
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Trace {
+    pub steps: Vec<TraceStep>,
+    pub abort: Option<AbortLocation>,
+}
+
+impl Trace {
+    pub fn succeeded(&self) -> bool {
+        self.steps.iter().all(|step| step.error.is_none())
+    }
+
+    /// The gas of the traced commands, summed per command, e.g. per Move function called.
+    pub fn gas_by_command(&self) -> BTreeMap<String, GasCostSummary> {
+        let mut gas = BTreeMap::<String, GasCostSummary>::new();
+        for step in &self.steps {
+            match gas.get_mut(&step.command) {
+                Some(cost) => {
+                    cost.computation_cost += step.computation_cost;
+                    cost.storage_cost += step.storage_cost;
+                    cost.storage_rebate += step.storage_rebate;
+                }
+                None => {
+                    gas.insert(step.command.clone(), step.gas_cost());
+                }
+            }
+        }
+        gas
+    }
+
+    /// The step with the highest computation cost.
+    pub fn most_expensive(&self) -> Option<&TraceStep> {
+        self.steps.iter().max_by_key(|step| step.computation_cost)
+    }
+}
+
+pub struct Tracer<'a> {
+    client: &'a SuiClient,
+    keystore: &'a SuiKeystore,
+}
+
+impl<'a> Tracer<'a> {
+    /// A tracer signing the dry-run transactions with the keys of `keystore`, which must
+    /// hold the key of the sender of traced transactions.
+    pub fn new(client: &'a SuiClient, keystore: &'a SuiKeystore) -> Self {
+        Self { client, keystore }
+    }
+
+    pub async fn trace(&self, data: &TransactionData) -> SuiRpcResult<Trace> {
+        let commands = data.kind.single_transactions().cloned().collect::<Vec<_>>();
+        let mut trace = Trace::default();
+        let mut previous: Option<SuiTransactionEffects> = None;
+        for index in 0..commands.len() {
+            let kind = match &data.kind {
+                TransactionKind::Single(kind) => TransactionKind::Single(kind.clone()),
+                TransactionKind::Batch(_) => TransactionKind::Batch(commands[..=index].to_vec()),
+            };
+            let effects = self.dry_run(data, kind).await?;
+            let step = step(index, &commands[index], &effects, previous.as_ref());
+            if let Some(ExecutionError::MoveAbort { location, code }) = &step.error {
+                trace.abort = Some(abort_location(index, &commands[index], location, *code));
+            }
+            let failed = step.error.is_some();
+            trace.steps.push(step);
+            if failed {
+                break;
+            }
+            previous = Some(effects);
+        }
+        Ok(trace)
+    }
+
+    async fn dry_run(
+        &self,
+        data: &TransactionData,
+        kind: TransactionKind,
+    ) -> anyhow::Result<SuiTransactionEffects> {
+        let data = TransactionData::new_with_gas_price(
+            kind,
+            data.signer(),
+            data.gas(),
+            data.gas_budget,
+            data.gas_price,
+        );
+        let signature = self.keystore.sign(&data.signer(), &data.to_bytes())?;
+        Ok(self
+            .client
+            .full_node_api()
+            .dry_run_transaction(Transaction::new(data, signature))
+            .await?)
+    }
+}
+
+/// The effects of the command at `index`: those of the dry run of the commands up to it,
+/// minus those of the commands before it.
+fn step(
+    index: usize,
+    command: &SingleTransactionKind,
+    effects: &SuiTransactionEffects,
+    previous: Option<&SuiTransactionEffects>,
+) -> TraceStep {
+    let new = |f: fn(&SuiTransactionEffects) -> Vec<ObjectID>| {
+        let before = previous.map(f).unwrap_or_default();
+        let before = before.into_iter().collect::<BTreeSet<_>>();
+        f(effects)
+            .into_iter()
+            .filter(|id| !before.contains(id))
+            .collect()
+    };
+    let added =
+        |f: fn(&SuiTransactionEffects) -> u64| f(effects).saturating_sub(previous.map_or(0, f));
+    TraceStep {
+        index,
+        command: command_name(command),
+        created: new(|e| e.created.iter().map(|o| o.reference.object_id).collect()),
+        mutated: new(|e| e.mutated.iter().map(|o| o.reference.object_id).collect()),
+        deleted: new(|e| e.deleted.iter().map(|o| o.object_id).collect()),
+        computation_cost: added(|e| e.gas_used.computation_cost),
+        storage_cost: added(|e| e.gas_used.storage_cost),
+        storage_rebate: added(|e| e.gas_used.storage_rebate),
+        error: ExecutionError::from_status(&effects.status),
+    }
+}
+
+fn command_name(command: &SingleTransactionKind) -> String {
+    match command {
+        SingleTransactionKind::TransferObject(_) => "TransferObject".to_string(),
+        SingleTransactionKind::Publish(_) => "Publish".to_string(),
+        SingleTransactionKind::Call(call) => {
+            format!("{}::{}::{}", call.package.0, call.module, call.function)
+        }
+        SingleTransactionKind::TransferSui(_) => "TransferSui".to_string(),
+        SingleTransactionKind::Pay(_) => "Pay".to_string(),
+        SingleTransactionKind::ChangeEpoch(_) => "ChangeEpoch".to_string(),
+    }
+}
+
+fn abort_location(
+    command_index: usize,
+    command: &SingleTransactionKind,
+    module: &str,
+    code: u64,
+) -> AbortLocation {
+    let function = match command {
+        SingleTransactionKind::Call(call)
+            if same_module(module, call.package.0, call.module.as_str()) =>
+        {
+            Some(call.function.to_string())
+        }
+        _ => None,
+    };
+    AbortLocation {
+        command_index,
+        module: module.to_string(),
+        function,
+        code,
+    }
+}
+
+/// Whether `location`, e.g. `0x2::coin`, is the module `module` of `package`.
+fn same_module(location: &str, package: ObjectID, module: &str) -> bool {
+    let normalize = |address: &str| {
+        address
+            .trim_start_matches("0x")
+            .trim_start_matches('0')
+            .to_lowercase()
+    };
+    match location.rsplit_once("::") {
+        Some((address, name)) => {
+            name == module && normalize(address) == normalize(&package.to_string())
+        }
+        None => false,
+    }
+}
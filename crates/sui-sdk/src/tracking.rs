@@ -0,0 +1,166 @@
+// Copyright (c) 2022, Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Tracking of submitted transactions until they are final.
+//!
+//! [SuiClient::track] streams the [TransactionStatus] of a transaction: submitted, then
+//! finalized with its effects. It polls the node first, as transactions executed through the
+//! quorum driver are final on return, and switches to a transaction subscription when the
+//! endpoint has one, polling again after subscribing so that a transaction finalized in
+//! between is not missed. Without a subscription, or if it ends, the node is polled with an
+//! exponential backoff. Nodes of this protocol version do not tell which checkpoint a
+//! transaction is in, so tracking ends at finality.
+//!
+//! The subscription receives every transaction of the node, there is no filter by digest in
+//! this protocol version, so set [TrackOptions::with_subscription] to `false` on busy nodes.
+
+use std::time::Duration;
+
+use anyhow::anyhow;
+use futures::{stream, Stream, StreamExt};
+
+use sui_json_rpc_types::{SuiTransactionFilter, SuiTransactionResponse};
+use sui_types::base_types::TransactionDigest;
+
+use crate::error::{Error, RpcErrorKind, SuiRpcResult};
+use crate::runtime::timeout;
+use crate::SuiClient;
+
+const SUBSCRIBE_TRANSACTION: &str = "sui_subscribeTransaction";
+
+#[derive(Debug)]
+pub enum TransactionStatus {
+    /// Submitted, not known to the node yet.
+    Submitted,
+    /// Executed by a quorum of validators. Final.
+    Finalized(Box<SuiTransactionResponse>),
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct TrackOptions {
+    poll_interval: Duration,
+    max_poll_interval: Duration,
+    timeout: Duration,
+    subscription: bool,
+}
+
+impl Default for TrackOptions {
+    fn default() -> Self {
+        Self {
+            poll_interval: Duration::from_millis(200),
+            max_poll_interval: Duration::from_secs(5),
+            timeout: Duration::from_secs(60),
+            subscription: true,
+        }
+    }
+}
+
+impl TrackOptions {
+    /// Delay before the first poll after the initial one, doubled after each poll up to
+    /// `max`. Defaults to 200 milliseconds, up to 5 seconds.
+    pub fn with_poll_interval(mut self, initial: Duration, max: Duration) -> Self {
+        self.poll_interval = initial;
+        self.max_poll_interval = max.max(initial);
+        self
+    }
+
+    /// Fail if the transaction is not final after `timeout`. Defaults to a minute.
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Whether to wait on a transaction subscription when the endpoint has one. Defaults
+    /// to `true`.
+    pub fn with_subscription(mut self, subscription: bool) -> Self {
+        self.subscription = subscription;
+        self
+    }
+}
+
+impl SuiClient {
+    /// Stream the status of the transaction `digest` until it is final, with the default
+    /// [TrackOptions].
+    pub fn track(
+        &self,
+        digest: TransactionDigest,
+    ) -> impl Stream<Item = SuiRpcResult<TransactionStatus>> + '_ {
+        self.track_with(digest, TrackOptions::default())
+    }
+
+    pub fn track_with(
+        &self,
+        digest: TransactionDigest,
+        options: TrackOptions,
+    ) -> impl Stream<Item = SuiRpcResult<TransactionStatus>> + '_ {
+        stream::once(async { Ok(TransactionStatus::Submitted) }).chain(stream::once(async move {
+            let finalized = timeout(
+                self.runtime(),
+                options.timeout,
+                self.wait_for_finality(digest, options),
+            )
+            .await
+            .unwrap_or_else(|| {
+                Err(Error::Other(anyhow!(
+                    "Transaction [{digest}] is not final after {:?}.",
+                    options.timeout
+                )))
+            })?;
+            Ok::<_, Error>(TransactionStatus::Finalized(Box::new(finalized)))
+        }))
+    }
+
+    async fn wait_for_finality(
+        &self,
+        digest: TransactionDigest,
+        options: TrackOptions,
+    ) -> SuiRpcResult<SuiTransactionResponse> {
+        if let Some(response) = self.poll_transaction(digest).await? {
+            return Ok(response);
+        }
+        let subscription = if options.subscription
+            && self
+                .available_subscriptions()
+                .iter()
+                .any(|method| method == SUBSCRIBE_TRANSACTION)
+        {
+            self.event_api()
+                .subscribe_transaction(SuiTransactionFilter::Any)
+                .await
+                .ok()
+        } else {
+            None
+        };
+        if let Some(transactions) = subscription {
+            if let Some(response) = self.poll_transaction(digest).await? {
+                return Ok(response);
+            }
+            let mut transactions = Box::pin(transactions);
+            while let Some(Ok(response)) = transactions.next().await {
+                if response.certificate.transaction_digest == digest {
+                    return Ok(response);
+                }
+            }
+            // The subscription failed or ended, poll instead.
+        }
+        let mut interval = options.poll_interval;
+        loop {
+            self.runtime().sleep(interval).await;
+            if let Some(response) = self.poll_transaction(digest).await? {
+                return Ok(response);
+            }
+            interval = (interval * 2).min(options.max_poll_interval);
+        }
+    }
+
+    async fn poll_transaction(
+        &self,
+        digest: TransactionDigest,
+    ) -> SuiRpcResult<Option<SuiTransactionResponse>> {
+        match self.read_api().get_transaction(digest).await {
+            Ok(response) => Ok(Some(response)),
+            Err(e) if e.rpc_kind() == Some(RpcErrorKind::TransactionNotFound) => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+}
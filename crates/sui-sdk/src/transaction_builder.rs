@@ -19,14 +19,19 @@ use sui_types::error::SuiError;
 use sui_types::gas_coin::GasCoin;
 use sui_types::messages::{
     CallArg, InputObjectKind, MoveCall, ObjectArg, SingleTransactionKind, TransactionData,
-    TransactionKind, TransferObject,
+    TransactionKind,
 };
 use sui_types::move_package::MovePackage;
 use sui_types::object::Object;
 use sui_types::{coin, fp_ensure, SUI_FRAMEWORK_OBJECT_ID};
 
+use crate::amount::Amount;
+use crate::error::{Error, SuiRpcResult};
+use crate::offline_builder::{self, ValidatorRegistration};
+use crate::vesting::VestingSchedule;
 use crate::ReadApi;
 
+#[derive(Clone)]
 pub struct TransactionBuilder(pub(crate) Arc<ReadApi>);
 
 impl TransactionBuilder {
@@ -36,7 +41,7 @@ impl TransactionBuilder {
         input_gas: Option<ObjectID>,
         budget: u64,
         input_objects: Vec<ObjectID>,
-    ) -> Result<ObjectRef, anyhow::Error> {
+    ) -> anyhow::Result<ObjectRef> {
         if let Some(gas) = input_gas {
             self.get_object_ref(gas).await
         } else {
@@ -64,16 +69,13 @@ impl TransactionBuilder {
         gas: Option<ObjectID>,
         gas_budget: u64,
         recipient: SuiAddress,
-    ) -> anyhow::Result<TransactionData> {
-        let single_transfer = self.single_transfer_object(object_id, recipient).await?;
+    ) -> SuiRpcResult<TransactionData> {
+        let object = self.get_object_ref(object_id).await?;
         let gas = self
             .select_gas(signer, gas, gas_budget, vec![object_id])
             .await?;
-        Ok(TransactionData::new(
-            TransactionKind::Single(single_transfer),
-            signer,
-            gas,
-            gas_budget,
+        Ok(offline_builder::transfer_object(
+            signer, object, recipient, gas, gas_budget,
         ))
     }
 
@@ -81,11 +83,11 @@ impl TransactionBuilder {
         &self,
         object_id: ObjectID,
         recipient: SuiAddress,
-    ) -> Result<SingleTransactionKind, anyhow::Error> {
-        Ok(SingleTransactionKind::TransferObject(TransferObject {
+    ) -> anyhow::Result<SingleTransactionKind> {
+        Ok(offline_builder::transfer_object_call(
+            self.get_object_ref(object_id).await?,
             recipient,
-            object_ref: self.get_object_ref(object_id).await?,
-        }))
+        ))
     }
 
     pub async fn transfer_sui(
@@ -94,26 +96,28 @@ impl TransactionBuilder {
         sui_object_id: ObjectID,
         gas_budget: u64,
         recipient: SuiAddress,
-        amount: Option<u64>,
-    ) -> anyhow::Result<TransactionData> {
+        amount: Option<Amount>,
+    ) -> SuiRpcResult<TransactionData> {
         let object = self.get_object_ref(sui_object_id).await?;
-        Ok(TransactionData::new_transfer_sui(
-            recipient, signer, amount, object, gas_budget,
+        Ok(offline_builder::transfer_sui(
+            signer, object, recipient, amount, gas_budget,
         ))
     }
 
+    /// Pay `amounts` of the coin type of `input_coins` to `recipients`. Only the base units
+    /// of the amounts are sent, their decimals are not checked against the coin type.
     pub async fn pay(
         &self,
         signer: SuiAddress,
         input_coins: Vec<ObjectID>,
         recipients: Vec<SuiAddress>,
-        amounts: Vec<u64>,
+        amounts: Vec<Amount>,
         gas: Option<ObjectID>,
         gas_budget: u64,
-    ) -> anyhow::Result<TransactionData> {
+    ) -> SuiRpcResult<TransactionData> {
         if let Some(gas) = gas {
             if input_coins.contains(&gas) {
-                return Err(anyhow!("Gas coin is in input coins of Pay transaction, use PaySui transaction instead!"));
+                return Err(Error::Other(anyhow!("Gas coin is in input coins of Pay transaction, use PaySui transaction instead!")));
             }
         }
 
@@ -129,8 +133,9 @@ impl TransactionBuilder {
         let gas = self
             .select_gas(signer, gas, gas_budget, input_coins)
             .await?;
-        let data = TransactionData::new_pay(signer, coins, recipients, amounts, gas, gas_budget);
-        Ok(data)
+        Ok(offline_builder::pay(
+            signer, coins, recipients, amounts, gas, gas_budget,
+        ))
     }
 
     pub async fn move_call(
@@ -143,7 +148,7 @@ impl TransactionBuilder {
         call_args: Vec<SuiJsonValue>,
         gas: Option<ObjectID>,
         gas_budget: u64,
-    ) -> anyhow::Result<TransactionData> {
+    ) -> SuiRpcResult<TransactionData> {
         let single_move_call = self
             .single_move_call(package_object_id, module, function, type_args, call_args)
             .await?;
@@ -208,7 +213,7 @@ impl TransactionBuilder {
         &self,
         id: ObjectID,
         objects: &mut BTreeMap<ObjectID, Object>,
-    ) -> Result<ObjectArg, anyhow::Error> {
+    ) -> anyhow::Result<ObjectArg> {
         let response = self.0.get_object(id).await?;
         let obj: Object = response.into_object()?.try_into()?;
         let obj_ref = obj.compute_object_reference();
@@ -228,7 +233,7 @@ impl TransactionBuilder {
         function: &Identifier,
         type_args: &[TypeTag],
         json_args: Vec<SuiJsonValue>,
-    ) -> Result<Vec<CallArg>, anyhow::Error> {
+    ) -> anyhow::Result<Vec<CallArg>> {
         let package = self.0.get_object(package_id).await?.into_object()?;
         let package = package
             .data
@@ -281,7 +286,7 @@ impl TransactionBuilder {
         compiled_modules: Vec<Vec<u8>>,
         gas: Option<ObjectID>,
         gas_budget: u64,
-    ) -> anyhow::Result<TransactionData> {
+    ) -> SuiRpcResult<TransactionData> {
         let gas = self.select_gas(sender, gas, gas_budget, vec![]).await?;
         Ok(TransactionData::new_module(
             sender,
@@ -295,31 +300,15 @@ impl TransactionBuilder {
         &self,
         signer: SuiAddress,
         coin_object_id: ObjectID,
-        split_amounts: Vec<u64>,
+        split_amounts: Vec<Amount>,
         gas: Option<ObjectID>,
         gas_budget: u64,
-    ) -> anyhow::Result<TransactionData> {
-        let coin = self.0.get_object(coin_object_id).await?.into_object()?;
-        let coin_object_ref = coin.reference.to_object_ref();
-        let coin: Object = coin.try_into()?;
-        let type_args = vec![coin.get_move_template_type()?];
-        let gas = self
-            .select_gas(signer, gas, gas_budget, vec![coin_object_id])
-            .await?;
-
-        Ok(TransactionData::new_move_call(
-            signer,
-            self.get_object_ref(SUI_FRAMEWORK_OBJECT_ID).await?,
-            coin::COIN_MODULE_NAME.to_owned(),
-            coin::COIN_SPLIT_VEC_FUNC_NAME.to_owned(),
-            type_args,
-            gas,
-            vec![
-                CallArg::Object(ObjectArg::ImmOrOwnedObject(coin_object_ref)),
-                CallArg::Pure(bcs::to_bytes(&split_amounts)?),
-            ],
-            gas_budget,
-        ))
+    ) -> SuiRpcResult<TransactionData> {
+        let (coin, coin_type) = self.get_coin(coin_object_id).await?;
+        let framework = self.get_object_ref(SUI_FRAMEWORK_OBJECT_ID).await?;
+        let call = offline_builder::split_coin_call(framework, coin, coin_type, &split_amounts)?;
+        self.move_calls_transaction(signer, vec![call], gas, gas_budget)
+            .await
     }
 
     pub async fn split_coin_equal(
@@ -329,28 +318,12 @@ impl TransactionBuilder {
         split_count: u64,
         gas: Option<ObjectID>,
         gas_budget: u64,
-    ) -> anyhow::Result<TransactionData> {
-        let coin = self.0.get_object(coin_object_id).await?.into_object()?;
-        let coin_object_ref = coin.reference.to_object_ref();
-        let coin: Object = coin.try_into()?;
-        let type_args = vec![coin.get_move_template_type()?];
-        let gas = self
-            .select_gas(signer, gas, gas_budget, vec![coin_object_id])
-            .await?;
-
-        Ok(TransactionData::new_move_call(
-            signer,
-            self.get_object_ref(SUI_FRAMEWORK_OBJECT_ID).await?,
-            coin::COIN_MODULE_NAME.to_owned(),
-            coin::COIN_SPLIT_N_FUNC_NAME.to_owned(),
-            type_args,
-            gas,
-            vec![
-                CallArg::Object(ObjectArg::ImmOrOwnedObject(coin_object_ref)),
-                CallArg::Pure(bcs::to_bytes(&split_count)?),
-            ],
-            gas_budget,
-        ))
+    ) -> SuiRpcResult<TransactionData> {
+        let (coin, coin_type) = self.get_coin(coin_object_id).await?;
+        let framework = self.get_object_ref(SUI_FRAMEWORK_OBJECT_ID).await?;
+        let call = offline_builder::split_coin_equal_call(framework, coin, coin_type, split_count)?;
+        self.move_calls_transaction(signer, vec![call], gas, gas_budget)
+            .await
     }
 
     pub async fn merge_coins(
@@ -360,29 +333,256 @@ impl TransactionBuilder {
         coin_to_merge: ObjectID,
         gas: Option<ObjectID>,
         gas_budget: u64,
-    ) -> anyhow::Result<TransactionData> {
-        let coin = self.0.get_object(primary_coin).await?.into_object()?;
-        let primary_coin_ref = coin.reference.to_object_ref();
-        let coin_to_merge_ref = self.get_object_ref(coin_to_merge).await?;
-        let coin: Object = coin.try_into()?;
-        let type_args = vec![coin.get_move_template_type()?];
-        let gas = self
-            .select_gas(signer, gas, gas_budget, vec![primary_coin, coin_to_merge])
-            .await?;
+    ) -> SuiRpcResult<TransactionData> {
+        let (primary_coin, coin_type) = self.get_coin(primary_coin).await?;
+        let coin_to_merge = self.get_object_ref(coin_to_merge).await?;
+        let framework = self.get_object_ref(SUI_FRAMEWORK_OBJECT_ID).await?;
+        let call =
+            offline_builder::merge_coins_call(framework, primary_coin, coin_to_merge, coin_type);
+        self.move_calls_transaction(signer, vec![call], gas, gas_budget)
+            .await
+    }
 
-        Ok(TransactionData::new_move_call(
-            signer,
-            self.get_object_ref(SUI_FRAMEWORK_OBJECT_ID).await?,
-            coin::COIN_MODULE_NAME.to_owned(),
-            coin::COIN_JOIN_FUNC_NAME.to_owned(),
-            type_args,
-            gas,
-            vec![
-                CallArg::Object(ObjectArg::ImmOrOwnedObject(primary_coin_ref)),
-                CallArg::Object(ObjectArg::ImmOrOwnedObject(coin_to_merge_ref)),
-            ],
-            gas_budget,
-        ))
+    /// Delegate the whole of `coin` to `validator`. The delegation becomes active at the
+    /// start of the next epoch.
+    pub async fn request_add_delegation(
+        &self,
+        signer: SuiAddress,
+        coin: ObjectID,
+        validator: SuiAddress,
+        gas: Option<ObjectID>,
+        gas_budget: u64,
+    ) -> SuiRpcResult<TransactionData> {
+        let framework = self.get_object_ref(SUI_FRAMEWORK_OBJECT_ID).await?;
+        let coin = self.get_object_ref(coin).await?;
+        let call = offline_builder::add_delegation_call(framework, coin, validator)?;
+        self.move_calls_transaction(signer, vec![call], gas, gas_budget)
+            .await
+    }
+
+    /// Withdraw `pool_token_amount` pool tokens worth of stake from `delegation`, paying the
+    /// principal back from `staked_sui`.
+    pub async fn request_withdraw_delegation(
+        &self,
+        signer: SuiAddress,
+        delegation: ObjectID,
+        staked_sui: ObjectID,
+        pool_token_amount: u64,
+        gas: Option<ObjectID>,
+        gas_budget: u64,
+    ) -> SuiRpcResult<TransactionData> {
+        let framework = self.get_object_ref(SUI_FRAMEWORK_OBJECT_ID).await?;
+        let delegation = self.get_object_ref(delegation).await?;
+        let staked_sui = self.get_object_ref(staked_sui).await?;
+        let call = offline_builder::withdraw_delegation_call(
+            framework,
+            delegation,
+            staked_sui,
+            pool_token_amount,
+        )?;
+        self.move_calls_transaction(signer, vec![call], gas, gas_budget)
+            .await
+    }
+
+    /// Request to join the validator set at the next epoch, staking the whole of `stake`.
+    pub async fn request_add_validator(
+        &self,
+        signer: SuiAddress,
+        metadata: ValidatorRegistration,
+        stake: ObjectID,
+        gas: Option<ObjectID>,
+        gas_budget: u64,
+    ) -> SuiRpcResult<TransactionData> {
+        let framework = self.get_object_ref(SUI_FRAMEWORK_OBJECT_ID).await?;
+        let stake = self.get_object_ref(stake).await?;
+        let call = offline_builder::add_validator_call(framework, &metadata, stake)?;
+        self.move_calls_transaction(signer, vec![call], gas, gas_budget)
+            .await
+    }
+
+    /// Request to leave the validator set at the end of the current epoch.
+    pub async fn request_remove_validator(
+        &self,
+        signer: SuiAddress,
+        gas: Option<ObjectID>,
+        gas_budget: u64,
+    ) -> SuiRpcResult<TransactionData> {
+        let framework = self.get_object_ref(SUI_FRAMEWORK_OBJECT_ID).await?;
+        let call = offline_builder::remove_validator_call(framework);
+        self.move_calls_transaction(signer, vec![call], gas, gas_budget)
+            .await
+    }
+
+    /// Submit the signer's gas price quote for the next epoch.
+    pub async fn request_set_gas_price(
+        &self,
+        signer: SuiAddress,
+        gas_price: u64,
+        gas: Option<ObjectID>,
+        gas_budget: u64,
+    ) -> SuiRpcResult<TransactionData> {
+        let framework = self.get_object_ref(SUI_FRAMEWORK_OBJECT_ID).await?;
+        let call = offline_builder::set_gas_price_call(framework, gas_price)?;
+        self.move_calls_transaction(signer, vec![call], gas, gas_budget)
+            .await
+    }
+
+    /// Add the whole of `coin` to the signer's validator stake, effective at the next epoch.
+    pub async fn request_add_stake(
+        &self,
+        signer: SuiAddress,
+        coin: ObjectID,
+        gas: Option<ObjectID>,
+        gas_budget: u64,
+    ) -> SuiRpcResult<TransactionData> {
+        let framework = self.get_object_ref(SUI_FRAMEWORK_OBJECT_ID).await?;
+        let coin = self.get_object_ref(coin).await?;
+        let call = offline_builder::add_stake_call(framework, coin);
+        self.move_calls_transaction(signer, vec![call], gas, gas_budget)
+            .await
+    }
+
+    /// Withdraw `amount` from the validator `stake` object.
+    pub async fn request_withdraw_stake(
+        &self,
+        signer: SuiAddress,
+        stake: ObjectID,
+        amount: u64,
+        gas: Option<ObjectID>,
+        gas_budget: u64,
+    ) -> SuiRpcResult<TransactionData> {
+        let framework = self.get_object_ref(SUI_FRAMEWORK_OBJECT_ID).await?;
+        let stake = self.get_object_ref(stake).await?;
+        let call = offline_builder::withdraw_stake_call(framework, stake, amount)?;
+        self.move_calls_transaction(signer, vec![call], gas, gas_budget)
+            .await
+    }
+
+    /// Send the whole of `coin` to `recipient`, locked until `unlock_epoch`: the recipient can
+    /// only claim it with [TransactionBuilder::unlock_coin] from that epoch on, e.g. for
+    /// vesting payments or a deadman switch. `unlock_epoch` must be after the current epoch.
+    pub async fn lock_coin(
+        &self,
+        signer: SuiAddress,
+        coin: ObjectID,
+        recipient: SuiAddress,
+        unlock_epoch: u64,
+        gas: Option<ObjectID>,
+        gas_budget: u64,
+    ) -> SuiRpcResult<TransactionData> {
+        let framework = self.get_object_ref(SUI_FRAMEWORK_OBJECT_ID).await?;
+        let (coin, coin_type) = self.get_coin(coin).await?;
+        let call =
+            offline_builder::lock_coin_call(framework, coin, coin_type, recipient, unlock_epoch)?;
+        self.move_calls_transaction(signer, vec![call], gas, gas_budget)
+            .await
+    }
+
+    /// Turn a `LockedCoin` received from [TransactionBuilder::lock_coin] back into a coin of
+    /// the signer, once its unlock epoch has started.
+    pub async fn unlock_coin(
+        &self,
+        signer: SuiAddress,
+        locked_coin: ObjectID,
+        gas: Option<ObjectID>,
+        gas_budget: u64,
+    ) -> SuiRpcResult<TransactionData> {
+        self.claim_vesting(signer, vec![locked_coin], gas, gas_budget)
+            .await
+    }
+
+    /// Grant the tranches of `schedule` to `grantee`, as one `LockedCoin` per tranche.
+    /// `coins` hold the amounts of [VestingSchedule::tranches] in order, e.g. split off a
+    /// larger coin with [TransactionBuilder::split_coin]. Tranches vested by the current
+    /// epoch are sent unlocked.
+    pub async fn create_vesting(
+        &self,
+        signer: SuiAddress,
+        schedule: &VestingSchedule,
+        grantee: SuiAddress,
+        coins: Vec<ObjectID>,
+        gas: Option<ObjectID>,
+        gas_budget: u64,
+    ) -> SuiRpcResult<TransactionData> {
+        let tranches = schedule.tranches();
+        if coins.len() != tranches.len() {
+            return Err(Error::Other(anyhow!(
+                "The schedule has {} tranches, {} coins were given.",
+                tranches.len(),
+                coins.len()
+            )));
+        }
+        let epoch = self.0.get_sui_system_state().await?.epoch;
+        let framework = self.get_object_ref(SUI_FRAMEWORK_OBJECT_ID).await?;
+        let mut calls = vec![];
+        for (tranche, coin_id) in tranches.iter().zip(coins) {
+            let coin = self.get_full_object(coin_id).await?;
+            let amount = coin::Coin::extract_balance_if_coin(&coin)?
+                .ok_or_else(|| anyhow!("Object [{coin_id}] is not a coin."))?;
+            if amount != tranche.amount {
+                return Err(Error::Other(anyhow!(
+                    "Coin [{coin_id}] holds {amount}, not the {} of its tranche.",
+                    tranche.amount
+                )));
+            }
+            calls.push(offline_builder::vesting_tranche_call(
+                framework,
+                coin.compute_object_reference(),
+                coin.get_move_template_type()?,
+                grantee,
+                tranche.unlock_epoch,
+                epoch,
+            )?);
+        }
+        self.move_calls_transaction(signer, calls, gas, gas_budget)
+            .await
+    }
+
+    /// Unlock the `locked_coins` of the signer whose unlock epoch has started, e.g. the
+    /// [claimable](crate::vesting::VestingPosition::claimable) coins of a grant.
+    pub async fn claim_vesting(
+        &self,
+        signer: SuiAddress,
+        locked_coins: Vec<ObjectID>,
+        gas: Option<ObjectID>,
+        gas_budget: u64,
+    ) -> SuiRpcResult<TransactionData> {
+        let framework = self.get_object_ref(SUI_FRAMEWORK_OBJECT_ID).await?;
+        let mut calls = vec![];
+        for locked_coin in locked_coins {
+            let (locked_coin, coin_type) = self.get_coin(locked_coin).await?;
+            calls.push(offline_builder::unlock_coin_call(
+                framework,
+                locked_coin,
+                coin_type,
+            ));
+        }
+        self.move_calls_transaction(signer, calls, gas, gas_budget)
+            .await
+    }
+
+    /// A transaction of `calls`, batched if there are several, paying the gas with `gas` or
+    /// with a gas coin of the signer not used by the calls.
+    async fn move_calls_transaction(
+        &self,
+        signer: SuiAddress,
+        calls: Vec<SingleTransactionKind>,
+        gas: Option<ObjectID>,
+        gas_budget: u64,
+    ) -> SuiRpcResult<TransactionData> {
+        let mut inputs = vec![];
+        for call in &calls {
+            inputs.extend(
+                call.input_objects()?
+                    .into_iter()
+                    .filter_map(|obj| match obj {
+                        InputObjectKind::ImmOrOwnedMoveObject((id, _, _)) => Some(id),
+                        _ => None,
+                    }),
+            );
+        }
+        let gas = self.select_gas(signer, gas, gas_budget, inputs).await?;
+        offline_builder::transaction(signer, calls, gas, gas_budget)
     }
 
     pub async fn batch_transaction(
@@ -391,7 +591,7 @@ impl TransactionBuilder {
         single_transaction_params: Vec<RPCTransactionRequestParams>,
         gas: Option<ObjectID>,
         gas_budget: u64,
-    ) -> anyhow::Result<TransactionData> {
+    ) -> SuiRpcResult<TransactionData> {
         fp_ensure!(
             !single_transaction_params.is_empty(),
             SuiError::InvalidBatchTransaction {
@@ -443,6 +643,24 @@ impl TransactionBuilder {
         ))
     }
 
+    async fn get_full_object(&self, object_id: ObjectID) -> anyhow::Result<Object> {
+        Ok(self
+            .0
+            .get_object(object_id)
+            .await?
+            .into_object()?
+            .try_into()?)
+    }
+
+    /// The reference of the coin `coin_id`, a `Coin<T>` or a `LockedCoin<T>`, and its `T`.
+    async fn get_coin(&self, coin_id: ObjectID) -> anyhow::Result<(ObjectRef, TypeTag)> {
+        let coin = self.get_full_object(coin_id).await?;
+        Ok((
+            coin.compute_object_reference(),
+            coin.get_move_template_type()?,
+        ))
+    }
+
     async fn get_object_ref(&self, object_id: ObjectID) -> anyhow::Result<ObjectRef> {
         Ok(self
             .0
@@ -0,0 +1,881 @@
+// Copyright (c) 2022, Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Pluggable HTTP transports for the JSON-RPC client.
+//!
+//! By default, RPC clients send requests with the jsonrpsee HTTP client, built on hyper. A
+//! [HttpTransport] set with [SuiClientBuilder::http_transport] replaces it, e.g. to use
+//! another HTTP stack, a client with its own retry policy, or a test double. The SDK still
+//! encodes the JSON-RPC requests and decodes the responses, so a transport only sends bytes.
+//! Subscriptions are still sent over the WebSocket client.
+//!
+//! [EtagCachingTransport] revalidates repeated reads with their ETag, for providers
+//! supporting HTTP caching. [ReqwestTransport] negotiates the [Compression] of responses,
+//! which [SuiClientBuilder::compression] enables. [HyperTransport] resolves and connects to
+//! the nodes as set by the [ConnectOptions] of [SuiClientBuilder::connect_options], and pools
+//! its connections as set by the [PoolOptions] of [SuiClientBuilder::pool_options].
+//!
+//! [SuiClientBuilder::http_transport]: crate::SuiClientBuilder::http_transport
+//! [SuiClientBuilder::compression]: crate::SuiClientBuilder::compression
+//! [SuiClientBuilder::connect_options]: crate::SuiClientBuilder::connect_options
+//! [SuiClientBuilder::pool_options]: crate::SuiClientBuilder::pool_options
+
+use std::fmt::{Debug, Formatter};
+use std::future::Future;
+use std::io::Write;
+use std::net::SocketAddr;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll};
+use std::time::{Duration, Instant};
+
+use anyhow::anyhow;
+
+use async_trait::async_trait;
+use flate2::write::GzEncoder;
+use futures::future::{BoxFuture, FutureExt};
+use hyper::client::connect::dns::Name;
+use hyper::client::HttpConnector;
+use hyper::service::Service;
+use hyper_rustls::{HttpsConnector, HttpsConnectorBuilder};
+use jsonrpsee::core::client::ClientT;
+use jsonrpsee::core::Error as JsonRpcError;
+use jsonrpsee::http_client::HttpClient;
+use jsonrpsee::types::error::{CallError, ErrorObject};
+use jsonrpsee::types::ParamsSer;
+use reqwest::header::{
+    HeaderMap, HeaderName, HeaderValue, CONTENT_ENCODING, CONTENT_TYPE, ETAG, IF_NONE_MATCH,
+};
+use reqwest::StatusCode;
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+use serde_json::value::RawValue;
+use serde_json::{json, Value};
+
+use crate::circuit_breaker::CircuitBreaker;
+use crate::dns::{ConnectOptions, DnsResolver, IpPreference};
+use crate::error::HttpStatusError;
+use crate::hedging::{is_hedgeable, Hedger};
+use crate::request_log::RequestLogger;
+use crate::runtime::{timeout, Runtime, TokioRuntime};
+
+/// Sends JSON-RPC requests over HTTP.
+#[async_trait]
+pub trait HttpTransport: Send + Sync {
+    /// POST the JSON `body` to `url` and return the body of the response. A response with a
+    /// non-success status fails with an [HttpStatusError], so that throttled requests are
+    /// recognized, see [RpcError::is_throttled](crate::error::RpcError::is_throttled).
+    async fn post(&self, url: &str, body: Vec<u8>) -> anyhow::Result<Vec<u8>>;
+}
+
+/// Compression of the HTTP traffic of a [ReqwestTransport]. Large responses, e.g. objects
+/// or transaction pages, typically shrink several times.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Compression {
+    /// Accept gzip encoded responses.
+    pub gzip: bool,
+    /// Accept brotli encoded responses.
+    pub brotli: bool,
+    /// Gzip the bodies of requests, for nodes accepting `Content-Encoding: gzip`.
+    pub gzip_requests: bool,
+}
+
+impl Compression {
+    /// Accept gzip and brotli encoded responses, and send requests uncompressed.
+    pub fn responses() -> Self {
+        Self {
+            gzip: true,
+            brotli: true,
+            gzip_requests: false,
+        }
+    }
+}
+
+/// A [HttpTransport] over reqwest.
+#[derive(Debug, Clone)]
+pub struct ReqwestTransport {
+    client: reqwest::Client,
+    gzip_requests: bool,
+    headers: HeaderMap,
+}
+
+impl ReqwestTransport {
+    pub fn new(request_timeout: Duration) -> anyhow::Result<Self> {
+        Self::with_compression(request_timeout, Compression::default())
+    }
+
+    pub fn with_compression(
+        request_timeout: Duration,
+        compression: Compression,
+    ) -> anyhow::Result<Self> {
+        Ok(Self {
+            client: reqwest::Client::builder()
+                .timeout(request_timeout)
+                .gzip(compression.gzip)
+                .brotli(compression.brotli)
+                .build()?,
+            gzip_requests: compression.gzip_requests,
+            headers: HeaderMap::new(),
+        })
+    }
+
+    /// Send `headers` with every request.
+    pub fn with_headers(mut self, headers: HeaderMap) -> Self {
+        self.headers = headers;
+        self
+    }
+}
+
+#[async_trait]
+impl HttpTransport for ReqwestTransport {
+    async fn post(&self, url: &str, body: Vec<u8>) -> anyhow::Result<Vec<u8>> {
+        let mut request = self
+            .client
+            .post(url)
+            .headers(self.headers.clone())
+            .header(CONTENT_TYPE, "application/json");
+        request = if self.gzip_requests {
+            request.header(CONTENT_ENCODING, "gzip").body(gzip(&body)?)
+        } else {
+            request.body(body)
+        };
+        let response = check_status(request.send().await?)?;
+        Ok(response.bytes().await?.to_vec())
+    }
+}
+
+/// `response`, or an [HttpStatusError] if its status is a client or server error.
+fn check_status(response: reqwest::Response) -> anyhow::Result<reqwest::Response> {
+    let status = response.status();
+    if status.is_client_error() || status.is_server_error() {
+        return Err(HttpStatusError(status.as_u16()).into());
+    }
+    Ok(response)
+}
+
+/// The headers `headers`, as name and value pairs.
+pub fn header_map(headers: &[(String, String)]) -> anyhow::Result<HeaderMap> {
+    headers
+        .iter()
+        .map(|(name, value)| {
+            Ok((
+                HeaderName::from_bytes(name.as_bytes())?,
+                HeaderValue::from_str(value)?,
+            ))
+        })
+        .collect()
+}
+
+fn gzip(bytes: &[u8]) -> anyhow::Result<Vec<u8>> {
+    let mut encoder = GzEncoder::new(Vec::new(), flate2::Compression::default());
+    encoder.write_all(bytes)?;
+    Ok(encoder.finish()?)
+}
+
+/// How a [HyperTransport] pools its connections to the nodes. High-QPS services running out
+/// of ephemeral ports should keep more idle connections, or multiplex requests over HTTP/2.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PoolOptions {
+    pub(crate) max_idle_per_host: usize,
+    pub(crate) idle_timeout: Option<Duration>,
+    pub(crate) http2_only: bool,
+    pub(crate) http2_keep_alive_interval: Option<Duration>,
+    pub(crate) tcp_keepalive: Option<Duration>,
+}
+
+impl Default for PoolOptions {
+    fn default() -> Self {
+        Self {
+            max_idle_per_host: usize::MAX,
+            idle_timeout: Some(Duration::from_secs(90)),
+            http2_only: false,
+            http2_keep_alive_interval: None,
+            tcp_keepalive: None,
+        }
+    }
+}
+
+impl PoolOptions {
+    /// Maximum number of idle connections kept per node, 0 to close connections after each
+    /// request. Unbounded by default.
+    pub fn with_max_idle_per_host(mut self, max_idle_per_host: usize) -> Self {
+        self.max_idle_per_host = max_idle_per_host;
+        self
+    }
+
+    /// Close connections idle for `timeout`, or never if `None`. Defaults to 90 seconds.
+    pub fn with_idle_timeout(mut self, timeout: Option<Duration>) -> Self {
+        self.idle_timeout = timeout;
+        self
+    }
+
+    /// Send all requests to a node over a single HTTP/2 connection, which the node must
+    /// support, including over plain HTTP. By default, HTTP/2 is used when negotiated with
+    /// TLS nodes, and HTTP/1.1 otherwise.
+    pub fn with_http2_only(mut self, http2_only: bool) -> Self {
+        self.http2_only = http2_only;
+        self
+    }
+
+    /// Ping HTTP/2 connections every `interval`, so that idle connections stay open through
+    /// proxies and dead ones are detected. Disabled by default.
+    pub fn with_http2_keep_alive_interval(mut self, interval: Option<Duration>) -> Self {
+        self.http2_keep_alive_interval = interval;
+        self
+    }
+
+    /// Send TCP keep-alive probes on connections idle for `keepalive`. Disabled by default.
+    pub fn with_tcp_keepalive(mut self, keepalive: Option<Duration>) -> Self {
+        self.tcp_keepalive = keepalive;
+        self
+    }
+
+    pub fn max_idle_per_host(&self) -> usize {
+        self.max_idle_per_host
+    }
+
+    pub fn idle_timeout(&self) -> Option<Duration> {
+        self.idle_timeout
+    }
+
+    pub fn http2_only(&self) -> bool {
+        self.http2_only
+    }
+
+    pub fn http2_keep_alive_interval(&self) -> Option<Duration> {
+        self.http2_keep_alive_interval
+    }
+
+    pub fn tcp_keepalive(&self) -> Option<Duration> {
+        self.tcp_keepalive
+    }
+}
+
+/// A [HttpTransport] over hyper, resolving host names and connecting as set by
+/// [ConnectOptions], and pooling connections as set by [PoolOptions].
+#[derive(Clone)]
+pub struct HyperTransport {
+    client: hyper::Client<HttpsConnector<HttpConnector<HyperResolver>>>,
+    request_timeout: Duration,
+    pool: PoolOptions,
+    headers: HeaderMap,
+    runtime: Arc<dyn Runtime>,
+}
+
+impl HyperTransport {
+    pub fn new(request_timeout: Duration, options: ConnectOptions) -> Self {
+        Self::with_pool(request_timeout, options, PoolOptions::default())
+    }
+
+    pub fn with_pool(
+        request_timeout: Duration,
+        options: ConnectOptions,
+        pool: PoolOptions,
+    ) -> Self {
+        let mut http = HttpConnector::new_with_resolver(HyperResolver {
+            resolver: options.resolver,
+            ip_preference: options.ip_preference,
+        });
+        http.enforce_http(false);
+        http.set_happy_eyeballs_timeout(options.happy_eyeballs_timeout);
+        http.set_connect_timeout(options.connect_timeout);
+        http.set_keepalive(pool.tcp_keepalive);
+        let https = HttpsConnectorBuilder::new()
+            .with_native_roots()
+            .https_or_http();
+        let https = if pool.http2_only {
+            https.enable_http2().wrap_connector(http)
+        } else {
+            https.enable_all_versions().wrap_connector(http)
+        };
+        let client = hyper::Client::builder()
+            .pool_max_idle_per_host(pool.max_idle_per_host)
+            .pool_idle_timeout(pool.idle_timeout)
+            .http2_only(pool.http2_only)
+            .http2_keep_alive_interval(pool.http2_keep_alive_interval)
+            .http2_keep_alive_while_idle(pool.http2_keep_alive_interval.is_some())
+            .build(https);
+        Self {
+            client,
+            request_timeout,
+            pool,
+            headers: HeaderMap::new(),
+            runtime: Arc::new(TokioRuntime),
+        }
+    }
+
+    pub fn pool_options(&self) -> &PoolOptions {
+        &self.pool
+    }
+
+    /// Send `headers` with every request.
+    pub fn with_headers(mut self, headers: HeaderMap) -> Self {
+        self.headers = headers;
+        self
+    }
+
+    /// Time out requests on `runtime`. Defaults to [TokioRuntime].
+    pub fn with_runtime(mut self, runtime: Arc<dyn Runtime>) -> Self {
+        self.runtime = runtime;
+        self
+    }
+}
+
+#[async_trait]
+impl HttpTransport for HyperTransport {
+    async fn post(&self, url: &str, body: Vec<u8>) -> anyhow::Result<Vec<u8>> {
+        let mut request = hyper::Request::post(url)
+            .header(CONTENT_TYPE, "application/json")
+            .body(hyper::Body::from(body))?;
+        request.headers_mut().extend(self.headers.clone());
+        let response = async {
+            let response = self.client.request(request).await?;
+            if !response.status().is_success() {
+                return Err(
+                    anyhow::Error::new(HttpStatusError(response.status().as_u16()))
+                        .context(format!("Request to [{url}] failed.")),
+                );
+            }
+            Ok(hyper::body::to_bytes(response.into_body()).await?.to_vec())
+        };
+        timeout(self.runtime.as_ref(), self.request_timeout, response)
+            .await
+            .ok_or_else(|| anyhow!("Request to [{url}] timed out."))?
+    }
+}
+
+/// A [DnsResolver] as a hyper resolver, ordering the addresses by preference. The
+/// connector tries the addresses of the family of the first address first.
+#[derive(Clone)]
+struct HyperResolver {
+    resolver: Arc<dyn DnsResolver>,
+    ip_preference: IpPreference,
+}
+
+impl Service<Name> for HyperResolver {
+    type Response = std::vec::IntoIter<SocketAddr>;
+    type Error = Box<dyn std::error::Error + Send + Sync>;
+    type Future = BoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    fn poll_ready(&mut self, _: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, name: Name) -> Self::Future {
+        let resolver = self.clone();
+        async move {
+            let addresses = resolver.resolver.resolve(name.as_str()).await?;
+            let addresses = resolver.ip_preference.sort(addresses);
+            if addresses.is_empty() {
+                return Err(format!("No allowed address for host [{name}].").into());
+            }
+            // The connector sets the port of the URL.
+            Ok(addresses
+                .into_iter()
+                .map(|address| SocketAddr::new(address, 0))
+                .collect::<Vec<_>>()
+                .into_iter())
+        }
+        .boxed()
+    }
+}
+
+/// A [HttpTransport] over reqwest caching the responses of reads with their ETag, for
+/// providers validating requests with `If-None-Match`. Repeated reads the provider answers
+/// with `304 Not Modified` are served from the cache, saving the bandwidth of the response.
+/// Only single requests of read methods (`sui_get*`, `sui_tryGet*`) are cached.
+pub struct EtagCachingTransport {
+    client: reqwest::Client,
+    /// ETag and body of the responses, keyed by URL and request without its id.
+    responses: Mutex<lru::LruCache<String, (String, Vec<u8>)>>,
+}
+
+impl EtagCachingTransport {
+    /// A transport caching up to `capacity` responses.
+    pub fn new(request_timeout: Duration, capacity: usize) -> anyhow::Result<Self> {
+        Ok(Self {
+            client: reqwest::Client::builder()
+                .timeout(request_timeout)
+                .build()?,
+            responses: Mutex::new(lru::LruCache::new(capacity)),
+        })
+    }
+}
+
+/// The cache key and id of a request, if its response may be cached.
+fn cache_key(url: &str, body: &[u8]) -> Option<(String, Value)> {
+    let mut request: Value = serde_json::from_slice(body).ok()?;
+    let method = request.get("method")?.as_str()?;
+    if !method.starts_with("sui_get") && !method.starts_with("sui_tryGet") {
+        return None;
+    }
+    let id = request.as_object_mut()?.remove("id")?;
+    Some((format!("{url} {request}"), id))
+}
+
+#[async_trait]
+impl HttpTransport for EtagCachingTransport {
+    async fn post(&self, url: &str, body: Vec<u8>) -> anyhow::Result<Vec<u8>> {
+        let key = cache_key(url, &body);
+        let cached = key
+            .as_ref()
+            .and_then(|(key, _)| self.responses.lock().unwrap().get(key).cloned());
+        let mut request = self
+            .client
+            .post(url)
+            .header(CONTENT_TYPE, "application/json")
+            .body(body);
+        if let Some((etag, _)) = &cached {
+            request = request.header(IF_NONE_MATCH, etag);
+        }
+        let response = request.send().await?;
+        if let (StatusCode::NOT_MODIFIED, Some((_, cached)), Some((_, id))) =
+            (response.status(), cached, &key)
+        {
+            // The cached response answered an earlier request, with another id.
+            let mut cached: Value = serde_json::from_slice(&cached)?;
+            cached["id"] = id.clone();
+            return Ok(serde_json::to_vec(&cached)?);
+        }
+        let response = check_status(response)?;
+        let etag = response
+            .headers()
+            .get(ETAG)
+            .and_then(|etag| etag.to_str().ok())
+            .map(str::to_string);
+        let body = response.bytes().await?.to_vec();
+        if let (Some((key, _)), Some(etag)) = (key, etag) {
+            self.responses
+                .lock()
+                .unwrap()
+                .put(key, (etag, body.clone()));
+        }
+        Ok(body)
+    }
+}
+
+/// A JSON-RPC client sending requests over a [HttpTransport].
+pub struct TransportClient {
+    url: String,
+    transport: Arc<dyn HttpTransport>,
+    next_id: AtomicU64,
+}
+
+impl Debug for TransportClient {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("TransportClient")
+            .field("url", &self.url)
+            .finish()
+    }
+}
+
+#[derive(Deserialize)]
+struct Response<'a> {
+    #[serde(default)]
+    id: Option<u64>,
+    #[serde(borrow, default)]
+    result: Option<&'a RawValue>,
+    #[serde(default)]
+    error: Option<ResponseError>,
+}
+
+#[derive(Deserialize)]
+struct ResponseError {
+    code: i32,
+    message: String,
+    #[serde(default)]
+    data: Option<Value>,
+}
+
+impl TransportClient {
+    pub fn new(url: impl Into<String>, transport: Arc<dyn HttpTransport>) -> Self {
+        Self {
+            url: url.into(),
+            transport,
+            next_id: AtomicU64::new(0),
+        }
+    }
+
+    fn request_body(&self, method: &str, params: Option<ParamsSer<'_>>, id: Option<u64>) -> Value {
+        let mut request = json!({ "jsonrpc": "2.0", "method": method });
+        if let Some(id) = id {
+            request["id"] = id.into();
+        }
+        if let Some(params) = params {
+            request["params"] = json!(params);
+        }
+        request
+    }
+
+    async fn post(&self, request: &Value) -> Result<Vec<u8>, JsonRpcError> {
+        let body = serde_json::to_vec(request)?;
+        self.transport
+            .post(&self.url, body)
+            .await
+            .map_err(JsonRpcError::Transport)
+    }
+}
+
+fn parse_result<R: DeserializeOwned>(response: Response<'_>) -> Result<R, JsonRpcError> {
+    if let Some(error) = response.error {
+        return Err(JsonRpcError::Call(CallError::Custom(ErrorObject::owned(
+            error.code,
+            error.message,
+            error.data,
+        ))));
+    }
+    let result = response
+        .result
+        .ok_or_else(|| JsonRpcError::Custom("Response has neither result nor error.".into()))?;
+    Ok(serde_json::from_str(result.get())?)
+}
+
+#[async_trait]
+impl ClientT for TransportClient {
+    async fn notification<'a>(
+        &self,
+        method: &'a str,
+        params: Option<ParamsSer<'a>>,
+    ) -> Result<(), JsonRpcError> {
+        self.post(&self.request_body(method, params, None)).await?;
+        Ok(())
+    }
+
+    async fn request<'a, R>(
+        &self,
+        method: &'a str,
+        params: Option<ParamsSer<'a>>,
+    ) -> Result<R, JsonRpcError>
+    where
+        R: DeserializeOwned,
+    {
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        let body = self
+            .post(&self.request_body(method, params, Some(id)))
+            .await?;
+        parse_result(serde_json::from_slice(&body)?)
+    }
+
+    async fn batch_request<'a, R>(
+        &self,
+        batch: Vec<(&'a str, Option<ParamsSer<'a>>)>,
+    ) -> Result<Vec<R>, JsonRpcError>
+    where
+        R: DeserializeOwned + Default + Clone,
+    {
+        let first_id = self
+            .next_id
+            .fetch_add(batch.len() as u64, Ordering::Relaxed);
+        let requests = batch
+            .into_iter()
+            .zip(first_id..)
+            .map(|((method, params), id)| self.request_body(method, params, Some(id)))
+            .collect::<Vec<_>>();
+        let mut results = vec![R::default(); requests.len()];
+        let body = self.post(&Value::Array(requests)).await?;
+        // Responses of a batch may come in any order.
+        let responses: Vec<Response> = serde_json::from_slice(&body)?;
+        for response in responses {
+            let index = response
+                .id
+                .and_then(|id| id.checked_sub(first_id))
+                .filter(|index| *index < results.len() as u64)
+                .ok_or(JsonRpcError::InvalidRequestId)?;
+            results[index as usize] = parse_result(response)?;
+        }
+        Ok(results)
+    }
+}
+
+/// The HTTP client of an RPC client: the jsonrpsee client, or a custom transport, behind an
+/// optional circuit breaker, hedging reads on another endpoint and logging requests if set.
+/// Scoped clients share the backend, and with it the connections, of the client they were
+/// made from.
+pub(crate) struct RpcHttpClient {
+    url: String,
+    backend: Arc<HttpBackend>,
+    breaker: Option<Arc<CircuitBreaker>>,
+    hedge: Option<(Arc<RpcHttpClient>, Arc<Hedger>)>,
+    timeout: Option<Duration>,
+    /// Maximum number of attempts and initial backoff of requests failing in transport.
+    retry: Option<(u32, Duration)>,
+    logger: Option<Arc<RequestLogger>>,
+    runtime: Arc<dyn Runtime>,
+}
+
+enum HttpBackend {
+    Jsonrpsee(HttpClient),
+    Transport(TransportClient),
+}
+
+impl RpcHttpClient {
+    pub fn jsonrpsee(url: &str, client: HttpClient) -> Self {
+        Self {
+            url: url.to_string(),
+            backend: Arc::new(HttpBackend::Jsonrpsee(client)),
+            breaker: None,
+            hedge: None,
+            timeout: None,
+            retry: None,
+            logger: None,
+            runtime: Arc::new(TokioRuntime),
+        }
+    }
+
+    pub fn transport(url: &str, transport: Arc<dyn HttpTransport>) -> Self {
+        Self {
+            url: url.to_string(),
+            backend: Arc::new(HttpBackend::Transport(TransportClient::new(url, transport))),
+            breaker: None,
+            hedge: None,
+            timeout: None,
+            retry: None,
+            logger: None,
+            runtime: Arc::new(TokioRuntime),
+        }
+    }
+
+    pub fn with_circuit_breaker(mut self, breaker: Arc<CircuitBreaker>) -> Self {
+        self.breaker = Some(breaker);
+        self
+    }
+
+    /// Hedge reads on `client` as `hedger` decides.
+    pub fn with_hedging(mut self, client: RpcHttpClient, hedger: Arc<Hedger>) -> Self {
+        self.hedge = Some((Arc::new(client), hedger));
+        self
+    }
+
+    /// Log requests with `logger`.
+    pub fn with_logger(mut self, logger: Arc<RequestLogger>) -> Self {
+        self.logger = Some(logger);
+        self
+    }
+
+    /// Time out and back off the requests of the scope on `runtime`.
+    pub fn with_runtime(mut self, runtime: Arc<dyn Runtime>) -> Self {
+        self.runtime = runtime;
+        self
+    }
+
+    /// A client sharing the backend, circuit breaker and hedging of this one.
+    pub fn share(&self) -> Self {
+        Self {
+            url: self.url.clone(),
+            backend: self.backend.clone(),
+            breaker: self.breaker.clone(),
+            hedge: self.hedge.clone(),
+            timeout: self.timeout,
+            retry: self.retry,
+            logger: self.logger.clone(),
+            runtime: self.runtime.clone(),
+        }
+    }
+
+    /// Fail requests after `timeout`, and retry those failing in transport up to
+    /// `retry.0` attempts, with a backoff starting at `retry.1` and doubling after each.
+    pub fn with_scope(mut self, timeout: Option<Duration>, retry: Option<(u32, Duration)>) -> Self {
+        self.timeout = timeout.or(self.timeout);
+        self.retry = retry.or(self.retry);
+        self
+    }
+
+    pub fn hedger(&self) -> Option<&Hedger> {
+        self.hedge.as_ref().map(|(_, hedger)| hedger.as_ref())
+    }
+
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+
+    pub fn circuit_breaker(&self) -> Option<&CircuitBreaker> {
+        self.breaker.as_deref()
+    }
+
+    async fn guarded<T>(
+        &self,
+        request: impl Future<Output = Result<T, JsonRpcError>>,
+    ) -> Result<T, JsonRpcError> {
+        match &self.breaker {
+            Some(breaker) => breaker.call(&self.url, request).await,
+            None => request.await,
+        }
+    }
+
+    /// Run `request` with the timeout and retries of the scope.
+    async fn scoped<T, F, Fut>(&self, mut request: F) -> Result<T, JsonRpcError>
+    where
+        F: FnMut() -> Fut,
+        Fut: Future<Output = Result<T, JsonRpcError>>,
+    {
+        let (max_attempts, mut backoff) = self.retry.unwrap_or((1, Duration::ZERO));
+        let mut attempt = 1;
+        loop {
+            let result = match self.timeout {
+                Some(duration) => timeout(self.runtime.as_ref(), duration, request())
+                    .await
+                    .unwrap_or(Err(JsonRpcError::RequestTimeout)),
+                None => request().await,
+            };
+            match result {
+                Err(JsonRpcError::Transport(_) | JsonRpcError::RequestTimeout)
+                    if attempt < max_attempts =>
+                {
+                    self.runtime.sleep(backoff).await;
+                    backoff *= 2;
+                    attempt += 1;
+                }
+                result => return result,
+            }
+        }
+    }
+
+    async fn request_once<'a, R>(
+        &self,
+        method: &'a str,
+        params: Option<ParamsSer<'a>>,
+    ) -> Result<R, JsonRpcError>
+    where
+        R: DeserializeOwned,
+    {
+        match &self.hedge {
+            Some((client, hedger)) if is_hedgeable(method) => {
+                let hedge_params = params.clone();
+                hedger
+                    .run(self.request_primary(method, params), || {
+                        client.request(method, hedge_params)
+                    })
+                    .await
+            }
+            _ => self.request_primary(method, params).await,
+        }
+    }
+
+    async fn request_primary<'a, R>(
+        &self,
+        method: &'a str,
+        params: Option<ParamsSer<'a>>,
+    ) -> Result<R, JsonRpcError>
+    where
+        R: DeserializeOwned,
+    {
+        self.guarded(async {
+            match &*self.backend {
+                HttpBackend::Jsonrpsee(c) => c.request(method, params).await,
+                HttpBackend::Transport(c) => c.request(method, params).await,
+            }
+        })
+        .await
+    }
+}
+
+impl Debug for RpcHttpClient {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match &*self.backend {
+            HttpBackend::Jsonrpsee(c) => c.fmt(f),
+            HttpBackend::Transport(c) => c.fmt(f),
+        }
+    }
+}
+
+#[async_trait]
+impl ClientT for RpcHttpClient {
+    async fn notification<'a>(
+        &self,
+        method: &'a str,
+        params: Option<ParamsSer<'a>>,
+    ) -> Result<(), JsonRpcError> {
+        let start = Instant::now();
+        let result = self
+            .scoped(|| {
+                let params = params.clone();
+                self.guarded(async move {
+                    match &*self.backend {
+                        HttpBackend::Jsonrpsee(c) => c.notification(method, params).await,
+                        HttpBackend::Transport(c) => c.notification(method, params).await,
+                    }
+                })
+            })
+            .await;
+        if let Some(logger) = &self.logger {
+            logger.log(
+                &self.url,
+                method,
+                &params,
+                start,
+                None,
+                result.as_ref().err(),
+            );
+        }
+        result
+    }
+
+    async fn request<'a, R>(
+        &self,
+        method: &'a str,
+        params: Option<ParamsSer<'a>>,
+    ) -> Result<R, JsonRpcError>
+    where
+        R: DeserializeOwned,
+    {
+        let logger = match &self.logger {
+            Some(logger) => logger,
+            None => {
+                return self
+                    .scoped(|| self.request_once(method, params.clone()))
+                    .await
+            }
+        };
+        // Read the raw result to log its size.
+        let start = Instant::now();
+        let result = self
+            .scoped(|| self.request_once::<Box<RawValue>>(method, params.clone()))
+            .await
+            .and_then(|raw| Ok((serde_json::from_str(raw.get())?, raw.get().len())));
+        match result {
+            Ok((response, size)) => {
+                logger.log(&self.url, method, &params, start, Some(size), None);
+                Ok(response)
+            }
+            Err(e) => {
+                logger.log(&self.url, method, &params, start, None, Some(&e));
+                Err(e)
+            }
+        }
+    }
+
+    async fn batch_request<'a, R>(
+        &self,
+        batch: Vec<(&'a str, Option<ParamsSer<'a>>)>,
+    ) -> Result<Vec<R>, JsonRpcError>
+    where
+        R: DeserializeOwned + Default + Clone,
+    {
+        let start = Instant::now();
+        let result = self
+            .scoped(|| {
+                let batch = batch.clone();
+                self.guarded(async move {
+                    match &*self.backend {
+                        HttpBackend::Jsonrpsee(c) => c.batch_request(batch).await,
+                        HttpBackend::Transport(c) => c.batch_request(batch).await,
+                    }
+                })
+            })
+            .await;
+        if let Some(logger) = &self.logger {
+            // Each request of the batch is logged with the latency of the batch.
+            for (method, params) in &batch {
+                logger.log(
+                    &self.url,
+                    method,
+                    params,
+                    start,
+                    None,
+                    result.as_ref().err(),
+                );
+            }
+        }
+        result
+    }
+}
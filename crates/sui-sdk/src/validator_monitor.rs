@@ -0,0 +1,240 @@
+// Copyright (c) 2022, Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Monitoring of the validator set across epochs, for delegators and staking services
+//! watching the validators they stake with.
+//!
+//! A [ValidatorMonitor] snapshots the validator set at every epoch change and reports how it
+//! changed: validators joining, leaving or scheduled for removal, and changes of metadata,
+//! stake and gas price. The system framework of this tree has no validator commission,
+//! operator reports or tallying rule, so these cannot be tracked yet.
+
+use std::collections::BTreeMap;
+
+use futures::{stream, Stream, StreamExt};
+
+use sui_types::base_types::SuiAddress;
+use sui_types::sui_system_state::{SuiSystemState, Validator};
+
+use crate::error::SuiRpcResult;
+use crate::SuiClient;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ValidatorStatus {
+    Active,
+    /// Active until the end of the epoch.
+    PendingRemoval,
+    /// Joining at the start of the next epoch.
+    Pending,
+}
+
+/// A validator at some epoch.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ValidatorRecord {
+    pub sui_address: SuiAddress,
+    pub status: ValidatorStatus,
+    pub name: String,
+    pub net_address: Vec<u8>,
+    pub pubkey_bytes: Vec<u8>,
+    pub network_pubkey_bytes: Vec<u8>,
+    pub stake_amount: u64,
+    pub delegation_amount: u64,
+    pub gas_price: u64,
+}
+
+impl ValidatorRecord {
+    fn new(validator: &Validator, status: ValidatorStatus) -> Self {
+        let metadata = &validator.metadata;
+        Self {
+            sui_address: metadata.sui_address.into(),
+            status,
+            name: String::from_utf8_lossy(&metadata.name).into_owned(),
+            net_address: metadata.net_address.clone(),
+            pubkey_bytes: metadata.pubkey_bytes.clone(),
+            network_pubkey_bytes: metadata.network_pubkey_bytes.clone(),
+            stake_amount: validator.stake_amount,
+            delegation_amount: validator.delegation_staking_pool.sui_balance,
+            gas_price: validator.gas_price,
+        }
+    }
+
+    /// The validator's own stake plus its delegated stake.
+    pub fn total_stake(&self) -> u64 {
+        self.stake_amount + self.delegation_amount
+    }
+}
+
+/// The validator set at some epoch, keyed by validator address.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ValidatorSetSnapshot {
+    pub epoch: u64,
+    pub validators: BTreeMap<SuiAddress, ValidatorRecord>,
+}
+
+impl ValidatorSetSnapshot {
+    pub fn new(state: &SuiSystemState) -> Self {
+        let validators = &state.validators;
+        let active = validators
+            .active_validators
+            .iter()
+            .enumerate()
+            .map(|(index, validator)| {
+                let status = if validators.pending_removals.contains(&(index as u64)) {
+                    ValidatorStatus::PendingRemoval
+                } else {
+                    ValidatorStatus::Active
+                };
+                ValidatorRecord::new(validator, status)
+            });
+        let pending = validators
+            .pending_validators
+            .iter()
+            .map(|validator| ValidatorRecord::new(validator, ValidatorStatus::Pending));
+        Self {
+            epoch: state.epoch,
+            validators: active
+                .chain(pending)
+                .map(|record| (record.sui_address, record))
+                .collect(),
+        }
+    }
+
+    /// The changes from `previous` to this snapshot.
+    pub fn changes_since(&self, previous: &ValidatorSetSnapshot) -> Vec<ValidatorChange> {
+        let mut changes = vec![];
+        for (address, record) in &self.validators {
+            let before = match previous.validators.get(address) {
+                Some(before) => before,
+                None => {
+                    changes.push(ValidatorChange::Joined(record.clone()));
+                    continue;
+                }
+            };
+            if before.status != record.status {
+                changes.push(ValidatorChange::StatusChanged {
+                    sui_address: *address,
+                    before: before.status,
+                    after: record.status,
+                });
+            }
+            if before.name != record.name
+                || before.net_address != record.net_address
+                || before.pubkey_bytes != record.pubkey_bytes
+                || before.network_pubkey_bytes != record.network_pubkey_bytes
+            {
+                changes.push(ValidatorChange::MetadataChanged {
+                    before: before.clone(),
+                    after: record.clone(),
+                });
+            }
+            if before.stake_amount != record.stake_amount
+                || before.delegation_amount != record.delegation_amount
+            {
+                changes.push(ValidatorChange::StakeChanged {
+                    sui_address: *address,
+                    before: before.total_stake(),
+                    after: record.total_stake(),
+                });
+            }
+            if before.gas_price != record.gas_price {
+                changes.push(ValidatorChange::GasPriceChanged {
+                    sui_address: *address,
+                    before: before.gas_price,
+                    after: record.gas_price,
+                });
+            }
+        }
+        changes.extend(
+            previous
+                .validators
+                .values()
+                .filter(|record| !self.validators.contains_key(&record.sui_address))
+                .cloned()
+                .map(ValidatorChange::Left),
+        );
+        changes
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ValidatorChange {
+    Joined(ValidatorRecord),
+    Left(ValidatorRecord),
+    StatusChanged {
+        sui_address: SuiAddress,
+        before: ValidatorStatus,
+        after: ValidatorStatus,
+    },
+    /// The name, network address or keys of the validator changed.
+    MetadataChanged {
+        before: ValidatorRecord,
+        after: ValidatorRecord,
+    },
+    /// The total stake of the validator changed.
+    StakeChanged {
+        sui_address: SuiAddress,
+        before: u64,
+        after: u64,
+    },
+    GasPriceChanged {
+        sui_address: SuiAddress,
+        before: u64,
+        after: u64,
+    },
+}
+
+/// The changes of the validator set at an epoch change.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ValidatorSetUpdate {
+    pub snapshot: ValidatorSetSnapshot,
+    pub changes: Vec<ValidatorChange>,
+}
+
+pub struct ValidatorMonitor<'a> {
+    client: &'a SuiClient,
+}
+
+impl<'a> ValidatorMonitor<'a> {
+    pub fn new(client: &'a SuiClient) -> Self {
+        Self { client }
+    }
+
+    pub async fn snapshot(&self) -> SuiRpcResult<ValidatorSetSnapshot> {
+        let state = self.client.read_api().get_sui_system_state().await?;
+        Ok(ValidatorSetSnapshot::new(&state))
+    }
+
+    /// Stream the changes of the validator set at every epoch change from now on, compared to
+    /// `previous`, or to the current validator set if `None`. Pass the last snapshot seen to
+    /// resume monitoring without missing changes.
+    pub async fn watch(
+        &self,
+        previous: Option<ValidatorSetSnapshot>,
+    ) -> SuiRpcResult<impl Stream<Item = SuiRpcResult<ValidatorSetUpdate>> + 'a> {
+        let client = self.client;
+        let epochs = Box::pin(client.subscribe_epochs().await?);
+        let previous = match previous {
+            Some(previous) => previous,
+            None => self.snapshot().await?,
+        };
+        Ok(stream::unfold(
+            (epochs, previous),
+            move |(mut epochs, previous)| async move {
+                let snapshot = match epochs.next().await? {
+                    Ok(_) => ValidatorMonitor::new(client).snapshot().await,
+                    Err(e) => return Some((Err(e), (epochs, previous))),
+                };
+                match snapshot {
+                    Ok(snapshot) => {
+                        let update = ValidatorSetUpdate {
+                            changes: snapshot.changes_since(&previous),
+                            snapshot: snapshot.clone(),
+                        };
+                        Some((Ok(update), (epochs, snapshot)))
+                    }
+                    Err(e) => Some((Err(e), (epochs, previous))),
+                }
+            },
+        ))
+    }
+}
@@ -0,0 +1,223 @@
+// Copyright (c) 2022, Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Linear and cliff vesting of grants, for foundations managing grants.
+//!
+//! The framework has no vesting object, only `LockedCoin`s claimable from an epoch on. A
+//! grant following a [VestingSchedule] is granted as one `LockedCoin` per tranche, each
+//! unlocking at the epoch the tranche vests, with the `create_vesting` transaction builder.
+//! The grantee reads its [VestingPosition] and claims the unlocked tranches with the
+//! `claim_vesting` transaction builder. Schedules are counted in epochs.
+
+use std::collections::BTreeSet;
+
+use futures::future::join_all;
+use move_core_types::language_storage::TypeTag;
+use serde::Deserialize;
+
+use sui_types::balance::Balance;
+use sui_types::base_types::{ObjectID, SuiAddress};
+use sui_types::id::UID;
+use sui_types::parse_sui_struct_tag;
+use sui_types::sui_system_state::EpochTimeLock;
+use sui_types::SUI_FRAMEWORK_ADDRESS;
+
+use crate::coin_type::CoinType;
+use crate::error::SuiRpcResult;
+use crate::ReadApi;
+
+/// `total` vesting linearly over `duration_epochs` from `start_epoch`, in steps of
+/// `period_epochs`, with nothing vested before the end of the cliff.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct VestingSchedule {
+    pub total: u64,
+    pub start_epoch: u64,
+    pub duration_epochs: u64,
+    pub cliff_epochs: u64,
+    pub period_epochs: u64,
+}
+
+/// A part of a grant, vested from `unlock_epoch` on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Tranche {
+    pub unlock_epoch: u64,
+    pub amount: u64,
+}
+
+impl VestingSchedule {
+    /// `total` vesting every epoch over `duration_epochs` from `start_epoch`.
+    pub fn linear(total: u64, start_epoch: u64, duration_epochs: u64) -> Self {
+        Self {
+            total,
+            start_epoch,
+            duration_epochs,
+            cliff_epochs: 0,
+            period_epochs: 1,
+        }
+    }
+
+    /// `total` vesting at once, `cliff_epochs` after `start_epoch`.
+    pub fn cliff(total: u64, start_epoch: u64, cliff_epochs: u64) -> Self {
+        Self::linear(total, start_epoch, 0).with_cliff(cliff_epochs)
+    }
+
+    /// Nothing vests before `cliff_epochs` after the start; what vested by then vests at the
+    /// end of the cliff.
+    pub fn with_cliff(mut self, cliff_epochs: u64) -> Self {
+        self.cliff_epochs = cliff_epochs;
+        self
+    }
+
+    /// Vest every `period_epochs` instead of every epoch.
+    pub fn with_period(mut self, period_epochs: u64) -> Self {
+        self.period_epochs = period_epochs.max(1);
+        self
+    }
+
+    pub fn end_epoch(&self) -> u64 {
+        self.start_epoch
+            .saturating_add(self.duration_epochs.max(self.cliff_epochs))
+    }
+
+    /// The amount vested at `epoch`, rounded down.
+    pub fn vested_at(&self, epoch: u64) -> u64 {
+        if epoch < self.start_epoch.saturating_add(self.cliff_epochs) {
+            return 0;
+        }
+        let elapsed = epoch - self.start_epoch;
+        if elapsed >= self.duration_epochs {
+            return self.total;
+        }
+        let elapsed = elapsed / self.period_epochs * self.period_epochs;
+        (self.total as u128 * elapsed as u128 / self.duration_epochs as u128) as u64
+    }
+
+    /// The amounts vesting at each epoch of the schedule, in order, adding up to `total`.
+    pub fn tranches(&self) -> Vec<Tranche> {
+        let mut epochs = BTreeSet::new();
+        let mut elapsed = 0u64;
+        while elapsed < self.duration_epochs {
+            elapsed = elapsed
+                .saturating_add(self.period_epochs)
+                .min(self.duration_epochs);
+            epochs.insert(
+                self.start_epoch
+                    .saturating_add(elapsed.max(self.cliff_epochs)),
+            );
+        }
+        epochs.insert(self.end_epoch());
+
+        let mut vested = 0;
+        epochs
+            .into_iter()
+            .filter_map(|unlock_epoch| {
+                let amount = self.vested_at(unlock_epoch) - vested;
+                vested += amount;
+                (amount > 0).then_some(Tranche {
+                    unlock_epoch,
+                    amount,
+                })
+            })
+            .collect()
+    }
+}
+
+#[derive(Deserialize)]
+struct LockedCoin {
+    id: UID,
+    balance: Balance,
+    locked_until_epoch: EpochTimeLock,
+}
+
+/// A `LockedCoin` owned by a grantee.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LockedCoinSummary {
+    pub object_id: ObjectID,
+    pub amount: u64,
+    pub unlock_epoch: u64,
+}
+
+/// The `LockedCoin`s of one coin type owned by a grantee, at `epoch`.
+#[derive(Debug, Clone)]
+pub struct VestingPosition {
+    pub epoch: u64,
+    pub locked_coins: Vec<LockedCoinSummary>,
+}
+
+impl VestingPosition {
+    /// The locked coins the grantee can claim now.
+    pub fn claimable(&self) -> Vec<ObjectID> {
+        self.locked_coins
+            .iter()
+            .filter(|coin| coin.unlock_epoch <= self.epoch)
+            .map(|coin| coin.object_id)
+            .collect()
+    }
+
+    pub fn claimable_amount(&self) -> u64 {
+        self.locked_coins
+            .iter()
+            .filter(|coin| coin.unlock_epoch <= self.epoch)
+            .map(|coin| coin.amount)
+            .sum()
+    }
+
+    /// The amount still locked.
+    pub fn unvested_amount(&self) -> u64 {
+        self.locked_coins
+            .iter()
+            .filter(|coin| coin.unlock_epoch > self.epoch)
+            .map(|coin| coin.amount)
+            .sum()
+    }
+
+    /// The epoch the next locked coin unlocks, if any is still locked.
+    pub fn next_unlock_epoch(&self) -> Option<u64> {
+        self.locked_coins
+            .iter()
+            .map(|coin| coin.unlock_epoch)
+            .filter(|epoch| *epoch > self.epoch)
+            .min()
+    }
+}
+
+impl ReadApi {
+    /// The `LockedCoin`s of `coin_type` owned by `grantee`, ordered by unlock epoch.
+    pub async fn get_vesting_position(
+        &self,
+        grantee: SuiAddress,
+        coin_type: &CoinType,
+    ) -> SuiRpcResult<VestingPosition> {
+        let object_ids = self
+            .get_objects_owned_by_address(grantee)
+            .await?
+            .into_iter()
+            .filter(|info| {
+                parse_sui_struct_tag(&info.type_).map_or(false, |tag| {
+                    tag.address == SUI_FRAMEWORK_ADDRESS
+                        && tag.module.as_str() == "locked_coin"
+                        && tag.name.as_str() == "LockedCoin"
+                        && tag.type_params == [TypeTag::Struct(coin_type.struct_tag().clone())]
+                })
+            })
+            .map(|info| info.object_id)
+            .collect::<Vec<_>>();
+        let locked_coins = join_all(object_ids.iter().map(|id| self.get_move_object(*id))).await;
+        let mut locked_coins = locked_coins
+            .into_iter()
+            .map(|locked_coin| {
+                let locked_coin: LockedCoin = locked_coin?;
+                Ok(LockedCoinSummary {
+                    object_id: *locked_coin.id.object_id(),
+                    amount: locked_coin.balance.value(),
+                    unlock_epoch: locked_coin.locked_until_epoch.epoch,
+                })
+            })
+            .collect::<anyhow::Result<Vec<_>>>()?;
+        locked_coins.sort_by_key(|coin| (coin.unlock_epoch, coin.object_id));
+        Ok(VestingPosition {
+            epoch: self.get_sui_system_state().await?.epoch,
+            locked_coins,
+        })
+    }
+}
@@ -0,0 +1,311 @@
+// Copyright (c) 2022, Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! POST events matching user-defined rules to webhook URLs.
+//!
+//! A [WebhookDispatcher] is an [EventHandler], so it is driven by an
+//! [EventIndexer](crate::indexer::EventIndexer) and inherits its at-least-once delivery: a
+//! delivery failing after all retries fails the batch, and the indexer replays it later.
+//! Receivers should deduplicate on the `X-Sui-Event-Key` header.
+//!
+//! Every request body is signed with HMAC-SHA256 using the secret of the rule, and the hex
+//! encoded signature is sent in the `X-Sui-Signature` header as `sha256=<signature>`.
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::{anyhow, bail};
+use async_trait::async_trait;
+use hmac::{Hmac, Mac};
+use reqwest::StatusCode;
+use serde::Serialize;
+use sha2::Sha256;
+use url::Url;
+
+use sui_json_rpc_types::{SuiEvent, SuiEventEnvelope, SuiEventFilter};
+use sui_types::base_types::{ObjectID, SuiAddress};
+use sui_types::event::EventType;
+
+use crate::error::{Error, SuiRpcResult};
+use crate::indexer::{EventHandler, EventKey, IndexerStore};
+use crate::runtime::{Runtime, TokioRuntime};
+
+pub const SIGNATURE_HEADER: &str = "X-Sui-Signature";
+pub const EVENT_KEY_HEADER: &str = "X-Sui-Event-Key";
+pub const RULE_HEADER: &str = "X-Sui-Webhook-Rule";
+
+/// Sends the events matching `filter` to `url`.
+#[derive(Debug)]
+pub struct WebhookRule {
+    pub name: String,
+    pub filter: SuiEventFilter,
+    pub url: Url,
+    /// Key of the HMAC signature of the requests.
+    pub secret: Vec<u8>,
+}
+
+/// The JSON body POSTed to a webhook.
+#[derive(Debug, Clone, Serialize)]
+pub struct WebhookPayload<'a> {
+    pub rule: &'a str,
+    pub key: &'a EventKey,
+    pub event: &'a SuiEventEnvelope,
+}
+
+pub struct WebhookDispatcher {
+    client: reqwest::Client,
+    rules: Vec<WebhookRule>,
+    max_attempts: u32,
+    retry_backoff: Duration,
+    runtime: Arc<dyn Runtime>,
+}
+
+impl Default for WebhookDispatcher {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl WebhookDispatcher {
+    pub fn new() -> Self {
+        Self {
+            client: reqwest::Client::new(),
+            rules: vec![],
+            max_attempts: 5,
+            retry_backoff: Duration::from_millis(500),
+            runtime: Arc::new(TokioRuntime),
+        }
+    }
+
+    pub fn with_rule(mut self, rule: WebhookRule) -> Self {
+        self.rules.push(rule);
+        self
+    }
+
+    /// Use a custom HTTP client, e.g. to set timeouts or proxies.
+    pub fn with_client(mut self, client: reqwest::Client) -> Self {
+        self.client = client;
+        self
+    }
+
+    /// Number of attempts per delivery before giving up. Defaults to 5.
+    pub fn with_max_attempts(mut self, max_attempts: u32) -> Self {
+        self.max_attempts = max_attempts.max(1);
+        self
+    }
+
+    /// Delay before the first retry, doubled after each failed attempt. Defaults to 500ms.
+    pub fn with_retry_backoff(mut self, retry_backoff: Duration) -> Self {
+        self.retry_backoff = retry_backoff;
+        self
+    }
+
+    /// Wait before retrying deliveries on `runtime`. Defaults to [TokioRuntime].
+    pub fn with_runtime(mut self, runtime: Arc<dyn Runtime>) -> Self {
+        self.runtime = runtime;
+        self
+    }
+
+    pub fn rules(&self) -> &[WebhookRule] {
+        &self.rules
+    }
+
+    /// The rules whose filter matches `event`.
+    pub fn matching_rules<'a>(
+        &'a self,
+        event: &'a SuiEventEnvelope,
+    ) -> impl Iterator<Item = &'a WebhookRule> + 'a {
+        self.rules
+            .iter()
+            .filter(move |rule| event_matches(&rule.filter, event))
+    }
+
+    /// Deliver `event` to every matching rule. Returns the number of deliveries.
+    pub async fn dispatch(&self, key: &EventKey, event: &SuiEventEnvelope) -> SuiRpcResult<usize> {
+        let mut delivered = 0;
+        for rule in self.matching_rules(event) {
+            let body = serde_json::to_vec(&WebhookPayload {
+                rule: &rule.name,
+                key,
+                event,
+            })?;
+            self.deliver(rule, key, body).await?;
+            delivered += 1;
+        }
+        Ok(delivered)
+    }
+
+    async fn deliver(
+        &self,
+        rule: &WebhookRule,
+        key: &EventKey,
+        body: Vec<u8>,
+    ) -> anyhow::Result<()> {
+        let signature = format!("sha256={}", sign_payload(&rule.secret, &body));
+        let mut backoff = self.retry_backoff;
+        let mut attempt = 1;
+        loop {
+            let result = self
+                .client
+                .post(rule.url.clone())
+                .header(reqwest::header::CONTENT_TYPE, "application/json")
+                .header(SIGNATURE_HEADER, &signature)
+                .header(EVENT_KEY_HEADER, key.to_string())
+                .header(RULE_HEADER, &rule.name)
+                .body(body.clone())
+                .send()
+                .await;
+            let error = match result {
+                Ok(response) if response.status().is_success() => return Ok(()),
+                Ok(response) if !is_retryable(response.status()) => {
+                    bail!(
+                        "Webhook [{}] rejected event [{key}] with status [{}].",
+                        rule.name,
+                        response.status()
+                    )
+                }
+                Ok(response) => anyhow!("status [{}]", response.status()),
+                Err(e) => e.into(),
+            };
+            if attempt >= self.max_attempts {
+                bail!(
+                    "Failed to deliver event [{key}] to webhook [{}] after {attempt} attempts: {error}",
+                    rule.name
+                );
+            }
+            self.runtime.sleep(backoff).await;
+            backoff *= 2;
+            attempt += 1;
+        }
+    }
+}
+
+#[async_trait]
+impl EventHandler for WebhookDispatcher {
+    fn matches(&self, event: &SuiEventEnvelope) -> bool {
+        self.matching_rules(event).next().is_some()
+    }
+
+    async fn handle(
+        &self,
+        key: &EventKey,
+        event: &SuiEventEnvelope,
+        _store: &dyn IndexerStore,
+    ) -> SuiRpcResult<()> {
+        self.dispatch(key, event).await.map(|_| ())
+    }
+}
+
+fn is_retryable(status: StatusCode) -> bool {
+    status.is_server_error()
+        || status == StatusCode::TOO_MANY_REQUESTS
+        || status == StatusCode::REQUEST_TIMEOUT
+}
+
+/// Hex encoded HMAC-SHA256 of `body` keyed with `secret`.
+pub fn sign_payload(secret: &[u8], body: &[u8]) -> String {
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret).expect("HMAC accepts keys of any size");
+    mac.update(body);
+    hex::encode(mac.finalize().into_bytes())
+}
+
+/// Check the value of the [SIGNATURE_HEADER] of a webhook request, in constant time.
+pub fn verify_signature(secret: &[u8], body: &[u8], header: &str) -> bool {
+    let signature = match header.strip_prefix("sha256=").map(hex::decode) {
+        Some(Ok(signature)) => signature,
+        _ => return false,
+    };
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret).expect("HMAC accepts keys of any size");
+    mac.update(body);
+    mac.verify_slice(&signature).is_ok()
+}
+
+/// Evaluate `filter` against an event locally, with the semantics the node applies to
+/// subscriptions.
+pub fn event_matches(filter: &SuiEventFilter, envelope: &SuiEventEnvelope) -> bool {
+    let event = &envelope.event;
+    match filter {
+        SuiEventFilter::Package(package) => package_id(event) == Some(package),
+        SuiEventFilter::Module(module) => transaction_module(event) == Some(module),
+        SuiEventFilter::MoveEventType(type_tag) => {
+            matches!(event, SuiEvent::MoveEvent { type_, .. } if type_ == type_tag)
+        }
+        SuiEventFilter::MoveEventField { path, value } => match event {
+            SuiEvent::MoveEvent {
+                fields: Some(fields),
+                ..
+            } => serde_json::to_value(fields)
+                .map(|json| json.pointer(path) == Some(value))
+                .unwrap_or_default(),
+            _ => false,
+        },
+        SuiEventFilter::SenderAddress(address) => sender(event) == Some(address),
+        SuiEventFilter::EventType(event_type) => &sui_event_type(event) == event_type,
+        SuiEventFilter::ObjectId(id) => object_id(event) == Some(id),
+        SuiEventFilter::All(filters) => filters.iter().all(|f| event_matches(f, envelope)),
+        SuiEventFilter::Any(filters) => filters.iter().any(|f| event_matches(f, envelope)),
+        SuiEventFilter::And(a, b) => event_matches(a, envelope) && event_matches(b, envelope),
+        SuiEventFilter::Or(a, b) => event_matches(a, envelope) || event_matches(b, envelope),
+    }
+}
+
+fn sui_event_type(event: &SuiEvent) -> EventType {
+    match event {
+        SuiEvent::MoveEvent { .. } => EventType::MoveEvent,
+        SuiEvent::Publish { .. } => EventType::Publish,
+        SuiEvent::TransferObject { .. } => EventType::TransferObject,
+        SuiEvent::DeleteObject { .. } => EventType::DeleteObject,
+        SuiEvent::NewObject { .. } => EventType::NewObject,
+        SuiEvent::EpochChange(_) => EventType::EpochChange,
+        SuiEvent::Checkpoint(_) => EventType::Checkpoint,
+    }
+}
+
+fn package_id(event: &SuiEvent) -> Option<&ObjectID> {
+    match event {
+        SuiEvent::MoveEvent { package_id, .. }
+        | SuiEvent::Publish { package_id, .. }
+        | SuiEvent::TransferObject { package_id, .. }
+        | SuiEvent::DeleteObject { package_id, .. }
+        | SuiEvent::NewObject { package_id, .. } => Some(package_id),
+        SuiEvent::EpochChange(_) | SuiEvent::Checkpoint(_) => None,
+    }
+}
+
+fn transaction_module(event: &SuiEvent) -> Option<&String> {
+    match event {
+        SuiEvent::MoveEvent {
+            transaction_module, ..
+        }
+        | SuiEvent::TransferObject {
+            transaction_module, ..
+        }
+        | SuiEvent::DeleteObject {
+            transaction_module, ..
+        }
+        | SuiEvent::NewObject {
+            transaction_module, ..
+        } => Some(transaction_module),
+        _ => None,
+    }
+}
+
+fn sender(event: &SuiEvent) -> Option<&SuiAddress> {
+    match event {
+        SuiEvent::MoveEvent { sender, .. }
+        | SuiEvent::Publish { sender, .. }
+        | SuiEvent::TransferObject { sender, .. }
+        | SuiEvent::DeleteObject { sender, .. }
+        | SuiEvent::NewObject { sender, .. } => Some(sender),
+        SuiEvent::EpochChange(_) | SuiEvent::Checkpoint(_) => None,
+    }
+}
+
+fn object_id(event: &SuiEvent) -> Option<&ObjectID> {
+    match event {
+        SuiEvent::TransferObject { object_id, .. }
+        | SuiEvent::DeleteObject { object_id, .. }
+        | SuiEvent::NewObject { object_id, .. } => Some(object_id),
+        _ => None,
+    }
+}
@@ -0,0 +1,130 @@
+// Copyright (c) 2022, Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Names of the objects transactions refer to, instead of IDs hardcoded through application
+//! code.
+//!
+//! [WellKnownObject]s are the objects at the same ID on every network: the Move standard
+//! library, the Sui framework and the system state. An [ObjectRegistry] adds the aliases of
+//! an application, e.g. its packages, which differ per network and are configured with the
+//! `objects` of each [NetworkConfig](crate::config::NetworkConfig).
+
+use std::collections::BTreeMap;
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+
+use anyhow::anyhow;
+use serde::{Deserialize, Serialize};
+
+use sui_types::base_types::ObjectID;
+use sui_types::{SUI_FRAMEWORK_OBJECT_ID, SUI_SYSTEM_STATE_OBJECT_ID};
+
+use crate::error::{Error, SuiRpcResult};
+
+/// 0x1: the package of the Move standard library.
+pub const MOVE_STDLIB_OBJECT_ID: ObjectID = ObjectID::from_single_byte(1);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum WellKnownObject {
+    MoveStdlib,
+    SuiFramework,
+    SuiSystemState,
+}
+
+impl WellKnownObject {
+    pub const ALL: [WellKnownObject; 3] = [
+        WellKnownObject::MoveStdlib,
+        WellKnownObject::SuiFramework,
+        WellKnownObject::SuiSystemState,
+    ];
+
+    pub const fn id(&self) -> ObjectID {
+        match self {
+            WellKnownObject::MoveStdlib => MOVE_STDLIB_OBJECT_ID,
+            WellKnownObject::SuiFramework => SUI_FRAMEWORK_OBJECT_ID,
+            WellKnownObject::SuiSystemState => SUI_SYSTEM_STATE_OBJECT_ID,
+        }
+    }
+
+    pub const fn name(&self) -> &'static str {
+        match self {
+            WellKnownObject::MoveStdlib => "std",
+            WellKnownObject::SuiFramework => "sui",
+            WellKnownObject::SuiSystemState => "system_state",
+        }
+    }
+
+    pub fn from_id(id: &ObjectID) -> Option<Self> {
+        Self::ALL.into_iter().find(|object| object.id() == *id)
+    }
+}
+
+impl Display for WellKnownObject {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+impl FromStr for WellKnownObject {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::ALL
+            .into_iter()
+            .find(|object| object.name() == s)
+            .ok_or_else(|| Error::Other(anyhow!("Unknown well-known object [{s}].")))
+    }
+}
+
+/// The aliases of the objects of a network, on top of the [WellKnownObject]s.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct ObjectRegistry {
+    aliases: BTreeMap<String, ObjectID>,
+}
+
+impl ObjectRegistry {
+    pub fn new(aliases: BTreeMap<String, ObjectID>) -> Self {
+        Self { aliases }
+    }
+
+    /// Name `id` with `alias`, shadowing a well-known object of the same name.
+    pub fn with_alias(mut self, alias: &str, id: ObjectID) -> Self {
+        self.aliases.insert(alias.to_string(), id);
+        self
+    }
+
+    pub fn aliases(&self) -> &BTreeMap<String, ObjectID> {
+        &self.aliases
+    }
+
+    /// The object named `name`, if any.
+    pub fn get(&self, name: &str) -> Option<ObjectID> {
+        self.aliases.get(name).copied().or_else(|| {
+            WellKnownObject::from_str(name)
+                .ok()
+                .map(|object| object.id())
+        })
+    }
+
+    /// The object named `name_or_id`, or with ID `name_or_id` in hex.
+    pub fn resolve(&self, name_or_id: &str) -> SuiRpcResult<ObjectID> {
+        match self.get(name_or_id) {
+            Some(id) => Ok(id),
+            None => ObjectID::from_str(name_or_id).map_err(|_| {
+                Error::Other(anyhow!(
+                    "[{name_or_id}] is neither an object alias nor an ID."
+                ))
+            }),
+        }
+    }
+
+    /// The name of `id`, its alias if it has one.
+    pub fn name_of(&self, id: &ObjectID) -> Option<&str> {
+        self.aliases
+            .iter()
+            .find(|(_, alias_id)| *alias_id == id)
+            .map(|(alias, _)| alias.as_str())
+            .or_else(|| WellKnownObject::from_id(id).map(|object| object.name()))
+    }
+}
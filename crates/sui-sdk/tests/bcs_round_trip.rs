@@ -0,0 +1,48 @@
+// Copyright (c) 2022, Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+use move_core_types::value::MoveValue;
+use proptest::prelude::*;
+
+use sui_sdk::fuzzing::{
+    arb_move_value_with_layout, arb_signature, arb_transaction, arb_transaction_data, arb_type_tag,
+};
+use sui_types::crypto::{Signature, SuiSignature};
+use sui_types::messages::{Transaction, TransactionData};
+
+proptest! {
+    #[test]
+    fn transaction_data_round_trip(data in arb_transaction_data()) {
+        let bytes = bcs::to_bytes(&data).unwrap();
+        prop_assert_eq!(&data, &bcs::from_bytes::<TransactionData>(&bytes).unwrap());
+        prop_assert_eq!(&data, &serde_json::from_value::<TransactionData>(serde_json::to_value(&data).unwrap()).unwrap());
+    }
+
+    #[test]
+    fn type_tag_round_trip(tag in arb_type_tag()) {
+        let bytes = bcs::to_bytes(&tag).unwrap();
+        prop_assert_eq!(tag, bcs::from_bytes(&bytes).unwrap());
+    }
+
+    #[test]
+    fn move_value_round_trip((layout, value) in arb_move_value_with_layout()) {
+        let bytes = value.simple_serialize().unwrap();
+        prop_assert_eq!(value, MoveValue::simple_deserialize(&bytes, &layout).unwrap());
+    }
+
+    #[test]
+    fn signature_round_trip(signature in arb_signature()) {
+        let bytes = bcs::to_bytes(&signature).unwrap();
+        prop_assert_eq!(signature.as_ref(), bcs::from_bytes::<Signature>(&bytes).unwrap().as_ref());
+        let json = serde_json::to_string(&signature).unwrap();
+        prop_assert_eq!(signature.as_ref(), serde_json::from_str::<Signature>(&json).unwrap().as_ref());
+    }
+
+    #[test]
+    fn transaction_round_trip(transaction in arb_transaction()) {
+        let data = &transaction.signed_data.data;
+        prop_assert!(transaction.signed_data.tx_signature.verify(data, data.signer()).is_ok());
+        let bytes = bcs::to_bytes(&transaction).unwrap();
+        let decoded: Transaction = bcs::from_bytes(&bytes).unwrap();
+        prop_assert_eq!(transaction.digest(), decoded.digest());
+    }
+}
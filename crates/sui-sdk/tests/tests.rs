@@ -1,15 +1,139 @@
 // Copyright (c) 2022, Mysten Labs, Inc.
 // SPDX-License-Identifier: Apache-2.0
+use std::collections::{BTreeMap, BTreeSet};
+use std::net::IpAddr;
 use std::str::FromStr;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
 
+use async_trait::async_trait;
+use futures::future::BoxFuture;
+use futures::stream::BoxStream;
+use futures::StreamExt;
+use jsonrpsee::core::client::ClientT;
+use jsonrpsee::core::server::rpc_module::SubscriptionSink;
+use jsonrpsee::rpc_params;
+use jsonrpsee::types::SubscriptionResult;
+use jsonrpsee::ws_server::{WsServerBuilder, WsServerHandle};
+use move_core_types::identifier::Identifier;
+use move_core_types::language_storage::{StructTag, TypeTag};
+use move_core_types::value::{MoveFieldLayout, MoveStructLayout, MoveTypeLayout};
+use serde_json::{json, Value};
 use sha3::{Digest, Sha3_256};
+use signature::Signer;
 use tempfile::TempDir;
+use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader};
+use tokio::net::{TcpListener, TcpStream};
 
-use sui_sdk::crypto::KeystoreType;
-use sui_types::crypto::{SignatureScheme, SuiSignatureInner};
+use sui_json_rpc::api::TransactionStreamingApiServer;
+use sui_json_rpc::streaming_api::spawn_subscription;
+use sui_json_rpc_types::{
+    OwnedObjectRef, SuiEvent, SuiEventEnvelope, SuiEventFilter, SuiExecutionStatus,
+    SuiGasCostSummary, SuiMoveAbility, SuiObject, SuiObjectInfo, SuiObjectRead, SuiParsedData,
+    SuiPastObjectRead, SuiRawData, SuiTransactionFilter, SuiTransactionResponse,
+};
+use sui_sdk::accounting::{LedgerEntry, LedgerEntryKind};
+use sui_sdk::address::{address_from_encoded_public_key, address_from_public_key, is_address_of};
+use sui_sdk::airdrop::{read_entries, write_report, AirdropEntry, AirdropItem, AirdropStatus};
+use sui_sdk::amount::{Amount, NumberFormat};
+use sui_sdk::api::EventApi;
+use sui_sdk::backpressure::{buffered, BufferConfig, OverflowPolicy, StreamMetrics};
+use sui_sdk::balance_changes::BalanceDirection;
+use sui_sdk::bulk::{AdaptiveLimit, BulkFetcher, BulkProgress};
+use sui_sdk::cache::{Cache, CacheCategory, ClientCache, LruCache};
+use sui_sdk::canary::{CanaryMetrics, CanaryResult};
+use sui_sdk::cancellation::{cancellable, BackgroundTasks, CancellationToken, ShutdownTimeout};
+use sui_sdk::circuit_breaker::{CircuitBreaker, CircuitState};
+use sui_sdk::coin_type::{coin_types_equal, normalize_coin_type, CoinType};
+use sui_sdk::config::{Config, KeystoreConfig, NetworkConfig};
+use sui_sdk::consistency::{compare, Observation};
+use sui_sdk::crypto::{aliases_path, merge_aliases, parse_keys, KeystoreType};
+use sui_sdk::cursor_store::{CursorStore, FileCursorStore, SqliteCursorStore, StreamCursor};
+use sui_sdk::deposit_tracker::{ConfirmationPolicy, Deposit, DepositTracker};
+use sui_sdk::dex::{constant_product_output, split_order, PoolQuoter};
+use sui_sdk::digest::{encoded_transaction_digest, transaction_digest};
+use sui_sdk::dns::{ConnectOptions, DnsResolver, IpPreference, StaticResolver};
+use sui_sdk::encrypted_store::{encrypt_file, is_sealed, StoreCipher};
+use sui_sdk::error::{
+    Error, ExecutionError, HttpStatusError, KeystoreError, RpcError, RpcErrorKind, SuiRpcResult,
+};
+use sui_sdk::export::{write_rows, BalanceChangeRow, EventRow, ExportFormat, ExportRow};
+use sui_sdk::fan_in::{address_filter, tag_events};
+use sui_sdk::fee_analytics::FeeReport;
+use sui_sdk::follower::{FileSequenceStore, FollowedTransaction, SequenceStore};
+use sui_sdk::gas_coins::{plan_gas_coins, GasCoinAction};
+use sui_sdk::gas_station::{AllowListPolicy, GasPool, GasStation, SponsorRequest};
+use sui_sdk::hedging::{HedgePolicy, HedgeStats, Hedger};
+use sui_sdk::idempotent::IdempotentSubmitter;
+use sui_sdk::indexer::{EventCursor, EventKey, IndexerStore};
+use sui_sdk::introspection::{abilities_str, ModuleInfo, Visibility};
+use sui_sdk::js_parity::{
+    decode_address, decode_transaction_data, encode_address, encode_transaction_data,
+    SignaturePubkeyPair,
+};
+use sui_sdk::json::SuiJsonValue;
+use sui_sdk::lazy::LazyResponse;
+use sui_sdk::local_store::LocalStore;
+use sui_sdk::move_call_validation::MoveCallValidationError;
+use sui_sdk::move_json::{bcs_to_json, json_to_bcs};
+use sui_sdk::nft::{try_render_template, DisplayError, DisplayTemplate};
+use sui_sdk::nft_media::{Media, MediaFetcher};
+use sui_sdk::object_query::{select_fields, ObjectFilter, ObjectQuery};
+use sui_sdk::object_stream::ContentDecoder;
+use sui_sdk::offline_builder;
+use sui_sdk::oracle::{OracleError, OracleLayout};
+use sui_sdk::payment_request::PaymentRequest;
+use sui_sdk::preflight::{PreflightError, TransactionLimits, TransactionStats};
+use sui_sdk::remote_signer::{InMemoryRelay, PairingProposal, WalletPairing};
+use sui_sdk::request_log::{Redactor, REDACTED};
+use sui_sdk::runtime::{spawn_blocking, timeout, Runtime, TokioRuntime};
+use sui_sdk::scheduler::dependencies;
+use sui_sdk::signing_policy::{PolicyViolation, SigningPolicy};
+use sui_sdk::spending_guard::{declared_outgoing_amount, CoSigner, LimitExceeded, SpendingGuard};
+use sui_sdk::sqlite_store::{SqliteStore, MIGRATIONS};
+use sui_sdk::system_state::SystemStateSummary;
+use sui_sdk::test_vectors::{check, key_vector, vectors};
+use sui_sdk::tracer::{AbortLocation, Trace, TraceStep};
+use sui_sdk::tracking::{TrackOptions, TransactionStatus};
+use sui_sdk::transport::{
+    Compression, HttpTransport, HyperTransport, PoolOptions, TransportClient,
+};
+use sui_sdk::validator_monitor::{
+    ValidatorChange, ValidatorRecord, ValidatorSetSnapshot, ValidatorStatus,
+};
+use sui_sdk::vesting::{LockedCoinSummary, VestingPosition, VestingSchedule};
+use sui_sdk::webhook::{event_matches, sign_payload, verify_signature};
+use sui_sdk::well_known::{ObjectRegistry, WellKnownObject};
+use sui_sdk::{ClientOptions, SuiClient, SuiClientBuilder};
+use sui_types::balance::{Balance, Supply};
+use sui_types::coin::Coin;
+use sui_types::crypto::{
+    get_key_pair, AccountKeyPair, AccountPublicKey, EncodeDecodeBase64, Signature, SignatureScheme,
+    SuiKeyPair, SuiSignature, SuiSignatureInner, ToFromBytes,
+};
+use sui_types::error::SuiError;
+use sui_types::event::{EventType, TransferType};
+use sui_types::gas::MAX_GAS_BUDGET;
+use sui_types::gas_coin::GasCoin;
+use sui_types::id::UID;
+use sui_types::messages::{
+    CallArg, MoveCall, ObjectArg, Pay, SingleTransactionKind, Transaction, TransactionData,
+    TransactionKind, TransferSui,
+};
+use sui_types::object::{MoveObject, Object, Owner};
+use sui_types::sui_serde::{Base64, Encoding};
+use sui_types::sui_system_state::{
+    Delegation, EpochTimeLock, MoveOption, PendingDelegationEntry, StakedSui, StakingPool,
+    SuiSystemState, SystemParameters, Validator, ValidatorMetadata, ValidatorSet,
+};
 use sui_types::{
-    base_types::{SuiAddress, SUI_ADDRESS_LENGTH},
+    base_types::{
+        ObjectDigest, ObjectID, ObjectRef, SequenceNumber, SuiAddress, TransactionDigest,
+        SUI_ADDRESS_LENGTH,
+    },
     crypto::Ed25519SuiSignature,
+    SUI_SYSTEM_STATE_OBJECT_ID,
 };
 #[test]
 fn mnemonic_test() {
@@ -58,3 +182,3992 @@ fn sui_wallet_address_mnemonic_test() -> Result<(), anyhow::Error> {
 
     Ok(())
 }
+
+#[test]
+fn sign_all_test() {
+    let keystore = KeystoreType::InMem(3).init().unwrap();
+    let unknown = SuiAddress::random_for_testing_only();
+    let requests = keystore
+        .addresses()
+        .into_iter()
+        .chain([unknown])
+        .flat_map(|address| (0..4u8).map(move |i| (address, vec![i; 32])))
+        .collect::<Vec<_>>();
+
+    let signatures = keystore.sign_all(requests.clone());
+    assert_eq!(requests.len(), signatures.len());
+    for ((address, msg), signature) in requests.iter().zip(signatures) {
+        match keystore.sign(address, msg) {
+            Ok(expected) => assert_eq!(expected, signature.unwrap()),
+            Err(_) => assert!(*address == unknown && signature.is_err()),
+        }
+    }
+}
+
+#[test]
+fn keystore_aliases_test() -> Result<(), anyhow::Error> {
+    let temp_dir = TempDir::new()?;
+    let keystore_path = temp_dir.path().join("sui.keystore");
+    let mut keystore = KeystoreType::File(keystore_path.clone()).init()?;
+    let (first, _, _) = keystore.generate_new_key(SignatureScheme::ED25519, None)?;
+    let (second, _, _) = keystore.generate_new_key(SignatureScheme::ED25519, None)?;
+    keystore.set_alias(&first, "main")?;
+    assert!(keystore.set_alias(&second, "main").is_err());
+
+    // The CLI names the second key "deploy" while we rename the first one "deploy".
+    let second_key = keystore
+        .keys()
+        .into_iter()
+        .find(|key| SuiAddress::from(key) == second)
+        .unwrap();
+    let first_key = keystore
+        .keys()
+        .into_iter()
+        .find(|key| SuiAddress::from(key) == first)
+        .unwrap();
+    std::fs::write(
+        aliases_path(&keystore_path),
+        json!([
+            {"alias": "main", "public_key_base64": first_key.encode_base64()},
+            {"alias": "deploy", "public_key_base64": second_key.encode_base64()},
+        ])
+        .to_string(),
+    )?;
+    keystore.set_alias(&first, "deploy")?;
+    assert_eq!(Some("deploy-2".to_string()), keystore.alias(&second));
+
+    let keystore = KeystoreType::File(keystore_path).init()?;
+    assert_eq!(Some("deploy".to_string()), keystore.alias(&first));
+    assert_eq!(Some(second), keystore.address_by_alias("deploy-2"));
+
+    let ours = [(first, "a".to_string())].into_iter().collect();
+    let theirs = [(first, "b".to_string()), (second, "a".to_string())]
+        .into_iter()
+        .collect();
+    let merged = merge_aliases(&ours, &theirs);
+    assert_eq!("a", merged[&first]);
+    assert_eq!("a-2", merged[&second]);
+    Ok(())
+}
+
+#[test]
+fn keystore_blob_test() -> Result<(), anyhow::Error> {
+    let keys = (0..2)
+        .map(|_| SuiKeyPair::Ed25519SuiKeyPair(get_key_pair().1).encode_base64())
+        .collect::<Vec<_>>();
+    let file = serde_json::to_string(&keys)?;
+    assert_eq!(2, parse_keys(&file)?.len());
+    assert_eq!(2, parse_keys(&Base64::encode(&file))?.len());
+    assert_eq!(2, parse_keys(&format!("{}\n{}\n", keys[0], keys[1]))?.len());
+    assert!(parse_keys("not a key").is_err());
+
+    std::env::set_var("SUI_SDK_KEYSTORE_BLOB_TEST", &keys[0]);
+    let keystore = KeystoreType::Env("SUI_SDK_KEYSTORE_BLOB_TEST".to_string()).init()?;
+    assert_eq!(
+        parse_keys(&keys[0])?.into_keys().collect::<Vec<_>>(),
+        keystore.addresses()
+    );
+    assert!(KeystoreType::Env("SUI_SDK_KEYSTORE_UNSET".to_string())
+        .init()
+        .is_err());
+    Ok(())
+}
+
+#[test]
+fn read_only_keystore_test() {
+    let mut keystore = KeystoreType::InMem(2).init().unwrap();
+    let addresses = keystore.addresses();
+    keystore.remove_key(&addresses[1]).unwrap();
+    assert_eq!(1, keystore.addresses().len());
+
+    let mut keystore = keystore.into_read_only();
+    assert!(keystore.sign(&addresses[0], b"message").is_ok());
+    assert!(matches!(
+        keystore.add_key(SuiKeyPair::Ed25519SuiKeyPair(get_key_pair().1)),
+        Err(KeystoreError::ReadOnly)
+    ));
+    assert!(matches!(
+        keystore.remove_key(&addresses[0]),
+        Err(KeystoreError::ReadOnly)
+    ));
+    assert!(matches!(
+        keystore.generate_new_key(SignatureScheme::ED25519, None),
+        Err(KeystoreError::ReadOnly)
+    ));
+    assert_eq!(vec![addresses[0]], keystore.addresses());
+}
+
+#[test]
+fn signing_policy_test() -> Result<(), anyhow::Error> {
+    let keystore = KeystoreType::InMem(1).init()?;
+    let sender = keystore.addresses()[0];
+    let friend = SuiAddress::random_for_testing_only();
+    let stranger = SuiAddress::random_for_testing_only();
+    let object = |id| (id, SequenceNumber::from(1), ObjectDigest::random());
+    let gas = object(ObjectID::random());
+    let package = object(sui_types::SUI_FRAMEWORK_OBJECT_ID);
+    let call = |function: &str| {
+        TransactionData::new_move_call(
+            sender,
+            package,
+            Identifier::new("devnet_nft").unwrap(),
+            Identifier::new(function).unwrap(),
+            vec![],
+            gas,
+            vec![],
+            1000,
+        )
+    };
+
+    let policy = SigningPolicy::default()
+        .deny_function(package.0, "devnet_nft", "burn")
+        .with_address_book([friend]);
+    let to_friend = TransactionData::new_transfer_sui(friend, sender, Some(1), gas, 1000);
+    assert!(policy.sign(&keystore, &to_friend).is_ok());
+    let to_self = TransactionData::new_pay(sender, vec![gas], vec![sender], vec![1], gas, 1000);
+    assert_eq!(Ok(()), policy.check(&to_self));
+    let to_stranger =
+        TransactionData::new_transfer(stranger, object(ObjectID::random()), sender, gas, 1000);
+    assert_eq!(
+        Err(PolicyViolation::UnknownRecipient {
+            recipient: stranger
+        }),
+        policy.check(&to_stranger)
+    );
+    assert!(policy.sign(&keystore, &to_stranger).is_err());
+    assert_eq!(Ok(()), policy.check(&call("mint")));
+    assert!(matches!(
+        policy.check(&call("burn")),
+        Err(PolicyViolation::DeniedFunction { .. })
+    ));
+    assert!(matches!(
+        policy
+            .allow_only_packages([ObjectID::random()])
+            .check(&call("mint")),
+        Err(PolicyViolation::PackageNotAllowed { .. })
+    ));
+    Ok(())
+}
+
+struct ApproveUpTo(u64);
+
+#[async_trait]
+impl CoSigner for ApproveUpTo {
+    async fn approve(&self, request: &LimitExceeded) -> SuiRpcResult<bool> {
+        Ok(request.amount <= self.0)
+    }
+}
+
+#[tokio::test]
+async fn spending_guard_test() -> Result<(), anyhow::Error> {
+    let dir = TempDir::new()?;
+    let path = dir.path().join("spending.json");
+    let address = SuiAddress::random_for_testing_only();
+    let hour = Duration::from_secs(3600);
+    let guard = SpendingGuard::new(FileCursorStore::new(path.clone())).with_limit(hour, 100);
+
+    let start = 1_000_000;
+    guard.authorize_at(address, 60, start).await?;
+    let error = guard
+        .authorize_at(address, 50, start + 1000)
+        .await
+        .unwrap_err();
+    let exceeded = match &error {
+        Error::Other(error) => error.downcast_ref::<LimitExceeded>().unwrap(),
+        _ => panic!("Expected a limit error, got {error:?}."),
+    };
+    assert_eq!(60, exceeded.spent);
+    // The first spend leaves the window after an hour.
+    guard.authorize_at(address, 50, start + 3_600_000).await?;
+
+    // Spends are persisted, and spends over the limit can be co-signed.
+    let guard = SpendingGuard::new(FileCursorStore::new(path))
+        .with_limit(hour, 100)
+        .with_cosigner(ApproveUpTo(80));
+    guard.authorize_at(address, 80, start + 3_600_001).await?;
+    assert!(guard
+        .authorize_at(address, 90, start + 3_600_002)
+        .await
+        .is_err());
+
+    let keystore = KeystoreType::InMem(1).init()?;
+    let sender = keystore.addresses()[0];
+    let (gas, coin, other_coin) = (object_ref(), object_ref(), object_ref());
+    let sui_coins = BTreeSet::from([gas.0, coin.0]);
+    // Payments count what they send to others, plus the most the gas can cost.
+    let pay = TransactionData::new_pay(
+        sender,
+        vec![coin],
+        vec![address, sender],
+        vec![5, 7],
+        gas,
+        1000,
+    );
+    assert_eq!(Some(1005), declared_outgoing_amount(&pay, &sui_coins));
+    let pay = TransactionData::new_pay(sender, vec![other_coin], vec![address], vec![5], gas, 10);
+    assert_eq!(None, declared_outgoing_amount(&pay, &sui_coins));
+    let transfer = TransactionData::new_transfer_sui(address, sender, None, gas, 1000);
+    assert_eq!(None, declared_outgoing_amount(&transfer, &sui_coins));
+
+    // A Move call may move any coin of the sender, so it is never signed for free.
+    let call = TransactionData::new_move_call(
+        sender,
+        object_ref(),
+        Identifier::new("pay").unwrap(),
+        Identifier::new("split_and_transfer").unwrap(),
+        vec![],
+        gas,
+        vec![],
+        10,
+    );
+    assert_eq!(None, declared_outgoing_amount(&call, &sui_coins));
+    let guard = SpendingGuard::new(FileCursorStore::new(dir.path().join("sign.json")))
+        .with_limit(hour, 100);
+    assert!(guard.sign(&keystore, &call, &sui_coins).await.is_err());
+    assert_eq!(0, guard.spent(sender, hour).await?);
+    let pay = TransactionData::new_pay(sender, vec![coin], vec![address], vec![50], gas, 10);
+    guard.sign(&keystore, &pay, &sui_coins).await?;
+    assert_eq!(60, guard.spent(sender, hour).await?);
+    assert!(guard.sign(&keystore, &pay, &sui_coins).await.is_err());
+    Ok(())
+}
+
+struct TestPool {
+    input_reserve: u64,
+    output_reserve: u64,
+}
+
+impl PoolQuoter for TestPool {
+    fn pool_id(&self) -> ObjectID {
+        ObjectID::ZERO
+    }
+
+    fn quote(&self, amount_in: u64) -> u64 {
+        constant_product_output(amount_in, self.input_reserve, self.output_reserve, 30)
+    }
+
+    fn swap_call(&self, _coin: ObjectRef) -> MoveCall {
+        unimplemented!()
+    }
+}
+
+#[test]
+fn dex_split_order_test() {
+    // 1000 in a pool of 10_000 / 10_000 with a 0.3% fee.
+    assert_eq!(906, constant_product_output(1000, 10_000, 10_000, 30));
+    assert_eq!(0, constant_product_output(1000, 0, 0, 30));
+
+    let pools: Vec<Box<dyn PoolQuoter>> = vec![
+        Box::new(TestPool {
+            input_reserve: 10_000,
+            output_reserve: 10_000,
+        }),
+        Box::new(TestPool {
+            input_reserve: 30_000,
+            output_reserve: 30_000,
+        }),
+    ];
+    let amounts = split_order(&pools, 4000, 20);
+    assert_eq!(4000, amounts.iter().sum::<u64>());
+    // The deeper pool takes most of the order.
+    assert!(amounts[1] > 2 * amounts[0]);
+    let split: u64 = pools
+        .iter()
+        .zip(&amounts)
+        .map(|(pool, amount)| pool.quote(*amount))
+        .sum();
+    assert!(split > pools[1].quote(4000));
+}
+
+#[test]
+fn object_content_decoder_test() -> Result<(), anyhow::Error> {
+    let content = (0..=255u8).cycle().take(1000).collect::<Vec<_>>();
+    let response = json!({
+        "jsonrpc": "2.0",
+        "id": 1,
+        "result": {
+            "status": "Exists",
+            "details": {"data": {
+                "dataType": "moveObject",
+                "type": "0x2::devnet_nft::DevNetNFT",
+                "has_public_transfer": true,
+                "version": 1,
+                "bcs_bytes": Base64::encode(&content),
+            }},
+        },
+    })
+    .to_string();
+    let mut decoder = ContentDecoder::default();
+    let mut decoded = vec![];
+    for chunk in response.as_bytes().chunks(7) {
+        decoded.extend(decoder.feed(chunk)?);
+    }
+    decoder.finish()?;
+    assert_eq!(content, decoded);
+
+    let mut decoder = ContentDecoder::default();
+    decoder.feed(br#"{"jsonrpc":"2.0","id":1,"error":{"code":-32000,"message":"no"}}"#)?;
+    assert!(decoder.finish().is_err());
+    Ok(())
+}
+
+#[test]
+fn oracle_price_test() -> Result<(), anyhow::Error> {
+    let id = ObjectID::random();
+    let fields = json!({
+        "price_info": {
+            "price": "12345000000",
+            "conf": 2500000,
+            "expo": {"negative": true, "magnitude": 8},
+            "timestamp": 1_600_000_000u64,
+        }
+    });
+    let layout = OracleLayout::new("price_info.price")
+        .with_exponent("price_info.expo")
+        .with_confidence("price_info.conf")
+        .with_timestamp_secs("price_info.timestamp");
+    let price = layout.parse(id, &fields)?;
+    assert_eq!(-8, price.exponent);
+    assert_eq!(Some(12345), price.normalized(2));
+    assert_eq!(Some(123_450_000_000_000_000_000), price.normalized(18));
+    assert_eq!(Some(25), price.normalized_confidence(3));
+    assert_eq!(Some(1_600_000_000_000), price.timestamp_ms);
+    assert!(matches!(
+        price.check_age(Duration::from_secs(60)),
+        Err(OracleError::Stale { .. })
+    ));
+
+    let price = OracleLayout::new("price_info.conf")
+        .with_decimals(6)
+        .parse(id, &fields)?;
+    assert!((price.to_f64() - 2.5).abs() < 1e-9);
+    assert_eq!(
+        Err(OracleError::MissingTimestamp { object_id: id }),
+        price.check_age(Duration::from_secs(60))
+    );
+    assert!(OracleLayout::new("missing").parse(id, &fields).is_err());
+    Ok(())
+}
+
+#[test]
+fn vesting_schedule_test() {
+    let schedule = VestingSchedule::linear(1000, 5, 10)
+        .with_cliff(3)
+        .with_period(2);
+    assert_eq!(0, schedule.vested_at(7));
+    assert_eq!(200, schedule.vested_at(8));
+    assert_eq!(400, schedule.vested_at(10));
+    assert_eq!(1000, schedule.vested_at(15));
+    let tranches = schedule
+        .tranches()
+        .iter()
+        .map(|tranche| (tranche.unlock_epoch, tranche.amount))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        vec![(8, 200), (9, 200), (11, 200), (13, 200), (15, 200)],
+        tranches
+    );
+
+    let tranches = VestingSchedule::cliff(1000, 5, 4).tranches();
+    assert_eq!(1, tranches.len());
+    assert_eq!((9, 1000), (tranches[0].unlock_epoch, tranches[0].amount));
+}
+
+#[tokio::test]
+async fn hedger_test() {
+    let policy = HedgePolicy::default()
+        .with_initial_delay(Duration::from_millis(10))
+        .with_budget(0.0, 1);
+    let hedger = Hedger::new(policy, Arc::new(TokioRuntime));
+    let slow = async {
+        tokio::time::sleep(Duration::from_secs(5)).await;
+        Ok::<_, ()>("primary")
+    };
+    assert_eq!(
+        Ok("hedge"),
+        hedger.run(slow, || async { Ok("hedge") }).await
+    );
+
+    // The budget allowed a single hedged request.
+    let slow = async {
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        Ok::<_, ()>("primary")
+    };
+    assert_eq!(
+        Ok("primary"),
+        hedger.run(slow, || async { Ok("hedge") }).await
+    );
+    let fast = async { Ok::<_, ()>("primary") };
+    assert_eq!(
+        Ok("primary"),
+        hedger.run(fast, || async { Ok("hedge") }).await
+    );
+    assert_eq!(
+        HedgeStats {
+            requests: 3,
+            hedged: 1,
+            hedge_wins: 1,
+        },
+        hedger.stats()
+    );
+}
+
+#[tokio::test]
+async fn dns_resolver_test() -> Result<(), anyhow::Error> {
+    let v4: IpAddr = "10.0.0.1".parse()?;
+    let v6: IpAddr = "fd00::1".parse()?;
+    let resolver = StaticResolver::default()
+        .with_host("Fullnode.internal", [v6, v4])
+        .without_fallback();
+    let addresses = resolver.resolve("fullnode.internal").await?;
+    assert_eq!(vec![v6, v4], addresses);
+    assert!(resolver.resolve("other.internal").await.is_err());
+
+    assert_eq!(
+        vec![v4, v6],
+        IpPreference::PreferIpv4.sort(addresses.clone())
+    );
+    assert_eq!(vec![v6], IpPreference::Ipv6Only.sort(addresses.clone()));
+    assert_eq!(vec![v4], IpPreference::Ipv4Only.sort(addresses));
+    Ok(())
+}
+
+#[test]
+fn request_log_redaction_test() {
+    let redactor = Redactor::default().with_key("api_key");
+    let params = json!(["dGVzdA==", "ED25519", "c2ln", "a2V5", "WaitForEffectsCert"]);
+    assert_eq!(
+        json!([
+            "dGVzdA==",
+            "ED25519",
+            REDACTED,
+            REDACTED,
+            "WaitForEffectsCert"
+        ]),
+        redactor.redact("sui_executeTransaction", params.clone())
+    );
+    assert_eq!(params, redactor.redact("sui_getObject", params.clone()));
+    assert_eq!(
+        json!([{ "owner": "0x1", "auth": { "API_KEY": REDACTED } }]),
+        redactor.redact(
+            "sui_getObject",
+            json!([{ "owner": "0x1", "auth": { "API_KEY": "secret" } }])
+        )
+    );
+}
+
+/// An event API without events.
+struct NoEvents;
+
+#[async_trait]
+impl EventApi for NoEvents {
+    async fn subscribe_event(
+        &self,
+        _filter: SuiEventFilter,
+    ) -> SuiRpcResult<BoxStream<'static, SuiRpcResult<SuiEventEnvelope>>> {
+        Ok(futures::stream::empty().boxed())
+    }
+
+    async fn subscribe_transaction(
+        &self,
+        _filter: SuiTransactionFilter,
+    ) -> SuiRpcResult<BoxStream<'static, SuiRpcResult<SuiTransactionResponse>>> {
+        Err(Error::Unsupported("NoEvents"))
+    }
+
+    async fn get_events_by_timerange(
+        &self,
+        _count: usize,
+        _start_time: u64,
+        _end_time: u64,
+    ) -> SuiRpcResult<Vec<SuiEventEnvelope>> {
+        Ok(vec![])
+    }
+
+    async fn get_events_by_move_event_struct_name(
+        &self,
+        _move_event_struct_name: String,
+        _count: usize,
+        _start_time: u64,
+        _end_time: u64,
+    ) -> SuiRpcResult<Vec<SuiEventEnvelope>> {
+        Ok(vec![])
+    }
+}
+
+#[tokio::test]
+async fn event_api_trait_object_test() -> Result<(), anyhow::Error> {
+    let api: &dyn EventApi = &NoEvents;
+    assert!(api.get_events_by_timerange(10, 0, 1).await?.is_empty());
+    let events = api.subscribe_event(SuiEventFilter::All(vec![])).await?;
+    assert_eq!(0, events.count().await);
+    assert!(matches!(
+        api.subscribe_transaction(SuiTransactionFilter::Any).await,
+        Err(Error::Unsupported(_))
+    ));
+    Ok(())
+}
+
+#[test]
+fn amount_conversion_test() -> Result<(), anyhow::Error> {
+    let amount = Amount::from_human("1.5", 9)?;
+    assert_eq!(1_500_000_000, amount.value());
+    assert_eq!("1.5 SUI", amount.to_string());
+    assert_eq!("0.000000001 SUI", Amount::from_mist(1).to_string());
+    assert_eq!("42 (3 decimals)", Amount::from_human("42", 3)?.to_string());
+    assert!(Amount::from_human("0.0001", 3).is_err());
+    assert!(Amount::from_human("1.2.3", 9).is_err());
+    assert!(Amount::from_human("18446744073.709551616", 9).is_err());
+
+    // Amounts of different coins neither add up nor compare.
+    let usdc = Amount::from_base_units(1_000_000, 6);
+    assert_eq!(None, usdc.checked_add(Amount::from_mist(1)));
+    assert_eq!(None, usdc.partial_cmp(&Amount::from_mist(1)));
+    assert!(usdc < Amount::from_base_units(1_000_001, 6));
+    assert_eq!(
+        Some(Amount::from_base_units(2_000_000, 6)),
+        usdc.checked_add(usdc)
+    );
+    assert_eq!(
+        "1,000,000.5",
+        Amount::from_human("1000000.5", 6)?.format(&NumberFormat::EN)
+    );
+    Ok(())
+}
+
+#[test]
+fn sui_amount_test() -> Result<(), anyhow::Error> {
+    let amount: Amount = "1.5 SUI".parse()?;
+    assert_eq!(1_500_000_000, amount.value());
+    assert_eq!(amount, "1_500_000_000 mist".parse()?);
+    assert_eq!("1.5 SUI", amount.to_string());
+    assert!("1.5".parse::<Amount>().is_err());
+    assert!("0.0000000001 SUI".parse::<Amount>().is_err());
+    assert!("18446744074 SUI".parse::<Amount>().is_err());
+
+    let amount = Amount::from_sui(1_234_567)
+        .unwrap()
+        .saturating_add(Amount::from_mist(5));
+    assert_eq!("1,234,567.000000005", amount.format(&NumberFormat::EN));
+    assert_eq!(
+        "1.234.567,000000005",
+        amount.format(&NumberFormat::for_locale("de_DE"))
+    );
+    assert_eq!(None, Amount::MAX.checked_add(Amount::from_mist(1)));
+    assert_eq!(Amount::ZERO, Amount::ZERO.saturating_sub(amount));
+    assert_eq!(Amount::MAX, Amount::saturating_from_mist(i128::MAX));
+    assert_eq!(
+        Some(Amount::from_mist(7)),
+        Amount::from_coin("0x2::sui::SUI", 7)
+    );
+    Ok(())
+}
+
+#[test]
+fn amount_display_round_trip_test() -> Result<(), anyhow::Error> {
+    for amount in [
+        Amount::ZERO,
+        Amount::from_mist(1),
+        Amount::from_mist(1_500_000_000),
+        Amount::MAX,
+        Amount::from_base_units(1_000_005, 6),
+        Amount::from_base_units(42, 0),
+        Amount::from_base_units(u64::MAX, 18),
+    ] {
+        assert_eq!(amount, amount.to_string().parse()?, "{amount}");
+    }
+    assert_eq!(
+        "1.000005 (6 decimals)",
+        Amount::from_base_units(1_000_005, 6).to_string()
+    );
+    assert!("1.5 (x decimals)".parse::<Amount>().is_err());
+    assert!("1.0000005 (6 decimals)".parse::<Amount>().is_err());
+    Ok(())
+}
+
+#[test]
+fn coin_type_test() -> Result<(), anyhow::Error> {
+    let sui: CoinType = "0x0000000000000000000000000000000000000002::sui::SUI".parse()?;
+    assert!(sui.is_sui());
+    assert_eq!("0x2::sui::SUI", sui.to_string());
+    assert_eq!(sui, "sui::sui::SUI".parse()?);
+    assert_eq!(
+        "0x0000000000000000000000000000000000000002::sui::SUI",
+        sui.to_long_string()
+    );
+    assert_eq!(
+        Some(sui.clone()),
+        CoinType::from_object_type("0x2::coin::Coin<0x2::sui::SUI>")
+    );
+    assert_eq!(None, CoinType::from_object_type("0x2::sui::SUI"));
+
+    let lp: CoinType = "0xabc::pool::LP<0x2::sui::SUI,0x00def::usdc::USDC>".parse()?;
+    assert_eq!("pool", lp.module());
+    assert_eq!(2, lp.type_params().len());
+    assert_eq!(
+        "0xabc::pool::LP<0x2::sui::SUI, 0xdef::usdc::USDC>",
+        normalize_coin_type(&lp.to_long_string())?
+    );
+    assert!(coin_types_equal("0xdef::usdc::USDC", " 0x0def::usdc::USDC"));
+    assert!(!coin_types_equal("0xdef::usdc::USDC", "0xdef::usdc::USDT"));
+    assert!("0x2::sui".parse::<CoinType>().is_err());
+    Ok(())
+}
+
+#[test]
+fn address_derivation_test() -> Result<(), anyhow::Error> {
+    let vectors: serde_json::Value =
+        serde_json::from_str(include_str!("data/sui_js_vectors.json"))?;
+    for case in vectors["addresses"].as_array().unwrap() {
+        let public_key = Base64::decode(case["pub_key"].as_str().unwrap())?;
+        let address = address_from_public_key(SignatureScheme::ED25519, &public_key)?;
+        assert_eq!(case["address"], encode_address(&address));
+        let mut flagged = vec![SignatureScheme::ED25519.flag()];
+        flagged.extend(&public_key);
+        assert_eq!(
+            address,
+            address_from_encoded_public_key(&Base64::encode(flagged))?
+        );
+    }
+
+    // The compressed secp256k1 generator point.
+    let address =
+        address_from_encoded_public_key("AQJ5vmZ++dy7rFWgYpXOhwsHApv82y3OKNlZ8oFbFvgXmA==")?;
+    assert_eq!(
+        "6458f7674c0b0261495bd7325fa0d0c11d2ce144",
+        encode_address(&address)
+    );
+    assert!(address_from_public_key(SignatureScheme::ED25519, &[0; 31]).is_err());
+    assert!(address_from_encoded_public_key("").is_err());
+
+    let keypair = SuiKeyPair::from_str(vectors["keypair"].as_str().unwrap()).unwrap();
+    let sender = decode_address(vectors["address"].as_str().unwrap())?;
+    assert!(is_address_of(&sender, &keypair.public()));
+    assert!(!is_address_of(
+        &SuiAddress::random_for_testing_only(),
+        &keypair.public()
+    ));
+    Ok(())
+}
+
+#[test]
+fn test_vectors_test() -> Result<(), anyhow::Error> {
+    let mut vectors = vectors();
+    check(&vectors)?;
+
+    let key = vectors.keys[0].clone();
+    assert_eq!(key, key_vector(&key.mnemonic, &key.scheme, &key.path)?);
+    vectors.keys[1].address = key.address;
+    vectors.transactions[0].digest = vectors.transactions[1].digest.clone();
+    let error = check(&vectors).unwrap_err().to_string();
+    assert!(error.starts_with("2 test vectors do not match"));
+    Ok(())
+}
+
+#[test]
+fn payment_request_uri_test() -> Result<(), anyhow::Error> {
+    let recipient = SuiAddress::from_str("0x1a4623343cd42be47d67314fce0ad042f3c82685")?;
+    let request = PaymentRequest::sui(recipient, 1_000)
+        .with_memo("order #42 & more")
+        .with_expiry_ms(1_700_000_000_000);
+    let uri = request.to_uri();
+    assert!(uri.starts_with("sui:0x1a4623343cd42be47d67314fce0ad042f3c82685?"));
+    assert_eq!(request, PaymentRequest::from_uri(&uri)?);
+
+    let request: PaymentRequest =
+        "sui:0x1a4623343cd42be47d67314fce0ad042f3c82685?amount=5".parse()?;
+    assert_eq!("0x2::sui::SUI", request.coin_type);
+    assert_eq!(5, request.amount);
+    assert!(PaymentRequest::from_uri("sui:0x1a4623343cd42be47d67314fce0ad042f3c82685").is_err());
+    assert!(PaymentRequest::from_uri("http://example.com?amount=5").is_err());
+    assert!(request.to_qr_code().is_ok());
+    Ok(())
+}
+
+#[tokio::test]
+async fn remote_signer_pairing_test() -> Result<(), anyhow::Error> {
+    let relay = InMemoryRelay::default();
+    let account = SuiAddress::from_str("0x1a4623343cd42be47d67314fce0ad042f3c82685")?;
+
+    let proposal = PairingProposal::new(Duration::from_secs(60));
+    let pairing = WalletPairing::from_uri(&proposal.uri())?;
+    let wallet = pairing
+        .approve(&relay, vec![account], Duration::from_secs(60))
+        .await?;
+    let dapp = proposal.await_session(&relay).await?;
+    assert_eq!(&[account], dapp.accounts());
+    assert_eq!(wallet.expiry_ms(), dapp.expiry_ms());
+
+    dapp.disconnect(&relay).await?;
+    assert!(wallet.next_request(&relay).await?.is_none());
+
+    let proposal = PairingProposal::new(Duration::from_secs(60));
+    WalletPairing::from_uri(&proposal.uri())?
+        .reject(&relay, "not now")
+        .await?;
+    assert!(proposal.await_session(&relay).await.is_err());
+    Ok(())
+}
+
+#[test]
+fn gas_pool_reservation_test() {
+    let pool = GasPool::default();
+    let small = (
+        ObjectID::random(),
+        SequenceNumber::new(),
+        ObjectDigest::random(),
+    );
+    let large = (
+        ObjectID::random(),
+        SequenceNumber::new(),
+        ObjectDigest::random(),
+    );
+    pool.add_coin(small, 100);
+    pool.add_coin(large, 1_000);
+
+    let reservation = pool.reserve(50, Duration::from_secs(60)).unwrap();
+    assert_eq!(small, reservation.coin);
+    assert_eq!(1_000, pool.available_balance());
+    assert!(pool.reserve(2_000, Duration::from_secs(60)).is_none());
+
+    let used = (small.0, SequenceNumber::from(1), ObjectDigest::random());
+    pool.release(reservation.id, used, 40);
+    assert_eq!(1_040, pool.available_balance());
+    assert_eq!(used, pool.reserve(40, Duration::ZERO).unwrap().coin);
+    assert_eq!(vec![small.0], pool.expire_reservations());
+    assert_eq!(0, pool.reserved_count());
+}
+
+#[test]
+fn gas_station_test() -> Result<(), anyhow::Error> {
+    let keystore = KeystoreType::InMem(1).init()?;
+    let sponsor = keystore.addresses()[0];
+    let sender = SuiAddress::random_for_testing_only();
+    let request = |sender, gas_budget| SponsorRequest {
+        sender,
+        kind: TransactionKind::Single(offline_builder::transfer_object_call(object_ref(), sender)),
+        gas_budget,
+    };
+    let policy = AllowListPolicy {
+        senders: Some(BTreeSet::from([sender])),
+        max_gas_budget: Some(10_000),
+    };
+    let station = GasStation::new(sponsor, keystore, policy)
+        .with_funding_gas_budget(100)
+        .with_gas_price(2);
+    let coin = object_ref();
+    station.pool().add_coin(coin, 30_000);
+
+    // The policy refuses other senders and larger budgets.
+    let other = SuiAddress::random_for_testing_only();
+    assert!(station.sponsor(&request(other, 1_000)).is_err());
+    assert!(station.sponsor(&request(sender, 10_001)).is_err());
+    assert_eq!(
+        2,
+        station.metrics().rejected_requests.load(Ordering::Relaxed)
+    );
+
+    // The gas budget is sent at the gas price, and the coin also pays the funding gas.
+    let sponsorship = station.sponsor(&request(sender, 5_000))?;
+    assert_eq!(10_000, sponsorship.amount);
+    assert_eq!(coin, sponsorship.reservation.coin);
+    let data = &sponsorship.transaction.signed_data.data;
+    assert_eq!(
+        (sponsor, 100, 2),
+        (data.signer(), data.gas_budget, data.gas_price)
+    );
+    assert!(matches!(
+        data.kind,
+        TransactionKind::Single(SingleTransactionKind::TransferSui(TransferSui {
+            recipient,
+            amount: Some(10_000)
+        })) if recipient == sender
+    ));
+    // The coin stays reserved until the funding transaction is confirmed.
+    assert_eq!(1, station.pool().reserved_count());
+    assert!(station.sponsor(&request(sender, 1)).is_err());
+
+    let response: SuiTransactionResponse =
+        serde_json::from_value(transaction_response(TransactionDigest::random()))?;
+    let mut effects = response.effects;
+    let used = (coin.0, SequenceNumber::from(2), ObjectDigest::random());
+    effects.gas_object.reference = used.into();
+    station.confirm(&sponsorship, &effects);
+    // The example effects cost 100 + 100 - 10 MIST of gas.
+    assert_eq!(
+        vec![(used, 30_000 - 10_000 - 190)],
+        station.pool().available_coins()
+    );
+    let metrics = station.metrics();
+    assert_eq!(1, metrics.sponsored_transactions.load(Ordering::Relaxed));
+    assert_eq!(10_000, metrics.sponsored_amount.load(Ordering::Relaxed));
+    assert_eq!(190, metrics.funding_gas.load(Ordering::Relaxed));
+    assert_eq!(
+        BTreeMap::from([(sender, 10_000)]),
+        metrics.spend_by_sender()
+    );
+
+    // Budgets whose price does not fit in a u64 are refused rather than wrapped.
+    let station = GasStation::new(
+        sponsor,
+        KeystoreType::InMem(1).init()?,
+        AllowListPolicy::default(),
+    )
+    .with_gas_price(2);
+    station.pool().add_coin(object_ref(), u64::MAX);
+    assert!(station.sponsor(&request(sender, u64::MAX / 2 + 1)).is_err());
+    assert_eq!(
+        1,
+        station.metrics().rejected_requests.load(Ordering::Relaxed)
+    );
+    Ok(())
+}
+
+#[test]
+fn gas_coin_plan_test() {
+    let coin = |balance: u64| {
+        (
+            (
+                ObjectID::random(),
+                SequenceNumber::new(),
+                ObjectDigest::random(),
+            ),
+            balance,
+        )
+    };
+    let (large, small, dust) = (coin(10_000), coin(2_000), coin(10));
+
+    // A single coin splits off one coin at a time, paying its own gas.
+    assert_eq!(
+        Some(GasCoinAction::SplitFromGas {
+            coin: large.0,
+            amount: 1_000
+        }),
+        plan_gas_coins(&[large], 0, 4, 1_000, 100)
+    );
+    // Otherwise the missing coins are split off the largest coin in one transaction.
+    assert_eq!(
+        Some(GasCoinAction::Split {
+            coin: large.0,
+            gas: small.0,
+            amounts: vec![1_000; 2],
+        }),
+        plan_gas_coins(&[small, large], 0, 4, 1_000, 100)
+    );
+    // Leased coins count towards the target.
+    assert_eq!(None, plan_gas_coins(&[small, large], 2, 4, 1_000, 100));
+    assert_eq!(
+        Some(GasCoinAction::Merge {
+            primary: large.0,
+            coin: dust.0,
+            gas: small.0,
+        }),
+        plan_gas_coins(&[dust, small, large], 0, 2, 1_000, 100)
+    );
+    assert_eq!(None, plan_gas_coins(&[dust], 0, 2, 1_000, 100));
+}
+
+#[test]
+fn webhook_signature_and_filter_test() {
+    let body = br#"{"rule":"publish"}"#;
+    let header = format!("sha256={}", sign_payload(b"secret", body));
+    assert!(verify_signature(b"secret", body, &header));
+    assert!(!verify_signature(b"other", body, &header));
+    assert!(!verify_signature(b"secret", b"{}", &header));
+
+    let sender = SuiAddress::random_for_testing_only();
+    let package_id = ObjectID::random();
+    let event = SuiEventEnvelope {
+        timestamp: 0,
+        tx_digest: None,
+        event: SuiEvent::Publish { sender, package_id },
+    };
+    let filter = SuiEventFilter::All(vec![
+        SuiEventFilter::EventType(EventType::Publish),
+        SuiEventFilter::SenderAddress(sender),
+        SuiEventFilter::Package(package_id),
+    ]);
+    assert!(event_matches(&filter, &event));
+    assert!(!event_matches(
+        &SuiEventFilter::Or(
+            Box::new(SuiEventFilter::EventType(EventType::MoveEvent)),
+            Box::new(SuiEventFilter::ObjectId(package_id)),
+        ),
+        &event
+    ));
+}
+
+#[test]
+fn lazy_response_field_test() -> Result<(), anyhow::Error> {
+    let envelope = SuiEventEnvelope {
+        timestamp: 42,
+        tx_digest: None,
+        event: SuiEvent::EpochChange(7),
+    };
+    let response = LazyResponse::from_value(&envelope)?;
+    assert_eq!(Some(42), response.field::<u64>(&["timestamp"])?);
+    assert!(response.field::<u64>(&["timestamp", "missing"]).is_err());
+    assert_eq!(None, response.field::<u64>(&["missing"])?);
+    assert!(response.raw_field(&["event"])?.is_some());
+    assert_eq!(envelope, response.parse()?);
+
+    let raw = response.raw().to_string();
+    let with_raw = response.with_raw()?;
+    assert_eq!(42, with_raw.timestamp);
+    assert_eq!(raw, with_raw.raw());
+    assert_eq!(envelope, with_raw.into_inner());
+    Ok(())
+}
+
+#[tokio::test]
+async fn bulk_fetcher_test() -> Result<(), anyhow::Error> {
+    let reports = Arc::new(Mutex::new(vec![]));
+    let fetcher = {
+        let reports = reports.clone();
+        BulkFetcher::new()
+            .with_max_in_flight(4)
+            .with_chunk_size(1, 8, 2)
+            .with_progress(move |_, progress| reports.lock().unwrap().push(progress))
+    };
+
+    let items = (0..100u64).collect::<Vec<_>>();
+    let doubled = fetcher
+        .fetch_chunks("node", items.clone(), |chunk| async move {
+            tokio::time::sleep(Duration::from_millis(chunk[0] % 3)).await;
+            Ok(chunk.into_iter().map(|i| i * 2).collect())
+        })
+        .await?;
+    assert_eq!(items.iter().map(|i| i * 2).collect::<Vec<_>>(), doubled);
+    assert_eq!(8, fetcher.chunk_size());
+    assert_eq!(
+        Some(&BulkProgress {
+            completed: 100,
+            total: 100
+        }),
+        reports.lock().unwrap().last()
+    );
+
+    let squared = fetcher
+        .fetch_all("node", items.clone(), |i| async move { Ok(i * i) })
+        .await?;
+    assert_eq!(items.iter().map(|i| i * i).collect::<Vec<_>>(), squared);
+    assert!(fetcher
+        .fetch_chunks("node", items, |_| async { Ok(Vec::<u64>::new()) })
+        .await
+        .is_err());
+    Ok(())
+}
+
+#[tokio::test]
+async fn adaptive_concurrency_test() -> Result<(), anyhow::Error> {
+    let limit = AdaptiveLimit::new(2, 8);
+    let (first, second) = (limit.acquire().await, limit.acquire().await);
+    first.throttled();
+    // Sent before the decrease, so it does not decrease the limit again.
+    second.throttled();
+    assert_eq!(4, limit.limit());
+    for _ in 0..4 {
+        limit.acquire().await.succeeded();
+    }
+    assert_eq!(5, limit.limit());
+
+    let runtime = Arc::new(RecordingRuntime::default());
+    let fetcher = BulkFetcher::new()
+        .with_max_in_flight_per_endpoint(8)
+        .with_adaptive_concurrency(1)
+        .with_runtime(runtime.clone());
+    let throttled = Mutex::new(BTreeSet::new());
+    let items = (0..20u64).collect::<Vec<_>>();
+    let doubled = fetcher
+        .fetch_all("node", items.clone(), |i| {
+            let first_attempt = throttled.lock().unwrap().insert(i);
+            async move {
+                if first_attempt && i % 5 == 0 {
+                    return Err(RpcError {
+                        code: None,
+                        http_status: Some(429),
+                        message: "Request rejected `429`".to_string(),
+                    }
+                    .into());
+                }
+                Ok(i * 2)
+            }
+        })
+        .await?;
+    assert_eq!(items.iter().map(|i| i * 2).collect::<Vec<_>>(), doubled);
+    assert!(fetcher.in_flight_limit("node") < 8);
+    // Each throttled request waited on the runtime of the fetcher before its retry.
+    assert_eq!(
+        vec![Duration::from_millis(200); 4],
+        *runtime.sleeps.lock().unwrap()
+    );
+    Ok(())
+}
+
+/// A [Runtime] recording the durations it sleeps for, without waiting.
+#[derive(Debug, Default)]
+struct RecordingRuntime {
+    sleeps: Mutex<Vec<Duration>>,
+}
+
+impl Runtime for RecordingRuntime {
+    fn sleep(&self, duration: Duration) -> BoxFuture<'static, ()> {
+        self.sleeps.lock().unwrap().push(duration);
+        Box::pin(async {})
+    }
+
+    fn spawn(&self, task: BoxFuture<'static, ()>) {
+        tokio::spawn(task);
+    }
+
+    fn spawn_blocking(&self, f: Box<dyn FnOnce() + Send>) {
+        tokio::task::spawn_blocking(f);
+    }
+}
+
+#[tokio::test]
+async fn client_cache_test() -> Result<(), anyhow::Error> {
+    let lru = LruCache::new(1);
+    lru.put(CacheCategory::Package, "a", vec![1], None).await?;
+    lru.put(CacheCategory::Package, "b", vec![2], None).await?;
+    assert_eq!(None, lru.get(CacheCategory::Package, "a").await?);
+    assert_eq!(Some(vec![2]), lru.get(CacheCategory::Package, "b").await?);
+    assert_eq!(None, lru.get(CacheCategory::CoinMetadata, "b").await?);
+
+    let cache = ClientCache::new(LruCache::new(16))
+        .with_ttl(CacheCategory::CoinMetadata, Some(Duration::ZERO));
+    let fetches = Mutex::new(0);
+    let fetch = || async {
+        *fetches.lock().unwrap() += 1;
+        Ok(42u8)
+    };
+    for _ in 0..2 {
+        assert_eq!(
+            42,
+            cache
+                .get_or_fetch(CacheCategory::Package, "key", fetch)
+                .await?
+        );
+        assert_eq!(
+            42,
+            cache
+                .get_or_fetch(CacheCategory::CoinMetadata, "key", fetch)
+                .await?
+        );
+    }
+    // Package entries never expire, coin metadata entries expire immediately.
+    assert_eq!(3, *fetches.lock().unwrap());
+
+    let key = ObjectID::random().to_string();
+    assert_eq!(
+        None,
+        cache
+            .get::<String>(CacheCategory::ImmutableObject, &key)
+            .await
+    );
+    cache
+        .put(CacheCategory::ImmutableObject, &key, &"object".to_string())
+        .await;
+    assert_eq!(
+        Some("object".to_string()),
+        cache.get(CacheCategory::ImmutableObject, &key).await
+    );
+    Ok(())
+}
+
+/// Checks the vectors shared with the TypeScript SDK tests.
+#[test]
+fn sui_js_parity_test() -> Result<(), anyhow::Error> {
+    let vectors: serde_json::Value =
+        serde_json::from_str(include_str!("data/sui_js_vectors.json"))?;
+    for case in vectors["addresses"].as_array().unwrap() {
+        let pub_key =
+            AccountPublicKey::from_bytes(&Base64::decode(case["pub_key"].as_str().unwrap())?)?;
+        assert_eq!(case["address"], encode_address(&SuiAddress::from(&pub_key)));
+    }
+
+    let keypair = SuiKeyPair::from_str(vectors["keypair"].as_str().unwrap()).unwrap();
+    let sender = decode_address(vectors["address"].as_str().unwrap())?;
+    assert_eq!(sender, SuiAddress::from(&keypair.public()));
+    for case in vectors["transfer_sui"].as_array().unwrap() {
+        let digest = Base64::decode(case["gas_object_digest"].as_str().unwrap())?;
+        let gas = (
+            ObjectID::from_str(case["gas_object_id"].as_str().unwrap()).unwrap(),
+            SequenceNumber::from_u64(case["gas_object_version"].as_u64().unwrap()),
+            ObjectDigest(digest.try_into().unwrap()),
+        );
+        let data = TransactionData::new_transfer_sui(
+            decode_address(case["recipient"].as_str().unwrap())?,
+            sender,
+            case["amount"].as_u64(),
+            gas,
+            case["gas_budget"].as_u64().unwrap(),
+        );
+        assert_eq!(case["tx_bytes"], encode_transaction_data(&data));
+        assert_eq!(
+            data,
+            decode_transaction_data(case["tx_bytes"].as_str().unwrap())?
+        );
+
+        let signature: Signature = keypair.sign(&data.to_bytes());
+        let expected: SignaturePubkeyPair = serde_json::from_value(case["signature"].clone())?;
+        assert_eq!(expected, SignaturePubkeyPair::from_signature(&signature));
+        assert_eq!(signature.as_ref(), expected.to_signature()?.as_ref());
+    }
+    Ok(())
+}
+
+#[tokio::test]
+async fn config_test() -> Result<(), anyhow::Error> {
+    let toml = Config::from_toml(
+        r#"
+        active_network = "local"
+
+        [networks.local]
+        http_url = "http://127.0.0.1:5001"
+        ws_url = "ws://127.0.0.1:9001"
+
+        [keystore]
+        type = "in_memory"
+        initial_keys = 2
+        "#,
+    )?;
+    let yaml = Config::from_yaml(
+        r#"
+        active_network: local
+        networks:
+          local:
+            http_url: "http://127.0.0.1:5001"
+            ws_url: "ws://127.0.0.1:9001"
+        keystore:
+          type: in_memory
+          initial_keys: 2
+        "#,
+    )?;
+    assert_eq!(toml, yaml);
+    assert_eq!("http://127.0.0.1:5001", toml.network(None)?.http_url);
+    assert!(toml.network(Some("devnet")).is_err());
+    // Omitted sections take their default values.
+    assert_eq!(Config::default().retry, toml.retry);
+    assert_eq!(
+        2,
+        sui_sdk::crypto::SuiKeystore::from_config(&toml)?
+            .addresses()
+            .len()
+    );
+
+    let config = toml.with_overrides(|name| match name {
+        "SUI_RPC_URL" => Some("http://127.0.0.1:5002".to_string()),
+        "SUI_KEYSTORE_PATH" => Some("/tmp/sui.keystore".to_string()),
+        "SUI_MAX_ATTEMPTS" => Some("2".to_string()),
+        _ => None,
+    })?;
+    let network = config.network(None)?;
+    assert_eq!("http://127.0.0.1:5002", network.http_url);
+    assert_eq!(Some("ws://127.0.0.1:9001"), network.ws_url.as_deref());
+    assert_eq!(
+        KeystoreConfig::File {
+            path: Some("/tmp/sui.keystore".into())
+        },
+        config.keystore
+    );
+    assert_eq!(2, config.retry.max_attempts);
+    assert!(Config::default()
+        .with_overrides(|_| Some("not a number".to_string()))
+        .is_err());
+
+    // The client of a network retries with the policy of the config, and resolves the
+    // objects of the network. The node drops the connection of the first request.
+    let calls = Arc::new(AtomicUsize::new(0));
+    let node_calls = calls.clone();
+    let node = MockNode::start(
+        move |_, _| match node_calls.fetch_add(1, Ordering::SeqCst) {
+            0 => Reply::Close,
+            _ => Reply::Result(json!(7)),
+        },
+    )
+    .await?;
+    let game = ObjectID::random();
+    let mut config = config;
+    config.networks.insert(
+        "mock".to_string(),
+        NetworkConfig {
+            http_url: node.url.clone(),
+            ws_url: None,
+            objects: ObjectRegistry::default().with_alias("game", game),
+        },
+    );
+    config.retry.initial_backoff_ms = 1;
+    let client = SuiClient::from_config(&config, Some("mock")).await?;
+    assert_eq!(7, client.read_api().get_total_transaction_number().await?);
+    assert_eq!(2, calls.load(Ordering::SeqCst));
+    assert_eq!(Some(game), client.objects().get("game"));
+
+    // Without retries, the failure is returned.
+    calls.store(0, Ordering::SeqCst);
+    config.retry.max_attempts = 1;
+    let client = SuiClient::from_config(&config, Some("mock")).await?;
+    assert!(client
+        .read_api()
+        .get_total_transaction_number()
+        .await
+        .is_err());
+    Ok(())
+}
+
+#[test]
+fn error_test() {
+    let abort = SuiExecutionStatus::Failure {
+        error: "MoveAbort(ModuleId { address: 0000000000000000000000000000000000000002, \
+                name: Identifier(\"coin\") }, 2)"
+            .to_string(),
+    };
+    assert_eq!(
+        Some(ExecutionError::MoveAbort {
+            location: "0x0000000000000000000000000000000000000002::coin".to_string(),
+            code: 2
+        }),
+        ExecutionError::from_status(&abort)
+    );
+    let failure = SuiExecutionStatus::Failure {
+        error: "InsufficientGas".to_string(),
+    };
+    assert_eq!(
+        Some(ExecutionError::Failure("InsufficientGas".to_string())),
+        ExecutionError::from_status(&failure)
+    );
+    assert_eq!(
+        None,
+        ExecutionError::from_status(&SuiExecutionStatus::Success)
+    );
+
+    // Typed errors survive a round trip through anyhow.
+    let error = Error::from(anyhow::Error::from(Error::Unsupported("test client")));
+    assert!(matches!(error, Error::Unsupported("test client")));
+    let error = Error::from(anyhow::Error::from(RpcError {
+        code: Some(-32602),
+        http_status: None,
+        message: "Invalid params".to_string(),
+    }));
+    assert!(matches!(
+        error,
+        Error::Rpc(RpcError {
+            code: Some(-32602),
+            ..
+        })
+    ));
+    assert!(matches!(
+        Error::from(anyhow::anyhow!("Other")),
+        Error::Other(_)
+    ));
+}
+
+#[test]
+fn rpc_error_kind_test() {
+    let object_id = ObjectID::random();
+    let kind = |error: SuiError| RpcErrorKind::from_message(&format!("Call error: {error}"));
+    assert_eq!(
+        RpcErrorKind::ObjectNotFound {
+            object_id,
+            version: None
+        },
+        kind(SuiError::ObjectNotFound { object_id })
+    );
+    assert_eq!(
+        RpcErrorKind::ObjectNotFound {
+            object_id,
+            version: Some(SequenceNumber::from(3))
+        },
+        kind(SuiError::ObjectVersionNotFound {
+            object_id,
+            version: SequenceNumber::from(3)
+        })
+    );
+    assert_eq!(
+        RpcErrorKind::InsufficientGas {
+            reason: "Gas balance is 10, not enough to pay 100".to_string()
+        },
+        kind(SuiError::InsufficientGas {
+            error: "Gas balance is 10, not enough to pay 100".to_string()
+        })
+    );
+    assert_eq!(
+        RpcErrorKind::PackageVerificationFailure {
+            reason: "\"bad\" module".to_string()
+        },
+        kind(SuiError::ModuleVerificationFailure {
+            error: "\"bad\" module".to_string()
+        })
+    );
+
+    let quorum = kind(SuiError::QuorumNotReached {
+        errors: vec![
+            SuiError::ObjectNotFound { object_id },
+            SuiError::InsufficientGas {
+                error: "budget".to_string(),
+            },
+        ],
+    });
+    let errors = match quorum {
+        RpcErrorKind::QuorumFailure { errors } => errors,
+        kind => panic!("Unexpected error kind {kind:?}"),
+    };
+    assert_eq!(2, errors.len());
+    assert_eq!(
+        RpcErrorKind::ObjectNotFound {
+            object_id,
+            version: None
+        },
+        RpcErrorKind::from_message(&errors[0])
+    );
+
+    let error = RpcError {
+        code: None,
+        http_status: Some(429),
+        message: "HTTP status client error (429 Too Many Requests)".to_string(),
+    };
+    assert_eq!(RpcErrorKind::Throttled, error.kind());
+    // Throttling is detected by the status, not by a status code quoted in the message.
+    let error = RpcError {
+        code: Some(-32000),
+        http_status: None,
+        message: "Transfer of 429 coins failed".to_string(),
+    };
+    assert!(!error.is_throttled());
+    assert_eq!(
+        RpcErrorKind::Other,
+        RpcErrorKind::from_message("Invalid params")
+    );
+}
+
+/// Answers every request of a batch, in reverse order, with its first parameter, and fails
+/// requests without parameters.
+struct EchoTransport;
+
+#[async_trait]
+impl HttpTransport for EchoTransport {
+    async fn post(&self, _url: &str, body: Vec<u8>) -> anyhow::Result<Vec<u8>> {
+        let answer = |request: &Value| match request["params"].get(0) {
+            Some(param) => json!({ "jsonrpc": "2.0", "id": request["id"], "result": param }),
+            None => json!({
+                "jsonrpc": "2.0",
+                "id": request["id"],
+                "error": { "code": -32602, "message": "Missing params" }
+            }),
+        };
+        let request: Value = serde_json::from_slice(&body)?;
+        let response = match request.as_array() {
+            Some(batch) => Value::Array(batch.iter().rev().map(answer).collect()),
+            None => answer(&request),
+        };
+        Ok(serde_json::to_vec(&response)?)
+    }
+}
+
+#[tokio::test]
+async fn http_transport_test() -> Result<(), anyhow::Error> {
+    let client = TransportClient::new("http://127.0.0.1:9000", Arc::new(EchoTransport));
+
+    let echo: String = client.request("echo", rpc_params!["hello"]).await?;
+    assert_eq!("hello", echo);
+
+    let error = client.request::<String>("echo", None).await.unwrap_err();
+    assert!(matches!(
+        Error::from(error),
+        Error::Rpc(RpcError {
+            code: Some(-32602),
+            ..
+        })
+    ));
+
+    let batch: Vec<u64> = client
+        .batch_request(vec![("echo", rpc_params![1]), ("echo", rpc_params![2])])
+        .await?;
+    assert_eq!(vec![1, 2], batch);
+    Ok(())
+}
+
+/// Rejects every request with an HTTP status.
+struct StatusTransport(u16);
+
+#[async_trait]
+impl HttpTransport for StatusTransport {
+    async fn post(&self, _url: &str, _body: Vec<u8>) -> anyhow::Result<Vec<u8>> {
+        Err(HttpStatusError(self.0).into())
+    }
+}
+
+#[tokio::test]
+async fn http_status_test() {
+    let client = TransportClient::new("http://127.0.0.1:9000", Arc::new(StatusTransport(429)));
+    let error = Error::from(client.request::<String>("echo", None).await.unwrap_err());
+    assert!(matches!(
+        error,
+        Error::Rpc(RpcError {
+            http_status: Some(429),
+            ..
+        })
+    ));
+    assert!(error.is_throttled());
+
+    let client = TransportClient::new("http://127.0.0.1:9000", Arc::new(StatusTransport(500)));
+    let error = Error::from(client.request::<String>("echo", None).await.unwrap_err());
+    assert!(!error.is_throttled());
+}
+
+#[tokio::test]
+async fn incompatible_settings_test() {
+    let result = SuiClientBuilder::default()
+        .http_transport(Arc::new(EchoTransport))
+        .header("x-api-key", "key")
+        .build("http://127.0.0.1:9000", None)
+        .await;
+    assert!(matches!(
+        result,
+        Err(Error::IncompatibleSettings("header", "http_transport"))
+    ));
+
+    let result = SuiClientBuilder::default()
+        .compression(Compression::default())
+        .connect_options(ConnectOptions::default())
+        .build("http://127.0.0.1:9000", None)
+        .await;
+    assert!(matches!(
+        result,
+        Err(Error::IncompatibleSettings(
+            "compression",
+            "connect_options"
+        ))
+    ));
+    let result = SuiClientBuilder::default()
+        .compression(Compression::default())
+        .pool_options(PoolOptions::default())
+        .build("http://127.0.0.1:9000", None)
+        .await;
+    assert!(matches!(
+        result,
+        Err(Error::IncompatibleSettings("compression", "pool_options"))
+    ));
+}
+
+/// The answer of a [MockNode] to a request.
+enum Reply {
+    Result(Value),
+    Error(String),
+    /// Close the connection without answering.
+    Close,
+    Delayed(Duration, Box<Reply>),
+}
+
+type Responder = Arc<dyn Fn(&str, &Value) -> Reply + Send + Sync>;
+
+/// A JSON-RPC node on a local port, serving HTTP/1.1 with keep-alive. Answers `rpc.discover`
+/// itself, and every other request with its responder, counting the connections and the
+/// requests it accepts.
+struct MockNode {
+    url: String,
+    connections: Arc<AtomicUsize>,
+    requests: Arc<AtomicUsize>,
+}
+
+impl MockNode {
+    async fn start(
+        responder: impl Fn(&str, &Value) -> Reply + Send + Sync + 'static,
+    ) -> anyhow::Result<Self> {
+        let listener = TcpListener::bind("127.0.0.1:0").await?;
+        let node = Self {
+            url: format!("http://{}", listener.local_addr()?),
+            connections: Default::default(),
+            requests: Default::default(),
+        };
+        let responder: Responder = Arc::new(responder);
+        let (connections, requests) = (node.connections.clone(), node.requests.clone());
+        tokio::spawn(async move {
+            while let Ok((stream, _)) = listener.accept().await {
+                connections.fetch_add(1, Ordering::SeqCst);
+                tokio::spawn(Self::serve(stream, responder.clone(), requests.clone()));
+            }
+        });
+        Ok(node)
+    }
+
+    fn connections(&self) -> usize {
+        self.connections.load(Ordering::SeqCst)
+    }
+
+    fn requests(&self) -> usize {
+        self.requests.load(Ordering::SeqCst)
+    }
+
+    async fn serve(
+        stream: TcpStream,
+        responder: Responder,
+        requests: Arc<AtomicUsize>,
+    ) -> anyhow::Result<()> {
+        let mut stream = BufReader::new(stream);
+        loop {
+            let mut content_length = 0;
+            let mut line = String::new();
+            loop {
+                line.clear();
+                if stream.read_line(&mut line).await? == 0 {
+                    return Ok(());
+                }
+                if line == "\r\n" {
+                    break;
+                }
+                if let Some((name, value)) = line.split_once(':') {
+                    if name.eq_ignore_ascii_case("content-length") {
+                        content_length = value.trim().parse()?;
+                    }
+                }
+            }
+            let mut body = vec![0; content_length];
+            stream.read_exact(&mut body).await?;
+            requests.fetch_add(1, Ordering::SeqCst);
+
+            let request: Value = serde_json::from_slice(&body)?;
+            let method = request["method"].as_str().unwrap_or_default();
+            let mut reply = match method {
+                "rpc.discover" => Reply::Result(json!({
+                    "info": { "version": "0.0.0" },
+                    "methods": [{ "name": "sui_getTotalTransactionNumber" }],
+                })),
+                _ => responder(method, &request["params"]),
+            };
+            let response = loop {
+                reply = match reply {
+                    Reply::Result(result) => {
+                        break json!({ "jsonrpc": "2.0", "id": request["id"], "result": result })
+                    }
+                    Reply::Error(message) => {
+                        let error = json!({ "code": -32000, "message": message });
+                        break json!({ "jsonrpc": "2.0", "id": request["id"], "error": error });
+                    }
+                    Reply::Close => return Ok(()),
+                    Reply::Delayed(delay, reply) => {
+                        tokio::time::sleep(delay).await;
+                        *reply
+                    }
+                }
+            };
+            let body = serde_json::to_vec(&response)?;
+            let head = format!(
+                "HTTP/1.1 200 OK\r\ncontent-type: application/json\r\ncontent-length: {}\r\n\r\n",
+                body.len()
+            );
+            stream.get_mut().write_all(head.as_bytes()).await?;
+            stream.get_mut().write_all(&body).await?;
+        }
+    }
+}
+
+#[tokio::test]
+async fn pool_options_test() -> Result<(), anyhow::Error> {
+    let options = PoolOptions::default()
+        .with_max_idle_per_host(0)
+        .with_idle_timeout(None)
+        .with_http2_keep_alive_interval(Some(Duration::from_secs(20)))
+        .with_tcp_keepalive(Some(Duration::from_secs(60)));
+    assert_eq!(0, options.max_idle_per_host());
+    assert_eq!(None, options.idle_timeout());
+    assert!(!options.http2_only());
+    assert_eq!(
+        Some(Duration::from_secs(20)),
+        options.http2_keep_alive_interval()
+    );
+    assert_eq!(Some(Duration::from_secs(60)), options.tcp_keepalive());
+    let transport =
+        HyperTransport::with_pool(Duration::from_secs(5), ConnectOptions::default(), options);
+    assert_eq!(&options, transport.pool_options());
+
+    // Idle connections are kept by default, so that all requests share one connection.
+    let node = MockNode::start(|_, _| Reply::Result(json!(7))).await?;
+    let client = SuiClientBuilder::default()
+        .pool_options(PoolOptions::default())
+        .build(&node.url, None)
+        .await?;
+    for _ in 0..3 {
+        assert_eq!(7, client.read_api().get_total_transaction_number().await?);
+    }
+    assert_eq!((1, 4), (node.connections(), node.requests()));
+
+    // Without idle connections, every request opens a new one.
+    let node = MockNode::start(|_, _| Reply::Result(json!(7))).await?;
+    let client = SuiClientBuilder::default()
+        .pool_options(options)
+        .build(&node.url, None)
+        .await?;
+    for _ in 0..3 {
+        assert_eq!(7, client.read_api().get_total_transaction_number().await?);
+    }
+    assert_eq!((4, 4), (node.connections(), node.requests()));
+    Ok(())
+}
+
+#[tokio::test]
+async fn scoped_options_test() -> Result<(), anyhow::Error> {
+    // The node drops the connection of the first request.
+    let calls = Arc::new(AtomicUsize::new(0));
+    let node_calls = calls.clone();
+    let node = MockNode::start(
+        move |_, _| match node_calls.fetch_add(1, Ordering::SeqCst) {
+            0 => Reply::Close,
+            _ => Reply::Result(json!(7)),
+        },
+    )
+    .await?;
+    let client = SuiClientBuilder::default().build(&node.url, None).await?;
+    let retrying =
+        client.with_options(ClientOptions::default().with_retry(3, Duration::from_millis(10)))?;
+    assert_eq!(7, retrying.read_api().get_total_transaction_number().await?);
+    assert_eq!(2, calls.load(Ordering::SeqCst));
+
+    // Without retries, the failure is returned.
+    calls.store(0, Ordering::SeqCst);
+    assert!(client
+        .read_api()
+        .get_total_transaction_number()
+        .await
+        .is_err());
+    assert_eq!(1, calls.load(Ordering::SeqCst));
+
+    // Requests of a scope time out after its deadline, and are retried after timing out.
+    let node = MockNode::start(|_, _| {
+        Reply::Delayed(Duration::from_secs(5), Box::new(Reply::Result(json!(7))))
+    })
+    .await?;
+    let client = SuiClientBuilder::default().build(&node.url, None).await?;
+    let start = std::time::Instant::now();
+    let scoped = client.with_options(
+        ClientOptions::default()
+            .with_request_timeout(Duration::from_millis(100))
+            .with_retry(2, Duration::from_millis(10)),
+    )?;
+    assert!(scoped
+        .read_api()
+        .get_total_transaction_number()
+        .await
+        .is_err());
+    assert!(start.elapsed() < Duration::from_secs(5));
+    // The build request, then both attempts.
+    assert_eq!(3, node.requests());
+    Ok(())
+}
+
+#[tokio::test]
+async fn runtime_test() -> Result<(), anyhow::Error> {
+    let runtime = TokioRuntime;
+    assert_eq!(4, spawn_blocking(&runtime, || 2 + 2).await?);
+    assert_eq!(
+        Some(1),
+        timeout(&runtime, Duration::from_secs(10), async { 1 }).await
+    );
+    assert_eq!(
+        None,
+        timeout(
+            &runtime,
+            Duration::from_millis(10),
+            futures::future::pending::<()>()
+        )
+        .await
+    );
+    Ok(())
+}
+
+#[tokio::test]
+async fn cancellation_test() {
+    let token = CancellationToken::new();
+    let items = cancellable(futures::stream::iter(vec![1, 2]), token.clone());
+    assert_eq!(vec![1, 2], items.collect::<Vec<_>>().await);
+
+    token.cancel();
+    let items = cancellable(futures::stream::pending::<u64>(), token);
+    assert!(items.collect::<Vec<_>>().await.is_empty());
+}
+
+#[tokio::test]
+async fn background_tasks_shutdown_test() {
+    let tasks = BackgroundTasks::new(Arc::new(TokioRuntime));
+    let token = tasks.token();
+    tasks.spawn(async move { token.cancelled().await });
+    let source = futures::stream::pending::<SuiRpcResult<u64>>();
+    let (items, _) = buffered(source, BufferConfig::default(), &tasks);
+    assert_eq!(2, tasks.running());
+    tasks.shutdown(Duration::from_secs(5)).await.unwrap();
+    assert_eq!(0, tasks.running());
+    assert!(items.collect::<Vec<_>>().await.is_empty());
+
+    let stuck = BackgroundTasks::new(Arc::new(TokioRuntime));
+    stuck.spawn(futures::future::pending());
+    assert_eq!(
+        Err(ShutdownTimeout { running: 1 }),
+        stuck.shutdown(Duration::from_millis(10)).await
+    );
+}
+
+#[tokio::test]
+async fn backpressure_test() {
+    let buffer = |overflow| {
+        let source = futures::stream::iter(1..=3).map(Ok::<u64, Error>);
+        let config = BufferConfig {
+            capacity: 1,
+            overflow,
+        };
+        buffered(
+            source,
+            config,
+            &BackgroundTasks::new(Arc::new(TokioRuntime)),
+        )
+    };
+    // Wait for the background task to read `count` items from the source.
+    async fn received(metrics: &StreamMetrics, count: u64) {
+        while metrics.received() < count {
+            tokio::time::sleep(Duration::from_millis(1)).await;
+        }
+    }
+
+    let (items, metrics) = buffer(OverflowPolicy::Block);
+    let items = items.map(Result::unwrap).collect::<Vec<_>>().await;
+    assert_eq!(vec![1, 2, 3], items);
+    assert_eq!(
+        (3, 0, 0),
+        (metrics.delivered(), metrics.dropped(), metrics.lag())
+    );
+
+    let (items, metrics) = buffer(OverflowPolicy::DropOldest);
+    received(&metrics, 3).await;
+    assert_eq!(1, metrics.lag());
+    let items = items.map(Result::unwrap).collect::<Vec<_>>().await;
+    assert_eq!(vec![3], items);
+    assert_eq!(2, metrics.dropped());
+
+    let (items, metrics) = buffer(OverflowPolicy::Error);
+    received(&metrics, 2).await;
+    let items = items.collect::<Vec<_>>().await;
+    assert_eq!(2, items.len());
+    assert!(matches!(items[0], Ok(1)));
+    assert!(matches!(items[1], Err(Error::BufferOverflow(1))));
+}
+
+#[tokio::test]
+async fn file_sequence_store_test() -> Result<(), anyhow::Error> {
+    let dir = TempDir::new()?;
+    let path = dir.path().join("sequence");
+    let store = FileSequenceStore(path.clone());
+    assert_eq!(None, store.load().await?);
+    store.save(41).await?;
+    store.save(42).await?;
+    assert_eq!(Some(42), store.load().await?);
+    assert_eq!("42", std::fs::read_to_string(&path)?);
+    // The file is replaced through a temporary file, which does not outlive the save.
+    assert!(!path.with_extension("tmp").exists());
+    Ok(())
+}
+
+#[tokio::test]
+async fn cursor_store_test() -> Result<(), anyhow::Error> {
+    let dir = TempDir::new()?;
+    let file = FileCursorStore::new(dir.path().join("cursors.json"));
+    assert_eq!(None, file.load("events").await?);
+    file.save("events", json!({ "timestamp": 1 })).await?;
+    file.save("transactions", json!(5)).await?;
+    assert_eq!(Some(json!({ "timestamp": 1 })), file.load("events").await?);
+
+    let follower = StreamCursor::new(file, "transactions");
+    assert_eq!(Some(5), follower.load().await?);
+    follower.save(6).await?;
+    assert_eq!(Some(json!(6)), follower.store.load("transactions").await?);
+
+    let sqlite = SqliteCursorStore::in_memory().await?;
+    sqlx::query("CREATE TABLE balances (address TEXT PRIMARY KEY, balance INTEGER)")
+        .execute(sqlite.pool())
+        .await?;
+    sqlite
+        .commit("events", json!(1), |connection| {
+            Box::pin(async move {
+                sqlx::query("INSERT INTO balances VALUES ('0x1', 10)")
+                    .execute(connection)
+                    .await?;
+                Ok(())
+            })
+        })
+        .await?;
+    // A failing handler stores neither its writes nor the cursor.
+    let result = sqlite
+        .commit("events", json!(2), |connection| {
+            Box::pin(async move {
+                sqlx::query("UPDATE balances SET balance = 0")
+                    .execute(connection)
+                    .await?;
+                Err(Error::Other(anyhow::anyhow!("Handler failed")))
+            })
+        })
+        .await;
+    assert!(result.is_err());
+    assert_eq!(Some(json!(1)), sqlite.load("events").await?);
+    let balance: i64 = sqlx::query_scalar("SELECT balance FROM balances")
+        .fetch_one(sqlite.pool())
+        .await?;
+    assert_eq!(10, balance);
+    Ok(())
+}
+
+#[test]
+fn export_test() -> Result<(), anyhow::Error> {
+    let sender = SuiAddress::random_for_testing_only();
+    let recipient = SuiAddress::random_for_testing_only();
+    let envelope = SuiEventEnvelope {
+        timestamp: 42,
+        tx_digest: None,
+        event: SuiEvent::TransferObject {
+            package_id: ObjectID::random(),
+            transaction_module: "pay".to_string(),
+            sender,
+            recipient: Owner::AddressOwner(recipient),
+            object_id: ObjectID::random(),
+            version: SequenceNumber::new(),
+            type_: TransferType::Coin,
+            amount: Some(100),
+        },
+    };
+    let changes = BalanceChangeRow::from_event(&envelope);
+    assert_eq!(2, changes.len());
+    assert_eq!(
+        (sender.to_string(), -100),
+        (changes[0].address.clone(), changes[0].amount)
+    );
+    assert_eq!(
+        (recipient.to_string(), 100),
+        (changes[1].address.clone(), changes[1].amount)
+    );
+
+    let temp_dir = TempDir::new()?;
+    let path = temp_dir.path().join("events.csv");
+    write_rows(&[EventRow::new(0, &envelope)], ExportFormat::Csv, &path)?;
+    let mut reader = csv::Reader::from_path(&path)?;
+    let header = reader.headers()?.clone();
+    assert_eq!(EventRow::schema().len(), header.len());
+    let record = reader.records().next().unwrap()?;
+    let field = |name| &record[header.iter().position(|h| h == name).unwrap()];
+    assert_eq!("", field("tx_digest"));
+    assert_eq!("transferObject", field("event_type"));
+    assert_eq!(recipient.to_string(), field("recipient"));
+    assert_eq!("100", field("amount"));
+    Ok(())
+}
+
+#[tokio::test]
+async fn sqlite_store_test() -> Result<(), anyhow::Error> {
+    let temp_dir = TempDir::new()?;
+    let path = temp_dir.path().join("store.db");
+    let address = SuiAddress::random_for_testing_only();
+    let key = EventKey {
+        tx_digest: None,
+        timestamp: 10,
+        event_seq: 0,
+    };
+    {
+        let store = SqliteStore::open(&path).await?;
+        assert_eq!(MIGRATIONS.len(), store.schema_version().await?);
+        store.add_address(address).await?;
+        store
+            .save_cursor(
+                "balances",
+                EventCursor {
+                    timestamp: 10,
+                    processed: 1,
+                },
+            )
+            .await?;
+        store.upsert_row("balances", &key, json!(1)).await?;
+        // Replayed events overwrite their rows.
+        store.upsert_row("balances", &key, json!(2)).await?;
+    }
+    // Reopening an up to date database applies no migration and keeps the data.
+    let store = SqliteStore::open(&path).await?;
+    assert_eq!(MIGRATIONS.len(), store.schema_version().await?);
+    assert!(store.addresses().await?.contains(&address));
+    assert_eq!(
+        0,
+        store
+            .balance(address, "0x2::coin::Coin<0x2::sui::SUI>")
+            .await?
+    );
+    assert_eq!(
+        Some(10),
+        store.load_cursor("balances").await?.map(|c| c.timestamp)
+    );
+    assert_eq!(vec![(key, json!(2))], store.rows("balances").await?);
+
+    assert_eq!(0, store.compact(10).await?);
+    assert_eq!(1, store.compact(11).await?);
+    assert!(store.rows("balances").await?.is_empty());
+    Ok(())
+}
+
+#[tokio::test]
+async fn encrypted_store_test() -> Result<(), anyhow::Error> {
+    let cipher = Arc::new(StoreCipher::new("password"));
+    let sealed = cipher.seal(b"balances")?;
+    assert!(is_sealed(&sealed));
+    assert_eq!(b"balances".to_vec(), cipher.open(&sealed)?);
+    assert!(StoreCipher::new("wrong").open(&sealed).is_err());
+
+    let temp_dir = TempDir::new()?;
+    // Plaintext cursors are refused until explicitly encrypted.
+    let path = temp_dir.path().join("cursors.json");
+    FileCursorStore::new(path.clone())
+        .save("events", json!(1))
+        .await?;
+    let store = FileCursorStore::new(path.clone()).with_cipher(cipher.clone());
+    assert!(store.load("events").await.is_err());
+    assert!(store.save("events", json!(2)).await.is_err());
+    encrypt_file(&path, &cipher)?;
+    assert!(is_sealed(&std::fs::read(&path)?));
+    assert_eq!(Some(json!(1)), store.load("events").await?);
+    store.save("events", json!(2)).await?;
+    assert_eq!(Some(json!(2)), store.load("events").await?);
+    assert!(FileCursorStore::new(path.clone())
+        .load("events")
+        .await
+        .is_err());
+    // Encrypting again checks the password and leaves the file as is.
+    encrypt_file(&path, &cipher)?;
+    assert!(encrypt_file(&path, &StoreCipher::new("wrong")).is_err());
+
+    // A plaintext file swapped in for an encrypted one is refused.
+    std::fs::write(&path, br#"{"events": 0}"#)?;
+    assert!(store.load("events").await.is_err());
+
+    let path = temp_dir.path().join("store.json");
+    let address = SuiAddress::random_for_testing_only();
+    let mut local_store = LocalStore::open_encrypted(path.clone(), cipher)?;
+    local_store.add_address(address);
+    local_store.save()?;
+    assert!(!String::from_utf8_lossy(&std::fs::read(&path)?).contains(&address.to_string()));
+    let reopened = LocalStore::open_encrypted(path, Arc::new(StoreCipher::new("password")))?;
+    assert!(reopened.addresses().contains(&address));
+    Ok(())
+}
+
+#[test]
+fn airdrop_file_test() -> Result<(), anyhow::Error> {
+    let recipient = SuiAddress::random_for_testing_only();
+    let file = format!("recipient,amount\n{recipient},100\nnot-an-address,5\n{recipient},0\n");
+    let (entries, invalid) = read_entries(file.as_bytes())?;
+    assert_eq!(
+        vec![AirdropEntry {
+            line: 2,
+            recipient,
+            item: AirdropItem::Coins(100),
+        }],
+        entries
+    );
+    assert_eq!(
+        vec![3, 4],
+        invalid.iter().map(|r| r.line).collect::<Vec<_>>()
+    );
+    assert!(invalid.iter().all(|r| r.status == AirdropStatus::Invalid));
+
+    let file = format!("recipient,name,description,url\n{recipient},Drop,First drop,https://x\n");
+    let (entries, _) = read_entries(file.as_bytes())?;
+    assert!(matches!(&entries[0].item, AirdropItem::Nft { name, .. } if name == "Drop"));
+    assert!(read_entries("recipient,note\n".as_bytes()).is_err());
+
+    let temp_dir = TempDir::new()?;
+    let path = temp_dir.path().join("report.csv");
+    write_report(&invalid, &path)?;
+    let report = std::fs::read_to_string(&path)?;
+    assert!(report.starts_with("line,recipient,status,digest,error\n3,not-an-address,invalid,"));
+    Ok(())
+}
+
+#[test]
+fn fee_report_test() {
+    let sender = SuiAddress::random_for_testing_only();
+    let gas = SuiGasCostSummary {
+        computation_cost: 10,
+        storage_cost: 5,
+        storage_rebate: 20,
+    };
+    let mut report = FeeReport::default();
+    let batch = BTreeSet::from([
+        "0x2::devnet_nft::mint".to_string(),
+        "0x2::coin::join".to_string(),
+    ]);
+    report.record(sender, &batch, &gas);
+    report.record(sender, &BTreeSet::new(), &gas);
+    assert_eq!(2, report.total.transactions);
+    assert_eq!(30, report.total.gas_used());
+    assert_eq!(-10, report.total.net_gas_usage());
+    assert_eq!(report.total, report.by_sender[&sender]);
+    assert_eq!(1, report.by_function["0x2::coin::join"].transactions);
+}
+
+#[test]
+fn abort_location_test() {
+    let mut location = AbortLocation {
+        command_index: 1,
+        module: "0x2::coin".to_string(),
+        function: Some("split".to_string()),
+        code: 3,
+    };
+    assert_eq!(
+        "0x2::coin::split aborted with code 3 in command 1",
+        location.to_string()
+    );
+    location.function = None;
+    assert_eq!(
+        "0x2::coin (dependency) aborted with code 3 in command 1",
+        location.to_string()
+    );
+}
+
+#[test]
+fn gas_profile_test() {
+    let step = |index, command: &str, computation_cost| TraceStep {
+        index,
+        command: command.to_string(),
+        created: vec![],
+        mutated: vec![],
+        deleted: vec![],
+        computation_cost,
+        storage_cost: 2,
+        storage_rebate: 1,
+        error: None,
+    };
+    let trace = Trace {
+        steps: vec![
+            step(0, "0x2::coin::split", 10),
+            step(1, "TransferObject", 5),
+            step(2, "0x2::coin::split", 30),
+        ],
+        abort: None,
+    };
+    let gas = trace.gas_by_command();
+    assert_eq!(2, gas.len());
+    assert_eq!(40, gas["0x2::coin::split"].computation_cost);
+    assert_eq!(4, gas["0x2::coin::split"].storage_cost);
+    assert_eq!(2, gas["0x2::coin::split"].storage_rebate);
+    assert_eq!(7, gas["TransferObject"].gas_used());
+    assert_eq!(Some(2), trace.most_expensive().map(|step| step.index));
+}
+
+#[test]
+fn validator_set_changes_test() {
+    let record = |name: &str, stake_amount: u64| ValidatorRecord {
+        sui_address: SuiAddress::random_for_testing_only(),
+        status: ValidatorStatus::Active,
+        name: name.to_string(),
+        net_address: vec![],
+        pubkey_bytes: vec![],
+        network_pubkey_bytes: vec![],
+        stake_amount,
+        delegation_amount: 0,
+        gas_price: 1,
+    };
+    let (staying, leaving, joining) = (record("a", 10), record("b", 10), record("c", 10));
+    let snapshot = |records: Vec<&ValidatorRecord>| ValidatorSetSnapshot {
+        epoch: 0,
+        validators: records
+            .into_iter()
+            .map(|record| (record.sui_address, record.clone()))
+            .collect(),
+    };
+    let previous = snapshot(vec![&staying, &leaving]);
+    let mut restaked = staying.clone();
+    restaked.stake_amount = 15;
+    let changes = snapshot(vec![&restaked, &joining]).changes_since(&previous);
+    assert_eq!(3, changes.len());
+    assert!(changes.contains(&ValidatorChange::Joined(joining)));
+    assert!(changes.contains(&ValidatorChange::Left(leaving)));
+    assert!(changes.contains(&ValidatorChange::StakeChanged {
+        sui_address: staying.sui_address,
+        before: 10,
+        after: 15,
+    }));
+    assert!(previous.changes_since(&previous).is_empty());
+}
+
+#[test]
+fn consistency_compare_test() {
+    let observation = |endpoint: &str, value: Result<&str, &str>| Observation {
+        endpoint: endpoint.to_string(),
+        value: value.map(str::to_string).map_err(str::to_string),
+    };
+    let agreeing = vec![
+        observation("http://own:9000", Ok("version 3")),
+        observation("http://public:443", Ok("version 3")),
+    ];
+    assert_eq!(None, compare("object 0x5".to_string(), agreeing));
+    let diverging = vec![
+        observation("http://own:9000", Ok("version 3")),
+        observation("http://public:443", Err("timeout")),
+    ];
+    let divergence = compare("object 0x5".to_string(), diverging.clone()).unwrap();
+    assert_eq!(diverging, divergence.observations);
+    assert_eq!(None, compare("object 0x5".to_string(), vec![]));
+}
+
+#[test]
+fn canary_metrics_test() {
+    let result = |latency_ms: u64, error: Option<&str>| CanaryResult {
+        digest: None,
+        latency: Duration::from_millis(latency_ms),
+        error: error.map(str::to_string),
+    };
+    let metrics = CanaryMetrics::default();
+    metrics.record(&result(120, None));
+    metrics.record(&result(5000, Some("timeout")));
+    metrics.record(&result(5000, Some("timeout")));
+    assert_eq!(1, metrics.succeeded());
+    assert_eq!(2, metrics.failed());
+    assert_eq!(2, metrics.consecutive_failures());
+    assert_eq!(120, metrics.last_latency_ms());
+    metrics.record(&result(80, None));
+    assert_eq!(0, metrics.consecutive_failures());
+    assert_eq!(80, metrics.last_latency_ms());
+}
+
+#[tokio::test]
+async fn circuit_breaker_test() {
+    let breaker = CircuitBreaker::new(2, Duration::from_millis(50));
+    breaker.record_failure();
+    assert_eq!(CircuitState::Closed, breaker.state());
+    breaker.record_failure();
+    assert_eq!(CircuitState::Open, breaker.state());
+    assert!(!breaker.allow_request());
+
+    tokio::time::sleep(Duration::from_millis(60)).await;
+    assert_eq!(CircuitState::HalfOpen, breaker.state());
+    // A single probe is let through.
+    assert!(breaker.allow_request());
+    assert!(!breaker.allow_request());
+    breaker.record_failure();
+    assert_eq!(CircuitState::Open, breaker.state());
+
+    tokio::time::sleep(Duration::from_millis(60)).await;
+    assert!(breaker.allow_request());
+    breaker.record_success();
+    assert_eq!(CircuitState::Closed, breaker.state());
+    assert!(breaker.allow_request());
+
+    // Requests dropped before they complete, e.g. on a timeout, count as failures.
+    let pending = || breaker.call("node", futures::future::pending::<Result<(), _>>());
+    for _ in 0..2 {
+        let timed_out = tokio::time::timeout(Duration::from_millis(1), pending()).await;
+        assert!(timed_out.is_err());
+    }
+    assert_eq!(CircuitState::Open, breaker.state());
+
+    // A cancelled probe opens the breaker again instead of blocking every later probe.
+    tokio::time::sleep(Duration::from_millis(60)).await;
+    assert!(tokio::time::timeout(Duration::from_millis(1), pending())
+        .await
+        .is_err());
+    assert_eq!(CircuitState::Open, breaker.state());
+    tokio::time::sleep(Duration::from_millis(60)).await;
+    assert_eq!(
+        Ok(7),
+        breaker.call("node", async { Ok(7) }).await.map_err(|_| ())
+    );
+    assert_eq!(CircuitState::Closed, breaker.state());
+}
+
+/// The no_std signing core must produce the same bytes as sui-types.
+#[test]
+fn signing_core_parity_test() -> Result<(), anyhow::Error> {
+    let vectors: serde_json::Value =
+        serde_json::from_str(include_str!("data/sui_js_vectors.json"))?;
+    let keypair = SuiKeyPair::from_str(vectors["keypair"].as_str().unwrap()).unwrap();
+    let public_key = keypair.public();
+    let sender = SuiAddress::from(&public_key);
+    let scheme = sui_sdk_core::SignatureScheme::from_flag(public_key.flag())?;
+    assert_eq!(
+        sender.to_inner(),
+        sui_sdk_core::address_from_public_key(scheme, public_key.as_ref())?
+    );
+
+    let gas = (
+        ObjectID::random(),
+        SequenceNumber::new(),
+        ObjectDigest::random(),
+    );
+    let data = TransactionData::new_transfer_sui(sender, sender, Some(1), gas, 1000);
+    let data_bcs = bcs::to_bytes(&data)?;
+    assert_eq!(
+        data.to_bytes(),
+        sui_sdk_core::transaction_signing_message(&data_bcs)
+    );
+
+    let signature: Signature = keypair.sign(&data.to_bytes());
+    let assembled = sui_sdk_core::assemble_signature(
+        scheme,
+        signature.signature_bytes(),
+        signature.public_key_bytes(),
+    )?;
+    assert_eq!(signature.as_ref(), assembled.as_slice());
+    assert_eq!(
+        Transaction::new(data, signature).digest().as_ref(),
+        &sui_sdk_core::transaction_digest(&data_bcs, &assembled)[..]
+    );
+    assert!(sui_sdk_core::assemble_signature(scheme, &assembled, public_key.as_ref()).is_err());
+    Ok(())
+}
+
+#[test]
+fn transaction_digest_test() -> Result<(), anyhow::Error> {
+    let (_, keypair): (_, AccountKeyPair) = get_key_pair();
+    let keypair = SuiKeyPair::Ed25519SuiKeyPair(keypair);
+    let sender = SuiAddress::from(&keypair.public());
+    let gas = (
+        ObjectID::random(),
+        SequenceNumber::new(),
+        ObjectDigest::random(),
+    );
+    let data = TransactionData::new_transfer_sui(sender, sender, Some(1), gas, 1000);
+    let signature: Signature = keypair.sign(&data.to_bytes());
+    let digest = transaction_digest(&data, &signature);
+    assert_eq!(
+        digest,
+        encoded_transaction_digest(
+            &Base64::encode(data.to_bytes()),
+            &Base64::encode(signature.as_ref())
+        )?
+    );
+    assert_eq!(&digest, Transaction::new(data, signature).digest());
+    Ok(())
+}
+
+#[test]
+fn module_introspection_test() -> Result<(), anyhow::Error> {
+    let coin = sui_framework::get_sui_framework()
+        .into_iter()
+        .find(|module| module.self_id().name().as_str() == "coin")
+        .unwrap();
+    let mut bytes = vec![];
+    coin.serialize(&mut bytes)?;
+    let module = ModuleInfo::from_bytecode(&bytes)?;
+    assert_eq!("coin", module.id.name().as_str());
+
+    let coin_struct = &module.structs["Coin"];
+    assert_eq!("key + store", abilities_str(&coin_struct.abilities));
+    assert_eq!(
+        vec!["id", "balance"],
+        coin_struct
+            .fields
+            .iter()
+            .map(|field| field.name.as_str())
+            .collect::<Vec<_>>()
+    );
+
+    assert_eq!(
+        Some("public fun value<T0>(&0x2::coin::Coin<T0>): u64".to_string()),
+        module.signature("value")
+    );
+    assert!(module.entry_functions().any(|(name, _)| name == "join"));
+    assert!(module.public_functions().any(|(name, _)| name == "value"));
+    assert!(module
+        .functions
+        .values()
+        .all(|function| function.visibility != Visibility::Friend));
+    Ok(())
+}
+
+#[test]
+fn move_json_test() -> Result<(), anyhow::Error> {
+    let field = |name: &str, layout| MoveFieldLayout::new(Identifier::new(name).unwrap(), layout);
+    let layout = MoveTypeLayout::Struct(MoveStructLayout::WithFields(vec![
+        field("owner", MoveTypeLayout::Address),
+        field("balance", MoveTypeLayout::U128),
+        field(
+            "tags",
+            MoveTypeLayout::Vector(Box::new(MoveTypeLayout::Vector(Box::new(
+                MoveTypeLayout::U8,
+            )))),
+        ),
+        field(
+            "point",
+            MoveTypeLayout::Struct(MoveStructLayout::Runtime(vec![
+                MoveTypeLayout::U64,
+                MoveTypeLayout::Bool,
+            ])),
+        ),
+    ]));
+    let config = json!({
+        "owner": "0x2",
+        "balance": "340282366920938463463374607431768211455",
+        "tags": ["0x0102", [3]],
+        "point": [7, true],
+    });
+    let bytes = json_to_bcs(&config, &layout)?;
+    let rendered = bcs_to_json(&bytes, &layout)?;
+    assert_eq!(json!([[1, 2], [3]]), rendered["tags"]);
+    assert_eq!(config["balance"], rendered["balance"]);
+    assert_eq!(bytes, json_to_bcs(&rendered, &layout)?);
+
+    assert!(json_to_bcs(&json!({ "owner": "0x2" }), &layout).is_err());
+    assert!(json_to_bcs(&json!(256), &MoveTypeLayout::U8).is_err());
+    Ok(())
+}
+
+#[test]
+fn object_query_test() -> Result<(), anyhow::Error> {
+    let coin = ObjectFilter::struct_type("0x2::coin::Coin")?;
+    let sui_coin = ObjectFilter::struct_type("0x2::coin::Coin<0x2::sui::SUI>")?;
+    let other_coin = sui_types::parse_sui_struct_tag("0x2::coin::Coin<0x3::usd::USD>")?;
+    assert!(coin.matches(&other_coin));
+    assert!(!sui_coin.matches(&other_coin));
+    assert!(sui_coin
+        .matches_type_str("0x0000000000000000000000000000000000000002::coin::Coin<0x2::sui::SUI>"));
+    assert!(!coin.matches_type_str("package"));
+
+    let package = ObjectID::from_hex_literal("0x2")?;
+    let query = ObjectQuery::owned_by(SuiAddress::random_for_testing_only())
+        .with_package(package)
+        .without(coin.clone());
+    let filter = query.filter().unwrap();
+    assert!(filter.matches_type_str("0x2::devnet_nft::DevNetNFT"));
+    assert!(!filter.matches_type_str("0x2::coin::Coin<0x2::sui::SUI>"));
+    assert!(!filter.matches_type_str("0x3::usd::USD"));
+    assert_eq!(
+        filter.to_rpc_filter(),
+        json!({ "MatchAll": [
+            { "Package": package.to_string() },
+            { "MatchNone": [{ "StructType": "0x2::coin::Coin" }] },
+        ] })
+    );
+    assert_eq!(
+        serde_json::from_value::<ObjectFilter>(filter.to_rpc_filter())?,
+        *filter
+    );
+
+    let content = json!({ "name": "a", "url": "b", "meta": { "rarity": 3 } });
+    assert_eq!(
+        select_fields(&content, &["name".to_string(), "meta.rarity".to_string()]),
+        json!({ "name": "a", "meta.rarity": 3 })
+    );
+    assert_eq!(select_fields(&content, &[]), content);
+    Ok(())
+}
+
+#[test]
+fn accounting_ledger_test() -> Result<(), anyhow::Error> {
+    let mut entry = LedgerEntry {
+        seq: 7,
+        tx_digest: TransactionDigest::random(),
+        timestamp_ms: None,
+        kind: LedgerEntryKind::Gas,
+        coin_type: "0x2::sui::SUI".to_string(),
+        direction: BalanceDirection::Debit,
+        amount: 1_500_000_000,
+        decimals: None,
+    };
+    assert_eq!(None, entry.quantity());
+    entry.decimals = Some(9);
+    assert_eq!(Some("-1.5".to_string()), entry.quantity());
+
+    let temp_dir = TempDir::new()?;
+    let path = temp_dir.path().join("ledger.json");
+    write_rows(&[entry], ExportFormat::Json, &path)?;
+    let rows: Value = serde_json::from_slice(&std::fs::read(&path)?)?;
+    assert_eq!("gas", rows[0]["kind"]);
+    assert_eq!("debit", rows[0]["direction"]);
+    assert_eq!(1_500_000_000u64, rows[0]["amount"]);
+    assert_eq!(Value::Null, rows[0]["timestamp_ms"]);
+    assert_eq!("-1.5", rows[0]["quantity"]);
+    Ok(())
+}
+
+#[test]
+fn scheduler_dependencies_test() {
+    let (a, b, c) = (ObjectID::random(), ObjectID::random(), ObjectID::random());
+    let objects = [
+        BTreeSet::from([a]),
+        BTreeSet::from([b]),
+        BTreeSet::from([a, b]),
+        BTreeSet::from([c]),
+        BTreeSet::from([a]),
+    ];
+    assert_eq!(
+        vec![
+            BTreeSet::new(),
+            BTreeSet::new(),
+            BTreeSet::from([0, 1]),
+            BTreeSet::new(),
+            BTreeSet::from([2]),
+        ],
+        dependencies(&objects)
+    );
+}
+
+#[test]
+fn preflight_test() -> Result<(), anyhow::Error> {
+    let sender = SuiAddress::random_for_testing_only();
+    let object = |id| (id, SequenceNumber::from(1), ObjectDigest::random());
+    let (coin, gas) = (object(ObjectID::random()), object(ObjectID::random()));
+    let pay = |gas_budget| {
+        TransactionData::new_pay(sender, vec![coin], vec![sender], vec![1], gas, gas_budget)
+    };
+    let stats = TransactionLimits::default().check(&pay(*MAX_GAS_BUDGET))?;
+    assert_eq!(1, stats.commands);
+    assert_eq!(2, stats.input_objects);
+    assert_eq!(stats, TransactionStats::of(&pay(*MAX_GAS_BUDGET))?);
+    assert!(matches!(
+        TransactionLimits::default().check(&pay(*MAX_GAS_BUDGET + 1)),
+        Err(PreflightError::GasBudgetOutOfRange { .. })
+    ));
+    assert!(matches!(
+        TransactionLimits::default()
+            .with_max_input_objects(Some(1))
+            .check(&pay(*MAX_GAS_BUDGET)),
+        Err(PreflightError::TooManyInputObjects {
+            actual: 2,
+            limit: 1
+        })
+    ));
+    assert!(matches!(
+        TransactionLimits::default()
+            .with_max_tx_bytes(Some(stats.tx_bytes - 1))
+            .check(&pay(*MAX_GAS_BUDGET)),
+        Err(PreflightError::TooLarge { .. })
+    ));
+    Ok(())
+}
+
+#[test]
+fn display_template_test() {
+    let fields = json!({
+        "name": "Sui",
+        "level": 3,
+        "attributes": [{ "key": "color", "value": "blue" }],
+        "owner": { "name": null },
+    });
+    assert_eq!(
+        Ok("Sui \\ level 3 {blue}".to_string()),
+        try_render_template(
+            r"{name} \\ level { level } \{{attributes.0.value}\}",
+            &fields
+        )
+    );
+    assert_eq!(
+        Err(DisplayError::UnresolvedField {
+            template: "{owner.name}".into(),
+            field: "owner.name".into(),
+        }),
+        try_render_template("{owner.name}", &fields)
+    );
+    assert!(matches!(
+        try_render_template("{name", &fields),
+        Err(DisplayError::UnclosedPlaceholder { .. })
+    ));
+    assert!(matches!(
+        try_render_template("name}", &fields),
+        Err(DisplayError::UnmatchedBrace { .. })
+    ));
+    let rendered = DisplayTemplate::new([("name", "{name}"), ("image_url", "{url}")]);
+    assert_eq!(1, rendered.render(&fields).len());
+    assert!(rendered.try_render(&fields)["image_url"].is_err());
+}
+
+#[test]
+fn nft_media_url_test() -> Result<(), anyhow::Error> {
+    let fetcher = MediaFetcher::default();
+    assert_eq!(
+        "https://ipfs.io/ipfs/bafy/image.png",
+        fetcher.resolve("ipfs://bafy/image.png")?.as_str()
+    );
+    assert_eq!(
+        "https://ipfs.io/ipfs/bafy",
+        fetcher.resolve("ipfs://ipfs/bafy")?.as_str()
+    );
+    assert_eq!(
+        "https://arweave.net/tx-id",
+        fetcher.resolve("ar://tx-id")?.as_str()
+    );
+    assert_eq!(
+        "https://example.com/nft.png",
+        fetcher.resolve("https://example.com/nft.png")?.as_str()
+    );
+    assert!(fetcher.resolve("http://example.com/nft.png").is_err());
+    assert!(fetcher.resolve("file:///etc/passwd").is_err());
+    assert!(fetcher.resolve("ipfs://").is_err());
+    assert!(fetcher
+        .with_allow_http(true)
+        .resolve("http://localhost/nft.png")
+        .is_ok());
+    Ok(())
+}
+
+#[tokio::test]
+async fn nft_media_cache_policy_test() -> Result<(), anyhow::Error> {
+    let cache = Arc::new(LruCache::new(4));
+    let url = "https://example.com/metadata.json";
+    let media = Media {
+        url: url.to_string(),
+        content_type: "application/json".to_string(),
+        bytes: br#"{"name":"nft"}"#.to_vec(),
+    };
+    cache
+        .put(CacheCategory::NftMedia, url, bcs::to_bytes(&media)?, None)
+        .await?;
+    let fetcher = MediaFetcher::default().with_cache(cache, None);
+
+    // Cache hits are served only if the fetcher would have fetched them.
+    assert_eq!(json!({ "name": "nft" }), fetcher.fetch_json(url).await?);
+    assert!(fetcher.fetch(url).await.is_err());
+    assert!(fetcher
+        .clone()
+        .with_content_types(["application/"])
+        .with_max_size(4)
+        .fetch(url)
+        .await
+        .is_err());
+    assert_eq!(
+        media,
+        fetcher
+            .with_content_types(["application/"])
+            .fetch(url)
+            .await?
+    );
+    Ok(())
+}
+
+#[test]
+fn object_registry_test() -> Result<(), anyhow::Error> {
+    let config = Config::from_toml(
+        r#"
+        [networks.local]
+        http_url = "http://127.0.0.1:5001"
+
+        [networks.local.objects]
+        game = "0x4a1f0e5c1bd44c7b4e1c9d7c2c2a3f6c1e8f3b2a"
+        "#,
+    )?;
+    let objects = &config.network(Some("local"))?.objects;
+    let game = ObjectID::from_hex_literal("0x4a1f0e5c1bd44c7b4e1c9d7c2c2a3f6c1e8f3b2a")?;
+    assert_eq!(game, objects.resolve("game")?);
+    assert_eq!(Some("game"), objects.name_of(&game));
+    assert_eq!(
+        sui_types::SUI_SYSTEM_STATE_OBJECT_ID,
+        objects.resolve("system_state")?
+    );
+    assert_eq!(WellKnownObject::SuiFramework.id(), objects.resolve("0x2")?);
+    assert!(objects.resolve("unknown").is_err());
+
+    let shadowed = ObjectRegistry::default().with_alias("sui", game);
+    assert_eq!(game, shadowed.resolve("sui")?);
+    assert_eq!(
+        Some(WellKnownObject::MoveStdlib),
+        WellKnownObject::from_id(&ObjectID::from_single_byte(1))
+    );
+    Ok(())
+}
+
+#[test]
+fn system_state_summary_test() -> Result<(), anyhow::Error> {
+    let address = SuiAddress::random_for_testing_only();
+    let current = json!({
+        "info": { "id": "0x5" },
+        "epoch": 3,
+        "validators": {
+            "validator_stake": 100,
+            "delegation_stake": 20,
+            "active_validators": [{
+                "metadata": { "sui_address": address.to_string(), "name": Base64::encode("v1") },
+                "stake_amount": 100,
+                "gas_price": 1,
+                "delegation_staking_pool": { "sui_balance": 20 },
+            }],
+            "pending_validators": [],
+        },
+        "treasury_cap": { "value": 1000 },
+        "storage_fund": 7,
+        "parameters": { "min_validator_stake": 10, "storage_gas_price": 2 },
+        "reference_gas_price": 1,
+    });
+    let summary = SystemStateSummary::from_fields(&current)?;
+    assert_eq!(120, summary.total_stake);
+    assert_eq!(7, summary.storage_fund);
+    assert_eq!(None, summary.stake_subsidy);
+    assert_eq!(Some(&10), summary.parameters.get("min_validator_stake"));
+    let validator = summary.validator(&address).unwrap();
+    assert_eq!(
+        ("v1", 20),
+        (validator.name.as_str(), validator.delegated_stake)
+    );
+
+    // A later layout, with numbers as strings, a storage fund struct and stake subsidies.
+    let upgraded = json!({
+        "epoch": "4",
+        "validators": {
+            "total_stake": "120",
+            "active_validators": [{
+                "metadata": { "sui_address": address.to_string(), "name": "v1" },
+                "next_epoch_gas_price": "1",
+                "staking_pool": { "sui_balance": "120" },
+            }],
+        },
+        "treasury_cap": { "total_supply": { "value": "1000" } },
+        "storage_fund": { "total_object_storage_rebates": "5", "non_refundable_balance": "2" },
+        "stake_subsidy": { "current_distribution_amount": "3" },
+        "parameters": { "min_validator_stake": "10" },
+        "reference_gas_price": "1",
+    });
+    let summary = SystemStateSummary::from_fields(&upgraded)?;
+    assert_eq!(
+        (4, 120, 7),
+        (summary.epoch, summary.total_stake, summary.storage_fund)
+    );
+    assert_eq!(Some(3), summary.stake_subsidy);
+    assert_eq!("v1", summary.active_validators[0].name);
+    assert!(SystemStateSummary::from_fields(&json!({ "epoch": 1 })).is_err());
+    Ok(())
+}
+
+#[tokio::test]
+async fn address_fan_in_test() {
+    let (watched, other) = (
+        SuiAddress::random_for_testing_only(),
+        SuiAddress::random_for_testing_only(),
+    );
+    let transfer = |sender, recipient| SuiEventEnvelope {
+        timestamp: 0,
+        tx_digest: None,
+        event: SuiEvent::TransferObject {
+            package_id: sui_types::SUI_FRAMEWORK_OBJECT_ID,
+            transaction_module: "pay".to_string(),
+            sender,
+            recipient: Owner::AddressOwner(recipient),
+            object_id: ObjectID::random(),
+            version: SequenceNumber::from(1),
+            type_: TransferType::Coin,
+            amount: Some(1),
+        },
+    };
+    let events = futures::stream::iter(vec![
+        Ok(transfer(other, watched)),
+        Ok(transfer(other, other)),
+        Err(Error::BufferOverflow(1)),
+        Ok(transfer(watched, watched)),
+    ]);
+    let tagged = tag_events(events, BTreeSet::from([watched]))
+        .collect::<Vec<_>>()
+        .await;
+    assert_eq!(3, tagged.len());
+    assert_eq!(vec![watched], tagged[0].as_ref().unwrap().addresses);
+    assert!(tagged[1].is_err());
+    assert_eq!(vec![watched], tagged[2].as_ref().unwrap().addresses);
+    assert!(matches!(
+        address_filter(&BTreeSet::from([watched])),
+        SuiEventFilter::Any(filters) if filters.len() == 3
+    ));
+}
+
+fn object_ref() -> ObjectRef {
+    (
+        ObjectID::random(),
+        SequenceNumber::from(1),
+        ObjectDigest::random(),
+    )
+}
+
+#[test]
+fn offline_builder_test() -> Result<(), anyhow::Error> {
+    let signer = SuiAddress::random_for_testing_only();
+    let recipient = SuiAddress::random_for_testing_only();
+    let (object, gas) = (object_ref(), object_ref());
+    let data = offline_builder::transfer_object(signer, object, recipient, gas, 1000);
+    assert_eq!(signer, data.signer());
+    assert_eq!(gas, data.gas());
+    assert_eq!(1000, data.gas_budget);
+    assert!(matches!(
+        &data.kind,
+        TransactionKind::Single(SingleTransactionKind::TransferObject(transfer))
+            if transfer.object_ref == object && transfer.recipient == recipient
+    ));
+
+    let calls = vec![
+        offline_builder::transfer_object_call(object, recipient),
+        offline_builder::transfer_object_call(object_ref(), recipient),
+    ];
+    let data = offline_builder::transaction(signer, calls, gas, 1000)?;
+    assert!(matches!(&data.kind, TransactionKind::Batch(calls) if calls.len() == 2));
+    assert!(offline_builder::transaction(signer, vec![], gas, 1000).is_err());
+    Ok(())
+}
+
+/// The Move call of a single command, as `(module, function, type arguments, arguments)`.
+fn move_call(kind: SingleTransactionKind) -> (String, String, Vec<TypeTag>, Vec<CallArg>) {
+    match kind {
+        SingleTransactionKind::Call(MoveCall {
+            module,
+            function,
+            type_arguments,
+            arguments,
+            ..
+        }) => (
+            module.to_string(),
+            function.to_string(),
+            type_arguments,
+            arguments,
+        ),
+        kind => panic!("expected a Move call, got {kind:?}"),
+    }
+}
+
+fn pure<T: serde::Serialize>(value: &T) -> CallArg {
+    CallArg::Pure(bcs::to_bytes(value).unwrap())
+}
+
+fn owned(object: ObjectRef) -> CallArg {
+    CallArg::Object(ObjectArg::ImmOrOwnedObject(object))
+}
+
+fn system_state() -> CallArg {
+    CallArg::Object(ObjectArg::SharedObject(SUI_SYSTEM_STATE_OBJECT_ID))
+}
+
+#[test]
+fn delegation_calls_test() -> Result<(), anyhow::Error> {
+    let framework = object_ref();
+    let validator = SuiAddress::random_for_testing_only();
+    let (coin, delegation, staked_sui) = (object_ref(), object_ref(), object_ref());
+
+    // request_add_delegation(self, delegate_stake: Coin<SUI>, validator_address: address, ctx)
+    let call = offline_builder::add_delegation_call(framework, coin, validator)?;
+    assert_eq!(
+        (
+            "sui_system".to_owned(),
+            "request_add_delegation".to_owned(),
+            vec![],
+            vec![system_state(), owned(coin), pure(&validator)],
+        ),
+        move_call(call)
+    );
+
+    // request_withdraw_delegation(
+    //     self, delegation, staked_sui, withdraw_pool_token_amount: u64, ctx)
+    let call = offline_builder::withdraw_delegation_call(framework, delegation, staked_sui, 5)?;
+    assert_eq!(
+        (
+            "sui_system".to_owned(),
+            "request_withdraw_delegation".to_owned(),
+            vec![],
+            vec![
+                system_state(),
+                owned(delegation),
+                owned(staked_sui),
+                pure(&5u64),
+            ],
+        ),
+        move_call(call)
+    );
+
+    // request_add_stake(self, new_stake: Coin<SUI>, ctx)
+    let call = offline_builder::add_stake_call(framework, coin);
+    assert_eq!(
+        (
+            "sui_system".to_owned(),
+            "request_add_stake".to_owned(),
+            vec![],
+            vec![system_state(), owned(coin)],
+        ),
+        move_call(call)
+    );
+
+    // request_withdraw_stake(self, stake, withdraw_amount: u64, ctx)
+    let call = offline_builder::withdraw_stake_call(framework, staked_sui, 7)?;
+    assert_eq!(
+        (
+            "sui_system".to_owned(),
+            "request_withdraw_stake".to_owned(),
+            vec![],
+            vec![system_state(), owned(staked_sui), pure(&7u64)],
+        ),
+        move_call(call)
+    );
+    Ok(())
+}
+
+#[test]
+fn validator_calls_test() -> Result<(), anyhow::Error> {
+    let (framework, stake) = (object_ref(), object_ref());
+    let registration = offline_builder::ValidatorRegistration {
+        pubkey_bytes: vec![1; 96],
+        network_pubkey_bytes: vec![2; 32],
+        proof_of_possession: vec![3; 48],
+        name: "validator-0".to_owned(),
+        net_address: vec![4, 5, 6],
+        gas_price: 100,
+    };
+
+    // request_add_validator(self, pubkey_bytes, network_pubkey_bytes, proof_of_possession,
+    //     name, net_address, stake: Coin<SUI>, gas_price: u64, ctx)
+    let call = offline_builder::add_validator_call(framework, &registration, stake)?;
+    assert_eq!(
+        (
+            "sui_system".to_owned(),
+            "request_add_validator".to_owned(),
+            vec![],
+            vec![
+                system_state(),
+                pure(&vec![1u8; 96]),
+                pure(&vec![2u8; 32]),
+                pure(&vec![3u8; 48]),
+                pure(&b"validator-0".to_vec()),
+                pure(&vec![4u8, 5, 6]),
+                owned(stake),
+                pure(&100u64),
+            ],
+        ),
+        move_call(call)
+    );
+
+    // request_remove_validator(self, ctx)
+    let call = offline_builder::remove_validator_call(framework);
+    assert_eq!(
+        (
+            "sui_system".to_owned(),
+            "request_remove_validator".to_owned(),
+            vec![],
+            vec![system_state()],
+        ),
+        move_call(call)
+    );
+
+    // request_set_gas_price(self, new_gas_price: u64, ctx)
+    let call = offline_builder::set_gas_price_call(framework, 250)?;
+    assert_eq!(
+        (
+            "sui_system".to_owned(),
+            "request_set_gas_price".to_owned(),
+            vec![],
+            vec![system_state(), pure(&250u64)],
+        ),
+        move_call(call)
+    );
+    Ok(())
+}
+
+#[test]
+fn locked_coin_calls_test() -> Result<(), anyhow::Error> {
+    let (framework, coin) = (object_ref(), object_ref());
+    let recipient = SuiAddress::random_for_testing_only();
+    let coin_type = sui_types::parse_sui_type_tag("0x2::sui::SUI").unwrap();
+
+    // lock_coin<T>(coin: Coin<T>, recipient: address, locked_until_epoch: u64, ctx)
+    let call = offline_builder::lock_coin_call(framework, coin, coin_type.clone(), recipient, 9)?;
+    assert_eq!(
+        (
+            "locked_coin".to_owned(),
+            "lock_coin".to_owned(),
+            vec![coin_type.clone()],
+            vec![owned(coin), pure(&recipient), pure(&9u64)],
+        ),
+        move_call(call)
+    );
+
+    // unlock_coin<T>(locked_coin: LockedCoin<T>, ctx)
+    let call = offline_builder::unlock_coin_call(framework, coin, coin_type.clone());
+    assert_eq!(
+        (
+            "locked_coin".to_owned(),
+            "unlock_coin".to_owned(),
+            vec![coin_type],
+            vec![owned(coin)],
+        ),
+        move_call(call)
+    );
+    Ok(())
+}
+
+#[tokio::test]
+async fn vesting_calls_test() -> Result<(), anyhow::Error> {
+    let framework = object_ref();
+    let grantee = SuiAddress::random_for_testing_only();
+    let coin_type = sui_types::parse_sui_type_tag("0x2::sui::SUI").unwrap();
+    let tranches = VestingSchedule::linear(1000, 5, 4).tranches();
+    let coins = tranches.iter().map(|_| object_ref()).collect::<Vec<_>>();
+
+    // At epoch 7 the tranches of epochs 6 and 7 are sent as is, the later ones locked.
+    let calls = tranches
+        .iter()
+        .zip(&coins)
+        .map(|(tranche, coin)| {
+            offline_builder::vesting_tranche_call(
+                framework,
+                *coin,
+                coin_type.clone(),
+                grantee,
+                tranche.unlock_epoch,
+                7,
+            )
+        })
+        .collect::<SuiRpcResult<Vec<_>>>()?;
+    assert_eq!(
+        vec![6, 7, 8, 9],
+        tranches
+            .iter()
+            .map(|tranche| tranche.unlock_epoch)
+            .collect::<Vec<_>>()
+    );
+    for (call, coin) in calls[..2].iter().zip(&coins) {
+        assert!(matches!(
+            call,
+            SingleTransactionKind::TransferObject(transfer)
+                if transfer.object_ref == *coin && transfer.recipient == grantee
+        ));
+    }
+    for ((call, coin), epoch) in calls[2..].iter().zip(&coins[2..]).zip([8u64, 9]) {
+        assert_eq!(
+            (
+                "locked_coin".to_owned(),
+                "lock_coin".to_owned(),
+                vec![coin_type.clone()],
+                vec![owned(*coin), pure(&grantee), pure(&epoch)],
+            ),
+            move_call(call.clone())
+        );
+    }
+
+    // The coins must match the tranches one to one, checked before reading from the node.
+    let node = MockNode::start(|_, _| Reply::Close).await?;
+    let client = SuiClientBuilder::default().build(&node.url, None).await?;
+    let schedule = VestingSchedule::linear(1000, 5, 4);
+    let requests = node.requests();
+    let result = client
+        .transaction_builder()
+        .create_vesting(
+            SuiAddress::random_for_testing_only(),
+            &schedule,
+            grantee,
+            vec![ObjectID::random()],
+            None,
+            1000,
+        )
+        .await;
+    assert_eq!(
+        "The schedule has 4 tranches, 1 coins were given.",
+        result.unwrap_err().to_string()
+    );
+    assert_eq!(requests, node.requests());
+
+    let locked_coin = |unlock_epoch, amount| LockedCoinSummary {
+        object_id: ObjectID::random(),
+        amount,
+        unlock_epoch,
+    };
+    let position = VestingPosition {
+        epoch: 7,
+        locked_coins: vec![
+            locked_coin(6, 250),
+            locked_coin(7, 250),
+            locked_coin(8, 250),
+            locked_coin(9, 250),
+        ],
+    };
+    assert_eq!(
+        vec![
+            position.locked_coins[0].object_id,
+            position.locked_coins[1].object_id
+        ],
+        position.claimable()
+    );
+    assert_eq!(500, position.claimable_amount());
+    assert_eq!(500, position.unvested_amount());
+    assert_eq!(Some(8), position.next_unlock_epoch());
+    Ok(())
+}
+
+fn coin_object(owner: SuiAddress, coin_type: &str, balance: u64) -> Object {
+    let id = ObjectID::random();
+    let coin_type = sui_types::parse_sui_struct_tag(coin_type).unwrap();
+    let contents = Coin::new(UID::new(id), balance).to_bcs_bytes();
+    Object::new_move(
+        MoveObject::new_coin(Coin::type_(coin_type), SequenceNumber::from(1), contents),
+        Owner::AddressOwner(owner),
+        TransactionDigest::genesis(),
+    )
+}
+
+/// A node serving the owned objects and raw objects of `objects`.
+async fn object_node(objects: Vec<Object>) -> Result<MockNode, anyhow::Error> {
+    MockNode::start(move |method, params| {
+        Reply::Result(match method {
+            "sui_getObjectsOwnedByAddress" => {
+                let address = SuiAddress::from_str(params[0].as_str().unwrap()).unwrap();
+                let infos = objects
+                    .iter()
+                    .filter(|object| object.owner == Owner::AddressOwner(address))
+                    .map(|object| SuiObjectInfo {
+                        object_id: object.id(),
+                        version: object.version(),
+                        digest: object.digest(),
+                        type_: object.data.type_().unwrap().to_string(),
+                        owner: object.owner,
+                        previous_transaction: object.previous_transaction,
+                    })
+                    .collect::<Vec<_>>();
+                json!(infos)
+            }
+            "sui_getRawObject" => {
+                let id = ObjectID::from_str(params[0].as_str().unwrap()).unwrap();
+                let response = match objects.iter().find(|object| object.id() == id) {
+                    Some(object) => SuiObjectRead::Exists(
+                        SuiObject::<SuiRawData>::try_from(
+                            object.clone(),
+                            Some(MoveStructLayout::Runtime(vec![])),
+                        )
+                        .unwrap(),
+                    ),
+                    None => SuiObjectRead::NotExists(id),
+                };
+                json!(response)
+            }
+            _ => Value::Null,
+        })
+    })
+    .await
+}
+
+#[tokio::test]
+async fn sweep_test() -> Result<(), anyhow::Error> {
+    const SUI: &str = "0x2::sui::SUI";
+    const USDC: &str = "0x5::usdc::USDC";
+    let address = || SuiAddress::random_for_testing_only();
+    let (mixed, sui_only, unfunded, empty) = (address(), address(), address(), address());
+    let (cold, fee_payer) = (address(), address());
+    let mixed_gas = coin_object(mixed, SUI, 100);
+    let sui_only_gas = coin_object(sui_only, SUI, 500);
+    let payer_gas = coin_object(fee_payer, SUI, 1000);
+    let payer_coin = coin_object(fee_payer, SUI, 200);
+    let (mixed_sui, mixed_usdc) = (coin_object(mixed, SUI, 30), coin_object(mixed, USDC, 50));
+    let objects = vec![
+        mixed_gas.clone(),
+        mixed_sui.clone(),
+        mixed_usdc.clone(),
+        coin_object(mixed, SUI, 5),
+        sui_only_gas.clone(),
+        coin_object(unfunded, USDC, 70),
+        payer_gas.clone(),
+        payer_coin.clone(),
+    ];
+    let node = object_node(objects).await?;
+    let client = SuiClientBuilder::default().build(&node.url, None).await?;
+    let builder = client.transaction_builder();
+
+    let deposits = [mixed, sui_only, unfunded, empty];
+    let plan = builder.sweep(&deposits, cold, Some(fee_payer), 10).await?;
+    assert_eq!(vec![unfunded], plan.unfunded);
+    assert_eq!(
+        vec![mixed, sui_only],
+        plan.sweeps
+            .iter()
+            .map(|sweep| sweep.address)
+            .collect::<Vec<_>>()
+    );
+
+    // The largest SUI coin pays the gas and stays behind, every other coin type is paid to
+    // the cold address as a whole.
+    let sweep = &plan.sweeps[0];
+    let sui_type = GasCoin::type_().to_string();
+    let usdc_type = Coin::type_(sui_types::parse_sui_struct_tag(USDC)?).to_string();
+    assert_eq!(
+        BTreeMap::from([(sui_type.clone(), 35), (usdc_type, 50)]),
+        sweep.amounts
+    );
+    assert_eq!(mixed, sweep.data.signer());
+    assert_eq!(mixed_gas.compute_object_reference(), sweep.data.gas());
+    let payments = match &sweep.data.kind {
+        TransactionKind::Batch(payments) => payments,
+        kind => panic!("expected a batch, got {kind:?}"),
+    };
+    assert_eq!(2, payments.len());
+    let mut paid = BTreeSet::new();
+    for payment in payments {
+        match payment {
+            SingleTransactionKind::Pay(Pay {
+                coins,
+                recipients,
+                amounts,
+            }) => {
+                assert_eq!(vec![cold], *recipients);
+                assert_eq!(1, amounts.len());
+                paid.extend(coins.iter().map(|coin| coin.0));
+            }
+            kind => panic!("expected a payment, got {kind:?}"),
+        }
+    }
+    assert!(paid.contains(&mixed_sui.id()) && paid.contains(&mixed_usdc.id()));
+    assert!(!paid.contains(&mixed_gas.id()));
+    assert_eq!(3, paid.len());
+
+    // An address holding only its gas coin sends it as a whole.
+    let sweep = &plan.sweeps[1];
+    assert_eq!(BTreeMap::from([(sui_type, 490)]), sweep.amounts);
+    assert_eq!(sui_only_gas.compute_object_reference(), sweep.data.gas());
+    assert!(matches!(
+        &sweep.data.kind,
+        TransactionKind::Single(SingleTransactionKind::TransferSui(transfer))
+            if transfer.recipient == cold && transfer.amount.is_none()
+    ));
+
+    // The fee payer funds the gas of the unfunded address from its other SUI coins.
+    let funding = plan.funding.unwrap();
+    assert_eq!(fee_payer, funding.signer());
+    assert_eq!(payer_gas.compute_object_reference(), funding.gas());
+    assert!(matches!(
+        &funding.kind,
+        TransactionKind::Single(SingleTransactionKind::Pay(pay))
+            if pay.coins == vec![payer_coin.compute_object_reference()]
+                && pay.recipients == vec![unfunded]
+                && pay.amounts == vec![10]
+    ));
+
+    // Without a fee payer, unfunded addresses are only reported.
+    let plan = builder.sweep(&deposits, cold, None, 10).await?;
+    assert_eq!((vec![unfunded], None), (plan.unfunded, plan.funding));
+
+    // A fee payer needs a SUI coin besides its gas coin.
+    let result = builder.sweep(&[unfunded], cold, Some(sui_only), 10).await;
+    assert!(result.is_err());
+    Ok(())
+}
+
+#[tokio::test]
+async fn portfolio_test() -> Result<(), anyhow::Error> {
+    const SUI: &str = "0x2::sui::SUI";
+    const USDC: &str = "0x5::usdc::USDC";
+    let (alice, bob) = (
+        SuiAddress::random_for_testing_only(),
+        SuiAddress::random_for_testing_only(),
+    );
+    let objects = vec![
+        coin_object(alice, SUI, 100),
+        coin_object(alice, SUI, 20),
+        coin_object(bob, SUI, 3),
+        coin_object(alice, USDC, u64::MAX - 1),
+        coin_object(alice, USDC, 5),
+        coin_object(bob, USDC, 7),
+    ];
+    let node = object_node(objects).await?;
+    let client = SuiClientBuilder::default().build(&node.url, None).await?;
+    let portfolio = client.get_portfolio(&[alice, bob]).await?;
+    let holding = |symbol: &str| {
+        let mut holdings = portfolio.holdings.values();
+        holdings.find(|holding| holding.symbol == symbol).unwrap()
+    };
+
+    let sui = holding("SUI");
+    assert_eq!((123, 3), (sui.total, sui.coin_count));
+    assert_eq!(BTreeMap::from([(alice, 120), (bob, 3)]), sui.balances);
+    // Sums beyond the range of a balance saturate.
+    let usdc = holding("USDC");
+    assert_eq!((u64::MAX, 3), (usdc.total, usdc.coin_count));
+    assert_eq!(BTreeMap::from([(alice, u64::MAX), (bob, 7)]), usdc.balances);
+    Ok(())
+}
+
+/// A Move object of type `type_` holding `value`, without public transfer like the staking
+/// objects.
+fn move_object<T: serde::Serialize>(type_: StructTag, owner: Owner, value: &T) -> Object {
+    let contents = bcs::to_bytes(value).unwrap();
+    let object =
+        unsafe { MoveObject::new_from_execution(type_, false, SequenceNumber::from(1), contents) };
+    Object::new_move(object, owner, TransactionDigest::genesis())
+}
+
+/// The system state at epoch 6 with one validator, whose delegation pool started at epoch 2
+/// and holds 300 SUI for 200 pool tokens.
+fn staking_system_state(
+    address: SuiAddress,
+    pending_delegations: Vec<PendingDelegationEntry>,
+) -> SuiSystemState {
+    let validator = Validator {
+        metadata: ValidatorMetadata {
+            sui_address: address.into(),
+            pubkey_bytes: vec![],
+            network_pubkey_bytes: vec![],
+            proof_of_possession_bytes: vec![],
+            name: b"validator-0".to_vec(),
+            net_address: vec![1, 2],
+            next_epoch_stake: 1000,
+            next_epoch_delegation: 300,
+            next_epoch_gas_price: 1,
+        },
+        stake_amount: 1000,
+        pending_stake: 0,
+        pending_withdraw: 0,
+        gas_price: 1,
+        delegation_staking_pool: StakingPool {
+            validator_address: address.into(),
+            starting_epoch: 2,
+            epoch_starting_sui_balance: 300,
+            sui_balance: 300,
+            rewards_pool: Balance::new(0),
+            delegation_token_supply: Supply { value: 200 },
+            pending_delegations,
+        },
+    };
+    SuiSystemState {
+        info: UID::new(SUI_SYSTEM_STATE_OBJECT_ID),
+        epoch: 6,
+        validators: ValidatorSet {
+            validator_stake: 1000,
+            delegation_stake: 300,
+            quorum_stake_threshold: 667,
+            active_validators: vec![validator],
+            pending_validators: vec![],
+            pending_removals: vec![],
+            next_epoch_validators: vec![],
+        },
+        treasury_cap: Supply { value: 0 },
+        storage_fund: Balance::new(0),
+        parameters: SystemParameters {
+            min_validator_stake: 0,
+            max_validator_candidate_count: 10,
+            storage_gas_price: 1,
+        },
+        reference_gas_price: 1,
+    }
+}
+
+#[tokio::test]
+async fn staking_test() -> Result<(), anyhow::Error> {
+    let (validator, delegator) = (
+        SuiAddress::random_for_testing_only(),
+        SuiAddress::random_for_testing_only(),
+    );
+    let owned = Owner::AddressOwner(delegator);
+    let staked_sui = |principal, lock: Option<u64>| {
+        let staked_sui = StakedSui {
+            id: UID::new(ObjectID::random()),
+            principal: Balance::new(principal),
+            sui_token_lock: MoveOption {
+                vec: lock
+                    .into_iter()
+                    .map(|epoch| EpochTimeLock { epoch })
+                    .collect(),
+            },
+        };
+        move_object(StakedSui::type_(), owned, &staked_sui)
+    };
+    let delegation = Delegation {
+        id: UID::new(ObjectID::random()),
+        validator_address: validator.into(),
+        pool_starting_epoch: 2,
+        pool_tokens: Balance::new(100),
+        principal_sui_amount: 120,
+    };
+    // One of the two stakes of 40 SUI was requested in the current epoch.
+    let pending = vec![PendingDelegationEntry {
+        delegator: delegator.into(),
+        sui_amount: 40,
+    }];
+    let state = staking_system_state(validator, pending);
+    let objects = vec![
+        move_object(SuiSystemState::type_(), Owner::Shared, &state),
+        move_object(Delegation::type_(), owned, &delegation),
+        staked_sui(120, None),
+        staked_sui(40, Some(9)),
+        staked_sui(40, None),
+    ];
+    let ids = objects.iter().map(|object| object.id()).collect::<Vec<_>>();
+    let node = object_node(objects).await?;
+    let client = SuiClientBuilder::default().build(&node.url, None).await?;
+    let read_api = client.read_api();
+
+    let validators = read_api.get_validators().await?;
+    assert_eq!(1, validators.len());
+    let summary = &validators[0];
+    assert_eq!(
+        ("validator-0", 1000, 300, 2, 6),
+        (
+            summary.name.as_str(),
+            summary.stake_amount,
+            summary.delegation_amount,
+            summary.pool_starting_epoch,
+            summary.epoch
+        )
+    );
+    assert_eq!(1.5, summary.pool_exchange_rate);
+    // The pool grew by half over four epochs.
+    assert!((summary.estimated_apy(4) - 0.5).abs() < 1e-9);
+
+    let delegations = read_api.get_delegations(delegator).await?;
+    assert_eq!(1, delegations.len());
+    // 100 pool tokens are worth 150 SUI.
+    assert_eq!(
+        (ids[1], validator, 100, 120, 30),
+        (
+            delegations[0].object_id,
+            delegations[0].validator_address,
+            delegations[0].pool_tokens,
+            delegations[0].principal_sui_amount,
+            delegations[0].estimated_rewards
+        )
+    );
+
+    let staked = read_api
+        .get_staked_sui(delegator)
+        .await?
+        .into_iter()
+        .map(|staked| {
+            (
+                staked.object_id,
+                staked.principal,
+                staked.activation_epoch,
+                staked.locked_until_epoch,
+            )
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(
+        vec![
+            (ids[2], 120, None, None),
+            (ids[3], 40, Some(7), Some(9)),
+            (ids[4], 40, None, None),
+        ],
+        staked
+    );
+    assert!(read_api.get_staked_sui(validator).await?.is_empty());
+    Ok(())
+}
+
+#[tokio::test]
+async fn gas_cost_estimation_test() -> Result<(), anyhow::Error> {
+    let owner = SuiAddress::random_for_testing_only();
+    let (mut first, mut second) = (
+        coin_object(owner, "0x2::sui::SUI", 10),
+        coin_object(owner, "0x2::sui::SUI", 20),
+    );
+    first.storage_rebate = 30;
+    second.storage_rebate = 45;
+    let ids = [first.id(), second.id()];
+    let node = object_node(vec![first, second]).await?;
+    let client = SuiClientBuilder::default().build(&node.url, None).await?;
+    let read_api = client.read_api();
+
+    assert_eq!(75, read_api.estimate_storage_rebate(&ids).await?);
+    assert_eq!(30, read_api.estimate_storage_rebate(&ids[..1]).await?);
+    assert_eq!(0, read_api.estimate_storage_rebate(&[]).await?);
+    // Deleted or unknown objects have no rebate to estimate.
+    assert!(read_api
+        .estimate_storage_rebate(&[ObjectID::random()])
+        .await
+        .is_err());
+
+    // The net cost is computation + storage - rebate.
+    let cost = read_api.estimate_gas_cost(100, 50, &ids).await?;
+    assert_eq!(
+        SuiGasCostSummary {
+            computation_cost: 100,
+            storage_cost: 50,
+            storage_rebate: 75,
+        },
+        cost
+    );
+    assert_eq!(75, cost.net_gas_usage());
+    // A rebate larger than the costs is a net gain.
+    let cost = read_api.estimate_gas_cost(10, 5, &ids).await?;
+    assert_eq!(-60, cost.net_gas_usage());
+    Ok(())
+}
+
+/// A transaction at `seq` sending `coins` to their owners from another address.
+fn deposit_transaction(seq: u64, coins: &[&Object]) -> FollowedTransaction {
+    let digest = TransactionDigest::random();
+    let mut response: SuiTransactionResponse =
+        serde_json::from_value(transaction_response(digest)).unwrap();
+    response.effects.mutated = coins
+        .iter()
+        .map(|coin| OwnedObjectRef {
+            owner: coin.owner,
+            reference: coin.compute_object_reference().into(),
+        })
+        .collect();
+    FollowedTransaction {
+        seq,
+        digest,
+        response,
+    }
+}
+
+/// Run `tracker` over `transactions`, returning the deposits it sent.
+async fn track_deposits(
+    tracker: &mut DepositTracker<'_>,
+    transactions: Vec<FollowedTransaction>,
+) -> SuiRpcResult<Vec<Deposit>> {
+    let (sender, mut receiver) = tokio::sync::mpsc::channel(16);
+    let transactions = futures::stream::iter(transactions.into_iter().map(Ok));
+    tracker.run(transactions, sender).await?;
+    let mut deposits = vec![];
+    while let Some(deposit) = receiver.recv().await {
+        deposits.push(deposit);
+    }
+    Ok(deposits)
+}
+
+#[tokio::test]
+async fn deposit_tracker_test() -> Result<(), anyhow::Error> {
+    const SUI: &str = "0x2::sui::SUI";
+    let (hot, cold) = (
+        SuiAddress::random_for_testing_only(),
+        SuiAddress::random_for_testing_only(),
+    );
+    let (first, second) = (coin_object(hot, SUI, 100), coin_object(cold, SUI, 250));
+    let coins = vec![first.clone(), second.clone()];
+    let node = MockNode::start(move |method, params| {
+        Reply::Result(match method {
+            "sui_tryGetPastObject" => {
+                let id = ObjectID::from_str(params[0].as_str().unwrap()).unwrap();
+                let response = match coins.iter().find(|coin| coin.id() == id) {
+                    Some(coin) => SuiPastObjectRead::VersionFound(
+                        SuiObject::<SuiParsedData>::try_from(
+                            coin.clone(),
+                            Some(Coin::layout(sui_types::parse_sui_struct_tag(SUI).unwrap())),
+                        )
+                        .unwrap(),
+                    ),
+                    None => SuiPastObjectRead::ObjectNotExists(id),
+                };
+                json!(response)
+            }
+            _ => Value::Null,
+        })
+    })
+    .await?;
+    let client = SuiClientBuilder::default().build(&node.url, None).await?;
+    let dir = TempDir::new()?;
+    let path = dir.path().join("deposits.json");
+    let new_tracker = || {
+        DepositTracker::new(
+            client.read_api(),
+            [hot, cold],
+            ConfirmationPolicy::AfterTransactions(2),
+        )
+        .with_store(FileCursorStore::new(path.clone()), "deposits")
+    };
+    let summary = |deposits: Vec<Deposit>| {
+        deposits
+            .into_iter()
+            .map(|deposit| (deposit.seq, deposit.recipient, deposit.amount))
+            .collect::<Vec<_>>()
+    };
+    let seen = || {
+        let store = FileCursorStore::new(path.clone());
+        async move {
+            let state = store.load("deposits").await?;
+            anyhow::Ok(state.unwrap()["seen"].as_array().unwrap().len())
+        }
+    };
+
+    // The deposit waits for two later transactions.
+    let (deposit, transfer) = (
+        deposit_transaction(0, &[&first]),
+        deposit_transaction(1, &[]),
+    );
+    let mut tracker = new_tracker();
+    let deposits = track_deposits(&mut tracker, vec![deposit.clone(), transfer.clone()]).await?;
+    assert!(deposits.is_empty());
+
+    // After a restart, the pending deposit is restored and the replayed transaction is not
+    // reported again.
+    let mut tracker = new_tracker();
+    let transactions = vec![
+        deposit.clone(),
+        transfer,
+        deposit_transaction(2, &[&second]),
+    ];
+    let deposits = track_deposits(&mut tracker, transactions).await?;
+    assert_eq!(vec![(0, hot, 100)], summary(deposits));
+    assert_eq!(2, seen().await?);
+
+    // The coins reported before the confirmation horizon are forgotten.
+    let transactions = vec![deposit_transaction(3, &[]), deposit_transaction(4, &[])];
+    let deposits = track_deposits(&mut tracker, transactions).await?;
+    assert_eq!(vec![(2, cold, 250)], summary(deposits));
+    assert_eq!(1, seen().await?);
+
+    // Transactions before the horizon are ignored.
+    let transactions = vec![deposit, deposit_transaction(5, &[])];
+    assert!(track_deposits(&mut tracker, transactions).await?.is_empty());
+    assert_eq!(0, seen().await?);
+    Ok(())
+}
+
+/// Normalized Move signatures of a test package, as returned by a node.
+fn normalized_move_api(method: &str, params: &Value) -> Reply {
+    let struct_ = |address: &str, module: &str, name: &str, type_arguments: Value| {
+        json!({ "Struct": {
+            "address": address,
+            "module": module,
+            "name": name,
+            "type_arguments": type_arguments,
+        }})
+    };
+    let tx_context =
+        json!({ "MutableReference": struct_("0x2", "tx_context", "TxContext", json!([])) });
+    let function = |type_parameters: Value, parameters: Vec<Value>| {
+        json!({
+            "visibility": "Public",
+            "is_entry": true,
+            "type_parameters": type_parameters,
+            "parameters": parameters,
+            "return_": [],
+        })
+    };
+    let struct_abilities = |abilities: Value, type_parameters: Value| {
+        json!({
+            "abilities": { "abilities": abilities },
+            "type_parameters": type_parameters,
+            "fields": [],
+        })
+    };
+    let name = params[2].as_str().unwrap_or_default();
+    Reply::Result(match (method, name) {
+        // entry fun transfer<T: key + store>(
+        //     object: Wrapper<T>, recipient: address, ctx: &mut TxContext)
+        ("sui_getNormalizedMoveFunction", "transfer") => function(
+            json!([{ "abilities": ["Store", "Key"] }]),
+            vec![
+                struct_("0x3", "test", "Wrapper", json!([{ "TypeParameter": 0 }])),
+                json!("Address"),
+                tx_context,
+            ],
+        ),
+        // entry fun nested<T: copy + drop>(
+        //     bytes: vector<vector<u8>>, values: Option<vector<T>>, ctx: &TxContext)
+        ("sui_getNormalizedMoveFunction", "nested") => function(
+            json!([{ "abilities": ["Copy", "Drop"] }]),
+            vec![
+                json!({ "Vector": { "Vector": "U8" } }),
+                struct_(
+                    "0x1",
+                    "option",
+                    "Option",
+                    json!([{ "Vector": { "TypeParameter": 0 } }]),
+                ),
+                json!({ "Reference": struct_("0x2", "tx_context", "TxContext", json!([])) }),
+            ],
+        ),
+        ("sui_getNormalizedMoveFunction", "view") => {
+            let mut function = function(json!([]), vec![]);
+            function["is_entry"] = json!(false);
+            function
+        }
+        ("sui_getNormalizedMoveStruct", "Hero") => {
+            struct_abilities(json!(["Key", "Store"]), json!([]))
+        }
+        ("sui_getNormalizedMoveStruct", "Ticket") => struct_abilities(json!(["Key"]), json!([])),
+        // struct Box<T> has key, store, and Tagged<phantom T> has key, store.
+        ("sui_getNormalizedMoveStruct", "Box" | "Tagged") => struct_abilities(
+            json!(["Key", "Store"]),
+            json!([{ "constraints": { "abilities": [] }, "is_phantom": name == "Tagged" }]),
+        ),
+        _ => Value::Null,
+    })
+}
+
+#[tokio::test]
+async fn move_call_validation_test() -> Result<(), anyhow::Error> {
+    let node = MockNode::start(normalized_move_api).await?;
+    let client = SuiClientBuilder::default().build(&node.url, None).await?;
+    let api = client.full_node_api();
+    let package = ObjectID::from_hex_literal("0x3")?;
+    let args = |values: Vec<Value>| -> Vec<SuiJsonValue> {
+        values
+            .into_iter()
+            .map(|value| SuiJsonValue::new(value).unwrap())
+            .collect()
+    };
+    let type_tag = |tag: &str| sui_types::parse_sui_type_tag(tag).unwrap();
+    let object = json!(ObjectID::random().to_hex_literal());
+    let recipient = json!(SuiAddress::random_for_testing_only().to_string());
+    let validate = |function: &'static str, type_args: Vec<TypeTag>, call_args: Vec<Value>| {
+        let call_args = args(call_args);
+        async move {
+            api.validate_move_call(package, "test", function, &type_args, &call_args)
+                .await
+        }
+    };
+
+    // The trailing `&mut TxContext` is not an argument of the call.
+    let hero = type_tag("0x3::test::Hero");
+    validate(
+        "transfer",
+        vec![hero.clone()],
+        vec![object.clone(), recipient.clone()],
+    )
+    .await?;
+    assert!(matches!(
+        validate("transfer", vec![hero.clone()], vec![object.clone()]).await,
+        Err(MoveCallValidationError::ArgumentCountMismatch {
+            expected: 2,
+            actual: 1
+        })
+    ));
+    assert!(matches!(
+        validate(
+            "transfer",
+            vec![hero.clone()],
+            vec![object.clone(), recipient.clone(), object.clone()]
+        )
+        .await,
+        Err(MoveCallValidationError::ArgumentCountMismatch {
+            expected: 2,
+            actual: 3
+        })
+    ));
+    assert!(matches!(
+        validate("transfer", vec![], vec![object.clone(), recipient.clone()]).await,
+        Err(MoveCallValidationError::TypeArgumentCountMismatch {
+            expected: 1,
+            actual: 0
+        })
+    ));
+    assert!(matches!(
+        validate(
+            "transfer",
+            vec![hero.clone()],
+            vec![recipient.clone(), json!(7)]
+        )
+        .await,
+        Err(MoveCallValidationError::ArgumentTypeMismatch { index: 1, .. })
+    ));
+    assert!(matches!(
+        validate("view", vec![], vec![]).await,
+        Err(MoveCallValidationError::NotEntryFunction { .. })
+    ));
+
+    // Type arguments must have the abilities of their type parameter.
+    let missing = |type_arg: &str| {
+        let type_arg = type_tag(type_arg);
+        let call_args = vec![object.clone(), recipient.clone()];
+        let validation = validate("transfer", vec![type_arg], call_args);
+        async move {
+            match validation.await {
+                Err(MoveCallValidationError::TypeArgumentConstraintNotSatisfied {
+                    index: 0,
+                    missing,
+                    ..
+                }) => missing,
+                Ok(()) => vec![],
+                Err(e) => panic!("Unexpected error {e}"),
+            }
+        }
+    };
+    assert_eq!(vec![SuiMoveAbility::Key], missing("u64").await);
+    assert_eq!(
+        vec![SuiMoveAbility::Key],
+        missing("vector<0x3::test::Hero>").await
+    );
+    assert_eq!(
+        vec![SuiMoveAbility::Store],
+        missing("0x3::test::Ticket").await
+    );
+    assert!(missing("0x3::test::Box<0x3::test::Hero>").await.is_empty());
+    // A struct has `key` and `store` only if its type arguments have `store`, unless they
+    // are phantom.
+    assert_eq!(
+        vec![SuiMoveAbility::Store, SuiMoveAbility::Key],
+        missing("0x3::test::Box<0x3::test::Ticket>").await
+    );
+    assert!(missing("0x3::test::Tagged<0x3::test::Ticket>")
+        .await
+        .is_empty());
+
+    // Arguments are matched against nested generic parameter types.
+    let u64_type = vec![type_tag("u64")];
+    validate(
+        "nested",
+        u64_type.clone(),
+        vec![json!([[1, 2], [3]]), json!([[4, 5]])],
+    )
+    .await?;
+    validate(
+        "nested",
+        u64_type.clone(),
+        vec![json!(["bytes"]), json!([])],
+    )
+    .await?;
+    assert!(matches!(
+        validate("nested", u64_type.clone(), vec![json!([[256]]), json!([])]).await,
+        Err(MoveCallValidationError::ArgumentTypeMismatch { index: 0, .. })
+    ));
+    assert!(matches!(
+        validate(
+            "nested",
+            u64_type.clone(),
+            vec![json!([[1]]), json!([[1], [2]])]
+        )
+        .await,
+        Err(MoveCallValidationError::ArgumentTypeMismatch { index: 1, .. })
+    ));
+    assert!(matches!(
+        validate(
+            "nested",
+            vec![type_tag("bool")],
+            vec![json!([[1]]), json!([[4]])]
+        )
+        .await,
+        Err(MoveCallValidationError::ArgumentTypeMismatch { index: 1, .. })
+    ));
+    Ok(())
+}
+
+/// The example `sui_getTransaction` response of the RPC spec, with `digest`.
+fn transaction_response(digest: TransactionDigest) -> Value {
+    let spec: Value =
+        serde_json::from_str(include_str!("../../sui-open-rpc/spec/openrpc.json")).unwrap();
+    let method = spec["methods"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|method| method["name"] == "sui_getTransaction")
+        .unwrap();
+    let mut response = method["examples"][0]["result"]["value"].clone();
+    response["certificate"]["transactionDigest"] = json!(digest);
+    response
+}
+
+/// A WebSocket node streaming `transactions` to every transaction subscriber, then ending
+/// the subscription.
+struct TransactionFeed(Vec<Value>);
+
+#[async_trait]
+impl TransactionStreamingApiServer for TransactionFeed {
+    fn subscribe_transaction(
+        &self,
+        sink: SubscriptionSink,
+        _: SuiTransactionFilter,
+    ) -> SubscriptionResult {
+        let transactions = self.0.clone().into_iter().map(anyhow::Ok);
+        spawn_subscription(sink, futures::stream::iter(transactions));
+        Ok(())
+    }
+}
+
+impl TransactionFeed {
+    async fn start(self) -> anyhow::Result<(String, WsServerHandle)> {
+        let server = WsServerBuilder::default().build("127.0.0.1:0").await?;
+        let url = format!("ws://{}", server.local_addr()?);
+        let mut module = self.into_rpc();
+        module.register_method("rpc.discover", |_, _| {
+            Ok(json!({
+                "info": { "version": "0.0.0" },
+                "methods": [{ "name": "sui_subscribeTransaction" }],
+            }))
+        })?;
+        Ok((url, server.start(module)?))
+    }
+}
+
+#[tokio::test]
+async fn track_test() -> Result<(), anyhow::Error> {
+    const NOT_FOUND: &str = "Could not find the referenced transaction [0x1].";
+    let digest = TransactionDigest::random();
+    let other = transaction_response(TransactionDigest::random());
+    let finalized = transaction_response(digest);
+    // A node answering `sui_getTransaction` once it has been asked `polls` times.
+    let node = |polls| {
+        let (finalized, calls) = (finalized.clone(), Arc::new(AtomicUsize::new(0)));
+        let node_calls = calls.clone();
+        let node = MockNode::start(move |method, _| match method {
+            "sui_getTransaction" if node_calls.fetch_add(1, Ordering::SeqCst) + 1 < polls => {
+                Reply::Error(NOT_FOUND.to_owned())
+            }
+            "sui_getTransaction" => Reply::Result(finalized.clone()),
+            _ => Reply::Close,
+        });
+        async move { anyhow::Ok((node.await?, calls)) }
+    };
+    let track = |client: SuiClient, options: TrackOptions| async move {
+        client
+            .track_with(digest, options)
+            .collect::<Vec<_>>()
+            .await
+            .into_iter()
+            .collect::<SuiRpcResult<Vec<_>>>()
+    };
+    let is_finalized = |statuses: &[TransactionStatus]| {
+        matches!(
+            statuses,
+            [TransactionStatus::Submitted, TransactionStatus::Finalized(response)]
+                if response.certificate.transaction_digest == digest
+        )
+    };
+    // Polling again would exceed the timeout.
+    let options = TrackOptions::default()
+        .with_poll_interval(Duration::from_secs(60), Duration::from_secs(60))
+        .with_timeout(Duration::from_secs(10));
+
+    // Not found by the polls around subscribing, then delivered by the subscription.
+    let (node, calls) = node(usize::MAX).await?;
+    let (ws_url, _ws) = TransactionFeed(vec![other.clone(), finalized.clone()])
+        .start()
+        .await?;
+    let client = SuiClientBuilder::default()
+        .build(&node.url, Some(&ws_url))
+        .await?;
+    assert!(is_finalized(&track(client, options).await?));
+    assert_eq!(2, calls.load(Ordering::SeqCst));
+
+    // Finalized between the first poll and the subscription, found by the second poll.
+    let (node, calls) = node(2).await?;
+    let (ws_url, _ws) = TransactionFeed(vec![]).start().await?;
+    let client = SuiClientBuilder::default()
+        .build(&node.url, Some(&ws_url))
+        .await?;
+    assert!(is_finalized(&track(client, options).await?));
+    assert_eq!(2, calls.load(Ordering::SeqCst));
+
+    // The subscription ends without the transaction, or is disabled: back to polling.
+    let options = options.with_poll_interval(Duration::from_millis(1), Duration::from_millis(4));
+    let (node, calls) = node(4).await?;
+    let (ws_url, _ws) = TransactionFeed(vec![other]).start().await?;
+    let client = SuiClientBuilder::default()
+        .build(&node.url, Some(&ws_url))
+        .await?;
+    assert!(is_finalized(&track(client, options).await?));
+    assert_eq!(4, calls.load(Ordering::SeqCst));
+
+    let (node, calls) = node(3).await?;
+    let (ws_url, _ws) = TransactionFeed(vec![finalized.clone()]).start().await?;
+    let client = SuiClientBuilder::default()
+        .build(&node.url, Some(&ws_url))
+        .await?;
+    let options = options.with_subscription(false);
+    assert!(is_finalized(&track(client, options).await?));
+    assert_eq!(3, calls.load(Ordering::SeqCst));
+
+    // Never final within the timeout.
+    let (node, _) = node(usize::MAX).await?;
+    let client = SuiClientBuilder::default().build(&node.url, None).await?;
+    let options = options.with_timeout(Duration::from_millis(50));
+    assert!(track(client, options).await.is_err());
+    Ok(())
+}
+
+#[tokio::test]
+async fn idempotent_submitter_test() -> Result<(), anyhow::Error> {
+    let (_, keypair): (_, AccountKeyPair) = get_key_pair();
+    let keypair = SuiKeyPair::Ed25519SuiKeyPair(keypair);
+    let sender = SuiAddress::from(&keypair.public());
+    let gas = (
+        ObjectID::random(),
+        SequenceNumber::new(),
+        ObjectDigest::random(),
+    );
+    let transfer = |amount| {
+        let data = TransactionData::new_transfer_sui(sender, sender, Some(amount), gas, 1000);
+        let signature: Signature = keypair.sign(&data.to_bytes());
+        Transaction::new(data, signature)
+    };
+    let txs = (1..=5).map(transfer).collect::<Vec<_>>();
+    let digests = txs
+        .iter()
+        .map(|tx| (Base64::encode(tx.signed_data.data.to_bytes()), *tx.digest()))
+        .collect::<BTreeMap<_, _>>();
+
+    // A node executing transactions while `online`, and finding only those it executed.
+    let online = Arc::new(AtomicBool::new(true));
+    let executed = Arc::new(Mutex::new(BTreeSet::new()));
+    let (node_online, node_executed) = (online.clone(), executed.clone());
+    let node = MockNode::start(move |method, params| match method {
+        "sui_executeTransaction" if node_online.load(Ordering::SeqCst) => {
+            let digest = digests[params[0].as_str().unwrap()];
+            node_executed.lock().unwrap().insert(digest);
+            Reply::Result(transaction_response(digest))
+        }
+        "sui_executeTransaction" => Reply::Error("Connection reset.".to_owned()),
+        "sui_getTransaction" => {
+            let digest: TransactionDigest = serde_json::from_value(params[0].clone()).unwrap();
+            if node_executed.lock().unwrap().contains(&digest) {
+                Reply::Result(transaction_response(digest))
+            } else {
+                Reply::Error(format!(
+                    "Could not find the referenced transaction [{digest}]."
+                ))
+            }
+        }
+        _ => Reply::Close,
+    })
+    .await?;
+    let client = SuiClientBuilder::default().build(&node.url, None).await?;
+    let dir = TempDir::new()?;
+    let submitter = IdempotentSubmitter::new(
+        &client,
+        FileCursorStore::new(dir.path().join("submissions.json")),
+    );
+    let digest = |index: usize| *txs[index].digest();
+
+    let submission = submitter
+        .submit_with_key("payment-1", txs[0].clone())
+        .await?;
+    assert_eq!(
+        (digest(0), false),
+        (submission.digest, submission.duplicate)
+    );
+    assert_eq!(
+        Some(digest(0)),
+        submitter.recorded_digest("payment-1").await?
+    );
+
+    // A retry signing a new transaction for the same payment broadcasts nothing.
+    let submission = submitter
+        .submit_with_key("payment-1", txs[1].clone())
+        .await?;
+    assert_eq!((digest(0), true), (submission.digest, submission.duplicate));
+    assert_eq!(
+        digest(0),
+        submission.response.certificate.transaction_digest
+    );
+    assert!(!executed.lock().unwrap().contains(&digest(1)));
+
+    // The broadcast of a recorded transaction failed: retrying broadcasts it again.
+    online.store(false, Ordering::SeqCst);
+    assert!(submitter.submit(txs[2].clone()).await.is_err());
+    assert_eq!(
+        Some(digest(2)),
+        submitter.recorded_digest(&digest(2).to_string()).await?
+    );
+    online.store(true, Ordering::SeqCst);
+    let submission = submitter.submit(txs[2].clone()).await?;
+    assert_eq!((digest(2), true), (submission.digest, submission.duplicate));
+    assert!(executed.lock().unwrap().contains(&digest(2)));
+
+    // A different transaction cannot be sent for a payment whose recorded transaction was
+    // never found, as that one may still execute.
+    online.store(false, Ordering::SeqCst);
+    assert!(submitter
+        .submit_with_key("payment-2", txs[3].clone())
+        .await
+        .is_err());
+    online.store(true, Ordering::SeqCst);
+    assert!(submitter
+        .submit_with_key("payment-2", txs[4].clone())
+        .await
+        .is_err());
+    assert!(!executed.lock().unwrap().contains(&digest(4)));
+    assert_eq!(
+        Some(digest(3)),
+        submitter.recorded_digest("payment-2").await?
+    );
+
+    // Once forgotten, the payment can be sent again.
+    submitter.forget("payment-2").await?;
+    let submission = submitter
+        .submit_with_key("payment-2", txs[4].clone())
+        .await?;
+    assert_eq!(
+        (digest(4), false),
+        (submission.digest, submission.duplicate)
+    );
+    assert_eq!(
+        BTreeSet::from([digest(0), digest(2), digest(4)]),
+        *executed.lock().unwrap()
+    );
+    Ok(())
+}
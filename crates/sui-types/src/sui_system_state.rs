@@ -15,6 +15,16 @@ use crate::{
 const SUI_SYSTEM_STATE_STRUCT_NAME: &IdentStr = ident_str!("SuiSystemState");
 pub const SUI_SYSTEM_MODULE_NAME: &IdentStr = ident_str!("sui_system");
 pub const ADVANCE_EPOCH_FUNCTION_NAME: &IdentStr = ident_str!("advance_epoch");
+pub const ADD_VALIDATOR_FUNCTION_NAME: &IdentStr = ident_str!("request_add_validator");
+pub const REMOVE_VALIDATOR_FUNCTION_NAME: &IdentStr = ident_str!("request_remove_validator");
+pub const SET_GAS_PRICE_FUNCTION_NAME: &IdentStr = ident_str!("request_set_gas_price");
+pub const ADD_STAKE_FUNCTION_NAME: &IdentStr = ident_str!("request_add_stake");
+pub const WITHDRAW_STAKE_FUNCTION_NAME: &IdentStr = ident_str!("request_withdraw_stake");
+pub const ADD_DELEGATION_FUNCTION_NAME: &IdentStr = ident_str!("request_add_delegation");
+pub const WITHDRAW_DELEGATION_FUNCTION_NAME: &IdentStr = ident_str!("request_withdraw_delegation");
+pub const STAKING_POOL_MODULE_NAME: &IdentStr = ident_str!("staking_pool");
+const DELEGATION_STRUCT_NAME: &IdentStr = ident_str!("Delegation");
+const STAKED_SUI_STRUCT_NAME: &IdentStr = ident_str!("StakedSui");
 
 /// Rust version of the Move sui::sui_system::SystemParameters type
 #[derive(Debug, Serialize, Deserialize, Clone, Eq, PartialEq)]
@@ -74,6 +84,64 @@ pub struct StakingPool {
     pub pending_delegations: Vec<PendingDelegationEntry>,
 }
 
+impl StakingPool {
+    /// Amount of SUI the given amount of pool tokens can be redeemed for at the current
+    /// exchange rate, mirroring `staking_pool::get_sui_amount`.
+    pub fn sui_amount(&self, pool_token_amount: u64) -> u64 {
+        if self.delegation_token_supply.value == 0 {
+            return pool_token_amount;
+        }
+        ((self.sui_balance as u128) * (pool_token_amount as u128)
+            / (self.delegation_token_supply.value as u128)) as u64
+    }
+}
+
+/// Rust version of the Move sui::epoch_time_lock::EpochTimeLock type
+#[derive(Debug, Serialize, Deserialize, Clone, Eq, PartialEq)]
+pub struct EpochTimeLock {
+    pub epoch: u64,
+}
+
+/// Rust version of the Move sui::staking_pool::Delegation type
+#[derive(Debug, Serialize, Deserialize, Clone, Eq, PartialEq)]
+pub struct Delegation {
+    pub id: UID,
+    pub validator_address: AccountAddress,
+    pub pool_starting_epoch: u64,
+    pub pool_tokens: Balance,
+    pub principal_sui_amount: u64,
+}
+
+impl Delegation {
+    pub fn type_() -> StructTag {
+        StructTag {
+            address: SUI_FRAMEWORK_ADDRESS,
+            name: DELEGATION_STRUCT_NAME.to_owned(),
+            module: STAKING_POOL_MODULE_NAME.to_owned(),
+            type_params: vec![],
+        }
+    }
+}
+
+/// Rust version of the Move sui::staking_pool::StakedSui type
+#[derive(Debug, Serialize, Deserialize, Clone, Eq, PartialEq)]
+pub struct StakedSui {
+    pub id: UID,
+    pub principal: Balance,
+    pub sui_token_lock: MoveOption<EpochTimeLock>,
+}
+
+impl StakedSui {
+    pub fn type_() -> StructTag {
+        StructTag {
+            address: SUI_FRAMEWORK_ADDRESS,
+            name: STAKED_SUI_STRUCT_NAME.to_owned(),
+            module: STAKING_POOL_MODULE_NAME.to_owned(),
+            type_params: vec![],
+        }
+    }
+}
+
 /// Rust version of the Move sui::validator_set::ValidatorSet type
 #[derive(Debug, Serialize, Deserialize, Clone, Eq, PartialEq)]
 pub struct ValidatorSet {
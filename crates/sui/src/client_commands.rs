@@ -27,6 +27,7 @@ use sui_json_rpc_types::{
 };
 use sui_json_rpc_types::{GetRawObjectDataResponse, SuiData};
 use sui_json_rpc_types::{SuiCertifiedTransaction, SuiExecutionStatus, SuiTransactionEffects};
+use sui_sdk::amount::Amount;
 use sui_sdk::crypto::SuiKeystore;
 use sui_sdk::{ClientType, SuiClient};
 use sui_types::crypto::SignatureScheme;
@@ -392,7 +393,13 @@ impl SuiClientCommands {
                 let data = context
                     .client
                     .transaction_builder()
-                    .transfer_sui(from, object_id, gas_budget, to, amount)
+                    .transfer_sui(
+                        from,
+                        object_id,
+                        gas_budget,
+                        to,
+                        amount.map(Amount::from_mist),
+                    )
                     .await?;
                 let signature = context.keystore.sign(&from, &data.to_bytes())?;
                 let response = context
@@ -434,7 +441,14 @@ impl SuiClientCommands {
                 let data = context
                     .client
                     .transaction_builder()
-                    .pay(from, input_coins, recipients, amounts, gas, gas_budget)
+                    .pay(
+                        from,
+                        input_coins,
+                        recipients,
+                        amounts.into_iter().map(Amount::from_mist).collect(),
+                        gas,
+                        gas_budget,
+                    )
                     .await?;
                 let signature = context.keystore.sign(&from, &data.to_bytes())?;
                 let response = context
@@ -514,7 +528,13 @@ impl SuiClientCommands {
                     context
                         .client
                         .transaction_builder()
-                        .split_coin(signer, coin_id, amounts, gas, gas_budget)
+                        .split_coin(
+                            signer,
+                            coin_id,
+                            amounts.into_iter().map(Amount::from_mist).collect(),
+                            gas,
+                            gas_budget,
+                        )
                         .await?
                 } else {
                     if count == 0 {
@@ -685,7 +705,7 @@ impl WalletContext {
         &self,
         object_id: ObjectID,
     ) -> Result<GetRawObjectDataResponse, anyhow::Error> {
-        self.client.read_api().get_object(object_id).await
+        Ok(self.client.read_api().get_object(object_id).await?)
     }
 
     /// Get all the gas objects (and conveniently, gas amounts) for the address
@@ -767,7 +787,7 @@ impl WalletContext {
     ) -> anyhow::Result<SuiTransactionResponse> {
         let tx_digest = *tx.digest();
         if self.client.is_gateway() {
-            self.client.quorum_driver().execute_transaction(tx).await
+            Ok(self.client.quorum_driver().execute_transaction(tx).await?)
         } else {
             let result = self
                 .client
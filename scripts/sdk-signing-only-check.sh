@@ -0,0 +1,15 @@
+#!/bin/bash
+# Copyright (c) 2022, Mysten Labs, Inc.
+# SPDX-License-Identifier: Apache-2.0
+
+set -e
+
+# Check that the signing-only build of the SDK compiles without the RPC client stack.
+# hyper is still pulled by sui-types, through tonic and narwhal-executor.
+features=(-p sui-sdk --no-default-features --features signing-only)
+cargo check "${features[@]}"
+
+forbidden="$(cargo tree "${features[@]}" -e normal --prefix none --format '{p}' \
+  | grep -E '^(reqwest|jsonrpsee(-[a-z-]+)?|hyper-rustls|sqlx|move-package|sui-framework|sui-adapter) ' | sort -u || true)"
+echo "${forbidden}"
+[[ -z "${forbidden}" ]]
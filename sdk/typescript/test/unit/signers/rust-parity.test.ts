@@ -0,0 +1,85 @@
+// Copyright (c) 2022, Mysten Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+import { readFileSync } from 'fs';
+import { join } from 'path';
+import { describe, it, expect } from 'vitest';
+import {
+  Base64DataBuffer,
+  bcs,
+  Ed25519Keypair,
+  Ed25519PublicKey,
+  RawSigner,
+} from '../../../src';
+
+// Vectors shared with the Rust SDK, see crates/sui-sdk/src/js_parity.rs.
+const VECTORS = JSON.parse(
+  readFileSync(
+    join(
+      __dirname,
+      '../../../../../crates/sui-sdk/tests/data/sui_js_vectors.json'
+    ),
+    'utf-8'
+  )
+);
+
+const TYPE_TAG = Array.from('TransactionData::').map((e) => e.charCodeAt(0));
+
+describe('Rust SDK parity', () => {
+  VECTORS.addresses.forEach(
+    ({ pub_key, address }: { pub_key: string; address: string }) => {
+      it(`toSuiAddress ${address}`, () => {
+        expect(new Ed25519PublicKey(pub_key).toSuiAddress()).toEqual(address);
+      });
+    }
+  );
+
+  VECTORS.transfer_sui.forEach((vector: any) => {
+    it(`TransferSui of ${vector.amount} bytes and signature`, async () => {
+      // The Rust keypair encoding is flag || public key || seed.
+      const seed = new Base64DataBuffer(VECTORS.keypair).getData().slice(33);
+      const keypair = Ed25519Keypair.fromSeed(seed);
+      expect(keypair.getPublicKey().toSuiAddress()).toEqual(VECTORS.address);
+
+      const data = bcs
+        .ser(
+          'TransactionData',
+          {
+            kind: {
+              Single: {
+                TransferSui: {
+                  recipient: vector.recipient,
+                  amount:
+                    vector.amount === null
+                      ? { None: null }
+                      : { Some: vector.amount },
+                },
+              },
+            },
+            sender: VECTORS.address,
+            gasPayment: {
+              objectId: vector.gas_object_id,
+              version: vector.gas_object_version,
+              digest: vector.gas_object_digest,
+            },
+            gasPrice: 1,
+            gasBudget: vector.gas_budget,
+          },
+          2048
+        )
+        .toBytes();
+      const txBytes = new Uint8Array(TYPE_TAG.length + data.length);
+      txBytes.set(TYPE_TAG);
+      txBytes.set(data, TYPE_TAG.length);
+      const txBuffer = new Base64DataBuffer(txBytes);
+      expect(txBuffer.toString()).toEqual(vector.tx_bytes);
+
+      const { signatureScheme, signature, pubKey } = await new RawSigner(
+        keypair
+      ).signData(txBuffer);
+      expect(signatureScheme).toEqual(vector.signature.signatureScheme);
+      expect(signature.toString()).toEqual(vector.signature.signature);
+      expect(pubKey.toBase64()).toEqual(vector.signature.pubKey);
+    });
+  });
+});